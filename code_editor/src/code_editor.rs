@@ -1,5 +1,6 @@
 use {
     crate::{
+        completion::{self, CompletionItem},
         decoration::{Decoration, DecorationType},
         layout::{BlockElement, WrappedElement},
         selection::Affinity,
@@ -26,6 +27,7 @@ live_design! {
         delimiter_highlight: #f,
         error_decoration: #f00,
         warning_decoration: #0f0,
+        search_decoration: #d7ba7d48,
         
         unknown: #C0C0C0,
         branch_keyword: #C485BE,
@@ -53,6 +55,11 @@ live_design! {
 
     DrawDecoration = {{DrawDecoration}} {
         fn pixel(self) -> vec4 {
+            if self.is_fill > 0.5 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0.0, 0.0, self.rect_size.x, self.rect_size.y, 1.5);
+                return sdf.fill(self.color);
+            }
             let transformed_pos = vec2(self.pos.x, self.pos.y + 0.03 * sin(self.pos.x * self.rect_size.x));
             let cx = Sdf2d::viewport(transformed_pos * self.rect_size);
             cx.move_to(0.0, self.rect_size.y - 1.0);
@@ -118,6 +125,16 @@ live_design! {
         pad_left_top: vec2(10.0,10.0)
         scroll_bars: <ScrollBars> {}
         draw_bg: { color: (THEME_COLOR_BG_CONTAINER) }
+        draw_popup_bg: { color: (THEME_COLOR_FLOATING_BG) }
+        draw_popup_selection: { color: (THEME_COLOR_CTRL_SELECTED) }
+        draw_popup_text: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_DEFAULT),
+        }
+        draw_popup_detail: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_META),
+        }
         draw_gutter: {
             draw_depth: 1.0,
             text_style: <THEME_FONT_CODE> {},
@@ -233,8 +250,21 @@ pub struct CodeEditor {
     #[live] draw_cursor: DrawColor,
     #[live] draw_cursor_bg: DrawColor,
     #[live] draw_bg: DrawColor,
+    #[rust(DrawList2d::new(cx))] popup_draw_list: DrawList2d,
+    #[live] draw_popup_bg: DrawColor,
+    #[live] draw_popup_selection: DrawColor,
+    #[live] draw_popup_text: DrawText,
+    #[live] draw_popup_detail: DrawText,
+    #[live(10usize)] completion_row_count: usize,
+    #[live(0.6)] hover_delay: f64,
+    #[rust] completion_popup: Option<CompletionPopup>,
+    #[rust] hover_tooltip: Option<HoverTooltip>,
+    #[rust] hover_timer: Timer,
+    #[rust] hover_abs: DVec2,
     #[rust(KeepCursorInView::Off)] keep_cursor_in_view: KeepCursorInView,
     #[rust] last_cursor_screen_pos: Option<DVec2>,
+    #[rust] last_text_input: String,
+    #[rust] cursor_area: Area,
     #[live] pad_left_top: DVec2, 
     #[rust] cell_size: DVec2,
     #[rust] gutter_rect: Rect,
@@ -267,6 +297,24 @@ enum KeepCursorInView {
     Off,
 }
 
+/// The maximum width, in columns, of the completion popup and the hover tooltip.
+const MAX_POPUP_COLUMNS: usize = 80;
+const MAX_HOVER_LINES: usize = 20;
+
+struct CompletionPopup {
+    items: Vec<CompletionItem>,
+    /// The indices of the items that match the word before the cursor.
+    visible_items: Vec<usize>,
+    selected_index: usize,
+    /// Where the word that is being completed starts.
+    word_start: Position,
+}
+
+struct HoverTooltip {
+    position: Position,
+    lines: Vec<String>,
+}
+
 impl KeepCursorInView {
     fn is_once(&self) -> bool {
         match self {
@@ -514,15 +562,26 @@ impl CodeEditor {
             self.draw_gutter(cx, session);
         }
         self.draw_selection_layer(cx, session);
+        self.draw_decoration_layer(cx, session, &session.search_matches());
         self.draw_text_layer(cx, session);
         self.draw_indent_guide_layer(cx, session);
-        self.draw_decoration_layer(cx, session);
+        self.draw_decoration_layer(cx, session, &session.document().decorations());
         self.draw_selection_layer(cx, session);
 
         // Get the last added selection.
         // Get the normalized cursor position. To go from normalized to screen position, multiply by
         // the cell size, then shift by the viewport origin.
         
+        // the input method candidate window goes at the last added cursor
+        let cursor_rect = Rect {
+            pos: cursor_pos + self.viewport_rect.pos,
+            size: dvec2(2.0, self.cell_size.y),
+        };
+        cx.add_aligned_rect_area(&mut self.cursor_area, cursor_rect);
+        if cx.has_key_focus(self.scroll_bars.area()) {
+            cx.show_text_ime(self.cursor_area, DVec2::default());
+        }
+
         cx.turtle_mut().set_used(
             session.layout().width() * self.cell_size.x +self.pad_left_top.x,
             self.height_scale * session.layout().height() * self.cell_size.y + if height_is_fit{0.0} else {self.viewport_rect.size.y}
@@ -530,6 +589,7 @@ impl CodeEditor {
         );
         
         self.scroll_bars.end(cx);
+        self.draw_popups(cx, session);
         if session.update_folds() {
             self.scroll_bars.area().redraw(cx);
         } else if self.keep_cursor_in_view.is_locked() {
@@ -552,6 +612,247 @@ impl CodeEditor {
         self.redraw(cx);
     }
 
+    /// Scrolls the cursor of the last added selection into view, for instance after the session
+    /// selected a search match.
+    pub fn jump_to_cursor(&mut self, cx: &mut Cx) {
+        self.keep_cursor_in_view = KeepCursorInView::JumpToPosition;
+        self.redraw(cx);
+    }
+
+    /// Shows a popup with the `items` that match the word before the cursor, from which one can be
+    /// picked with the arrow keys and accepted with Return or Tab.
+    pub fn show_completions(
+        &mut self,
+        cx: &mut Cx,
+        session: &CodeSession,
+        items: Vec<CompletionItem>,
+    ) {
+        let cursor = self.cursor_position(session);
+        let word_start = Position {
+            line_index: cursor.line_index,
+            byte_index: completion::word_start(
+                &session.document().as_text().as_lines()[cursor.line_index],
+                cursor.byte_index,
+            ),
+        };
+        self.completion_popup = Some(CompletionPopup {
+            items,
+            visible_items: Vec::new(),
+            selected_index: 0,
+            word_start,
+        });
+        self.update_completion_popup(cx, session);
+        self.redraw(cx);
+    }
+
+    pub fn hide_completions(&mut self, cx: &mut Cx) {
+        if self.completion_popup.take().is_some() {
+            self.redraw(cx);
+        }
+    }
+
+    pub fn is_showing_completions(&self) -> bool {
+        self.completion_popup.is_some()
+    }
+
+    /// Shows a tooltip with `text` above `position`, until the mouse moves or a key is pressed.
+    pub fn show_hover(&mut self, cx: &mut Cx, position: Position, text: &str) {
+        let lines: Vec<_> = text
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        if lines.iter().all(|line| line.is_empty()) {
+            self.hide_hover(cx);
+            return;
+        }
+        self.hover_tooltip = Some(HoverTooltip { position, lines });
+        self.redraw(cx);
+    }
+
+    pub fn hide_hover(&mut self, cx: &mut Cx) {
+        if self.hover_tooltip.take().is_some() {
+            self.redraw(cx);
+        }
+    }
+
+    fn cursor_position(&self, session: &CodeSession) -> Position {
+        session.selections()[session.last_added_selection_index().unwrap()]
+            .cursor
+            .position
+    }
+
+    fn handle_completion_key_down(
+        &mut self,
+        cx: &mut Cx,
+        event: &KeyEvent,
+        session: &mut CodeSession,
+        actions: &mut Vec<CodeEditorAction>,
+    ) -> bool {
+        let popup = self.completion_popup.as_mut().unwrap();
+        match event.key_code {
+            KeyCode::ArrowUp => {
+                popup.selected_index = popup.selected_index.saturating_sub(1);
+            }
+            KeyCode::ArrowDown => {
+                if popup.selected_index + 1 < popup.visible_items.len() {
+                    popup.selected_index += 1;
+                }
+            }
+            KeyCode::PageUp => {
+                popup.selected_index = popup
+                    .selected_index
+                    .saturating_sub(self.completion_row_count);
+            }
+            KeyCode::PageDown => {
+                popup.selected_index = (popup.selected_index + self.completion_row_count)
+                    .min(popup.visible_items.len().saturating_sub(1));
+            }
+            KeyCode::ReturnKey | KeyCode::Tab if !self.read_only => {
+                let popup = self.completion_popup.take().unwrap();
+                let item = &popup.items[popup.visible_items[popup.selected_index]];
+                session.replace_range(
+                    popup.word_start,
+                    self.cursor_position(session),
+                    item.insert_text.as_str().into(),
+                );
+                self.keep_cursor_in_view = KeepCursorInView::Once;
+                self.reset_cursor_blinker(cx);
+                actions.push(CodeEditorAction::TextDidChange);
+            }
+            KeyCode::Escape => {
+                self.completion_popup = None;
+            }
+            _ => return false,
+        }
+        self.redraw(cx);
+        true
+    }
+
+    /// Filters the items of the completion popup by the word before the cursor, and hides the
+    /// popup once the cursor leaves that word or nothing matches anymore.
+    fn update_completion_popup(&mut self, cx: &mut Cx, session: &CodeSession) {
+        let cursor = self.cursor_position(session);
+        let popup = self.completion_popup.as_mut().unwrap();
+        let text = session.document().as_text();
+        let line = &text.as_lines()[cursor.line_index];
+        let is_in_word = cursor.line_index == popup.word_start.line_index
+            && cursor.byte_index >= popup.word_start.byte_index
+            && completion::word_start(line, cursor.byte_index) == popup.word_start.byte_index;
+        if is_in_word {
+            let word = &line[popup.word_start.byte_index..cursor.byte_index];
+            popup.visible_items = completion::filter_completion_items(&popup.items, word);
+            popup.selected_index = popup
+                .selected_index
+                .min(popup.visible_items.len().saturating_sub(1));
+        }
+        if !is_in_word || popup.visible_items.is_empty() {
+            self.completion_popup = None;
+        }
+        self.redraw(cx);
+    }
+
+    fn draw_popups(&mut self, cx: &mut Cx2d, session: &CodeSession) {
+        self.popup_draw_list.begin_overlay_reuse(cx);
+        cx.begin_pass_sized_turtle(Layout::default());
+        let cell_size =
+            self.draw_popup_text.text_style.font_size * self.draw_popup_text.get_monospace_base(cx);
+        let padding = dvec2(cell_size.x * 0.5, cell_size.y * 0.25);
+        if let Some(popup) = &self.completion_popup {
+            let first_row = popup
+                .selected_index
+                .saturating_sub(self.completion_row_count.saturating_sub(1));
+            let rows = &popup.visible_items
+                [first_row..popup.visible_items.len().min(first_row + self.completion_row_count)];
+            let label_columns = rows
+                .iter()
+                .map(|&index| popup.items[index].label.chars().count())
+                .max()
+                .unwrap_or(0)
+                .min(MAX_POPUP_COLUMNS);
+            let detail_columns = rows
+                .iter()
+                .filter_map(|&index| popup.items[index].detail.as_ref())
+                .map(|detail| detail.chars().count() + 2)
+                .max()
+                .unwrap_or(0)
+                .min(MAX_POPUP_COLUMNS - label_columns);
+            let size = dvec2(
+                (label_columns + detail_columns) as f64 * cell_size.x,
+                rows.len() as f64 * cell_size.y,
+            ) + 2.0 * padding;
+            let pos = self.popup_pos(session, popup.word_start, size);
+            self.draw_popup_bg.draw_abs(cx, Rect { pos, size });
+            for (row_index, &item_index) in rows.iter().enumerate() {
+                let item = &popup.items[item_index];
+                let row_pos = pos + padding + dvec2(0.0, row_index as f64 * cell_size.y);
+                if first_row + row_index == popup.selected_index {
+                    self.draw_popup_selection.draw_abs(
+                        cx,
+                        Rect {
+                            pos: dvec2(pos.x, row_pos.y),
+                            size: dvec2(size.x, cell_size.y),
+                        },
+                    );
+                }
+                self.draw_popup_text
+                    .draw_abs(cx, row_pos, truncate(&item.label, label_columns));
+                if let Some(detail) = &item.detail {
+                    if detail_columns > 2 {
+                        self.draw_popup_detail.draw_abs(
+                            cx,
+                            row_pos + dvec2((label_columns + 2) as f64 * cell_size.x, 0.0),
+                            truncate(detail, detail_columns - 2),
+                        );
+                    }
+                }
+            }
+        } else if let Some(tooltip) = &self.hover_tooltip {
+            let lines = &tooltip.lines[..tooltip.lines.len().min(MAX_HOVER_LINES)];
+            let columns = lines
+                .iter()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0)
+                .min(MAX_POPUP_COLUMNS);
+            let size = dvec2(
+                columns as f64 * cell_size.x,
+                lines.len() as f64 * cell_size.y,
+            ) + 2.0 * padding;
+            let (x, y) = session
+                .layout()
+                .logical_to_normalized_position(tooltip.position, Affinity::Before);
+            let mut pos = dvec2(x, y) * self.cell_size + self.viewport_rect.pos;
+            pos.y -= size.y;
+            if pos.y < self.viewport_rect.pos.y {
+                pos.y += size.y + self.cell_size.y;
+            }
+            self.draw_popup_bg.draw_abs(cx, Rect { pos, size });
+            for (line_index, line) in lines.iter().enumerate() {
+                self.draw_popup_text.draw_abs(
+                    cx,
+                    pos + padding + dvec2(0.0, line_index as f64 * cell_size.y),
+                    truncate(line, columns),
+                );
+            }
+        }
+        cx.end_pass_sized_turtle();
+        self.popup_draw_list.end(cx);
+    }
+
+    /// Returns where to draw a popup of the given size below `position`, or above it if there is
+    /// no room below.
+    fn popup_pos(&self, session: &CodeSession, position: Position, size: DVec2) -> DVec2 {
+        let (x, y) = session
+            .layout()
+            .logical_to_normalized_position(position, Affinity::Before);
+        let mut pos = dvec2(x, y) * self.cell_size + self.viewport_rect.pos;
+        pos.y += self.cell_size.y;
+        if pos.y + size.y > self.viewport_rect.pos.y + self.viewport_rect.size.y {
+            pos.y -= self.cell_size.y + size.y;
+        }
+        pos
+    }
+
     pub fn reset_font_size(&mut self) {
         self.draw_gutter.text_style.font_size = 9.0;
         self.draw_text.text_style.font_size = 9.0;
@@ -616,10 +917,28 @@ impl CodeEditor {
             }
             self.blink_timer = cx.start_timeout(self.blink_speed)
         }
+        if self.hover_timer.is_event(event).is_some() {
+            let ((position, _), is_in_gutter) = self.pick(session, self.hover_abs);
+            if !is_in_gutter {
+                actions.push(CodeEditorAction::RequestHover(position));
+            }
+        }
+        if let Event::KeyDown(key_event) = event {
+            if self.hover_tooltip.take().is_some() {
+                self.redraw(cx);
+            }
+            if self.completion_popup.is_some()
+                && cx.has_key_focus(self.scroll_bars.area())
+                && self.handle_completion_key_down(cx, key_event, session, &mut actions)
+            {
+                return actions;
+            }
+        }
         let mut keyboard_moved_cursor = false;
         match event.hits(cx, self.scroll_bars.area()) {
             Hit::KeyFocusLost(_) => {
                 self.animator_play(cx, id!(focus.off));
+                cx.hide_text_ime();
             }
             Hit::KeyFocus(_) => {
                 self.animator_play(cx, id!(focus.on));
@@ -685,6 +1004,44 @@ impl CodeEditor {
                     self.redraw(cx);
                 }
             }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyF,
+                modifiers: KeyModifiers { control, logo, .. },
+                ..
+            }) => {
+                if control || logo {
+                    actions.push(CodeEditorAction::Find);
+                }
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::Space,
+                modifiers: KeyModifiers { control: true, .. },
+                ..
+            }) => {
+                actions.push(CodeEditorAction::RequestCompletion(
+                    session.selections()[session.last_added_selection_index().unwrap()]
+                        .cursor
+                        .position,
+                ));
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyG,
+                modifiers: KeyModifiers { shift, control, logo, .. },
+                ..
+            }) => {
+                if control || logo {
+                    let found = if shift {
+                        session.find_prev()
+                    } else {
+                        session.find_next()
+                    };
+                    if found {
+                        self.keep_cursor_in_view = KeepCursorInView::JumpToPosition;
+                        self.reset_cursor_blinker(cx);
+                        self.redraw(cx);
+                    }
+                }
+            }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::KeyA,
                 modifiers: KeyModifiers {control, logo, ..},
@@ -804,9 +1161,16 @@ impl CodeEditor {
             Hit::TextInput(TextInputEvent {
                 ref input,
                 was_paste: false,
-                ..
-            }) if input.len() > 0 && !self.read_only => {
-                session.insert(input.into());
+                replace_last,
+            }) if (input.len() > 0 || replace_last) && !self.read_only => {
+                // input method compositions send every update of the preedit text with
+                // replace_last set, an empty one removes a cancelled composition
+                if replace_last {
+                    session.replace_preedit(&self.last_text_input, input.into());
+                } else {
+                    session.insert(input.into());
+                }
+                self.last_text_input.clone_from(input);
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
                 actions.push(CodeEditorAction::TextDidChange);
@@ -981,8 +1345,23 @@ impl CodeEditor {
                 self.reset_cursor_blinker(cx);
                 self.keep_cursor_in_view = KeepCursorInView::Off;
             }
-            Hit::FingerHoverIn(_) | Hit::FingerHoverOver(_) => {
+            Hit::FingerHoverIn(FingerHoverEvent { abs, .. })
+            | Hit::FingerHoverOver(FingerHoverEvent { abs, .. }) => {
                 cx.set_cursor(MouseCursor::Text);
+                if (abs - self.hover_abs).length() > self.cell_size.x * 0.5 {
+                    if self.hover_tooltip.take().is_some() {
+                        self.redraw(cx);
+                    }
+                    cx.stop_timer(self.hover_timer);
+                    self.hover_timer = cx.start_timeout(self.hover_delay);
+                    self.hover_abs = abs;
+                }
+            }
+            Hit::FingerHoverOut(_) => {
+                cx.stop_timer(self.hover_timer);
+                if self.hover_tooltip.take().is_some() {
+                    self.redraw(cx);
+                }
             }
             Hit::FingerDown(FingerDownEvent {
                 abs,
@@ -1006,6 +1385,9 @@ impl CodeEditor {
             self.keep_cursor_in_view = KeepCursorInView::Once;
             self.reset_cursor_blinker(cx);
         }
+        if self.completion_popup.is_some() {
+            self.update_completion_popup(cx, session);
+        }
         if let KeepCursorInView::Always(abs, next) = &mut self.keep_cursor_in_view {
             if next.is_event(event).is_some() {
                 *next = cx.new_next_frame();
@@ -1222,9 +1604,13 @@ impl CodeEditor {
         }
     }
 
-    fn draw_decoration_layer(&mut self, cx: &mut Cx2d<'_>, session: &CodeSession) {
+    fn draw_decoration_layer(
+        &mut self,
+        cx: &mut Cx2d<'_>,
+        session: &CodeSession,
+        decorations: &[Decoration],
+    ) {
         let mut active_decoration = None;
-        let decorations = session.document().decorations();
         let mut decorations = decorations.iter();
         while decorations.as_slice().first().map_or(false, |decoration| {
             decoration.end().line_index < self.line_start
@@ -1487,9 +1873,24 @@ impl CodeEditor {
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, DefaultNone)]
 pub enum CodeEditorAction {
     TextDidChange,
+    /// Cmd/Ctrl+F was pressed, so the find bar should be shown.
+    Find,
+    /// Ctrl+Space was pressed, so completions for the cursor position should be shown with
+    /// `CodeEditor::show_completions`.
+    RequestCompletion(Position),
+    /// The mouse rested over this position, so information about it can be shown with
+    /// `CodeEditor::show_hover`.
+    RequestHover(Position),
     None
 }
 
+fn truncate(string: &str, column_count: usize) -> &str {
+    match string.char_indices().nth(column_count) {
+        Some((index, _)) => &string[..index],
+        None => string,
+    }
+}
+
 struct DrawDecorationLayer<'a> {
     code_editor: &'a mut CodeEditor,
     active_decoration: Option<ActiveDecoration>,
@@ -1656,11 +2057,16 @@ impl<'a> DrawDecorationLayer<'a> {
     ) {
         let start_x = mem::take(&mut self.active_decoration.as_mut().unwrap().start_x);
         let (x, y) = line.grid_to_normalized_position(row_index, column_index);
-        self.code_editor.draw_decoration.color =
-            match self.active_decoration.as_mut().unwrap().decoration.ty {
-                DecorationType::Warning => self.code_editor.token_colors.warning_decoration,
-                DecorationType::Error => self.code_editor.token_colors.error_decoration,
-            };
+        let ty = self.active_decoration.as_mut().unwrap().decoration.ty;
+        self.code_editor.draw_decoration.color = match ty {
+            DecorationType::Warning => self.code_editor.token_colors.warning_decoration,
+            DecorationType::Error => self.code_editor.token_colors.error_decoration,
+            DecorationType::SearchMatch => self.code_editor.token_colors.search_decoration,
+        };
+        self.code_editor.draw_decoration.is_fill = match ty {
+            DecorationType::SearchMatch => 1.0,
+            _ => 0.0,
+        };
 
         self.code_editor.draw_decoration.draw_abs(
             cx,
@@ -1970,6 +2376,8 @@ struct TokenColors {
     error_decoration: Vec4,
     #[live]
     warning_decoration: Vec4,
+    #[live]
+    search_decoration: Vec4,
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
@@ -1982,11 +2390,14 @@ pub struct DrawIndentGuide {
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
 struct DrawDecoration {
     #[deref]
     draw_super: DrawQuad,
     #[live]
     color: Vec4,
+    #[live]
+    is_fill: f32,
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
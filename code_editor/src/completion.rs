@@ -0,0 +1,56 @@
+/// An item in the completion popup of the code editor.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CompletionItem {
+    /// The text that is shown in the popup, and matched against the word before the cursor.
+    pub label: String,
+    /// Extra information, such as a type signature, that is shown next to the label.
+    pub detail: Option<String>,
+    /// The text that replaces the word before the cursor when the item is accepted.
+    pub insert_text: String,
+}
+
+impl CompletionItem {
+    pub fn new(label: impl Into<String>) -> Self {
+        let label = label.into();
+        Self {
+            insert_text: label.clone(),
+            label,
+            detail: None,
+        }
+    }
+}
+
+/// Returns the indices of the `items` whose label matches `word`, with those that start with it
+/// before those that only contain its characters in order.
+pub fn filter_completion_items(items: &[CompletionItem], word: &str) -> Vec<usize> {
+    let word = word.to_lowercase();
+    let mut prefix_matches = Vec::new();
+    let mut other_matches = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let label = item.label.to_lowercase();
+        if label.starts_with(&word) {
+            prefix_matches.push(index);
+        } else if is_subsequence(&word, &label) {
+            other_matches.push(index);
+        }
+    }
+    prefix_matches.extend(other_matches);
+    prefix_matches
+}
+
+/// Returns the byte index where the word that ends at `byte_index` in `line` starts.
+pub fn word_start(line: &str, byte_index: usize) -> usize {
+    line[..byte_index]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, char)| char.is_alphanumeric() || char == '_')
+        .last()
+        .map_or(byte_index, |(index, _)| index)
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle
+        .chars()
+        .all(|needle_char| haystack.any(|char| char == needle_char))
+}
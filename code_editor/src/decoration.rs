@@ -7,6 +7,7 @@ use {
 pub enum DecorationType {
     Error,
     Warning,
+    SearchMatch,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -94,6 +95,12 @@ impl DecorationSet {
         self.decorations.clear();
     }
 
+    /// Removes the decorations with the given `id`, so that each source of decorations can
+    /// replace its own without touching those of others.
+    pub fn remove_decorations_with_id(&mut self, id: usize) {
+        self.decorations.retain(|decoration| decoration.id != id);
+    }
+
     pub fn apply_edit(&mut self, edit: &Edit) {
         for decoration in &mut self.decorations {
             *decoration = decoration.apply_edit(edit);
@@ -5,6 +5,7 @@ use {
         history::{EditKind, History},
         inlays::{BlockInlay, InlineInlay},
         iter::IteratorExt,
+        language::Language,
         selection::SelectionSet,
         session::SessionId,
         settings::Settings,
@@ -20,7 +21,7 @@ use {
         iter,
         ops::Range,
         rc::Rc,
-        sync::mpsc::Sender,
+        sync::mpsc::{self, Receiver, Sender},
     },
 };
 
@@ -44,6 +45,7 @@ impl CodeDocument {
             tokenizer: RefCell::new(Tokenizer::new(line_count)),
             decorations: RefCell::new(decorations),
             edit_senders: RefCell::new(HashMap::new()),
+            edit_listeners: RefCell::new(Vec::new()),
         }));
         inner.update_indent_state();
         inner.0.tokenizer.borrow_mut().update(
@@ -95,6 +97,20 @@ impl CodeDocument {
         })
     }
 
+    pub fn language(&self) -> &'static dyn Language {
+        self.0.tokenizer.borrow().language()
+    }
+
+    /// Switches the language that the document is tokenized with, and tokenizes it again.
+    pub fn set_language(&self, language: &'static dyn Language) {
+        let mut tokenizer = self.0.tokenizer.borrow_mut();
+        tokenizer.set_language(language);
+        tokenizer.update(
+            self.0.history.borrow().as_text(),
+            &mut self.0.layout.borrow_mut().tokens,
+        );
+    }
+
     pub fn edit_selections(
         &self,
         session_id: SessionId,
@@ -202,6 +218,39 @@ impl CodeDocument {
         self.update_after_edit(Some(origin_id), None, &edits);
     }
 
+    /// Replaces each of the given ranges with its text as a single undo group. The ranges must be
+    /// sorted and must not overlap.
+    pub fn replace_ranges(
+        &self,
+        session_id: SessionId,
+        selections: &SelectionSet,
+        ranges: Vec<(Position, Length, Text)>,
+    ) {
+        if ranges.is_empty() {
+            return;
+        }
+        let mut history = self.0.history.borrow_mut();
+        history.push_or_extend_group(session_id, EditKind::Other, selections);
+        let mut edits = Vec::new();
+        let mut editor = Editor {
+            history: &mut *history,
+            edits: &mut edits,
+        };
+        // Going back to front keeps the positions of the ranges that are still to be replaced valid.
+        for (position, length, text) in ranges.into_iter().rev() {
+            editor.apply_edit(Edit {
+                change: Change::Delete(position, length),
+                drift: Drift::Before,
+            });
+            editor.apply_edit(Edit {
+                change: Change::Insert(position, text),
+                drift: Drift::Before,
+            });
+        }
+        drop(history);
+        self.update_after_edit(Some(session_id), None, &edits);
+    }
+
     pub fn add_decoration(&mut self, decoration: Decoration) {
         self.0.decorations.borrow_mut().add_decoration(decoration);
     }
@@ -210,6 +259,18 @@ impl CodeDocument {
         self.0.decorations.borrow_mut().clear()
     }
 
+    pub fn remove_decorations_with_id(&mut self, id: usize) {
+        self.0.decorations.borrow_mut().remove_decorations_with_id(id)
+    }
+
+    /// Returns a receiver for the edits that are made to the document from now on, in the order
+    /// in which they are applied. Dropping the receiver unsubscribes it.
+    pub fn subscribe_to_edits(&self) -> Receiver<Vec<Edit>> {
+        let (edit_sender, edit_receiver) = mpsc::channel();
+        self.0.edit_listeners.borrow_mut().push(edit_sender);
+        edit_receiver
+    }
+
     pub fn add_session(
         &mut self,
         session_id: SessionId,
@@ -414,6 +475,10 @@ impl CodeDocument {
             decorations.apply_edit(edit);
         }
         drop(decorations);
+        self.0
+            .edit_listeners
+            .borrow_mut()
+            .retain(|edit_sender| edit_sender.send(edits.to_vec()).is_ok());
         for (&session_id, edit_sender) in &*self.0.edit_senders.borrow() {
             if Some(session_id) == origin_id {
                 edit_sender
@@ -713,6 +778,7 @@ struct DocumentInner {
     tokenizer: RefCell<Tokenizer>,
     decorations: RefCell<DecorationSet>,
     edit_senders: RefCell<HashMap<SessionId, Sender<(Option<SelectionSet>, Vec<Edit>)>>>,
+    edit_listeners: RefCell<Vec<Sender<Vec<Edit>>>>,
 }
 
 fn tokenize(text: &str) -> impl Iterator<Item = Token> + '_ {
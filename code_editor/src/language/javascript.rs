@@ -0,0 +1,87 @@
+use super::*;
+
+const KEYWORDS: Keywords = Keywords {
+    branch: &[
+        "case", "catch", "default", "else", "finally", "if", "return", "switch", "throw", "try",
+        "yield",
+    ],
+    loops: &["break", "continue", "do", "for", "while"],
+    other: &[
+        "abstract", "as", "async", "await", "class", "const", "debugger", "declare", "delete",
+        "enum", "export", "extends", "false", "from", "function", "get", "implements", "import",
+        "in", "instanceof", "interface", "let", "namespace", "new", "null", "of", "private",
+        "protected", "public", "readonly", "set", "static", "super", "this", "true", "type",
+        "typeof", "undefined", "var", "void", "with",
+    ],
+};
+
+/// JavaScript and TypeScript.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct JavaScript;
+
+impl Language for JavaScript {
+    fn name(&self) -> &'static str {
+        "JavaScript"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["js", "mjs", "cjs", "jsx", "ts", "tsx"]
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        Some("//")
+    }
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        Some(("/*", "*/"))
+    }
+
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match state {
+            State::BlockComment { depth } => block_comment_tail(cursor, depth, None, "*/"),
+            State::String(string) => string_tail(cursor, string),
+            State::Initial | State::Other(_) => self.initial(cursor),
+        }
+    }
+}
+
+impl JavaScript {
+    fn initial(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '/') => (State::Initial, line_comment(cursor)),
+            ('/', '*') => {
+                cursor.skip(2);
+                block_comment_tail(cursor, 0, None, "*/")
+            }
+            ('`', _) => {
+                // Template literals are the only strings that can span multiple lines.
+                cursor.skip(1);
+                string_tail(cursor, StringState::new('`'))
+            }
+            ('"', _) | ('\'', _) => {
+                let quote = cursor.peek(0);
+                cursor.skip(1);
+                match string_tail(cursor, StringState::new(quote)) {
+                    (State::String(_), _) => (State::Initial, TokenKind::Unknown),
+                    result => result,
+                }
+            }
+            ('.', char) if char.is_ascii_digit() => (State::Initial, number(cursor)),
+            (char, _) if char.is_ascii_digit() => (State::Initial, number(cursor)),
+            (char, _) if char.is_identifier_start() || char == '$' => {
+                (State::Initial, self.identifier_or_keyword(cursor))
+            }
+            (char, _) if char.is_whitespace() => (State::Initial, whitespace(cursor)),
+            _ => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+        }
+    }
+
+    fn identifier_or_keyword(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        let start = cursor.index();
+        cursor.skip_while(|char| char.is_identifier_continue() || char == '$');
+        let string = cursor.slice_from(start);
+        KEYWORDS
+            .kind(string)
+            .unwrap_or_else(|| identifier_kind(string, cursor))
+    }
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+/// JSON, where comments as in JSONC are accepted, and object keys are tokenized as identifiers.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Json;
+
+impl Language for Json {
+    fn name(&self) -> &'static str {
+        "JSON"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json", "jsonc"]
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        Some("//")
+    }
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        Some(("/*", "*/"))
+    }
+
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match state {
+            State::BlockComment { depth } => block_comment_tail(cursor, depth, None, "*/"),
+            _ => self.initial(cursor),
+        }
+    }
+}
+
+impl Json {
+    fn initial(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '/') => (State::Initial, line_comment(cursor)),
+            ('/', '*') => {
+                cursor.skip(2);
+                block_comment_tail(cursor, 0, None, "*/")
+            }
+            ('"', _) => (State::Initial, self.string_or_key(cursor)),
+            ('-', char) if char.is_ascii_digit() => {
+                cursor.skip(1);
+                (State::Initial, number(cursor))
+            }
+            (char, _) if char.is_ascii_digit() => (State::Initial, number(cursor)),
+            (char, _) if char.is_identifier_start() => (State::Initial, self.literal(cursor)),
+            (char, _) if char.is_whitespace() => (State::Initial, whitespace(cursor)),
+            _ => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+        }
+    }
+
+    fn string_or_key(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        debug_assert!(cursor.peek(0) == '"');
+        cursor.skip(1);
+        if let (State::String(_), _) = string_tail(cursor, StringState::new('"')) {
+            return TokenKind::Unknown;
+        }
+        let mut index = 0;
+        while cursor.peek(index).is_whitespace() {
+            index += 1;
+        }
+        if cursor.peek(index) == ':' {
+            TokenKind::Identifier
+        } else {
+            TokenKind::String
+        }
+    }
+
+    fn literal(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        let start = cursor.index();
+        cursor.skip_while(|char| char.is_identifier_continue());
+        match cursor.slice_from(start) {
+            "true" | "false" => TokenKind::OtherKeyword,
+            "null" => TokenKind::Constant,
+            _ => TokenKind::Unknown,
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+
+/// The state inside a code block fenced with backticks.
+const BACKTICK_FENCE: usize = 1;
+/// The state inside a code block fenced with tildes.
+const TILDE_FENCE: usize = 2;
+
+/// Markdown, where block level constructs like headings and quotes are tokenized a line at a time,
+/// and the contents of fenced code blocks as strings.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Markdown;
+
+impl Language for Markdown {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["md", "markdown"]
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        Some(("<!--", "-->"))
+    }
+
+    fn brackets(&self) -> &'static [(char, char)] {
+        &[('(', ')'), ('[', ']')]
+    }
+
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match state {
+            State::BlockComment { depth } => block_comment_tail(cursor, depth, None, "-->"),
+            State::Other(fence) => self.fenced_code(cursor, fence),
+            State::Initial | State::String(_) => self.initial(cursor),
+        }
+    }
+}
+
+impl Markdown {
+    fn initial(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        if cursor.is_at_line_start() {
+            if let Some(result) = self.block(cursor) {
+                return result;
+            }
+        }
+        match cursor.peek(0) {
+            '`' => (State::Initial, self.code_span(cursor)),
+            '<' if cursor.starts_with("<!--") => {
+                cursor.skip(4);
+                block_comment_tail(cursor, 0, None, "-->")
+            }
+            char if char.is_alphanumeric() => {
+                cursor.skip_while(|char| char.is_alphanumeric() || char == '_');
+                (State::Initial, TokenKind::Identifier)
+            }
+            char if char.is_whitespace() => (State::Initial, whitespace(cursor)),
+            _ => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+        }
+    }
+
+    /// Reads the marker of a block level construct at the start of a line, if there is one.
+    fn block(self, cursor: &mut Cursor<'_>) -> Option<(State, TokenKind)> {
+        match (cursor.peek(0), cursor.peek(1)) {
+            (char, _) if char.is_whitespace() => Some((State::Initial, whitespace(cursor))),
+            ('`', _) if cursor.starts_with("```") => {
+                cursor.skip_to_end();
+                Some((State::Other(BACKTICK_FENCE), TokenKind::OtherKeyword))
+            }
+            ('~', _) if cursor.starts_with("~~~") => {
+                cursor.skip_to_end();
+                Some((State::Other(TILDE_FENCE), TokenKind::OtherKeyword))
+            }
+            ('#', _) if is_heading(cursor) => {
+                cursor.skip_to_end();
+                Some((State::Initial, TokenKind::Typename))
+            }
+            ('>', _) => {
+                cursor.skip_to_end();
+                Some((State::Initial, TokenKind::Comment))
+            }
+            ('-', ' ') | ('*', ' ') | ('+', ' ') => {
+                cursor.skip(1);
+                Some((State::Initial, TokenKind::Punctuator))
+            }
+            (char, _) if char.is_ascii_digit() => {
+                let mut count = 1;
+                while cursor.peek(count).is_ascii_digit() {
+                    count += 1;
+                }
+                if !matches!(cursor.peek(count), '.' | ')') || cursor.peek(count + 1) != ' ' {
+                    return None;
+                }
+                cursor.skip(count + 1);
+                Some((State::Initial, TokenKind::Number))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads a line inside a fenced code block, which is either code or the closing fence.
+    fn fenced_code(self, cursor: &mut Cursor<'_>, fence: usize) -> (State, TokenKind) {
+        let fence_char = if fence == TILDE_FENCE { '~' } else { '`' };
+        let mut index = 0;
+        while cursor.peek(index) == ' ' {
+            index += 1;
+        }
+        let is_closing_fence = (index..index + 3).all(|index| cursor.peek(index) == fence_char);
+        cursor.skip_to_end();
+        if is_closing_fence {
+            (State::Initial, TokenKind::OtherKeyword)
+        } else {
+            (State::Other(fence), TokenKind::String)
+        }
+    }
+
+    /// Reads a code span like `` `code` ``, which ends with as many backticks as it starts with.
+    fn code_span(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        debug_assert!(cursor.peek(0) == '`');
+        let mut count = 0;
+        while cursor.peek(count) == '`' {
+            count += 1;
+        }
+        let mut index = count;
+        loop {
+            match cursor.peek(index) {
+                '\0' => {
+                    cursor.skip(count);
+                    return TokenKind::Punctuator;
+                }
+                '`' => {
+                    let start = index;
+                    while cursor.peek(index) == '`' {
+                        index += 1;
+                    }
+                    if index - start == count {
+                        cursor.skip(index);
+                        return TokenKind::String;
+                    }
+                }
+                _ => index += 1,
+            }
+        }
+    }
+}
+
+fn is_heading(cursor: &Cursor<'_>) -> bool {
+    let mut count = 0;
+    while cursor.peek(count) == '#' {
+        count += 1;
+    }
+    count <= 6 && matches!(cursor.peek(count), ' ' | '\t' | '\0')
+}
@@ -0,0 +1,575 @@
+//! Languages that the tokenizer can highlight.
+//!
+//! A language tokenizes one line at a time. Everything it needs to carry over to the next line,
+//! such as being inside a block comment or a multiline string, goes in the returned `State`. This
+//! lets the `Tokenizer` retokenize only the lines that changed, and stop as soon as the state at
+//! the start of a line is the same as before.
+
+mod javascript;
+mod json;
+mod markdown;
+mod python;
+mod rust;
+mod shader;
+mod toml;
+
+pub use self::{
+    javascript::JavaScript, json::Json, markdown::Markdown, python::Python, rust::Rust,
+    shader::Shader, toml::Toml,
+};
+
+use {
+    crate::{
+        token::TokenKind,
+        tokenizer::{CharExt, Cursor, State, StringState},
+    },
+    std::{fmt, path::Path, sync::Mutex},
+};
+
+pub trait Language: fmt::Debug + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The lowercase file extensions, without the leading `.`, that this language is used for.
+    fn extensions(&self) -> &'static [&'static str];
+
+    fn line_comment(&self) -> Option<&'static str>;
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)>;
+
+    /// The pairs of brackets that are tokenized as delimiters.
+    fn brackets(&self) -> &'static [(char, char)] {
+        &[('(', ')'), ('[', ']'), ('{', '}')]
+    }
+
+    /// Reads the next token from `cursor`, which is never at the end of the line, and returns
+    /// the state after it together with its kind.
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind);
+}
+
+const BUILTIN_LANGUAGES: &[&dyn Language] = &[
+    &Rust,
+    &Toml,
+    &Json,
+    &Markdown,
+    &Shader,
+    &JavaScript,
+    &Python,
+];
+
+static REGISTERED_LANGUAGES: Mutex<Vec<&'static dyn Language>> = Mutex::new(Vec::new());
+
+/// Registers a language, which takes precedence over the built-in languages and earlier
+/// registered ones for its extensions.
+pub fn register_language(language: &'static dyn Language) {
+    REGISTERED_LANGUAGES.lock().unwrap().push(language);
+}
+
+pub fn language_for_extension(extension: &str) -> Option<&'static dyn Language> {
+    let extension = extension.to_lowercase();
+    let registered_languages = REGISTERED_LANGUAGES.lock().unwrap();
+    registered_languages
+        .iter()
+        .rev()
+        .chain(BUILTIN_LANGUAGES)
+        .copied()
+        .find(|language| language.extensions().contains(&extension.as_str()))
+}
+
+pub fn language_for_path(path: impl AsRef<Path>) -> Option<&'static dyn Language> {
+    language_for_extension(path.as_ref().extension()?.to_str()?)
+}
+
+struct Keywords {
+    branch: &'static [&'static str],
+    loops: &'static [&'static str],
+    other: &'static [&'static str],
+}
+
+impl Keywords {
+    fn kind(&self, string: &str) -> Option<TokenKind> {
+        if self.branch.contains(&string) {
+            Some(TokenKind::BranchKeyword)
+        } else if self.loops.contains(&string) {
+            Some(TokenKind::LoopKeyword)
+        } else if self.other.contains(&string) {
+            Some(TokenKind::OtherKeyword)
+        } else {
+            None
+        }
+    }
+}
+
+/// Classifies an identifier that is not a keyword by its casing, and whether it is called.
+fn identifier_kind(string: &str, cursor: &Cursor<'_>) -> TokenKind {
+    let mut chars = string.chars();
+    if chars.next().map_or(false, |char| char.is_uppercase()) {
+        match chars.next() {
+            Some(char) if char.is_uppercase() => TokenKind::Constant,
+            _ => TokenKind::Typename,
+        }
+    } else if cursor.peek(0) == '(' {
+        TokenKind::Function
+    } else {
+        TokenKind::Identifier
+    }
+}
+
+fn identifier_or_keyword(cursor: &mut Cursor<'_>, keywords: &Keywords) -> TokenKind {
+    debug_assert!(cursor.peek(0).is_identifier_start());
+    let start = cursor.index();
+    cursor.skip_while(|char| char.is_identifier_continue());
+    let string = cursor.slice_from(start);
+    keywords
+        .kind(string)
+        .unwrap_or_else(|| identifier_kind(string, cursor))
+}
+
+fn whitespace(cursor: &mut Cursor<'_>) -> TokenKind {
+    debug_assert!(cursor.peek(0).is_whitespace());
+    cursor.skip_while(|char| char.is_whitespace());
+    TokenKind::Whitespace
+}
+
+fn line_comment(cursor: &mut Cursor<'_>) -> TokenKind {
+    cursor.skip_to_end();
+    TokenKind::Comment
+}
+
+/// Reads the rest of a block comment that ends with `close`. If `open` is given, comments nest.
+fn block_comment_tail(
+    cursor: &mut Cursor<'_>,
+    depth: usize,
+    open: Option<&str>,
+    close: &str,
+) -> (State, TokenKind) {
+    let mut depth = depth;
+    loop {
+        if cursor.peek(0) == '\0' {
+            break (State::BlockComment { depth }, TokenKind::Comment);
+        }
+        if open.map_or(false, |open| cursor.skip_str(open)) {
+            depth += 1;
+        } else if cursor.skip_str(close) {
+            if depth == 0 {
+                break (State::Initial, TokenKind::Comment);
+            }
+            depth -= 1;
+        } else {
+            cursor.skip(1);
+        }
+    }
+}
+
+/// Reads the rest of a string, up to and including its closing quotes or the end of the line.
+fn string_tail(cursor: &mut Cursor<'_>, string: StringState) -> (State, TokenKind) {
+    loop {
+        match cursor.peek(0) {
+            '\0' => break (State::String(string), TokenKind::String),
+            '\\' if !string.is_raw => cursor.skip(2),
+            char if char == string.quote && is_string_end(cursor, string) => {
+                cursor.skip(string.quote_count + string.hash_count);
+                break (State::Initial, TokenKind::String);
+            }
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+fn is_string_end(cursor: &Cursor<'_>, string: StringState) -> bool {
+    (0..string.quote_count).all(|index| cursor.peek(index) == string.quote)
+        && (0..string.hash_count)
+            .all(|index| cursor.peek(string.quote_count + index) == '#')
+}
+
+/// Reads a number with an optional `0b`, `0o` or `0x` prefix, fraction, exponent and suffix, as
+/// most C-like languages have them.
+fn number(cursor: &mut Cursor<'_>) -> TokenKind {
+    let radix = match (cursor.peek(0), cursor.peek(1)) {
+        ('0', 'b') | ('0', 'B') => 2,
+        ('0', 'o') | ('0', 'O') => 8,
+        ('0', 'x') | ('0', 'X') => 16,
+        _ => 10,
+    };
+    if radix != 10 {
+        cursor.skip(2);
+        if !cursor.skip_digits(radix) {
+            return TokenKind::Unknown;
+        }
+        cursor.skip_suffix();
+        return TokenKind::Number;
+    }
+    cursor.skip_digits(10);
+    if cursor.peek(0) == '.' && cursor.peek(1) != '.' && !cursor.peek(1).is_identifier_start() {
+        cursor.skip(1);
+        cursor.skip_digits(10);
+    }
+    if cursor.peek(0) == 'E' || cursor.peek(0) == 'e' {
+        if !cursor.skip_exponent() {
+            return TokenKind::Unknown;
+        }
+    }
+    cursor.skip_suffix();
+    TokenKind::Number
+}
+
+/// Reads a single character that is not part of any other token.
+fn delimiter_or_punctuator(language: &dyn Language, cursor: &mut Cursor<'_>) -> TokenKind {
+    let char = cursor.peek(0);
+    cursor.skip(1);
+    if language
+        .brackets()
+        .iter()
+        .any(|&(open, close)| char == open || char == close)
+    {
+        TokenKind::Delimiter
+    } else if char.is_ascii_punctuation() {
+        TokenKind::Punctuator
+    } else {
+        TokenKind::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{text::Text, token::TokenKind::*, tokenizer::Tokenizer},
+    };
+
+    fn tokens<'a>(
+        language: &'static dyn Language,
+        text: &'a Text,
+    ) -> Vec<Vec<(&'a str, TokenKind)>> {
+        let lines = text.as_lines();
+        let mut tokens = vec![Vec::new(); lines.len()];
+        Tokenizer::with_language(language, lines.len()).update(text, &mut tokens);
+        lines
+            .iter()
+            .zip(tokens)
+            .map(|(line, tokens)| {
+                let mut start = 0;
+                tokens
+                    .into_iter()
+                    .map(|token| {
+                        start += token.len;
+                        (&line[start - token.len..start], token.kind)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Compares the tokens of each line, leaving out the whitespace.
+    fn assert_tokens(
+        language: &'static dyn Language,
+        source: &str,
+        expected: &[&[(&str, TokenKind)]],
+    ) {
+        let text = Text::from(source);
+        let mut tokens = tokens(language, &text);
+        for line in &mut tokens {
+            line.retain(|&(_, kind)| kind != Whitespace);
+        }
+        assert_eq!(tokens, expected);
+    }
+
+    // The output of the Rust tokenizer from before languages were pluggable, whitespace included.
+    #[test]
+    fn rust_matches_baseline() {
+        let source = r##"/// Docs with `code`.
+pub fn main<'a>(x: &'a str) -> Option<u32> {
+    let mut count = 0x_ff_u8 as u32 + 1_000 + 2.5e-3f32 as u32; // trailing
+    for (index, char) in x.chars().enumerate() {
+        if char == '\'' || char == '"' {
+            continue;
+        } else if index > MAX_LEN {
+            break;
+        }
+        loop { count += 1; }
+    }
+    while count < 10 { count += 1 }
+    let s = "multi
+line \" string";
+    let r = r#"raw "quoted" \n"# ;
+    let b = b'x'; let l = 'label: loop { break 'label; };
+    /* nested /* block
+       comment */ still */ match x { _ => Vec::<String>::new() }
+    Some(count.max(1..=2))
+}"##;
+        let expected: &[&[(&str, TokenKind)]] = &[
+            &[
+                ("/// Docs with `code`.", Comment),
+            ],
+            &[
+                ("pub", OtherKeyword), (" ", Whitespace), ("fn", OtherKeyword), (" ", Whitespace),
+                ("main", Identifier), ("<", Punctuator), ("'a", String), (">", Punctuator),
+                ("(", Delimiter), ("x", Identifier), (":", Punctuator), (" ", Whitespace),
+                ("&", Punctuator), ("'a", String), (" ", Whitespace), ("str", Identifier),
+                (")", Delimiter), (" ", Whitespace), ("->", Punctuator), (" ", Whitespace),
+                ("Option", Typename), ("<", Punctuator), ("u32", OtherKeyword), (">", Punctuator),
+                (" ", Whitespace), ("{", Delimiter),
+            ],
+            &[
+                ("    ", Whitespace), ("let", OtherKeyword), (" ", Whitespace),
+                ("mut", OtherKeyword), (" ", Whitespace), ("count", Identifier), (" ", Whitespace),
+                ("=", Punctuator), (" ", Whitespace), ("0x_ff_", Number), ("u8", OtherKeyword),
+                (" ", Whitespace), ("as", OtherKeyword), (" ", Whitespace), ("u32", OtherKeyword),
+                (" ", Whitespace), ("+", Punctuator), (" ", Whitespace), ("1_000", Number),
+                (" ", Whitespace), ("+", Punctuator), (" ", Whitespace), ("2.5e-3f32", Number),
+                (" ", Whitespace), ("as", OtherKeyword), (" ", Whitespace), ("u32", OtherKeyword),
+                (";", Punctuator), (" ", Whitespace), ("// trailing", Comment),
+            ],
+            &[
+                ("    ", Whitespace), ("for", LoopKeyword), (" ", Whitespace), ("(", Delimiter),
+                ("index", Identifier), (",", Punctuator), (" ", Whitespace), ("char", Identifier),
+                (")", Delimiter), (" ", Whitespace), ("in", OtherKeyword), (" ", Whitespace),
+                ("x", Identifier), (".", Punctuator), ("chars", Function), ("(", Delimiter),
+                (")", Delimiter), (".", Punctuator), ("enumerate", Function), ("(", Delimiter),
+                (")", Delimiter), (" ", Whitespace), ("{", Delimiter),
+            ],
+            &[
+                ("        ", Whitespace), ("if", BranchKeyword), (" ", Whitespace),
+                ("char", Identifier), (" ", Whitespace), ("==", Punctuator), (" ", Whitespace),
+                ("'\\''", String), (" ", Whitespace), ("||", Punctuator), (" ", Whitespace),
+                ("char", Identifier), (" ", Whitespace), ("==", Punctuator), (" ", Whitespace),
+                ("'\"'", String), (" ", Whitespace), ("{", Delimiter),
+            ],
+            &[
+                ("            ", Whitespace), ("continue", LoopKeyword), (";", Punctuator),
+            ],
+            &[
+                ("        ", Whitespace), ("}", Delimiter), (" ", Whitespace),
+                ("else", BranchKeyword), (" ", Whitespace), ("if", BranchKeyword),
+                (" ", Whitespace), ("index", Identifier), (" ", Whitespace), (">", Punctuator),
+                (" ", Whitespace), ("MAX_LEN", Constant), (" ", Whitespace), ("{", Delimiter),
+            ],
+            &[
+                ("            ", Whitespace), ("break", LoopKeyword), (";", Punctuator),
+            ],
+            &[
+                ("        ", Whitespace), ("}", Delimiter),
+            ],
+            &[
+                ("        ", Whitespace), ("loop", LoopKeyword), (" ", Whitespace),
+                ("{", Delimiter), (" ", Whitespace), ("count", Identifier), (" ", Whitespace),
+                ("+=", Punctuator), (" ", Whitespace), ("1", Number), (";", Punctuator),
+                (" ", Whitespace), ("}", Delimiter),
+            ],
+            &[
+                ("    ", Whitespace), ("}", Delimiter),
+            ],
+            &[
+                ("    ", Whitespace), ("while", LoopKeyword), (" ", Whitespace),
+                ("count", Identifier), (" ", Whitespace), ("<", Punctuator), (" ", Whitespace),
+                ("10", Number), (" ", Whitespace), ("{", Delimiter), (" ", Whitespace),
+                ("count", Identifier), (" ", Whitespace), ("+=", Punctuator), (" ", Whitespace),
+                ("1", Number), (" ", Whitespace), ("}", Delimiter),
+            ],
+            &[
+                ("    ", Whitespace), ("let", OtherKeyword), (" ", Whitespace), ("s", Identifier),
+                (" ", Whitespace), ("=", Punctuator), (" ", Whitespace), ("\"multi", String),
+            ],
+            &[
+                ("line \\\" string\"", String), (";", Punctuator),
+            ],
+            &[
+                ("    ", Whitespace), ("let", OtherKeyword), (" ", Whitespace), ("r", Identifier),
+                (" ", Whitespace), ("=", Punctuator), (" ", Whitespace),
+                ("r#\"raw \"quoted\" \\n\"#", String), (" ", Whitespace), (";", Punctuator),
+            ],
+            &[
+                ("    ", Whitespace), ("let", OtherKeyword), (" ", Whitespace), ("b", Identifier),
+                (" ", Whitespace), ("=", Punctuator), (" ", Whitespace), ("b'x'", String),
+                (";", Punctuator), (" ", Whitespace), ("let", OtherKeyword), (" ", Whitespace),
+                ("l", Identifier), (" ", Whitespace), ("=", Punctuator), (" ", Whitespace),
+                ("'label", String), (":", Punctuator), (" ", Whitespace), ("loop", LoopKeyword),
+                (" ", Whitespace), ("{", Delimiter), (" ", Whitespace), ("break", LoopKeyword),
+                (" ", Whitespace), ("'label", String), (";", Punctuator), (" ", Whitespace),
+                ("}", Delimiter), (";", Punctuator),
+            ],
+            &[
+                ("    ", Whitespace), ("/* nested /* block", Comment),
+            ],
+            &[
+                ("       comment */ still */", Comment), (" ", Whitespace),
+                ("match", BranchKeyword), (" ", Whitespace), ("x", Identifier), (" ", Whitespace),
+                ("{", Delimiter), (" ", Whitespace), ("_", Punctuator), (" ", Whitespace),
+                ("=>", Punctuator), (" ", Whitespace), ("Vec", Typename), ("::", Punctuator),
+                ("<", Punctuator), ("String", Typename), (">", Punctuator), ("::", Punctuator),
+                ("new", Function), ("(", Delimiter), (")", Delimiter), (" ", Whitespace),
+                ("}", Delimiter),
+            ],
+            &[
+                ("    ", Whitespace), ("Some", Typename), ("(", Delimiter), ("count", Identifier),
+                (".", Punctuator), ("max", Function), ("(", Delimiter), ("1", Number),
+                ("..", Punctuator), ("=", Punctuator), ("2", Number), (")", Delimiter),
+                (")", Delimiter),
+            ],
+            &[
+                ("}", Delimiter),
+            ],
+        ];
+        assert_eq!(tokens(&Rust, &Text::from(source)), expected);
+    }
+
+    #[test]
+    fn toml() {
+        assert_tokens(
+            &Toml,
+            "[package]\nname = \"demo\" # comment\nfloat = -1.5e3\ndate = 1979-05-27T07:32:00Z\nflags = [true, inf]\n[[bin]]\ntext = \"\"\"\nmulti\n\"\"\"",
+            &[
+                &[("[package]", Typename)],
+                &[
+                    ("name", Identifier), ("=", Punctuator), ("\"demo\"", String),
+                    ("# comment", Comment),
+                ],
+                &[("float", Identifier), ("=", Punctuator), ("-1.5e3", Number)],
+                &[("date", Identifier), ("=", Punctuator), ("1979-05-27T07:32:00Z", Number)],
+                &[
+                    ("flags", Identifier), ("=", Punctuator), ("[", Delimiter),
+                    ("true", OtherKeyword), (",", Punctuator), ("inf", Number), ("]", Delimiter),
+                ],
+                &[("[[bin]]", Typename)],
+                &[("text", Identifier), ("=", Punctuator), ("\"\"\"", String)],
+                &[("multi", String)],
+                &[("\"\"\"", String)],
+            ],
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_tokens(
+            &Json,
+            "{\"key\": \"esc\\\"aped\", // comment\n\"n\": [-1.5e3, true, null, bad] /* block\n*/}",
+            &[
+                &[
+                    ("{", Delimiter), ("\"key\"", Identifier), (":", Punctuator),
+                    ("\"esc\\\"aped\"", String), (",", Punctuator), ("// comment", Comment),
+                ],
+                &[
+                    ("\"n\"", Identifier), (":", Punctuator), ("[", Delimiter), ("-1.5e3", Number),
+                    (",", Punctuator), ("true", OtherKeyword), (",", Punctuator),
+                    ("null", Constant), (",", Punctuator), ("bad", Unknown), ("]", Delimiter),
+                    ("/* block", Comment),
+                ],
+                &[("*/", Comment), ("}", Delimiter)],
+            ],
+        );
+    }
+
+    #[test]
+    fn markdown() {
+        assert_tokens(
+            &Markdown,
+            "# Heading\n> quote\n- item with `code` <!-- note -->\n1. first\n```rust\nlet x = 1;\n```\nplain *text*",
+            &[
+                &[("# Heading", Typename)],
+                &[("> quote", Comment)],
+                &[
+                    ("-", Punctuator), ("item", Identifier), ("with", Identifier),
+                    ("`code`", String), ("<!-- note -->", Comment),
+                ],
+                &[("1.", Number), ("first", Identifier)],
+                &[("```rust", OtherKeyword)],
+                &[("let x = 1;", String)],
+                &[("```", OtherKeyword)],
+                &[
+                    ("plain", Identifier), ("*", Punctuator), ("text", Identifier),
+                    ("*", Punctuator),
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn shader() {
+        assert_tokens(
+            &Shader,
+            "#version 300 es\nuniform vec4 color; // tint\nfn pixel(self) {\n    if self.x > 0.5 { return #f00; }\n    /* block\n    */ for i in 0..4 { discard; }\n}",
+            &[
+                &[("#version", OtherKeyword), ("300", Number), ("es", Identifier)],
+                &[
+                    ("uniform", OtherKeyword), ("vec4", OtherKeyword), ("color", Identifier),
+                    (";", Punctuator), ("// tint", Comment),
+                ],
+                &[
+                    ("fn", OtherKeyword), ("pixel", Function), ("(", Delimiter),
+                    ("self", OtherKeyword), (")", Delimiter), ("{", Delimiter),
+                ],
+                &[
+                    ("if", BranchKeyword), ("self", OtherKeyword), (".", Punctuator),
+                    ("x", Identifier), (">", Punctuator), ("0.5", Number), ("{", Delimiter),
+                    ("return", BranchKeyword), ("#f00", Number), (";", Punctuator),
+                    ("}", Delimiter),
+                ],
+                &[("/* block", Comment)],
+                &[
+                    ("    */", Comment), ("for", LoopKeyword), ("i", Identifier),
+                    ("in", OtherKeyword), ("0", Number), ("..", Punctuator), ("4", Number),
+                    ("{", Delimiter), ("discard", BranchKeyword), (";", Punctuator),
+                    ("}", Delimiter),
+                ],
+                &[("}", Delimiter)],
+            ],
+        );
+    }
+
+    #[test]
+    fn javascript() {
+        assert_tokens(
+            &JavaScript,
+            "const $el = query(`tmpl ${x}\nnext`);\nif (a == 0x1F) { return 'str'; }\nwhile (i < 10) i++; /* block\n*/ class Foo extends Bar {}",
+            &[
+                &[
+                    ("const", OtherKeyword), ("$el", Identifier), ("=", Punctuator),
+                    ("query", Function), ("(", Delimiter), ("`tmpl ${x}", String),
+                ],
+                &[("next`", String), (")", Delimiter), (";", Punctuator)],
+                &[
+                    ("if", BranchKeyword), ("(", Delimiter), ("a", Identifier), ("=", Punctuator),
+                    ("=", Punctuator), ("0x1F", Number), (")", Delimiter), ("{", Delimiter),
+                    ("return", BranchKeyword), ("'str'", String), (";", Punctuator),
+                    ("}", Delimiter),
+                ],
+                &[
+                    ("while", LoopKeyword), ("(", Delimiter), ("i", Identifier), ("<", Punctuator),
+                    ("10", Number), (")", Delimiter), ("i", Identifier), ("+", Punctuator),
+                    ("+", Punctuator), (";", Punctuator), ("/* block", Comment),
+                ],
+                &[
+                    ("*/", Comment), ("class", OtherKeyword), ("Foo", Typename),
+                    ("extends", OtherKeyword), ("Bar", Typename), ("{", Delimiter),
+                    ("}", Delimiter),
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn python() {
+        assert_tokens(
+            &Python,
+            "@decorator\ndef run(self, x=1.5):\n    if x is None: return f\"{x}\" # note\n    s = '''multi\n    line'''\n    return rb'raw' + Foo + CONST",
+            &[
+                &[("@decorator", Function)],
+                &[
+                    ("def", OtherKeyword), ("run", Function), ("(", Delimiter),
+                    ("self", OtherKeyword), (",", Punctuator), ("x", Identifier), ("=", Punctuator),
+                    ("1.5", Number), (")", Delimiter), (":", Punctuator),
+                ],
+                &[
+                    ("if", BranchKeyword), ("x", Identifier), ("is", OtherKeyword),
+                    ("None", OtherKeyword), (":", Punctuator), ("return", BranchKeyword),
+                    ("f\"{x}\"", String), ("# note", Comment),
+                ],
+                &[("s", Identifier), ("=", Punctuator), ("'''multi", String)],
+                &[("    line'''", String)],
+                &[
+                    ("return", BranchKeyword), ("rb'raw'", String), ("+", Punctuator),
+                    ("Foo", Typename), ("+", Punctuator), ("CONST", Constant),
+                ],
+            ],
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use super::*;
+
+const KEYWORDS: Keywords = Keywords {
+    branch: &[
+        "elif", "else", "except", "finally", "if", "match", "case", "raise", "return", "try",
+        "yield",
+    ],
+    loops: &["break", "continue", "for", "while"],
+    other: &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "class", "def", "del",
+        "from", "global", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "self",
+        "with",
+    ],
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Python;
+
+impl Language for Python {
+    fn name(&self) -> &'static str {
+        "Python"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["py", "pyi", "pyw"]
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        Some("#")
+    }
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        None
+    }
+
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match state {
+            State::String(string) => string_tail(cursor, string),
+            _ => self.initial(cursor),
+        }
+    }
+}
+
+impl Python {
+    fn initial(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('#', _) => (State::Initial, line_comment(cursor)),
+            ('@', char) if char.is_identifier_start() => {
+                cursor.skip(1);
+                cursor.skip_while(|char| char.is_identifier_continue() || char == '.');
+                (State::Initial, TokenKind::Function)
+            }
+            ('"', _) | ('\'', _) => self.string(cursor, 0),
+            ('.', char) if char.is_ascii_digit() => (State::Initial, number(cursor)),
+            (char, _) if char.is_ascii_digit() => (State::Initial, number(cursor)),
+            (char, _) if char.is_identifier_start() => match string_prefix_len(cursor) {
+                Some(prefix_len) => self.string(cursor, prefix_len),
+                None => (State::Initial, identifier_or_keyword(cursor, &KEYWORDS)),
+            },
+            (char, _) if char.is_whitespace() => (State::Initial, whitespace(cursor)),
+            _ => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+        }
+    }
+
+    /// Reads a string that starts with a prefix of `prefix_len` characters, like `rb` in `rb"..."`.
+    fn string(self, cursor: &mut Cursor<'_>, prefix_len: usize) -> (State, TokenKind) {
+        let is_raw = (0..prefix_len).any(|index| matches!(cursor.peek(index), 'r' | 'R'));
+        cursor.skip(prefix_len);
+        let quote = cursor.peek(0);
+        let string = StringState {
+            is_raw,
+            ..StringState::new(quote)
+        };
+        if cursor.peek(1) == quote && cursor.peek(2) == quote {
+            cursor.skip(3);
+            return string_tail(
+                cursor,
+                StringState {
+                    quote_count: 3,
+                    ..string
+                },
+            );
+        }
+        cursor.skip(1);
+        match string_tail(cursor, string) {
+            (State::String(_), _) => (State::Initial, TokenKind::Unknown),
+            result => result,
+        }
+    }
+}
+
+/// Returns the length of the string prefix at the cursor, if it is followed by a quote.
+fn string_prefix_len(cursor: &Cursor<'_>) -> Option<usize> {
+    let mut count = 0;
+    while count < 2 && matches!(cursor.peek(count).to_ascii_lowercase(), 'r' | 'b' | 'u' | 'f') {
+        count += 1;
+    }
+    if count > 0 && matches!(cursor.peek(count), '"' | '\'') {
+        Some(count)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,229 @@
+use super::*;
+
+/// Rust, including the `live_design!` DSL, whose colors like `#f00` are tokenized as numbers.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rust;
+
+impl Language for Rust {
+    fn name(&self) -> &'static str {
+        "Rust"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["rs"]
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        Some("//")
+    }
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        Some(("/*", "*/"))
+    }
+
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match state {
+            State::BlockComment { depth } => block_comment_tail(cursor, depth, Some("/*"), "*/"),
+            State::String(string) => self.string_tail(cursor, string),
+            State::Initial | State::Other(_) => self.initial(cursor),
+        }
+    }
+}
+
+impl Rust {
+    fn initial(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+            ('r', '"', _) | ('r', '#', '"') | ('r', '#', '#') => self.raw_string(cursor),
+            ('b', 'r', '"') | ('b', 'r', '#') => self.raw_byte_string(cursor),
+            ('/', '/', _) => (State::Initial, line_comment(cursor)),
+            ('/', '*', _) => {
+                cursor.skip(2);
+                block_comment_tail(cursor, 0, Some("/*"), "*/")
+            }
+            ('b', '\'', _) => self.byte(cursor),
+            ('b', '"', _) => self.byte_string(cursor),
+            ('!', '=', _)
+            | ('%', '=', _)
+            | ('&', '&', _)
+            | ('&', '=', _)
+            | ('*', '=', _)
+            | ('+', '=', _)
+            | ('-', '=', _)
+            | ('-', '>', _)
+            | ('.', '.', _)
+            | ('/', '=', _)
+            | (':', ':', _)
+            | ('<', '<', _)
+            | ('<', '=', _)
+            | ('=', '=', _)
+            | ('=', '>', _)
+            | ('>', '=', _)
+            | ('>', '>', _)
+            | ('^', '=', _)
+            | ('|', '=', _)
+            | ('|', '|', _) => {
+                cursor.skip(2);
+                (State::Initial, TokenKind::Punctuator)
+            }
+            ('\'', _, _) => self.char_or_lifetime(cursor),
+            ('"', _, _) => self.string(cursor),
+            ('#', char, _) if char.is_ascii_hexdigit() => self.color_or_punctuator(cursor),
+            ('.', char, _) if char.is_digit(10) => self.number(cursor),
+            // A `_` is a punctuator, also at the start of a name like `_unused`.
+            ('_', _, _) => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+            (char, _, _) if char.is_identifier_start() => self.identifier_or_keyword(cursor),
+            (char, _, _) if char.is_digit(10) => self.number(cursor),
+            (char, _, _) if char.is_whitespace() => (State::Initial, whitespace(cursor)),
+            _ => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+        }
+    }
+
+    fn identifier_or_keyword(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0).is_identifier_start());
+        let start = cursor.index();
+        cursor.skip(1);
+        while cursor.skip_if(|char| char.is_identifier_continue()) {}
+        let string = cursor.slice_from(start);
+        (
+            State::Initial,
+            match string {
+                "else" | "if" | "match" | "return" => TokenKind::BranchKeyword,
+                "break" | "continue" | "for" | "loop" | "while" => TokenKind::LoopKeyword,
+                "Self" | "as" | "async" | "await" | "const" | "crate" | "dyn" | "enum"
+                | "extern" | "false" | "fn" | "impl" | "in" | "let" | "mod" | "move" | "mut"
+                | "pub" | "ref" | "self" | "static" | "struct" | "super" | "trait" | "true"
+                | "type" | "unsafe" | "use" | "where" | "usize" | "isize" | "u8" | "u16"
+                | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" | "vec2" | "vec3" | "vec4"
+                | "bool" | "f32" | "f64" => TokenKind::OtherKeyword,
+                _ => identifier_kind(string, cursor),
+            },
+        )
+    }
+
+    /// Binary, octal and hex numbers end at their last digit, so the suffix of `0xffu8` is
+    /// highlighted as a keyword of its own.
+    fn number(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        let radix = match (cursor.peek(0), cursor.peek(1)) {
+            ('0', 'b') => 2,
+            ('0', 'o') => 8,
+            ('0', 'x') => 16,
+            _ => return (State::Initial, number(cursor)),
+        };
+        cursor.skip(2);
+        if !cursor.skip_digits(radix) {
+            return (State::Initial, TokenKind::Unknown);
+        }
+        (State::Initial, TokenKind::Number)
+    }
+
+    /// Reads a color like `#f00` or `#ff000080` in the `live_design!` DSL. A `#` that isn't
+    /// followed by only hex digits, as in `#[derive]` or `#name` in `quote!`, is a punctuator.
+    fn color_or_punctuator(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0) == '#');
+        let mut count = 1;
+        while cursor.peek(count).is_ascii_hexdigit() {
+            count += 1;
+        }
+        if cursor.peek(count).is_identifier_continue() {
+            cursor.skip(1);
+            return (State::Initial, TokenKind::Punctuator);
+        }
+        cursor.skip(count);
+        (State::Initial, TokenKind::Number)
+    }
+
+    fn char_or_lifetime(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        if cursor.peek(1).is_identifier_start() && cursor.peek(2) != '\'' {
+            debug_assert!(cursor.peek(0) == '\'');
+            cursor.skip(2);
+            while cursor.skip_if(|ch| ch.is_identifier_continue()) {}
+            if cursor.peek(0) == '\'' {
+                cursor.skip(1);
+                cursor.skip_suffix();
+                (State::Initial, TokenKind::String)
+            } else {
+                (State::Initial, TokenKind::String)
+            }
+        } else {
+            self.single_quoted_string(cursor)
+        }
+    }
+
+    fn byte(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'b');
+        cursor.skip(1);
+        self.single_quoted_string(cursor)
+    }
+
+    fn string(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        self.double_quoted_string(cursor)
+    }
+
+    fn byte_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'b');
+        cursor.skip(1);
+        self.double_quoted_string(cursor)
+    }
+
+    fn raw_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'r');
+        cursor.skip(1);
+        self.raw_double_quoted_string(cursor)
+    }
+
+    fn raw_byte_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'b' && cursor.peek(1) == 'r');
+        cursor.skip(2);
+        self.raw_double_quoted_string(cursor)
+    }
+
+    fn single_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0) == '\'');
+        cursor.skip(1);
+        loop {
+            match (cursor.peek(0), cursor.peek(1)) {
+                ('\'', _) => {
+                    cursor.skip(1);
+                    cursor.skip_suffix();
+                    break;
+                }
+                ('\0', _) => return (State::Initial, TokenKind::Unknown),
+                ('\\', '\'') | ('\\', '\\') => cursor.skip(2),
+                _ => cursor.skip(1),
+            }
+        }
+        (State::Initial, TokenKind::String)
+    }
+
+    fn double_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        debug_assert!(cursor.peek(0) == '"');
+        cursor.skip(1);
+        self.string_tail(cursor, StringState::new('"'))
+    }
+
+    fn raw_double_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
+        let mut hash_count = 0;
+        while cursor.skip_if(|ch| ch == '#') {
+            hash_count += 1;
+        }
+        if !cursor.skip_if(|ch| ch == '"') {
+            return (State::Initial, TokenKind::Unknown);
+        }
+        self.string_tail(
+            cursor,
+            StringState {
+                hash_count,
+                is_raw: true,
+                ..StringState::new('"')
+            },
+        )
+    }
+
+    fn string_tail(self, cursor: &mut Cursor, string: StringState) -> (State, TokenKind) {
+        let (state, kind) = string_tail(cursor, string);
+        if state == State::Initial {
+            cursor.skip_suffix();
+        }
+        (state, kind)
+    }
+}
@@ -0,0 +1,93 @@
+use super::*;
+
+const KEYWORDS: Keywords = Keywords {
+    branch: &["discard", "else", "if", "return", "switch", "case", "default"],
+    loops: &["break", "continue", "do", "for", "while"],
+    other: &[
+        "const", "false", "fn", "geometry", "impl", "in", "inout", "instance", "let", "out",
+        "self", "struct", "texture", "true", "uniform", "var", "varying", "attribute", "layout",
+        "precision", "highp", "mediump", "lowp", "void", "bool", "int", "uint", "float", "double",
+        "vec2", "vec3", "vec4", "bvec2", "bvec3", "bvec4", "ivec2", "ivec3", "ivec4", "uvec2",
+        "uvec3", "uvec4", "mat2", "mat3", "mat4", "sampler2D", "samplerCube", "texture2D",
+        "textureOES",
+    ],
+};
+
+/// The shader DSL of `live_design!`, as well as GLSL, WGSL, HLSL and Metal shaders, which share
+/// enough of their syntax to be tokenized the same way.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Shader;
+
+impl Language for Shader {
+    fn name(&self) -> &'static str {
+        "Shader"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["glsl", "vert", "frag", "comp", "wgsl", "hlsl", "metal"]
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        Some("//")
+    }
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        Some(("/*", "*/"))
+    }
+
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match state {
+            State::BlockComment { depth } => block_comment_tail(cursor, depth, None, "*/"),
+            _ => self.initial(cursor),
+        }
+    }
+}
+
+impl Shader {
+    fn initial(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '/') => (State::Initial, line_comment(cursor)),
+            ('/', '*') => {
+                cursor.skip(2);
+                block_comment_tail(cursor, 0, None, "*/")
+            }
+            ('#', _) => (State::Initial, self.color_or_directive(cursor)),
+            ('"', _) => {
+                cursor.skip(1);
+                match string_tail(cursor, StringState::new('"')) {
+                    (State::String(_), _) => (State::Initial, TokenKind::Unknown),
+                    result => result,
+                }
+            }
+            ('.', '.') => {
+                cursor.skip(2);
+                (State::Initial, TokenKind::Punctuator)
+            }
+            ('.', char) if char.is_ascii_digit() => (State::Initial, number(cursor)),
+            (char, _) if char.is_ascii_digit() => (State::Initial, number(cursor)),
+            (char, _) if char.is_identifier_start() => {
+                (State::Initial, identifier_or_keyword(cursor, &KEYWORDS))
+            }
+            (char, _) if char.is_whitespace() => (State::Initial, whitespace(cursor)),
+            _ => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+        }
+    }
+
+    /// Reads a color like `#f00`, or a preprocessor directive like `#version`.
+    fn color_or_directive(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        debug_assert!(cursor.peek(0) == '#');
+        let mut count = 1;
+        while cursor.peek(count).is_ascii_hexdigit() {
+            count += 1;
+        }
+        if count > 1 && !cursor.peek(count).is_identifier_continue() {
+            cursor.skip(count);
+            return TokenKind::Number;
+        }
+        cursor.skip(1);
+        if !cursor.skip_suffix() {
+            return TokenKind::Punctuator;
+        }
+        TokenKind::OtherKeyword
+    }
+}
@@ -0,0 +1,106 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Toml;
+
+impl Language for Toml {
+    fn name(&self) -> &'static str {
+        "TOML"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["toml"]
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        Some("#")
+    }
+
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        None
+    }
+
+    fn next_token(&self, state: State, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match state {
+            State::String(string) => string_tail(cursor, string),
+            _ => self.initial(cursor),
+        }
+    }
+}
+
+impl Toml {
+    fn initial(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('#', _) => (State::Initial, line_comment(cursor)),
+            ('[', _) if cursor.is_at_line_start() => (State::Initial, self.table_header(cursor)),
+            ('"', _) | ('\'', _) => self.string(cursor),
+            ('+', char) | ('-', char) if char.is_ascii_digit() => {
+                cursor.skip(1);
+                (State::Initial, self.number_or_date(cursor))
+            }
+            (char, _) if char.is_ascii_digit() => (State::Initial, self.number_or_date(cursor)),
+            (char, _) if is_bare_key_char(char) => (State::Initial, self.bare_key_or_value(cursor)),
+            (char, _) if char.is_whitespace() => (State::Initial, whitespace(cursor)),
+            _ => (State::Initial, delimiter_or_punctuator(&self, cursor)),
+        }
+    }
+
+    /// Reads a `[table]` or `[[array.of.tables]]` header as a single token.
+    fn table_header(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        debug_assert!(cursor.peek(0) == '[');
+        let is_array = cursor.peek(1) == '[';
+        cursor.skip(if is_array { 2 } else { 1 });
+        cursor.skip_while(|char| char != ']' && char != '#');
+        if !cursor.skip_str(if is_array { "]]" } else { "]" }) {
+            return TokenKind::Unknown;
+        }
+        TokenKind::Typename
+    }
+
+    fn string(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
+        let quote = cursor.peek(0);
+        let string = StringState {
+            is_raw: quote == '\'',
+            ..StringState::new(quote)
+        };
+        if cursor.peek(1) == quote && cursor.peek(2) == quote {
+            cursor.skip(3);
+            return string_tail(
+                cursor,
+                StringState {
+                    quote_count: 3,
+                    ..string
+                },
+            );
+        }
+        cursor.skip(1);
+        match string_tail(cursor, string) {
+            // Strings with a single quote can't span multiple lines.
+            (State::String(_), _) => (State::Initial, TokenKind::Unknown),
+            result => result,
+        }
+    }
+
+    /// Reads a number, or a date or time like `1979-05-27T07:32:00Z`.
+    fn number_or_date(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        if cursor.peek(0) == '0' && matches!(cursor.peek(1), 'b' | 'o' | 'x') {
+            return number(cursor);
+        }
+        cursor.skip_while(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '.' | ':' | '-' | '+'));
+        TokenKind::Number
+    }
+
+    fn bare_key_or_value(self, cursor: &mut Cursor<'_>) -> TokenKind {
+        let start = cursor.index();
+        cursor.skip_while(is_bare_key_char);
+        match cursor.slice_from(start) {
+            "true" | "false" => TokenKind::OtherKeyword,
+            "inf" | "nan" => TokenKind::Number,
+            _ => TokenKind::Identifier,
+        }
+    }
+}
+
+fn is_bare_key_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_' || char == '-'
+}
@@ -3,12 +3,16 @@ use makepad_widgets::*;
 
 pub mod char;
 pub mod code_editor;
+pub mod completion;
 pub mod decoration;
 pub mod document;
 pub mod history;
 pub mod inlays;
 pub mod iter;
+pub mod language;
 pub mod layout;
+pub mod regex;
+pub mod search;
 pub mod selection;
 pub mod session;
 pub mod settings;
@@ -21,11 +25,12 @@ pub mod wrap;
 pub mod code_view;
 
 pub use self::{
-    code_editor::CodeEditor, document::CodeDocument, history::History, layout::Line,
-    selection::Selection, session::CodeSession, settings::Settings, token::Token, tokenizer::Tokenizer,
+    code_editor::CodeEditor, completion::CompletionItem, document::CodeDocument, history::History, layout::Line,
+    search::SearchQuery, selection::Selection, session::CodeSession, settings::Settings, token::Token, tokenizer::Tokenizer,
 };
 
 pub fn live_design(cx: &mut Cx) {
     crate::code_editor::live_design(cx);
     crate::code_view::live_design(cx);
+    crate::widgets::live_design(cx);
 }
@@ -0,0 +1,838 @@
+//! A small regular expression engine for searching in code.
+//!
+//! Patterns are compiled to a program for a Pike VM, which runs in time linear in the length of
+//! the text and keeps track of capture groups for replacements. Supported are literals, `.`,
+//! classes like `[a-z_]` or `[^"]`, the escapes `\d \w \s \D \W \S`, the assertions `^ $ \b \B`,
+//! groups `(...)` and `(?:...)`, alternation `|` and the greedy and lazy quantifiers
+//! `* + ? {n} {n,} {n,m}`. Backreferences and lookaround are not supported.
+
+use std::{fmt, iter::Peekable, mem, ops::Range, str::CharIndices};
+
+const MAX_REPEAT_COUNT: usize = 1000;
+const MAX_INST_COUNT: usize = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct Regex {
+    insts: Vec<Inst>,
+    slot_count: usize,
+    ignore_case: bool,
+}
+
+impl Regex {
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            pattern,
+            chars: pattern.char_indices().peekable(),
+            group_count: 0,
+            ignore_case,
+        };
+        let node = parser.parse_alternation()?;
+        if let Some((position, _)) = parser.chars.next() {
+            return Err(RegexError::new("unmatched `)`", position));
+        }
+        let mut compiler = Compiler { insts: Vec::new() };
+        compiler.compile(&Node::Group(Box::new(node), Some(0)))?;
+        compiler.push(Inst::Match)?;
+        Ok(Self {
+            insts: compiler.insts,
+            slot_count: 2 * (parser.group_count + 1),
+            ignore_case,
+        })
+    }
+
+    /// Finds the leftmost match in `text` that starts at or after the byte index `start`.
+    pub fn find_at(&self, text: &str, start: usize) -> Option<Captures> {
+        let mut current = Threads::new(self.insts.len());
+        let mut next = Threads::new(self.insts.len());
+        let mut slots = vec![None; self.slot_count];
+        let mut matched = None;
+        let mut position = start;
+        loop {
+            if matched.is_none() {
+                // A match starting here has a lower priority than the threads that started earlier.
+                self.add_thread(&mut current, 0, text, position, &mut slots);
+            }
+            if current.is_empty() && matched.is_some() {
+                break;
+            }
+            let char = text[position..].chars().next();
+            for (pc, thread_slots) in current.threads.drain(..) {
+                let is_match = match &self.insts[pc] {
+                    Inst::Match => {
+                        // Threads after this one have a lower priority, so they are cut off.
+                        matched = Some(thread_slots);
+                        break;
+                    }
+                    Inst::Char(expected) => char.map_or(false, |char| self.eq(*expected, char)),
+                    Inst::Any => char.is_some(),
+                    Inst::Class(class) => char.map_or(false, |char| class.matches(char, self.ignore_case)),
+                    _ => unreachable!(),
+                };
+                if is_match {
+                    let mut thread_slots = thread_slots;
+                    let next_position = position + char.unwrap().len_utf8();
+                    self.add_thread(&mut next, pc + 1, text, next_position, &mut thread_slots);
+                }
+            }
+            current.clear();
+            let Some(char) = char else {
+                break;
+            };
+            mem::swap(&mut current, &mut next);
+            position += char.len_utf8();
+        }
+        matched.map(|slots| Captures { slots })
+    }
+
+    /// Returns an iterator over the successive non-overlapping matches in `text`.
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Captures> + 'a {
+        let mut start = Some(0);
+        std::iter::from_fn(move || {
+            let captures = self.find_at(text, start?)?;
+            let range = captures.range();
+            // Step past an empty match, so it isn't found again.
+            start = if range.is_empty() {
+                text[range.end..]
+                    .chars()
+                    .next()
+                    .map(|char| range.end + char.len_utf8())
+            } else {
+                Some(range.end)
+            };
+            Some(captures)
+        })
+    }
+
+    /// Follows the jumps, splits, saves and assertions from `pc` and adds the threads it reaches, in
+    /// order of priority. This uses an explicit stack, since nested repetitions can make the
+    /// closure far deeper than the call stack allows.
+    fn add_thread(
+        &self,
+        threads: &mut Threads,
+        pc: usize,
+        text: &str,
+        position: usize,
+        slots: &mut Vec<Option<usize>>,
+    ) {
+        let mut stack = mem::take(&mut threads.stack);
+        stack.push(ClosureStep::Visit(pc));
+        while let Some(step) = stack.pop() {
+            let pc = match step {
+                ClosureStep::Visit(pc) => pc,
+                ClosureStep::Restore(slot, old) => {
+                    slots[slot] = old;
+                    continue;
+                }
+            };
+            if !threads.visit(pc) {
+                continue;
+            }
+            match self.insts[pc] {
+                Inst::Jump(target) => stack.push(ClosureStep::Visit(target)),
+                Inst::Split(first, second) => {
+                    // The first branch has to be explored fully before the second one.
+                    stack.push(ClosureStep::Visit(second));
+                    stack.push(ClosureStep::Visit(first));
+                }
+                Inst::Save(slot) => {
+                    let old = mem::replace(&mut slots[slot], Some(position));
+                    stack.push(ClosureStep::Restore(slot, old));
+                    stack.push(ClosureStep::Visit(pc + 1));
+                }
+                Inst::Assert(assertion) => {
+                    if assertion.holds(text, position) {
+                        stack.push(ClosureStep::Visit(pc + 1));
+                    }
+                }
+                _ => threads.threads.push((pc, slots.clone())),
+            }
+        }
+        threads.stack = stack;
+    }
+
+    fn eq(&self, expected: char, char: char) -> bool {
+        expected == char || self.ignore_case && expected == fold_case(char)
+    }
+}
+
+/// The byte ranges of a match and its capture groups.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Captures {
+    slots: Vec<Option<usize>>,
+}
+
+impl Captures {
+    /// The byte range of the whole match.
+    pub fn range(&self) -> Range<usize> {
+        self.get(0).unwrap()
+    }
+
+    /// The byte range of capture group `index`, if it took part in the match.
+    pub fn get(&self, index: usize) -> Option<Range<usize>> {
+        match (self.slots.get(2 * index)?, self.slots.get(2 * index + 1)?) {
+            (Some(start), Some(end)) => Some(*start..*end),
+            _ => None,
+        }
+    }
+
+    /// Expands `replacement` for this match in `text`, where `$n` or `${n}` is the text
+    /// of capture group `n`, `$0` is the whole match and `$$` is a literal `$`.
+    pub fn expand(&self, text: &str, replacement: &str, output: &mut String) {
+        let mut chars = replacement.char_indices().peekable();
+        while let Some((index, char)) = chars.next() {
+            if char != '$' {
+                output.push(char);
+                continue;
+            }
+            let rest = &replacement[index + 1..];
+            let (digits, skip) = if let Some(braced) = rest.strip_prefix('{') {
+                match braced.find('}') {
+                    Some(end) if braced[..end].chars().all(|char| char.is_ascii_digit()) => {
+                        (&braced[..end], end + 2)
+                    }
+                    _ => ("", 0),
+                }
+            } else {
+                let end = rest
+                    .find(|char: char| !char.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                (&rest[..end], end)
+            };
+            if let Ok(group) = digits.parse::<usize>() {
+                if let Some(range) = self.get(group) {
+                    output.push_str(&text[range]);
+                }
+                for _ in 0..skip {
+                    chars.next();
+                }
+            } else if rest.starts_with('$') {
+                output.push('$');
+                chars.next();
+            } else {
+                output.push('$');
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegexError {
+    pub message: String,
+    /// The byte index in the pattern where the error was found.
+    pub position: usize,
+}
+
+impl RegexError {
+    fn new(message: &str, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.position)
+    }
+}
+
+/// Escapes all characters in `string` that have a special meaning in a pattern.
+pub fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for char in string.chars() {
+        if "\\.+*?()|[]{}^$".contains(char) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+pub fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+fn fold_case(char: char) -> char {
+    let mut lowercase = char.to_lowercase();
+    match (lowercase.next(), lowercase.next()) {
+        (Some(lower), None) => lower,
+        _ => char,
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Split(usize, usize),
+    Jump(usize),
+    Save(usize),
+    Match,
+}
+
+#[derive(Clone, Debug)]
+struct Class {
+    is_negated: bool,
+    items: Vec<ClassItem>,
+}
+
+impl Class {
+    fn perl(item: ClassItem, is_negated: bool) -> Self {
+        Self {
+            is_negated,
+            items: vec![item],
+        }
+    }
+
+    fn matches(&self, char: char, ignore_case: bool) -> bool {
+        let matches = |char: char| self.items.iter().any(|item| item.matches(char));
+        let is_match = matches(char)
+            || ignore_case && (matches(fold_case(char)) || char.to_uppercase().any(matches));
+        is_match != self.is_negated
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(self, char: char) -> bool {
+        match self {
+            Self::Range(start, end) => (start..=end).contains(&char),
+            Self::Digit(is_negated) => char.is_ascii_digit() != is_negated,
+            Self::Word(is_negated) => is_word_char(char) != is_negated,
+            Self::Space(is_negated) => char.is_whitespace() != is_negated,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Assertion {
+    Start,
+    End,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+impl Assertion {
+    fn holds(self, text: &str, position: usize) -> bool {
+        let is_word_boundary = || {
+            let before = text[..position].chars().next_back().map_or(false, is_word_char);
+            let after = text[position..].chars().next().map_or(false, is_word_char);
+            before != after
+        };
+        match self {
+            Self::Start => position == 0,
+            Self::End => position == text.len(),
+            Self::WordBoundary => is_word_boundary(),
+            Self::NotWordBoundary => !is_word_boundary(),
+        }
+    }
+}
+
+/// The threads of the VM at one position in the text, in order of priority.
+struct Threads {
+    threads: Vec<(usize, Vec<Option<usize>>)>,
+    visited: Vec<bool>,
+    visited_pcs: Vec<usize>,
+    stack: Vec<ClosureStep>,
+}
+
+/// The pending work of `Regex::add_thread`.
+enum ClosureStep {
+    Visit(usize),
+    /// Puts back the value a `Save` overwrote, once everything after it has been added.
+    Restore(usize, Option<usize>),
+}
+
+impl Threads {
+    fn new(inst_count: usize) -> Self {
+        Self {
+            threads: Vec::new(),
+            visited: vec![false; inst_count],
+            visited_pcs: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.threads.is_empty()
+    }
+
+    /// Marks `pc` as visited at this position, returning false if it already was.
+    fn visit(&mut self, pc: usize) -> bool {
+        if self.visited[pc] {
+            return false;
+        }
+        self.visited[pc] = true;
+        self.visited_pcs.push(pc);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.threads.clear();
+        for pc in self.visited_pcs.drain(..) {
+            self.visited[pc] = false;
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        is_greedy: bool,
+    },
+}
+
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    group_count: usize,
+    ignore_case: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_alternation(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = vec![self.parse_concat()?];
+        while self.eat('|') {
+            nodes.push(self.parse_concat()?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::Alternate(nodes)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(&(_, char)) = self.chars.peek() {
+            if char == '|' || char == ')' {
+                break;
+            }
+            let node = self.parse_atom()?;
+            nodes.push(self.parse_quantifiers(node)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        let (position, char) = self.chars.next().unwrap();
+        Ok(match char {
+            '(' => {
+                let index = if self.pattern[position + 1..].starts_with("?:") {
+                    self.chars.next();
+                    self.chars.next();
+                    None
+                } else if self.pattern[position + 1..].starts_with('?') {
+                    return Err(RegexError::new("lookaround is not supported", position));
+                } else {
+                    self.group_count += 1;
+                    Some(self.group_count)
+                };
+                let node = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err(RegexError::new("unclosed group", position));
+                }
+                Node::Group(Box::new(node), index)
+            }
+            '[' => Node::Class(self.parse_class(position)?),
+            '.' => Node::Any,
+            '^' => Node::Assert(Assertion::Start),
+            '$' => Node::Assert(Assertion::End),
+            '*' | '+' | '?' => return Err(RegexError::new("nothing to repeat", position)),
+            '\\' => self.parse_escape(position)?,
+            char => self.char(char),
+        })
+    }
+
+    fn parse_escape(&mut self, position: usize) -> Result<Node, RegexError> {
+        let Some((_, char)) = self.chars.next() else {
+            return Err(RegexError::new("trailing `\\`", position));
+        };
+        Ok(match char {
+            'b' => Node::Assert(Assertion::WordBoundary),
+            'B' => Node::Assert(Assertion::NotWordBoundary),
+            '1'..='9' => {
+                return Err(RegexError::new("backreferences are not supported", position))
+            }
+            char => match self.parse_escaped_char(char) {
+                Ok(char) => self.char(char),
+                Err(item) => Node::Class(Class::perl(item, false)),
+            },
+        })
+    }
+
+    /// Returns the char for an escape like `\n`, or the class item for one like `\d`.
+    fn parse_escaped_char(&self, char: char) -> Result<char, ClassItem> {
+        match char {
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            'd' => Err(ClassItem::Digit(false)),
+            'D' => Err(ClassItem::Digit(true)),
+            'w' => Err(ClassItem::Word(false)),
+            'W' => Err(ClassItem::Word(true)),
+            's' => Err(ClassItem::Space(false)),
+            'S' => Err(ClassItem::Space(true)),
+            char => Ok(char),
+        }
+    }
+
+    fn parse_class(&mut self, position: usize) -> Result<Class, RegexError> {
+        let is_negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut is_first = true;
+        loop {
+            let Some((_, char)) = self.chars.next() else {
+                return Err(RegexError::new("unclosed class", position));
+            };
+            let start = match char {
+                ']' if !is_first => break,
+                '\\' => match self.chars.next() {
+                    Some((_, char)) => match self.parse_escaped_char(char) {
+                        Ok(char) => char,
+                        Err(item) => {
+                            items.push(item);
+                            is_first = false;
+                            continue;
+                        }
+                    },
+                    None => return Err(RegexError::new("unclosed class", position)),
+                },
+                char => char,
+            };
+            is_first = false;
+            let is_range = self.chars.peek().map_or(false, |&(_, char)| char == '-')
+                && self.pattern[self.chars.peek().unwrap().0 + 1..]
+                    .chars()
+                    .next()
+                    .map_or(false, |char| char != ']');
+            if !is_range {
+                items.push(ClassItem::Range(start, start));
+                continue;
+            }
+            self.chars.next();
+            let (end_position, end) = self.chars.next().unwrap();
+            let end = if end == '\\' {
+                match self.chars.next().map(|(_, char)| self.parse_escaped_char(char)) {
+                    Some(Ok(char)) => char,
+                    _ => return Err(RegexError::new("invalid class range", end_position)),
+                }
+            } else {
+                end
+            };
+            if end < start {
+                return Err(RegexError::new("invalid class range", end_position));
+            }
+            items.push(ClassItem::Range(start, end));
+        }
+        if self.ignore_case {
+            for item in &mut items {
+                if let ClassItem::Range(start, end) = *item {
+                    if start == end {
+                        *item = ClassItem::Range(fold_case(start), fold_case(start));
+                    }
+                }
+            }
+        }
+        Ok(Class { is_negated, items })
+    }
+
+    fn parse_quantifiers(&mut self, mut node: Node) -> Result<Node, RegexError> {
+        while let Some(&(position, char)) = self.chars.peek() {
+            let (min, max) = match char {
+                '*' => (0, None),
+                '+' => (1, None),
+                '?' => (0, Some(1)),
+                '{' => match self.parse_counts(position) {
+                    Some(counts) => counts?,
+                    // A `{` that doesn't start a count is a literal.
+                    None => break,
+                },
+                _ => break,
+            };
+            if char != '{' {
+                self.chars.next();
+            }
+            if matches!(node, Node::Repeat { .. }) {
+                return Err(RegexError::new("nested quantifier", position));
+            }
+            let is_greedy = !self.eat('?');
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                is_greedy,
+            };
+        }
+        Ok(node)
+    }
+
+    fn parse_counts(&mut self, position: usize) -> Option<Result<(usize, Option<usize>), RegexError>> {
+        let rest = &self.pattern[position + 1..];
+        let end = rest.find('}')?;
+        let (min, max) = match rest[..end].split_once(',') {
+            Some((min, "")) => (min.parse().ok()?, None),
+            Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?)),
+            None => {
+                let count: usize = rest[..end].parse().ok()?;
+                (count, Some(count))
+            }
+        };
+        while self.chars.next().map_or(false, |(_, char)| char != '}') {}
+        if max.map_or(false, |max| max < min) {
+            return Some(Err(RegexError::new("invalid repeat count", position)));
+        }
+        if min.max(max.unwrap_or(0)) > MAX_REPEAT_COUNT {
+            return Some(Err(RegexError::new("repeat count is too large", position)));
+        }
+        Some(Ok((min, max)))
+    }
+
+    fn char(&self, char: char) -> Node {
+        Node::Char(if self.ignore_case { fold_case(char) } else { char })
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.chars.peek().map_or(false, |&(_, char)| char == expected) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct Compiler {
+    insts: Vec<Inst>,
+}
+
+impl Compiler {
+    fn compile(&mut self, node: &Node) -> Result<(), RegexError> {
+        match node {
+            Node::Empty => {}
+            Node::Char(char) => {
+                self.push(Inst::Char(*char))?;
+            }
+            Node::Any => {
+                self.push(Inst::Any)?;
+            }
+            Node::Class(class) => {
+                self.push(Inst::Class(class.clone()))?;
+            }
+            Node::Assert(assertion) => {
+                self.push(Inst::Assert(*assertion))?;
+            }
+            Node::Group(node, index) => {
+                if let Some(index) = index {
+                    self.push(Inst::Save(2 * index))?;
+                }
+                self.compile(node)?;
+                if let Some(index) = index {
+                    self.push(Inst::Save(2 * index + 1))?;
+                }
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            }
+            Node::Alternate(nodes) => {
+                let mut jumps = Vec::new();
+                for (index, node) in nodes.iter().enumerate() {
+                    if index + 1 < nodes.len() {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.compile(node)?;
+                        jumps.push(self.push(Inst::Jump(0))?);
+                        self.insts[split] = Inst::Split(split + 1, self.insts.len());
+                    } else {
+                        self.compile(node)?;
+                    }
+                }
+                for jump in jumps {
+                    self.insts[jump] = Inst::Jump(self.insts.len());
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                is_greedy,
+            } => {
+                for _ in 0..*min {
+                    self.compile(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.compile(node)?;
+                        self.push(Inst::Jump(split))?;
+                        self.insts[split] = self.split(split + 1, self.insts.len(), *is_greedy);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.compile(node)?;
+                        }
+                        for split in splits {
+                            self.insts[split] = self.split(split + 1, self.insts.len(), *is_greedy);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn split(&self, body: usize, exit: usize, is_greedy: bool) -> Inst {
+        if is_greedy {
+            Inst::Split(body, exit)
+        } else {
+            Inst::Split(exit, body)
+        }
+    }
+
+    fn push(&mut self, inst: Inst) -> Result<usize, RegexError> {
+        if self.insts.len() == MAX_INST_COUNT {
+            return Err(RegexError::new("pattern is too large", 0));
+        }
+        self.insts.push(inst);
+        Ok(self.insts.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, text: &str) -> Option<Range<usize>> {
+        Regex::new(pattern, false).unwrap().find_at(text, 0).map(|captures| captures.range())
+    }
+
+    fn find_all<'a>(pattern: &str, ignore_case: bool, text: &'a str) -> Vec<&'a str> {
+        let regex = Regex::new(pattern, ignore_case).unwrap();
+        let matches = regex.find_iter(text).map(|captures| &text[captures.range()]).collect();
+        matches
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(find("^ab", "ab ab"), Some(0..2));
+        assert_eq!(find("^ab", " ab"), None);
+        assert_eq!(find("ab$", "ab ab"), Some(3..5));
+        assert_eq!(find("^$", ""), Some(0..0));
+        assert_eq!(find(r"\bcat\b", "concat cat"), Some(7..10));
+        assert_eq!(find(r"\Bcat", "cat concat"), Some(7..10));
+        // `^` only holds at the start of the text, not where the search starts
+        let regex = Regex::new("^a", false).unwrap();
+        assert_eq!(regex.find_at("aa", 1), None);
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(find_all("[a-c_]+", false, "abx_c dd"), ["ab", "_c"]);
+        assert_eq!(find_all(r#"[^"]+"#, false, r#""ab"cd"#), ["ab", "cd"]);
+        assert_eq!(find_all(r"\d+", false, "a12b3"), ["12", "3"]);
+        assert_eq!(find_all(r"\w+", false, "foo_1 bär"), ["foo_1", "bär"]);
+        assert_eq!(find_all(r"\s", false, "a b\tc"), [" ", "\t"]);
+        assert_eq!(find_all(r"[\d.]+", false, "v1.25!"), ["1.25"]);
+        assert_eq!(find_all("[]a]", false, "]a"), ["]", "a"]);
+        assert_eq!(find_all("[a-]", false, "-a"), ["-", "a"]);
+        assert_eq!(find_all("a.c", false, "abc a\u{e9}c"), ["abc", "a\u{e9}c"]);
+    }
+
+    #[test]
+    fn alternation() {
+        assert_eq!(find_all("cat|dog", false, "dog cat cow"), ["dog", "cat"]);
+        // the leftmost match wins, then the first alternative
+        assert_eq!(find("b|ab", "ab"), Some(0..2));
+        assert_eq!(find("a|ab", "ab"), Some(0..1));
+        let captures = Regex::new("(?:x|(y))(z)", false).unwrap().find_at("yz", 0).unwrap();
+        assert_eq!(captures.get(1), Some(0..1));
+        assert_eq!(captures.get(2), Some(1..2));
+        let captures = Regex::new("(?:x|(y))(z)", false).unwrap().find_at("xz", 0).unwrap();
+        assert_eq!(captures.get(1), None);
+    }
+
+    #[test]
+    fn counted_repetition() {
+        assert_eq!(find_all("a{2}", false, "aaaaa"), ["aa", "aa"]);
+        assert_eq!(find_all("a{2,}", false, "a aa aaaa"), ["aa", "aaaa"]);
+        assert_eq!(find_all("a{1,3}", false, "aaaaa"), ["aaa", "aa"]);
+        assert_eq!(find_all("a{1,3}?", false, "aaa"), ["a", "a", "a"]);
+        assert_eq!(find_all("(?:ab){2}", false, "ababab"), ["abab"]);
+        assert_eq!(find_all("a{0}b", false, "ab"), ["b"]);
+        // a `{` that doesn't start a count is a literal
+        assert_eq!(find_all("a{x}", false, "a{x}"), ["a{x}"]);
+        assert_eq!(Regex::new("a{3,2}", false).unwrap_err().message, "invalid repeat count");
+        assert_eq!(Regex::new("a{1001}", false).unwrap_err().message, "repeat count is too large");
+        assert_eq!(Regex::new("a**", false).unwrap_err().message, "nested quantifier");
+    }
+
+    #[test]
+    fn deep_epsilon_closure() {
+        // 30000 optional items in a row chain that many splits before the first char is read
+        let pattern = "(?:(?:a?){1000}){30}";
+        assert_eq!(find(pattern, "aaab"), Some(0..3));
+        assert_eq!(find(pattern, "b"), Some(0..0));
+    }
+
+    #[test]
+    fn ignore_case() {
+        assert_eq!(find_all("hello", true, "Hello HELLO hello"), ["Hello", "HELLO", "hello"]);
+        assert_eq!(find_all("HeLLo", true, "hello"), ["hello"]);
+        assert_eq!(find_all("[a-c]+", true, "ABC xyz"), ["ABC"]);
+        assert_eq!(find_all("[A-C]+", true, "abc"), ["abc"]);
+        assert_eq!(find_all("[^a]", true, "aAb"), ["b"]);
+        assert_eq!(find_all("straße", true, "STRAßE"), ["STRAßE"]);
+        assert_eq!(find_all("hello", false, "Hello"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn empty_matches() {
+        assert_eq!(find("", "abc"), Some(0..0));
+        assert_eq!(find("x*", "abc"), Some(0..0));
+        assert_eq!(find_all("x*", false, "ab"), ["", "", ""]);
+        assert_eq!(find_all("a*", false, "baa"), ["", "aa", ""]);
+        // an empty match steps over a whole char, not a byte
+        let regex = Regex::new("", false).unwrap();
+        let starts: Vec<_> = regex.find_iter("é!").map(|captures| captures.range().start).collect();
+        assert_eq!(starts, [0, 2, 3]);
+        assert_eq!(find("a|", "b"), Some(0..0));
+        assert_eq!(find("()", "b"), Some(0..0));
+    }
+
+    #[test]
+    fn expand() {
+        let text = "key = value";
+        let captures = Regex::new(r"(\w+) = (\w+)", false).unwrap().find_at(text, 0).unwrap();
+        let mut output = String::new();
+        captures.expand(text, "$2 = ${1}; $$ $0 $9", &mut output);
+        assert_eq!(output, "value = key; $ key = value ");
+    }
+}
@@ -0,0 +1,126 @@
+use {
+    crate::{
+        regex::{self, Regex, RegexError},
+        text::Position,
+    },
+    std::ops::Range,
+};
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub match_case: bool,
+    pub whole_word: bool,
+    pub is_regex: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Searcher {
+    query: SearchQuery,
+    regex: Option<Regex>,
+}
+
+impl Searcher {
+    pub fn new(query: SearchQuery) -> Result<Self, RegexError> {
+        // Case sensitive literal queries don't need a regex, so they use `str::find` instead.
+        let regex = if query.is_regex {
+            Some(Regex::new(&query.text, !query.match_case)?)
+        } else if !query.match_case {
+            Some(Regex::new(&regex::escape(&query.text), true)?)
+        } else {
+            None
+        };
+        Ok(Self { query, regex })
+    }
+
+    pub fn query(&self) -> &SearchQuery {
+        &self.query
+    }
+
+    /// Finds the first match in `line` that starts at or after the byte index `start`. Matches
+    /// never span more than one line.
+    pub fn find_at(&self, line: &str, start: usize) -> Option<Range<usize>> {
+        if self.query.text.is_empty() {
+            return None;
+        }
+        let mut start = start;
+        loop {
+            let range = match &self.regex {
+                Some(regex) => regex.find_at(line, start)?.range(),
+                None => {
+                    let index = start + line[start..].find(&self.query.text)?;
+                    index..index + self.query.text.len()
+                }
+            };
+            if !self.query.whole_word || is_whole_word(line, range.clone()) {
+                return Some(range);
+            }
+            start = range.start + line[range.start..].chars().next()?.len_utf8();
+        }
+    }
+
+    /// Returns the start and end positions of all matches in `lines`.
+    pub fn find_all(&self, lines: &[String]) -> Vec<(Position, Position)> {
+        let mut matches = Vec::new();
+        for (line_index, line) in lines.iter().enumerate() {
+            for range in self.find_all_in_line(line) {
+                matches.push((
+                    Position {
+                        line_index,
+                        byte_index: range.start,
+                    },
+                    Position {
+                        line_index,
+                        byte_index: range.end,
+                    },
+                ));
+            }
+        }
+        matches
+    }
+
+    /// Returns the byte ranges of all matches in `line`.
+    pub fn find_all_in_line(&self, line: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        while let Some(range) = self.find_at(line, start) {
+            ranges.push(range.clone());
+            start = if range.is_empty() {
+                match line[range.end..].chars().next() {
+                    Some(char) => range.end + char.len_utf8(),
+                    None => break,
+                }
+            } else {
+                range.end
+            };
+        }
+        ranges
+    }
+
+    /// Returns the text to replace the match at `range` in `line` with. For regex queries, `$n`
+    /// in `replacement` is expanded to the text of capture group `n`.
+    pub fn replacement(&self, line: &str, range: Range<usize>, replacement: &str) -> String {
+        if !self.query.is_regex {
+            return replacement.to_string();
+        }
+        let mut output = String::new();
+        match self.regex.as_ref().unwrap().find_at(line, range.start) {
+            Some(captures) if captures.range() == range => {
+                captures.expand(line, replacement, &mut output)
+            }
+            _ => output.push_str(replacement),
+        }
+        output
+    }
+}
+
+fn is_whole_word(line: &str, range: Range<usize>) -> bool {
+    let text = &line[range.clone()];
+    let (Some(first), Some(last)) = (text.chars().next(), text.chars().next_back()) else {
+        return false;
+    };
+    let before = line[..range.start].chars().next_back();
+    let after = line[range.end..].chars().next();
+    !(regex::is_word_char(first) && before.map_or(false, regex::is_word_char))
+        && !(regex::is_word_char(last) && after.map_or(false, regex::is_word_char))
+}
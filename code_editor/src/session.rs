@@ -1,9 +1,12 @@
 use {
     crate::{
         char::CharExt,
+        decoration::{Decoration, DecorationSet, DecorationType},
         document::CodeDocument,
         history::{EditKind,NewGroup},
         layout::{BlockElement, Layout, WrappedElement},
+        regex::RegexError,
+        search::{SearchQuery, Searcher},
         selection::{Affinity, Cursor, SelectionSet},
         str::StrExt,
         text::{Change, Drift, Edit, Length, Position, Text},
@@ -30,6 +33,7 @@ pub struct CodeSession {
     selection_state: RefCell<SelectionState>,
     wrap_column: Cell<Option<usize>>,
     fold_state: RefCell<FoldState>,
+    search_state: RefCell<SearchState>,
     edit_receiver: Receiver<(Option<SelectionSet>, Vec<Edit>)>,
 }
 
@@ -63,6 +67,10 @@ impl CodeSession {
                 folded_lines: HashSet::new(),
                 unfolding_lines: HashSet::new(),
             }),
+            search_state: RefCell::new(SearchState {
+                searcher: None,
+                matches: DecorationSet::new(),
+            }),
             edit_receiver,
         };
         for line in 0..line_count {
@@ -113,6 +121,32 @@ impl CodeSession {
         })
     }
 
+    pub fn search_query(&self) -> Option<SearchQuery> {
+        self.search_state
+            .borrow()
+            .searcher
+            .as_ref()
+            .map(|searcher| searcher.query().clone())
+    }
+
+    pub fn search_matches(&self) -> Ref<'_, [Decoration]> {
+        Ref::map(self.search_state.borrow(), |search_state| {
+            search_state.matches.as_decorations()
+        })
+    }
+
+    /// Returns the index of the search match that is exactly covered by the last added selection.
+    pub fn current_search_match_index(&self) -> Option<usize> {
+        let selection_state = self.selection_state.borrow();
+        let selection = selection_state.selections[selection_state.last_added_selection_index?];
+        let search_state = self.search_state.borrow();
+        search_state
+            .matches
+            .binary_search_by_key(&selection.start(), |decoration| decoration.start())
+            .ok()
+            .filter(|&index| search_state.matches[index].end() == selection.end())
+    }
+
     pub fn set_wrap_column(&self, wrap_column: Option<usize>) {
         if self.wrap_column.get() == wrap_column {
             return;
@@ -419,6 +453,50 @@ impl CodeSession {
         );
     }
 
+    /// Replaces the text from `start` to `end` with `text`, leaving a single cursor after it, such
+    /// as when accepting a completion.
+    pub fn replace_range(&self, start: Position, end: Position, text: Text) {
+        self.select_range(start, end);
+        self.insert(text);
+    }
+
+    /// Replaces the `preedit` text that an input method composition inserted right before each
+    /// cursor with `text`. Cursors that are not preceded by `preedit` just insert `text`.
+    pub fn replace_preedit(&self, preedit: &str, text: Text) {
+        self.document.edit_selections(
+            self.id,
+            EditKind::Insert,
+            &self.selection_state.borrow().selections,
+            &self.settings,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                let mut position = position;
+                if editor.as_text().as_lines()[position.line_index][..position.byte_index]
+                    .ends_with(preedit)
+                {
+                    position.byte_index -= preedit.len();
+                    editor.apply_edit(Edit {
+                        change: Change::Delete(
+                            position,
+                            Length {
+                                line_count: 0,
+                                byte_count: preedit.len(),
+                            },
+                        ),
+                        drift: Drift::Before,
+                    });
+                }
+                editor.apply_edit(Edit {
+                    change: Change::Insert(position, text.clone()),
+                    drift: Drift::Before,
+                });
+            },
+        );
+    }
+
     pub fn paste(&self, text: Text) {
         self.document.edit_selections(
             self.id,
@@ -818,6 +896,149 @@ impl CodeSession {
             .redo(self.id, &self.selection_state.borrow().selections)
     }
 
+    /// Sets the query to search for, or clears the search if `query` is `None` or invalid. Returns
+    /// the number of matches. The matches are kept up to date as the document is edited.
+    pub fn set_search_query(&self, query: Option<SearchQuery>) -> Result<usize, RegexError> {
+        let (searcher, result) = match query.map(Searcher::new).transpose() {
+            Ok(searcher) => (searcher, Ok(())),
+            Err(error) => (None, Err(error)),
+        };
+        self.search_state.borrow_mut().searcher = searcher;
+        self.update_search_matches();
+        result.map(|_| self.search_state.borrow().matches.len())
+    }
+
+    /// Selects the first search match after the last added selection, wrapping around at the end
+    /// of the document.
+    pub fn find_next(&self) -> bool {
+        let selection = self.last_added_selection();
+        let search_state = self.search_state.borrow();
+        let Some(first_match) = search_state.matches.first() else {
+            return false;
+        };
+        let next_match = search_state
+            .matches
+            .iter()
+            .find(|decoration| {
+                decoration.start() >= selection.end()
+                    && (decoration.start(), decoration.end()) != (selection.start(), selection.end())
+            })
+            .unwrap_or(first_match);
+        let (start, end) = (next_match.start(), next_match.end());
+        drop(search_state);
+        self.select_range(start, end);
+        true
+    }
+
+    /// Selects the last search match before the last added selection, wrapping around at the start
+    /// of the document.
+    pub fn find_prev(&self) -> bool {
+        let selection = self.last_added_selection();
+        let search_state = self.search_state.borrow();
+        let Some(last_match) = search_state.matches.last() else {
+            return false;
+        };
+        let prev_match = search_state
+            .matches
+            .iter()
+            .rev()
+            .find(|decoration| decoration.start() < selection.start())
+            .unwrap_or(last_match);
+        let (start, end) = (prev_match.start(), prev_match.end());
+        drop(search_state);
+        self.select_range(start, end);
+        true
+    }
+
+    /// Replaces the selections with one selection for each search match. Returns the number of
+    /// selections.
+    pub fn select_all_search_matches(&self) -> usize {
+        let search_state = self.search_state.borrow();
+        if search_state.matches.is_empty() {
+            return 0;
+        }
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        let mut last_added_selection_index = 0;
+        for (index, decoration) in search_state.matches.iter().enumerate() {
+            let selection = Selection {
+                anchor: decoration.start(),
+                cursor: Cursor {
+                    position: decoration.end(),
+                    affinity: Affinity::Before,
+                    preferred_column_index: None,
+                },
+            };
+            if index == 0 {
+                selection_state.selections.set_selection(selection);
+            } else {
+                last_added_selection_index = selection_state.selections.add_selection(selection);
+            }
+        }
+        selection_state.last_added_selection_index = Some(last_added_selection_index);
+        selection_state.injected_char_stack.clear();
+        let selection_count = selection_state.selections.len();
+        drop(selection_state);
+        drop(search_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+        selection_count
+    }
+
+    /// Replaces the search match that is selected, if any, and selects the next one. Returns
+    /// whether a match was replaced.
+    pub fn replace_next(&mut self, replacement: &str) -> bool {
+        let Some(index) = self.current_search_match_index() else {
+            self.find_next();
+            return false;
+        };
+        let (start, end, text) = {
+            let search_state = self.search_state.borrow();
+            let decoration = search_state.matches[index];
+            (
+                decoration.start(),
+                decoration.end(),
+                self.search_replacement(&search_state, decoration, replacement),
+            )
+        };
+        let end_after_replace = start + text.length();
+        self.document.replace_ranges(
+            self.id,
+            &self.selection_state.borrow().selections,
+            vec![(start, end - start, text)],
+        );
+        self.handle_changes();
+        self.select_range(end_after_replace, end_after_replace);
+        self.find_next();
+        true
+    }
+
+    /// Replaces all search matches as a single undo group. Returns the number of replaced matches.
+    pub fn replace_all(&mut self, replacement: &str) -> usize {
+        let ranges: Vec<_> = {
+            let search_state = self.search_state.borrow();
+            search_state
+                .matches
+                .iter()
+                .map(|&decoration| {
+                    (
+                        decoration.start(),
+                        decoration.length(),
+                        self.search_replacement(&search_state, decoration, replacement),
+                    )
+                })
+                .collect()
+        };
+        let replaced_count = ranges.len();
+        self.document.replace_ranges(
+            self.id,
+            &self.selection_state.borrow().selections,
+            ranges,
+        );
+        self.handle_changes();
+        replaced_count
+    }
+
     pub fn handle_changes(&mut self) {
         while let Ok((selections, edits)) = self.edit_receiver.try_recv() {
             self.update_after_edit(selections, &edits);
@@ -925,6 +1146,127 @@ impl CodeSession {
         }
         drop(selection_state);
         self.update_highlighted_delimiter_positions();
+        self.update_search_matches_after_edits(edits);
+    }
+
+    fn last_added_selection(&self) -> Selection {
+        let selection_state = self.selection_state.borrow();
+        selection_state.selections[selection_state.last_added_selection_index.unwrap_or(0)]
+    }
+
+    fn select_range(&self, start: Position, end: Position) {
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        selection_state.selections.set_selection(Selection {
+            anchor: start,
+            cursor: Cursor {
+                position: end,
+                affinity: Affinity::Before,
+                preferred_column_index: None,
+            },
+        });
+        selection_state.last_added_selection_index = Some(0);
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+    }
+
+    fn search_replacement(
+        &self,
+        search_state: &SearchState,
+        decoration: Decoration,
+        replacement: &str,
+    ) -> Text {
+        let searcher = search_state.searcher.as_ref().unwrap();
+        let text = self.document.as_text();
+        let line = &text.as_lines()[decoration.start().line_index];
+        Text::from(searcher.replacement(
+            line,
+            decoration.start().byte_index..decoration.end().byte_index,
+            replacement,
+        ))
+    }
+
+    fn update_search_matches(&self) {
+        let mut search_state = self.search_state.borrow_mut();
+        let SearchState { searcher, matches } = &mut *search_state;
+        matches.clear();
+        let Some(searcher) = searcher else {
+            return;
+        };
+        let text = self.document.as_text();
+        for (index, (start, end)) in searcher.find_all(text.as_lines()).into_iter().enumerate() {
+            matches.add_decoration(Decoration::new(index, start, end, DecorationType::SearchMatch));
+        }
+    }
+
+    /// Updates the search matches after `edits` were applied. Matches never span more than one
+    /// line, so only the lines that were touched by an edit are searched again, and the matches on
+    /// the other lines are moved along with the text.
+    fn update_search_matches_after_edits(&self, edits: &[Edit]) {
+        let mut search_state = self.search_state.borrow_mut();
+        let SearchState { searcher, matches } = &mut *search_state;
+        let Some(searcher) = searcher else {
+            return;
+        };
+        let mut decorations = matches.as_decorations().to_vec();
+        let mut dirty_lines = Vec::new();
+        for edit in edits {
+            let (line_index, removed_line_count, inserted_line_count) = match edit.change {
+                Change::Insert(point, ref text) => (point.line_index, 0, text.length().line_count),
+                Change::Delete(start, length) => (start.line_index, length.line_count, 0),
+            };
+            let last_removed_line_index = line_index + removed_line_count;
+            decorations.retain(|decoration| {
+                let decoration_line_index = decoration.start().line_index;
+                decoration_line_index < line_index
+                    || decoration_line_index > last_removed_line_index
+            });
+            for decoration in &mut decorations {
+                *decoration = decoration.apply_edit(edit);
+            }
+            for dirty_line_index in &mut dirty_lines {
+                if *dirty_line_index > last_removed_line_index {
+                    *dirty_line_index = *dirty_line_index - removed_line_count + inserted_line_count;
+                } else if *dirty_line_index > line_index {
+                    *dirty_line_index = line_index;
+                }
+            }
+            dirty_lines.extend(line_index..=line_index + inserted_line_count);
+        }
+        dirty_lines.sort_unstable();
+        dirty_lines.dedup();
+        let text = self.document.as_text();
+        for line_index in dirty_lines {
+            let Some(line) = text.as_lines().get(line_index) else {
+                continue;
+            };
+            for range in searcher.find_all_in_line(line) {
+                decorations.push(Decoration::new(
+                    0,
+                    Position {
+                        line_index,
+                        byte_index: range.start,
+                    },
+                    Position {
+                        line_index,
+                        byte_index: range.end,
+                    },
+                    DecorationType::SearchMatch,
+                ));
+            }
+        }
+        decorations.sort_by_key(|decoration| decoration.start());
+        matches.clear();
+        for (index, decoration) in decorations.into_iter().enumerate() {
+            matches.add_decoration(Decoration::new(
+                index,
+                decoration.start(),
+                decoration.end(),
+                DecorationType::SearchMatch,
+            ));
+        }
     }
 
     fn update_y(&self) {
@@ -1053,6 +1395,12 @@ struct SelectionState {
     highlighted_delimiter_positions: HashSet<Position>,
 }
 
+#[derive(Debug)]
+struct SearchState {
+    searcher: Option<Searcher>,
+    matches: DecorationSet,
+}
+
 #[derive(Debug)]
 struct FoldState {
     folding_lines: HashSet<usize>,
@@ -1336,3 +1684,92 @@ fn find_closing_delimiter(
         position.byte_index = 0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matches_full_scan(session: &CodeSession) {
+        let expected = session
+            .search_state
+            .borrow()
+            .searcher
+            .as_ref()
+            .unwrap()
+            .find_all(session.document().as_text().as_lines());
+        let actual: Vec<_> = session
+            .search_matches()
+            .iter()
+            .map(|decoration| (decoration.start(), decoration.end()))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    fn position(line_index: usize, byte_index: usize) -> Position {
+        Position {
+            line_index,
+            byte_index,
+        }
+    }
+
+    #[test]
+    fn search_matches_follow_edits() {
+        let document = CodeDocument::new(
+            Text::from("ab aab\nxx ab\n\nb ab\nlast ab"),
+            DecorationSet::new(),
+        );
+        let mut session = CodeSession::new(document);
+        let query = SearchQuery {
+            text: "a+b".to_string(),
+            is_regex: true,
+            ..SearchQuery::default()
+        };
+        assert_eq!(session.set_search_query(Some(query)), Ok(5));
+
+        let edits = [
+            // within a line, creating and destroying matches
+            (position(0, 2), position(0, 3), "b a"),
+            (position(1, 0), position(1, 4), ""),
+            // inserting lines
+            (position(1, 1), position(1, 1), "a\nab ab\na"),
+            // joining lines
+            (position(0, 4), position(2, 1), ""),
+            (position(2, 0), position(4, 0), "aa"),
+            // the last line
+            (position(2, 9), position(2, 9), "\nab\n"),
+        ];
+        for (start, end, text) in edits {
+            session.replace_range(start, end, Text::from(text));
+            session.handle_changes();
+            assert_matches_full_scan(&session);
+        }
+        while session.undo() {
+            session.handle_changes();
+            assert_matches_full_scan(&session);
+        }
+        assert_eq!(session.search_matches().len(), 5);
+    }
+
+    #[test]
+    fn preedit_updates_replace_each_other() {
+        let document = CodeDocument::new(Text::from("ab\ncd"), DecorationSet::new());
+        let mut session = CodeSession::new(document);
+        session.set_selection(position(0, 1), Affinity::Before, SelectionMode::Simple, NewGroup::Yes);
+        session.add_selection(position(1, 2), Affinity::Before, SelectionMode::Simple);
+        for (preedit, text) in [("", "n"), ("n", "ni"), ("ni", "你")] {
+            session.replace_preedit(preedit, Text::from(text));
+            session.handle_changes();
+        }
+        assert_eq!(session.document().as_text().to_string(), "a你b\ncd你");
+
+        // a cancelled composition removes the preedit text again
+        session.replace_preedit("你", Text::default());
+        session.handle_changes();
+        assert_eq!(session.document().as_text().to_string(), "ab\ncd");
+
+        // the text before the cursor is left alone when it is not the preedit
+        session.replace_preedit("x", Text::from("y"));
+        session.handle_changes();
+        assert_eq!(session.document().as_text().to_string(), "ayb\ncdy");
+    }
+}
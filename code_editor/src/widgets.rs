@@ -1,3 +1,47 @@
+use {
+    crate::{regex::RegexError, search::SearchQuery},
+    makepad_widgets::*,
+};
+
+live_design! {
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    FindBar = {{FindBar}} {
+        width: Fill, height: Fit,
+        flow: Down,
+        padding: <THEME_MSPACE_1> {}
+        spacing: (THEME_SPACE_1)
+        show_bg: true,
+        draw_bg: { color: (THEME_COLOR_BG_CONTAINER) }
+
+        <View> {
+            width: Fill, height: Fit,
+            flow: Right,
+            spacing: (THEME_SPACE_1)
+            align: { y: 0.5 }
+            query = <TextInput> { width: Fill, empty_message: "Find" }
+            match_case = <CheckBox> { text: "Aa" }
+            whole_word = <CheckBox> { text: "Word" }
+            is_regex = <CheckBox> { text: ".*" }
+            match_count = <Label> { width: 80, text: "" }
+            find_prev = <ButtonFlat> { width: Fit, text: "Prev" }
+            find_next = <ButtonFlat> { width: Fit, text: "Next" }
+            select_all = <ButtonFlat> { width: Fit, text: "All" }
+            close = <ButtonFlat> { width: Fit, text: "Close" }
+        }
+        <View> {
+            width: Fill, height: Fit,
+            flow: Right,
+            spacing: (THEME_SPACE_1)
+            align: { y: 0.5 }
+            replacement = <TextInput> { width: Fill, empty_message: "Replace" }
+            replace = <ButtonFlat> { width: Fit, text: "Replace" }
+            replace_all = <ButtonFlat> { width: Fit, text: "Replace All" }
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct InlineWidget {
     pub column_count: usize,
@@ -7,3 +51,120 @@ pub struct InlineWidget {
 pub struct BlockWidget {
     pub height: f64,
 }
+
+#[derive(Live, LiveHook, Widget)]
+pub struct FindBar {
+    #[deref]
+    view: View,
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum FindBarAction {
+    QueryChanged(SearchQuery),
+    FindNext,
+    FindPrev,
+    SelectAll,
+    Replace(String),
+    ReplaceAll(String),
+    Close,
+    None,
+}
+
+impl Widget for FindBar {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        let uid = self.widget_uid();
+        let actions = cx.capture_actions(|cx| self.view.handle_event(cx, event, scope));
+        if actions.is_empty() {
+            return;
+        }
+        let query = self.view.text_input(id!(query));
+        let replacement = self.view.text_input(id!(replacement));
+        if query.changed(&actions).is_some()
+            || self.view.check_box(id!(match_case)).changed(&actions).is_some()
+            || self.view.check_box(id!(whole_word)).changed(&actions).is_some()
+            || self.view.check_box(id!(is_regex)).changed(&actions).is_some()
+        {
+            cx.widget_action(uid, &scope.path, FindBarAction::QueryChanged(self.query(cx)));
+        }
+        if query.returned(&actions).is_some() || self.view.button(id!(find_next)).clicked(&actions) {
+            cx.widget_action(uid, &scope.path, FindBarAction::FindNext);
+        }
+        if self.view.button(id!(find_prev)).clicked(&actions) {
+            cx.widget_action(uid, &scope.path, FindBarAction::FindPrev);
+        }
+        if self.view.button(id!(select_all)).clicked(&actions) {
+            cx.widget_action(uid, &scope.path, FindBarAction::SelectAll);
+        }
+        if replacement.returned(&actions).is_some() || self.view.button(id!(replace)).clicked(&actions) {
+            cx.widget_action(uid, &scope.path, FindBarAction::Replace(replacement.text()));
+        }
+        if self.view.button(id!(replace_all)).clicked(&actions) {
+            cx.widget_action(uid, &scope.path, FindBarAction::ReplaceAll(replacement.text()));
+        }
+        if query.escape(&actions) || replacement.escape(&actions) || self.view.button(id!(close)).clicked(&actions) {
+            cx.widget_action(uid, &scope.path, FindBarAction::Close);
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
+        self.view.draw_walk(cx, scope, walk)
+    }
+}
+
+impl FindBar {
+    pub fn query(&self, cx: &Cx) -> SearchQuery {
+        SearchQuery {
+            text: self.view.text_input(id!(query)).text(),
+            match_case: self.view.check_box(id!(match_case)).selected(cx),
+            whole_word: self.view.check_box(id!(whole_word)).selected(cx),
+            is_regex: self.view.check_box(id!(is_regex)).selected(cx),
+        }
+    }
+
+    /// Shows "n of m" for the match at index `current`, or why there are no matches.
+    pub fn set_match_count(&mut self, cx: &mut Cx, current: Option<usize>, count: Result<usize, RegexError>) {
+        let text = match count {
+            Ok(0) => "No results".to_string(),
+            Ok(count) => match current {
+                Some(current) => format!("{} of {}", current + 1, count),
+                None => format!("{} results", count),
+            },
+            Err(error) => format!("Invalid: {}", error.message),
+        };
+        self.view.label(id!(match_count)).set_text_and_redraw(cx, &text);
+    }
+}
+
+impl FindBarRef {
+    pub fn query(&self, cx: &Cx) -> Option<SearchQuery> {
+        Some(self.borrow()?.query(cx))
+    }
+
+    pub fn set_match_count(&self, cx: &mut Cx, current: Option<usize>, count: Result<usize, RegexError>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_match_count(cx, current, count);
+        }
+    }
+
+    pub fn visible(&self) -> bool {
+        self.borrow().map_or(false, |inner| inner.view.visible)
+    }
+
+    pub fn set_visible_and_redraw(&self, cx: &mut Cx, visible: bool) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.view.visible = visible;
+            inner.view.redraw(cx);
+        }
+    }
+
+    /// Focuses the query input and selects its text, so typing replaces the previous query.
+    pub fn set_key_focus(&self, cx: &mut Cx) {
+        if let Some(inner) = self.borrow() {
+            let query = inner.view.text_input(id!(query));
+            query.set_key_focus(cx);
+            if let Some(mut query) = query.borrow_mut() {
+                query.select_all();
+            };
+        }
+    }
+}
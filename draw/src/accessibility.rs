@@ -0,0 +1,172 @@
+use {
+    std::rc::Rc,
+    std::cell::RefCell,
+    crate::{
+        cx_2d::Cx2d,
+        makepad_platform::*,
+    }
+};
+
+// Widgets report accessibility nodes into the draw list they are drawn in, just
+// like nav stops. Draw lists that are not redrawn keep their items, so the tree
+// is complete again after every draw and can be flattened when Cx2d drops.
+
+#[derive(Default)]
+pub struct CxAccessibilityItems {
+    lists: Vec<Vec<AccessibilityItem>>
+}
+
+#[derive(Clone)]
+pub struct CxAccessibilityItemsRc(pub Rc<RefCell<CxAccessibilityItems >>);
+
+#[derive(Debug, Clone)]
+pub enum AccessibilityItem {
+    Child(DrawListId),
+    Begin(AccessibilityId, AccessibilityNode),
+    End(AccessibilityId, Area),
+}
+
+impl<'a> Cx2d<'a> {
+    pub fn lazy_construct_accessibility_items(cx: &mut Cx) {
+        if !cx.has_global::<CxAccessibilityItemsRc>() {
+            cx.set_global(CxAccessibilityItemsRc(Rc::new(RefCell::new(CxAccessibilityItems::default()))));
+        }
+    }
+
+    pub fn accessibility_list_clear(&mut self, draw_list_id: DrawListId) {
+        let mut items = self.accessibility_items_rc.0.borrow_mut();
+        if draw_list_id.index() >= items.lists.len() {
+            items.lists.resize(draw_list_id.index() + 1, Vec::new());
+        }
+        items.lists[draw_list_id.index()].clear();
+    }
+
+    pub fn accessibility_list_item_push(&mut self, draw_list_id: DrawListId, item: AccessibilityItem) {
+        if !self.accessibility_enabled() {
+            return
+        }
+        let mut items = self.accessibility_items_rc.0.borrow_mut();
+        items.lists[draw_list_id.index()].push(item);
+    }
+
+    /// Opens a node, everything reported until the matching `end_accessibility_node`
+    /// becomes its children. Returns false when the node is already open, which
+    /// happens when a widget that draws in steps is entered again.
+    pub fn begin_accessibility_node(&mut self, id: AccessibilityId, node: AccessibilityNode) -> bool {
+        if !self.accessibility_enabled() || self.accessibility_open.contains(&id) {
+            return false
+        }
+        let draw_list_id = *self.draw_list_stack.last().unwrap();
+        self.accessibility_list_item_push(draw_list_id, AccessibilityItem::Begin(id, node));
+        self.accessibility_open.push(id);
+        true
+    }
+
+    pub fn end_accessibility_node(&mut self, id: AccessibilityId, area: Area) {
+        if !self.accessibility_open.contains(&id) {
+            return
+        }
+        let draw_list_id = *self.draw_list_stack.last().unwrap();
+        // nodes left open by an unfinished step are closed along with their parent
+        while let Some(open) = self.accessibility_open.pop() {
+            let area = if open == id {area} else {Area::Empty};
+            self.accessibility_list_item_push(draw_list_id, AccessibilityItem::End(open, area));
+            if open == id {
+                break
+            }
+        }
+    }
+
+    pub fn add_accessibility_node(&mut self, id: AccessibilityId, node: AccessibilityNode, area: Area) {
+        if self.begin_accessibility_node(id, node) {
+            self.end_accessibility_node(id, area);
+        }
+    }
+
+    pub(crate) fn update_accessibility_tree_from_items(&mut self) {
+        if !self.accessibility_enabled() {
+            return
+        }
+        let items_rc = self.accessibility_items_rc.clone();
+        let items = items_rc.0.borrow();
+        let mut tree = AccessibilityTree::default();
+        for window_id in self.windows.created_window_ids() {
+            let Some(draw_list_id) = self.windows[window_id].main_pass_id
+                .and_then( | pass_id | self.passes[pass_id].main_draw_list_id) else {
+                continue
+            };
+            let id = AccessibilityId::from_window_id(window_id);
+            tree.roots.push(id);
+            tree.nodes.insert(id, AccessibilityTreeNode {
+                id,
+                parent: None,
+                children: Vec::new(),
+                node: AccessibilityNode {
+                    role: AccessibilityRole::Window,
+                    name: self.windows[window_id].create_title.clone(),
+                    ..Default::default()
+                },
+                window_id,
+                area: Area::Empty,
+                rect: Rect {pos: DVec2::default(), size: self.windows[window_id].window_geom.inner_size},
+                screen_rect: Rect::default(),
+            });
+            let mut stack = vec![Some(id)];
+            self.flatten_accessibility_items(&items, draw_list_id, window_id, &mut stack, &mut tree);
+        }
+        drop(items);
+        self.cx.update_accessibility_tree(tree);
+    }
+
+    fn flatten_accessibility_items(&self, items: &CxAccessibilityItems, draw_list_id: DrawListId, window_id: WindowId, stack: &mut Vec<Option<AccessibilityId>>, tree: &mut AccessibilityTree) {
+        let Some(list) = items.lists.get(draw_list_id.index()) else {return};
+        for item in list {
+            match item {
+                AccessibilityItem::Child(draw_list_id) => {
+                    self.flatten_accessibility_items(items, *draw_list_id, window_id, stack, tree);
+                }
+                AccessibilityItem::Begin(id, node) => {
+                    // a widget drawn twice only shows up once, the second copy is transparent
+                    if tree.nodes.contains_key(id) {
+                        stack.push(None);
+                        continue
+                    }
+                    let parent = stack.iter().rev().find_map( | id | *id);
+                    if let Some(parent) = parent {
+                        tree.nodes.get_mut(&parent).unwrap().children.push(*id);
+                    }
+                    tree.nodes.insert(*id, AccessibilityTreeNode {
+                        id: *id,
+                        parent,
+                        children: Vec::new(),
+                        node: node.clone(),
+                        window_id,
+                        area: Area::Empty,
+                        rect: Rect::default(),
+                        screen_rect: Rect::default(),
+                    });
+                    stack.push(Some(*id));
+                }
+                AccessibilityItem::End(id, area) => {
+                    if stack.len() <= 1 {
+                        continue
+                    }
+                    if let Some(Some(open)) = stack.pop() {
+                        if open == *id {
+                            let node = tree.nodes.get_mut(id).unwrap();
+                            node.area = *area;
+                            node.rect = area.rect(self);
+                            // lists draw rows above the first one last, read in visual order
+                            let mut children = std::mem::take(&mut node.children);
+                            children.sort_by( | a, b | {
+                                let (a, b) = (tree.nodes[a].rect.pos, tree.nodes[b].rect.pos);
+                                a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+                            });
+                            tree.nodes.get_mut(id).unwrap().children = children;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -13,9 +13,11 @@ use {
             Pass,
             CxPassParent,
             CxPassRect,
-            Cx
+            Cx,
+            AccessibilityId,
         },
         nav::CxNavTreeRc,
+        accessibility::CxAccessibilityItemsRc,
         icon_atlas::CxIconAtlasRc,
         font_atlas::{CxFontsAtlasRc, ShapeCacheRc},
         draw_list_2d::DrawList2d,
@@ -47,6 +49,8 @@ pub struct Cx2d<'a> {
     pub shape_cache_rc: ShapeCacheRc,
     pub icon_atlas_rc: CxIconAtlasRc,
     pub nav_tree_rc: CxNavTreeRc,
+    pub accessibility_items_rc: CxAccessibilityItemsRc,
+    pub (crate) accessibility_open: Vec<AccessibilityId>,
     pub rustybuzz_buffer: Option<UnicodeBuffer>, 
 }
 
@@ -57,6 +61,7 @@ impl<'a> Drop for Cx2d<'a> {
     fn drop(&mut self) {
         self.draw_font_atlas();
         self.draw_icon_atlas();
+        self.update_accessibility_tree_from_items();
     }
 }
 
@@ -77,11 +82,13 @@ impl<'a> Cx2d<'a> {
         Self::lazy_construct_font_atlas(cx);
         Self::lazy_construct_shape_cache(cx);
         Self::lazy_construct_nav_tree(cx);
+        Self::lazy_construct_accessibility_items(cx);
         Self::lazy_construct_icon_atlas(cx);
         cx.redraw_id += 1;
         let fonts_atlas_rc = cx.get_global::<CxFontsAtlasRc>().clone();
         let shape_cache_rc = cx.get_global::<ShapeCacheRc>().clone();
         let nav_tree_rc = cx.get_global::<CxNavTreeRc>().clone();
+        let accessibility_items_rc = cx.get_global::<CxAccessibilityItemsRc>().clone();
         let icon_atlas_rc = cx.get_global::<CxIconAtlasRc>().clone();
         Self {
             overlay_id: None,
@@ -97,6 +104,8 @@ impl<'a> Cx2d<'a> {
             turtles: Vec::with_capacity(64),
             align_list: Vec::with_capacity(4096),
             nav_tree_rc,
+            accessibility_items_rc,
+            accessibility_open: Vec::new(),
             icon_atlas_rc,
             rustybuzz_buffer: Some(UnicodeBuffer::new()),
         }
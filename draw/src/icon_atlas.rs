@@ -16,10 +16,11 @@ pub use {
         turtle::{Walk, Layout},
         draw_list_2d::{ManyInstances, DrawList2d, RedrawingApi},
         geometry::GeometryQuad2D,
-        makepad_vector::trapezoidator::Trapezoidator,
+        svg::{SvgDocument, SvgShape},
+        makepad_vector::trapezoidator::{FillRule, Trapezoidator},
         makepad_vector::geometry::{AffineTransformation, Transform, Vector, Point},
         makepad_vector::internal_iter::*,
-        makepad_vector::path::{PathIterator, PathCommand},
+        makepad_vector::path::{LinePathIterator, PathIterator, PathCommand, StrokeStyle},
     }
 };
 
@@ -33,31 +34,13 @@ pub struct CxIconSlot {
 #[derive(Clone)]
 pub struct CxIconEntry {
     path_hash: CxIconPathHash,
+    // the shape of the document to draw, or all of them if `None`
+    shape: Option<usize>,
     pos: DVec2,
     slot: CxIconSlot,
     args: CxIconArgs,
 }
 
-struct CxIconPathCommands {
-    bounds: Rect,
-    path: Vec<PathCommand>
-}
-
-impl<'a> InternalIterator for &CxIconPathCommands {
-    type Item = PathCommand;
-    fn for_each<F>(self, f: &mut F) -> bool
-    where
-    F: FnMut(PathCommand) -> bool,
-    {
-        for item in &self.path {
-            if !f(item.clone()) {
-                return false
-            }
-        }
-        true
-    }
-}
-
 #[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub struct CxIconPathHash(LiveId);
 
@@ -68,7 +51,7 @@ pub struct CxIconAtlas {
     pub texture: Texture,
     pub clear_buffer: bool,
     svg_deps: HashMap<String, CxIconPathHash>,
-    paths: HashMap<CxIconPathHash, Vec<CxIconPathCommands>>,
+    documents: HashMap<CxIconPathHash, Rc<SvgDocument>>,
     entries: HashMap<CxIconEntryHash, CxIconEntry>,
     alloc: CxIconAtlasAlloc
 }
@@ -112,7 +95,7 @@ impl CxIconAtlas {
             clear_buffer: false,
             entries: HashMap::new(),
             svg_deps: HashMap::new(),
-            paths: HashMap::new(),
+            documents: HashMap::new(),
             alloc: CxIconAtlasAlloc {
                 texture_size: DVec2 {x: 2048.0, y: 2048.0},
                 xpos: 0.0,
@@ -156,21 +139,7 @@ impl CxIconAtlas {
                     }
                 }
                 let bounds = Rect {pos: min, size: max - min};
-                if let Some( foundpath) = self.paths.get_mut(&path_hash) {
-                    foundpath.push(CxIconPathCommands {
-                        bounds,
-                        path
-                    })
-                }
-                else
-                {
-
-
-                    self.paths.insert(path_hash,vec![ CxIconPathCommands {
-                        bounds,
-                        path
-                    }]);
-                }
+                self.documents.insert(path_hash, Rc::new(SvgDocument::from_path(path, bounds)));
                 return Some((path_hash, bounds));
             }
             Err(e) => {
@@ -179,71 +148,29 @@ impl CxIconAtlas {
             }
         }
     }
-   
-
-    pub fn get_icon_bounds(&mut self, cx: &Cx, path_str: &Arc<String>, svg_dep: &Arc<String>) -> Option<(CxIconPathHash, Rect)> {
+    
+    /// Returns the parsed document for an svg file dependency, or for a bare svg path if
+    /// `svg_dep` is empty. Documents are parsed once and then cached by the atlas.
+    pub fn get_svg_document(&mut self, cx: &Cx, path_str: &Arc<String>, svg_dep: &Arc<String>) -> Option<(CxIconPathHash, Rc<SvgDocument>)> {
         if svg_dep.len() != 0 {
             // alright so. lets see if we have a path hash
             if let Some(path_hash) = self.svg_deps.get(svg_dep.as_str()) {
-                if let Some(path) = self.paths.get(&path_hash) {
-                    let mut bounds:Rect = path[0].bounds;
-                    for i in 1..path.len(){
-                        bounds = bounds.hull(path[i].bounds);
-                    }
-                    return Some((*path_hash, bounds))
-                }
-                return None
+                return self.documents.get(&path_hash).map(|doc| (*path_hash, doc.clone()))
             }
             let path_hash = CxIconPathHash(LiveId(self.svg_deps.len() as u64));
             self.svg_deps.insert(svg_dep.as_str().to_string(), path_hash);
-            // lets parse the path range out of the svg file
+            // lets parse the shapes out of the svg file
             match cx.get_dependency(svg_dep.as_str()) {
-                Ok(data)=>{        
-
-                    let mut errors = Some(Vec::new());
+                Ok(data)=>{
                     let svg_string = std::str::from_utf8(&data).unwrap();
-                    let  doc = parse_html(svg_string, &mut errors, InternLiveId::No);
-
-                    if errors.as_ref().unwrap().len()>0{
-                        log!("SVG parser returned errors {:?}", errors)
-                    }
-                    let mut node = doc.new_walker();
-                    
-                    while !node.done(){
-                        match node.open_tag_lc() 
-                        {
-                            some_id!(g)=>{
-                                // do something with clipping/transform groups here.
-                            }                            
-                            some_id!(path)=>{
-                                self.parse_and_cache_path(path_hash, node.find_attr_lc(live_id!(d)).unwrap().as_bytes());   
-                                                        }         
-                                                            
-                            _=>()
-                        }
-                        match node.close_tag_lc() 
-                        {
-                            some_id!(g)=>
-                            {
-                                
-                            }
-                            _=>()
-                        }
-                        node.walk();
+                    let doc = SvgDocument::parse(svg_string);
+                    if doc.shapes.is_empty() {
+                        error!("No SVG shapes found in svg file {}",path_str);
+                        return None
                     }
-                    
-                   
-                    if let Some(path) = self.paths.get(&path_hash) {
-                        let mut bounds:Rect = path[0].bounds;             
-                        for i in 1..path.len(){
-                            bounds = bounds.hull(path[i].bounds);
-                        }
-                        return Some((path_hash, bounds));              
-                    }
-
-                    error!("No SVG path tag found in svg file {}",path_str);
-                    return None
-                    
+                    let doc = Rc::new(doc);
+                    self.documents.insert(path_hash, doc.clone());
+                    return Some((path_hash, doc))
                 }
                 Err(_err)=>{
                     error!("Error in SVG file {}: {}",path_str, _err);
@@ -255,19 +182,30 @@ impl CxIconAtlas {
             return None
         }
         let path_hash = CxIconPathHash(LiveId(Arc::as_ptr(path_str) as u64));
-        if let Some(path) = self.paths.get(&path_hash) {
-            let mut bounds:Rect = path[0].bounds;
-            for i in 1..path.len(){
-                bounds = bounds.hull(path[i].bounds);
-            }
-            return Some((path_hash,bounds))
+        if self.documents.get(&path_hash).is_none() {
+            self.parse_and_cache_path(path_hash, path_str.as_str().as_bytes())?;
         }
-        self.parse_and_cache_path(path_hash, path_str.as_str().as_bytes())
+        self.documents.get(&path_hash).map(|doc| (path_hash, doc.clone()))
+    }
+
+    pub fn get_icon_bounds(&mut self, cx: &Cx, path_str: &Arc<String>, svg_dep: &Arc<String>) -> Option<(CxIconPathHash, Rect)> {
+        let (path_hash, doc) = self.get_svg_document(cx, path_str, svg_dep)?;
+        Some((path_hash, doc.shape_bounds()?))
     }
     
     pub fn get_icon_slot(&mut self, args: CxIconArgs, path_hash: CxIconPathHash) -> CxIconSlot {
         let entry_hash = CxIconEntryHash(path_hash.0.id_append(args.hash()));
-        
+        self.get_entry_slot(entry_hash, args, path_hash, None)
+    }
+    
+    /// Returns the slot for the coverage of a single shape of a document, as used by `DrawSvg`
+    /// to paint each shape separately.
+    pub fn get_svg_shape_slot(&mut self, args: CxIconArgs, path_hash: CxIconPathHash, shape: usize) -> CxIconSlot {
+        let entry_hash = CxIconEntryHash(path_hash.0.id_append(args.hash()).id_append(LiveId(shape as u64 + 1)));
+        self.get_entry_slot(entry_hash, args, path_hash, Some(shape))
+    }
+    
+    fn get_entry_slot(&mut self, entry_hash: CxIconEntryHash, args: CxIconArgs, path_hash: CxIconPathHash, shape: Option<usize>) -> CxIconSlot {
         if let Some(entry) = self.entries.get(&entry_hash) {
             return entry.slot
         }
@@ -277,6 +215,7 @@ impl CxIconAtlas {
             entry_hash,
             CxIconEntry {
                 path_hash,
+                shape,
                 slot,
                 pos,
                 args
@@ -338,24 +277,32 @@ impl CxIconAtlas {
 
 impl DrawTrapezoidVector {
     // atlas drawing function used by CxAfterDraw
-    fn draw_vector(&mut self, entry: &CxIconEntry, path: &CxIconPathCommands, many: &mut ManyInstances) {
+    fn draw_vector(&mut self, entry: &CxIconEntry, shape: &SvgShape, many: &mut ManyInstances) {
+        let transform = AffineTransformation::identity()
+            .translate(Vector::new(entry.args.translate.x, entry.args.translate.y))
+            .uniform_scale(entry.args.scale)
+            .translate(Vector::new(entry.pos.x + entry.args.subpixel.x, entry.pos.y + entry.args.subpixel.y));
+        let mut path = Vec::with_capacity(shape.path.len());
+        for cmd in &shape.path {
+            path.push(cmd.transform(&transform));
+        }
         let trapezoids = {
             let mut trapezoids = Vec::new();
-            //log_str(&format!("Serializing char {} {} {} {}", glyphtc.tx1 , cx.fonts_atlas.texture_size.x ,todo.subpixel_x_fract ,atlas_page.dpi_factor));
-            let trapezoidate = self.trapezoidator.trapezoidate(
-                path.map({
-                    //log!("{:?} {:?}", entry.args, entry.pos);
-                    move | cmd | {
-                        let cmd = cmd.transform(
-                            &AffineTransformation::identity()
-                                .translate(Vector::new(entry.args.translate.x, entry.args.translate.y))
-                                .uniform_scale(entry.args.scale)
-                                .translate(Vector::new(entry.pos.x + entry.args.subpixel.x, entry.pos.y + entry.args.subpixel.y))
-                        );
-                        cmd
-                    }
-                }).linearize(entry.args.linearize)
-            );
+            let commands = path.iter().cloned().linearize(entry.args.linearize);
+            let trapezoidate = if let Some(stroke) = shape.stroke {
+                // the outline of a stroke consists of overlapping parts, which the non-zero fill
+                // rule merges
+                self.trapezoidator.trapezoidate_with_fill_rule(
+                    commands.stroke(StrokeStyle {
+                        width: stroke.width * entry.args.scale,
+                        ..stroke
+                    }, entry.args.linearize),
+                    FillRule::NonZero
+                )
+            }
+            else {
+                self.trapezoidator.trapezoidate_with_fill_rule(commands, shape.fill_rule)
+            };
             if let Some(trapezoidate) = trapezoidate {
                 trapezoids.extend_from_internal_iter(
                     trapezoidate
@@ -456,9 +403,14 @@ impl<'a> Cx2d<'a> {
             if let Some(mut many) = self.begin_many_instances(&draw_atlas.draw_trapezoid.draw_vars) {
                 for todo in atlas_todo {
                     let entry = atlas.entries.get(&todo).unwrap();
-                    let path = atlas.paths.get(&entry.path_hash).unwrap();
-                    for i in 0..path.len(){                        
-                        draw_atlas.draw_trapezoid.draw_vector(entry, &path[i], &mut many);
+                    let doc = atlas.documents.get(&entry.path_hash).unwrap();
+                    if let Some(shape) = entry.shape {
+                        draw_atlas.draw_trapezoid.draw_vector(entry, &doc.shapes[shape], &mut many);
+                    }
+                    else {
+                        for shape in &doc.shapes {
+                            draw_atlas.draw_trapezoid.draw_vector(entry, shape, &mut many);
+                        }
                     }
                     
                 }
@@ -11,7 +11,9 @@ pub mod turtle;
 pub mod font_atlas;
 pub mod geometry;
 pub mod nav;
+pub mod accessibility;
 pub mod icon_atlas;
+pub mod svg;
 mod owned_font_face;
  
 pub use crate::{
@@ -24,6 +26,10 @@ pub use crate::{
         Padding,
         Flow,
         Size,
+        GridSpan,
+        GridTrack,
+        GridTracks,
+        GRID_MAX_TRACKS,
         TurtleAlignRange,
         DeferWalk
     },
@@ -37,6 +43,7 @@ pub use crate::{
         NavItem,
         NavScrollIndex
     },
+    accessibility::AccessibilityItem,
     draw_list_2d::{
         DrawList2d,
         ManyInstances,
@@ -49,6 +56,7 @@ pub use crate::{
     shader::{
         //draw_shape::{DrawShape, Shape, Fill},
         draw_icon::DrawIcon,
+        draw_svg::DrawSvg,
         draw_quad::DrawQuad,
         draw_line::DrawLine,
         draw_text::{Affinity, DrawText, IndexAffinity},
@@ -64,6 +72,7 @@ pub fn live_design(cx: &mut Cx) {
     crate::shader::draw_quad::live_design(cx);
     crate::shader::draw_color::live_design(cx);
     crate::shader::draw_icon::live_design(cx);
+    crate::shader::draw_svg::live_design(cx);
     crate::shader::draw_text::live_design(cx);
     crate::shader::draw_line::live_design(cx);
     crate::geometry::geometry_gen::live_design(cx);
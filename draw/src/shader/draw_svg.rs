@@ -0,0 +1,301 @@
+use {
+    std::rc::Rc,
+    crate::{
+        makepad_platform::*,
+        geometry::GeometryQuad2D,
+        icon_atlas::{CxIconAtlas, CxIconArgs},
+        svg::{SvgDocument, SvgGradient, SvgPaint, SvgShape},
+        cx_2d::Cx2d,
+        turtle::{Walk, Size},
+        makepad_vector::geometry::{Point, Transformation, Vector},
+    },
+};
+
+
+live_design!{
+
+    DrawSvg = {{DrawSvg}} {
+        color: #000
+
+        texture tex: texture2d
+        varying pos: vec2,
+        varying tex_coord1: vec2
+
+        fn clip_and_transform_vertex(self, rect_pos: vec2, rect_size: vec2) -> vec4 {
+            let clipped: vec2 = clamp(
+                self.geom_pos * rect_size + rect_pos,
+                self.draw_clip.xy,
+                self.draw_clip.zw
+            )
+            self.pos = (clipped - rect_pos) / rect_size
+
+            self.tex_coord1 = mix(
+                self.icon_t1.xy,
+                self.icon_t2.xy,
+                self.pos.xy
+            )
+
+            // only pass the clipped position forward
+            return self.camera_projection * (self.camera_view * (self.view_transform * vec4(
+                clipped.x,
+                clipped.y,
+                self.draw_depth + self.draw_zbias,
+                1.
+            )))
+        }
+
+        fn vertex(self) -> vec4 {
+            return self.clip_and_transform_vertex(self.rect_pos, self.rect_size)
+        }
+
+        fn gradient_color(self, t: float) -> vec4 {
+            let o = self.stop_offsets;
+            if t <= o.x {
+                return self.stop_color0
+            }
+            if t <= o.y {
+                return mix(self.stop_color0, self.stop_color1, (t - o.x) / max(o.y - o.x, 0.00001))
+            }
+            if t <= o.z {
+                return mix(self.stop_color1, self.stop_color2, (t - o.y) / max(o.z - o.y, 0.00001))
+            }
+            if t <= o.w {
+                return mix(self.stop_color2, self.stop_color3, (t - o.z) / max(o.w - o.z, 0.00001))
+            }
+            return self.stop_color3
+        }
+
+        fn get_color(self) -> vec4 {
+            // maps the position in the rect to gradient space
+            let p = vec2(
+                self.paint_transform.x * self.pos.x + self.paint_transform.z * self.pos.y,
+                self.paint_transform.y * self.pos.x + self.paint_transform.w * self.pos.y
+            ) + self.paint_offset;
+            if self.paint_kind < 0.5 {
+                return self.stop_color0
+            }
+            if self.paint_kind < 1.5 {
+                return self.gradient_color(p.x)
+            }
+            if self.paint_kind < 2.5 {
+                return self.gradient_color(length(p))
+            }
+            return self.color
+        }
+
+        fn pixel(self) -> vec4 {
+            let s = sample2d_rt(self.tex, self.tex_coord1.xy).x;
+            let col = self.get_color();
+            let a = s * col.a * self.opacity;
+            return vec4(col.rgb * a, a);
+        }
+    }
+}
+
+/// Draws an SVG document, painting every shape with its own colour or gradient.
+///
+/// The coverage of each shape is rendered into the icon atlas, where it is cached in the same
+/// way as the icons of `DrawIcon`.
+#[derive(Live, LiveRegister)]
+#[repr(C)]
+pub struct DrawSvg {
+    #[live(0.5)] pub linearize: f32,
+
+    #[live] pub svg_file: LiveDependency,
+    #[live] pub svg_path: ArcStringMut,
+    #[live(1.0)] pub scale: f64,
+
+    #[live] pub geometry: GeometryQuad2D,
+    #[deref] pub draw_vars: DrawVars,
+    #[calc] pub rect_pos: Vec2,
+    #[calc] pub rect_size: Vec2,
+    #[calc] pub draw_clip: Vec4,
+    #[live(1.0)] pub draw_depth: f32,
+
+    /// The colour of shapes that are painted with `currentColor`.
+    #[live] pub color: Vec4,
+    #[calc] pub icon_t1: Vec2,
+    #[calc] pub icon_t2: Vec2,
+    #[calc] pub opacity: f32,
+    #[calc] pub paint_kind: f32,
+    #[calc] pub paint_transform: Vec4,
+    #[calc] pub paint_offset: Vec2,
+    #[calc] pub stop_offsets: Vec4,
+    #[calc] pub stop_color0: Vec4,
+    #[calc] pub stop_color1: Vec4,
+    #[calc] pub stop_color2: Vec4,
+    #[calc] pub stop_color3: Vec4,
+}
+
+impl LiveHook for DrawSvg{
+    fn before_apply(&mut self, cx: &mut Cx, apply: &mut Apply, index: usize, nodes: &[LiveNode]){
+        self.draw_vars.before_apply_init_shader(cx, apply, index, nodes, &self.geometry);
+    }
+    fn after_apply(&mut self, cx: &mut Cx, apply: &mut Apply, index: usize, nodes: &[LiveNode]) {
+        self.draw_vars.after_apply_update_self(cx, apply, index, nodes, &self.geometry);
+    }
+}
+
+impl DrawSvg {
+
+    pub fn new_draw_call(&self, cx: &mut Cx2d) {
+        cx.new_draw_call(&self.draw_vars);
+    }
+
+    /// Returns the parsed document, for instance to find the size it should be drawn at.
+    pub fn document(&self, cx: &mut Cx2d) -> Option<Rc<SvgDocument>> {
+        let icon_atlas_rc = cx.icon_atlas_rc.clone();
+        let mut icon_atlas = icon_atlas_rc.0.borrow_mut();
+        icon_atlas.get_svg_document(cx, &self.svg_path.as_arc(), self.svg_file.as_ref()).map(|(_, doc)| doc)
+    }
+
+    pub fn draw_walk(&mut self, cx: &mut Cx2d, mut walk: Walk) {
+        let icon_atlas_rc = cx.icon_atlas_rc.clone();
+        let mut icon_atlas = icon_atlas_rc.0.borrow_mut();
+        let icon_atlas = &mut*icon_atlas;
+
+        let Some((path_hash, doc)) = icon_atlas.get_svg_document(cx, &self.svg_path.as_arc(), self.svg_file.as_ref()) else {
+            return
+        };
+        let Some(bounds) = doc.bounds() else {
+            return
+        };
+        if bounds.size.x <= 0.0 || bounds.size.y <= 0.0 {
+            return
+        }
+        let width_is_fit = walk.width.is_fit();
+        let height_is_fit = walk.height.is_fit();
+        let peek_rect = cx.peek_walk_turtle(walk);
+        let mut scale = 1.0;
+
+        if width_is_fit {
+            if !height_is_fit {
+                scale = peek_rect.size.y / bounds.size.y
+            };
+            walk.width = Size::Fixed(bounds.size.x * self.scale * scale);
+        }
+        if height_is_fit {
+            if !width_is_fit {
+                scale = peek_rect.size.x / bounds.size.x
+            };
+            walk.height = Size::Fixed(bounds.size.y * self.scale * scale);
+        }
+        if !width_is_fit && !height_is_fit {
+            scale = (peek_rect.size.y / bounds.size.y).min(peek_rect.size.x / bounds.size.x);
+        }
+        let rect = cx.walk_turtle(walk);
+        if rect.is_nan(){
+            return
+        }
+
+        let dpi_factor = cx.current_dpi_factor();
+        // the number of physical pixels per unit of the document
+        let doc_scale = self.scale * scale * dpi_factor;
+
+        let mi = cx.begin_many_aligned_instances(&self.draw_vars);
+        self.update_draw_call_vars(icon_atlas);
+        let Some(mut mi) = mi else {
+            return
+        };
+        for (index, shape) in doc.shapes.iter().enumerate() {
+            // every shape gets its own slot, covering only the bounds of the shape
+            let dpi_pos = rect.pos * dpi_factor + (shape.bounds.pos - bounds.pos) * doc_scale;
+            let snapped_pos = dpi_pos.floor();
+            let snapped_size = (shape.bounds.size * doc_scale).ceil() + dvec2(2.0, 2.0);
+            // we should snap the subpixel to 8x8 steps
+            let subpixel = dvec2(
+                ((dpi_pos.x - snapped_pos.x) * 8.0).floor() / 8.0,
+                ((dpi_pos.y - snapped_pos.y) * 8.0).floor() / 8.0
+            );
+            let slot = icon_atlas.get_svg_shape_slot(CxIconArgs {
+                linearize: self.linearize as f64,
+                size: snapped_size,
+                scale: doc_scale,
+                translate: -shape.bounds.pos,
+                subpixel: subpixel
+            }, path_hash, index);
+
+            // lets snap the pos/size to actual pixels
+            self.rect_pos = (snapped_pos / dpi_factor).into();
+            self.rect_size = (snapped_size / dpi_factor).into();
+            self.icon_t1 = slot.t1;
+            self.icon_t2 = slot.t2;
+
+            // a position in the rect maps to this point in the document
+            let doc_origin = shape.bounds.pos - subpixel / doc_scale;
+            let doc_size = snapped_size / doc_scale;
+            self.set_paint(shape, doc_origin, doc_size);
+
+            mi.instances.extend_from_slice(self.draw_vars.as_slice());
+        }
+        let new_area = cx.end_many_instances(mi);
+        self.draw_vars.area = cx.update_area_refs(self.draw_vars.area, new_area);
+    }
+
+    fn set_paint(&mut self, shape: &SvgShape, doc_origin: DVec2, doc_size: DVec2) {
+        self.opacity = shape.opacity;
+        self.paint_transform = vec4(1.0, 0.0, 0.0, 1.0);
+        self.paint_offset = vec2(0.0, 0.0);
+        let gradient = match &shape.paint {
+            SvgPaint::Color(color) => {
+                self.paint_kind = 0.0;
+                self.stop_color0 = *color;
+                return
+            }
+            SvgPaint::CurrentColor => {
+                self.paint_kind = 3.0;
+                return
+            }
+            SvgPaint::LinearGradient(gradient) => {
+                self.paint_kind = 1.0;
+                gradient
+            }
+            SvgPaint::RadialGradient(gradient) => {
+                self.paint_kind = 2.0;
+                gradient
+            }
+        };
+        // the gradient transform is applied to `doc_origin + pos * doc_size`, for a position `pos`
+        // in the rect
+        let t = gradient.transform;
+        let x = t.transform_vector(Vector::new(doc_size.x, 0.0));
+        let y = t.transform_vector(Vector::new(0.0, doc_size.y));
+        let o = t.transform_point(Point::new(doc_origin.x, doc_origin.y));
+        self.paint_transform = vec4(x.x as f32, x.y as f32, y.x as f32, y.y as f32);
+        self.paint_offset = vec2(o.x as f32, o.y as f32);
+        self.set_stops(gradient);
+    }
+
+    // The shader supports up to four stops. Gradients with more stops are resampled at four
+    // evenly spaced offsets.
+    fn set_stops(&mut self, gradient: &SvgGradient) {
+        let mut offsets = [0.0f32; 4];
+        let mut colors = [Vec4::default(); 4];
+        let stops = &gradient.stops;
+        if stops.len() <= 4 {
+            for i in 0..4 {
+                let stop = stops[i.min(stops.len() - 1)];
+                offsets[i] = if i < stops.len() {stop.offset} else {1.0};
+                colors[i] = stop.color;
+            }
+        }
+        else {
+            let first = stops[0].offset;
+            let last = stops[stops.len() - 1].offset;
+            for i in 0..4 {
+                offsets[i] = first + (last - first) * i as f32 / 3.0;
+                colors[i] = gradient.color_at(offsets[i]);
+            }
+        }
+        self.stop_offsets = vec4(offsets[0], offsets[1], offsets[2], offsets[3]);
+        self.stop_color0 = colors[0];
+        self.stop_color1 = colors[1];
+        self.stop_color2 = colors[2];
+        self.stop_color3 = colors[3];
+    }
+
+    pub fn update_draw_call_vars(&mut self, atlas: &CxIconAtlas) {
+        self.draw_vars.texture_slots[0] = Some(atlas.texture.clone());
+    }
+
+}
@@ -798,7 +798,7 @@ impl DrawText {
 
         // Compute the fixed width of the bounding box, if it has one.
         let fixed_width = if !walk.width.is_fit() {
-            Some(cx.turtle().eval_width(walk.width, walk.margin, cx.turtle().layout().flow))
+            Some(walk.clamp_width(cx.turtle().eval_width(walk.width, walk.margin, cx.turtle().layout().flow)))
         } else {
             None
         };
@@ -810,9 +810,10 @@ impl DrawText {
             None
         };
 
-        // If word wrapping is enabled, set the wrap width to the fixed width of the bounding box.
+        // If word wrapping is enabled, set the wrap width to the fixed width of the bounding box,
+        // or to its maximum width when it fits the text.
         let wrap_width = if self.wrap == TextWrap::Word {
-            fixed_width
+            fixed_width.or(walk.max_width)
         } else {
             None
         };
@@ -853,12 +854,12 @@ impl DrawText {
             height = fixed_height;
         }
 
-        // Walk the turtle with the bounding box to obtain the draw rectangle.
+        // Walk the turtle with the bounding box to obtain the draw rectangle, the turtle
+        // applies the size constraints of the walk.
         let rect = cx.walk_turtle(Walk {
-            abs_pos: walk.abs_pos,
-            margin: walk.margin,
             width: Size::Fixed(width),
             height: Size::Fixed(height),
+            ..walk
         });
 
         // cx.cx.debug.rect(rect, vec4(1.0, 0.0, 0.0, 1.0));
@@ -985,7 +986,8 @@ impl DrawText {
                             abs_pos: None,
                             margin: Margin::default(),
                             width: Size::Fixed(width),
-                            height: Size::Fixed(line_height)
+                            height: Size::Fixed(line_height),
+                            ..Default::default()
                         });
 
                         self.draw_glyphs(
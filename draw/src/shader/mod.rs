@@ -1,6 +1,7 @@
 pub mod draw_color;
 pub mod draw_quad;
 pub mod draw_icon;
+pub mod draw_svg;
 pub mod draw_line;
 //pub mod draw_shape;
 pub mod draw_text;
@@ -0,0 +1,944 @@
+use {
+    std::{
+        collections::HashMap,
+        f64::consts::PI,
+    },
+    makepad_html::*,
+    crate::{
+        makepad_platform::*,
+        icon_atlas::parse_svg_path,
+        makepad_vector::{
+            geometry::{AffineTransformation, Arc, LinearTransformation, Point, Transform, Transformation, Vector},
+            path::{LineCap, LineJoin, LinePathCommand, LinePathIterator, PathCommand, PathIterator, StrokeStyle},
+            trapezoidator::FillRule,
+        },
+    }
+};
+
+/// A parsed SVG document, flattened into a list of shapes in paint order.
+///
+/// Groups are resolved while parsing, so every shape carries its own outline in document
+/// coordinates, together with the paint and opacity it inherited from its ancestors.
+#[derive(Clone, Debug, Default)]
+pub struct SvgDocument {
+    /// The area of the document that should be shown, taken from the `viewBox` or the `width`
+    /// and `height` of the root element.
+    pub view_box: Option<Rect>,
+    pub shapes: Vec<SvgShape>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SvgShape {
+    /// The outline of the shape in document coordinates.
+    pub path: Vec<PathCommand>,
+    /// The area covered by the shape in document coordinates, including its stroke.
+    pub bounds: Rect,
+    pub fill_rule: FillRule,
+    /// If set, the outline is stroked with this style instead of filled.
+    pub stroke: Option<StrokeStyle>,
+    pub paint: SvgPaint,
+    /// The opacity of the shape, multiplied with the opacity of its ancestors.
+    pub opacity: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum SvgPaint {
+    Color(Vec4),
+    /// The colour that the document is drawn with, for `currentColor` when no ancestor sets it.
+    CurrentColor,
+    LinearGradient(SvgGradient),
+    RadialGradient(SvgGradient),
+}
+
+#[derive(Clone, Debug)]
+pub struct SvgGradient {
+    /// Maps document coordinates to gradient space. A linear gradient runs from 0 to 1 along the
+    /// x-axis, and a radial gradient from 0 at the origin to 1 at the unit circle.
+    pub transform: AffineTransformation,
+    pub stops: Vec<SvgGradientStop>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SvgGradientStop {
+    pub offset: f32,
+    pub color: Vec4,
+}
+
+impl SvgGradient {
+    /// Returns the colour of the gradient at the given offset.
+    pub fn color_at(&self, offset: f32) -> Vec4 {
+        let stops = &self.stops;
+        let Some(first) = stops.first() else {
+            return Vec4::default()
+        };
+        if offset <= first.offset {
+            return first.color
+        }
+        for pair in stops.windows(2) {
+            if offset <= pair[1].offset {
+                let range = pair[1].offset - pair[0].offset;
+                if range <= 0.0 {
+                    return pair[1].color
+                }
+                let t = (offset - pair[0].offset) / range;
+                return pair[0].color + (pair[1].color - pair[0].color) * t
+            }
+        }
+        stops.last().unwrap().color
+    }
+}
+
+impl SvgDocument {
+    /// Creates a document with a single shape that fills the given path with `currentColor`.
+    pub fn from_path(path: Vec<PathCommand>, bounds: Rect) -> SvgDocument {
+        SvgDocument {
+            view_box: None,
+            shapes: vec![SvgShape {
+                path,
+                bounds,
+                fill_rule: FillRule::NonZero,
+                stroke: None,
+                paint: SvgPaint::CurrentColor,
+                opacity: 1.0,
+            }]
+        }
+    }
+
+    /// Parses an SVG document. Elements that can not be parsed are logged and skipped, as are
+    /// elements that are not supported, like text, images and `<use>`.
+    pub fn parse(svg: &str) -> SvgDocument {
+        let mut errors = Some(Vec::new());
+        let doc = parse_html(svg, &mut errors, InternLiveId::No);
+        if errors.as_ref().unwrap().len()>0{
+            log!("SVG parser returned errors {:?}", errors)
+        }
+        let mut parser = SvgParser::default();
+        let mut node = doc.new_walker();
+        while !node.done() {
+            if let Some(tag) = node.open_tag_lc() {
+                parser.open_tag(tag, &element_attributes(&node));
+            }
+            if let Some(tag) = node.close_tag_lc() {
+                parser.close_tag(tag);
+            }
+            node.walk();
+        }
+        parser.finish()
+    }
+
+    /// Returns the union of the bounds of all shapes.
+    pub fn shape_bounds(&self) -> Option<Rect> {
+        let mut shapes = self.shapes.iter();
+        let mut bounds = shapes.next()?.bounds;
+        for shape in shapes {
+            bounds = bounds.hull(shape.bounds);
+        }
+        Some(bounds)
+    }
+
+    /// Returns the area of the document that should be shown: the view box if there is one,
+    /// and the union of the bounds of all shapes otherwise.
+    pub fn bounds(&self) -> Option<Rect> {
+        self.view_box.or_else(|| self.shape_bounds())
+    }
+}
+
+const VIEWBOX: LiveId = LiveId::from_str_lc("viewBox");
+const FILL_OPACITY: LiveId = LiveId::from_str_lc("fill-opacity");
+const FILL_RULE: LiveId = LiveId::from_str_lc("fill-rule");
+const STROKE_WIDTH: LiveId = LiveId::from_str_lc("stroke-width");
+const STROKE_OPACITY: LiveId = LiveId::from_str_lc("stroke-opacity");
+const STROKE_LINECAP: LiveId = LiveId::from_str_lc("stroke-linecap");
+const STROKE_LINEJOIN: LiveId = LiveId::from_str_lc("stroke-linejoin");
+const STROKE_MITERLIMIT: LiveId = LiveId::from_str_lc("stroke-miterlimit");
+const STOP_COLOR: LiveId = LiveId::from_str_lc("stop-color");
+const STOP_OPACITY: LiveId = LiveId::from_str_lc("stop-opacity");
+const GRADIENT_UNITS: LiveId = LiveId::from_str_lc("gradientUnits");
+const GRADIENT_TRANSFORM: LiveId = LiveId::from_str_lc("gradientTransform");
+const XLINK_HREF: LiveId = LiveId::from_str_lc("xlink:href");
+
+// Returns the attributes of the tag the walker is on, followed by the declarations in its
+// `style` attribute, so that the latter take precedence when searching from the back.
+fn element_attributes<'a>(node: &HtmlWalker<'a>) -> Vec<(LiveId, &'a str)> {
+    let mut walker = node.clone();
+    walker.index += 1;
+    let mut attrs = Vec::new();
+    let mut style = None;
+    while let Some((id, value)) = walker.while_attr_lc() {
+        if id == live_id!(style) {
+            style = Some(value);
+        }
+        else {
+            attrs.push((id, value));
+        }
+    }
+    if let Some(style) = style {
+        for declaration in style.split(';') {
+            if let Some((key, value)) = declaration.split_once(':') {
+                attrs.push((LiveId::from_str_lc(key.trim()), value.trim()));
+            }
+        }
+    }
+    attrs
+}
+
+fn find_attr<'a>(attrs: &[(LiveId, &'a str)], id: LiveId) -> Option<&'a str> {
+    attrs.iter().rev().find(|(attr_id, _)| *attr_id == id).map(|(_, value)| *value)
+}
+
+#[derive(Clone, Debug)]
+enum Paint {
+    None,
+    Color(Vec4),
+    CurrentColor,
+    Url(String),
+}
+
+// The state that is inherited from an element by its children.
+#[derive(Clone, Debug)]
+struct Style {
+    transform: AffineTransformation,
+    opacity: f32,
+    color: Option<Vec4>,
+    fill: Paint,
+    fill_opacity: f32,
+    fill_rule: FillRule,
+    stroke: Paint,
+    stroke_opacity: f32,
+    stroke_style: StrokeStyle,
+    // Whether shapes are drawn, which is not the case inside `<defs>` and the like.
+    render: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            transform: AffineTransformation::identity(),
+            opacity: 1.0,
+            color: None,
+            fill: Paint::Color(vec4(0.0, 0.0, 0.0, 1.0)),
+            fill_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            stroke: Paint::None,
+            stroke_opacity: 1.0,
+            stroke_style: StrokeStyle::default(),
+            render: true,
+        }
+    }
+}
+
+#[derive(Default)]
+struct GradientDef {
+    radial: bool,
+    attrs: HashMap<LiveId, String>,
+    stops: Vec<SvgGradientStop>,
+}
+
+// A shape with a gradient paint, which is resolved once the whole document has been parsed,
+// since gradients can be defined after they are used.
+struct PendingGradient {
+    shape: usize,
+    id: String,
+    transform: AffineTransformation,
+    local_bounds: Rect,
+}
+
+#[derive(Default)]
+struct SvgParser {
+    styles: Vec<Style>,
+    view_box: Option<Rect>,
+    shapes: Vec<SvgShape>,
+    gradients: HashMap<String, GradientDef>,
+    gradient: Option<(String, GradientDef)>,
+    pending_gradients: Vec<PendingGradient>,
+}
+
+impl SvgParser {
+    fn open_tag(&mut self, tag: LiveId, attrs: &[(LiveId, &str)]) {
+        let mut style = self.styles.last().cloned().unwrap_or_default();
+        self.apply_style(&mut style, attrs);
+        match tag {
+            live_id!(svg) if self.styles.is_empty() => {
+                let view_box = find_attr(attrs, VIEWBOX).map(parse_numbers);
+                self.view_box = match view_box {
+                    Some(v) if v.len() == 4 && v[2] > 0.0 && v[3] > 0.0 => {
+                        Some(Rect {pos: dvec2(v[0], v[1]), size: dvec2(v[2], v[3])})
+                    }
+                    _ => {
+                        let width = find_attr(attrs, live_id!(width)).and_then(|v| parse_length(v, 0.0));
+                        let height = find_attr(attrs, live_id!(height)).and_then(|v| parse_length(v, 0.0));
+                        match (width, height) {
+                            (Some(width), Some(height)) if width > 0.0 && height > 0.0 => {
+                                Some(Rect {pos: dvec2(0.0, 0.0), size: dvec2(width, height)})
+                            }
+                            _ => None
+                        }
+                    }
+                };
+            }
+            live_id!(defs) | live_id!(clippath) | live_id!(mask) | live_id!(symbol) |
+            live_id!(marker) | live_id!(pattern) => {
+                style.render = false;
+            }
+            live_id!(lineargradient) | live_id!(radialgradient) => {
+                let mut gradient = GradientDef {
+                    radial: tag == live_id!(radialgradient),
+                    ..Default::default()
+                };
+                for (id, value) in attrs {
+                    gradient.attrs.insert(*id, value.to_string());
+                }
+                let id = find_attr(attrs, live_id!(id)).unwrap_or("").to_string();
+                self.gradient = Some((id, gradient));
+            }
+            live_id!(stop) => if let Some((_, gradient)) = &mut self.gradient {
+                let offset = find_attr(attrs, live_id!(offset))
+                    .and_then(|v| parse_length(v, 1.0))
+                    .unwrap_or(0.0)
+                    .clamp(0.0, 1.0) as f32;
+                // offsets that are smaller than the previous offset are raised to it
+                let offset = gradient.stops.last().map_or(offset, |stop| offset.max(stop.offset));
+                let mut color = match find_attr(attrs, STOP_COLOR).and_then(parse_paint) {
+                    Some(Paint::Color(color)) => color,
+                    Some(Paint::CurrentColor) => style.color.unwrap_or(vec4(0.0, 0.0, 0.0, 1.0)),
+                    _ => vec4(0.0, 0.0, 0.0, 1.0)
+                };
+                color.w *= find_attr(attrs, STOP_OPACITY).and_then(parse_number).unwrap_or(1.0) as f32;
+                gradient.stops.push(SvgGradientStop {offset, color});
+            }
+            live_id!(path) | live_id!(rect) | live_id!(circle) | live_id!(ellipse) |
+            live_id!(line) | live_id!(polyline) | live_id!(polygon) if style.render => {
+                match self.shape_path(tag, attrs) {
+                    Ok(path) => self.add_shapes(&style, path),
+                    Err(e) => log!("Error in SVG {} element: {}", tag, e)
+                }
+            }
+            _ => ()
+        }
+        self.styles.push(style);
+    }
+
+    fn close_tag(&mut self, tag: LiveId) {
+        self.styles.pop();
+        if let live_id!(lineargradient) | live_id!(radialgradient) = tag {
+            if let Some((id, gradient)) = self.gradient.take() {
+                self.gradients.insert(id, gradient);
+            }
+        }
+    }
+
+    fn apply_style(&self, style: &mut Style, attrs: &[(LiveId, &str)]) {
+        let mut opacity = 1.0;
+        for (id, value) in attrs {
+            match *id {
+                live_id!(transform) => {
+                    style.transform = compose(style.transform, parse_transform(value));
+                }
+                live_id!(opacity) => if let Some(v) = parse_number(value) {
+                    opacity = v.clamp(0.0, 1.0) as f32;
+                }
+                live_id!(color) => if let Some(Paint::Color(color)) = parse_paint(value) {
+                    style.color = Some(color);
+                }
+                live_id!(fill) => if let Some(paint) = parse_paint(value) {
+                    style.fill = paint;
+                }
+                live_id!(stroke) => if let Some(paint) = parse_paint(value) {
+                    style.stroke = paint;
+                }
+                live_id!(display) => if value.trim() == "none" {
+                    style.render = false;
+                }
+                FILL_OPACITY => if let Some(v) = parse_number(value) {
+                    style.fill_opacity = v.clamp(0.0, 1.0) as f32;
+                }
+                FILL_RULE => match value.trim() {
+                    "evenodd" => style.fill_rule = FillRule::EvenOdd,
+                    "nonzero" => style.fill_rule = FillRule::NonZero,
+                    _ => ()
+                }
+                STROKE_OPACITY => if let Some(v) = parse_number(value) {
+                    style.stroke_opacity = v.clamp(0.0, 1.0) as f32;
+                }
+                STROKE_WIDTH => if let Some(v) = parse_length(value, self.viewport_diagonal()) {
+                    style.stroke_style.width = v.max(0.0);
+                }
+                STROKE_LINECAP => match value.trim() {
+                    "butt" => style.stroke_style.line_cap = LineCap::Butt,
+                    "round" => style.stroke_style.line_cap = LineCap::Round,
+                    "square" => style.stroke_style.line_cap = LineCap::Square,
+                    _ => ()
+                }
+                STROKE_LINEJOIN => match value.trim() {
+                    "miter" => style.stroke_style.line_join = LineJoin::Miter,
+                    "round" => style.stroke_style.line_join = LineJoin::Round,
+                    "bevel" => style.stroke_style.line_join = LineJoin::Bevel,
+                    _ => ()
+                }
+                STROKE_MITERLIMIT => if let Some(v) = parse_number(value) {
+                    style.stroke_style.miter_limit = v.max(1.0);
+                }
+                _ => ()
+            }
+        }
+        // opacity is not inherited, but it applies to everything that is drawn by the children
+        style.opacity *= opacity;
+    }
+
+    fn viewport_size(&self) -> DVec2 {
+        self.view_box.map_or(dvec2(100.0, 100.0), |view_box| view_box.size)
+    }
+
+    // The reference length for percentages that are neither horizontal nor vertical.
+    fn viewport_diagonal(&self) -> f64 {
+        let size = self.viewport_size();
+        (size.x * size.x + size.y * size.y).sqrt() / 2.0f64.sqrt()
+    }
+
+    // Returns the outline of a basic shape in its local coordinates.
+    fn shape_path(&self, tag: LiveId, attrs: &[(LiveId, &str)]) -> Result<Vec<PathCommand>, String> {
+        let size = self.viewport_size();
+        let diagonal = self.viewport_diagonal();
+        let length = |id: LiveId, reference: f64| {
+            find_attr(attrs, id).and_then(|v| parse_length(v, reference)).unwrap_or(0.0)
+        };
+        let mut path = Vec::new();
+        match tag {
+            live_id!(path) => {
+                path = parse_svg_path(find_attr(attrs, live_id!(d)).unwrap_or("").as_bytes())?;
+            }
+            live_id!(rect) => {
+                let (x, y) = (length(live_id!(x), size.x), length(live_id!(y), size.y));
+                let (w, h) = (length(live_id!(width), size.x), length(live_id!(height), size.y));
+                if w <= 0.0 || h <= 0.0 {
+                    return Ok(path)
+                }
+                let rx = find_attr(attrs, live_id!(rx)).and_then(|v| parse_length(v, size.x));
+                let ry = find_attr(attrs, live_id!(ry)).and_then(|v| parse_length(v, size.y));
+                // a missing corner radius defaults to the other one
+                let rx = rx.or(ry).unwrap_or(0.0).clamp(0.0, w / 2.0);
+                let ry = ry.or(Some(rx)).unwrap_or(0.0).clamp(0.0, h / 2.0);
+                if rx > 0.0 && ry > 0.0 {
+                    let r = Point::new(rx, ry);
+                    path.push(PathCommand::MoveTo(Point::new(x + rx, y)));
+                    path.push(PathCommand::LineTo(Point::new(x + w - rx, y)));
+                    path.push(PathCommand::ArcTo(Point::new(x + w, y + ry), r, 0.0, false, true));
+                    path.push(PathCommand::LineTo(Point::new(x + w, y + h - ry)));
+                    path.push(PathCommand::ArcTo(Point::new(x + w - rx, y + h), r, 0.0, false, true));
+                    path.push(PathCommand::LineTo(Point::new(x + rx, y + h)));
+                    path.push(PathCommand::ArcTo(Point::new(x, y + h - ry), r, 0.0, false, true));
+                    path.push(PathCommand::LineTo(Point::new(x, y + ry)));
+                    path.push(PathCommand::ArcTo(Point::new(x + rx, y), r, 0.0, false, true));
+                }
+                else {
+                    path.push(PathCommand::MoveTo(Point::new(x, y)));
+                    path.push(PathCommand::LineTo(Point::new(x + w, y)));
+                    path.push(PathCommand::LineTo(Point::new(x + w, y + h)));
+                    path.push(PathCommand::LineTo(Point::new(x, y + h)));
+                }
+                path.push(PathCommand::Close);
+            }
+            live_id!(circle) | live_id!(ellipse) => {
+                let (cx, cy) = (length(live_id!(cx), size.x), length(live_id!(cy), size.y));
+                let (rx, ry) = if tag == live_id!(circle) {
+                    let r = length(live_id!(r), diagonal);
+                    (r, r)
+                }
+                else {
+                    (length(live_id!(rx), size.x), length(live_id!(ry), size.y))
+                };
+                if rx <= 0.0 || ry <= 0.0 {
+                    return Ok(path)
+                }
+                let r = Point::new(rx, ry);
+                path.push(PathCommand::MoveTo(Point::new(cx + rx, cy)));
+                path.push(PathCommand::ArcTo(Point::new(cx - rx, cy), r, 0.0, false, true));
+                path.push(PathCommand::ArcTo(Point::new(cx + rx, cy), r, 0.0, false, true));
+                path.push(PathCommand::Close);
+            }
+            live_id!(line) => {
+                path.push(PathCommand::MoveTo(Point::new(length(live_id!(x1), size.x), length(live_id!(y1), size.y))));
+                path.push(PathCommand::LineTo(Point::new(length(live_id!(x2), size.x), length(live_id!(y2), size.y))));
+            }
+            live_id!(polyline) | live_id!(polygon) => {
+                let points = parse_numbers(find_attr(attrs, live_id!(points)).unwrap_or(""));
+                for (index, point) in points.chunks_exact(2).enumerate() {
+                    let point = Point::new(point[0], point[1]);
+                    path.push(if index == 0 {PathCommand::MoveTo(point)} else {PathCommand::LineTo(point)});
+                }
+                if tag == live_id!(polygon) && !path.is_empty() {
+                    path.push(PathCommand::Close);
+                }
+            }
+            _ => ()
+        }
+        Ok(path)
+    }
+
+    // Adds a shape for the fill and a shape for the stroke of the given outline.
+    fn add_shapes(&mut self, style: &Style, path: Vec<PathCommand>) {
+        if path.is_empty() {
+            return
+        }
+        // arcs can not be transformed by every affine transformation, but cubics can
+        let local_path = arcs_to_cubics(&path);
+        let Some(local_bounds) = control_point_bounds(&local_path) else {
+            return
+        };
+        let mut doc_path = Vec::with_capacity(local_path.len());
+        for command in &local_path {
+            doc_path.push(command.transform(&style.transform));
+        }
+        let Some(doc_bounds) = control_point_bounds(&doc_path) else {
+            return
+        };
+        // the tolerance is only used for the bounds, the atlas linearizes at its own resolution
+        let epsilon = (doc_bounds.size.x.max(doc_bounds.size.y) * 1e-3).max(1e-6);
+        if !matches!(style.fill, Paint::None) {
+            let bounds = line_path_bounds(doc_path.iter().cloned().linearize(epsilon));
+            if let Some(bounds) = bounds.filter(|bounds| bounds.size.x > 0.0 && bounds.size.y > 0.0) {
+                self.add_shape(style, &style.fill, SvgShape {
+                    path: doc_path.clone(),
+                    bounds,
+                    fill_rule: style.fill_rule,
+                    stroke: None,
+                    paint: SvgPaint::CurrentColor,
+                    opacity: style.opacity * style.fill_opacity,
+                }, local_bounds);
+            }
+        }
+        if !matches!(style.stroke, Paint::None) && style.stroke_style.width > 0.0 {
+            // non-uniform scales are approximated with a uniform scale of the same area
+            let xy = style.transform.xy;
+            let scale = xy.x.cross(xy.y).abs().sqrt();
+            let stroke_style = StrokeStyle {
+                width: style.stroke_style.width * scale,
+                ..style.stroke_style
+            };
+            let epsilon = epsilon.max(stroke_style.width * 1e-3);
+            let bounds = line_path_bounds(
+                doc_path.iter().cloned().linearize(epsilon).stroke(stroke_style, epsilon)
+            );
+            if let Some(bounds) = bounds {
+                self.add_shape(style, &style.stroke, SvgShape {
+                    path: doc_path,
+                    bounds,
+                    fill_rule: FillRule::NonZero,
+                    stroke: Some(stroke_style),
+                    paint: SvgPaint::CurrentColor,
+                    opacity: style.opacity * style.stroke_opacity,
+                }, local_bounds);
+            }
+        }
+    }
+
+    fn add_shape(&mut self, style: &Style, paint: &Paint, mut shape: SvgShape, local_bounds: Rect) {
+        match paint {
+            Paint::None => return,
+            Paint::Color(color) => shape.paint = SvgPaint::Color(*color),
+            Paint::CurrentColor => if let Some(color) = style.color {
+                shape.paint = SvgPaint::Color(color);
+            }
+            Paint::Url(id) => self.pending_gradients.push(PendingGradient {
+                shape: self.shapes.len(),
+                id: id.clone(),
+                transform: style.transform,
+                local_bounds,
+            })
+        }
+        self.shapes.push(shape);
+    }
+
+    fn finish(mut self) -> SvgDocument {
+        let mut removed = Vec::new();
+        for pending in std::mem::take(&mut self.pending_gradients) {
+            match self.resolve_gradient(&pending) {
+                Some(paint) => self.shapes[pending.shape].paint = paint,
+                None => {
+                    log!("SVG gradient #{} not found", pending.id);
+                    removed.push(pending.shape);
+                }
+            }
+        }
+        for index in removed.into_iter().rev() {
+            self.shapes.remove(index);
+        }
+        SvgDocument {
+            view_box: self.view_box,
+            shapes: self.shapes,
+        }
+    }
+
+    // Looks up an attribute of a gradient, following `href` to the gradients it inherits from.
+    fn gradient_attr(&self, id: &str, attr: LiveId) -> Option<&str> {
+        let mut gradient = self.gradients.get(id)?;
+        for _ in 0..8 {
+            if let Some(value) = gradient.attrs.get(&attr) {
+                return Some(value)
+            }
+            let href = gradient.attrs.get(&live_id!(href)).or(gradient.attrs.get(&XLINK_HREF))?;
+            gradient = self.gradients.get(href.trim().trim_start_matches('#'))?;
+        }
+        None
+    }
+
+    fn gradient_stops(&self, id: &str) -> Option<&[SvgGradientStop]> {
+        let mut gradient = self.gradients.get(id)?;
+        for _ in 0..8 {
+            if !gradient.stops.is_empty() {
+                return Some(&gradient.stops)
+            }
+            let href = gradient.attrs.get(&live_id!(href)).or(gradient.attrs.get(&XLINK_HREF))?;
+            gradient = self.gradients.get(href.trim().trim_start_matches('#'))?;
+        }
+        None
+    }
+
+    fn resolve_gradient(&self, pending: &PendingGradient) -> Option<SvgPaint> {
+        let id = pending.id.as_str();
+        let radial = self.gradients.get(id)?.radial;
+        let stops = self.gradient_stops(id)?.to_vec();
+        let user_space = self.gradient_attr(id, GRADIENT_UNITS) == Some("userSpaceOnUse");
+        // percentages refer to the viewport in user space, and to the bounding box otherwise
+        let (size, diagonal) = if user_space {
+            (self.viewport_size(), self.viewport_diagonal())
+        }
+        else {
+            (dvec2(1.0, 1.0), 1.0)
+        };
+        let length = |attr: LiveId, reference: f64, default: f64| {
+            self.gradient_attr(id, attr).and_then(|v| parse_length(v, reference)).unwrap_or(default)
+        };
+        // maps gradient coordinates to the local coordinates of the shape
+        let mut local_from_gradient = pending.transform;
+        if !user_space {
+            let bounds = pending.local_bounds;
+            local_from_gradient = compose(local_from_gradient, AffineTransformation::new(
+                LinearTransformation::scaling(Vector::new(bounds.size.x, bounds.size.y)),
+                Vector::new(bounds.pos.x, bounds.pos.y)
+            ));
+        }
+        if let Some(transform) = self.gradient_attr(id, GRADIENT_TRANSFORM) {
+            local_from_gradient = compose(local_from_gradient, parse_transform(transform));
+        }
+        let gradient_from_doc = invert(local_from_gradient)?;
+        if radial {
+            let cx = length(live_id!(cx), size.x, 0.5 * size.x);
+            let cy = length(live_id!(cy), size.y, 0.5 * size.y);
+            let r = length(live_id!(r), diagonal, 0.5 * diagonal);
+            if r <= 0.0 {
+                return Some(SvgPaint::Color(stops.last()?.color))
+            }
+            let unit_from_gradient = AffineTransformation::new(
+                LinearTransformation::uniform_scaling(1.0 / r),
+                Vector::new(-cx / r, -cy / r)
+            );
+            Some(SvgPaint::RadialGradient(SvgGradient {
+                transform: compose(unit_from_gradient, gradient_from_doc),
+                stops
+            }))
+        }
+        else {
+            let x1 = length(live_id!(x1), size.x, 0.0);
+            let y1 = length(live_id!(y1), size.y, 0.0);
+            let x2 = length(live_id!(x2), size.x, size.x);
+            let y2 = length(live_id!(y2), size.y, 0.0);
+            let d = Vector::new(x2 - x1, y2 - y1);
+            let l2 = d.dot(d);
+            if l2 <= 0.0 {
+                return Some(SvgPaint::Color(stops.last()?.color))
+            }
+            // projects onto the gradient vector, so that x runs from 0 at (x1, y1) to 1 at (x2, y2)
+            let unit_from_gradient = AffineTransformation::new(
+                LinearTransformation::new(Vector::new(d.x / l2, 0.0), Vector::new(d.y / l2, 0.0)),
+                Vector::new(-(x1 * d.x + y1 * d.y) / l2, 0.0)
+            );
+            Some(SvgPaint::LinearGradient(SvgGradient {
+                transform: compose(unit_from_gradient, gradient_from_doc),
+                stops
+            }))
+        }
+    }
+}
+
+// Returns the transformation that applies `b` and then `a`.
+fn compose(a: AffineTransformation, b: AffineTransformation) -> AffineTransformation {
+    AffineTransformation::new(a.xy.compose(b.xy), a.transform_vector(b.z) + a.z)
+}
+
+fn invert(t: AffineTransformation) -> Option<AffineTransformation> {
+    let det = t.xy.x.cross(t.xy.y);
+    if det == 0.0 || !det.is_finite() {
+        return None
+    }
+    let xy = LinearTransformation::new(
+        Vector::new(t.xy.y.y / det, -t.xy.x.y / det),
+        Vector::new(-t.xy.y.x / det, t.xy.x.x / det)
+    );
+    Some(AffineTransformation::new(xy, -xy.transform_vector(t.z)))
+}
+
+fn arcs_to_cubics(path: &[PathCommand]) -> Vec<PathCommand> {
+    let mut out = Vec::with_capacity(path.len());
+    let mut initial_point = Point::origin();
+    let mut current_point = Point::origin();
+    for command in path {
+        match *command {
+            PathCommand::MoveTo(p) => {
+                initial_point = p;
+                current_point = p;
+            }
+            PathCommand::LineTo(p) | PathCommand::QuadraticTo(_, p) | PathCommand::CubicTo(_, _, p) => {
+                current_point = p;
+            }
+            PathCommand::ArcTo(e, r, xr, l, s) => {
+                for cubic in Arc::new(current_point, e, r, xr, l, s).to_cubics() {
+                    out.push(PathCommand::CubicTo(cubic.p1, cubic.p2, cubic.p3));
+                }
+                current_point = e;
+                continue;
+            }
+            PathCommand::Close => {
+                current_point = initial_point;
+            }
+        }
+        out.push(*command);
+    }
+    out
+}
+
+fn control_point_bounds(path: &[PathCommand]) -> Option<Rect> {
+    let mut bounds = PointBounds::default();
+    for command in path {
+        match *command {
+            PathCommand::MoveTo(p) | PathCommand::LineTo(p) => bounds.add(p),
+            PathCommand::ArcTo(e, _, _, _, _) => bounds.add(e),
+            PathCommand::QuadraticTo(p1, p) => {
+                bounds.add(p1);
+                bounds.add(p);
+            }
+            PathCommand::CubicTo(p1, p2, p) => {
+                bounds.add(p1);
+                bounds.add(p2);
+                bounds.add(p);
+            }
+            PathCommand::Close => ()
+        }
+    }
+    bounds.rect()
+}
+
+fn line_path_bounds<P: LinePathIterator>(path: P) -> Option<Rect> {
+    let mut bounds = PointBounds::default();
+    // `InternalIterator` is not imported, since its methods would be ambiguous with those of
+    // `Iterator` in this module
+    makepad_vector::internal_iter::InternalIterator::for_each(path, &mut |command| {
+        match command {
+            LinePathCommand::MoveTo(p) | LinePathCommand::LineTo(p) => bounds.add(p),
+            LinePathCommand::Close => ()
+        }
+        true
+    });
+    bounds.rect()
+}
+
+#[derive(Default)]
+struct PointBounds {
+    min_max: Option<(DVec2, DVec2)>,
+}
+
+impl PointBounds {
+    fn add(&mut self, p: Point) {
+        if !p.x.is_finite() || !p.y.is_finite() {
+            return
+        }
+        let p = dvec2(p.x, p.y);
+        self.min_max = Some(match self.min_max {
+            Some((min, max)) => (dvec2(min.x.min(p.x), min.y.min(p.y)), dvec2(max.x.max(p.x), max.y.max(p.y))),
+            None => (p, p)
+        });
+    }
+
+    fn rect(&self) -> Option<Rect> {
+        self.min_max.map(|(min, max)| Rect {pos: min, size: max - min})
+    }
+}
+
+// Parses a list of numbers that are separated by whitespace, commas, or nothing at all when the
+// next number starts with a sign or a second decimal point, as in `1.5.5-2`.
+fn parse_numbers(s: &str) -> Vec<f64> {
+    let bytes = s.as_bytes();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        if bytes[i] == b'+' || bytes[i] == b'-' {
+            i += 1;
+        }
+        let mut has_dot = false;
+        let mut has_digits = false;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'0'..=b'9' => has_digits = true,
+                b'.' if !has_dot => has_dot = true,
+                b'e' | b'E' if has_digits => {
+                    if i + 1 < bytes.len() && (bytes[i + 1] == b'+' || bytes[i + 1] == b'-') {
+                        i += 1;
+                    }
+                }
+                _ => break
+            }
+            i += 1;
+        }
+        if has_digits {
+            if let Ok(number) = s[start..i].parse() {
+                numbers.push(number);
+            }
+        }
+        else {
+            i = start + 1;
+        }
+    }
+    numbers
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if let Some(percentage) = s.strip_suffix('%') {
+        return parse_numbers(percentage).first().map(|v| v / 100.0)
+    }
+    parse_numbers(s).first().copied()
+}
+
+// Parses a length, resolving percentages against `reference`. Units are ignored.
+fn parse_length(s: &str, reference: f64) -> Option<f64> {
+    let s = s.trim();
+    if let Some(percentage) = s.strip_suffix('%') {
+        return parse_numbers(percentage).first().map(|v| v / 100.0 * reference)
+    }
+    parse_numbers(s).first().copied()
+}
+
+fn parse_transform(s: &str) -> AffineTransformation {
+    let mut transform = AffineTransformation::identity();
+    for part in s.split(')') {
+        let Some((name, args)) = part.split_once('(') else {
+            continue
+        };
+        let args = parse_numbers(args);
+        let arg = |index: usize, default: f64| args.get(index).copied().unwrap_or(default);
+        let local = match name.trim_matches(|c: char| c.is_whitespace() || c == ',') {
+            "matrix" if args.len() == 6 => AffineTransformation::new(
+                LinearTransformation::new(Vector::new(args[0], args[1]), Vector::new(args[2], args[3])),
+                Vector::new(args[4], args[5])
+            ),
+            "translate" => AffineTransformation::translation(Vector::new(arg(0, 0.0), arg(1, 0.0))),
+            "scale" => AffineTransformation::scaling(Vector::new(arg(0, 1.0), arg(1, arg(0, 1.0)))),
+            "rotate" => {
+                let (sin, cos) = (arg(0, 0.0) * PI / 180.0).sin_cos();
+                let center = Vector::new(arg(1, 0.0), arg(2, 0.0));
+                let rotation = LinearTransformation::new(Vector::new(cos, sin), Vector::new(-sin, cos));
+                // rotates around the center
+                AffineTransformation::new(rotation, center - rotation.transform_vector(center))
+            }
+            "skewX" => AffineTransformation::new(
+                LinearTransformation::new(Vector::new(1.0, 0.0), Vector::new((arg(0, 0.0) * PI / 180.0).tan(), 1.0)),
+                Vector::zero()
+            ),
+            "skewY" => AffineTransformation::new(
+                LinearTransformation::new(Vector::new(1.0, (arg(0, 0.0) * PI / 180.0).tan()), Vector::new(0.0, 1.0)),
+                Vector::zero()
+            ),
+            _ => continue
+        };
+        transform = compose(transform, local);
+    }
+    transform
+}
+
+// Parses a paint, returning `None` for values that should not override the inherited paint.
+fn parse_paint(s: &str) -> Option<Paint> {
+    let s = s.trim();
+    if s == "none" {
+        return Some(Paint::None)
+    }
+    if s.eq_ignore_ascii_case("currentcolor") {
+        return Some(Paint::CurrentColor)
+    }
+    if let Some(url) = s.strip_prefix("url(") {
+        let id = url.split(')').next().unwrap_or("");
+        let id = id.trim().trim_matches(|c| c == '\'' || c == '"').trim_start_matches('#');
+        return Some(Paint::Url(id.to_string()))
+    }
+    parse_color(s).map(Paint::Color)
+}
+
+fn parse_color(s: &str) -> Option<Vec4> {
+    if let Some(hex) = s.strip_prefix('#') {
+        let digits: Vec<u32> = hex.chars().map(|c| c.to_digit(16)).collect::<Option<_>>()?;
+        let value = match digits.len() {
+            3 | 4 => {
+                let mut value = 0;
+                for digit in &digits {
+                    value = value << 8 | digit * 17;
+                }
+                if digits.len() == 3 {value << 8 | 0xff} else {value}
+            }
+            6 | 8 => {
+                let mut value = 0;
+                for digit in &digits {
+                    value = value << 4 | digit;
+                }
+                if digits.len() == 6 {value << 8 | 0xff} else {value}
+            }
+            _ => return None
+        };
+        return Some(Vec4::from_u32(value))
+    }
+    let lower = s.to_ascii_lowercase();
+    if let Some(args) = lower.strip_prefix("rgba(").or(lower.strip_prefix("rgb(")) {
+        let args = args.trim_end_matches(')');
+        let mut channels = [0.0f32, 0.0, 0.0, 1.0];
+        for (index, arg) in args.split(|c| c == ',' || c == '/').enumerate().take(4) {
+            let arg = arg.trim();
+            let value = if let Some(percentage) = arg.strip_suffix('%') {
+                parse_numbers(percentage).first()? / 100.0
+            }
+            else if index < 3 {
+                parse_numbers(arg).first()? / 255.0
+            }
+            else {
+                *parse_numbers(arg).first()?
+            };
+            channels[index] = value.clamp(0.0, 1.0) as f32;
+        }
+        return Some(vec4(channels[0], channels[1], channels[2], channels[3]))
+    }
+    let value = match lower.as_str() {
+        "transparent" => 0x00000000,
+        "black" => 0x000000ff,
+        "white" => 0xffffffff,
+        "red" => 0xff0000ff,
+        "green" => 0x008000ff,
+        "lime" => 0x00ff00ff,
+        "blue" => 0x0000ffff,
+        "yellow" => 0xffff00ff,
+        "cyan" | "aqua" => 0x00ffffff,
+        "magenta" | "fuchsia" => 0xff00ffff,
+        "gray" | "grey" => 0x808080ff,
+        "silver" => 0xc0c0c0ff,
+        "maroon" => 0x800000ff,
+        "olive" => 0x808000ff,
+        "teal" => 0x008080ff,
+        "navy" => 0x000080ff,
+        "purple" => 0x800080ff,
+        "orange" => 0xffa500ff,
+        "pink" => 0xffc0cbff,
+        "brown" => 0xa52a2aff,
+        "gold" => 0xffd700ff,
+        "darkgray" | "darkgrey" => 0xa9a9a9ff,
+        "lightgray" | "lightgrey" => 0xd3d3d3ff,
+        _ => return None
+    };
+    Some(Vec4::from_u32(value))
+}
@@ -2,7 +2,11 @@ use {
     crate::{
         makepad_platform::*,
         cx_2d::{Cx2d},
-    }
+    },
+    std::{
+        cell::{Ref, RefCell},
+        sync::Mutex,
+    },
 };
 
 #[derive(Copy, Clone, Debug, Live, LiveHook, LiveRegister)]
@@ -40,6 +44,26 @@ pub struct Walk {
     #[live] pub margin: Margin,
     #[live] pub width: Size,
     #[live] pub height: Size,
+    #[live] pub min_width: Option<f64>,
+    #[live] pub max_width: Option<f64>,
+    #[live] pub min_height: Option<f64>,
+    #[live] pub max_height: Option<f64>,
+    /// Width divided by height. An axis that fits its content is derived from
+    /// the other one, when both are known the size shrinks to the ratio.
+    #[live] pub aspect_ratio: Option<f64>,
+    #[live] pub grid_row: GridSpan,
+    #[live] pub grid_column: GridSpan,
+}
+
+/// Where a child goes in a `Flow::Grid`. `start` counts tracks from 1 like CSS does,
+/// 0 places the child in the next free cell. A `span` of 0 is the same as 1.
+/// `align` places the child in its cell along this axis instead of the grid's `align`.
+#[derive(Clone, Copy, Default, Debug, Live, LiveHook, LiveRegister, PartialEq)]
+#[live_ignore]
+pub struct GridSpan {
+    #[live] pub start: usize,
+    #[live] pub span: usize,
+    #[live] pub align: Option<f64>,
 }
 
 #[derive(Clone, Copy, Default, Debug, Live, LiveHook, LiveRegister)]
@@ -79,7 +103,109 @@ pub enum Flow {
     //Left,
     //Up,
     Overlay, 
-    RightWrap
+    RightWrap,
+    // lays children out in cells. children without a grid_row/grid_column fill
+    // the cells row by row, rows past the defined ones fit their content
+    #[live {columns: GridTracks::default(), rows: GridTracks::default(), column_gap: 0.0, row_gap: 0.0}]
+    Grid {columns: GridTracks, rows: GridTracks, column_gap: f64, row_gap: f64}
+}
+
+#[derive(Copy, Clone, Debug, Live, LiveHook, PartialEq)]
+#[live_ignore]
+pub enum GridTrack {
+    #[pick] Fit,
+    #[live(100.0)] Fixed(f64),
+    // a share of the space the other tracks leave, behaves like Fit when the
+    // grid itself fits its content
+    #[live(1.0)] Fr(f64),
+}
+
+pub const GRID_MAX_TRACKS: usize = 16;
+
+/// The track list of a grid. The tracks are stored out of line, in a list shared by
+/// every grid with the same tracks, so `Layout` stays `Copy` and small.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GridTracks {
+    tracks: &'static [GridTrack],
+}
+
+/// Every distinct track list that has been used, they live as long as the program.
+static GRID_TRACK_LISTS: Mutex<Vec<&'static [GridTrack]>> = Mutex::new(Vec::new());
+
+impl GridTracks {
+    pub fn new(tracks: &[GridTrack]) -> Self {
+        if tracks.is_empty() {
+            return Self::default()
+        }
+        let tracks = if tracks.len() > GRID_MAX_TRACKS {
+            error!("a grid supports at most {} tracks", GRID_MAX_TRACKS);
+            &tracks[0..GRID_MAX_TRACKS]
+        }
+        else {
+            tracks
+        };
+        let mut lists = GRID_TRACK_LISTS.lock().unwrap();
+        if let Some(list) = lists.iter().find( | list | **list == tracks) {
+            return Self {tracks: list}
+        }
+        let list: &'static [GridTrack] = Box::leak(tracks.into());
+        lists.push(list);
+        Self {tracks: list}
+    }
+    
+    pub fn push(&mut self, track: GridTrack) {
+        let mut tracks = self.tracks.to_vec();
+        tracks.push(track);
+        *self = Self::new(&tracks);
+    }
+    
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+    
+    pub fn get(&self, index: usize) -> Option<GridTrack> {
+        self.tracks.get(index).copied()
+    }
+    
+    pub fn as_slice(&self) -> &[GridTrack] {
+        self.tracks
+    }
+}
+
+impl LiveHook for GridTracks {}
+impl LiveApply for GridTracks {
+    fn apply(&mut self, cx: &mut Cx, apply: &mut Apply, index: usize, nodes: &[LiveNode]) -> usize {
+        if !nodes[index].is_array() {
+            // a single track
+            let mut track = GridTrack::Fit;
+            let index = track.apply(cx, apply, index, nodes);
+            *self = Self::new(&[track]);
+            return index
+        }
+        let mut tracks = Vec::new();
+        let mut index = index + 1;
+        while !nodes[index].is_close() {
+            let mut track = GridTrack::Fit;
+            index = track.apply(cx, apply, index, nodes);
+            tracks.push(track);
+        }
+        *self = Self::new(&tracks);
+        index + 1
+    }
+}
+
+impl LiveNew for GridTracks {
+    fn new(_cx: &mut Cx) -> Self {
+        Self::default()
+    }
+    
+    fn live_type_info(cx: &mut Cx) -> LiveTypeInfo {
+        GridTrack::live_type_info(cx)
+    }
 }
 
 #[derive(Copy, Clone, Debug, Live)]
@@ -95,8 +221,7 @@ pub enum Size {
 pub enum DeferWalk{
     Unresolved{
         defer_index: usize,
-        margin: Margin,
-        other_axis: Size,
+        walk: Walk,
         pos: DVec2
     },
     Resolved(Walk)
@@ -116,9 +241,43 @@ pub enum AlignEntry{
 pub struct TurtleWalk {
     align_start: usize,
     defer_index: usize,
+    grid_cell: Option<GridCell>,
     rect: Rect,
 }
 
+#[derive(Clone, Copy, Default, Debug)]
+struct GridCell {
+    row: usize,
+    column: usize,
+    row_span: usize,
+    column_span: usize,
+    row_align: Option<f64>,
+    column_align: Option<f64>,
+}
+
+/// Placement and content sizes of the children of a `Flow::Grid` turtle. Children
+/// are walked where their cell is at that moment and moved to where it ends up
+/// when the turtle ends, like alignment does.
+#[derive(Clone, Default, Debug)]
+struct TurtleGrid {
+    occupied: Vec<bool>,
+    cursor: (usize, usize),
+    column_content: Vec<f64>,
+    row_content: Vec<f64>,
+    spanning: Vec<(GridCell, DVec2)>,
+    deferred: Vec<GridCell>,
+    /// The track sizes, kept until a child changes them or the grid gets another size.
+    sizes: RefCell<Option<GridTrackSizes>>,
+}
+
+#[derive(Clone, Debug)]
+struct GridTrackSizes {
+    row_count: usize,
+    available: DVec2,
+    columns: Vec<f64>,
+    rows: Vec<f64>,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct Turtle {
     walk: Walk,
@@ -134,7 +293,8 @@ pub struct Turtle {
     height: f64,
     width_used: f64,
     height_used: f64,
-    guard_area: Area
+    guard_area: Area,
+    grid: TurtleGrid,
 }
 
 impl<'a> Cx2d<'a> {
@@ -157,10 +317,7 @@ impl<'a> Cx2d<'a> {
         let turtle = self.turtles.last_mut().unwrap();
         let defer_index = turtle.defer_count;
         let pos = turtle.pos;
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk_size(&walk);
         let margin_size = walk.margin.size();
         match turtle.layout.flow {
             Flow::Right if walk.width.is_fill() => {
@@ -171,8 +328,7 @@ impl<'a> Cx2d<'a> {
                 turtle.defer_count += 1;
                 Some(DeferWalk::Unresolved{
                     defer_index,
-                    margin: walk.margin,
-                    other_axis: walk.height,
+                    walk,
                     pos: pos + spacing
                 })
             },
@@ -184,8 +340,7 @@ impl<'a> Cx2d<'a> {
                 turtle.defer_count += 1;
                 Some(DeferWalk::Unresolved {
                     defer_index,
-                    margin: walk.margin,
-                    other_axis: walk.width,
+                    walk,
                     pos: pos + spacing
                 })
             },
@@ -193,6 +348,17 @@ impl<'a> Cx2d<'a> {
                 error!("flow RightWrap does not support fill childnodes");
                 None
             },
+            Flow::Grid{..} if !turtle.grid_fill_is_final(&walk) => {
+                // reserve the cell, its size is known once the other children are walked
+                let cell = turtle.grid_place(&walk);
+                turtle.grid.deferred.push(cell);
+                turtle.defer_count += 1;
+                Some(DeferWalk::Unresolved{
+                    defer_index,
+                    walk,
+                    pos
+                })
+            }
             _ => {
                 None
             }
@@ -225,6 +391,7 @@ impl<'a> Cx2d<'a> {
             width_used: layout.padding.left,
             height_used: layout.padding.top,
             guard_area: Area::Empty,
+            grid: TurtleGrid::default(),
         };
         self.turtles.push(turtle);
     }
@@ -267,12 +434,16 @@ impl<'a> Cx2d<'a> {
     pub fn begin_turtle_with_guard(&mut self, walk: Walk, layout: Layout, guard_area: Area) {
         let (origin, width, height, draw_clip) = if let Some(parent) = self.turtles.last() {
             
-            let o = walk.margin.left_top() + if let Some(pos) = walk.abs_pos {pos} else {
+            let o = walk.margin.left_top() + if let Some(pos) = walk.abs_pos {pos} 
+            else if let Flow::Grid{..} = parent.layout.flow {
+                parent.grid_cell_rect(parent.grid_peek(&walk)).pos
+            }
+            else {
                 parent.pos + parent.child_spacing(self.turtle_walks.len())
             };
             
-            let w = parent.eval_width(walk.width, walk.margin, parent.layout.flow);
-            let h = parent.eval_height(walk.height, walk.margin, parent.layout.flow);
+            let size = parent.eval_walk_size(&walk);
+            let (w, h) = (size.x, size.y);
             
             // figure out new clipping rect
             let (x0, x1) = if layout.clip_x {
@@ -298,8 +469,8 @@ impl<'a> Cx2d<'a> {
         }
         else {
             let o = DVec2 {x: walk.margin.left, y: walk.margin.top};
-            let w = walk.width.fixed_or_nan();
-            let h = walk.height.fixed_or_nan();
+            let size = walk.constrain_size(dvec2(walk.width.fixed_or_nan(), walk.height.fixed_or_nan()));
+            let (w, h) = (size.x, size.y);
             
             (o, w, h, (dvec2(o.x, o.y), dvec2(o.x + w, o.y + h)))
         };
@@ -322,6 +493,7 @@ impl<'a> Cx2d<'a> {
             width_used: layout.padding.left,
             height_used: layout.padding.top,
             guard_area,
+            grid: TurtleGrid::default(),
         };
         
         self.turtles.push(turtle);
@@ -349,29 +521,49 @@ impl<'a> Cx2d<'a> {
         let turtle_align_start = turtle.align_start;
         let turtle_abs_pos = turtle.walk.abs_pos;
         let turtle_margin = turtle.walk.margin;
+        let turtle_grid = (turtle.walk.grid_row, turtle.walk.grid_column);
         let turtle_walks_start = turtle.turtle_walks_start;
         let turtle_shift = turtle.shift;
-                
+        
         // computed width / height
-        let w = if turtle.width.is_nan() {
-            let w = turtle.width_used + turtle.layout.padding.right - turtle.layout.scroll.x;
+        let fit_w = turtle.width.is_nan();
+        let fit_h = turtle.height.is_nan();
+        let used = dvec2(
+            turtle.width_used + turtle.layout.padding.right - turtle.layout.scroll.x,
+            turtle.height_used + turtle.layout.padding.bottom - turtle.layout.scroll.y
+        );
+        let fit_size = turtle.walk.constrain_fit_size(dvec2(
+            if fit_w {used.x} else {turtle.width},
+            if fit_h {used.y} else {turtle.height}
+        ), fit_w, fit_h);
+        
+        // a constrained fit turtle aligns its children in the size it ends up with
+        let turtle = self.turtles.last_mut().unwrap();
+        if fit_w && fit_size.x != used.x {
+            turtle.width = fit_size.x;
+        }
+        if fit_h && fit_size.y != used.y {
+            turtle.height = fit_size.y;
+        }
+        let turtle = self.turtles.last().unwrap();
+        
+        let w = if fit_w {
             // we should update the clip pos
             if let AlignEntry::BeginTurtle(p1,p2) = &mut self.align_list[turtle_align_start]{
-                p2.x = p1.x + w;
+                p2.x = p1.x + fit_size.x;
             }
-            Size::Fixed(w)
+            Size::Fixed(fit_size.x)
         }
         else {
             Size::Fixed(turtle.width)
         };
         
-        let h = if turtle.height.is_nan() {
-            let h =  turtle.height_used + turtle.layout.padding.bottom - turtle.layout.scroll.y;
+        let h = if fit_h {
             // we should update the clip pos
             if let AlignEntry::BeginTurtle(p1,p2) = &mut self.align_list[turtle_align_start]{
-                p2.y = p1.y + h;
+                p2.y = p1.y + fit_size.y;
             }
-            Size::Fixed(h)
+            Size::Fixed(fit_size.y)
         }
         else {
             Size::Fixed(turtle.height)
@@ -446,6 +638,26 @@ impl<'a> Cx2d<'a> {
                     }
                 }
             },
+            Flow::Grid{..} => {
+                let sizes = turtle.grid_track_sizes(0);
+                let align = turtle.layout.align;
+                let mut shifts = Vec::new();
+                for i in turtle_walks_start..self.turtle_walks.len() {
+                    let walk = &self.turtle_walks[i];
+                    if let Some(cell) = walk.grid_cell {
+                        let rect = turtle.grid_cell_rect_in(cell, &sizes.columns, &sizes.rows);
+                        let shift = rect.pos - walk.rect.pos + dvec2(
+                            cell.column_align.unwrap_or(align.x) * (rect.size.x - walk.rect.size.x),
+                            cell.row_align.unwrap_or(align.y) * (rect.size.y - walk.rect.size.y)
+                        );
+                        shifts.push((shift, walk.align_start, self.get_turtle_walk_align_end(i)));
+                    }
+                }
+                drop(sizes);
+                for (shift, align_start, align_end) in shifts {
+                    self.move_align_list(shift.x, shift.y, align_start, align_end, false, turtle_shift);
+                }
+            }
             Flow::Overlay => {
                 let align_x = turtle.layout.align.x;
                 let align_y = turtle.layout.align.y;
@@ -472,7 +684,15 @@ impl<'a> Cx2d<'a> {
                 size: dvec2(w.fixed_or_zero(), h.fixed_or_zero())
             }
         }
-        let rect = self.walk_turtle_move(Walk {width: w, height: h, abs_pos:turtle_abs_pos, margin:turtle_margin}, turtle_align_start);
+        let rect = self.walk_turtle_move(Walk {
+            width: w,
+            height: h,
+            abs_pos: turtle_abs_pos,
+            margin: turtle_margin,
+            grid_row: turtle_grid.0,
+            grid_column: turtle_grid.1,
+            ..Default::default()
+        }, turtle_align_start);
         rect
     }
     
@@ -519,6 +739,9 @@ impl<'a> Cx2d<'a> {
         }
         else {
             let turtle = self.turtles.last().unwrap();
+            if let Flow::Grid{..} = turtle.layout.flow {
+                return turtle.grid_cell_rect(turtle.grid_peek(&walk)).pos + walk.margin.left_top()
+            }
             turtle.pos + walk.margin.left_top()
         }
     }
@@ -526,22 +749,20 @@ impl<'a> Cx2d<'a> {
      fn walk_turtle_move(&mut self, walk: Walk, align_start: usize) -> Rect {
         
         let turtle = self.turtles.last_mut().unwrap();
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk_size(&walk);
         
         if let Some(pos) = walk.abs_pos {
             self.turtle_walks.push(TurtleWalk {
                 align_start,
                 defer_index: 0,
+                grid_cell: None,
                 rect: Rect {pos, size: size + walk.margin.size()}
             });
             
             match turtle.layout.flow {
                 Flow::Right=>turtle.update_height_max(pos.y, size.y + walk.margin.size().y),
                 Flow::Down=>turtle.update_width_max(pos.x, size.x + walk.margin.size().x),
-                Flow::Overlay => { // do not walk
+                Flow::Overlay | Flow::Grid{..} => { // do not walk
                     turtle.update_width_max(pos.x, size.x);
                     turtle.update_height_max(pos.y,size.y);
                 }
@@ -556,6 +777,7 @@ impl<'a> Cx2d<'a> {
             let mut pos = turtle.pos;
             let margin_size = walk.margin.size();
             let defer_index = turtle.defer_count;
+            let mut grid_cell = None;
             match turtle.layout.flow {
                 Flow::Right => {
                     turtle.pos.x = pos.x + size.x + margin_size.x + spacing.x;
@@ -612,11 +834,19 @@ impl<'a> Cx2d<'a> {
                     turtle.update_width_max(turtle.pos.x, size.x);
                     turtle.update_height_max(turtle.pos.y,size.y);
                 }
+                Flow::Grid{..} => {
+                    // the position from before measuring is the one the child began at
+                    let cell = turtle.grid_place(&walk);
+                    pos = turtle.grid_cell_rect(cell).pos;
+                    turtle.grid_measure(cell, size + margin_size);
+                    grid_cell = Some(cell);
+                }
             };
             
             self.turtle_walks.push(TurtleWalk {
                 align_start,
                 defer_index,
+                grid_cell,
                 rect: Rect {pos, size: size + margin_size}
             });
             Rect {pos: pos + walk.margin.left_top() + spacing, size}
@@ -628,14 +858,15 @@ impl<'a> Cx2d<'a> {
             return Rect::default()
         }
         let turtle = self.turtles.last().unwrap();
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk_size(&walk);
         
         if let Some(pos) = walk.abs_pos {
             Rect {pos: pos + walk.margin.left_top(), size}
         }
+        else if let Flow::Grid{..} = turtle.layout.flow {
+            let pos = turtle.grid_cell_rect(turtle.grid_peek(&walk)).pos;
+            Rect {pos: pos + walk.margin.left_top(), size}
+        }
         else {
             let spacing = turtle.child_spacing(self.turtle_walks.len());
             let pos = turtle.pos;
@@ -875,7 +1106,7 @@ impl Turtle {
                 Flow::Down => {
                     dvec2(0.0, self.layout.spacing)
                 }
-                Flow::Overlay => {
+                Flow::Overlay | Flow::Grid{..} => {
                     dvec2(0.0, 0.0)
                 }
                 Flow::RightWrap=>{
@@ -939,6 +1170,9 @@ impl Turtle {
                         }
                         return r
                     }
+                    Flow::Grid{..} => {
+                        self.grid_cell_inner_size(&Walk {margin, ..Walk::default()}).x
+                    }
                 }
             },
             Size::All=>self.width
@@ -961,12 +1195,156 @@ impl Turtle {
                     Flow::Down => {
                         max_zero_keep_nan(self.height_left() - margin.height())
                     }
+                    Flow::Grid{..} => {
+                        self.grid_cell_inner_size(&Walk {margin, ..Walk::default()}).y
+                    }
                 }
             }
             Size::All=>self.height
         }
     }
     
+    /// Evaluates both axes of `walk` in this turtle and applies its size constraints.
+    pub fn eval_walk_size(&self, walk: &Walk) -> DVec2 {
+        let is_fill = walk.width.is_fill() || walk.height.is_fill();
+        if let (Flow::Grid{..}, None, true) = (self.layout.flow, walk.abs_pos, is_fill) {
+            // fill the cell the child is placed in
+            let cell = self.grid_cell_inner_size(walk);
+            return walk.constrain_size(dvec2(
+                if walk.width.is_fill() {cell.x} else {self.eval_width(walk.width, walk.margin, self.layout.flow)},
+                if walk.height.is_fill() {cell.y} else {self.eval_height(walk.height, walk.margin, self.layout.flow)}
+            ))
+        }
+        walk.constrain_size(dvec2(
+            self.eval_width(walk.width, walk.margin, self.layout.flow),
+            self.eval_height(walk.height, walk.margin, self.layout.flow)
+        ))
+    }
+    
+    fn grid_column_count(&self) -> usize {
+        match self.layout.flow {
+            Flow::Grid{columns, ..} => columns.len().max(1),
+            _ => 1
+        }
+    }
+    
+    fn grid_peek(&self, walk: &Walk) -> GridCell {
+        self.grid.peek_cell(self.grid_column_count(), walk)
+    }
+    
+    fn grid_place(&mut self, walk: &Walk) -> GridCell {
+        let cell = self.grid.place_cell(self.grid_column_count(), walk);
+        self.update_grid_used();
+        cell
+    }
+    
+    fn grid_measure(&mut self, cell: GridCell, size: DVec2) {
+        self.grid.measure(cell, size);
+        self.update_grid_used();
+    }
+    
+    /// Keeps the used size in line with the tracks, scroll bars read it before the turtle ends.
+    fn update_grid_used(&mut self) {
+        let Flow::Grid{column_gap, row_gap, ..} = self.layout.flow else {return};
+        let sizes = self.grid_track_sizes(0);
+        let width = grid_span_size(&sizes.columns, 0, sizes.columns.len(), column_gap);
+        let height = grid_span_size(&sizes.rows, 0, sizes.rows.len(), row_gap);
+        drop(sizes);
+        self.width_used = self.width_used.max(self.layout.padding.left + width);
+        self.height_used = self.height_used.max(self.layout.padding.top + height);
+    }
+    
+    /// A child with a fill axis can be walked right away when the tracks it fills are fixed.
+    fn grid_fill_is_final(&self, walk: &Walk) -> bool {
+        if walk.abs_pos.is_some() {
+            return true
+        }
+        let Flow::Grid{columns, rows, ..} = self.layout.flow else {return true};
+        let cell = self.grid_peek(walk);
+        let fixed = | tracks: &GridTracks, start: usize, span: usize | {
+            (start..start + span).all( | i | matches!(tracks.get(i), Some(GridTrack::Fixed(_))))
+        };
+        (!walk.width.is_fill() || fixed(&columns, cell.column, cell.column_span)) &&
+        (!walk.height.is_fill() || fixed(&rows, cell.row, cell.row_span))
+    }
+    
+    /// The sizes of the column and row tracks from what has been walked so far,
+    /// with at least `min_rows` rows. They are only recomputed when they changed.
+    fn grid_track_sizes(&self, min_rows: usize) -> Ref<'_, GridTrackSizes> {
+        let rows = match self.layout.flow {
+            Flow::Grid{rows, ..} => rows.len(),
+            _ => 0
+        };
+        let row_count = rows.max(self.grid.row_count(self.grid_column_count())).max(min_rows);
+        let available = dvec2(self.width - self.layout.padding.width(), self.height - self.layout.padding.height());
+        let same = | a: f64, b: f64 | a == b || a.is_nan() && b.is_nan();
+        let is_current = self.grid.sizes.borrow().as_ref().is_some_and( | sizes | {
+            sizes.row_count == row_count && same(sizes.available.x, available.x) && same(sizes.available.y, available.y)
+        });
+        if !is_current {
+            *self.grid.sizes.borrow_mut() = Some(self.compute_grid_track_sizes(row_count, available));
+        }
+        Ref::map(self.grid.sizes.borrow(), | sizes | sizes.as_ref().unwrap())
+    }
+    
+    fn compute_grid_track_sizes(&self, row_count: usize, available: DVec2) -> GridTrackSizes {
+        let Flow::Grid{columns, rows, column_gap, row_gap} = self.layout.flow else {
+            return GridTrackSizes {row_count, available, columns: Vec::new(), rows: Vec::new()}
+        };
+        let column_sizes = grid_track_sizes(
+            &columns,
+            GridTrack::Fr(1.0),
+            self.grid_column_count(),
+            &self.grid.column_content,
+            self.grid.spanning.iter().filter( | (cell, _) | cell.column_span > 1).map( | (cell, size) | (cell.column, cell.column_span, size.x)),
+            available.x,
+            column_gap
+        );
+        let row_sizes = grid_track_sizes(
+            &rows,
+            GridTrack::Fit,
+            row_count,
+            &self.grid.row_content,
+            self.grid.spanning.iter().filter( | (cell, _) | cell.row_span > 1).map( | (cell, size) | (cell.row, cell.row_span, size.y)),
+            available.y,
+            row_gap
+        );
+        GridTrackSizes {row_count, available, columns: column_sizes, rows: row_sizes}
+    }
+    
+    fn grid_cell_rect(&self, cell: GridCell) -> Rect {
+        let sizes = self.grid_track_sizes(cell.row + cell.row_span);
+        self.grid_cell_rect_in(cell, &sizes.columns, &sizes.rows)
+    }
+    
+    fn grid_cell_rect_in(&self, cell: GridCell, columns: &[f64], rows: &[f64]) -> Rect {
+        let Flow::Grid{column_gap, row_gap, ..} = self.layout.flow else {return Rect::default()};
+        Rect {
+            pos: self.origin + self.layout.padding.left_top() + dvec2(
+                grid_span_size(columns, 0, cell.column, column_gap) + if cell.column > 0 {column_gap} else {0.0},
+                grid_span_size(rows, 0, cell.row, row_gap) + if cell.row > 0 {row_gap} else {0.0}
+            ),
+            size: dvec2(
+                grid_span_size(columns, cell.column, cell.column_span, column_gap),
+                grid_span_size(rows, cell.row, cell.row_span, row_gap)
+            )
+        }
+    }
+    
+    /// The rect covering `row_span` rows and `column_span` columns of a `Flow::Grid`
+    /// turtle, with the tracks sized by what has been walked so far. Rows and
+    /// columns count from 0.
+    pub fn grid_area_rect(&self, row: usize, column: usize, row_span: usize, column_span: usize) -> Rect {
+        let column_span = column_span.max(1);
+        let row_span = row_span.max(1);
+        self.grid_cell_rect(GridCell {row, column, row_span, column_span, ..GridCell::default()})
+    }
+    
+    fn grid_cell_inner_size(&self, walk: &Walk) -> DVec2 {
+        let size = self.grid_cell_rect(self.grid_peek(walk)).size - walk.margin.size();
+        dvec2(size.x.max(0.0), size.y.max(0.0))
+    }
+    
     pub fn rect(&self) -> Rect {
         Rect {
             pos: self.origin,
@@ -1034,12 +1412,172 @@ impl Turtle {
     }
 }
 
+impl TurtleGrid {
+    fn row_count(&self, columns: usize) -> usize {
+        self.occupied.len() / columns
+    }
+    
+    fn is_free(&self, columns: usize, row: usize, column: usize, row_span: usize, column_span: usize) -> bool {
+        (row..row + row_span).all( | r | (column..column + column_span).all( | c | {
+            !self.occupied.get(r * columns + c).copied().unwrap_or(false)
+        }))
+    }
+    
+    /// The cell the next child with this walk ends up in. Auto placed children go
+    /// row by row from the last auto placed one, explicit starts past the last
+    /// column are clamped.
+    fn peek_cell(&self, columns: usize, walk: &Walk) -> GridCell {
+        let column_span = walk.grid_column.span.max(1).min(columns);
+        let row_span = walk.grid_row.span.max(1);
+        let explicit_column = (walk.grid_column.start > 0).then( || (walk.grid_column.start - 1).min(columns - column_span));
+        let explicit_row = (walk.grid_row.start > 0).then( || walk.grid_row.start - 1);
+        let (row, column) = match (explicit_row, explicit_column) {
+            (Some(row), Some(column)) => (row, column),
+            (Some(row), None) => {
+                let column = (0..=columns - column_span)
+                    .find( | c | self.is_free(columns, row, *c, row_span, column_span))
+                    .unwrap_or(0);
+                (row, column)
+            }
+            (None, Some(column)) => {
+                let mut row = self.cursor.0 + if column < self.cursor.1 {1} else {0};
+                while !self.is_free(columns, row, column, row_span, column_span) {
+                    row += 1;
+                }
+                (row, column)
+            }
+            (None, None) => {
+                let (mut row, mut column) = self.cursor;
+                loop {
+                    if column + column_span > columns {
+                        row += 1;
+                        column = 0;
+                    }
+                    else if self.is_free(columns, row, column, row_span, column_span) {
+                        break
+                    }
+                    else {
+                        column += 1;
+                    }
+                }
+                (row, column)
+            }
+        };
+        GridCell {
+            row,
+            column,
+            row_span,
+            column_span,
+            row_align: walk.grid_row.align,
+            column_align: walk.grid_column.align
+        }
+    }
+    
+    fn place_cell(&mut self, columns: usize, walk: &Walk) -> GridCell {
+        let cell = self.peek_cell(columns, walk);
+        let end = (cell.row + cell.row_span) * columns;
+        if self.occupied.len() < end {
+            self.occupied.resize(end, false);
+        }
+        for r in cell.row..cell.row + cell.row_span {
+            for c in cell.column..cell.column + cell.column_span {
+                self.occupied[r * columns + c] = true;
+            }
+        }
+        if walk.grid_row.start == 0 {
+            self.cursor = (cell.row, cell.column + cell.column_span);
+        }
+        cell
+    }
+    
+    fn measure(&mut self, cell: GridCell, size: DVec2) {
+        let mut changed = false;
+        if cell.column_span == 1 {
+            changed |= grow_track_content(&mut self.column_content, cell.column, size.x);
+        }
+        if cell.row_span == 1 {
+            changed |= grow_track_content(&mut self.row_content, cell.row, size.y);
+        }
+        if cell.column_span > 1 || cell.row_span > 1 {
+            self.spanning.push((cell, size));
+            changed = true;
+        }
+        if changed {
+            *self.sizes.get_mut() = None;
+        }
+    }
+}
+
+/// Returns whether the content size of the track grew.
+fn grow_track_content(content: &mut Vec<f64>, index: usize, size: f64) -> bool {
+    if content.len() <= index {
+        content.resize(index + 1, 0.0);
+    }
+    if size.is_nan() || size <= content[index] {
+        return false
+    }
+    content[index] = size;
+    true
+}
+
+fn grid_span_size(sizes: &[f64], start: usize, span: usize, gap: f64) -> f64 {
+    let end = (start + span).min(sizes.len());
+    if start >= end {
+        return 0.0
+    }
+    sizes[start..end].iter().sum::<f64>() + gap * (end - start - 1) as f64
+}
+
+/// Sizes one axis of a grid. Fit tracks take their largest child, children spanning
+/// several tracks grow the last fit track they cross, and fractional tracks share
+/// what is left of `available`, or fit their content when it is NaN.
+fn grid_track_sizes(tracks: &GridTracks, implicit: GridTrack, count: usize, content: &[f64], spanning: impl Iterator<Item = (usize, usize, f64)>, available: f64, gap: f64) -> Vec<f64> {
+    let track = | i: usize | tracks.get(i).unwrap_or(implicit);
+    let fits_content = | track: GridTrack | match track {
+        GridTrack::Fit => true,
+        GridTrack::Fr(_) => available.is_nan(),
+        GridTrack::Fixed(_) => false,
+    };
+    let mut sizes: Vec<f64> = (0..count).map( | i | match track(i) {
+        GridTrack::Fixed(v) => v.max(0.0),
+        t if fits_content(t) => content.get(i).copied().unwrap_or(0.0),
+        _ => 0.0
+    }).collect();
+    
+    for (start, span, size) in spanning {
+        let end = (start + span).min(count);
+        let have = grid_span_size(&sizes, start, span, gap);
+        if size > have {
+            if let Some(i) = (start..end).rev().find( | i | fits_content(track(*i))) {
+                sizes[i] += size - have;
+            }
+        }
+    }
+    
+    if !available.is_nan() {
+        let total_fr: f64 = (0..count).filter_map( | i | match track(i) {
+            GridTrack::Fr(fr) => Some(fr.max(0.0)),
+            _ => None
+        }).sum();
+        if total_fr > 0.0 {
+            let taken: f64 = (0..count).filter( | i | !matches!(track(*i), GridTrack::Fr(_))).map( | i | sizes[i]).sum();
+            let left = (available - taken - gap * count.saturating_sub(1) as f64).max(0.0);
+            for i in 0..count {
+                if let GridTrack::Fr(fr) = track(i) {
+                    sizes[i] = left * fr.max(0.0) / total_fr;
+                }
+            }
+        }
+    }
+    sizes
+}
+
 impl DeferWalk {
     
     pub fn resolve(&mut self, cx: &Cx2d) -> Walk {
         match self{
             Self::Resolved(walk)=>{*walk},
-            Self::Unresolved{pos, defer_index, margin, other_axis}=>{
+            Self::Unresolved{pos, defer_index, walk}=>{
                 let turtle = cx.turtles.last().unwrap();
                 let walk = match turtle.layout.flow {
                     Flow::Right => {
@@ -1047,9 +1585,8 @@ impl DeferWalk {
                         let part = left / turtle.defer_count as f64;
                         Walk {
                             abs_pos: Some(*pos + dvec2(part * *defer_index as f64, 0.)),
-                            margin: *margin,
                             width: Size::Fixed(part),
-                            height: *other_axis
+                            ..*walk
                         }
                     },
                     Flow::RightWrap => {
@@ -1060,12 +1597,23 @@ impl DeferWalk {
                         let part = left / turtle.defer_count as f64;
                         Walk {
                             abs_pos: Some(*pos + dvec2(0., part * *defer_index as f64)),
-                            margin: *margin,
                             height: Size::Fixed(part),
-                            width: *other_axis
+                            ..*walk
+                        }
+                    }
+                    Flow::Overlay => panic!(),
+                    Flow::Grid{..} => {
+                        // walk into the reserved cell, now that its tracks are known
+                        let cell = turtle.grid.deferred[*defer_index];
+                        let size = turtle.grid_cell_rect(cell).size - walk.margin.size();
+                        Walk {
+                            width: if walk.width.is_fill() {Size::Fixed(size.x.max(0.0))} else {walk.width},
+                            height: if walk.height.is_fill() {Size::Fixed(size.y.max(0.0))} else {walk.height},
+                            grid_row: GridSpan {start: cell.row + 1, span: cell.row_span, align: cell.row_align},
+                            grid_column: GridSpan {start: cell.column + 1, span: cell.column_span, align: cell.column_align},
+                            ..*walk
                         }
                     }
-                    Flow::Overlay => panic!()
                 };
                 *self = DeferWalk::Resolved(walk);
                 walk
@@ -1156,6 +1704,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(0.0),
             height: Size::Fixed(0.0),
+            ..Default::default()
         }
     }
     
@@ -1165,6 +1714,7 @@ impl Walk {
             margin: Margin::default(),
             width: w,
             height: h,
+            ..Default::default()
         }
     }
 
@@ -1174,6 +1724,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(rect.size.x),
             height: Size::Fixed(rect.size.y),
+            ..Default::default()
         }
     }
     
@@ -1183,6 +1734,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(w),
             height: Size::Fixed(h),
+            ..Default::default()
         }
     }
         
@@ -1192,6 +1744,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(size.x),
             height: Size::Fixed(size.y),
+            ..Default::default()
         }
     }
     
@@ -1201,6 +1754,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fit,
             height: Size::Fit,
+            ..Default::default()
         }
     }
     
@@ -1210,6 +1764,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fill,
             height: Size::Fill,
+            ..Default::default()
         }
     }
     
@@ -1219,6 +1774,7 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fill,
             height: Size::Fit,
+            ..Default::default()
         }
     }
     
@@ -1263,6 +1819,127 @@ impl Walk {
         self.margin.bottom += v.bottom;
         self
     }
+    
+    pub fn with_min_width(mut self, v: f64) -> Self {
+        self.min_width = Some(v);
+        self
+    }
+    
+    pub fn with_max_width(mut self, v: f64) -> Self {
+        self.max_width = Some(v);
+        self
+    }
+    
+    pub fn with_min_height(mut self, v: f64) -> Self {
+        self.min_height = Some(v);
+        self
+    }
+    
+    pub fn with_max_height(mut self, v: f64) -> Self {
+        self.max_height = Some(v);
+        self
+    }
+    
+    pub fn with_aspect_ratio(mut self, v: f64) -> Self {
+        self.aspect_ratio = Some(v);
+        self
+    }
+    
+    pub fn with_grid_row(mut self, start: usize, span: usize) -> Self {
+        self.grid_row = GridSpan {start, span, ..self.grid_row};
+        self
+    }
+    
+    pub fn with_grid_column(mut self, start: usize, span: usize) -> Self {
+        self.grid_column = GridSpan {start, span, ..self.grid_column};
+        self
+    }
+    
+    pub fn with_grid_align(mut self, x: Option<f64>, y: Option<f64>) -> Self {
+        self.grid_column.align = x;
+        self.grid_row.align = y;
+        self
+    }
+    
+    pub fn has_constraints(&self) -> bool {
+        self.min_width.is_some() || self.max_width.is_some() ||
+        self.min_height.is_some() || self.max_height.is_some() ||
+        self.aspect_ratio.is_some()
+    }
+    
+    /// Clamps a width between `min_width` and `max_width`, min wins when they cross.
+    /// A NaN width (fit, not known yet) stays NaN.
+    pub fn clamp_width(&self, w: f64) -> f64 {
+        clamp_min_max(w, self.min_width, self.max_width)
+    }
+    
+    pub fn clamp_height(&self, h: f64) -> f64 {
+        clamp_min_max(h, self.min_height, self.max_height)
+    }
+    
+    fn ratio(&self) -> Option<f64> {
+        self.aspect_ratio.filter( | r | *r > 0.0 && r.is_finite())
+    }
+    
+    /// Applies the constraints to a size evaluated from `width`/`height`, where NaN
+    /// is an axis that fits its content. With an aspect ratio a NaN axis is derived
+    /// from the other one, and when both are known the size shrinks to the ratio.
+    pub fn constrain_size(&self, size: DVec2) -> DVec2 {
+        if !self.has_constraints() {
+            return size
+        }
+        let mut size = dvec2(self.clamp_width(size.x), self.clamp_height(size.y));
+        if let Some(ratio) = self.ratio() {
+            match (size.x.is_nan(), size.y.is_nan()) {
+                (false, true) => size.y = size.x / ratio,
+                (true, false) => size.x = size.y * ratio,
+                (false, false) => if size.x > size.y * ratio {
+                    size.x = size.y * ratio
+                }
+                else {
+                    size.y = size.x / ratio
+                },
+                (true, true) => return size
+            }
+            size = dvec2(self.clamp_width(size.x), self.clamp_height(size.y));
+        }
+        size
+    }
+    
+    /// Applies the constraints to the size a turtle ends up with. Axes that fit their
+    /// content are the ones allowed to change, with an aspect ratio the short
+    /// side grows to match the content of the other one.
+    pub fn constrain_fit_size(&self, size: DVec2, fit_x: bool, fit_y: bool) -> DVec2 {
+        if !self.has_constraints() {
+            return size
+        }
+        let mut size = size;
+        if fit_x {
+            size.x = self.clamp_width(size.x);
+        }
+        if fit_y {
+            size.y = self.clamp_height(size.y);
+        }
+        if let Some(ratio) = self.ratio() {
+            if fit_x && fit_y {
+                if size.x < size.y * ratio {
+                    size.x = self.clamp_width(size.y * ratio);
+                }
+                else {
+                    size.y = self.clamp_height(size.x / ratio);
+                }
+            }
+        }
+        size
+    }
+}
+
+fn clamp_min_max(v: f64, min: Option<f64>, max: Option<f64>) -> f64 {
+    if v.is_nan() {
+        return v
+    }
+    let v = if let Some(max) = max {v.min(max)} else {v};
+    if let Some(min) = min {v.max(min)} else {v}
 }
 
 impl Padding {
@@ -1405,3 +2082,144 @@ fn max_zero_keep_nan(v: f64) -> f64 {
         f64::max(v, 0.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn walk(row: (usize, usize), column: (usize, usize)) -> Walk {
+        Walk {
+            grid_row: GridSpan {start: row.0, span: row.1, align: None},
+            grid_column: GridSpan {start: column.0, span: column.1, align: None},
+            ..Walk::default()
+        }
+    }
+    
+    fn place(grid: &mut TurtleGrid, columns: usize, row: (usize, usize), column: (usize, usize)) -> (usize, usize) {
+        let walk = walk(row, column);
+        let peeked = grid.peek_cell(columns, &walk);
+        let cell = grid.place_cell(columns, &walk);
+        assert_eq!((peeked.row, peeked.column), (cell.row, cell.column));
+        (cell.row, cell.column)
+    }
+    
+    #[test]
+    fn peek_cell_auto_placement() {
+        let mut grid = TurtleGrid::default();
+        assert_eq!(place(&mut grid, 3, (0, 0), (0, 0)), (0, 0));
+        // a span that doesn't fit the rest of the row wraps
+        assert_eq!(place(&mut grid, 3, (0, 0), (0, 3)), (1, 0));
+        assert_eq!(place(&mut grid, 3, (0, 0), (0, 2)), (2, 0));
+        // spans are clamped to the column count
+        let cell = grid.peek_cell(3, &walk((0, 0), (0, 5)));
+        assert_eq!((cell.row, cell.column, cell.column_span), (3, 0, 3));
+        // peeking doesn't place
+        assert_eq!(place(&mut grid, 3, (0, 0), (0, 0)), (2, 2));
+    }
+    
+    #[test]
+    fn peek_cell_explicit_placement() {
+        let mut grid = TurtleGrid::default();
+        // explicit starts count from 1, a column past the last one is clamped
+        assert_eq!(place(&mut grid, 3, (2, 2), (2, 1)), (1, 1));
+        assert_eq!(place(&mut grid, 3, (0, 0), (9, 1)), (0, 2));
+        // a row start takes the first free column in that row
+        assert_eq!(place(&mut grid, 3, (2, 1), (0, 0)), (1, 0));
+        // auto placement skips the cells the row spanning child covers
+        assert_eq!(place(&mut grid, 3, (0, 0), (0, 0)), (1, 2));
+        assert_eq!(place(&mut grid, 3, (0, 0), (0, 0)), (2, 0));
+        assert_eq!(place(&mut grid, 3, (0, 0), (0, 0)), (2, 2));
+        // a column start goes to the next row that is free there
+        assert_eq!(place(&mut grid, 3, (0, 0), (2, 1)), (3, 1));
+    }
+    
+    #[test]
+    fn track_sizes() {
+        let tracks = GridTracks::new(&[GridTrack::Fixed(50.0), GridTrack::Fit, GridTrack::Fr(1.0), GridTrack::Fr(3.0)]);
+        let content = [10.0, 30.0, 20.0, 20.0];
+        let sizes = grid_track_sizes(&tracks, GridTrack::Fit, 4, &content, std::iter::empty(), 300.0, 10.0);
+        // the fr tracks share what the fixed and fit tracks and the gaps leave
+        assert_eq!(sizes, [50.0, 30.0, 47.5, 142.5]);
+        
+        // without an available size fr tracks fit their content
+        let sizes = grid_track_sizes(&tracks, GridTrack::Fit, 4, &content, std::iter::empty(), f64::NAN, 10.0);
+        assert_eq!(sizes, [50.0, 30.0, 20.0, 20.0]);
+        
+        // implicit tracks past the defined ones, and a child spanning two tracks
+        // growing the last fit track it crosses
+        let sizes = grid_track_sizes(&tracks, GridTrack::Fit, 6, &[10.0, 30.0, 20.0, 20.0, 5.0], [(3, 3, 100.0)].into_iter(), f64::NAN, 10.0);
+        assert_eq!(sizes, [50.0, 30.0, 20.0, 20.0, 5.0, 55.0]);
+        
+        // fixed tracks don't grow for spanning children
+        let fixed = GridTracks::new(&[GridTrack::Fixed(20.0), GridTrack::Fixed(20.0)]);
+        let sizes = grid_track_sizes(&fixed, GridTrack::Fit, 2, &[], [(0, 2, 100.0)].into_iter(), f64::NAN, 0.0);
+        assert_eq!(sizes, [20.0, 20.0]);
+    }
+    
+    #[test]
+    fn grid_tracks_are_shared() {
+        let a = GridTracks::new(&[GridTrack::Fit, GridTrack::Fr(2.0)]);
+        let mut b = GridTracks::new(&[GridTrack::Fit]);
+        b.push(GridTrack::Fr(2.0));
+        assert_eq!(a, b);
+        assert!(std::ptr::eq(a.as_slice(), b.as_slice()));
+        assert_eq!(std::mem::size_of::<GridTracks>(), std::mem::size_of::<&[GridTrack]>());
+    }
+    
+    #[test]
+    fn clamp_min_max_size() {
+        let walk = Walk::default().with_min_width(50.0).with_max_width(100.0);
+        assert_eq!(walk.clamp_width(20.0), 50.0);
+        assert_eq!(walk.clamp_width(70.0), 70.0);
+        assert_eq!(walk.clamp_width(200.0), 100.0);
+        assert!(walk.clamp_width(f64::NAN).is_nan());
+        assert_eq!(walk.clamp_height(20.0), 20.0);
+        // min wins when they cross
+        let walk = Walk::default().with_min_height(100.0).with_max_height(50.0);
+        assert_eq!(walk.clamp_height(70.0), 100.0);
+    }
+    
+    #[test]
+    fn constrain_size() {
+        let size = dvec2(30.0, f64::NAN);
+        let constrained = Walk::default().constrain_size(size);
+        assert_eq!(constrained.x, 30.0);
+        assert!(constrained.y.is_nan());
+        
+        let walk = Walk::default().with_min_width(50.0).with_max_height(40.0);
+        assert_eq!(walk.constrain_size(dvec2(30.0, 60.0)), dvec2(50.0, 40.0));
+        
+        // a fit axis is derived from the other one, two known axes shrink to the ratio
+        let walk = Walk::default().with_aspect_ratio(2.0);
+        assert_eq!(walk.constrain_size(dvec2(100.0, f64::NAN)), dvec2(100.0, 50.0));
+        assert_eq!(walk.constrain_size(dvec2(f64::NAN, 30.0)), dvec2(60.0, 30.0));
+        assert_eq!(walk.constrain_size(dvec2(100.0, 100.0)), dvec2(100.0, 50.0));
+        assert_eq!(walk.constrain_size(dvec2(100.0, 20.0)), dvec2(40.0, 20.0));
+        assert!(walk.constrain_size(dvec2(f64::NAN, f64::NAN)).x.is_nan());
+        
+        // the derived axis is clamped as well
+        let walk = Walk::default().with_aspect_ratio(2.0).with_max_height(40.0);
+        assert_eq!(walk.constrain_size(dvec2(100.0, f64::NAN)), dvec2(100.0, 40.0));
+        
+        // a ratio that is not positive and finite is ignored
+        for ratio in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            let walk = Walk::default().with_aspect_ratio(ratio);
+            assert_eq!(walk.constrain_size(dvec2(100.0, 100.0)), dvec2(100.0, 100.0));
+        }
+    }
+    
+    #[test]
+    fn constrain_fit_size() {
+        // only the axes that fit their content are clamped
+        let walk = Walk::default().with_min_width(50.0).with_max_height(100.0);
+        assert_eq!(walk.constrain_fit_size(dvec2(20.0, 500.0), true, false), dvec2(50.0, 500.0));
+        assert_eq!(walk.constrain_fit_size(dvec2(20.0, 500.0), false, true), dvec2(20.0, 100.0));
+        assert_eq!(walk.constrain_fit_size(dvec2(20.0, 500.0), true, true), dvec2(50.0, 100.0));
+        
+        // with an aspect ratio the short side grows to the content of the other one
+        let walk = Walk::default().with_aspect_ratio(2.0);
+        assert_eq!(walk.constrain_fit_size(dvec2(40.0, 40.0), true, true), dvec2(80.0, 40.0));
+        assert_eq!(walk.constrain_fit_size(dvec2(200.0, 40.0), true, true), dvec2(200.0, 100.0));
+        assert_eq!(walk.constrain_fit_size(dvec2(40.0, 40.0), true, false), dvec2(40.0, 40.0));
+    }
+}
@@ -1,6 +1,6 @@
 use std::f64::consts::{PI, TAU};
 
-use crate::geometry::{Point, Transform, Transformation};
+use crate::geometry::{CubicSegment, Point, Transform, Transformation, Vector};
 use crate::internal_iter::InternalIterator;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -208,6 +208,46 @@ impl Arc {
         ))
     }
 
+    /// Returns a sequence of cubic Bezier curve segments that approximate `self`, each of which
+    /// spans at most a quarter of the ellipse.
+    ///
+    /// Unlike arcs, cubic segments can be transformed by any affine transformation.
+    pub fn to_cubics(&self) -> Vec<CubicSegment> {
+        if self.from == self.to {
+            return Vec::new();
+        }
+        if self.rx == 0.0 || self.ry == 0.0 {
+            return vec![CubicSegment::new(self.from, self.from, self.to, self.to)];
+        }
+        let x_axis_rotation_radians = self.x_axis_rotation * (PI / 180.0);
+        let (sin_rotation, cos_rotation) = x_axis_rotation_radians.sin_cos();
+        let rotate = |v: Vector| {
+            Vector::new(
+                cos_rotation * v.x - sin_rotation * v.y,
+                sin_rotation * v.x + cos_rotation * v.y,
+            )
+        };
+        let segment_count = (self.sweep_angle.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+        let delta = self.sweep_angle / segment_count as f64;
+        let k = 4.0 / 3.0 * (delta / 4.0).tan();
+        let mut cubics = Vec::with_capacity(segment_count);
+        let mut p0 = self.from;
+        for index in 0..segment_count {
+            let angle0 = self.start_angle + delta * index as f64;
+            let angle1 = angle0 + delta;
+            let p3 = if index == segment_count - 1 {
+                self.to
+            } else {
+                self.point_on_curve((index + 1) as f64 / segment_count as f64)
+            };
+            let tangent0 = rotate(Vector::new(-self.rx * angle0.sin(), self.ry * angle0.cos()));
+            let tangent1 = rotate(Vector::new(-self.rx * angle1.sin(), self.ry * angle1.cos()));
+            cubics.push(CubicSegment::new(p0, p0 + tangent0 * k, p3 - tangent1 * k, p3));
+            p0 = p3;
+        }
+        cubics
+    }
+
     pub fn linearize(self, epsilon: f64) -> Linearize {
         Linearize {
             segment: self,
@@ -24,6 +24,24 @@ impl LineSegment {
         (p - self.p0).cross(self.p1 - p).partial_cmp(&0.0)
     }
 
+    /// Returns the point where `self` and `other` intersect, or None if they do not intersect or
+    /// are parallel.
+    pub fn intersect(self, other: LineSegment) -> Option<Point> {
+        let d0 = self.p1 - self.p0;
+        let d1 = other.p1 - other.p0;
+        let denominator = d0.cross(d1);
+        if denominator == 0.0 {
+            return None;
+        }
+        let d = other.p0 - self.p0;
+        let t0 = d.cross(d1) / denominator;
+        let t1 = d.cross(d0) / denominator;
+        if !(0.0..=1.0).contains(&t0) || !(0.0..=1.0).contains(&t1) {
+            return None;
+        }
+        Some(self.p0 + d0 * t0)
+    }
+
     /// Returns the intersection point of the supporting line of `self` with the vertical line
     /// through `x`, or None if these lines are coincident.
     pub fn intersect_with_vertical_line(self, x: f64) -> Option<Point> {
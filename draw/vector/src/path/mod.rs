@@ -5,6 +5,7 @@ mod line_path_command;
 mod line_path_iterator;
 mod path_command;
 mod path_iterator;
+mod stroke;
 
 pub use self::line_path::LinePath;
 pub use self::line_path_command::LinePathCommand;
@@ -12,3 +13,4 @@ pub use self::line_path_iterator::LinePathIterator;
 pub use self::path::Path;
 pub use self::path_command::PathCommand;
 pub use self::path_iterator::PathIterator;
+pub use self::stroke::{LineCap, LineJoin, Stroke, StrokeStyle};
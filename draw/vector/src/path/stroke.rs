@@ -0,0 +1,344 @@
+use crate::geometry::{Point, Vector};
+use crate::internal_iter::InternalIterator;
+use crate::path::{LinePathCommand, LinePathIterator};
+use std::f64::consts::PI;
+
+/// The shape at the end of an open contour of a stroke.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// The shape at the corners of a stroke.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// The parameters of a stroke.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f64,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    pub miter_limit: f64,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1.0,
+            line_cap: LineCap::default(),
+            line_join: LineJoin::default(),
+            miter_limit: 4.0,
+        }
+    }
+}
+
+/// An iterator over line path commands that define the outline of a stroke along `self`.
+///
+/// The outline is made up of overlapping closed contours that all have the same orientation, so
+/// it should be filled with the non-zero fill rule.
+#[derive(Clone, Debug)]
+pub struct Stroke<P> {
+    path: P,
+    style: StrokeStyle,
+    epsilon: f64,
+}
+
+impl<P> Stroke<P> {
+    pub(crate) fn new(path: P, style: StrokeStyle, epsilon: f64) -> Stroke<P> {
+        Stroke {
+            path,
+            style,
+            epsilon,
+        }
+    }
+}
+
+impl<P> InternalIterator for Stroke<P>
+where
+    P: LinePathIterator,
+{
+    type Item = LinePathCommand;
+
+    fn for_each<F>(self, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        // Collect the contours first, since the joins and caps of a contour depend on whether it
+        // is closed.
+        let mut contours: Vec<(Vec<Point>, bool)> = Vec::new();
+        self.path.for_each(&mut |command| {
+            match command {
+                LinePathCommand::MoveTo(p) => contours.push((vec![p], false)),
+                LinePathCommand::LineTo(p) => {
+                    // A line after a close continues from the start of the closed contour.
+                    if let Some((points, true)) = contours.last() {
+                        let p0 = points[0];
+                        contours.push((vec![p0], false));
+                    }
+                    if let Some((points, _)) = contours.last_mut() {
+                        if points.last() != Some(&p) {
+                            points.push(p);
+                        }
+                    }
+                }
+                LinePathCommand::Close => {
+                    if let Some((points, closed)) = contours.last_mut() {
+                        if points.len() > 1 && points.first() == points.last() {
+                            points.pop();
+                        }
+                        *closed = true;
+                    }
+                }
+            }
+            true
+        });
+        let stroker = Stroker {
+            half_width: self.style.width / 2.0,
+            style: self.style,
+            epsilon: self.epsilon,
+        };
+        for (points, closed) in &contours {
+            if !stroker.stroke_contour(points, *closed, f) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+struct Stroker {
+    style: StrokeStyle,
+    half_width: f64,
+    epsilon: f64,
+}
+
+impl Stroker {
+    fn stroke_contour<F>(&self, points: &[Point], closed: bool, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        if self.half_width <= 0.0 {
+            return true;
+        }
+        if points.len() == 1 {
+            // A contour that consists of a single point only has caps, and only if they extend
+            // beyond the point.
+            return match self.style.line_cap {
+                LineCap::Butt => true,
+                LineCap::Round => self.circle(points[0], f),
+                LineCap::Square => {
+                    let d = Vector::new(self.half_width, 0.0);
+                    let n = Vector::new(0.0, self.half_width);
+                    emit_polygon(
+                        &[
+                            points[0] - d - n,
+                            points[0] + d - n,
+                            points[0] + d + n,
+                            points[0] - d + n,
+                        ],
+                        f,
+                    )
+                }
+            };
+        }
+        let segment_count = if closed && points.len() > 2 {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        for index in 0..segment_count {
+            let p0 = points[index];
+            let p1 = points[(index + 1) % points.len()];
+            let n = self.normal(p1 - p0);
+            if !emit_polygon(&[p0 + n, p1 + n, p1 - n, p0 - n], f) {
+                return false;
+            }
+        }
+        if closed && points.len() > 2 {
+            for index in 0..points.len() {
+                let p0 = points[(index + points.len() - 1) % points.len()];
+                let p1 = points[index];
+                let p2 = points[(index + 1) % points.len()];
+                if !self.join(p0, p1, p2, f) {
+                    return false;
+                }
+            }
+        } else {
+            for index in 1..points.len() - 1 {
+                if !self.join(points[index - 1], points[index], points[index + 1], f) {
+                    return false;
+                }
+            }
+            if !self.cap(points[0], points[1], f)
+                || !self.cap(points[points.len() - 1], points[points.len() - 2], f)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Returns the normal of the given direction, scaled to half the stroke width.
+    fn normal(&self, d: Vector) -> Vector {
+        let d = d.normalize().unwrap_or(Vector::new(1.0, 0.0));
+        Vector::new(-d.y, d.x) * self.half_width
+    }
+
+    // Emits the join at `p1` between the segments from `p0` to `p1` and from `p1` to `p2`.
+    fn join<F>(&self, p0: Point, p1: Point, p2: Point, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        let d0 = p1 - p0;
+        let d1 = p2 - p1;
+        let cross = d0.cross(d1);
+        if cross == 0.0 && d0.dot(d1) > 0.0 {
+            return true;
+        }
+        if self.style.line_join == LineJoin::Round {
+            return self.circle(p1, f);
+        }
+        // The join fills the gap on the outer side of the corner.
+        let sign = if cross > 0.0 { -1.0 } else { 1.0 };
+        let o0 = self.normal(d0) * sign;
+        let o1 = self.normal(d1) * sign;
+        if self.style.line_join == LineJoin::Miter {
+            let m = o0 + o1;
+            let cos_half_angle = m.length() / (2.0 * self.half_width);
+            if cos_half_angle > 0.0 && 1.0 / cos_half_angle <= self.style.miter_limit {
+                let miter = p1 + m.normalize().unwrap() * (self.half_width / cos_half_angle);
+                return emit_polygon(&[p1, p1 + o0, miter, p1 + o1], f);
+            }
+        }
+        emit_polygon(&[p1, p1 + o0, p1 + o1], f)
+    }
+
+    // Emits the cap at `p0` for the segment from `p0` to `p1`.
+    fn cap<F>(&self, p0: Point, p1: Point, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        match self.style.line_cap {
+            LineCap::Butt => true,
+            LineCap::Round => self.circle(p0, f),
+            LineCap::Square => {
+                let n = self.normal(p1 - p0);
+                let d = Vector::new(-n.y, n.x);
+                emit_polygon(&[p0 + n, p0 - n, p0 - n + d, p0 + n + d], f)
+            }
+        }
+    }
+
+    fn circle<F>(&self, p: Point, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        // Choose the number of segments so that the distance between each segment and the circle
+        // is at most `epsilon`.
+        let step = 2.0 * (1.0 - self.epsilon / self.half_width).max(-1.0).acos();
+        let segment_count = if step > 0.0 {
+            ((2.0 * PI / step).ceil() as usize).clamp(8, 256)
+        } else {
+            256
+        };
+        let mut points = Vec::with_capacity(segment_count);
+        for index in 0..segment_count {
+            let angle = 2.0 * PI * index as f64 / segment_count as f64;
+            points.push(p + Vector::new(angle.cos(), angle.sin()) * self.half_width);
+        }
+        emit_polygon(&points, f)
+    }
+}
+
+// Emits the given polygon as a closed contour with a positive orientation, so that overlapping
+// polygons are merged by the non-zero fill rule instead of cancelling each other out.
+fn emit_polygon<F>(points: &[Point], f: &mut F) -> bool
+where
+    F: FnMut(LinePathCommand) -> bool,
+{
+    let mut area = 0.0;
+    for index in 0..points.len() {
+        let p0 = points[index];
+        let p1 = points[(index + 1) % points.len()];
+        area += p0.x * p1.y - p1.x * p0.y;
+    }
+    if area == 0.0 {
+        return true;
+    }
+    let mut emit = |index: usize, p: Point| {
+        if index == 0 {
+            f(LinePathCommand::MoveTo(p))
+        } else {
+            f(LinePathCommand::LineTo(p))
+        }
+    };
+    if area > 0.0 {
+        for (index, &p) in points.iter().enumerate() {
+            if !emit(index, p) {
+                return false;
+            }
+        }
+    } else {
+        for (index, &p) in points.iter().rev().enumerate() {
+            if !emit(index, p) {
+                return false;
+            }
+        }
+    }
+    f(LinePathCommand::Close)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Trapezoid;
+    use crate::path::{Path, PathIterator};
+    use crate::trapezoidator::Trapezoidator;
+
+    fn stroke_area(path: &Path, style: StrokeStyle) -> f32 {
+        let mut trapezoidator = Trapezoidator::new();
+        let trapezoids: Vec<Trapezoid> = trapezoidator
+            .trapezoidate(path.commands().linearize(0.1).stroke(style, 0.1))
+            .unwrap()
+            .collect();
+        let mut area = 0.0;
+        for t in &trapezoids {
+            area += (t.xs[1] - t.xs[0]) * ((t.ys[2] - t.ys[0]) + (t.ys[3] - t.ys[1])) / 2.0;
+        }
+        area
+    }
+
+    #[test]
+    fn test_caps() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(10.0, 0.0));
+        let style = StrokeStyle { width: 2.0, ..StrokeStyle::default() };
+        assert_eq!(stroke_area(&path, style), 20.0);
+        let style = StrokeStyle { line_cap: LineCap::Square, ..style };
+        assert_eq!(stroke_area(&path, style), 24.0);
+    }
+
+    #[test]
+    fn test_closed_square() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(10.0, 0.0));
+        path.line_to(Point::new(10.0, 10.0));
+        path.line_to(Point::new(0.0, 10.0));
+        path.close();
+        let style = StrokeStyle { width: 2.0, ..StrokeStyle::default() };
+        assert_eq!(stroke_area(&path, style), 12.0 * 12.0 - 8.0 * 8.0);
+        let style = StrokeStyle { line_join: LineJoin::Bevel, ..style };
+        assert_eq!(stroke_area(&path, style), 12.0 * 12.0 - 8.0 * 8.0 - 4.0 * 0.5);
+    }
+}
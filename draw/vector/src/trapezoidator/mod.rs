@@ -10,10 +10,30 @@ use std::ops::Range;
 /// should define a set of closed contours.
 #[derive(Clone, Debug, Default)]
 pub struct Trapezoidator {
+    fill_rule: FillRule,
     event_queue: BinaryHeap<Event>,
     active_segments: Vec<ActiveSegment>,
 }
 
+/// The rule that is used to determine which regions of a path are considered inside.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum FillRule {
+    /// A region is inside if its winding is non-zero.
+    #[default]
+    NonZero,
+    /// A region is inside if its winding is odd.
+    EvenOdd,
+}
+
+impl FillRule {
+    fn is_inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
 impl Trapezoidator {
     /// Creates a new trapezoidator.
     pub fn new() -> Trapezoidator {
@@ -23,6 +43,17 @@ impl Trapezoidator {
     /// Returns an iterator over trapezoids corresponding to the given iterator over line path
     /// commands.
     pub fn trapezoidate<P: LinePathIterator>(&mut self, path: P) -> Option<Trapezoidate> {
+        self.trapezoidate_with_fill_rule(path, FillRule::NonZero)
+    }
+
+    /// Returns an iterator over trapezoids corresponding to the given iterator over line path
+    /// commands, using the given fill rule to determine which regions are inside.
+    pub fn trapezoidate_with_fill_rule<P: LinePathIterator>(
+        &mut self,
+        path: P,
+        fill_rule: FillRule,
+    ) -> Option<Trapezoidate<'_>> {
+        self.fill_rule = fill_rule;
         let mut initial_point = None;
         let mut current_point = None;
         if !path.for_each(&mut |command| {
@@ -59,7 +90,8 @@ impl Trapezoidator {
         // Determine the winding, the leftmost point, and the rightmost point of the segment.
         //
         // The winding is used to determine which regions are considered inside, and which are
-        // considered outside. A region is considered inside if its winding is non-zero.
+        // considered outside. With the non-zero fill rule, a region is considered inside if its
+        // winding is non-zero, and with the even-odd fill rule, if its winding is odd.
         // Conceptually, the winding of a region is determined by casting an imaginary ray from
         // any point inside the region to infinity in any direction, and adding the windings of
         // all segments that are intersected by the ray. The winding of a segment is +1 if it
@@ -143,15 +175,12 @@ impl Trapezoidator {
     {
         // Find the range of active segments that are incident with the given point.
         let mut incident_segment_range = self.find_incident_segment_range(point);
-        // If there is an active segment that lies below the current point, and the region below it
-        // is considered outside, then this segment is the lower boundary of a trapezoid. We split
-        // the segment where it intersects the sweepline, adding the part on the left to the list of
-        // trapezoid segments, while keeping the part on the right in the list of active segments.
-        if let Some(trapezoid_segment) =
-            self.find_trapezoid_segment_below(point, incident_segment_range.start)
-        {
-            trapezoid_segments.push(trapezoid_segment);
-        }
+        // If there are active segments that lie below the current point, and the region above them
+        // is considered inside, then these segments are the boundaries of trapezoids. We split
+        // each segment where it intersects the sweepline, adding the part on the left to the list
+        // of trapezoid segments, while keeping the part on the right in the list of active
+        // segments.
+        self.find_trapezoid_segments_below(point, incident_segment_range.start, trapezoid_segments);
         // If there are any active segments that are incident with the given point, we remove them
         // from the list of active segments, and then split each segment where it intersects the
         // sweepline, adding the part on the left to the list of trapezoid segments, while adding
@@ -176,6 +205,15 @@ impl Trapezoidator {
         {
             trapezoid_segments.push(trapezoid_segment);
         }
+        // The active segments that are now adjacent to each other might intersect to the right of
+        // the current point. If so, we split them at the intersection point, so that they are
+        // reordered when the sweepline reaches it.
+        if incident_segment_range.start > 0 {
+            self.split_segments_at_intersection(point, incident_segment_range.start - 1);
+        }
+        if incident_segment_range.end > incident_segment_range.start {
+            self.split_segments_at_intersection(point, incident_segment_range.end - 1);
+        }
         // At this point, `trapezoid_segments` contains a list of segments that stop intersecting the
         // sweepline at the current point, and that potentially form trapezoid boundaries. We generate
         // trapezoids for these segments, and pass them to the given closure.
@@ -204,24 +242,34 @@ impl Trapezoidator {
         }
     }
 
-    // Finds the first active segment that lies below the given point. If such a segment exists,
-    // and the region below it is considered outside, then this segment is the lower boundary of a
-    // trapezoid. We split the segment where it intersects the sweepline, keeping the part on the
-    // right in the list of active segments, and returning the part on the left.
-    fn find_trapezoid_segment_below(
+    // Finds the active segments that lie below the given point, and that have a region above them
+    // that is considered inside. Starting with the first such segment, these segments are the
+    // boundaries of a stack of trapezoids that ends with the first segment that has a region below
+    // it that is considered outside. We split each segment where it intersects the sweepline,
+    // keeping the part on the right in the list of active segments, and adding the part on the
+    // left to the list of trapezoid segments.
+    fn find_trapezoid_segments_below(
         &mut self,
         point: Point,
         incident_segment_start: usize,
-    ) -> Option<ActiveSegment> {
-        if incident_segment_start == 0
-            || !self.active_segments[incident_segment_start - 1].region_above.is_inside {
-            return None;
+        trapezoid_segments: &mut Vec<ActiveSegment>,
+    ) {
+        let mut index = incident_segment_start;
+        while index > 0 && self.active_segments[index - 1].region_above.is_inside {
+            index -= 1;
+            let intersection = self.active_segments[index]
+                .segment
+                .intersect_with_vertical_line(point.x)
+                .unwrap_or(point);
+            match self.active_segments[index].split_left_mut(intersection) {
+                Some(trapezoid_segment) => trapezoid_segments.push(trapezoid_segment),
+                // If the segment already starts at the sweepline, so do the segments below it.
+                None => break,
+            }
         }
-        let intersection = self.active_segments[incident_segment_start - 1]
-            .segment
-            .intersect_with_vertical_line(point.x)
-            .unwrap_or(point);
-        self.active_segments[incident_segment_start - 1].split_left_mut(intersection)
+        // The segments were added from top to bottom, but trapezoids are generated from bottom to
+        // top.
+        trapezoid_segments.reverse();
     }
 
     // Removes all active segments that are incident with the given point from the list of active
@@ -280,6 +328,7 @@ impl Trapezoidator {
         incident_segment_range: &mut Range<usize>,
         right_segments: &[PendingSegment],
     ) {
+        let fill_rule = self.fill_rule;
         let mut lower_region = if incident_segment_range.end == 0 {
             Region {
                 is_inside: false,
@@ -294,7 +343,7 @@ impl Trapezoidator {
                 let upper_region = {
                     let winding = lower_region.winding + right_segment.winding;
                     Region {
-                        is_inside: winding != 0,
+                        is_inside: fill_rule.is_inside(winding),
                         winding,
                     }
                 };
@@ -340,6 +389,34 @@ impl Trapezoidator {
         Some(self.active_segments[incident_segment_end])
     }
 
+    // Splits the active segment at the given index and the one above it at the point where they
+    // intersect, if that point lies to the right of the given point. Generates an event for the
+    // part on the right of each segment.
+    fn split_segments_at_intersection(&mut self, point: Point, index: usize) {
+        if index + 1 >= self.active_segments.len() {
+            return;
+        }
+        let segment_0 = self.active_segments[index].segment;
+        let segment_1 = self.active_segments[index + 1].segment;
+        // Segments that share an endpoint can only meet at that endpoint.
+        if segment_0.p0 == segment_1.p0 || segment_0.p1 == segment_1.p1 {
+            return;
+        }
+        let intersection = match segment_0.intersect(segment_1) {
+            Some(intersection) if intersection > point => intersection,
+            _ => return,
+        };
+        for index in index..index + 2 {
+            if let Some(pending_segment) = self.active_segments[index].split_right_mut(intersection)
+            {
+                self.event_queue.push(Event {
+                    point: intersection,
+                    pending_segment: Some(pending_segment),
+                });
+            }
+        }
+    }
+
     fn generate_trapezoids<F>(&self, trapezoid_segments: &[ActiveSegment], f: &mut F) -> bool
     where
         F: FnMut(Trapezoid) -> bool,
@@ -539,4 +616,56 @@ mod tests {
             Trapezoid { xs: [0.0, 1.0], ys: [0.0, 0.0, 1.0, 1.0] }
         ]);
     }
+
+    #[test]
+    fn test_fill_rule() {
+        let mut path = Path::new();
+        for &(min, max) in &[(0.0, 3.0), (1.0, 2.0)] {
+            path.move_to(Point::new(min, min));
+            path.line_to(Point::new(max, min));
+            path.line_to(Point::new(max, max));
+            path.line_to(Point::new(min, max));
+            path.close();
+        }
+        let mut trapezoidator = Trapezoidator::new();
+        let area = |trapezoidator: &mut Trapezoidator, fill_rule| {
+            let trapezoids: Vec<Trapezoid> = trapezoidator
+                .trapezoidate_with_fill_rule(path.commands().linearize(0.1), fill_rule)
+                .unwrap()
+                .collect();
+            let mut area = 0.0;
+            for t in &trapezoids {
+                area += (t.xs[1] - t.xs[0]) * ((t.ys[2] - t.ys[0]) + (t.ys[3] - t.ys[1])) / 2.0;
+            }
+            area
+        };
+        assert_eq!(area(&mut trapezoidator, FillRule::NonZero), 9.0);
+        assert_eq!(area(&mut trapezoidator, FillRule::EvenOdd), 8.0);
+    }
+
+    #[test]
+    fn test_intersecting_contours() {
+        let mut path = Path::new();
+        for &(min, max) in &[(0.0, 2.0), (1.0, 3.0)] {
+            path.move_to(Point::new(min, min));
+            path.line_to(Point::new(max, min));
+            path.line_to(Point::new(max, max));
+            path.line_to(Point::new(min, max));
+            path.close();
+        }
+        let mut trapezoidator = Trapezoidator::new();
+        let area = |trapezoidator: &mut Trapezoidator, fill_rule| {
+            let trapezoids: Vec<Trapezoid> = trapezoidator
+                .trapezoidate_with_fill_rule(path.commands().linearize(0.1), fill_rule)
+                .unwrap()
+                .collect();
+            let mut area = 0.0;
+            for t in &trapezoids {
+                area += (t.xs[1] - t.xs[0]) * ((t.ys[2] - t.ys[0]) + (t.ys[3] - t.ys[1])) / 2.0;
+            }
+            area
+        };
+        assert_eq!(area(&mut trapezoidator, FillRule::NonZero), 7.0);
+        assert_eq!(area(&mut trapezoidator, FillRule::EvenOdd), 6.0);
+    }
 }
\ No newline at end of file
@@ -407,6 +407,22 @@ live_design!{
                     }
                 }
 
+                <ZooHeader> {
+                    title = {text:"Grid layout"}
+                    <ZooDesc> { text:"flow: Grid lines up children in columns and rows without nesting Views" }
+                    <ZooGroup> {
+                        flow: Grid {columns: [Fit, Fr(1.0), Fixed(100.0)], column_gap: (THEME_SPACE_2), row_gap: (THEME_SPACE_1)}
+                        <P> { text: "Name", width: Fit }
+                        <TextInput> { width: Fill, empty_message: "Your name" }
+                        <Button> { text: "Clear" }
+                        <P> { text: "E-mail address", width: Fit }
+                        <TextInput> { width: Fill, empty_message: "you@example.com" }
+                        <Button> { text: "Verify" }
+                        <P> { text: "Notes", width: Fit }
+                        <TextInput> { width: Fill, height: 60, grid_column: {span: 2}, empty_message: "Spans two columns" }
+                    }
+                }
+
                 <ZooHeader> {
                     title = {text:"<Label>"}
                     <ZooDesc> { text:"Default single line textbox" }
@@ -511,7 +527,7 @@ live_design!{
                     <ZooGroup> {
                         <Html> {
                             width:Fill, height:Fit,
-                            body:"<H1>H1 Headline</H1><H2>H2 Headline</H2><H3>H3 Headline</H3><H4>H4 Headline</H4><H5>H5 Headline</H5><H6>H6 Headline</H6>This is <b>bold</b>&nbsp;and <i>italic text</i>.<sep><b><i>Bold italic</i></b>, <u>underlined</u>, and <s>strike through</s> text. <p>This is a paragraph</p> <code>A code block</code>. <br/> And this is a <a href='https://www.google.com/'>link</a><br/><ul><li>lorem</li><li>ipsum</li><li>dolor</li></ul><ol><li>lorem</li><li>ipsum</li><li>dolor</li></ol><br/> <blockquote>Blockquote</blockquote> <pre>pre</pre><sub>sub</sub><del>del</del>"
+                            body:"<H1>H1 Headline</H1><H2>H2 Headline</H2><H3>H3 Headline</H3><H4>H4 Headline</H4><H5>H5 Headline</H5><H6>H6 Headline</H6>This is <b>bold</b>&nbsp;and <i>italic text</i>.<sep><b><i>Bold italic</i></b>, <u>underlined</u>, and <s>strike through</s> text. <p>This is a paragraph</p> <code>A code block</code>. <br/> And this is a <a href='https://www.google.com/'>link</a><br/><ul><li>lorem</li><li>ipsum</li><li>dolor</li></ul><ol><li>lorem</li><li>ipsum</li><li>dolor</li></ol><br/> <blockquote>Blockquote</blockquote> <pre>pre</pre><sub>sub</sub><del>del</del><p>Text in <span style='color: #e06c75; font-size: 120%'>color and size</span> from inline CSS.</p><table><tr><th>Table</th><th align='right'>Value</th></tr><tr><td>Row</td><td align='right'>1</td></tr><tr><td colspan='2'>Spanning cell</td></tr></table><img src='crate://self/resources/ducky.png' width='40' alt='ducky'>"
                         }
                     }
                 }
@@ -522,7 +538,7 @@ live_design!{
                     <ZooGroup> {
                         <Markdown> {
                             width:Fill, height: Fit,
-                            body:"# Headline 1 \n ## Headline 2 \n ### Headline 3 \n #### Headline 4 \n This is standard text with a  \n\n line break a short ~~strike through~~ demo.\n\n *Italic text* \n\n **Bold text** \n\n - Bullet\n - Another bullet\n\n - Third bullet\n\n 1. Numbered list Bullet\n 2. Another list entry\n\n 3. Third list entry\n\n `Monospaced text`\n\n> This is a quote.\n\nThis is `inline code`.\n\n ```code block```"
+                            body:"# Headline 1 \n ## Headline 2 \n ### Headline 3 \n #### Headline 4 \n This is standard text with a  \n\n line break a short ~~strike through~~ demo.\n\n *Italic text* \n\n **Bold text** \n\n - Bullet\n - Another bullet\n\n - Third bullet\n\n 1. Numbered list Bullet\n 2. Another list entry\n\n 3. Third list entry\n\n `Monospaced text`\n\n> This is a quote.\n\nThis is `inline code`.\n\n| Column | Centered | Right |\n|:--|:--:|--:|\n| Table | **bold** | 1 |\n| Rows | `code` | 200 |\n\n- [x] Finished task\n- [ ] Open task\n\nAn autolink to https://makepad.dev and a footnote[^1].\n\n[^1]: Footnotes are listed at the end.\n\n ```code block```"
                         }
                     }
                 }
@@ -546,9 +562,9 @@ live_design!{
                             width: Fit, height: Fit, flow: Down,
                             <View> {
                                 show_bg: true, draw_bg: { color: (THEME_COLOR_BG_CONTAINER)}, width: 125, height: 250,
-                                <Image> { height: Fill, source: dep("crate://self/resources/ducky.png" ), min_height: 100 }
+                                <Image> { height: Fill, source: dep("crate://self/resources/ducky.png" ), max_height: 100 }
                             }
-                            <P> { text: "min_height: 100" } // TODO: get this to work correctly
+                            <P> { text: "max_height: 100" }
                         }
                         <View> {
                             width: Fit, height: Fit, flow: Down,
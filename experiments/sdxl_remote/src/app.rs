@@ -87,7 +87,7 @@ impl MatchEvent for App{
                     let _ = img.load_png_from_data(cx, &data);
                     img.redraw(cx);
                 }
-                Ok(WebSocketMessage::Closed)=>{
+                Ok(WebSocketMessage::Closed(_))=>{
                      println!("WEBSOCKET CLOSED");
                     self.open_websocket(cx);
                 }
@@ -31,6 +31,7 @@ pub struct HtmlAttribute {
     pub value: String,
 }*/
  
+#[derive(Clone)]
 pub struct HtmlWalker<'a>{
     decoded: &'a str,
     pub nodes: &'a [HtmlNode],
@@ -110,13 +111,15 @@ impl<'a> HtmlWalker<'a>{
     pub fn find_attr_lc(&self, flc:LiveId)->Option<&'a str>{
         for i in self.index..self.nodes.len(){
             match &self.nodes[i]{
-                HtmlNode::CloseTag{..}=>{
-                    return None
-                }
                 HtmlNode::Attribute{lc, nc:_, start, end} if *lc == flc=>{
                     return Some(&self.decoded[*start..*end])
                 }
-                _=>()
+                HtmlNode::Attribute{..}=>(),
+                // the attributes directly follow the open tag, stop before the next tag's
+                HtmlNode::OpenTag{..} if i == self.index=>(),
+                _=>{
+                    return None
+                }
             }
         }
         None
@@ -142,13 +145,15 @@ impl<'a> HtmlWalker<'a>{
     pub fn find_attr_nc(&self, fnc:LiveId)->Option<&'a str>{
         for i in self.index..self.nodes.len(){
             match &self.nodes[i]{
-                HtmlNode::CloseTag{..}=>{
-                    return None
-                }
                 HtmlNode::Attribute{lc:_, nc, start, end} if *nc == fnc=>{
                     return Some(&self.decoded[*start..*end])
                 }
-                _=>()
+                HtmlNode::Attribute{..}=>(),
+                // the attributes directly follow the open tag, stop before the next tag's
+                HtmlNode::OpenTag{..} if i == self.index=>(),
+                _=>{
+                    return None
+                }
             }
         }
         None
@@ -0,0 +1,50 @@
+use {
+    makepad_html::*,
+    makepad_live_id::*,
+};
+
+fn walker_at<'a>(doc: &'a HtmlDoc, tag: LiveId, nth: usize) -> HtmlWalker<'a> {
+    let mut walker = doc.new_walker();
+    let mut count = 0;
+    while !walker.done() {
+        if walker.open_tag_lc() == Some(tag) {
+            if count == nth {
+                return walker
+            }
+            count += 1;
+        }
+        walker.walk();
+    }
+    panic!("no such tag")
+}
+
+#[test]
+fn attributes_belong_to_their_own_tag() {
+    let doc = parse_html("<p class=intro><a href=\"x.html\">link</a></p>", &mut None, InternLiveId::No);
+    let p = walker_at(&doc, live_id!(p), 0);
+    assert_eq!(p.find_attr_lc(live_id!(class)), Some("intro"));
+    // the href is the link's, the paragraph has none
+    assert_eq!(p.find_attr_lc(live_id!(href)), None);
+    assert_eq!(p.find_attr_nc(live_id!(href)), None);
+    let a = walker_at(&doc, live_id!(a), 0);
+    assert_eq!(a.find_attr_lc(live_id!(href)), Some("x.html"));
+}
+
+#[test]
+fn nested_inline_style() {
+    // the cell has no style of its own, it used to get the one of the span inside it
+    let doc = parse_html("<td><span style=\"color:red\">x</span></td><td style=\"color:blue\"/>", &mut None, InternLiveId::No);
+    assert_eq!(walker_at(&doc, live_id!(td), 0).find_attr_lc(live_id!(style)), None);
+    assert_eq!(walker_at(&doc, live_id!(span), 0).find_attr_lc(live_id!(style)), Some("color:red"));
+    assert_eq!(walker_at(&doc, live_id!(td), 1).find_attr_lc(live_id!(style)), Some("color:blue"));
+}
+
+#[test]
+fn attribute_case() {
+    let doc = parse_html("<div Data-Id=\"7\" style=\"color:red\"></div>", &mut None, InternLiveId::No);
+    let div = walker_at(&doc, live_id!(div), 0);
+    assert_eq!(div.find_attr_lc(LiveId::from_str_lc("data-id")), Some("7"));
+    assert_eq!(div.find_attr_nc(LiveId::from_str("Data-Id")), Some("7"));
+    assert_eq!(div.find_attr_nc(LiveId::from_str("data-id")), None);
+    assert_eq!(div.find_attr_lc(live_id!(style)), Some("color:red"));
+}
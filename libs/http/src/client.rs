@@ -0,0 +1,623 @@
+// A small blocking HTTP/1.1 client, so the platform layers and tools don't each hand-roll their own requests.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The bidirectional stream a connection runs over, a tcp socket or a tls session wrapping one
+pub trait HttpClientStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> HttpClientStream for T {}
+
+/// Wraps a connected socket in a tls session for `https` urls. The crate has no tls
+/// implementation of its own, so users that need https plug one in with `HttpClient::set_tls_connector`
+pub type HttpClientTlsConnector = fn(host: &str, stream: TcpStream) -> io::Result<Box<dyn HttpClientStream>>;
+
+#[derive(Debug)]
+pub enum HttpClientError {
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+    Connect(io::Error),
+    Io(io::Error),
+    Timeout,
+    InvalidResponse(String),
+    TooManyRedirects,
+    Decompress(String),
+}
+
+impl std::fmt::Display for HttpClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidUrl(url) => write!(f, "invalid url {}", url),
+            Self::UnsupportedScheme(scheme) => write!(f, "unsupported url scheme {}", scheme),
+            Self::Connect(e) => write!(f, "cannot connect: {}", e),
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Timeout => write!(f, "request timed out"),
+            Self::InvalidResponse(e) => write!(f, "invalid response: {}", e),
+            Self::TooManyRedirects => write!(f, "too many redirects"),
+            Self::Decompress(e) => write!(f, "cannot decompress body: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HttpClientError {}
+
+impl From<io::Error> for HttpClientError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Self::Timeout,
+            _ => Self::Io(e)
+        }
+    }
+}
+
+/// The parts of an `http://host:port/path?query` url the client needs
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpClientUrl {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl HttpClientUrl {
+    pub fn parse(url: &str) -> Result<HttpClientUrl, HttpClientError> {
+        let invalid = || HttpClientError::InvalidUrl(url.to_string());
+        let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+        let scheme = scheme.to_ascii_lowercase();
+        let default_port = match scheme.as_str() {
+            "http" => 80,
+            "https" => 443,
+            _ => return Err(HttpClientError::UnsupportedScheme(scheme))
+        };
+        let (authority, path) = match rest.find(['/', '?', '#']) {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/")
+        };
+        // user info isn't supported, but shouldn't end up in the host either
+        let authority = authority.rsplit('@').next().unwrap();
+        let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+            let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            (host, rest.strip_prefix(':'))
+        }
+        else {
+            match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None)
+            }
+        };
+        if host.is_empty() {
+            return Err(invalid())
+        }
+        let port = match port {
+            Some(port) if !port.is_empty() => port.parse().map_err( | _ | invalid())?,
+            _ => default_port
+        };
+        let path = path.split('#').next().unwrap();
+        let path = if path.starts_with('?') {format!("/{}", path)} else {path.to_string()};
+        Ok(HttpClientUrl {
+            scheme,
+            host: host.to_string(),
+            port,
+            path: if path.is_empty() {"/".to_string()} else {path},
+        })
+    }
+
+    /// Resolves a `Location` header, which may be absolute, scheme relative or a path, against this url.
+    pub fn join(&self, location: &str) -> Result<HttpClientUrl, HttpClientError> {
+        if location.contains("://") {
+            return Self::parse(location)
+        }
+        if let Some(rest) = location.strip_prefix("//") {
+            return Self::parse(&format!("{}://{}", self.scheme, rest))
+        }
+        let path = if location.starts_with('/') {
+            location.to_string()
+        }
+        else if location.starts_with('?') {
+            let base = self.path.split('?').next().unwrap();
+            format!("{}{}", base, location)
+        }
+        else {
+            let base = self.path.split('?').next().unwrap();
+            let dir = &base[..base.rfind('/').map( | i | i + 1).unwrap_or(0)];
+            format!("{}{}", dir, location)
+        };
+        Ok(HttpClientUrl {
+            scheme: self.scheme.clone(),
+            host: self.host.clone(),
+            port: self.port,
+            path,
+        })
+    }
+
+    fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {format!("[{}]", self.host)} else {self.host.clone()};
+        let default_port = if self.scheme == "https" {443} else {80};
+        if self.port == default_port {host} else {format!("{}:{}", host, self.port)}
+    }
+
+    fn connection_key(&self) -> String {
+        format!("{}://{}:{}", self.scheme, self.host, self.port)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpClientRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpClientRequest {
+    pub fn new(url: &str, method: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn get(url: &str) -> Self {
+        Self::new(url, "GET")
+    }
+
+    pub fn post(url: &str, body: Vec<u8>) -> Self {
+        let mut request = Self::new(url, "POST");
+        request.body = body;
+        request
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers.iter().any( | (n, _) | n.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpClientResponse {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// The url the response came from, after following redirects
+    pub url: String,
+}
+
+impl HttpClientResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    pub fn body_string(&self) -> Option<String> {
+        String::from_utf8(self.body.clone()).ok()
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find( | (n, _) | n.eq_ignore_ascii_case(name)).map( | (_, v) | v.as_str())
+}
+
+type Connection = BufReader<Box<dyn HttpClientStream>>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BodyFraming {
+    None,
+    Length(u64),
+    Chunked,
+    UntilClose,
+}
+
+enum ChunkState {
+    Size,
+    Data(u64),
+    Done,
+}
+
+/// A response whose body is read as it arrives. The connection goes back to the
+/// client for reuse once the body has been read to the end.
+pub struct HttpClientResponseStream {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub url: String,
+    connection: Option<Connection>,
+    framing: BodyFraming,
+    chunk_state: ChunkState,
+    keep_alive: bool,
+    connection_key: String,
+    pool: ConnectionPool,
+}
+
+impl HttpClientResponseStream {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Reads the rest of the body into a vec.
+    pub fn read_body(&mut self) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        self.read_to_end(&mut body)?;
+        Ok(body)
+    }
+
+    fn finish(&mut self) {
+        self.framing = BodyFraming::None;
+        if let Some(connection) = self.connection.take() {
+            if self.keep_alive {
+                self.pool.put(&self.connection_key, connection);
+            }
+        }
+    }
+
+    fn read_chunked(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let connection = self.connection.as_mut().unwrap();
+        loop {
+            match self.chunk_state {
+                ChunkState::Size => {
+                    let line = read_line(connection)?;
+                    let size = line.split(';').next().unwrap().trim();
+                    let size = u64::from_str_radix(size, 16).map_err( | _ | invalid_data("invalid chunk size"))?;
+                    if size == 0 {
+                        // skip the trailers up to the empty line
+                        while !read_line(connection)?.is_empty() {}
+                        self.chunk_state = ChunkState::Done;
+                        return Ok(0)
+                    }
+                    self.chunk_state = ChunkState::Data(size);
+                }
+                ChunkState::Data(left) => {
+                    let max = (buf.len() as u64).min(left) as usize;
+                    let read = connection.read(&mut buf[..max])?;
+                    if read == 0 {
+                        return Err(io::ErrorKind::UnexpectedEof.into())
+                    }
+                    if left - read as u64 == 0 {
+                        if !read_line(connection)?.is_empty() {
+                            return Err(invalid_data("missing chunk terminator"))
+                        }
+                        self.chunk_state = ChunkState::Size;
+                    }
+                    else {
+                        self.chunk_state = ChunkState::Data(left - read as u64);
+                    }
+                    return Ok(read)
+                }
+                ChunkState::Done => return Ok(0)
+            }
+        }
+    }
+}
+
+impl Read for HttpClientResponseStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+        let read = match self.framing {
+            BodyFraming::None => return Ok(0),
+            BodyFraming::Length(left) => {
+                let max = (buf.len() as u64).min(left) as usize;
+                let read = self.connection.as_mut().unwrap().read(&mut buf[..max])?;
+                if read == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into())
+                }
+                self.framing = BodyFraming::Length(left - read as u64);
+                read
+            }
+            BodyFraming::Chunked => self.read_chunked(buf)?,
+            BodyFraming::UntilClose => self.connection.as_mut().unwrap().read(buf)?,
+        };
+        let done = match self.framing {
+            BodyFraming::Length(left) => left == 0,
+            BodyFraming::Chunked => matches!(self.chunk_state, ChunkState::Done),
+            _ => read == 0
+        };
+        if done {
+            self.finish();
+        }
+        Ok(read)
+    }
+}
+
+fn is_idempotent(method: &str) -> bool {
+    matches!(method, "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS" | "TRACE")
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads a crlf terminated line, without the line ending.
+fn read_line(connection: &mut Connection) -> io::Result<String> {
+    let mut line = Vec::new();
+    // a line that long isn't http anymore
+    let read = connection.by_ref().take(16384).read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into())
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(invalid_data("header line too long"))
+    }
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line).map_err( | _ | invalid_data("header line is not utf8"))
+}
+
+#[derive(Clone, Default)]
+struct ConnectionPool {
+    idle: Arc<Mutex<HashMap<String, Vec<Connection >> >>,
+}
+
+impl ConnectionPool {
+    // keeping a few idle connections per host is plenty for a desktop app
+    const MAX_IDLE_PER_HOST: usize = 4;
+
+    fn take(&self, key: &str) -> Option<Connection> {
+        self.idle.lock().unwrap().get_mut(key).and_then( | idle | idle.pop())
+    }
+
+    fn put(&self, key: &str, connection: Connection) {
+        let mut idle = self.idle.lock().unwrap();
+        let idle = idle.entry(key.to_string()).or_default();
+        if idle.len() < Self::MAX_IDLE_PER_HOST {
+            idle.push(connection);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpClient {
+    pub connect_timeout: Duration,
+    /// How long a read or write on the connection may block, `None` to wait forever
+    pub io_timeout: Option<Duration>,
+    pub max_redirects: usize,
+    pub user_agent: String,
+    tls_connector: Option<HttpClientTlsConnector>,
+    pool: ConnectionPool,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            io_timeout: Some(Duration::from_secs(30)),
+            max_redirects: 10,
+            user_agent: "makepad".to_string(),
+            tls_connector: None,
+            pool: Default::default(),
+        }
+    }
+}
+
+impl HttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_tls_connector(&mut self, connector: HttpClientTlsConnector) {
+        self.tls_connector = Some(connector);
+    }
+
+    /// Sends the request and reads the whole response, following redirects and
+    /// decompressing gzip and deflate bodies.
+    pub fn send(&self, request: &HttpClientRequest) -> Result<HttpClientResponse, HttpClientError> {
+        let mut request = request.clone();
+        if !request.has_header("Accept-Encoding") {
+            request.set_header("Accept-Encoding", "gzip, deflate");
+        }
+        let mut stream = self.send_streaming(&request)?;
+        let body = stream.read_body()?;
+        let body = match stream.header("Content-Encoding").map( | e | e.trim().to_ascii_lowercase()) {
+            Some(encoding) if encoding == "gzip" || encoding == "x-gzip" => {
+                makepad_zune_inflate::DeflateDecoder::new(&body).decode_gzip()
+                    .map_err( | e | HttpClientError::Decompress(format!("{:?}", e)))?
+            }
+            Some(encoding) if encoding == "deflate" => {
+                // deflate is supposed to be zlib wrapped, but plenty of servers send it raw
+                makepad_miniz::decompress_to_vec_zlib(&body)
+                    .or_else( | _ | makepad_miniz::decompress_to_vec(&body))
+                    .map_err( | e | HttpClientError::Decompress(format!("{:?}", e)))?
+            }
+            _ => body
+        };
+        Ok(HttpClientResponse {
+            status: stream.status,
+            reason: std::mem::take(&mut stream.reason),
+            headers: std::mem::take(&mut stream.headers),
+            body,
+            url: std::mem::take(&mut stream.url),
+        })
+    }
+
+    /// Sends the request and returns as soon as the response headers are in, following
+    /// redirects. `Authorization` and `Cookie` headers are dropped once a redirect leaves
+    /// the origin of the request. The body is passed through as is, so only ask for a
+    /// `Content-Encoding` you can decode yourself.
+    pub fn send_streaming(&self, request: &HttpClientRequest) -> Result<HttpClientResponseStream, HttpClientError> {
+        let mut url = HttpClientUrl::parse(&request.url)?;
+        let mut method = request.method.clone();
+        let mut headers = request.headers.clone();
+        let mut body = request.body.clone();
+        let mut redirects = 0;
+        loop {
+            let mut response = self.send_once(&url, &method, &headers, &body)?;
+            let location = match response.status {
+                301 | 302 | 303 | 307 | 308 => response.header("Location").map( | l | l.trim().to_string()),
+                _ => None
+            };
+            let Some(location) = location else {
+                return Ok(response)
+            };
+            if redirects == self.max_redirects {
+                return Err(HttpClientError::TooManyRedirects)
+            }
+            redirects += 1;
+            // drain the redirect body so the connection can be reused
+            let _ = response.read_body();
+            // only 307 and 308 promise the method and body survive the redirect
+            if response.status == 303 || (matches!(response.status, 301 | 302) && method == "POST") {
+                method = "GET".to_string();
+                body.clear();
+            }
+            let next_url = url.join(&location)?;
+            // credentials are only meant for the origin they were given for
+            if next_url.connection_key() != url.connection_key() {
+                headers.retain( | (name, _) | !name.eq_ignore_ascii_case("Authorization") && !name.eq_ignore_ascii_case("Cookie"));
+            }
+            url = next_url;
+        }
+    }
+
+    fn send_once(&self, url: &HttpClientUrl, method: &str, headers: &[(String, String)], body: &[u8]) -> Result<HttpClientResponseStream, HttpClientError> {
+        let key = url.connection_key();
+        let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, url.path, url.host_header());
+        let has = | name: &str | headers.iter().any( | (n, _) | n.eq_ignore_ascii_case(name));
+        if !has("User-Agent") {
+            head.push_str(&format!("User-Agent: {}\r\n", self.user_agent));
+        }
+        if !has("Accept") {
+            head.push_str("Accept: */*\r\n");
+        }
+        if !has("Content-Length") && (!body.is_empty() || matches!(method, "POST" | "PUT" | "PATCH")) {
+            head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+        for (name, value) in headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+
+        // an idle connection may have been closed by the server in the meantime, in which
+        // case the request is retried once on a fresh one. The server may also have gotten
+        // the request before the connection broke, so requests that can't safely run twice
+        // skip the pool and only ever go out on a fresh connection
+        if is_idempotent(method) {
+            if let Some(connection) = self.pool.take(&key) {
+                if let Ok(response) = self.exchange(connection, url, method, &head, body) {
+                    return Ok(response)
+                }
+            }
+        }
+        let connection = self.connect(url)?;
+        self.exchange(connection, url, method, &head, body)
+    }
+
+    fn connect(&self, url: &HttpClientUrl) -> Result<Connection, HttpClientError> {
+        let addrs = (url.host.as_str(), url.port).to_socket_addrs().map_err(HttpClientError::Connect)?;
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "host has no addresses");
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(self.io_timeout)?;
+                    stream.set_write_timeout(self.io_timeout)?;
+                    let _ = stream.set_nodelay(true);
+                    let stream: Box<dyn HttpClientStream> = if url.scheme == "https" {
+                        let Some(tls_connector) = self.tls_connector else {
+                            return Err(HttpClientError::UnsupportedScheme(url.scheme.clone()))
+                        };
+                        tls_connector(&url.host, stream).map_err(HttpClientError::Connect)?
+                    }
+                    else {
+                        Box::new(stream)
+                    };
+                    return Ok(BufReader::new(stream))
+                }
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => return Err(HttpClientError::Timeout),
+                Err(e) => last_error = e
+            }
+        }
+        Err(HttpClientError::Connect(last_error))
+    }
+
+    fn exchange(&self, mut connection: Connection, url: &HttpClientUrl, method: &str, head: &str, body: &[u8]) -> Result<HttpClientResponseStream, HttpClientError> {
+        let stream = connection.get_mut();
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(body)?;
+        stream.flush()?;
+
+        let (status, reason, is_http_1_0) = loop {
+            let status_line = read_line(&mut connection)?;
+            let mut parts = status_line.splitn(3, ' ');
+            let version = parts.next().unwrap_or("");
+            if !version.starts_with("HTTP/1.") {
+                return Err(HttpClientError::InvalidResponse(status_line))
+            }
+            let status: u16 = parts.next().and_then( | s | s.parse().ok())
+                .ok_or_else( | | HttpClientError::InvalidResponse(status_line.clone()))?;
+            let reason = parts.next().unwrap_or("").to_string();
+            // skip interim responses like 100 continue
+            if (100..200).contains(&status) && status != 101 {
+                while !read_line(&mut connection)?.is_empty() {}
+                continue
+            }
+            break (status, reason, version == "HTTP/1.0")
+        };
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(&mut connection)?;
+            if line.is_empty() {
+                break
+            }
+            if headers.len() > 1024 {
+                return Err(HttpClientError::InvalidResponse("too many headers".to_string()))
+            }
+            let (name, value) = line.split_once(':')
+                .ok_or_else( | | HttpClientError::InvalidResponse(line.clone()))?;
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+
+        let connection_header = find_header(&headers, "Connection").map( | v | v.to_ascii_lowercase());
+        let mut keep_alive = match connection_header.as_deref() {
+            Some(v) if v.contains("close") => false,
+            Some(v) if v.contains("keep-alive") => true,
+            _ => !is_http_1_0
+        };
+        let is_chunked = find_header(&headers, "Transfer-Encoding")
+            .map( | v | v.to_ascii_lowercase().contains("chunked"))
+            .unwrap_or(false);
+        let framing = if method == "HEAD" || status == 204 || status == 304 {
+            BodyFraming::None
+        }
+        else if is_chunked {
+            BodyFraming::Chunked
+        }
+        else if let Some(length) = find_header(&headers, "Content-Length") {
+            BodyFraming::Length(length.parse().map_err( | _ | HttpClientError::InvalidResponse(format!("Content-Length: {}", length)))?)
+        }
+        else {
+            keep_alive = false;
+            BodyFraming::UntilClose
+        };
+
+        let mut response = HttpClientResponseStream {
+            status,
+            reason,
+            headers,
+            url: format!("{}://{}{}", url.scheme, url.host_header(), url.path),
+            connection: Some(connection),
+            framing,
+            chunk_state: ChunkState::Size,
+            keep_alive,
+            connection_key: url.connection_key(),
+            pool: self.pool.clone(),
+        };
+        if framing == BodyFraming::None || framing == BodyFraming::Length(0) {
+            response.finish();
+        }
+        Ok(response)
+    }
+}
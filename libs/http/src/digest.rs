@@ -95,6 +95,19 @@ pub fn base64_encode(input: &[u8]) -> String {
 }
 
 
+// bitwise crc32 (the gzip/zip one), plenty fast for the odd compressed response
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xedb8_8320} else {crc >> 1};
+        }
+    }
+    !crc
+}
+
+
 // sha1 digest impl from Rust crypto minus all the crap.
 
 pub const STATE_LEN: usize = 5;
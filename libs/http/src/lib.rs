@@ -2,3 +2,5 @@
  pub mod utils;
  pub mod server;
  pub mod websocket;
+ pub mod client;
+ pub mod router;
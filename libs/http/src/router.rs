@@ -0,0 +1,437 @@
+// A router on top of the HttpServer request channel: path patterns, and a static directory
+// handler that does what browsers expect (mime types, etags, ranges for video, compression).
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use crate::digest::crc32;
+use crate::server::{HttpServerRequest, HttpServerResponse};
+use crate::utils::HttpServerHeaders;
+
+pub struct HttpRouteRequest<'a> {
+    pub headers: &'a HttpServerHeaders,
+    pub params: Vec<(String, String)>,
+    /// Empty for GET and HEAD
+    pub body: &'a [u8],
+}
+
+impl<'a> HttpRouteRequest<'a> {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find( | (n, _) | n == name).map( | (_, v) | v.as_str())
+    }
+}
+
+type HttpRouteHandler = Box<dyn Fn(&HttpRouteRequest) -> HttpServerResponse>;
+
+struct HttpRoute {
+    verb: &'static str,
+    pattern: Vec<String>,
+    handler: HttpRouteHandler,
+}
+
+/// Routes the requests coming out of `start_http_server`:
+/// ```ignore
+/// let mut router = HttpRouter::default();
+/// router.get("/api/user/:id", |req| HttpServerResponse::text(200, req.param("id").unwrap()));
+/// router.static_dir("/", HttpStaticDir::new("www"));
+/// while let Ok(request) = rx_request.recv() {
+///     if let Some(request) = router.handle(request) {
+///         // websockets, and anything no route matched
+///     }
+/// }
+/// ```
+/// Patterns are matched segment by segment, `:name` takes one segment and `*name` the rest
+/// of the path. Segments are percent decoded one by one, and a path with a segment that decodes
+/// to something containing `/` or `\` matches no route. Routes are tried in the order they
+/// were added.
+#[derive(Default)]
+pub struct HttpRouter {
+    routes: Vec<HttpRoute>,
+}
+
+impl HttpRouter {
+    /// Also answers HEAD requests, the server leaves the body out
+    pub fn get<F>(&mut self, pattern: &str, handler: F) where F: Fn(&HttpRouteRequest) -> HttpServerResponse + 'static {
+        self.add_route("GET", pattern, Box::new(handler));
+    }
+
+    pub fn post<F>(&mut self, pattern: &str, handler: F) where F: Fn(&HttpRouteRequest) -> HttpServerResponse + 'static {
+        self.add_route("POST", pattern, Box::new(handler));
+    }
+
+    /// Serves the files in `dir` under `prefix`
+    pub fn static_dir(&mut self, prefix: &str, dir: HttpStaticDir) {
+        let pattern = format!("{}/*path", prefix.trim_end_matches('/'));
+        self.get(&pattern, move | req | dir.respond(req.headers, req.param("path").unwrap_or("")));
+    }
+
+    fn add_route(&mut self, verb: &'static str, pattern: &str, handler: HttpRouteHandler) {
+        let pattern = split_path(pattern).map( | s | s.to_string()).collect();
+        self.routes.push(HttpRoute {verb, pattern, handler});
+    }
+
+    /// Answers the request if a route matches it, otherwise hands it back
+    pub fn handle(&self, request: HttpServerRequest) -> Option<HttpServerRequest> {
+        match request {
+            HttpServerRequest::Get {headers, response_sender} => {
+                if let Some(response) = self.route("GET", &headers, &[]) {
+                    let _ = response_sender.send(response);
+                    return None
+                }
+                Some(HttpServerRequest::Get {headers, response_sender})
+            }
+            HttpServerRequest::Post {headers, body, response} => {
+                if let Some(res) = self.route("POST", &headers, &body) {
+                    let _ = response.send(res);
+                    return None
+                }
+                Some(HttpServerRequest::Post {headers, body, response})
+            }
+            request => Some(request)
+        }
+    }
+
+    fn route(&self, verb: &str, headers: &HttpServerHeaders, body: &[u8]) -> Option<HttpServerResponse> {
+        for route in self.routes.iter().filter( | r | r.verb == verb) {
+            if let Some(params) = match_pattern(&route.pattern, &headers.path) {
+                return Some((route.handler)(&HttpRouteRequest {headers, params, body}))
+            }
+        }
+        None
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter( | s | !s.is_empty())
+}
+
+fn match_pattern(pattern: &[String], path: &str) -> Option<Vec<(String, String)>> {
+    let mut params = Vec::new();
+    let mut segments = split_path(path);
+    for part in pattern {
+        if let Some(name) = part.strip_prefix('*') {
+            let rest = segments.map(decode_segment).collect::<Option<Vec<String>>>()?;
+            params.push((name.to_string(), rest.join("/")));
+            return Some(params)
+        }
+        let segment = segments.next()?;
+        if let Some(name) = part.strip_prefix(':') {
+            params.push((name.to_string(), decode_segment(segment)?));
+        }
+        else if part != segment {
+            return None
+        }
+    }
+    if segments.next().is_some() {
+        return None
+    }
+    Some(params)
+}
+
+// an encoded separator would otherwise turn into a path separator after splitting
+fn decode_segment(segment: &str) -> Option<String> {
+    percent_decode(segment).filter( | s | !s.contains(['/', '\\']))
+}
+
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        }
+        else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+impl HttpServerResponse {
+    pub fn new(status: u16, extra_headers: &str, body: Vec<u8>) -> Self {
+        Self {
+            header: format!(
+                "HTTP/1.1 {} {}\r\n{}Content-Length: {}\r\n\r\n",
+                status,
+                http_status_reason(status),
+                extra_headers,
+                body.len()
+            ),
+            body
+        }
+    }
+
+    pub fn text(status: u16, text: &str) -> Self {
+        Self::new(status, "Content-Type: text/plain; charset=utf-8\r\n", text.as_bytes().to_vec())
+    }
+}
+
+pub fn http_status_reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        500 => "Internal Server Error",
+        _ => ""
+    }
+}
+
+pub fn http_mime_type(path: &str) -> &'static str {
+    let ext = path.rsplit_once('.').map( | (_, ext) | ext).unwrap_or("");
+    match ext.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" | "rs" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "wasm" => "application/wasm",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream"
+    }
+}
+
+fn is_compressible(mime_type: &str) -> bool {
+    mime_type.starts_with("text/") || mime_type.starts_with("application/json") || mime_type.starts_with("application/xml") ||
+    mime_type.starts_with("application/wasm") || mime_type.starts_with("image/svg+xml")
+}
+
+/// The file types a wasm build needs, for servers that shouldn't hand out anything else
+pub const WEB_ASSET_EXTENSIONS: &[&str] = &["html", "wasm", "css", "js", "ttf", "woff", "woff2", "png", "jpg", "svg"];
+
+/// Serves files from a directory. Files with a `.br` or `.gz` sibling get that sent to clients
+/// that accept it, other text-like files are gzipped on the fly when `compress` is set
+/// (there's no brotli encoder here, so brotli only comes precompressed).
+/// Hidden files and directories such as `.git` or `.env` are never served.
+pub struct HttpStaticDir {
+    pub root: PathBuf,
+    /// Added to every response, for instance the cross origin isolation headers wasm threads need
+    pub extra_headers: String,
+    pub cache_control: String,
+    pub compress: bool,
+    /// When set only files with one of these extensions are served
+    pub extensions: Option<&'static [&'static str]>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Encoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+impl HttpStaticDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            extra_headers: String::new(),
+            cache_control: "no-cache".to_string(),
+            compress: true,
+            extensions: None,
+        }
+    }
+
+    /// The response for `path`, relative to the root and already percent decoded
+    pub fn respond(&self, headers: &HttpServerHeaders, path: &str) -> HttpServerResponse {
+        if path.contains('\\') || path.contains('\0') {
+            return HttpServerResponse::text(403, "Forbidden")
+        }
+        // only plain names, so neither `..` nor an absolute path can leave the root
+        let mut file_path = self.root.clone();
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(name) if name.to_string_lossy().starts_with('.') => {
+                    return HttpServerResponse::text(404, "Not Found")
+                }
+                Component::Normal(name) => file_path.push(name),
+                _ => return HttpServerResponse::text(403, "Forbidden")
+            }
+        }
+        if file_path.is_dir() {
+            // relative links in the index need the trailing slash
+            if !headers.path.ends_with('/') {
+                let location = format!("Location: {}/\r\n", headers.path);
+                return HttpServerResponse::new(301, &location, Vec::new())
+            }
+            file_path.push("index.html");
+        }
+        if let Some(extensions) = self.extensions {
+            let ext = file_path.extension().map(| ext | ext.to_string_lossy().to_ascii_lowercase());
+            if !ext.is_some_and( | ext | extensions.contains(&ext.as_str())) {
+                return HttpServerResponse::text(404, "Not Found")
+            }
+        }
+        let Ok(metadata) = fs::metadata(&file_path) else {
+            return HttpServerResponse::text(404, "Not Found")
+        };
+        let mime_type = http_mime_type(&file_path.to_string_lossy());
+        let mut common = format!("Content-Type: {}\r\nCache-Control: {}\r\n{}", mime_type, self.cache_control, self.extra_headers);
+
+        let range = headers.header("Range").filter( | _ | {
+            // a stale If-Range means the client wants the whole new file
+            headers.header("If-Range").is_none_or( | tag | tag == file_etag(&metadata, Encoding::Identity))
+        });
+        let encoding = if range.is_none() && is_compressible(mime_type) {
+            common.push_str("Vary: Accept-Encoding\r\n");
+            self.pick_encoding(headers, &file_path)
+        }
+        else {
+            Encoding::Identity
+        };
+
+        let precompressed = match encoding {
+            Encoding::Brotli => Some(append_ext(&file_path, "br")),
+            Encoding::Gzip => Some(append_ext(&file_path, "gz")).filter( | p | p.is_file()),
+            Encoding::Identity => None
+        };
+        let (file_path, metadata) = match precompressed.and_then( | p | Some((fs::metadata(&p).ok()?, p))) {
+            Some((metadata, path)) => (path, metadata),
+            None => (file_path, metadata)
+        };
+        let etag = file_etag(&metadata, encoding);
+        common.push_str(&format!("ETag: {}\r\nAccept-Ranges: bytes\r\n", etag));
+
+        if let Some(if_none_match) = headers.header("If-None-Match") {
+            let matches = if_none_match.split(',').any( | tag | {
+                let tag = tag.trim();
+                tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag
+            });
+            if matches {
+                return HttpServerResponse {
+                    header: format!("HTTP/1.1 304 Not Modified\r\n{}\r\n", common),
+                    body: Vec::new()
+                }
+            }
+        }
+
+        let len = metadata.len();
+        if let Some(range) = range {
+            let Some((start, end)) = parse_range(range, len) else {
+                common.push_str(&format!("Content-Range: bytes */{}\r\n", len));
+                return HttpServerResponse::new(416, &common, Vec::new())
+            };
+            let Some(body) = read_file_range(&file_path, start, end - start) else {
+                return HttpServerResponse::text(500, "Cannot read file")
+            };
+            common.push_str(&format!("Content-Range: bytes {}-{}/{}\r\n", start, end - 1, len));
+            return HttpServerResponse::new(206, &common, body)
+        }
+
+        let Ok(body) = fs::read(&file_path) else {
+            return HttpServerResponse::text(500, "Cannot read file")
+        };
+        match encoding {
+            Encoding::Brotli => common.push_str("Content-Encoding: br\r\n"),
+            Encoding::Gzip => common.push_str("Content-Encoding: gzip\r\n"),
+            Encoding::Identity => ()
+        }
+        // precompressed files are sent as they are, the rest is compressed here
+        if encoding == Encoding::Gzip && !file_path.to_string_lossy().ends_with(".gz") {
+            return HttpServerResponse::new(200, &common, gzip(&body))
+        }
+        HttpServerResponse::new(200, &common, body)
+    }
+
+    fn pick_encoding(&self, headers: &HttpServerHeaders, file_path: &Path) -> Encoding {
+        let accept = headers.header("Accept-Encoding").unwrap_or("");
+        let accepts = | name: &str | accept.split(',').any( | part | {
+            let mut parts = part.split(';');
+            parts.next().unwrap().trim().eq_ignore_ascii_case(name) && !parts.any( | p | {
+                p.trim().strip_prefix("q=").and_then( | q | q.trim().parse::<f32>().ok()) == Some(0.0)
+            })
+        });
+        if accepts("br") && append_ext(file_path, "br").is_file() {
+            return Encoding::Brotli
+        }
+        if accepts("gzip") && (self.compress || append_ext(file_path, "gz").is_file()) {
+            return Encoding::Gzip
+        }
+        Encoding::Identity
+    }
+}
+
+fn append_ext(path: &Path, ext: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(ext);
+    PathBuf::from(path)
+}
+
+fn file_etag(metadata: &fs::Metadata, encoding: Encoding) -> String {
+    let modified = metadata.modified().ok()
+        .and_then( | m | m.duration_since(UNIX_EPOCH).ok())
+        .map( | d | d.as_nanos())
+        .unwrap_or(0);
+    let suffix = match encoding {
+        Encoding::Identity => "",
+        Encoding::Gzip => "-gz",
+        Encoding::Brotli => "-br",
+    };
+    format!("\"{:x}-{:x}{}\"", metadata.len(), modified, suffix)
+}
+
+/// A single `bytes=` range as start and end (exclusive), None when it can't be satisfied.
+/// Multiple ranges are answered with the first one, which clients accept.
+fn parse_range(range: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = range.trim().strip_prefix("bytes=")?.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = if start.is_empty() {
+        // the last n bytes
+        let suffix: u64 = end.parse().ok()?;
+        (len.saturating_sub(suffix), len)
+    }
+    else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {len} else {end.parse::<u64>().ok()?.saturating_add(1).min(len)};
+        (start, end)
+    };
+    if start >= end {
+        return None
+    }
+    Some((start, end))
+}
+
+fn read_file_range(path: &Path, start: u64, len: u64) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut body = vec![0u8; len as usize];
+    file.read_exact(&mut body).ok()?;
+    Some(body)
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    out.extend(makepad_miniz::compress_to_vec(data, 6));
+    out.extend(crc32(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
@@ -7,6 +7,8 @@ use std::time::Duration;
 pub use crate::websocket::{SERVER_WEB_SOCKET_PONG_MESSAGE, ServerWebSocket, ServerWebSocketMessage, ServerWebSocketMessageFormat, ServerWebSocketMessageHeader, SERVER_WEB_SOCKET_PING_MESSAGE};
 use crate::utils::*;
 
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(15);
+
 #[derive(Clone)]
 pub struct HttpServer {
     pub listen_address: SocketAddr,
@@ -19,6 +21,24 @@ pub struct HttpServerResponse {
     pub body: Vec<u8>
 }
 
+impl HttpServerResponse {
+    /// The connection can only be reused when the client knows where the body ends
+    fn can_keep_alive(&self) -> bool {
+        let mut has_length = false;
+        for line in self.header.split("\r\n").skip(1) {
+            let Some((name, value)) = line.split_once(':') else {continue};
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("Connection") && value.trim().eq_ignore_ascii_case("close") {
+                return false
+            }
+            if name.eq_ignore_ascii_case("Content-Length") || name.eq_ignore_ascii_case("Transfer-Encoding") {
+                has_length = true;
+            }
+        }
+        has_length || self.header.starts_with("HTTP/1.1 304") || self.header.starts_with("HTTP/1.1 204")
+    }
+}
+
 pub enum HttpServerRequest {
     ConnectWebSocket {
         web_socket_id: u64,
@@ -64,23 +84,38 @@ pub fn start_http_server(
                 let http_server = http_server.clone();
                 connection_counter += 1;
                 let _read_thread = std::thread::spawn(move || {
-                    
-                    let headers = HttpServerHeaders::from_tcp_stream(&mut tcp_stream);
-                    if headers.is_none() {
-                        return http_error_out(tcp_stream, 500);
-                    }
-                    let headers = headers.unwrap();
-                    
-                    if headers.sec_websocket_key.is_some() {
-                        return handle_web_socket(http_server, tcp_stream, headers, connection_counter);
-                    }
-                    if headers.verb == "POST" {
-                        return handle_post(http_server, tcp_stream, headers);
-                    }
-                    if headers.verb == "GET" {
-                        return handle_get(http_server, tcp_stream, headers);
+                    let mut is_first_request = true;
+                    loop {
+                        let headers = HttpServerHeaders::from_tcp_stream(&mut tcp_stream);
+                        if headers.is_none() {
+                            // a kept alive connection going quiet is not an error
+                            if is_first_request {
+                                return http_error_out(tcp_stream, 500);
+                            }
+                            let _ = tcp_stream.shutdown(Shutdown::Both);
+                            return
+                        }
+                        let headers = headers.unwrap();
+                        
+                        if headers.sec_websocket_key.is_some() {
+                            return handle_web_socket(http_server, tcp_stream, headers, connection_counter);
+                        }
+                        let keep_alive = if headers.verb == "POST" {
+                            handle_post(&http_server, &mut tcp_stream, headers)
+                        }
+                        else if headers.verb == "GET" || headers.verb == "HEAD" {
+                            handle_get(&http_server, &mut tcp_stream, headers)
+                        }
+                        else {
+                            return http_error_out(tcp_stream, 500)
+                        };
+                        if !keep_alive {
+                            let _ = tcp_stream.shutdown(Shutdown::Both);
+                            return
+                        }
+                        is_first_request = false;
+                        let _ = tcp_stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT));
                     }
-                    http_error_out(tcp_stream, 500)
                 });
             }
         })
@@ -88,14 +123,14 @@ pub fn start_http_server(
     Some(listen_thread)
 }
 
-fn handle_post(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders) {
+fn handle_post(http_server: &HttpServer, tcp_stream: &mut TcpStream, headers: HttpServerHeaders) -> bool {
     // we have to have a content-length or bust
     if headers.content_length.is_none() {
-        return http_error_out(tcp_stream, 500);
+        return error_response(tcp_stream, 500);
     }
     let content_length = headers.content_length.unwrap();
     if content_length > http_server.post_max_size {
-        return http_error_out(tcp_stream, 500);
+        return error_response(tcp_stream, 500);
     }
     let bytes_total = content_length as usize;
     let mut body = Vec::new();
@@ -106,29 +141,39 @@ fn handle_post(http_server: HttpServer, mut tcp_stream: TcpStream, headers: Http
         let buf = &mut body[(bytes_total - bytes_left)..bytes_total];
         let bytes_read = tcp_stream.read(buf);
         if bytes_read.is_err() {
-            return http_error_out(tcp_stream, 500);
+            return error_response(tcp_stream, 500);
         }
         let bytes_read = bytes_read.unwrap();
         if bytes_read == 0 {
-            return http_error_out(tcp_stream, 500);
+            return error_response(tcp_stream, 500);
         }
         bytes_left -= bytes_read;
     }
     
+    let wants_keep_alive = headers.wants_keep_alive();
     let (tx_socket, rx_socket) = mpsc::channel::<HttpServerResponse> ();
     if http_server.request.send(HttpServerRequest::Post {
         headers,
         body,
         response: tx_socket
     }).is_err() {
-        return http_error_out(tcp_stream, 500);
+        return error_response(tcp_stream, 500);
     };
     
     if let Ok(response) = rx_socket.recv() {
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, response.header.as_bytes());
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, &response.body);
+        if write_bytes_to_tcp_stream_no_error(tcp_stream, response.header.as_bytes()) ||
+        write_bytes_to_tcp_stream_no_error(tcp_stream, &response.body) {
+            return false
+        }
+        return wants_keep_alive && response.can_keep_alive()
     }
-    let _ = tcp_stream.shutdown(Shutdown::Both);
+    false
+}
+
+/// Writes an error response, and returns that the connection should close
+fn error_response(tcp_stream: &mut TcpStream, code: usize) -> bool {
+    write_bytes_to_tcp_stream_no_error(tcp_stream, format!("HTTP/1.1 {}\r\n\r\n", code).as_bytes());
+    false
 }
 
 fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders, web_socket_id: u64) {
@@ -226,19 +271,24 @@ fn handle_web_socket(http_server: HttpServer, mut tcp_stream: TcpStream, headers
     });
 }
 
-fn handle_get(http_server: HttpServer, mut tcp_stream: TcpStream, headers: HttpServerHeaders) {
+fn handle_get(http_server: &HttpServer, tcp_stream: &mut TcpStream, headers: HttpServerHeaders) -> bool {
+    let wants_keep_alive = headers.wants_keep_alive();
+    let is_head = headers.verb == "HEAD";
     // send our channel the post
     let (tx_socket, rx_socket) = mpsc::channel::<HttpServerResponse> ();
     if http_server.request.send(HttpServerRequest::Get {
         headers,
         response_sender: tx_socket
     }).is_err() {
-        return http_error_out(tcp_stream, 500);
+        return error_response(tcp_stream, 500);
     };
     
     if let Ok(response) = rx_socket.recv() {
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, response.header.as_bytes());
-        write_bytes_to_tcp_stream_no_error(&mut tcp_stream, &response.body);
+        if write_bytes_to_tcp_stream_no_error(tcp_stream, response.header.as_bytes()) ||
+        !is_head && write_bytes_to_tcp_stream_no_error(tcp_stream, &response.body) {
+            return false
+        }
+        return wants_keep_alive && response.can_keep_alive()
     }
-    let _ = tcp_stream.shutdown(Shutdown::Both);
-}
\ No newline at end of file
+    false
+}
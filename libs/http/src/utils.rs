@@ -1,5 +1,4 @@
 use std::net::{TcpStream, Shutdown, SocketAddr};
-use std::io::BufReader;
 use std::io::prelude::*;
 
 pub fn write_bytes_to_tcp_stream_no_error(tcp_stream: &mut TcpStream, bytes: &[u8]) -> bool {
@@ -58,6 +57,33 @@ pub fn parse_url_path(url: &str) -> Option<(String, Option<String>)> {
     Some((url, search))
 }
 
+/// Reads up to and including the empty line that ends the headers, and not a byte further,
+/// so whatever follows is still in the socket for the body reader.
+pub fn read_head_from_tcp_stream(tcp_stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let peeked = tcp_stream.peek(&mut buf).ok()?;
+        if peeked == 0 {
+            return None
+        }
+        // the terminator can straddle the previous read
+        let search_from = head.len().saturating_sub(3);
+        let consumed = head.len();
+        head.extend_from_slice(&buf[0..peeked]);
+        if let Some(pos) = head[search_from..].windows(4).position( | w | w == b"\r\n\r\n") {
+            let end = search_from + pos + 4;
+            head.truncate(end);
+            tcp_stream.read_exact(&mut buf[0..end - consumed]).ok()?;
+            return Some(head)
+        }
+        tcp_stream.read_exact(&mut buf[0..peeked]).ok()?;
+        if head.len() > 65536 { // some overflow protection
+            return None
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct HttpServerHeaders {
     pub addr: SocketAddr,
@@ -74,34 +100,33 @@ pub struct HttpServerHeaders {
 impl HttpServerHeaders {
     pub fn from_tcp_stream(tcp_stream: &mut TcpStream) -> Option<HttpServerHeaders> {
         let addr = tcp_stream.peer_addr().unwrap();
-        let mut reader = BufReader::new(tcp_stream);
+        let head = read_head_from_tcp_stream(tcp_stream)?;
+        let head = String::from_utf8(head).ok()?;
         
         let mut lines = Vec::new();
         let mut content_length = None;
         let mut accept_encoding = None;
         let mut sec_websocket_key = None;
-        let mut line = String::new();
         
-        while reader.read_line(&mut line).is_ok() { // TODO replace this with a non-line read
+        for line in head.split_inclusive("\r\n") {
             if line == "\r\n" { // the newline
                 break;
             }
-            if let Some(v) = split_header_line(&line, "Content-Length: ") {
+            if let Some(v) = split_header_line(line, "Content-Length: ") {
                 content_length = Some(if let Ok(v) = v.parse() {v} else {
                     return None
                 });
             }
-            if let Some(v) = split_header_line(&line, "Accept-Encoding: ") {
+            if let Some(v) = split_header_line(line, "Accept-Encoding: ") {
                 accept_encoding = Some(v.to_string());
             }
-            if let Some(v) = split_header_line(&line, "sec-websocket-key: ") {
+            if let Some(v) = split_header_line(line, "sec-websocket-key: ") {
                 sec_websocket_key = Some(v.to_string());
             }
             if line.len() > 4096 || lines.len() > 4096 { // some overflow protection
                 return None
             }
-            lines.push(line.clone());
-            line.clear();
+            lines.push(line.to_string());
         }
         if lines.len() <2 {
             return None;
@@ -124,6 +149,10 @@ impl HttpServerHeaders {
             verb = "DELETE";
             path = parse_url_path(v)
         }
+        else if let Some(v) = split_header_line(&lines[0], "HEAD ") {
+            verb = "HEAD";
+            path = parse_url_path(v)
+        }
         else {
             return None
         }
@@ -142,4 +171,24 @@ impl HttpServerHeaders {
             sec_websocket_key
         })
     }
+    
+    /// The value of the first header called `name`, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.lines.iter().skip(1).find_map( | line | {
+            let (n, v) = line.split_once(':')?;
+            if n.trim().eq_ignore_ascii_case(name) {Some(v.trim())} else {None}
+        })
+    }
+    
+    /// HTTP/1.1 keeps the connection open unless told otherwise, HTTP/1.0 only when asked
+    pub fn wants_keep_alive(&self) -> bool {
+        let connection = self.header("Connection").unwrap_or("");
+        let has = | token: &str | connection.split(',').any( | v | v.trim().eq_ignore_ascii_case(token));
+        if self.lines[0].trim_end().ends_with("HTTP/1.1") {
+            !has("close")
+        }
+        else {
+            has("keep-alive")
+        }
+    }
 }
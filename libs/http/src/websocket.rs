@@ -1,5 +1,8 @@
 
 use crate::digest::{Sha1, base64_encode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, PartialEq)]
@@ -134,10 +137,8 @@ impl ServerWebSocketMessageHeader {
         }
     }
 
-    // TODO Improve this using a proper random number generator
     fn random_byte() -> u8 {
-        let num = SystemTime::now().duration_since(UNIX_EPOCH).expect("duration_since failed").subsec_nanos();
-        num as u8
+        web_socket_random_u64() as u8
     }
 }
 
@@ -176,11 +177,7 @@ impl ServerWebSocket {
     }
 
     pub fn create_upgrade_response(key: &str) -> String {
-        let to_hash = format!("{}258EAFA5-E914-47DA-95CA-C5AB0DC85B11", key);
-        let mut sha1 = Sha1::new();
-        sha1.update(to_hash.as_bytes());
-        let out_bytes = sha1.finalise();
-        let base64 = base64_encode(&out_bytes);
+        let base64 = web_socket_accept_key(key);
         let response_ack = format!(
             "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
             base64
@@ -373,3 +370,399 @@ impl Default for ServerWebSocket {
     }
 }
 
+
+// Client side of RFC 6455, with the permessage-deflate extension from RFC 7692
+
+const WEB_SOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+pub const WEB_SOCKET_CLOSE_NORMAL: u16 = 1000;
+pub const WEB_SOCKET_CLOSE_PROTOCOL_ERROR: u16 = 1002;
+/// Not sent over the wire, reported when a close frame carried no code
+pub const WEB_SOCKET_CLOSE_NO_STATUS: u16 = 1005;
+/// Not sent over the wire, reported when the connection dropped without a close frame
+pub const WEB_SOCKET_CLOSE_ABNORMAL: u16 = 1006;
+pub const WEB_SOCKET_CLOSE_INVALID_PAYLOAD: u16 = 1007;
+pub const WEB_SOCKET_CLOSE_MESSAGE_TOO_BIG: u16 = 1009;
+
+const OPCODE_CONTINUATION: u8 = 0;
+const OPCODE_TEXT: u8 = 1;
+const OPCODE_BINARY: u8 = 2;
+const OPCODE_CLOSE: u8 = 8;
+const OPCODE_PING: u8 = 9;
+const OPCODE_PONG: u8 = 10;
+
+// the empty stored block a sync flush ends with, which permessage-deflate strips from every message
+const DEFLATE_SYNC_TAIL: [u8; 4] = [0, 0, 0xff, 0xff];
+// an empty final block, so the inflater sees the end of the stream
+const DEFLATE_FINAL_BLOCK: [u8; 2] = [3, 0];
+
+/// The `Sec-WebSocket-Accept` value for a `Sec-WebSocket-Key`.
+pub fn web_socket_accept_key(key: &str) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(key.trim().as_bytes());
+    sha1.update(WEB_SOCKET_GUID.as_bytes());
+    base64_encode(&sha1.finalise())
+}
+
+/// Unpredictable numbers for handshake keys and frame masks. `RandomState` is seeded
+/// from the OS, which is all the randomness std gives us without another dependency.
+fn web_socket_random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).map( | d | d.as_nanos()).unwrap_or(0));
+    hasher.finish()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ClientWebSocketMessage<'a> {
+    Text(&'a str),
+    Binary(&'a [u8]),
+    Ping(&'a [u8]),
+    Pong(&'a [u8]),
+    /// The server closed the connection, with `WEB_SOCKET_CLOSE_NO_STATUS` when it gave no code
+    Close {code: u16, reason: &'a str},
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ClientWebSocketError {
+    Protocol(&'static str),
+    InvalidUtf8,
+    MessageTooBig,
+    InvalidDeflate,
+}
+
+impl ClientWebSocketError {
+    /// The code to close the connection with after this error
+    pub fn close_code(&self) -> u16 {
+        match self {
+            Self::Protocol(_) => WEB_SOCKET_CLOSE_PROTOCOL_ERROR,
+            Self::InvalidUtf8 | Self::InvalidDeflate => WEB_SOCKET_CLOSE_INVALID_PAYLOAD,
+            Self::MessageTooBig => WEB_SOCKET_CLOSE_MESSAGE_TOO_BIG,
+        }
+    }
+}
+
+/// A client connection after the upgrade handshake: parses the frames the server sends
+/// and builds the masked frames the client sends. A clone made right after the handshake
+/// can build frames on a writer thread while the original parses.
+#[derive(Clone)]
+pub struct ClientWebSocket {
+    /// The subprotocol the server picked out of the offered ones
+    pub protocol: Option<String>,
+    /// Whether permessage-deflate was negotiated
+    pub deflate: bool,
+    pub max_message_size: usize,
+    input: Vec<u8>,
+    message: Vec<u8>,
+    message_opcode: Option<u8>,
+    message_compressed: bool,
+    failed: bool,
+}
+
+impl ClientWebSocket {
+    pub fn create_key() -> String {
+        let mut key = [0u8; 16];
+        key[0..8].copy_from_slice(&web_socket_random_u64().to_le_bytes());
+        key[8..16].copy_from_slice(&web_socket_random_u64().to_le_bytes());
+        base64_encode(&key)
+    }
+
+    /// The upgrade request for `path` on `host`. `extra_headers` are `\r\n` terminated lines.
+    pub fn create_upgrade_request(host: &str, path: &str, key: &str, protocols: &[&str], deflate: bool, extra_headers: &str) -> String {
+        let mut request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: {}\r\n",
+            path,
+            host,
+            key
+        );
+        if !protocols.is_empty() {
+            request.push_str(&format!("Sec-WebSocket-Protocol: {}\r\n", protocols.join(", ")));
+        }
+        if deflate {
+            // without context takeover every message compresses on its own, so neither side keeps a window around
+            request.push_str("Sec-WebSocket-Extensions: permessage-deflate; client_no_context_takeover; server_no_context_takeover\r\n");
+        }
+        request.push_str(extra_headers);
+        request.push_str("\r\n");
+        request
+    }
+
+    /// Checks the response head, up to and including the empty line, against the request that was sent.
+    pub fn from_upgrade_response(response: &str, key: &str, protocols: &[&str], deflate: bool) -> Result<ClientWebSocket, String> {
+        let mut lines = response.split("\r\n");
+        let status_line = lines.next().unwrap_or("");
+        let status = status_line.split(' ').nth(1).unwrap_or("");
+        if status != "101" {
+            return Err(format!("Server refused the websocket upgrade: {}", status_line))
+        }
+        let mut upgrade = false;
+        let mut connection = false;
+        let mut accept = None;
+        let mut protocol = None;
+        let mut extensions = Vec::new();
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {continue};
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "upgrade" => upgrade = value.eq_ignore_ascii_case("websocket"),
+                "connection" => connection = value.split(',').any( | v | v.trim().eq_ignore_ascii_case("upgrade")),
+                "sec-websocket-accept" => accept = Some(value.to_string()),
+                "sec-websocket-protocol" => protocol = Some(value.to_string()),
+                "sec-websocket-extensions" => extensions.extend(value.split(',').map( | v | v.trim().to_string())),
+                _ => ()
+            }
+        }
+        if !upgrade || !connection {
+            return Err("Websocket upgrade response is missing the Upgrade or Connection header".to_string())
+        }
+        if accept.as_deref() != Some(web_socket_accept_key(key).as_str()) {
+            return Err("Websocket upgrade response has the wrong Sec-WebSocket-Accept".to_string())
+        }
+        if let Some(protocol) = &protocol {
+            if !protocols.contains(&protocol.as_str()) {
+                return Err(format!("Server picked a websocket subprotocol that wasn't offered: {}", protocol))
+            }
+        }
+        let mut deflate_accepted = false;
+        for extension in extensions.iter().filter( | e | !e.is_empty()) {
+            let mut params = extension.split(';').map( | p | p.trim());
+            if params.next() != Some("permessage-deflate") || !deflate || deflate_accepted {
+                return Err(format!("Server enabled a websocket extension that wasn't offered: {}", extension))
+            }
+            let mut server_no_context_takeover = false;
+            for param in params {
+                match param.split('=').next().unwrap().trim() {
+                    "server_no_context_takeover" => server_no_context_takeover = true,
+                    // we inflate with a full window anyway
+                    "client_no_context_takeover" | "server_max_window_bits" => (),
+                    _ => return Err(format!("Unsupported permessage-deflate parameter: {}", param))
+                }
+            }
+            if !server_no_context_takeover {
+                return Err("Server didn't accept server_no_context_takeover for permessage-deflate".to_string())
+            }
+            deflate_accepted = true;
+        }
+        Ok(ClientWebSocket {
+            protocol,
+            deflate: deflate_accepted,
+            max_message_size: 64 * 1024 * 1024,
+            input: Vec::new(),
+            message: Vec::new(),
+            message_opcode: None,
+            message_compressed: false,
+            failed: false,
+        })
+    }
+
+    pub fn build_text(&self, text: &str) -> Vec<u8> {
+        self.build_data_frame(OPCODE_TEXT, text.as_bytes())
+    }
+
+    pub fn build_binary(&self, data: &[u8]) -> Vec<u8> {
+        self.build_data_frame(OPCODE_BINARY, data)
+    }
+
+    pub fn build_ping(&self, data: &[u8]) -> Vec<u8> {
+        Self::build_frame(OPCODE_PING, false, &data[0..data.len().min(125)])
+    }
+
+    pub fn build_pong(&self, data: &[u8]) -> Vec<u8> {
+        Self::build_frame(OPCODE_PONG, false, &data[0..data.len().min(125)])
+    }
+
+    pub fn build_close(&self, code: u16, reason: &str) -> Vec<u8> {
+        // codes that only exist locally can't be sent, the frame goes out without one
+        if code == WEB_SOCKET_CLOSE_NO_STATUS || code == WEB_SOCKET_CLOSE_ABNORMAL {
+            return Self::build_frame(OPCODE_CLOSE, false, &[])
+        }
+        let mut payload = code.to_be_bytes().to_vec();
+        let mut end = reason.len().min(123);
+        while !reason.is_char_boundary(end) {
+            end -= 1;
+        }
+        payload.extend_from_slice(&reason.as_bytes()[0..end]);
+        Self::build_frame(OPCODE_CLOSE, false, &payload)
+    }
+
+    fn build_data_frame(&self, opcode: u8, data: &[u8]) -> Vec<u8> {
+        if self.deflate {
+            let compressed = makepad_miniz::compress_to_vec(data, 6);
+            let compressed = compressed.strip_suffix(&DEFLATE_SYNC_TAIL).unwrap_or(&compressed);
+            return Self::build_frame(opcode, true, compressed)
+        }
+        Self::build_frame(opcode, false, data)
+    }
+
+    fn build_frame(opcode: u8, compressed: bool, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(0x80 | if compressed {0x40} else {0} | opcode);
+        if payload.len() < 126 {
+            frame.push(0x80 | payload.len() as u8);
+        }
+        else if payload.len() < 65536 {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        }
+        else {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+        }
+        let mask = (web_socket_random_u64() as u32).to_ne_bytes();
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map( | (i, byte) | byte ^ mask[i & 3]));
+        frame
+    }
+
+    /// Feeds bytes read from the connection. After an error the connection should be
+    /// closed with `ClientWebSocketError::close_code`, later input is ignored.
+    pub fn parse<F>(&mut self, input: &[u8], mut result: F) where F: FnMut(Result<ClientWebSocketMessage, ClientWebSocketError>) {
+        if self.failed {
+            return
+        }
+        self.input.extend_from_slice(input);
+        let mut read = 0;
+        while !self.failed {
+            match self.parse_frame(read, &mut result) {
+                Ok(Some(frame_len)) => read += frame_len,
+                Ok(None) => break,
+                Err(e) => {
+                    self.failed = true;
+                    result(Err(e));
+                }
+            }
+        }
+        self.input.drain(0..read);
+    }
+
+    /// Handles the frame starting at `start` if all of it is there, returning its length.
+    fn parse_frame<F>(&mut self, start: usize, result: &mut F) -> Result<Option<usize>, ClientWebSocketError>
+    where F: FnMut(Result<ClientWebSocketMessage, ClientWebSocketError>) {
+        let input = &self.input[start..];
+        if input.len() < 2 {
+            return Ok(None)
+        }
+        let fin = input[0] & 0x80 != 0;
+        let rsv1 = input[0] & 0x40 != 0;
+        let opcode = input[0] & 0x0f;
+        if input[0] & 0x30 != 0 {
+            return Err(ClientWebSocketError::Protocol("reserved bits set"))
+        }
+        if input[1] & 0x80 != 0 {
+            return Err(ClientWebSocketError::Protocol("server frames must not be masked"))
+        }
+        let (payload_len, header_len) = match input[1] & 0x7f {
+            126 => {
+                if input.len() < 4 {
+                    return Ok(None)
+                }
+                (u16::from_be_bytes([input[2], input[3]]) as u64, 4)
+            }
+            127 => {
+                if input.len() < 10 {
+                    return Ok(None)
+                }
+                let len = u64::from_be_bytes(input[2..10].try_into().unwrap());
+                if len >> 63 != 0 {
+                    return Err(ClientWebSocketError::Protocol("payload length has the high bit set"))
+                }
+                (len, 10)
+            }
+            len => (len as u64, 2)
+        };
+        let is_control = opcode & 0x08 != 0;
+        if is_control && (!fin || payload_len > 125) {
+            return Err(ClientWebSocketError::Protocol("control frames must be short and unfragmented"))
+        }
+        if rsv1 && (!self.deflate || is_control || opcode == OPCODE_CONTINUATION) {
+            return Err(ClientWebSocketError::Protocol("compressed bit set on a frame that can't be compressed"))
+        }
+        if payload_len > self.max_message_size as u64 || self.message.len() as u64 + payload_len > self.max_message_size as u64 {
+            return Err(ClientWebSocketError::MessageTooBig)
+        }
+        let frame_len = header_len + payload_len as usize;
+        if input.len() < frame_len {
+            return Ok(None)
+        }
+        let payload = &input[header_len..frame_len];
+
+        match opcode {
+            OPCODE_TEXT | OPCODE_BINARY => {
+                if self.message_opcode.is_some() {
+                    return Err(ClientWebSocketError::Protocol("new message before the previous one finished"))
+                }
+                if fin {
+                    let payload = payload.to_vec();
+                    self.deliver_message(opcode, rsv1, payload, result)?;
+                }
+                else {
+                    self.message_opcode = Some(opcode);
+                    self.message_compressed = rsv1;
+                    self.message.clear();
+                    self.message.extend_from_slice(payload);
+                }
+            }
+            OPCODE_CONTINUATION => {
+                let Some(message_opcode) = self.message_opcode else {
+                    return Err(ClientWebSocketError::Protocol("continuation frame without a message"))
+                };
+                self.message.extend_from_slice(payload);
+                if fin {
+                    self.message_opcode = None;
+                    let payload = std::mem::take(&mut self.message);
+                    self.deliver_message(message_opcode, self.message_compressed, payload, result)?;
+                }
+            }
+            OPCODE_PING => result(Ok(ClientWebSocketMessage::Ping(payload))),
+            OPCODE_PONG => result(Ok(ClientWebSocketMessage::Pong(payload))),
+            OPCODE_CLOSE => {
+                let (code, reason) = match payload.len() {
+                    0 => (WEB_SOCKET_CLOSE_NO_STATUS, ""),
+                    1 => return Err(ClientWebSocketError::Protocol("close frame with a one byte payload")),
+                    _ => {
+                        let code = u16::from_be_bytes([payload[0], payload[1]]);
+                        if !matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999) {
+                            return Err(ClientWebSocketError::Protocol("invalid close code"))
+                        }
+                        let reason = std::str::from_utf8(&payload[2..]).map_err( | _ | ClientWebSocketError::InvalidUtf8)?;
+                        (code, reason)
+                    }
+                };
+                result(Ok(ClientWebSocketMessage::Close {code, reason}));
+                // nothing may follow a close frame
+                self.failed = true;
+            }
+            _ => return Err(ClientWebSocketError::Protocol("unknown opcode"))
+        }
+        Ok(Some(frame_len))
+    }
+
+    fn deliver_message<F>(&self, opcode: u8, compressed: bool, payload: Vec<u8>, result: &mut F) -> Result<(), ClientWebSocketError>
+    where F: FnMut(Result<ClientWebSocketMessage, ClientWebSocketError>) {
+        let payload = if compressed {
+            let mut deflated = payload;
+            deflated.extend_from_slice(&DEFLATE_SYNC_TAIL);
+            deflated.extend_from_slice(&DEFLATE_FINAL_BLOCK);
+            makepad_miniz::inflate::decompress_to_vec_with_limit(&deflated, self.max_message_size).map_err( | e | {
+                if e == makepad_miniz::inflate::TINFLStatus::HasMoreOutput {
+                    ClientWebSocketError::MessageTooBig
+                }
+                else {
+                    ClientWebSocketError::InvalidDeflate
+                }
+            })?
+        }
+        else {
+            payload
+        };
+        if opcode == OPCODE_TEXT {
+            let text = std::str::from_utf8(&payload).map_err( | _ | ClientWebSocketError::InvalidUtf8)?;
+            result(Ok(ClientWebSocketMessage::Text(text)));
+        }
+        else {
+            result(Ok(ClientWebSocketMessage::Binary(&payload)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+use {
+    makepad_http::{
+        client::{HttpClient, HttpClientError, HttpClientRequest, HttpClientUrl},
+        server::{start_http_server, HttpServer, HttpServerRequest, HttpServerResponse},
+    },
+    std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpListener},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Arc,
+        },
+        time::Duration,
+    },
+};
+
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xedb8_8320} else {crc >> 1};
+        }
+    }
+    !crc
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    out.extend(makepad_miniz::compress_to_vec(data, 6));
+    out.extend(crc32(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+fn response(header: &str, body: &[u8]) -> HttpServerResponse {
+    HttpServerResponse {
+        header: header.to_string(),
+        body: body.to_vec(),
+    }
+}
+
+/// Starts the crate's own server with a handful of test routes and returns its base url.
+fn start_test_server() -> String {
+    let listen_address = free_addr();
+    let (tx_request, rx_request) = mpsc::channel::<HttpServerRequest>();
+    start_http_server(HttpServer {
+        listen_address,
+        post_max_size: 1024 * 1024,
+        request: tx_request,
+    }).unwrap();
+    std::thread::spawn(move || {
+        while let Ok(request) = rx_request.recv() {
+            match request {
+                HttpServerRequest::Get {headers, response_sender} => {
+                    let text = "hello world ".repeat(100);
+                    let res = match headers.path.as_str() {
+                        "/hello" => response("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n", b"hello"),
+                        "/chunked" => response(
+                            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n",
+                            b"5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nX-Trailer: 1\r\n\r\n"
+                        ),
+                        "/until_close" => response("HTTP/1.1 200 OK\r\n\r\n", b"no length"),
+                        "/redirect" => response("HTTP/1.1 302 Found\r\nLocation: /hello\r\nContent-Length: 0\r\n\r\n", b""),
+                        "/redirect_absolute" => response(
+                            &format!("HTTP/1.1 301 Moved\r\nLocation: http://{}/chunked\r\nContent-Length: 0\r\n\r\n", listen_address),
+                            b""
+                        ),
+                        "/redirect_loop" => response("HTTP/1.1 302 Found\r\nLocation: /redirect_loop\r\nContent-Length: 0\r\n\r\n", b""),
+                        "/gzip" => {
+                            let body = gzip(text.as_bytes());
+                            response(&format!("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n", body.len()), &body)
+                        }
+                        "/deflate" => {
+                            let body = makepad_miniz::compress_to_vec_zlib(text.as_bytes(), 6);
+                            response(&format!("HTTP/1.1 200 OK\r\nContent-Encoding: deflate\r\nContent-Length: {}\r\n\r\n", body.len()), &body)
+                        }
+                        "/slow" => {
+                            std::thread::sleep(Duration::from_millis(500));
+                            response("HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\n", b"slow")
+                        }
+                        _ => response("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n", b"")
+                    };
+                    let _ = response_sender.send(res);
+                }
+                HttpServerRequest::Post {headers, body, response} => {
+                    let header = format!("HTTP/1.1 200 OK\r\nX-Path: {}\r\nContent-Length: {}\r\n\r\n", headers.path, body.len());
+                    let _ = response.send(HttpServerResponse {header, body});
+                }
+                _ => ()
+            }
+        }
+    });
+    format!("http://{}", listen_address)
+}
+
+#[test]
+fn parse_url() {
+    let url = HttpClientUrl::parse("http://example.com:8080/a/b?c=d#frag").unwrap();
+    assert_eq!(url.host, "example.com");
+    assert_eq!(url.port, 8080);
+    assert_eq!(url.path, "/a/b?c=d");
+    let url = HttpClientUrl::parse("https://[::1]?q").unwrap();
+    assert_eq!((url.host.as_str(), url.port, url.path.as_str()), ("::1", 443, "/?q"));
+    assert!(matches!(HttpClientUrl::parse("ftp://example.com"), Err(HttpClientError::UnsupportedScheme(_))));
+    assert!(HttpClientUrl::parse("example.com").is_err());
+
+    let base = HttpClientUrl::parse("http://example.com/dir/page?x").unwrap();
+    assert_eq!(base.join("other").unwrap().path, "/dir/other");
+    assert_eq!(base.join("/root").unwrap().path, "/root");
+    assert_eq!(base.join("?y").unwrap().path, "/dir/page?y");
+    assert_eq!(base.join("//other.com/x").unwrap().host, "other.com");
+}
+
+#[test]
+fn content_length_and_chunked_bodies() {
+    let base = start_test_server();
+    let client = HttpClient::new();
+
+    let response = client.send(&HttpClientRequest::get(&format!("{}/hello", base))).unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hello");
+
+    let response = client.send(&HttpClientRequest::get(&format!("{}/chunked", base))).unwrap();
+    assert_eq!(response.body, b"hello world");
+
+    let response = client.send(&HttpClientRequest::get(&format!("{}/until_close", base))).unwrap();
+    assert_eq!(response.body, b"no length");
+
+    let response = client.send(&HttpClientRequest::get(&format!("{}/missing", base))).unwrap();
+    assert_eq!(response.status, 404);
+}
+
+#[test]
+fn post_body() {
+    let base = start_test_server();
+    let client = HttpClient::new();
+    let response = client.send(&HttpClientRequest::post(&format!("{}/echo", base), b"ping".to_vec())).unwrap();
+    assert_eq!(response.header("x-path"), Some("/echo"));
+    assert_eq!(response.body, b"ping");
+}
+
+#[test]
+fn redirects() {
+    let base = start_test_server();
+    let mut client = HttpClient::new();
+
+    let response = client.send(&HttpClientRequest::get(&format!("{}/redirect", base))).unwrap();
+    assert_eq!(response.body, b"hello");
+    assert_eq!(response.url, format!("{}/hello", base));
+
+    let response = client.send(&HttpClientRequest::get(&format!("{}/redirect_absolute", base))).unwrap();
+    assert_eq!(response.body, b"hello world");
+
+    client.max_redirects = 3;
+    let response = client.send(&HttpClientRequest::get(&format!("{}/redirect_loop", base)));
+    assert!(matches!(response, Err(HttpClientError::TooManyRedirects)));
+}
+
+#[test]
+fn compressed_bodies() {
+    let base = start_test_server();
+    let client = HttpClient::new();
+    let text = "hello world ".repeat(100);
+    for path in ["/gzip", "/deflate"] {
+        let response = client.send(&HttpClientRequest::get(&format!("{}{}", base, path))).unwrap();
+        assert_eq!(response.body_string().unwrap(), text, "{}", path);
+    }
+}
+
+#[test]
+fn streaming_body() {
+    let base = start_test_server();
+    let client = HttpClient::new();
+    let mut stream = client.send_streaming(&HttpClientRequest::get(&format!("{}/chunked", base))).unwrap();
+    assert_eq!(stream.status, 200);
+    let mut first = [0u8; 3];
+    stream.read_exact(&mut first).unwrap();
+    assert_eq!(&first, b"hel");
+    assert_eq!(stream.read_body().unwrap(), b"lo world");
+}
+
+#[test]
+fn timeout() {
+    let base = start_test_server();
+    let mut client = HttpClient::new();
+    client.io_timeout = Some(Duration::from_millis(100));
+    let response = client.send(&HttpClientRequest::get(&format!("{}/slow", base)));
+    assert!(matches!(response, Err(HttpClientError::Timeout)));
+}
+
+#[test]
+fn keep_alive() {
+    // written by hand so it can count the connections
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    std::thread::spawn({
+        let accepted = accepted.clone();
+        move || {
+            for stream in listener.incoming() {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    let mut request_line = String::new();
+                    if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                        break
+                    }
+                    while reader.read_line(&mut line).is_ok() && line != "\r\n" {
+                        line.clear();
+                    }
+                    let body = request_line.split(' ').nth(1).unwrap().to_string();
+                    let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+                }
+            }
+        }
+    });
+    let client = HttpClient::new();
+    for path in ["/one", "/two", "/three"] {
+        let response = client.send(&HttpClientRequest::get(&format!("http://{}{}", addr, path))).unwrap();
+        assert_eq!(response.body, path.as_bytes());
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
+
+/// A hand written server that sends the head of every request it reads on the returned
+/// channel and answers with what `respond` returns for it. `None` drops the connection
+/// without an answer, and with `close_after_response` the connection is dropped after
+/// every answer as if it idled out.
+fn start_raw_server(respond: fn(&str) -> Option<String>, close_after_response: bool) -> (SocketAddr, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx_head, rx_head) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let tx_head = tx_head.clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut head = String::new();
+                    while reader.read_line(&mut head).unwrap_or(0) > 0 && !head.ends_with("\r\n\r\n") {}
+                    if head.is_empty() {
+                        break
+                    }
+                    let _ = tx_head.send(head.clone());
+                    let Some(response) = respond(&head) else {
+                        break
+                    };
+                    let _ = stream.write_all(response.as_bytes());
+                    if close_after_response {
+                        break
+                    }
+                }
+            });
+        }
+    });
+    (addr, rx_head)
+}
+
+fn ok_response(_head: &str) -> Option<String> {
+    Some("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_string())
+}
+
+#[test]
+fn idempotent_request_retried_on_stale_connection() {
+    let (addr, heads) = start_raw_server(ok_response, true);
+    let client = HttpClient::new();
+    for _ in 0..2 {
+        let response = client.send(&HttpClientRequest::get(&format!("http://{}/", addr))).unwrap();
+        assert_eq!(response.body, b"ok");
+    }
+    assert_eq!(heads.try_iter().count(), 2);
+}
+
+#[test]
+fn post_not_sent_twice() {
+    // answers a GET, but breaks the connection after reading a POST
+    fn respond(head: &str) -> Option<String> {
+        if head.starts_with("GET") {ok_response(head)} else {None}
+    }
+    let (addr, heads) = start_raw_server(respond, false);
+    let client = HttpClient::new();
+    // leaves a connection in the pool
+    client.send(&HttpClientRequest::get(&format!("http://{}/", addr))).unwrap();
+    let response = client.send(&HttpClientRequest::post(&format!("http://{}/", addr), Vec::new()));
+    assert!(response.is_err());
+    let posts = heads.try_iter().filter( | head | head.starts_with("POST")).count();
+    assert_eq!(posts, 1);
+}
+
+#[test]
+fn redirect_drops_credentials_for_other_origin() {
+    fn respond(_head: &str) -> Option<String> {
+        Some("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_string())
+    }
+    let (target_addr, target_heads) = start_raw_server(respond, false);
+    static TARGET: std::sync::OnceLock<SocketAddr> = std::sync::OnceLock::new();
+    TARGET.set(target_addr).unwrap();
+    fn redirect(head: &str) -> Option<String> {
+        let location = if head.starts_with("GET /same") {
+            "/done".to_string()
+        }
+        else if head.starts_with("GET /other") {
+            format!("http://{}/done", TARGET.get().unwrap())
+        }
+        else {
+            return Some("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_string())
+        };
+        Some(format!("HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\n\r\n", location))
+    }
+    let (addr, heads) = start_raw_server(redirect, false);
+    let client = HttpClient::new();
+    let request = | path: &str | {
+        let mut request = HttpClientRequest::get(&format!("http://{}{}", addr, path));
+        request.set_header("Authorization", "Bearer secret");
+        request.set_header("Cookie", "session=secret");
+        request.set_header("X-Other", "kept");
+        request
+    };
+
+    client.send(&request("/same")).unwrap();
+    let heads: Vec<String> = heads.try_iter().collect();
+    assert_eq!(heads.len(), 2);
+    assert!(heads[1].starts_with("GET /done"));
+    assert!(heads[1].contains("Authorization: Bearer secret\r\n"));
+    assert!(heads[1].contains("Cookie: session=secret\r\n"));
+
+    client.send(&request("/other")).unwrap();
+    let head = target_heads.try_recv().unwrap();
+    assert!(head.starts_with("GET /done"));
+    assert!(!head.contains("secret"));
+    assert!(head.contains("X-Other: kept\r\n"));
+}
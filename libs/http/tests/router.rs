@@ -0,0 +1,258 @@
+use {
+    makepad_http::{
+        client::{HttpClient, HttpClientRequest},
+        router::{HttpRouter, HttpStaticDir, WEB_ASSET_EXTENSIONS},
+        server::{start_http_server, HttpServer, HttpServerRequest, HttpServerResponse},
+        utils::HttpServerHeaders,
+    },
+    std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        path::PathBuf,
+        sync::mpsc,
+    },
+};
+
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("makepad_http_router_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("index.html"), "<html>index</html>").unwrap();
+    std::fs::write(dir.join("app.js"), "console.log('hello');\n".repeat(100)).unwrap();
+    std::fs::write(dir.join("video.mp4"), (0..=255u8).collect::<Vec<u8>>()).unwrap();
+    std::fs::write(dir.join("sub/index.html"), "sub index").unwrap();
+    std::fs::write(dir.join("sub/a b.txt"), "spaced").unwrap();
+    dir
+}
+
+/// Runs the router on the crate's own server and returns the base url
+fn start_router_server(dir: PathBuf) -> String {
+    let listen_address = free_addr();
+    let (tx_request, rx_request) = mpsc::channel::<HttpServerRequest>();
+    start_http_server(HttpServer {
+        listen_address,
+        post_max_size: 1024 * 1024,
+        request: tx_request,
+    }).unwrap();
+    std::thread::spawn(move || {
+        let mut router = HttpRouter::default();
+        router.get("/api/user/:id", | req | HttpServerResponse::text(200, &format!("user {}", req.param("id").unwrap())));
+        router.get("/api/files/*rest", | req | HttpServerResponse::text(200, req.param("rest").unwrap()));
+        router.post("/api/echo", | req | HttpServerResponse::new(200, "", req.body.to_vec()));
+        let mut static_dir = HttpStaticDir::new(dir);
+        static_dir.extra_headers = "Cross-Origin-Opener-Policy: same-origin\r\n".to_string();
+        router.static_dir("/", static_dir);
+        while let Ok(request) = rx_request.recv() {
+            match router.handle(request) {
+                Some(HttpServerRequest::Get {response_sender, ..}) => {
+                    let _ = response_sender.send(HttpServerResponse::text(404, "no route"));
+                }
+                Some(HttpServerRequest::Post {response, ..}) => {
+                    let _ = response.send(HttpServerResponse::text(404, "no route"));
+                }
+                _ => ()
+            }
+        }
+    });
+    format!("http://{}", listen_address)
+}
+
+fn get(url: &str, headers: &[(&str, &str)]) -> makepad_http::client::HttpClientResponse {
+    let mut request = HttpClientRequest::get(url);
+    for (name, value) in headers {
+        request.set_header(name, value);
+    }
+    HttpClient::new().send(&request).unwrap()
+}
+
+#[test]
+fn route_patterns() {
+    let base = start_router_server(test_dir("patterns"));
+    assert_eq!(get(&format!("{}/api/user/42", base), &[]).body, b"user 42");
+    assert_eq!(get(&format!("{}/api/files/a/b%20c", base), &[]).body, b"a/b c");
+    let response = HttpClient::new().send(&HttpClientRequest::post(&format!("{}/api/echo", base), b"body".to_vec())).unwrap();
+    assert_eq!(response.body, b"body");
+    let response = HttpClient::new().send(&HttpClientRequest::post(&format!("{}/api/other", base), Vec::new())).unwrap();
+    assert_eq!(response.status, 404);
+}
+
+#[test]
+fn static_files() {
+    let base = start_router_server(test_dir("static"));
+    let response = get(&format!("{}/", base), &[]);
+    assert_eq!(response.body, b"<html>index</html>");
+    assert_eq!(response.header("content-type"), Some("text/html; charset=utf-8"));
+    assert_eq!(response.header("cross-origin-opener-policy"), Some("same-origin"));
+    assert_eq!(get(&format!("{}/sub/a%20b.txt", base), &[]).body, b"spaced");
+    assert_eq!(get(&format!("{}/missing.txt", base), &[]).status, 404);
+    assert_eq!(get(&format!("{}/sub/../../secret", base), &[]).status, 403);
+
+    // the client follows the redirect to the slashed url
+    let response = get(&format!("{}/sub", base), &[]);
+    assert_eq!(response.body, b"sub index");
+    assert!(response.url.ends_with("/sub/"));
+}
+
+#[test]
+fn path_traversal() {
+    let dir = test_dir("traversal");
+    std::fs::write(dir.join("sub/secret.txt"), "secret").unwrap();
+    let base = start_router_server(dir.clone());
+    let secret = dir.join("sub/secret.txt").to_string_lossy().replace('/', "%2F");
+    // an encoded slash doesn't split a segment, and so can't make the path absolute
+    let response = get(&format!("{}/{}", base, secret), &[]);
+    assert_eq!(response.status, 404);
+    assert_eq!(get(&format!("{}/sub%2Fsecret.txt", base), &[]).status, 404);
+    assert_eq!(get(&format!("{}/sub%5Csecret.txt", base), &[]).status, 404);
+    assert_eq!(get(&format!("{}/api/files/a%2Fb", base), &[]).status, 404);
+    assert_eq!(get(&format!("{}/sub/%2E%2E/%2E%2E/secret", base), &[]).status, 403);
+
+    // called directly, an absolute path is refused rather than replacing the root
+    let static_dir = HttpStaticDir::new(dir.join("sub"));
+    let headers = HttpServerHeaders {
+        addr: free_addr(),
+        lines: Vec::new(),
+        verb: "GET".to_string(),
+        path: "/".to_string(),
+        path_no_slash: String::new(),
+        search: None,
+        content_length: None,
+        accept_encoding: None,
+        sec_websocket_key: None,
+    };
+    let absolute = dir.join("index.html");
+    let response = static_dir.respond(&headers, &absolute.to_string_lossy());
+    assert!(response.header.starts_with("HTTP/1.1 403"));
+    assert!(static_dir.respond(&headers, "secret.txt").header.starts_with("HTTP/1.1 200"));
+}
+
+#[test]
+fn hidden_files_and_extensions() {
+    let dir = test_dir("hidden");
+    std::fs::create_dir_all(dir.join(".git")).unwrap();
+    std::fs::write(dir.join(".git/config"), "secret").unwrap();
+    std::fs::write(dir.join(".env"), "secret").unwrap();
+    std::fs::write(dir.join("Cargo.toml"), "secret").unwrap();
+    let base = start_router_server(dir.clone());
+    assert_eq!(get(&format!("{}/.env", base), &[]).status, 404);
+    assert_eq!(get(&format!("{}/.git/config", base), &[]).status, 404);
+    assert_eq!(get(&format!("{}/%2Egit/config", base), &[]).status, 404);
+    assert_eq!(get(&format!("{}/Cargo.toml", base), &[]).status, 200);
+
+    let headers = |path: &str| HttpServerHeaders {
+        addr: free_addr(),
+        lines: Vec::new(),
+        verb: "GET".to_string(),
+        path: path.to_string(),
+        path_no_slash: String::new(),
+        search: None,
+        content_length: None,
+        accept_encoding: None,
+        sec_websocket_key: None,
+    };
+    let mut static_dir = HttpStaticDir::new(dir);
+    static_dir.extensions = Some(WEB_ASSET_EXTENSIONS);
+    assert!(static_dir.respond(&headers("/Cargo.toml"), "Cargo.toml").header.starts_with("HTTP/1.1 404"));
+    assert!(static_dir.respond(&headers("/sub/a b.txt"), "sub/a b.txt").header.starts_with("HTTP/1.1 404"));
+    assert!(static_dir.respond(&headers("/app.js"), "app.js").header.starts_with("HTTP/1.1 200"));
+    // a directory serves its index, which is html
+    assert!(static_dir.respond(&headers("/sub/"), "sub").header.starts_with("HTTP/1.1 200"));
+}
+
+#[test]
+fn etags() {
+    let base = start_router_server(test_dir("etags"));
+    let url = format!("{}/index.html", base);
+    let response = get(&url, &[]);
+    let etag = response.header("etag").unwrap().to_string();
+    assert_eq!(get(&url, &[("If-None-Match", &etag)]).status, 304);
+    assert_eq!(get(&url, &[("If-None-Match", &format!("\"other\", W/{}", etag))]).status, 304);
+    assert_eq!(get(&url, &[("If-None-Match", "\"other\"")]).status, 200);
+}
+
+#[test]
+fn ranges() {
+    let base = start_router_server(test_dir("ranges"));
+    let url = format!("{}/video.mp4", base);
+    let all: Vec<u8> = (0..=255u8).collect();
+
+    let response = get(&url, &[("Range", "bytes=10-19")]);
+    assert_eq!(response.status, 206);
+    assert_eq!(response.header("content-range"), Some("bytes 10-19/256"));
+    assert_eq!(response.body, &all[10..20]);
+
+    assert_eq!(get(&url, &[("Range", "bytes=250-")]).body, &all[250..]);
+    assert_eq!(get(&url, &[("Range", "bytes=-6")]).body, &all[250..]);
+    assert_eq!(get(&url, &[("Range", "bytes=200-1000")]).body, &all[200..]);
+
+    let response = get(&url, &[("Range", "bytes=300-")]);
+    assert_eq!(response.status, 416);
+    assert_eq!(response.header("content-range"), Some("bytes */256"));
+
+    // a range against an older version of the file gets the whole file
+    let response = get(&url, &[("Range", "bytes=0-1"), ("If-Range", "\"stale\"")]);
+    assert_eq!((response.status, response.body.len()), (200, 256));
+}
+
+#[test]
+fn compression() {
+    let dir = test_dir("compression");
+    let base = start_router_server(dir.clone());
+    let js = std::fs::read(dir.join("app.js")).unwrap();
+
+    // the client decodes gzip itself, so the body comes back as the file
+    let response = get(&format!("{}/app.js", base), &[("Accept-Encoding", "br, gzip")]);
+    assert_eq!(response.header("content-encoding"), Some("gzip"));
+    assert_eq!(response.header("vary"), Some("Accept-Encoding"));
+    assert_eq!(response.body, js);
+
+    let response = get(&format!("{}/app.js", base), &[("Accept-Encoding", "gzip;q=0")]);
+    assert_eq!(response.header("content-encoding"), None);
+
+    // a precompressed brotli sibling wins when brotli is accepted
+    std::fs::write(dir.join("app.js.br"), b"pretend brotli").unwrap();
+    let mut stream = TcpStream::connect(base.strip_prefix("http://").unwrap()).unwrap();
+    write!(stream, "GET /app.js HTTP/1.1\r\nAccept-Encoding: gzip, br\r\nConnection: close\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.contains("Content-Encoding: br\r\n"));
+    assert!(response.ends_with("\r\n\r\npretend brotli"));
+}
+
+#[test]
+fn keep_alive_and_head() {
+    let base = start_router_server(test_dir("keep_alive"));
+    let stream = TcpStream::connect(base.strip_prefix("http://").unwrap()).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+    let read_response = | reader: &mut BufReader<TcpStream> | {
+        let mut content_length = 0;
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break
+            }
+            if let Some(v) = line.strip_prefix("Content-Length: ") {
+                content_length = v.trim().parse().unwrap();
+            }
+        }
+        (status, content_length)
+    };
+    // two requests over one connection, the HEAD answer has a length but no body
+    write!(stream, "HEAD /index.html HTTP/1.1\r\nHost: test\r\n\r\n").unwrap();
+    let (status, len) = read_response(&mut reader);
+    assert!(status.starts_with("HTTP/1.1 200"));
+    assert_eq!(len, 18);
+    write!(stream, "GET /api/user/7 HTTP/1.1\r\nHost: test\r\n\r\n").unwrap();
+    let (_, len) = read_response(&mut reader);
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).unwrap();
+    assert_eq!(body, b"user 7");
+}
@@ -0,0 +1,291 @@
+use {
+    makepad_http::{
+        utils::read_head_from_tcp_stream,
+        websocket::{web_socket_accept_key, ClientWebSocket, ClientWebSocketError, ClientWebSocketMessage},
+    },
+    std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+    },
+};
+
+const RFC_KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+
+fn handshake_response(key: &str, extra: &str) -> String {
+    format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n{}\r\n",
+        web_socket_accept_key(key),
+        extra
+    )
+}
+
+fn client(deflate: bool) -> ClientWebSocket {
+    let extra = if deflate {"Sec-WebSocket-Extensions: permessage-deflate; server_no_context_takeover\r\n"} else {""};
+    ClientWebSocket::from_upgrade_response(&handshake_response(RFC_KEY, extra), RFC_KEY, &[], deflate).unwrap()
+}
+
+/// An unmasked frame as a server sends it
+fn frame(fin: bool, rsv1: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![if fin {0x80} else {0} | if rsv1 {0x40} else {0} | opcode];
+    if payload.len() < 126 {
+        out.push(payload.len() as u8);
+    }
+    else if payload.len() < 65536 {
+        out.push(126);
+        out.extend((payload.len() as u16).to_be_bytes());
+    }
+    else {
+        out.push(127);
+        out.extend((payload.len() as u64).to_be_bytes());
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close(u16, String),
+    Error(ClientWebSocketError),
+}
+
+fn parse(socket: &mut ClientWebSocket, input: &[u8]) -> Vec<Event> {
+    let mut events = Vec::new();
+    socket.parse(input, | result | events.push(match result {
+        Ok(ClientWebSocketMessage::Text(text)) => Event::Text(text.to_string()),
+        Ok(ClientWebSocketMessage::Binary(data)) => Event::Binary(data.to_vec()),
+        Ok(ClientWebSocketMessage::Ping(data)) => Event::Ping(data.to_vec()),
+        Ok(ClientWebSocketMessage::Pong(data)) => Event::Pong(data.to_vec()),
+        Ok(ClientWebSocketMessage::Close {code, reason}) => Event::Close(code, reason.to_string()),
+        Err(e) => Event::Error(e),
+    }));
+    events
+}
+
+/// Unmasks a frame the client built, returning its first byte and payload
+fn unmask(frame: &[u8]) -> (u8, Vec<u8>) {
+    assert!(frame[1] & 0x80 != 0, "client frames must be masked");
+    let (len, start) = match frame[1] & 0x7f {
+        126 => (u16::from_be_bytes([frame[2], frame[3]]) as usize, 4),
+        127 => (u64::from_be_bytes(frame[2..10].try_into().unwrap()) as usize, 10),
+        len => (len as usize, 2)
+    };
+    let mask = &frame[start..start + 4];
+    let payload = frame[start + 4..start + 4 + len].iter().enumerate().map( | (i, b) | b ^ mask[i & 3]).collect();
+    (frame[0], payload)
+}
+
+#[test]
+fn accept_key() {
+    // the example from RFC 6455 section 1.3
+    assert_eq!(web_socket_accept_key(RFC_KEY), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    let a = ClientWebSocket::create_key();
+    let b = ClientWebSocket::create_key();
+    assert_eq!(a.len(), 24);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn handshake_validation() {
+    let ok = handshake_response(RFC_KEY, "");
+    assert!(ClientWebSocket::from_upgrade_response(&ok, RFC_KEY, &[], false).is_ok());
+    // the accept key belongs to another request
+    assert!(ClientWebSocket::from_upgrade_response(&ok, "AQIDBAUGBwgJCgsMDQ4PEA==", &[], false).is_err());
+    assert!(ClientWebSocket::from_upgrade_response("HTTP/1.1 200 OK\r\n\r\n", RFC_KEY, &[], false).is_err());
+
+    let chat = handshake_response(RFC_KEY, "Sec-WebSocket-Protocol: chat\r\n");
+    let socket = ClientWebSocket::from_upgrade_response(&chat, RFC_KEY, &["superchat", "chat"], false).unwrap();
+    assert_eq!(socket.protocol.as_deref(), Some("chat"));
+    assert!(ClientWebSocket::from_upgrade_response(&chat, RFC_KEY, &["superchat"], false).is_err());
+
+    let deflate = handshake_response(RFC_KEY, "Sec-WebSocket-Extensions: permessage-deflate; server_no_context_takeover; client_no_context_takeover\r\n");
+    assert!(ClientWebSocket::from_upgrade_response(&deflate, RFC_KEY, &[], true).unwrap().deflate);
+    assert!(ClientWebSocket::from_upgrade_response(&deflate, RFC_KEY, &[], false).is_err());
+    let takeover = handshake_response(RFC_KEY, "Sec-WebSocket-Extensions: permessage-deflate\r\n");
+    assert!(ClientWebSocket::from_upgrade_response(&takeover, RFC_KEY, &[], true).is_err());
+    // the server may turn the offer down
+    assert!(!ClientWebSocket::from_upgrade_response(&ok, RFC_KEY, &[], true).unwrap().deflate);
+}
+
+#[test]
+fn fragmentation() {
+    let mut socket = client(false);
+    let mut input = frame(false, false, 1, "frag".as_bytes());
+    // control frames can come in between fragments
+    input.extend(frame(true, false, 9, b"ping"));
+    input.extend(frame(false, false, 0, "men".as_bytes()));
+    input.extend(frame(true, false, 0, "ted ✓".as_bytes()));
+    input.extend(frame(true, false, 2, &[1, 2, 3]));
+    // byte at a time, so every frame arrives split
+    let mut events = Vec::new();
+    for byte in &input {
+        events.extend(parse(&mut socket, &[*byte]));
+    }
+    assert_eq!(events, vec![
+        Event::Ping(b"ping".to_vec()),
+        Event::Text("fragmented ✓".to_string()),
+        Event::Binary(vec![1, 2, 3]),
+    ]);
+
+    // large payloads use the 16 and 64 bit lengths
+    let big = vec![7u8; 70000];
+    assert_eq!(parse(&mut socket, &frame(true, false, 2, &big[0..300])), vec![Event::Binary(big[0..300].to_vec())]);
+    assert_eq!(parse(&mut socket, &frame(true, false, 2, &big)), vec![Event::Binary(big)]);
+}
+
+#[test]
+fn fragmentation_errors() {
+    let cases: Vec<Vec<u8>> = vec![
+        frame(true, false, 0, b"continuation without a start"),
+        [frame(false, false, 1, b"a"), frame(true, false, 1, b"b")].concat(),
+        frame(false, false, 9, b"fragmented ping"),
+        frame(true, false, 9, &[0; 126]),
+        frame(true, false, 3, b"reserved opcode"),
+        frame(true, true, 1, b"rsv1 without deflate"),
+        vec![0x81, 0x81, 0, 0, 0, 0, b'a'],
+    ];
+    for case in cases {
+        let events = parse(&mut client(false), &case);
+        assert!(matches!(events.last(), Some(Event::Error(ClientWebSocketError::Protocol(_)))), "{:?}", events);
+    }
+}
+
+#[test]
+fn invalid_utf8() {
+    let mut socket = client(false);
+    assert_eq!(parse(&mut socket, &frame(true, false, 1, &[0xce, 0xba, 0xe1, 0xbd])), vec![Event::Error(ClientWebSocketError::InvalidUtf8)]);
+    // nothing is parsed after a failure
+    assert!(parse(&mut socket, &frame(true, false, 1, b"ok")).is_empty());
+    assert_eq!(ClientWebSocketError::InvalidUtf8.close_code(), 1007);
+
+    // a code point split between fragments is fine
+    let mut socket = client(false);
+    let input = [frame(false, false, 1, &[0xce]), frame(true, false, 0, &[0xba])].concat();
+    assert_eq!(parse(&mut socket, &input), vec![Event::Text("κ".to_string())]);
+}
+
+#[test]
+fn close_codes() {
+    let close = | payload: &[u8] | parse(&mut client(false), &frame(true, false, 8, payload));
+    assert_eq!(close(&[]), vec![Event::Close(1005, String::new())]);
+    assert_eq!(close(&[0x03, 0xe8, b'b', b'y', b'e']), vec![Event::Close(1000, "bye".to_string())]);
+    assert_eq!(close(&[0x0f, 0xa0]), vec![Event::Close(4000, String::new())]);
+    for code in [0u16, 999, 1004, 1005, 1006, 1015, 2000, 5000] {
+        let events = close(&code.to_be_bytes());
+        assert!(matches!(events[..], [Event::Error(ClientWebSocketError::Protocol(_))]), "{} {:?}", code, events);
+    }
+    assert!(matches!(close(&[3])[..], [Event::Error(ClientWebSocketError::Protocol(_))]));
+    assert_eq!(close(&[0x03, 0xe8, 0xff]), vec![Event::Error(ClientWebSocketError::InvalidUtf8)]);
+
+    let socket = client(false);
+    let (head, payload) = unmask(&socket.build_close(1001, "going away"));
+    assert_eq!(head, 0x88);
+    assert_eq!(payload, [&[0x03, 0xe9][..], b"going away"].concat());
+    // the local only codes go out without a code
+    assert_eq!(unmask(&socket.build_close(1005, "")).1, b"");
+}
+
+#[test]
+fn message_too_big() {
+    let mut socket = client(false);
+    socket.max_message_size = 100;
+    let input = [frame(false, false, 2, &[0; 60]), frame(true, false, 0, &[0; 60])].concat();
+    assert_eq!(parse(&mut socket, &input), vec![Event::Error(ClientWebSocketError::MessageTooBig)]);
+    assert_eq!(ClientWebSocketError::MessageTooBig.close_code(), 1009);
+}
+
+#[test]
+fn permessage_deflate() {
+    let text = "hello deflate ".repeat(50);
+    let mut socket = client(true);
+    let built = socket.build_text(&text);
+    let (head, compressed) = unmask(&built);
+    assert_eq!(head, 0x80 | 0x40 | 1);
+    assert!(compressed.len() < text.len());
+
+    // what the client sends the server can read back, also split over fragments
+    let half = compressed.len() / 2;
+    let input = [
+        frame(false, true, 1, &compressed[0..half]),
+        frame(true, false, 0, &compressed[half..]),
+        frame(true, false, 2, b"not compressed"),
+    ].concat();
+    assert_eq!(parse(&mut socket, &input), vec![Event::Text(text), Event::Binary(b"not compressed".to_vec())]);
+
+    // a sync flushed message with the trailing 00 00 ff ff removed, from RFC 7692 section 7.2.3.1
+    let hello = [0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00];
+    assert_eq!(parse(&mut socket, &frame(true, true, 1, &hello)), vec![Event::Text("Hello".to_string())]);
+
+    assert_eq!(parse(&mut socket, &frame(true, true, 2, &[0xff; 8])), vec![Event::Error(ClientWebSocketError::InvalidDeflate)]);
+    // control frames are never compressed
+    let events = parse(&mut client(true), &frame(true, true, 9, b""));
+    assert!(matches!(events[..], [Event::Error(ClientWebSocketError::Protocol(_))]));
+}
+
+#[test]
+fn echo_server() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let head = String::from_utf8(read_head_from_tcp_stream(&mut stream).unwrap()).unwrap();
+        let key = head.lines().find_map( | l | l.strip_prefix("Sec-WebSocket-Key: ")).unwrap();
+        assert!(head.contains("Sec-WebSocket-Protocol: echo\r\n"));
+        stream.write_all(handshake_response(key, "Sec-WebSocket-Protocol: echo\r\n").as_bytes()).unwrap();
+        // the ping goes out with the handshake so the client has to split the two
+        stream.write_all(&frame(true, false, 9, b"are you there")).unwrap();
+        let mut buf = vec![0u8; 1024];
+        let mut input = Vec::new();
+        loop {
+            let n = stream.read(&mut buf).unwrap();
+            input.extend_from_slice(&buf[0..n]);
+            while input.len() >= 6 && input.len() >= 6 + (input[1] & 0x7f) as usize {
+                let len = 6 + (input[1] & 0x7f) as usize;
+                let (head, payload) = unmask(&input[0..len]);
+                input.drain(0..len);
+                stream.write_all(&frame(true, false, head & 0x0f, &payload)).unwrap();
+                if head & 0x0f == 8 {
+                    return
+                }
+            }
+        }
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let key = ClientWebSocket::create_key();
+    let request = ClientWebSocket::create_upgrade_request(&addr.to_string(), "/", &key, &["echo"], false, "");
+    stream.write_all(request.as_bytes()).unwrap();
+    let head = read_head_from_tcp_stream(&mut stream).unwrap();
+    let mut socket = ClientWebSocket::from_upgrade_response(std::str::from_utf8(&head).unwrap(), &key, &["echo"], false).unwrap();
+
+    let mut events = Vec::new();
+    let mut buf = vec![0u8; 1024];
+    let mut sent_close = false;
+    while !events.iter().any( | e | matches!(e, Event::Close(..))) {
+        let n = stream.read(&mut buf).unwrap();
+        for event in parse(&mut socket, &buf[0..n]) {
+            match &event {
+                Event::Ping(data) => {
+                    stream.write_all(&socket.build_pong(data)).unwrap();
+                    stream.write_all(&socket.build_text("echo me")).unwrap();
+                }
+                Event::Text(_) if !sent_close => {
+                    stream.write_all(&socket.build_close(1000, "done")).unwrap();
+                    sent_close = true;
+                }
+                _ => ()
+            }
+            events.push(event);
+        }
+    }
+    server.join().unwrap();
+    assert_eq!(events, vec![
+        Event::Ping(b"are you there".to_vec()),
+        Event::Pong(b"are you there".to_vec()),
+        Event::Text("echo me".to_string()),
+        Event::Close(1000, "done".to_string()),
+    ]);
+}
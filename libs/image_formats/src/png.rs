@@ -1039,6 +1039,253 @@ pub fn decode(src: &[u8]) -> Result<ImageBuffer, String> {
     }
 }
 
-pub fn encode(_image: &ImageBuffer) -> Result<Vec<u8>, String> {
-    Err("not implemented".to_string())
+// Deflate algorithm (fixed huffman codes with hash chain matching)
+const WINDOW_SIZE: usize = 1 << 15;
+const HASH_SIZE: usize = 1 << 15;
+const MAX_CHAIN: usize = 32;
+const MAX_MATCH: usize = 258;
+
+struct ZipWriter {
+    dst: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl ZipWriter {
+    fn new() -> ZipWriter {
+        ZipWriter {
+            dst: Vec::new(),
+            bits: 0,
+            count: 0,
+        }
+    }
+    
+    fn write_bits(&mut self, value: u32, width: u32) {
+        self.bits |= value << self.count;
+        self.count += width;
+        while self.count >= 8 {
+            self.dst.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+    
+    // huffman codes are stored starting at the most significant bit
+    fn write_code(&mut self, code: u32, width: u32) {
+        self.write_bits(bit_reverse(code, width), width);
+    }
+    
+    fn write_litlen(&mut self, value: usize) {
+        match value {
+            0..=143 => self.write_code(0x30 + value as u32, 8),
+            144..=255 => self.write_code(0x190 + (value - 144) as u32, 9),
+            256..=279 => self.write_code((value - 256) as u32, 7),
+            _ => self.write_code(0xC0 + (value - 280) as u32, 8),
+        }
+    }
+    
+    fn write_match(&mut self, length: usize, dist: usize) {
+        let code = LITLEN_LENGTH.iter().rposition(|base| *base as usize <= length).unwrap();
+        self.write_litlen(257 + code);
+        self.write_bits((length - LITLEN_LENGTH[code] as usize) as u32, LITLEN_EXTRA[code] as u32);
+        let code = DIST_DIST.iter().rposition(|base| *base as usize <= dist).unwrap();
+        self.write_code(code as u32, 5);
+        self.write_bits((dist - DIST_DIST[code] as usize) as u32, DIST_EXTRA[code] as u32);
+    }
+    
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.dst.push(self.bits as u8);
+        }
+        self.dst
+    }
+}
+
+fn hash3(src: &[u8], p: usize) -> usize {
+    let v = ((src[p] as u32) << 16) | ((src[p + 1] as u32) << 8) | (src[p + 2] as u32);
+    (v.wrapping_mul(0x9E3779B1) >> 17) as usize & (HASH_SIZE - 1)
+}
+
+fn deflate(src: &[u8]) -> Vec<u8> {
+    let mut writer = ZipWriter::new();
+    writer.write_bits(1, 1); // final block
+    writer.write_bits(1, 2); // fixed huffman codes
+    
+    // head and prev store position + 1, 0 means empty
+    let mut head: Vec<usize> = vec![0; HASH_SIZE];
+    let mut prev: Vec<usize> = vec![0; WINDOW_SIZE];
+    let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, p: usize| {
+        if p + 3 <= src.len() {
+            let h = hash3(src, p);
+            prev[p & (WINDOW_SIZE - 1)] = head[h];
+            head[h] = p + 1;
+        }
+    };
+    
+    let mut sp: usize = 0;
+    while sp < src.len() {
+        let mut best_length = 0;
+        let mut best_dist = 0;
+        if sp + 3 <= src.len() {
+            let max_length = MAX_MATCH.min(src.len() - sp);
+            let mut candidate = head[hash3(src, sp)];
+            let mut chain = 0;
+            while candidate != 0 && chain < MAX_CHAIN {
+                let cp = candidate - 1;
+                if sp - cp > WINDOW_SIZE - 1 {
+                    break;
+                }
+                let mut length = 0;
+                while length < max_length && src[cp + length] == src[sp + length] {
+                    length += 1;
+                }
+                if length > best_length {
+                    best_length = length;
+                    best_dist = sp - cp;
+                    if length == max_length {
+                        break;
+                    }
+                }
+                candidate = prev[cp & (WINDOW_SIZE - 1)];
+                chain += 1;
+            }
+        }
+        if best_length >= 3 {
+            writer.write_match(best_length, best_dist);
+            for p in sp..sp + best_length {
+                insert(&mut head, &mut prev, p);
+            }
+            sp += best_length;
+        }
+        else {
+            writer.write_litlen(src[sp] as usize);
+            insert(&mut head, &mut prev, sp);
+            sp += 1;
+        }
+    }
+    writer.write_litlen(256);
+    writer.finish()
+}
+
+fn adler32(src: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for chunk in src.chunks(5552) {
+        for v in chunk {
+            a += *v as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn crc32(src: &[u8]) -> u32 {
+    let mut table: [u32; 256] = [0; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if (c & 1) != 0 {0xEDB88320 ^ (c >> 1)} else {c >> 1};
+        }
+        *entry = c;
+    }
+    let mut crc: u32 = 0xFFFFFFFF;
+    for v in src {
+        crc = table[((crc ^ *v as u32) & 255) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFFFFFF
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if (pa <= pb) && (pa <= pc) {
+        a
+    }
+    else if pb <= pc {
+        b
+    }
+    else {
+        c
+    }
+}
+
+// pick the filter per scanline with the smallest sum of absolute differences
+fn filter(src: &[u8], height: usize, stride: usize, bpp: usize) -> Vec<u8> {
+    let mut dst: Vec<u8> = Vec::with_capacity((stride + 1) * height);
+    let zero: Vec<u8> = vec![0; stride];
+    let mut candidate: Vec<u8> = vec![0; stride];
+    let mut best: Vec<u8> = vec![0; stride];
+    for y in 0..height {
+        let line = &src[y * stride..(y + 1) * stride];
+        let prev = if y > 0 {&src[(y - 1) * stride..y * stride]} else {&zero[..]};
+        let mut best_type = 0;
+        let mut best_sum = usize::MAX;
+        for filter_type in 0..5 {
+            for x in 0..stride {
+                let a = if x >= bpp {line[x - bpp]} else {0};
+                let b = prev[x];
+                let c = if x >= bpp {prev[x - bpp]} else {0};
+                candidate[x] = match filter_type {
+                    0 => line[x],
+                    1 => line[x].wrapping_sub(a),
+                    2 => line[x].wrapping_sub(b),
+                    3 => line[x].wrapping_sub(((a as u16 + b as u16) >> 1) as u8),
+                    _ => line[x].wrapping_sub(paeth(a, b, c)),
+                };
+            }
+            let sum = candidate.iter().map(|v| (*v as i8).unsigned_abs() as usize).sum();
+            if sum < best_sum {
+                best_sum = sum;
+                best_type = filter_type;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        dst.push(best_type as u8);
+        dst.extend_from_slice(&best);
+    }
+    dst
+}
+
+fn write_chunk(dst: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    dst.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = dst.len();
+    dst.extend_from_slice(chunk_type);
+    dst.extend_from_slice(data);
+    let crc = crc32(&dst[start..]);
+    dst.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encodes the image as an 8 bit RGBA PNG.
+pub fn encode(image: &ImageBuffer) -> Result<Vec<u8>, String> {
+    if (image.width == 0) || (image.height == 0) || (image.data.len() < image.width * image.height) {
+        return Err("invalid image size".to_string());
+    }
+    let stride = image.width * 4;
+    let mut raw_data: Vec<u8> = Vec::with_capacity(stride * image.height);
+    for c in &image.data[0..image.width * image.height] {
+        raw_data.push((c >> 16) as u8);
+        raw_data.push((c >> 8) as u8);
+        raw_data.push(*c as u8);
+        raw_data.push((c >> 24) as u8);
+    }
+    let filtered_data = filter(&raw_data, image.height, stride, 4);
+    
+    let mut zipped_data: Vec<u8> = vec![0x78, 0x01];
+    zipped_data.extend_from_slice(&deflate(&filtered_data));
+    zipped_data.extend_from_slice(&adler32(&filtered_data).to_be_bytes());
+    
+    let mut header: Vec<u8> = Vec::new();
+    header.extend_from_slice(&(image.width as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // RGBA8, deflate, adaptive filter, no interlace
+    
+    let mut dst: Vec<u8> = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    write_chunk(&mut dst, b"IHDR", &header);
+    write_chunk(&mut dst, b"IDAT", &zipped_data);
+    write_chunk(&mut dst, b"IEND", &[]);
+    Ok(dst)
 }
\ No newline at end of file
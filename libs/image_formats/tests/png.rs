@@ -0,0 +1,51 @@
+use makepad_image_formats::{png, ImageBuffer};
+
+fn round_trip(image: &ImageBuffer) {
+    let data = png::encode(image).unwrap();
+    assert_eq!(png::test(&data), Some((image.width, image.height)));
+    let decoded = png::decode(&data).unwrap();
+    assert_eq!(decoded.width, image.width);
+    assert_eq!(decoded.height, image.height);
+    assert_eq!(decoded.data, image.data);
+}
+
+#[test]
+fn single_pixel() {
+    let mut image = ImageBuffer::new(1, 1);
+    image.data[0] = 0x80FF4020;
+    round_trip(&image);
+}
+
+#[test]
+fn gradient() {
+    // long runs of repeated rows exercise the back references in the deflate stream
+    let mut image = ImageBuffer::new(67, 45);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            image.data[y * image.width + x] = 0xFF000000 | ((x as u32 * 3) << 16) | ((y as u32 * 5) << 8) | 0x40;
+        }
+    }
+    round_trip(&image);
+}
+
+#[test]
+fn noise_with_alpha() {
+    // data without repetitions has to be stored as literals
+    let mut image = ImageBuffer::new(129, 31);
+    let mut seed = 0x12345678u32;
+    for pixel in &mut image.data {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        *pixel = seed;
+    }
+    round_trip(&image);
+}
+
+#[test]
+fn invalid_size() {
+    assert!(png::encode(&ImageBuffer::new(0, 4)).is_err());
+    let mut image = ImageBuffer::new(4, 4);
+    image.data.truncate(15);
+    assert!(png::encode(&image).is_err());
+}
@@ -13,6 +13,14 @@ pub enum MarkdownListLabel{
     Minus,
     Star,
     Number{digit:usize, start:usize, end:usize},
+    Task{checked:bool},
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarkdownTableAlign{
+    None,
+    Left,
+    Center,
+    Right
 }
 #[derive(Debug, PartialEq)]
 pub enum MarkdownNode{
@@ -29,6 +37,17 @@ pub enum MarkdownNode{
     Separator, 
     BeginUnderline,
     EndUnderline,
+    BeginStrikethrough,
+    EndStrikethrough,
+    BeginTable{columns:Vec<MarkdownTableAlign>},
+    EndTable,
+    BeginTableRow{header:bool},
+    EndTableRow,
+    BeginTableCell,
+    EndTableCell,
+    FootnoteRef{number:usize},
+    BeginFootnote{number:usize},
+    EndFootnote,
     BeginCode{lang_start:usize, lang_end:usize},
     EndCode,
     BeginInlineCode,
@@ -93,15 +112,24 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
     let mut cursor = Cursor::new(body);
     enum State{
         Root{spaces:usize},
-        Inline{kind:Kind, bold:usize, italic:usize, underline:usize}, // terminates
+        Inline{kind:Kind, bold:usize, italic:usize, strike:usize}, // terminates
     }
     enum Kind{
         Normal,
         Head,
         Quote(usize),
-        List(usize)
+        List(usize),
+        Footnote,
+        Table{column:usize, columns:usize, header:bool, cell_open:bool}
     }
-    
+
+    // definitions are collected up front so a reference without one stays plain text
+    let footnote_labels:Vec<&str> = body.lines().filter_map(|line|{
+        line.trim_start().strip_prefix("[^")?.split_once("]:").map(|(label,_)| label)
+    }).collect();
+    // footnotes are numbered in the order they first show up
+    let mut footnotes:Vec<String> = Vec::new();
+
     let mut state = State::Root{spaces:0};
     
     fn push_char(nodes: &mut Vec<MarkdownNode>, decoded:&mut String, c:char){
@@ -153,27 +181,217 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
         nodes.push(MarkdownNode::Text{start, end:decoded.len()});
         nodes.push(MarkdownNode::EndCode);
     }
-    
+
+    fn end_styles(nodes: &mut Vec<MarkdownNode>, bold:&mut usize, italic:&mut usize, strike:&mut usize){
+        for _ in 0..*bold{
+            nodes.push(MarkdownNode::EndBold);
+        }
+        for _ in 0..*italic{
+            nodes.push(MarkdownNode::EndItalic);
+        }
+        for _ in 0..*strike{
+            nodes.push(MarkdownNode::EndStrikethrough);
+        }
+        *bold = 0;
+        *italic = 0;
+        *strike = 0;
+    }
+
+    fn trim_trailing_space(nodes: &mut Vec<MarkdownNode>, decoded:&str){
+        let mut empty = false;
+        if let Some(MarkdownNode::Text{start, end}) = nodes.last_mut(){
+            if *end > *start && decoded[..*end].ends_with(' '){
+                *end -= 1;
+                empty = *start == *end;
+            }
+        }
+        if empty{
+            nodes.pop();
+        }
+    }
+
+    fn push_link(nodes: &mut Vec<MarkdownNode>, decoded:&mut String, text:&str, url:&str){
+        let start = decoded.len();
+        decoded.push_str(text);
+        let url_start = decoded.len();
+        decoded.push_str(url);
+        nodes.push(MarkdownNode::Link{start, url_start, end:decoded.len()});
+    }
+
+    fn peek_line(cursor:&Cursor)->String{
+        let mut scan = cursor.clone();
+        let mut line = String::new();
+        while scan.chars[0] != '\n' && !scan.at_end(){
+            line.push(scan.chars[0]);
+            scan.next();
+        }
+        line
+    }
+
+    fn split_table_row(line:&str)->Vec<&str>{
+        let line = line.trim();
+        let line = line.strip_prefix('|').unwrap_or(line);
+        let line = if line.ends_with('|') && !line.ends_with("\\|"){&line[..line.len()-1]} else{line};
+        let mut cells = Vec::new();
+        let mut start = 0;
+        let mut last = '\0';
+        for (i, c) in line.char_indices(){
+            if c == '|' && last != '\\'{
+                cells.push(line[start..i].trim());
+                start = i + 1;
+            }
+            last = c;
+        }
+        cells.push(line[start..].trim());
+        cells
+    }
+
+    // a table is a line with pipes followed by a delimiter row like `| :-- | --: |`
+    // that has as many columns, the colons give the alignment of each column
+    fn table_start(cursor:&Cursor)->Option<Vec<MarkdownTableAlign>>{
+        let header = peek_line(cursor);
+        if !header.contains('|'){
+            return None
+        }
+        let mut scan = cursor.clone();
+        while scan.chars[0] != '\n' && !scan.at_end(){
+            scan.next();
+        }
+        scan.next();
+        let mut columns = Vec::new();
+        for cell in split_table_row(&peek_line(&scan)){
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-'){
+                return None
+            }
+            columns.push(match (cell.starts_with(':'), cell.ends_with(':')){
+                (true, true)=>MarkdownTableAlign::Center,
+                (true, false)=>MarkdownTableAlign::Left,
+                (false, true)=>MarkdownTableAlign::Right,
+                (false, false)=>MarkdownTableAlign::None,
+            });
+        }
+        if columns.len() != split_table_row(&header).len(){
+            return None
+        }
+        Some(columns)
+    }
+
+    fn skip_table_row_start(cursor:&mut Cursor){
+        while cursor.chars[0] == ' '{
+            cursor.next();
+        }
+        if cursor.chars[0] == '|'{
+            cursor.next();
+        }
+        while cursor.chars[0] == ' '{
+            cursor.next();
+        }
+    }
+
+    fn end_table_row(nodes: &mut Vec<MarkdownNode>, decoded:&str, mut column:usize, columns:usize, cell_open:bool){
+        if cell_open{
+            trim_trailing_space(nodes, decoded);
+            nodes.push(MarkdownNode::EndTableCell);
+            column += 1;
+        }
+        // short rows get empty cells so every row has all the columns
+        for _ in column..columns{
+            nodes.push(MarkdownNode::BeginTableCell);
+            nodes.push(MarkdownNode::EndTableCell);
+        }
+        nodes.push(MarkdownNode::EndTableRow);
+    }
+
+    // a bare http://, https:// or www. link, without the punctuation of the sentence around it
+    fn bare_url(cursor:&Cursor)->Option<String>{
+        let mut scan = cursor.clone();
+        let mut url = String::new();
+        while !scan.chars[0].is_whitespace() && scan.chars[0] != '<' && !scan.at_end(){
+            url.push(scan.chars[0]);
+            scan.next();
+        }
+        let prefix = ["https://", "http://", "www."].iter().find(|prefix| url.starts_with(**prefix))?;
+        loop{
+            match url.chars().last(){
+                Some('.' | ',' | ':' | ';' | '!' | '?' | '"' | '\'' | '*' | '_' | '~')=>{
+                    url.pop();
+                }
+                Some(')') if url.matches(')').count() > url.matches('(').count()=>{
+                    url.pop();
+                }
+                _=>break
+            }
+        }
+        if url.len() <= prefix.len(){
+            return None
+        }
+        Some(url)
+    }
+
+    // <scheme:address> or <user@host>, returns what is between the brackets
+    fn angle_link(cursor:&Cursor)->Option<String>{
+        let mut scan = cursor.clone();
+        scan.next();
+        let mut link = String::new();
+        while scan.chars[0] != '>'{
+            if scan.chars[0].is_whitespace() || scan.chars[0] == '<' || scan.at_end(){
+                return None
+            }
+            link.push(scan.chars[0]);
+            scan.next();
+        }
+        if let Some((scheme, _)) = link.split_once(':'){
+            let valid = scheme.len() >= 2 && scheme.starts_with(|c:char| c.is_ascii_alphabetic()) &&
+                scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+            valid.then_some(link)
+        }
+        else if let Some((user, host)) = link.split_once('@'){
+            (!user.is_empty() && host.contains('.')).then_some(link)
+        }
+        else{
+            None
+        }
+    }
+
+    // [^label] with a cursor just past it
+    fn footnote_label<'a>(cursor:&Cursor<'a>)->Option<(String, Cursor<'a>)>{
+        if cursor.chars[0] != '[' || cursor.chars[1] != '^'{
+            return None
+        }
+        let mut scan = cursor.clone();
+        scan.skip(2);
+        let mut label = String::new();
+        while scan.chars[0] != ']'{
+            if scan.chars[0].is_whitespace() || scan.at_end(){
+                return None
+            }
+            label.push(scan.chars[0]);
+            scan.next();
+        }
+        scan.next();
+        (!label.is_empty()).then_some((label, scan))
+    }
+
+    fn footnote_number(footnotes:&mut Vec<String>, label:String)->usize{
+        if let Some(index) = footnotes.iter().position(|l| *l == label){
+            index + 1
+        }
+        else{
+            footnotes.push(label);
+            footnotes.len()
+        }
+    }
+
     loop{
         match &mut state{
-            State::Inline{kind, bold, italic, underline}=> match cursor.chars{
-                [' ',' ','\n']=>{
+            State::Inline{kind, bold, italic, strike}=> match cursor.chars{
+                [' ',' ','\n'] if !matches!(kind, Kind::Table{..})=>{
                     nodes.push(MarkdownNode::NewLine{paragraph: true});
                     cursor.skip(2);
                 }
                 ['\n',_,_] | ['\0',_,_]=>{
-                    for _ in 0..*bold{
-                        nodes.push(MarkdownNode::EndBold);
-                    }
-                    for _ in 0..*italic{
-                        nodes.push(MarkdownNode::EndItalic);
-                    }
-                    for _ in 0..*underline{
-                        nodes.push(MarkdownNode::EndUnderline);
-                    }
-                    *bold = 0;
-                    *italic = 0;
-                    *underline = 0;
+                    end_styles(&mut nodes, bold, italic, strike);
                     
                     match kind{
                         Kind::Head=>{
@@ -211,7 +429,13 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                                 state = State::Root{spaces};
                             }
                         }
-                        Kind::Normal=>{
+                        Kind::Normal | Kind::Footnote=>{
+                            let end = if let Kind::Footnote = kind{
+                                MarkdownNode::EndFootnote
+                            }
+                            else{
+                                MarkdownNode::EndNormal
+                            };
                             let last_is_space = cursor.last_char == ' ';
                             cursor.next();
                             let mut spaces = 0;
@@ -219,19 +443,49 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                                 cursor.next();
                                 spaces += 1;
                             }
-                            if cursor.chars[0] == '#'{
+                            let block_start = spaces < 4 && (
+                                table_start(&cursor).is_some() ||
+                                footnote_label(&cursor).is_some_and(|(_, scan)| scan.chars[0] == ':')
+                            );
+                            if cursor.chars[0] == '#' || block_start{
                                 state = State::Root{spaces};
-                                nodes.push(MarkdownNode::EndNormal);
+                                nodes.push(end);
                             }
                             else if cursor.chars[0] == '\n' || cursor.chars[0] == '\0'{
                                 cursor.next();
                                 state = State::Root{spaces:0};
-                                nodes.push(MarkdownNode::EndNormal);
+                                nodes.push(end);
                             }
                             else if !last_is_space{
                                 push_char(&mut nodes, &mut decoded, ' ');
                             }
                         }
+                        Kind::Table{column, columns, header, cell_open}=>{
+                            end_table_row(&mut nodes, &decoded, *column, *columns, *cell_open);
+                            cursor.next();
+                            if *header{ // skip the delimiter row
+                                while cursor.chars[0] != '\n' && !cursor.at_end(){
+                                    cursor.next();
+                                }
+                                cursor.next();
+                            }
+                            let mut spaces = 0;
+                            while cursor.chars[0] == ' '{
+                                cursor.next();
+                                spaces += 1;
+                            }
+                            // the table goes on for as long as lines have pipes in them
+                            if spaces < 4 && peek_line(&cursor).contains('|'){
+                                nodes.push(MarkdownNode::BeginTableRow{header:false});
+                                skip_table_row_start(&mut cursor);
+                                nodes.push(MarkdownNode::BeginTableCell);
+                                *kind = Kind::Table{column:0, columns:*columns, header:false, cell_open:true};
+                            }
+                            else{
+                                nodes.push(MarkdownNode::EndTable);
+                                state = State::Root{spaces};
+                            }
+                        }
                         Kind::List(depth)=>{
                             let last_is_space = cursor.last_char == ' ';
                             cursor.next();
@@ -264,7 +518,39 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                             }
                         }
                     }
-                    
+
+                }
+                ['\\','|',_] if matches!(kind, Kind::Table{..})=>{
+                    push_char(&mut nodes, &mut decoded, '|');
+                    cursor.skip(2);
+                }
+                ['|',_,_] if matches!(kind, Kind::Table{..})=>{
+                    // trim before the styles close so `**open |` does not keep its space
+                    trim_trailing_space(&mut nodes, &decoded);
+                    end_styles(&mut nodes, bold, italic, strike);
+                    if let Kind::Table{column, columns, cell_open, ..} = kind{
+                        if *cell_open{
+                            nodes.push(MarkdownNode::EndTableCell);
+                            *column += 1;
+                        }
+                        cursor.next();
+                        while cursor.chars[0] == ' '{
+                            cursor.next();
+                        }
+                        // a trailing pipe ends the row, cells past the delimiter row are dropped
+                        *cell_open = false;
+                        if cursor.chars[0] != '\n' && !cursor.at_end(){
+                            if *column < *columns{
+                                nodes.push(MarkdownNode::BeginTableCell);
+                                *cell_open = true;
+                            }
+                            else{
+                                while cursor.chars[0] != '\n' && !cursor.at_end(){
+                                    cursor.next();
+                                }
+                            }
+                        }
+                    }
                 }
                 ['*','*',w] | ['_','_',w] if w != ' ' && w != '\n'=>{ // alright so have have 2 *'s
                     // this is the start of a bold block
@@ -284,19 +570,19 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                         cursor.next();
                     }
                 }
-                ['~','~',w] if w != ' ' && w != '\n'=>{ // alright so have have 2 *'s
-                    // this is the start of a bold block
-                    nodes.push(MarkdownNode::BeginUnderline);
-                    *underline += 1;
+                ['~','~',w] if w != ' ' && w != '\n'=>{
+                    // this is the start of a strikethrough block
+                    nodes.push(MarkdownNode::BeginStrikethrough);
+                    *strike += 1;
                     cursor.skip(2);
                 }
                 [w,'~','~'] if w != ' '&& w != '\n'=>{
-                    // end of a bold block
+                    // end of a strikethrough block
                     push_char(&mut nodes, &mut decoded, w);
-                    if *underline > 0{
-                        *underline -= 1;
+                    if *strike > 0{
+                        *strike -= 1;
                         cursor.skip(3);
-                        nodes.push(MarkdownNode::EndUnderline);
+                        nodes.push(MarkdownNode::EndStrikethrough);
                     }
                     else{
                         cursor.next();
@@ -354,11 +640,19 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                         Kind::Normal => {
                             nodes.push(MarkdownNode::EndNormal)
                         }
+                        Kind::Footnote => {
+                            nodes.push(MarkdownNode::EndFootnote)
+                        }
                         Kind::List(depth) => {
                             for _ in 0..*depth{
                                 nodes.push(MarkdownNode::EndListItem);
                             }
                         }
+                        Kind::Table{column, columns, cell_open, ..} => {
+                            end_styles(&mut nodes, bold, italic, strike);
+                            end_table_row(&mut nodes, &decoded, *column, *columns, *cell_open);
+                            nodes.push(MarkdownNode::EndTable);
+                        }
                     }
                     state = State::Root{spaces:0};
                 }
@@ -423,6 +717,49 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                     // parse inline image
                     cursor.skip(2);
                 }
+                ['[','^',_]=>{ // footnote reference
+                    match footnote_label(&cursor){
+                        Some((label, scan)) if footnote_labels.contains(&label.as_str())=>{
+                            let number = footnote_number(&mut footnotes, label);
+                            nodes.push(MarkdownNode::FootnoteRef{number});
+                            cursor = scan;
+                        }
+                        _=>{
+                            push_char(&mut nodes, &mut decoded, '[');
+                            cursor.next();
+                        }
+                    }
+                }
+                ['<',_,_]=>{ // autolink
+                    if let Some(link) = angle_link(&cursor){
+                        cursor.skip(link.chars().count() + 2);
+                        if link.contains(':'){
+                            push_link(&mut nodes, &mut decoded, &link, &link);
+                        }
+                        else{
+                            push_link(&mut nodes, &mut decoded, &link, &format!("mailto:{}", link));
+                        }
+                    }
+                    else{
+                        push_char(&mut nodes, &mut decoded, '<');
+                        cursor.next();
+                    }
+                }
+                ['h','t','t'] | ['w','w','w'] if !cursor.last_char.is_alphanumeric()=>{ // bare url
+                    if let Some(url) = bare_url(&cursor){
+                        cursor.skip(url.chars().count());
+                        if url.starts_with("www."){
+                            push_link(&mut nodes, &mut decoded, &url, &format!("http://{}", url));
+                        }
+                        else{
+                            push_link(&mut nodes, &mut decoded, &url, &url);
+                        }
+                    }
+                    else{
+                        push_char(&mut nodes, &mut decoded, cursor.chars[0]);
+                        cursor.next();
+                    }
+                }
                 ['[',_,_]=>{ // possible named link
                     let mut scan = cursor.clone();
                     scan.skip(1);
@@ -455,7 +792,7 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                         }
                     }
                     decoded.truncate(start);
-                    decoded.push(cursor.chars[0]);
+                    push_char(&mut nodes, &mut decoded, cursor.chars[0]);
                     cursor.next();
                 }
                 [' ',_,_]=>{
@@ -504,7 +841,7 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                         }
                         push_optional_char(&mut nodes, &mut decoded, ' ');
                         // alright now we know how deep in the block stack we need to be
-                        state = State::Inline{kind:Kind::Quote(blocks), bold:0, italic:0, underline:0};
+                        state = State::Inline{kind:Kind::Quote(blocks), bold:0, italic:0, strike:0};
                     }
                 }
                 ['#',_,_]=>{
@@ -526,13 +863,13 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                         else{
                             nodes.push(MarkdownNode::Text{start, end:decoded.len()});
                         }
-                        state = State::Inline{kind:Kind::Normal, bold:0, italic:0, underline:0};
+                        state = State::Inline{kind:Kind::Normal, bold:0, italic:0, strike:0};
                     }
                     else {
                         cursor.next();
                         decoded.truncate(start);
                         nodes.push(MarkdownNode::BeginHead{level});
-                        state = State::Inline{kind:Kind::Head, bold:0, italic:0, underline:0};
+                        state = State::Inline{kind:Kind::Head, bold:0, italic:0, strike:0};
                     }
                 }
                 ['-','-','-']=>{ // separator
@@ -545,7 +882,7 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                         if cursor.chars[2] != '\n'{
                             nodes.push(MarkdownNode::BeginNormal);
                             push_char(&mut nodes, &mut decoded, '-');
-                            state = State::Inline{kind:Kind::Normal, bold:0, italic:0, underline:0};
+                            state = State::Inline{kind:Kind::Normal, bold:0, italic:0, strike:0};
                         }
                         else{
                             cursor.skip(3);
@@ -603,7 +940,7 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                         }
                         else{ // its normal 
                             nodes.push(MarkdownNode::BeginNormal);
-                            state = State::Inline{kind:Kind::Normal, bold:0, italic:0, underline:0};
+                            state = State::Inline{kind:Kind::Normal, bold:0, italic:0, strike:0};
                         }
                     }
                     else{
@@ -612,16 +949,27 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                                 nodes.pop();
                             }
                         }
-                        // we always push a begin list item on
-                        nodes.push(MarkdownNode::BeginListItem{label:match cursor.chars[0]{
+                        let mut label = match cursor.chars[0]{
                             '-'=>MarkdownListLabel::Minus,
                             '*'=>MarkdownListLabel::Star,
                             '+'=>MarkdownListLabel::Plus,
                             _=>panic!()
-                        }});
-                        
-                        state = State::Inline{kind:Kind::List(depth), bold:0, italic:0, underline:0};
+                        };
                         cursor.skip(2);
+                        // a task list item starts with [ ] or [x]
+                        if let ['[', c, ']'] = cursor.chars{
+                            let mut scan = cursor.clone();
+                            scan.skip(3);
+                            if (c == ' ' || c == 'x' || c == 'X') && scan.chars[0] == ' '{
+                                label = MarkdownListLabel::Task{checked: c != ' '};
+                                scan.next();
+                                cursor = scan;
+                            }
+                        }
+                        // we always push a begin list item on
+                        nodes.push(MarkdownNode::BeginListItem{label});
+
+                        state = State::Inline{kind:Kind::List(depth), bold:0, italic:0, strike:0};
                     }
                     //push_optional_char(&mut nodes, &mut decoded, ' ');
                 }
@@ -630,6 +978,35 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                     state = State::Root{spaces:0};
                 }
                 [a,_b,_c]=>{
+                    if *spaces < 4{
+                        if let Some(columns) = table_start(&cursor){
+                            let column_count = columns.len();
+                            nodes.push(MarkdownNode::BeginTable{columns});
+                            nodes.push(MarkdownNode::BeginTableRow{header:true});
+                            skip_table_row_start(&mut cursor);
+                            nodes.push(MarkdownNode::BeginTableCell);
+                            state = State::Inline{
+                                kind:Kind::Table{column:0, columns:column_count, header:true, cell_open:true},
+                                bold:0,
+                                italic:0,
+                                strike:0
+                            };
+                            continue;
+                        }
+                        if let Some((label, mut scan)) = footnote_label(&cursor){
+                            if scan.chars[0] == ':'{
+                                scan.next();
+                                while scan.chars[0] == ' '{
+                                    scan.next();
+                                }
+                                cursor = scan;
+                                let number = footnote_number(&mut footnotes, label);
+                                nodes.push(MarkdownNode::BeginFootnote{number});
+                                state = State::Inline{kind:Kind::Footnote, bold:0, italic:0, strike:0};
+                                continue;
+                            }
+                        }
+                    }
                     let mut is_list_digit = None;
                     if a.is_ascii_digit(){
                         let mut scan = cursor.clone();
@@ -663,7 +1040,7 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                             }
                             else{ // its normal 
                                 nodes.push(MarkdownNode::BeginNormal);
-                                state = State::Inline{kind:Kind::Normal, bold:0, italic:0, underline:0};
+                                state = State::Inline{kind:Kind::Normal, bold:0, italic:0, strike:0};
                             }
                         }
                         else{ 
@@ -707,7 +1084,7 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                                 end
                             }});
                                                     
-                            state = State::Inline{kind:Kind::List(depth), bold:0, italic:0, underline:0}
+                            state = State::Inline{kind:Kind::List(depth), bold:0, italic:0, strike:0}
                         }
                     }
                     else if *spaces>=4{ // its code
@@ -716,14 +1093,192 @@ pub fn parse_markdown(body:&str)->MarkdownDoc{
                     }
                     else{
                         nodes.push(MarkdownNode::BeginNormal);
-                        state = State::Inline{kind:Kind::Normal, bold:0, italic:0, underline:0};
+                        state = State::Inline{kind:Kind::Normal, bold:0, italic:0, strike:0};
                     }
                 }
             }
         }
     }
     MarkdownDoc{
-        nodes,
+        nodes: move_footnotes_to_end(nodes),
         decoded,
     }
-}
\ No newline at end of file
+}
+
+// footnote definitions go at the end of the document, in the order they are numbered
+fn move_footnotes_to_end(nodes:Vec<MarkdownNode>)->Vec<MarkdownNode>{
+    if !nodes.iter().any(|node| matches!(node, MarkdownNode::BeginFootnote{..})){
+        return nodes
+    }
+    let mut body = Vec::new();
+    let mut footnotes:Vec<(usize, Vec<MarkdownNode>)> = Vec::new();
+    let mut in_footnote = false;
+    for node in nodes{
+        match node{
+            MarkdownNode::BeginFootnote{number}=>{
+                in_footnote = true;
+                footnotes.push((number, vec![node]));
+            }
+            MarkdownNode::EndFootnote=>{
+                in_footnote = false;
+                footnotes.last_mut().unwrap().1.push(node);
+            }
+            node if in_footnote=>{
+                footnotes.last_mut().unwrap().1.push(node);
+            }
+            node=>{
+                body.push(node);
+            }
+        }
+    }
+    footnotes.sort_by_key(|(number, _)| *number);
+    for (_, nodes) in footnotes{
+        body.extend(nodes);
+    }
+    body
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the node stream as one line, with the text and links spelled out
+    fn dump(body:&str)->String{
+        let doc = parse_markdown(body);
+        let mut out = String::new();
+        for node in &doc.nodes{
+            if !out.is_empty(){
+                out.push(' ');
+            }
+            match node{
+                MarkdownNode::Text{start, end}=>{
+                    write!(out, "{:?}", &doc.decoded[*start..*end]).unwrap();
+                }
+                MarkdownNode::Link{start, url_start, end}=>{
+                    write!(out, "Link({:?}, {:?})", &doc.decoded[*start..*url_start], &doc.decoded[*url_start..*end]).unwrap();
+                }
+                node=>{
+                    write!(out, "{:?}", node).unwrap();
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn table(){
+        assert_eq!(
+            dump("| a | b | c |\n| :-- | :-: | --: |\n| 1 | 2 | 3 |\n"),
+            "BeginTable { columns: [Left, Center, Right] } \
+            BeginTableRow { header: true } BeginTableCell \"a\" EndTableCell BeginTableCell \"b\" EndTableCell BeginTableCell \"c\" EndTableCell EndTableRow \
+            BeginTableRow { header: false } BeginTableCell \"1\" EndTableCell BeginTableCell \"2\" EndTableCell BeginTableCell \"3\" EndTableCell EndTableRow \
+            EndTable"
+        );
+        // an escaped pipe stays in the cell
+        assert_eq!(
+            dump("a \\| b\n---\n"),
+            "BeginTable { columns: [None] } BeginTableRow { header: true } BeginTableCell \"a | b\" EndTableCell EndTableRow EndTable"
+        );
+    }
+
+    #[test]
+    fn malformed_table(){
+        // no delimiter row, or one with the wrong column count, is not a table
+        assert_eq!(dump("| a | b |\n"), "BeginNormal \"| a | b |\" EndNormal");
+        assert_eq!(dump("| a | b |\n| --- |\n"), "BeginNormal \"| a | b | | --- |\" EndNormal");
+        assert_eq!(dump("| a | b |\n| --- | x |\n"), "BeginNormal \"| a | b | | --- | x |\" EndNormal");
+        // a table that runs into the end of the input is still closed
+        assert_eq!(
+            dump("| a | b |\n| --- | --- |"),
+            "BeginTable { columns: [None, None] } BeginTableRow { header: true } BeginTableCell \"a\" EndTableCell BeginTableCell \"b\" EndTableCell EndTableRow EndTable"
+        );
+        // short rows are padded and a line without pipes ends the table
+        assert_eq!(
+            dump("| a | b |\n| --- | --- |\n| 1 |\nafter\n"),
+            "BeginTable { columns: [None, None] } \
+            BeginTableRow { header: true } BeginTableCell \"a\" EndTableCell BeginTableCell \"b\" EndTableCell EndTableRow \
+            BeginTableRow { header: false } BeginTableCell \"1\" EndTableCell BeginTableCell EndTableCell EndTableRow \
+            EndTable BeginNormal \"after\" EndNormal"
+        );
+    }
+
+    #[test]
+    fn table_cell_styles(){
+        assert_eq!(
+            dump("| a | b |\n| --- | --- |\n| **x *y* z** | ~~s~~ |\n"),
+            "BeginTable { columns: [None, None] } \
+            BeginTableRow { header: true } BeginTableCell \"a\" EndTableCell BeginTableCell \"b\" EndTableCell EndTableRow \
+            BeginTableRow { header: false } BeginTableCell BeginBold \"x \" BeginItalic \"y\" EndItalic \" z\" EndBold EndTableCell \
+            BeginTableCell BeginStrikethrough \"s\" EndStrikethrough EndTableCell EndTableRow \
+            EndTable"
+        );
+        // an unclosed style ends with its cell instead of leaking into the next one
+        assert_eq!(
+            dump("| a | b |\n| --- | --- |\n| **open | x |\n"),
+            "BeginTable { columns: [None, None] } \
+            BeginTableRow { header: true } BeginTableCell \"a\" EndTableCell BeginTableCell \"b\" EndTableCell EndTableRow \
+            BeginTableRow { header: false } BeginTableCell BeginBold \"open\" EndBold EndTableCell BeginTableCell \"x\" EndTableCell EndTableRow \
+            EndTable"
+        );
+    }
+
+    #[test]
+    fn task_list(){
+        assert_eq!(
+            dump("- [ ] todo\n- [x] done\n- [X] also\n"),
+            "BeginListItem { label: Task { checked: false } } \"todo\" EndListItem \
+            BeginListItem { label: Task { checked: true } } \"done\" EndListItem \
+            BeginListItem { label: Task { checked: true } } \"also\" EndListItem"
+        );
+        // anything else in the brackets is a plain item
+        assert_eq!(dump("- [y] no\n"), "BeginListItem { label: Minus } \"[y] no\" EndListItem");
+        assert_eq!(dump("- [x]no\n"), "BeginListItem { label: Minus } \"[x]no\" EndListItem");
+    }
+
+    #[test]
+    fn footnotes(){
+        // numbered by first reference, definitions move to the end in that order
+        assert_eq!(
+            dump("text[^n] more[^m] again[^n]\n\n[^m]: em\n[^n]: en\n"),
+            "BeginNormal \"text\" FootnoteRef { number: 1 } \" more\" FootnoteRef { number: 2 } \" again\" FootnoteRef { number: 1 } EndNormal \
+            BeginFootnote { number: 1 } \"en\" EndFootnote BeginFootnote { number: 2 } \"em\" EndFootnote"
+        );
+        // a reference without a definition stays text
+        assert_eq!(dump("undefined[^x]\n"), "BeginNormal \"undefined[^x]\" EndNormal");
+        assert_eq!(dump("odd[^ x]\n"), "BeginNormal \"odd[^ x]\" EndNormal");
+    }
+
+    #[test]
+    fn autolinks(){
+        assert_eq!(
+            dump("see https://example.com/a_(b), and www.x.org.\n"),
+            "BeginNormal \"see \" Link(\"https://example.com/a_(b)\", \"https://example.com/a_(b)\") \", and \" \
+            Link(\"www.x.org\", \"http://www.x.org\") \".\" EndNormal"
+        );
+        assert_eq!(
+            dump("mail <me@example.com> and <https://a.b> and <nope>\n"),
+            "BeginNormal \"mail \" Link(\"me@example.com\", \"mailto:me@example.com\") \" and \" \
+            Link(\"https://a.b\", \"https://a.b\") \" and <nope>\" EndNormal"
+        );
+        // a bare prefix or a url glued to a word is not a link
+        assert_eq!(dump("http:// alone\n"), "BeginNormal \"http:// alone\" EndNormal");
+        assert_eq!(dump("xhttp://a.b\n"), "BeginNormal \"xhttp://a.b\" EndNormal");
+        assert_eq!(dump("<a b>\n"), "BeginNormal \"<a b>\" EndNormal");
+    }
+
+    #[test]
+    fn strikethrough(){
+        assert_eq!(
+            dump("~~gone~~ and ~single~\n"),
+            "BeginNormal BeginStrikethrough \"gone\" EndStrikethrough \" and ~single~\" EndNormal"
+        );
+        // an unclosed strikethrough ends with the paragraph
+        assert_eq!(
+            dump("~~open\n\nnext\n"),
+            "BeginNormal BeginStrikethrough \"open\" EndStrikethrough EndNormal BeginNormal \"next\" EndNormal"
+        );
+        assert_eq!(
+            dump("*a **b** c*\n"),
+            "BeginNormal BeginItalic \"a \" BeginBold \"b\" EndBold \" c\" EndItalic EndNormal"
+        );
+    }
+}
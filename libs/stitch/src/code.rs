@@ -122,6 +122,20 @@ pub(crate) trait InstrVisitor {
     fn visit_f64_const(&mut self, val: f64) -> Result<(), Self::Error>;
     fn visit_un_op(&mut self, info: UnOpInfo) -> Result<(), Self::Error>;
     fn visit_bin_op(&mut self, info: BinOpInfo) -> Result<(), Self::Error>;
+
+    // Vector instructions
+    fn visit_v128_const(&mut self, val: u128) -> Result<(), Self::Error>;
+    fn visit_i8x16_shuffle(&mut self, lane_idxs: [u8; 16]) -> Result<(), Self::Error>;
+    fn visit_simd_op(&mut self, info: SimdOpInfo) -> Result<(), Self::Error>;
+    fn visit_simd_lane_op(&mut self, lane_idx: u8, info: SimdLaneOpInfo)
+        -> Result<(), Self::Error>;
+    fn visit_simd_mem_op(&mut self, arg: MemArg, info: SimdMemOpInfo) -> Result<(), Self::Error>;
+    fn visit_simd_mem_lane_op(
+        &mut self,
+        arg: MemArg,
+        lane_idx: u8,
+        info: SimdMemLaneOpInfo,
+    ) -> Result<(), Self::Error>;
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -158,6 +172,8 @@ impl Decode for BlockType {
             0x7E => Ok(BlockType::ValType(Some(ValType::I64))),
             0x7D => Ok(BlockType::ValType(Some(ValType::F32))),
             0x7C => Ok(BlockType::ValType(Some(ValType::F64))),
+            0x7B if cfg!(feature = "simd") => Ok(BlockType::ValType(Some(ValType::V128))),
+            0x7B => Err(DecodeError::new("SIMD support is not enabled")),
             0x70 => Ok(BlockType::ValType(Some(ValType::FuncRef))),
             0x6F => Ok(BlockType::ValType(Some(ValType::ExternRef))),
             byte => {
@@ -230,6 +246,33 @@ pub(crate) struct BinOpInfo {
     pub(crate) instr_rr: Option<ThreadedInstr>,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SimdOpInfo {
+    pub(crate) _name: &'static str,
+    pub(crate) input_types: &'static [ValType],
+    pub(crate) output_type: Option<ValType>,
+    pub(crate) instr: ThreadedInstr,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SimdLaneOpInfo {
+    pub(crate) lane_count: u8,
+    pub(crate) op: SimdOpInfo,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SimdMemOpInfo {
+    pub(crate) max_align: u32,
+    pub(crate) op: SimdOpInfo,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SimdMemLaneOpInfo {
+    pub(crate) max_align: u32,
+    pub(crate) lane_count: u8,
+    pub(crate) op: SimdOpInfo,
+}
+
 pub(crate) fn decode_instr<V>(
     decoder: &mut Decoder<'_>,
     label_idxs: &mut Vec<u32>,
@@ -2340,6 +2383,1648 @@ where
             17 => visitor.visit_table_fill(decoder.decode()?),
             _ => Err(DecodeError::new("illegal opcode"))?,
         },
+        0xFD if !cfg!(feature = "simd") => Err(DecodeError::new("SIMD support is not enabled"))?,
+        0xFD => match decoder.decode::<u32>()? {
+            0 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 4,
+                    op: SimdOpInfo {
+                        _name: "v128_load",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load,
+                    },
+                },
+            ),
+            1 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load8x8_s",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load8x8_s,
+                    },
+                },
+            ),
+            2 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load8x8_u",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load8x8_u,
+                    },
+                },
+            ),
+            3 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load16x4_s",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load16x4_s,
+                    },
+                },
+            ),
+            4 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load16x4_u",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load16x4_u,
+                    },
+                },
+            ),
+            5 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load32x2_s",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load32x2_s,
+                    },
+                },
+            ),
+            6 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load32x2_u",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load32x2_u,
+                    },
+                },
+            ),
+            7 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 0,
+                    op: SimdOpInfo {
+                        _name: "v128_load8_splat",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load8_splat,
+                    },
+                },
+            ),
+            8 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 1,
+                    op: SimdOpInfo {
+                        _name: "v128_load16_splat",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load16_splat,
+                    },
+                },
+            ),
+            9 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 2,
+                    op: SimdOpInfo {
+                        _name: "v128_load32_splat",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load32_splat,
+                    },
+                },
+            ),
+            10 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load64_splat",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load64_splat,
+                    },
+                },
+            ),
+            11 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 4,
+                    op: SimdOpInfo {
+                        _name: "v128_store",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: None,
+                        instr: exec::v128_store,
+                    },
+                },
+            ),
+            12 => visitor.visit_v128_const(decoder.decode()?),
+            13 => visitor.visit_i8x16_shuffle(decoder.read_bytes(16)?.try_into().unwrap()),
+            14 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_swizzle",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_swizzle,
+            }),
+            15 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_splat",
+                input_types: &[ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_splat,
+            }),
+            16 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_splat",
+                input_types: &[ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_splat,
+            }),
+            17 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_splat",
+                input_types: &[ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_splat,
+            }),
+            18 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_splat",
+                input_types: &[ValType::I64],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_splat,
+            }),
+            19 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_splat",
+                input_types: &[ValType::F32],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_splat,
+            }),
+            20 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_splat",
+                input_types: &[ValType::F64],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_splat,
+            }),
+            21 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 16,
+                    op: SimdOpInfo {
+                        _name: "i8x16_extract_lane_s",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::I32),
+                        instr: exec::i8x16_extract_lane_s,
+                    },
+                },
+            ),
+            22 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 16,
+                    op: SimdOpInfo {
+                        _name: "i8x16_extract_lane_u",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::I32),
+                        instr: exec::i8x16_extract_lane_u,
+                    },
+                },
+            ),
+            23 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 16,
+                    op: SimdOpInfo {
+                        _name: "i8x16_replace_lane",
+                        input_types: &[ValType::V128, ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::i8x16_replace_lane,
+                    },
+                },
+            ),
+            24 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 8,
+                    op: SimdOpInfo {
+                        _name: "i16x8_extract_lane_s",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::I32),
+                        instr: exec::i16x8_extract_lane_s,
+                    },
+                },
+            ),
+            25 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 8,
+                    op: SimdOpInfo {
+                        _name: "i16x8_extract_lane_u",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::I32),
+                        instr: exec::i16x8_extract_lane_u,
+                    },
+                },
+            ),
+            26 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 8,
+                    op: SimdOpInfo {
+                        _name: "i16x8_replace_lane",
+                        input_types: &[ValType::V128, ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::i16x8_replace_lane,
+                    },
+                },
+            ),
+            27 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 4,
+                    op: SimdOpInfo {
+                        _name: "i32x4_extract_lane",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::I32),
+                        instr: exec::i32x4_extract_lane,
+                    },
+                },
+            ),
+            28 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 4,
+                    op: SimdOpInfo {
+                        _name: "i32x4_replace_lane",
+                        input_types: &[ValType::V128, ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::i32x4_replace_lane,
+                    },
+                },
+            ),
+            29 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 2,
+                    op: SimdOpInfo {
+                        _name: "i64x2_extract_lane",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::I64),
+                        instr: exec::i64x2_extract_lane,
+                    },
+                },
+            ),
+            30 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 2,
+                    op: SimdOpInfo {
+                        _name: "i64x2_replace_lane",
+                        input_types: &[ValType::V128, ValType::I64],
+                        output_type: Some(ValType::V128),
+                        instr: exec::i64x2_replace_lane,
+                    },
+                },
+            ),
+            31 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 4,
+                    op: SimdOpInfo {
+                        _name: "f32x4_extract_lane",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::F32),
+                        instr: exec::f32x4_extract_lane,
+                    },
+                },
+            ),
+            32 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 4,
+                    op: SimdOpInfo {
+                        _name: "f32x4_replace_lane",
+                        input_types: &[ValType::V128, ValType::F32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::f32x4_replace_lane,
+                    },
+                },
+            ),
+            33 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 2,
+                    op: SimdOpInfo {
+                        _name: "f64x2_extract_lane",
+                        input_types: &[ValType::V128],
+                        output_type: Some(ValType::F64),
+                        instr: exec::f64x2_extract_lane,
+                    },
+                },
+            ),
+            34 => visitor.visit_simd_lane_op(
+                decoder.read_byte()?,
+                SimdLaneOpInfo {
+                    lane_count: 2,
+                    op: SimdOpInfo {
+                        _name: "f64x2_replace_lane",
+                        input_types: &[ValType::V128, ValType::F64],
+                        output_type: Some(ValType::V128),
+                        instr: exec::f64x2_replace_lane,
+                    },
+                },
+            ),
+            35 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_eq",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_eq,
+            }),
+            36 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_ne",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_ne,
+            }),
+            37 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_lt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_lt_s,
+            }),
+            38 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_lt_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_lt_u,
+            }),
+            39 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_gt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_gt_s,
+            }),
+            40 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_gt_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_gt_u,
+            }),
+            41 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_le_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_le_s,
+            }),
+            42 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_le_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_le_u,
+            }),
+            43 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_ge_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_ge_s,
+            }),
+            44 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_ge_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_ge_u,
+            }),
+            45 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_eq",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_eq,
+            }),
+            46 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_ne",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_ne,
+            }),
+            47 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_lt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_lt_s,
+            }),
+            48 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_lt_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_lt_u,
+            }),
+            49 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_gt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_gt_s,
+            }),
+            50 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_gt_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_gt_u,
+            }),
+            51 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_le_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_le_s,
+            }),
+            52 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_le_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_le_u,
+            }),
+            53 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_ge_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_ge_s,
+            }),
+            54 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_ge_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_ge_u,
+            }),
+            55 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_eq",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_eq,
+            }),
+            56 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_ne",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_ne,
+            }),
+            57 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_lt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_lt_s,
+            }),
+            58 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_lt_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_lt_u,
+            }),
+            59 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_gt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_gt_s,
+            }),
+            60 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_gt_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_gt_u,
+            }),
+            61 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_le_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_le_s,
+            }),
+            62 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_le_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_le_u,
+            }),
+            63 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_ge_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_ge_s,
+            }),
+            64 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_ge_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_ge_u,
+            }),
+            65 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_eq",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_eq,
+            }),
+            66 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_ne",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_ne,
+            }),
+            67 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_lt",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_lt,
+            }),
+            68 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_gt",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_gt,
+            }),
+            69 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_le",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_le,
+            }),
+            70 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_ge",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_ge,
+            }),
+            71 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_eq",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_eq,
+            }),
+            72 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_ne",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_ne,
+            }),
+            73 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_lt",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_lt,
+            }),
+            74 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_gt",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_gt,
+            }),
+            75 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_le",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_le,
+            }),
+            76 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_ge",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_ge,
+            }),
+            77 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "v128_not",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::v128_not,
+            }),
+            78 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "v128_and",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::v128_and,
+            }),
+            79 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "v128_andnot",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::v128_andnot,
+            }),
+            80 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "v128_or",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::v128_or,
+            }),
+            81 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "v128_xor",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::v128_xor,
+            }),
+            82 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "v128_bitselect",
+                input_types: &[ValType::V128, ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::v128_bitselect,
+            }),
+            83 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "v128_any_true",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::v128_any_true,
+            }),
+            84 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 0,
+                    lane_count: 16,
+                    op: SimdOpInfo {
+                        _name: "v128_load8_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load8_lane,
+                    },
+                },
+            ),
+            85 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 1,
+                    lane_count: 8,
+                    op: SimdOpInfo {
+                        _name: "v128_load16_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load16_lane,
+                    },
+                },
+            ),
+            86 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 2,
+                    lane_count: 4,
+                    op: SimdOpInfo {
+                        _name: "v128_load32_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load32_lane,
+                    },
+                },
+            ),
+            87 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 3,
+                    lane_count: 2,
+                    op: SimdOpInfo {
+                        _name: "v128_load64_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load64_lane,
+                    },
+                },
+            ),
+            88 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 0,
+                    lane_count: 16,
+                    op: SimdOpInfo {
+                        _name: "v128_store8_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: None,
+                        instr: exec::v128_store8_lane,
+                    },
+                },
+            ),
+            89 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 1,
+                    lane_count: 8,
+                    op: SimdOpInfo {
+                        _name: "v128_store16_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: None,
+                        instr: exec::v128_store16_lane,
+                    },
+                },
+            ),
+            90 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 2,
+                    lane_count: 4,
+                    op: SimdOpInfo {
+                        _name: "v128_store32_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: None,
+                        instr: exec::v128_store32_lane,
+                    },
+                },
+            ),
+            91 => visitor.visit_simd_mem_lane_op(
+                decoder.decode()?,
+                decoder.read_byte()?,
+                SimdMemLaneOpInfo {
+                    max_align: 3,
+                    lane_count: 2,
+                    op: SimdOpInfo {
+                        _name: "v128_store64_lane",
+                        input_types: &[ValType::I32, ValType::V128],
+                        output_type: None,
+                        instr: exec::v128_store64_lane,
+                    },
+                },
+            ),
+            92 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 2,
+                    op: SimdOpInfo {
+                        _name: "v128_load32_zero",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load32_zero,
+                    },
+                },
+            ),
+            93 => visitor.visit_simd_mem_op(
+                decoder.decode()?,
+                SimdMemOpInfo {
+                    max_align: 3,
+                    op: SimdOpInfo {
+                        _name: "v128_load64_zero",
+                        input_types: &[ValType::I32],
+                        output_type: Some(ValType::V128),
+                        instr: exec::v128_load64_zero,
+                    },
+                },
+            ),
+            94 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_demote_f64x2_zero",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_demote_f64x2_zero,
+            }),
+            95 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_promote_low_f32x4",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_promote_low_f32x4,
+            }),
+            96 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_abs",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_abs,
+            }),
+            97 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_neg",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_neg,
+            }),
+            98 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_popcnt",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_popcnt,
+            }),
+            99 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_all_true",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i8x16_all_true,
+            }),
+            100 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_bitmask",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i8x16_bitmask,
+            }),
+            101 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_narrow_i16x8_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_narrow_i16x8_s,
+            }),
+            102 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_narrow_i16x8_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_narrow_i16x8_u,
+            }),
+            103 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_ceil",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_ceil,
+            }),
+            104 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_floor",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_floor,
+            }),
+            105 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_trunc",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_trunc,
+            }),
+            106 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_nearest",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_nearest,
+            }),
+            107 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_shl",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_shl,
+            }),
+            108 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_shr_s",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_shr_s,
+            }),
+            109 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_shr_u",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_shr_u,
+            }),
+            110 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_add",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_add,
+            }),
+            111 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_add_sat_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_add_sat_s,
+            }),
+            112 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_add_sat_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_add_sat_u,
+            }),
+            113 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_sub",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_sub,
+            }),
+            114 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_sub_sat_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_sub_sat_s,
+            }),
+            115 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_sub_sat_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_sub_sat_u,
+            }),
+            116 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_ceil",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_ceil,
+            }),
+            117 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_floor",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_floor,
+            }),
+            118 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_min_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_min_s,
+            }),
+            119 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_min_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_min_u,
+            }),
+            120 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_max_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_max_s,
+            }),
+            121 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_max_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_max_u,
+            }),
+            122 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_trunc",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_trunc,
+            }),
+            123 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i8x16_avgr_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_avgr_u,
+            }),
+            124 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extadd_pairwise_i8x16_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extadd_pairwise_i8x16_s,
+            }),
+            125 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extadd_pairwise_i8x16_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extadd_pairwise_i8x16_u,
+            }),
+            126 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extadd_pairwise_i16x8_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extadd_pairwise_i16x8_s,
+            }),
+            127 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extadd_pairwise_i16x8_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extadd_pairwise_i16x8_u,
+            }),
+            128 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_abs",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_abs,
+            }),
+            129 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_neg",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_neg,
+            }),
+            130 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_q15mulr_sat_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_q15mulr_sat_s,
+            }),
+            131 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_all_true",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i16x8_all_true,
+            }),
+            132 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_bitmask",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i16x8_bitmask,
+            }),
+            133 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_narrow_i32x4_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_narrow_i32x4_s,
+            }),
+            134 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_narrow_i32x4_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_narrow_i32x4_u,
+            }),
+            135 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extend_low_i8x16_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extend_low_i8x16_s,
+            }),
+            136 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extend_high_i8x16_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extend_high_i8x16_s,
+            }),
+            137 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extend_low_i8x16_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extend_low_i8x16_u,
+            }),
+            138 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extend_high_i8x16_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extend_high_i8x16_u,
+            }),
+            139 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_shl",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_shl,
+            }),
+            140 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_shr_s",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_shr_s,
+            }),
+            141 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_shr_u",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_shr_u,
+            }),
+            142 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_add",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_add,
+            }),
+            143 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_add_sat_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_add_sat_s,
+            }),
+            144 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_add_sat_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_add_sat_u,
+            }),
+            145 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_sub",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_sub,
+            }),
+            146 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_sub_sat_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_sub_sat_s,
+            }),
+            147 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_sub_sat_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_sub_sat_u,
+            }),
+            148 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_nearest",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_nearest,
+            }),
+            149 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_mul",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_mul,
+            }),
+            150 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_min_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_min_s,
+            }),
+            151 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_min_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_min_u,
+            }),
+            152 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_max_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_max_s,
+            }),
+            153 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_max_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_max_u,
+            }),
+            155 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_avgr_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_avgr_u,
+            }),
+            156 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extmul_low_i8x16_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extmul_low_i8x16_s,
+            }),
+            157 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extmul_high_i8x16_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extmul_high_i8x16_s,
+            }),
+            158 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extmul_low_i8x16_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extmul_low_i8x16_u,
+            }),
+            159 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i16x8_extmul_high_i8x16_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i16x8_extmul_high_i8x16_u,
+            }),
+            160 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_abs",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_abs,
+            }),
+            161 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_neg",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_neg,
+            }),
+            163 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_all_true",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i32x4_all_true,
+            }),
+            164 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_bitmask",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i32x4_bitmask,
+            }),
+            167 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extend_low_i16x8_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extend_low_i16x8_s,
+            }),
+            168 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extend_high_i16x8_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extend_high_i16x8_s,
+            }),
+            169 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extend_low_i16x8_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extend_low_i16x8_u,
+            }),
+            170 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extend_high_i16x8_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extend_high_i16x8_u,
+            }),
+            171 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_shl",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_shl,
+            }),
+            172 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_shr_s",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_shr_s,
+            }),
+            173 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_shr_u",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_shr_u,
+            }),
+            174 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_add",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_add,
+            }),
+            177 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_sub",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_sub,
+            }),
+            181 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_mul",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_mul,
+            }),
+            182 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_min_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_min_s,
+            }),
+            183 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_min_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_min_u,
+            }),
+            184 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_max_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_max_s,
+            }),
+            185 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_max_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_max_u,
+            }),
+            186 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_dot_i16x8_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_dot_i16x8_s,
+            }),
+            188 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extmul_low_i16x8_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extmul_low_i16x8_s,
+            }),
+            189 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extmul_high_i16x8_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extmul_high_i16x8_s,
+            }),
+            190 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extmul_low_i16x8_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extmul_low_i16x8_u,
+            }),
+            191 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_extmul_high_i16x8_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_extmul_high_i16x8_u,
+            }),
+            192 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_abs",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_abs,
+            }),
+            193 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_neg",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_neg,
+            }),
+            195 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_all_true",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i64x2_all_true,
+            }),
+            196 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_bitmask",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::I32),
+                instr: exec::i64x2_bitmask,
+            }),
+            199 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extend_low_i32x4_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extend_low_i32x4_s,
+            }),
+            200 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extend_high_i32x4_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extend_high_i32x4_s,
+            }),
+            201 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extend_low_i32x4_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extend_low_i32x4_u,
+            }),
+            202 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extend_high_i32x4_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extend_high_i32x4_u,
+            }),
+            203 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_shl",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_shl,
+            }),
+            204 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_shr_s",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_shr_s,
+            }),
+            205 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_shr_u",
+                input_types: &[ValType::V128, ValType::I32],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_shr_u,
+            }),
+            206 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_add",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_add,
+            }),
+            209 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_sub",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_sub,
+            }),
+            213 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_mul",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_mul,
+            }),
+            214 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_eq",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_eq,
+            }),
+            215 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_ne",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_ne,
+            }),
+            216 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_lt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_lt_s,
+            }),
+            217 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_gt_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_gt_s,
+            }),
+            218 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_le_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_le_s,
+            }),
+            219 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_ge_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_ge_s,
+            }),
+            220 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extmul_low_i32x4_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extmul_low_i32x4_s,
+            }),
+            221 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extmul_high_i32x4_s",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extmul_high_i32x4_s,
+            }),
+            222 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extmul_low_i32x4_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extmul_low_i32x4_u,
+            }),
+            223 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i64x2_extmul_high_i32x4_u",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i64x2_extmul_high_i32x4_u,
+            }),
+            224 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_abs",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_abs,
+            }),
+            225 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_neg",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_neg,
+            }),
+            227 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_sqrt",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_sqrt,
+            }),
+            228 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_add",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_add,
+            }),
+            229 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_sub",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_sub,
+            }),
+            230 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_mul",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_mul,
+            }),
+            231 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_div",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_div,
+            }),
+            232 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_min",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_min,
+            }),
+            233 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_max",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_max,
+            }),
+            234 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_pmin",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_pmin,
+            }),
+            235 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_pmax",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_pmax,
+            }),
+            236 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_abs",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_abs,
+            }),
+            237 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_neg",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_neg,
+            }),
+            239 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_sqrt",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_sqrt,
+            }),
+            240 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_add",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_add,
+            }),
+            241 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_sub",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_sub,
+            }),
+            242 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_mul",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_mul,
+            }),
+            243 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_div",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_div,
+            }),
+            244 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_min",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_min,
+            }),
+            245 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_max",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_max,
+            }),
+            246 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_pmin",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_pmin,
+            }),
+            247 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_pmax",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_pmax,
+            }),
+            248 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_trunc_sat_f32x4_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_trunc_sat_f32x4_s,
+            }),
+            249 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_trunc_sat_f32x4_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_trunc_sat_f32x4_u,
+            }),
+            250 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_convert_i32x4_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_convert_i32x4_s,
+            }),
+            251 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f32x4_convert_i32x4_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f32x4_convert_i32x4_u,
+            }),
+            252 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_trunc_sat_f64x2_s_zero",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_trunc_sat_f64x2_s_zero,
+            }),
+            253 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "i32x4_trunc_sat_f64x2_u_zero",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i32x4_trunc_sat_f64x2_u_zero,
+            }),
+            254 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_convert_low_i32x4_s",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_convert_low_i32x4_s,
+            }),
+            255 => visitor.visit_simd_op(SimdOpInfo {
+                _name: "f64x2_convert_low_i32x4_u",
+                input_types: &[ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::f64x2_convert_low_i32x4_u,
+            }),
+            _ => Err(DecodeError::new("illegal opcode"))?,
+        },
         _ => Err(DecodeError::new("illegal opcode"))?,
     }
 }
@@ -4,13 +4,14 @@ use {
         code,
         code::{
             BinOpInfo, BlockType, CompiledCode, InstrSlot, InstrVisitor, LoadInfo, MemArg,
-            StoreInfo, UnOpInfo, UncompiledCode,
+            SimdLaneOpInfo, SimdMemLaneOpInfo, SimdMemOpInfo, SimdOpInfo, StoreInfo, UnOpInfo,
+            UncompiledCode,
         },
         decode::DecodeError,
         exec,
         exec::ThreadedInstr,
         extern_ref::ExternRef,
-        func::{Func, FuncEntity, FuncType},
+        func::{Func, FuncEntity, FuncType, UnguardedFunc},
         func_ref::FuncRef,
         instance::Instance,
         ref_::RefType,
@@ -83,6 +84,11 @@ impl Compiler {
             max_stack_height: local_count,
             regs: [None; 2],
             code: Vec::new(),
+            metered_func: if store.engine().config().metering {
+                Some(func.to_unguarded(store.id()))
+            } else {
+                None
+            },
         };
         compile.push_block(
             BlockKind::Block,
@@ -97,6 +103,7 @@ impl Compiler {
                 .mem(0)
                 .map(|mem| mem.to_unguarded(store.id())),
         );
+        compile.emit_checkpoint();
 
         let mut decoder = Decoder::new(&code.expr);
         while !compile.blocks.is_empty() {
@@ -143,6 +150,8 @@ struct Compile<'a> {
     max_stack_height: usize,
     regs: [Option<usize>; 2],
     code: Vec<InstrSlot>,
+    // The function being compiled, if checkpoints should be emitted for it.
+    metered_func: Option<UnguardedFunc>,
 }
 
 impl<'a> Compile<'a> {
@@ -473,6 +482,14 @@ impl<'a> Compile<'a> {
         unsafe { *(self.code.last_mut().unwrap() as *mut _ as *mut T) = val };
     }
 
+    // Emits a `checkpoint` instruction, if metering is enabled.
+    fn emit_checkpoint(&mut self) {
+        if let Some(func) = self.metered_func {
+            self.emit(exec::checkpoint as ThreadedInstr);
+            self.emit(func);
+        }
+    }
+
     // Emits an operand.
     //
     // For local and temporary operands, which can be read from the stack, this emits the offset of
@@ -495,6 +512,12 @@ impl<'a> Compile<'a> {
             UnguardedVal::I64(val) => self.emit(val),
             UnguardedVal::F32(val) => self.emit(val),
             UnguardedVal::F64(val) => self.emit(val),
+            UnguardedVal::V128(val) => {
+                // A `v128` is wider than an instruction slot, so it is split over multiple slots.
+                for bytes in val.to_ne_bytes().chunks(mem::size_of::<InstrSlot>()) {
+                    self.emit(InstrSlot::from_ne_bytes(bytes.try_into().unwrap()));
+                }
+            }
             UnguardedVal::FuncRef(val) => self.emit(val),
             UnguardedVal::ExternRef(val) => self.emit(val),
         }
@@ -542,6 +565,45 @@ impl<'a> Compile<'a> {
         self.fixup_idxs.push(self.code.len());
         self.emit(instr_idx * mem::size_of::<InstrSlot>());
     }
+
+    // Methods for compiling vector instructions.
+
+    /// Compiles a SIMD operation.
+    ///
+    /// Unlike other operations, SIMD operations come in only a single variant, which reads all its
+    /// inputs from the stack and writes its output to the stack. This is because `v128` values are
+    /// never stored in a register or as an immediate. The given closure emits any immediates for
+    /// the operation, which follow the inputs.
+    fn compile_simd_op(&mut self, info: SimdOpInfo, emit_imms: impl FnOnce(&mut Self)) {
+        // Skip this instruction if it is unreachable.
+        if self.block(0).is_unreachable {
+            return;
+        }
+
+        // Ensure that the inputs can be read from the stack.
+        for opd_depth in 0..info.input_types.len() {
+            self.ensure_opd_not_imm(opd_depth);
+            self.ensure_opd_not_reg(opd_depth);
+        }
+
+        // Emit the instruction.
+        self.emit(info.instr);
+
+        // Emit the inputs and pop them from the stack.
+        for _ in 0..info.input_types.len() {
+            self.emit_and_pop_opd();
+        }
+
+        // Emit the immediates.
+        emit_imms(self);
+
+        // If the operation has an output, push the output onto the stack and emit its stack
+        // offset.
+        if let Some(output_type) = info.output_type {
+            self.push_opd(output_type);
+            self.emit_stack_offset(self.opd_stack_idx(0));
+        }
+    }
 }
 
 impl<'a> InstrVisitor for Compile<'a> {
@@ -613,6 +675,9 @@ impl<'a> InstrVisitor for Compile<'a> {
 
         self.push_block(BlockKind::Loop, type_);
 
+        // Branches to the loop go through the checkpoint, so each iteration is metered.
+        self.emit_checkpoint();
+
         Ok(())
     }
 
@@ -1151,6 +1216,25 @@ impl<'a> InstrVisitor for Compile<'a> {
 
         let type_ = type_.unwrap_or_else(|| self.opd(1).type_);
 
+        // A `v128` is never stored in a register, so the `select` instruction for `v128` values
+        // reads all its inputs from the stack and writes its output to the stack.
+        if type_.is_vec() {
+            self.ensure_opd_not_imm(0);
+            self.ensure_opd_not_reg(0);
+            self.emit(select_select(
+                type_,
+                self.opd(2).kind(),
+                self.opd(1).kind(),
+                self.opd(0).kind(),
+            ));
+            for _ in 0..3 {
+                self.emit_and_pop_opd();
+            }
+            self.push_opd(type_);
+            self.emit_stack_offset(self.opd_stack_idx(0));
+            return Ok(());
+        }
+
         // The `select` instruction does not have any _{sri}{sri}i variants.
         //
         // For instance, the following sequence of instructions:
@@ -2049,6 +2133,77 @@ impl<'a> InstrVisitor for Compile<'a> {
 
         Ok(())
     }
+
+    // Vector instructions
+
+    /// Compiles a `v128.const` instruction.
+    fn visit_v128_const(&mut self, val: u128) -> Result<(), DecodeError> {
+        // Skip this instruction if it is unreachable.
+        if self.block(0).is_unreachable {
+            return Ok(());
+        }
+
+        // A `v128` is never stored as an immediate operand, so we copy the value to the stack
+        // right away.
+        self.emit(exec::copy_imm_to_stack_v128 as ThreadedInstr);
+        self.emit_val(UnguardedVal::V128(val));
+
+        // Push the output onto the stack and emit its stack offset.
+        self.push_opd(ValType::V128);
+        self.emit_stack_offset(self.opd_stack_idx(0));
+
+        Ok(())
+    }
+
+    /// Compiles an `i8x16.shuffle` instruction.
+    fn visit_i8x16_shuffle(&mut self, lane_idxs: [u8; 16]) -> Result<(), DecodeError> {
+        self.compile_simd_op(
+            SimdOpInfo {
+                _name: "i8x16_shuffle",
+                input_types: &[ValType::V128, ValType::V128],
+                output_type: Some(ValType::V128),
+                instr: exec::i8x16_shuffle,
+            },
+            |this| this.emit_val(UnguardedVal::V128(u128::from_le_bytes(lane_idxs))),
+        );
+        Ok(())
+    }
+
+    /// Compiles a SIMD operation.
+    fn visit_simd_op(&mut self, info: SimdOpInfo) -> Result<(), DecodeError> {
+        self.compile_simd_op(info, |_| {});
+        Ok(())
+    }
+
+    /// Compiles a SIMD operation on a single lane.
+    fn visit_simd_lane_op(
+        &mut self,
+        lane_idx: u8,
+        info: SimdLaneOpInfo,
+    ) -> Result<(), DecodeError> {
+        self.compile_simd_op(info.op, |this| this.emit(lane_idx));
+        Ok(())
+    }
+
+    /// Compiles a SIMD load or store.
+    fn visit_simd_mem_op(&mut self, arg: MemArg, info: SimdMemOpInfo) -> Result<(), DecodeError> {
+        self.compile_simd_op(info.op, |this| this.emit(arg.offset));
+        Ok(())
+    }
+
+    /// Compiles a SIMD load or store of a single lane.
+    fn visit_simd_mem_lane_op(
+        &mut self,
+        arg: MemArg,
+        lane_idx: u8,
+        info: SimdMemLaneOpInfo,
+    ) -> Result<(), DecodeError> {
+        self.compile_simd_op(info.op, |this| {
+            this.emit(arg.offset);
+            this.emit(lane_idx);
+        });
+        Ok(())
+    }
 }
 
 /// A local on the stack.
@@ -2405,6 +2560,8 @@ fn select_select(
             exec::select_extern_ref_iir
         }
 
+        (ValType::V128, OpdKind::Stack, OpdKind::Stack, OpdKind::Stack) => exec::select_v128_sss,
+
         // The first operand is an integer or a reference, and the third operand is an integer,
         // both of which are stored in a register. Since we only have one integer register
         // available, there is no variant of this instruction that can handle this case.
@@ -2420,6 +2577,9 @@ fn select_select(
             OpdKind::Reg,
             OpdKind::Reg,
         )
+        // A `v128` is never stored in a register or as an immediate, and there is only a variant
+        // of this instruction for `v128` values that reads its condition from the stack.
+        | (ValType::V128, _, _, _)
         // The first and the second operand have the same type, which means they are stored in the
         // same register. Since we only have one register available for every type, there is no
         // variant of this instruction that can handle this case.
@@ -2434,6 +2594,7 @@ fn select_global_get(type_: ValType) -> ThreadedInstr {
         ValType::I64 => exec::global_get_i64,
         ValType::F32 => exec::global_get_f32,
         ValType::F64 => exec::global_get_f64,
+        ValType::V128 => exec::global_get_v128,
         ValType::FuncRef => exec::global_get_func_ref,
         ValType::ExternRef => exec::global_get_extern_ref,
     }
@@ -2453,12 +2614,16 @@ fn select_global_set(type_: ValType, kind: OpdKind) -> ThreadedInstr {
         (ValType::F64, OpdKind::Stack) => exec::global_set_f64_s,
         (ValType::F64, OpdKind::Reg) => exec::global_set_f64_r,
         (ValType::F64, OpdKind::Imm) => exec::global_set_f64_i,
+        (ValType::V128, OpdKind::Stack) => exec::global_set_v128_s,
         (ValType::FuncRef, OpdKind::Stack) => exec::global_set_func_ref_s,
         (ValType::FuncRef, OpdKind::Reg) => exec::global_set_func_ref_r,
         (ValType::FuncRef, OpdKind::Imm) => exec::global_set_func_ref_i,
         (ValType::ExternRef, OpdKind::Stack) => exec::global_set_extern_ref_s,
         (ValType::ExternRef, OpdKind::Reg) => exec::global_set_extern_ref_r,
         (ValType::ExternRef, OpdKind::Imm) => exec::global_set_extern_ref_i,
+
+        // A `v128` is never stored in a register or as an immediate.
+        (ValType::V128, OpdKind::Reg | OpdKind::Imm) => panic!("no suitable instruction found"),
     }
 }
 
@@ -2583,6 +2748,7 @@ fn select_copy_imm_to_stack(type_: ValType) -> ThreadedInstr {
         ValType::I64 => exec::copy_imm_to_stack_i64,
         ValType::F32 => exec::copy_imm_to_stack_f32,
         ValType::F64 => exec::copy_imm_to_stack_f64,
+        ValType::V128 => exec::copy_imm_to_stack_v128,
         ValType::FuncRef => exec::copy_imm_to_stack_func_ref,
         ValType::ExternRef => exec::copy_imm_to_stack_extern_ref,
     }
@@ -2594,6 +2760,7 @@ fn select_copy_stack(type_: ValType) -> ThreadedInstr {
         ValType::I64 => exec::copy_stack_i64,
         ValType::F32 => exec::copy_stack_f32,
         ValType::F64 => exec::copy_stack_f64,
+        ValType::V128 => exec::copy_stack_v128,
         ValType::FuncRef => exec::copy_stack_func_ref,
         ValType::ExternRef => exec::copy_stack_extern_ref,
     }
@@ -2605,6 +2772,7 @@ fn select_copy_reg_to_stack(type_: ValType) -> ThreadedInstr {
         ValType::I64 => exec::copy_reg_to_stack_i64,
         ValType::F32 => exec::copy_reg_to_stack_f32,
         ValType::F64 => exec::copy_reg_to_stack_f64,
+        ValType::V128 => panic!("no suitable instruction found"),
         ValType::FuncRef => exec::copy_reg_to_stack_func_ref,
         ValType::ExternRef => exec::copy_reg_to_stack_extern_ref,
     }
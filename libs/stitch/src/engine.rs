@@ -21,14 +21,25 @@ pub struct Engine {
 impl Engine {
     /// Creates a new [`Engine`].
     pub fn new() -> Engine {
+        Engine::with_config(EngineConfig::default())
+    }
+
+    /// Creates a new [`Engine`] with the given [`EngineConfig`].
+    pub fn with_config(config: EngineConfig) -> Engine {
         Engine {
             inner: Arc::new(EngineInner {
+                config,
                 validators: Mutex::new(Pool::new()),
                 compilers: Mutex::new(Pool::new()),
             }),
         }
     }
 
+    /// Returns the [`EngineConfig`] of this [`Engine`].
+    pub fn config(&self) -> &EngineConfig {
+        &self.inner.config
+    }
+
     pub(crate) fn validate(
         &self,
         type_: &FuncType,
@@ -61,8 +72,20 @@ impl Default for Engine {
     }
 }
 
+/// The configuration of an [`Engine`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EngineConfig {
+    /// Whether compiled code checks for fuel and interrupts on each function entry and loop
+    /// iteration.
+    ///
+    /// This is what makes [`Store::set_fuel`] and [`InterruptHandle`](crate::InterruptHandle)s
+    /// take effect, at the cost of some speed.
+    pub metering: bool,
+}
+
 #[derive(Debug)]
 struct EngineInner {
+    config: EngineConfig,
     validators: Mutex<Pool<Validator>>,
     compilers: Mutex<Pool<Compiler>>,
 }
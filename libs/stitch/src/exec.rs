@@ -13,6 +13,7 @@ use {
         global::UnguardedGlobal,
         mem::UnguardedMem,
         ops::*,
+        simd,
         stack::{Stack, StackGuard, StackSlot},
         store::{Handle, Store, UnguardedInternedFuncType},
         table::UnguardedTable,
@@ -118,6 +119,7 @@ pub(crate) struct Context<'a> {
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum ControlFlow {
     Stop,
+    Continue,
     Trap(Trap),
     Error,
 }
@@ -127,6 +129,8 @@ impl ControlFlow {
     pub(crate) fn from_bits(bits: usize) -> Option<Self> {
         if bits == 0 {
             Some(Self::Stop)
+        } else if bits == 1 {
+            Some(Self::Continue)
         } else if bits & 0x03 == 2 {
             Trap::from_usize(bits >> 2).map(Self::Trap)
         } else if bits & 0x03 == 3 {
@@ -140,6 +144,7 @@ impl ControlFlow {
     pub(crate) fn to_bits(self) -> ControlFlowBits {
         match self {
             Self::Stop => 0,
+            Self::Continue => 1,
             Self::Trap(trap) => trap.to_usize() << 2 | 2,
             Self::Error => 3,
         }
@@ -158,6 +163,30 @@ pub(crate) fn exec(
     args: &[Val],
     results: &mut [Val],
 ) -> Result<(), Error> {
+    exec_inner(store, func, args, results, false)?;
+    Ok(())
+}
+
+/// Executes the given [`Func`] with the given arguments, like [`exec`], but suspends execution
+/// instead of trapping if it runs out of fuel or is interrupted.
+///
+/// Returns a [`Suspension`] if execution was suspended.
+pub(crate) fn exec_resumable(
+    store: &mut Store,
+    func: Func,
+    args: &[Val],
+    results: &mut [Val],
+) -> Result<Option<Suspension>, Error> {
+    exec_inner(store, func, args, results, true)
+}
+
+fn exec_inner(
+    store: &mut Store,
+    func: Func,
+    args: &[Val],
+    results: &mut [Val],
+    resumable: bool,
+) -> Result<Option<Suspension>, Error> {
     // Lock the stack for the current thread.
     let mut stack = Stack::lock();
 
@@ -170,6 +199,10 @@ pub(crate) fn exec(
         return Err(Trap::StackOverflow)?;
     }
 
+    // Only calls at the bottom of the stack can be suspended, because calls made from host
+    // functions have to return before the Wasm code that called them can continue.
+    let resumable = resumable && stack_height == 0;
+
     // Copy the arguments to the stack.
     let mut ptr = stack.ptr();
     for arg in args.iter().copied() {
@@ -193,8 +226,16 @@ pub(crate) fn exec(
                 unreachable!();
             };
 
-            // Create a trampoline for the [`WasmFuncEntity`].
-            let mut trampoline = [
+            // Create a trampoline for the [`WasmFuncEntity`]. The trampoline is the bottom of the
+            // call stack, so it has to outlive this call if it is suspended.
+            let mut local_trampoline = [0; 4];
+            let mut boxed_trampoline = None;
+            let trampoline = if resumable {
+                boxed_trampoline.insert(Box::new([0; 4]))
+            } else {
+                &mut local_trampoline
+            };
+            *trampoline = [
                 call as InstrSlot,
                 code.code.as_mut_ptr() as InstrSlot,
                 type_.call_frame_size() * mem::size_of::<StackSlot>(),
@@ -202,7 +243,7 @@ pub(crate) fn exec(
             ];
 
             // Create an execution context.
-            let mut context = Context {
+            let context = Context {
                 ip: trampoline.as_mut_ptr(),
                 sp: stack.ptr(),
                 md: ptr::null_mut(),
@@ -210,52 +251,15 @@ pub(crate) fn exec(
                 ix: 0,
                 sx: 0.0,
                 dx: 0.0,
-                store,
+                store: &mut *store,
                 stack: Some(stack),
                 error: None,
             };
 
-            // Main interpreter loop
-            loop {
-                match ControlFlow::from_bits(unsafe {
-                    next_instr(
-                        context.ip,
-                        context.sp,
-                        context.md,
-                        context.ms,
-                        context.ix,
-                        context.sx,
-                        context.dx,
-                        &mut context as *mut _,
-                    )
-                })
-                .unwrap()
-                {
-                    ControlFlow::Stop => {
-                        stack = context.stack.take().unwrap();
-
-                        // Reset the stack to the start of the call frame.
-                        stack.set_ptr(ptr);
-
-                        break;
-                    }
-                    ControlFlow::Trap(trap) => {
-                        stack = context.stack.take().unwrap();
-
-                        // Reset the stack to the start of the call frame.
-                        stack.set_ptr(ptr);
-
-                        return Err(trap)?;
-                    }
-                    ControlFlow::Error => {
-                        stack = context.stack.take().unwrap();
-
-                        // Reset the stack to the start of the call frame.
-                        stack.set_ptr(ptr);
-
-                        return Err(context.error.take().unwrap());
-                    }
-                }
+            let (new_stack, result) = unsafe { run(context, ptr, boxed_trampoline) };
+            stack = new_stack;
+            if let Some(suspension) = result? {
+                return Ok(Some(suspension));
             }
         }
         FuncEntity::Host(func) => {
@@ -270,7 +274,173 @@ pub(crate) fn exec(
         }
     }
 
-    // Copy the results from the stack.
+    read_results(store, &mut stack, results);
+    Ok(None)
+}
+
+/// Resumes the execution of a call that was suspended.
+///
+/// Returns a new [`Suspension`] if execution was suspended again.
+///
+/// # Panics
+///
+/// If another call was suspended after the given one, and is still suspended.
+pub(crate) fn resume(
+    store: &mut Store,
+    suspension: Suspension,
+    results: &mut [Val],
+) -> Result<Option<Suspension>, Error> {
+    let mut stack = Stack::lock();
+    assert_eq!(
+        stack.ptr(),
+        suspension.top,
+        "another call is suspended on top of this one"
+    );
+
+    // Restore the registers, taking the ones that memory growth updates from the stack.
+    let regs = unsafe { suspension.top.sub(4) };
+    let context = unsafe {
+        Context {
+            ip: suspension.ip,
+            sp: *regs.offset(-3).cast(),
+            md: *regs.offset(-2).cast(),
+            ms: *regs.offset(-1).cast(),
+            ix: suspension.ix,
+            sx: suspension.sx,
+            dx: suspension.dx,
+            store: &mut *store,
+            stack: Some(stack),
+            error: None,
+        }
+    };
+
+    let (new_stack, result) =
+        unsafe { run(context, suspension.start, Some(suspension.trampoline)) };
+    stack = new_stack;
+    if let Some(suspension) = result? {
+        return Ok(Some(suspension));
+    }
+    read_results(store, &mut stack, results);
+    Ok(None)
+}
+
+/// Drops a suspended call, by removing its call frames from the stack.
+pub(crate) fn discard(suspension: &Suspension) {
+    let mut stack = Stack::lock();
+    if stack.ptr() == suspension.top {
+        stack.set_ptr(suspension.start);
+    }
+}
+
+/// The state of a suspended call.
+///
+/// The call frames of a suspended call stay on the stack, with two more frames on top of them: one
+/// that holds the `Sp`, `Md` and `Ms` registers, so memory growth can update them, and one that
+/// links to it. The other registers are stored here.
+#[derive(Debug)]
+pub(crate) struct Suspension {
+    trampoline: Box<[InstrSlot; 4]>,
+    // The start of the call frame of the suspended call.
+    start: *mut StackSlot,
+    // The end of the frame on top of the suspended call frames.
+    top: *mut StackSlot,
+    ip: Ip,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    pub(crate) trap: Trap,
+}
+
+/// Runs the threaded code in the given [`Context`] until it stops.
+///
+/// The stack is reset to `start` afterwards, unless execution is suspended. Execution can only be
+/// suspended if a `trampoline` is given, which is then owned by the [`Suspension`].
+///
+/// # Safety
+///
+/// The registers in the [`Context`] must be valid.
+unsafe fn run(
+    mut context: Context,
+    start: *mut StackSlot,
+    trampoline: Option<Box<[InstrSlot; 4]>>,
+) -> (StackGuard, Result<Option<Suspension>, Error>) {
+    // Main interpreter loop. When sibling calls can't be relied on, each instruction returns here
+    // with the registers stored in the context, instead of calling the next instruction directly.
+    let control_flow = loop {
+        let (instr, ip): (ThreadedInstr, _) = read_imm(context.ip);
+        match ControlFlow::from_bits(instr(
+            ip,
+            context.sp,
+            context.md,
+            context.ms,
+            context.ix,
+            context.sx,
+            context.dx,
+            &mut context as *mut _,
+        ))
+        .unwrap()
+        {
+            ControlFlow::Continue => {}
+            control_flow => break control_flow,
+        }
+    };
+    let result = match control_flow {
+        ControlFlow::Stop => Ok(None),
+        ControlFlow::Continue => unreachable!(),
+        ControlFlow::Trap(trap @ (Trap::OutOfFuel | Trap::Interrupted)) if trampoline.is_some() => {
+            // The `checkpoint` instruction that trapped stored the registers in the context, with
+            // `ip` pointing to itself, so it is executed again on resume.
+            let stack = context.stack.as_mut().unwrap();
+            let func: UnguardedFunc = *context.ip.add(1).cast();
+            let FuncEntity::Wasm(func) = func.as_ref() else {
+                unreachable!();
+            };
+            let Code::Compiled(code) = func.code() else {
+                unreachable!();
+            };
+            // Memory growth updates the frames below the one on top of the stack, so we need two
+            // frames: one that holds the registers, and one on top that links to it.
+            let regs = context.sp.add(code.max_stack_height + 4);
+            let top = regs.add(4);
+            if top.offset_from(stack.base_ptr()) as usize > Stack::SIZE {
+                Err(Trap::StackOverflow.into())
+            } else {
+                *regs.offset(-3).cast() = context.sp;
+                *regs.offset(-2).cast() = context.md;
+                *regs.offset(-1).cast() = context.ms;
+                *top.offset(-3).cast() = regs;
+                stack.set_ptr(top);
+                let stack = context.stack.take().unwrap();
+                return (
+                    stack,
+                    Ok(Some(Suspension {
+                        trampoline: trampoline.unwrap(),
+                        start,
+                        top,
+                        ip: context.ip,
+                        ix: context.ix,
+                        sx: context.sx,
+                        dx: context.dx,
+                        trap,
+                    })),
+                );
+            }
+        }
+        ControlFlow::Trap(trap) => Err(trap.into()),
+        ControlFlow::Error => Err(context.error.take().unwrap()),
+    };
+
+    // A host function that fails unlocks the stack without handing it back.
+    let mut stack = context.stack.take().unwrap_or_else(Stack::lock);
+
+    // Reset the stack to the start of the call frame.
+    stack.set_ptr(start);
+
+    (stack, result)
+}
+
+/// Copies the results of a call from the stack.
+fn read_results(store: &Store, stack: &mut StackGuard, results: &mut [Val]) {
     let mut ptr = stack.ptr();
     for result in results.iter_mut() {
         unsafe {
@@ -281,8 +451,6 @@ pub(crate) fn exec(
             ptr = ptr.add(1);
         }
     }
-
-    Ok(())
 }
 
 // Helper macros
@@ -1306,6 +1474,31 @@ select!(
     UnguardedExternRef
 );
 
+threaded_instr!(select_v128_sss(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    // Read operands
+    let (cond, ip): (u32, _) = read_stack(ip, sp);
+    let (x1, ip): (u128, _) = read_stack(ip, sp);
+    let (x0, ip): (u128, _) = read_stack(ip, sp);
+
+    // Perform operation
+    let y = if cond != 0 { x0 } else { x1 };
+
+    // Write result
+    let ip = write_stack(ip, sp, y);
+
+    // Execute next instruction
+    next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+});
+
 // Variable instructions
 
 macro_rules! global_get {
@@ -1343,6 +1536,7 @@ global_get!(global_get_i32, i32);
 global_get!(global_get_i64, i64);
 global_get!(global_get_f32, f32);
 global_get!(global_get_f64, f64);
+global_get!(global_get_v128, u128);
 global_get!(global_get_func_ref, UnguardedFuncRef);
 global_get!(global_get_extern_ref, UnguardedExternRef);
 
@@ -1442,6 +1636,31 @@ global_set!(
     UnguardedExternRef
 );
 
+threaded_instr!(global_set_v128_s(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    // Read operands
+    let (val, ip): (u128, _) = read_stack(ip, sp);
+    let (mut global, ip): (UnguardedGlobal, _) = read_imm(ip);
+
+    // Perform operation
+    global
+        .as_mut()
+        .downcast_mut::<u128>()
+        .unwrap_unchecked()
+        .set(val);
+
+    // Execute next instruction
+    next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+});
+
 // Table instructions
 
 macro_rules! table_get {
@@ -3817,6 +4036,884 @@ un_op!(
     <u64 as Trunc<f64>>::trunc_sat
 );
 
+// Vector instructions
+
+macro_rules! simd_un_op {
+    ($simd_un_op:ident, $f:expr) => {
+        threaded_instr!($simd_un_op(
+            ip: Ip,
+            sp: Sp,
+            md: Md,
+            ms: Ms,
+            ix: Ix,
+            sx: Sx,
+            dx: Dx,
+            cx: Cx,
+        ) -> ControlFlowBits {
+            // Read operands
+            let (x, ip) = read_stack(ip, sp);
+
+            // Perform operation
+            let y = r#try!($f(x));
+
+            // Write result
+            let ip = write_stack(ip, sp, y);
+
+            // Execute next instruction
+            next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+        });
+    };
+}
+
+macro_rules! simd_bin_op {
+    ($simd_bin_op:ident, $f:expr) => {
+        threaded_instr!($simd_bin_op(
+            ip: Ip,
+            sp: Sp,
+            md: Md,
+            ms: Ms,
+            ix: Ix,
+            sx: Sx,
+            dx: Dx,
+            cx: Cx,
+        ) -> ControlFlowBits {
+            // Read operands
+            let (x1, ip) = read_stack(ip, sp);
+            let (x0, ip) = read_stack(ip, sp);
+
+            // Perform operation
+            let y = r#try!($f(x0, x1));
+
+            // Write result
+            let ip = write_stack(ip, sp, y);
+
+            // Execute next instruction
+            next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+        });
+    };
+}
+
+macro_rules! simd_extract_lane {
+    ($simd_extract_lane:ident, $f:expr) => {
+        threaded_instr!($simd_extract_lane(
+            ip: Ip,
+            sp: Sp,
+            md: Md,
+            ms: Ms,
+            ix: Ix,
+            sx: Sx,
+            dx: Dx,
+            cx: Cx,
+        ) -> ControlFlowBits {
+            // Read operands
+            let (x, ip) = read_stack(ip, sp);
+            let (lane_idx, ip): (u8, _) = read_imm(ip);
+
+            // Perform operation
+            let y = r#try!($f(x, lane_idx));
+
+            // Write result
+            let ip = write_stack(ip, sp, y);
+
+            // Execute next instruction
+            next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+        });
+    };
+}
+
+macro_rules! simd_replace_lane {
+    ($simd_replace_lane:ident, $f:expr) => {
+        threaded_instr!($simd_replace_lane(
+            ip: Ip,
+            sp: Sp,
+            md: Md,
+            ms: Ms,
+            ix: Ix,
+            sx: Sx,
+            dx: Dx,
+            cx: Cx,
+        ) -> ControlFlowBits {
+            // Read operands
+            let (x1, ip) = read_stack(ip, sp);
+            let (x0, ip) = read_stack(ip, sp);
+            let (lane_idx, ip): (u8, _) = read_imm(ip);
+
+            // Perform operation
+            let y = r#try!($f(x0, lane_idx, x1));
+
+            // Write result
+            let ip = write_stack(ip, sp, y);
+
+            // Execute next instruction
+            next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+        });
+    };
+}
+
+macro_rules! simd_load {
+    ($simd_load:ident, $T:ty, $f:expr) => {
+        threaded_instr!($simd_load(
+            ip: Ip,
+            sp: Sp,
+            md: Md,
+            ms: Ms,
+            ix: Ix,
+            sx: Sx,
+            dx: Dx,
+            cx: Cx,
+        ) -> ControlFlowBits {
+            // Read operands
+            let (dyn_offset, ip): (u32, _) = read_stack(ip, sp);
+            let (static_offset, ip): (u32, _) = read_imm(ip);
+
+            // Perform operation
+            let offset = dyn_offset as u64 + static_offset as u64;
+            if offset + mem::size_of::<$T>() as u64 > ms as u64 {
+                return ControlFlow::Trap(Trap::MemAccessOutOfBounds).to_bits();
+            }
+            let mut bytes = [0u8; mem::size_of::<$T>()];
+            ptr::copy_nonoverlapping(md.add(offset as usize), bytes.as_mut_ptr(), bytes.len());
+            let y: u128 = r#try!($f(<$T>::from_le_bytes(bytes)));
+
+            // Write result
+            let ip = write_stack(ip, sp, y);
+
+            // Execute next instruction
+            next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+        });
+    };
+}
+
+macro_rules! simd_load_lane {
+    ($simd_load_lane:ident, $T:ty) => {
+        threaded_instr!($simd_load_lane(
+            ip: Ip,
+            sp: Sp,
+            md: Md,
+            ms: Ms,
+            ix: Ix,
+            sx: Sx,
+            dx: Dx,
+            cx: Cx,
+        ) -> ControlFlowBits {
+            // Read operands
+            let (x, ip): (u128, _) = read_stack(ip, sp);
+            let (dyn_offset, ip): (u32, _) = read_stack(ip, sp);
+            let (static_offset, ip): (u32, _) = read_imm(ip);
+            let (lane_idx, ip): (u8, _) = read_imm(ip);
+
+            // Perform operation
+            let offset = dyn_offset as u64 + static_offset as u64;
+            if offset + mem::size_of::<$T>() as u64 > ms as u64 {
+                return ControlFlow::Trap(Trap::MemAccessOutOfBounds).to_bits();
+            }
+            let mut bytes = [0u8; mem::size_of::<$T>()];
+            ptr::copy_nonoverlapping(md.add(offset as usize), bytes.as_mut_ptr(), bytes.len());
+            let y = simd::replace_lane(x, lane_idx, <$T>::from_le_bytes(bytes));
+
+            // Write result
+            let ip = write_stack(ip, sp, y);
+
+            // Execute next instruction
+            next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+        });
+    };
+}
+
+macro_rules! simd_store_lane {
+    ($simd_store_lane:ident, $T:ty) => {
+        threaded_instr!($simd_store_lane(
+            ip: Ip,
+            sp: Sp,
+            md: Md,
+            ms: Ms,
+            ix: Ix,
+            sx: Sx,
+            dx: Dx,
+            cx: Cx,
+        ) -> ControlFlowBits {
+            // Read operands
+            let (x, ip): (u128, _) = read_stack(ip, sp);
+            let (dyn_offset, ip): (u32, _) = read_stack(ip, sp);
+            let (static_offset, ip): (u32, _) = read_imm(ip);
+            let (lane_idx, ip): (u8, _) = read_imm(ip);
+
+            // Perform operation
+            let offset = dyn_offset as u64 + static_offset as u64;
+            if offset + mem::size_of::<$T>() as u64 > ms as u64 {
+                return ControlFlow::Trap(Trap::MemAccessOutOfBounds).to_bits();
+            }
+            let bytes = simd::extract_lane::<$T>(x, lane_idx).to_le_bytes();
+            ptr::copy_nonoverlapping(bytes.as_ptr(), md.add(offset as usize), bytes.len());
+
+            // Execute next instruction
+            next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+        });
+    };
+}
+
+threaded_instr!(v128_store(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    // Read operands
+    let (x, ip): (u128, _) = read_stack(ip, sp);
+    let (dyn_offset, ip): (u32, _) = read_stack(ip, sp);
+    let (static_offset, ip): (u32, _) = read_imm(ip);
+
+    // Perform operation
+    let offset = dyn_offset as u64 + static_offset as u64;
+    if offset + mem::size_of::<u128>() as u64 > ms as u64 {
+        return ControlFlow::Trap(Trap::MemAccessOutOfBounds).to_bits();
+    }
+    let bytes = x.to_le_bytes();
+    ptr::copy_nonoverlapping(bytes.as_ptr(), md.add(offset as usize), bytes.len());
+
+    // Execute next instruction
+    next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+});
+
+threaded_instr!(i8x16_shuffle(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    // Read operands
+    let (x1, ip) = read_stack(ip, sp);
+    let (x0, ip) = read_stack(ip, sp);
+    let (lane_idxs, ip) = read_imm_v128(ip);
+
+    // Perform operation
+    let y = simd::shuffle(x0, x1, lane_idxs.to_le_bytes());
+
+    // Write result
+    let ip = write_stack(ip, sp, y);
+
+    // Execute next instruction
+    next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+});
+
+threaded_instr!(v128_bitselect(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    // Read operands
+    let (x2, ip) = read_stack(ip, sp);
+    let (x1, ip) = read_stack(ip, sp);
+    let (x0, ip) = read_stack(ip, sp);
+
+    // Perform operation
+    let y = simd::bitselect(x0, x1, x2);
+
+    // Write result
+    let ip = write_stack(ip, sp, y);
+
+    // Execute next instruction
+    next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+});
+
+simd_load!(v128_load, u128, |x: u128| Ok(x));
+simd_load!(v128_load8x8_s, u64, |x: u64| simd::extend_low(
+    x as u128,
+    <i16 as Extend<i8>>::extend
+));
+simd_load!(v128_load8x8_u, u64, |x: u64| simd::extend_low(
+    x as u128,
+    <u16 as Extend<u8>>::extend
+));
+simd_load!(v128_load16x4_s, u64, |x: u64| simd::extend_low(
+    x as u128,
+    <i32 as Extend<i16>>::extend
+));
+simd_load!(v128_load16x4_u, u64, |x: u64| simd::extend_low(
+    x as u128,
+    <u32 as Extend<u16>>::extend
+));
+simd_load!(v128_load32x2_s, u64, |x: u64| simd::extend_low(
+    x as u128,
+    <i64 as Extend<i32>>::extend
+));
+simd_load!(v128_load32x2_u, u64, |x: u64| simd::extend_low(
+    x as u128,
+    <u64 as Extend<u32>>::extend
+));
+simd_load!(v128_load8_splat, u8, |x: u8| Ok(simd::splat(x)));
+simd_load!(v128_load16_splat, u16, |x: u16| Ok(simd::splat(x)));
+simd_load!(v128_load32_splat, u32, |x: u32| Ok(simd::splat(x)));
+simd_load!(v128_load64_splat, u64, |x: u64| Ok(simd::splat(x)));
+simd_bin_op!(i8x16_swizzle, |x0: u128, x1: u128| Ok(simd::swizzle(
+    x0, x1
+)));
+simd_un_op!(i8x16_splat, |x: i32| Ok(simd::splat(x as i8)));
+simd_un_op!(i16x8_splat, |x: i32| Ok(simd::splat(x as i16)));
+simd_un_op!(i32x4_splat, |x: i32| Ok(simd::splat(x)));
+simd_un_op!(i64x2_splat, |x: i64| Ok(simd::splat(x)));
+simd_un_op!(f32x4_splat, |x: f32| Ok(simd::splat(x)));
+simd_un_op!(f64x2_splat, |x: f64| Ok(simd::splat(x)));
+simd_extract_lane!(i8x16_extract_lane_s, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<i8>(x, lane_idx) as i32
+));
+simd_extract_lane!(i8x16_extract_lane_u, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<u8>(x, lane_idx) as i32
+));
+simd_replace_lane!(i8x16_replace_lane, |x0: u128, lane_idx, x1: i32| Ok(
+    simd::replace_lane(x0, lane_idx, x1 as i8)
+));
+simd_extract_lane!(i16x8_extract_lane_s, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<i16>(x, lane_idx) as i32
+));
+simd_extract_lane!(i16x8_extract_lane_u, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<u16>(x, lane_idx) as i32
+));
+simd_replace_lane!(i16x8_replace_lane, |x0: u128, lane_idx, x1: i32| Ok(
+    simd::replace_lane(x0, lane_idx, x1 as i16)
+));
+simd_extract_lane!(i32x4_extract_lane, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<i32>(x, lane_idx)
+));
+simd_replace_lane!(i32x4_replace_lane, |x0: u128, lane_idx, x1: i32| Ok(
+    simd::replace_lane(x0, lane_idx, x1)
+));
+simd_extract_lane!(i64x2_extract_lane, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<i64>(x, lane_idx)
+));
+simd_replace_lane!(i64x2_replace_lane, |x0: u128, lane_idx, x1: i64| Ok(
+    simd::replace_lane(x0, lane_idx, x1)
+));
+simd_extract_lane!(f32x4_extract_lane, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<f32>(x, lane_idx)
+));
+simd_replace_lane!(f32x4_replace_lane, |x0: u128, lane_idx, x1: f32| Ok(
+    simd::replace_lane(x0, lane_idx, x1)
+));
+simd_extract_lane!(f64x2_extract_lane, |x: u128, lane_idx| Ok(
+    simd::extract_lane::<f64>(x, lane_idx)
+));
+simd_replace_lane!(f64x2_replace_lane, |x0: u128, lane_idx, x1: f64| Ok(
+    simd::replace_lane(x0, lane_idx, x1)
+));
+simd_bin_op!(i8x16_eq, |x0, x1| simd::cmp(x0, x1, <i8 as RelOps>::eq));
+simd_bin_op!(i8x16_ne, |x0, x1| simd::cmp(x0, x1, <i8 as RelOps>::ne));
+simd_bin_op!(i8x16_lt_s, |x0, x1| simd::cmp(x0, x1, <i8 as RelOps>::lt));
+simd_bin_op!(i8x16_lt_u, |x0, x1| simd::cmp(x0, x1, <u8 as RelOps>::lt));
+simd_bin_op!(i8x16_gt_s, |x0, x1| simd::cmp(x0, x1, <i8 as RelOps>::gt));
+simd_bin_op!(i8x16_gt_u, |x0, x1| simd::cmp(x0, x1, <u8 as RelOps>::gt));
+simd_bin_op!(i8x16_le_s, |x0, x1| simd::cmp(x0, x1, <i8 as RelOps>::le));
+simd_bin_op!(i8x16_le_u, |x0, x1| simd::cmp(x0, x1, <u8 as RelOps>::le));
+simd_bin_op!(i8x16_ge_s, |x0, x1| simd::cmp(x0, x1, <i8 as RelOps>::ge));
+simd_bin_op!(i8x16_ge_u, |x0, x1| simd::cmp(x0, x1, <u8 as RelOps>::ge));
+simd_bin_op!(i16x8_eq, |x0, x1| simd::cmp(x0, x1, <i16 as RelOps>::eq));
+simd_bin_op!(i16x8_ne, |x0, x1| simd::cmp(x0, x1, <i16 as RelOps>::ne));
+simd_bin_op!(i16x8_lt_s, |x0, x1| simd::cmp(x0, x1, <i16 as RelOps>::lt));
+simd_bin_op!(i16x8_lt_u, |x0, x1| simd::cmp(x0, x1, <u16 as RelOps>::lt));
+simd_bin_op!(i16x8_gt_s, |x0, x1| simd::cmp(x0, x1, <i16 as RelOps>::gt));
+simd_bin_op!(i16x8_gt_u, |x0, x1| simd::cmp(x0, x1, <u16 as RelOps>::gt));
+simd_bin_op!(i16x8_le_s, |x0, x1| simd::cmp(x0, x1, <i16 as RelOps>::le));
+simd_bin_op!(i16x8_le_u, |x0, x1| simd::cmp(x0, x1, <u16 as RelOps>::le));
+simd_bin_op!(i16x8_ge_s, |x0, x1| simd::cmp(x0, x1, <i16 as RelOps>::ge));
+simd_bin_op!(i16x8_ge_u, |x0, x1| simd::cmp(x0, x1, <u16 as RelOps>::ge));
+simd_bin_op!(i32x4_eq, |x0, x1| simd::cmp(x0, x1, <i32 as RelOps>::eq));
+simd_bin_op!(i32x4_ne, |x0, x1| simd::cmp(x0, x1, <i32 as RelOps>::ne));
+simd_bin_op!(i32x4_lt_s, |x0, x1| simd::cmp(x0, x1, <i32 as RelOps>::lt));
+simd_bin_op!(i32x4_lt_u, |x0, x1| simd::cmp(x0, x1, <u32 as RelOps>::lt));
+simd_bin_op!(i32x4_gt_s, |x0, x1| simd::cmp(x0, x1, <i32 as RelOps>::gt));
+simd_bin_op!(i32x4_gt_u, |x0, x1| simd::cmp(x0, x1, <u32 as RelOps>::gt));
+simd_bin_op!(i32x4_le_s, |x0, x1| simd::cmp(x0, x1, <i32 as RelOps>::le));
+simd_bin_op!(i32x4_le_u, |x0, x1| simd::cmp(x0, x1, <u32 as RelOps>::le));
+simd_bin_op!(i32x4_ge_s, |x0, x1| simd::cmp(x0, x1, <i32 as RelOps>::ge));
+simd_bin_op!(i32x4_ge_u, |x0, x1| simd::cmp(x0, x1, <u32 as RelOps>::ge));
+simd_bin_op!(f32x4_eq, |x0, x1| simd::cmp(x0, x1, <f32 as RelOps>::eq));
+simd_bin_op!(f32x4_ne, |x0, x1| simd::cmp(x0, x1, <f32 as RelOps>::ne));
+simd_bin_op!(f32x4_lt, |x0, x1| simd::cmp(x0, x1, <f32 as RelOps>::lt));
+simd_bin_op!(f32x4_gt, |x0, x1| simd::cmp(x0, x1, <f32 as RelOps>::gt));
+simd_bin_op!(f32x4_le, |x0, x1| simd::cmp(x0, x1, <f32 as RelOps>::le));
+simd_bin_op!(f32x4_ge, |x0, x1| simd::cmp(x0, x1, <f32 as RelOps>::ge));
+simd_bin_op!(f64x2_eq, |x0, x1| simd::cmp(x0, x1, <f64 as RelOps>::eq));
+simd_bin_op!(f64x2_ne, |x0, x1| simd::cmp(x0, x1, <f64 as RelOps>::ne));
+simd_bin_op!(f64x2_lt, |x0, x1| simd::cmp(x0, x1, <f64 as RelOps>::lt));
+simd_bin_op!(f64x2_gt, |x0, x1| simd::cmp(x0, x1, <f64 as RelOps>::gt));
+simd_bin_op!(f64x2_le, |x0, x1| simd::cmp(x0, x1, <f64 as RelOps>::le));
+simd_bin_op!(f64x2_ge, |x0, x1| simd::cmp(x0, x1, <f64 as RelOps>::ge));
+simd_un_op!(v128_not, |x: u128| Ok(!x));
+simd_bin_op!(v128_and, |x0: u128, x1: u128| Ok(x0 & x1));
+simd_bin_op!(v128_andnot, |x0: u128, x1: u128| Ok(x0 & !x1));
+simd_bin_op!(v128_or, |x0: u128, x1: u128| Ok(x0 | x1));
+simd_bin_op!(v128_xor, |x0: u128, x1: u128| Ok(x0 ^ x1));
+simd_un_op!(v128_any_true, |x: u128| Ok((x != 0) as i32));
+simd_load_lane!(v128_load8_lane, u8);
+simd_load_lane!(v128_load16_lane, u16);
+simd_load_lane!(v128_load32_lane, u32);
+simd_load_lane!(v128_load64_lane, u64);
+simd_store_lane!(v128_store8_lane, u8);
+simd_store_lane!(v128_store16_lane, u16);
+simd_store_lane!(v128_store32_lane, u32);
+simd_store_lane!(v128_store64_lane, u64);
+simd_load!(v128_load32_zero, u32, |x: u32| Ok(x as u128));
+simd_load!(v128_load64_zero, u64, |x: u64| Ok(x as u128));
+simd_un_op!(f32x4_demote_f64x2_zero, |x| simd::narrow_zero(
+    x,
+    <f32 as Demote<f64>>::demote
+));
+simd_un_op!(f64x2_promote_low_f32x4, |x| simd::extend_low(
+    x,
+    <f64 as Promote<f32>>::promote
+));
+simd_un_op!(i8x16_abs, |x| simd::map(x, |a: i8| Ok(a.wrapping_abs())));
+simd_un_op!(i8x16_neg, |x| simd::map(x, |a: i8| Ok(a.wrapping_neg())));
+simd_un_op!(i8x16_popcnt, |x| simd::map(x, <u8 as IntOps>::popcnt));
+simd_un_op!(i8x16_all_true, |x| Ok(simd::all_true::<i8>(x)));
+simd_un_op!(i8x16_bitmask, |x| Ok(simd::bitmask::<i8>(x)));
+simd_bin_op!(i8x16_narrow_i16x8_s, |x0, x1| simd::narrow(
+    x0,
+    x1,
+    <i8 as Narrow<i16>>::narrow
+));
+simd_bin_op!(i8x16_narrow_i16x8_u, |x0, x1| simd::narrow(
+    x0,
+    x1,
+    <u8 as Narrow<i16>>::narrow
+));
+simd_un_op!(f32x4_ceil, |x| simd::map(x, <f32 as FloatOps>::ceil));
+simd_un_op!(f32x4_floor, |x| simd::map(x, <f32 as FloatOps>::floor));
+simd_un_op!(f32x4_trunc, |x| simd::map(x, <f32 as FloatOps>::trunc));
+simd_un_op!(f32x4_nearest, |x| simd::map(x, <f32 as FloatOps>::nearest));
+simd_bin_op!(i8x16_shl, |x0, x1: u32| simd::map(x0, |a: i8| Ok(
+    a.wrapping_shl(x1)
+)));
+simd_bin_op!(i8x16_shr_s, |x0, x1: u32| simd::map(x0, |a: i8| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i8x16_shr_u, |x0, x1: u32| simd::map(x0, |a: u8| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i8x16_add, |x0, x1| simd::zip(x0, x1, <i8 as IntOps>::add));
+simd_bin_op!(i8x16_add_sat_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i8, b: i8| Ok(a.saturating_add(b))
+));
+simd_bin_op!(i8x16_add_sat_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u8, b: u8| Ok(a.saturating_add(b))
+));
+simd_bin_op!(i8x16_sub, |x0, x1| simd::zip(x0, x1, <i8 as IntOps>::sub));
+simd_bin_op!(i8x16_sub_sat_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i8, b: i8| Ok(a.saturating_sub(b))
+));
+simd_bin_op!(i8x16_sub_sat_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u8, b: u8| Ok(a.saturating_sub(b))
+));
+simd_un_op!(f64x2_ceil, |x| simd::map(x, <f64 as FloatOps>::ceil));
+simd_un_op!(f64x2_floor, |x| simd::map(x, <f64 as FloatOps>::floor));
+simd_bin_op!(i8x16_min_s, |x0, x1| simd::zip(x0, x1, |a: i8, b: i8| Ok(
+    a.min(b)
+)));
+simd_bin_op!(i8x16_min_u, |x0, x1| simd::zip(x0, x1, |a: u8, b: u8| Ok(
+    a.min(b)
+)));
+simd_bin_op!(i8x16_max_s, |x0, x1| simd::zip(x0, x1, |a: i8, b: i8| Ok(
+    a.max(b)
+)));
+simd_bin_op!(i8x16_max_u, |x0, x1| simd::zip(x0, x1, |a: u8, b: u8| Ok(
+    a.max(b)
+)));
+simd_un_op!(f64x2_trunc, |x| simd::map(x, <f64 as FloatOps>::trunc));
+simd_bin_op!(i8x16_avgr_u, |x0, x1| simd::zip(x0, x1, |a: u8, b: u8| Ok(
+    (a as u16 + b as u16).div_ceil(2) as u8
+)));
+simd_un_op!(i16x8_extadd_pairwise_i8x16_s, |x| simd::extadd_pairwise(
+    x,
+    |a: i8, b: i8| Ok(a as i16 + b as i16)
+));
+simd_un_op!(i16x8_extadd_pairwise_i8x16_u, |x| simd::extadd_pairwise(
+    x,
+    |a: u8, b: u8| Ok(a as u16 + b as u16)
+));
+simd_un_op!(i32x4_extadd_pairwise_i16x8_s, |x| simd::extadd_pairwise(
+    x,
+    |a: i16, b: i16| Ok(a as i32 + b as i32)
+));
+simd_un_op!(i32x4_extadd_pairwise_i16x8_u, |x| simd::extadd_pairwise(
+    x,
+    |a: u16, b: u16| Ok(a as u32 + b as u32)
+));
+simd_un_op!(i16x8_abs, |x| simd::map(x, |a: i16| Ok(a.wrapping_abs())));
+simd_un_op!(i16x8_neg, |x| simd::map(x, |a: i16| Ok(a.wrapping_neg())));
+simd_bin_op!(i16x8_q15mulr_sat_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i16, b: i16| <i16 as Narrow<i32>>::narrow((a as i32 * b as i32 + 0x4000) >> 15)
+));
+simd_un_op!(i16x8_all_true, |x| Ok(simd::all_true::<i16>(x)));
+simd_un_op!(i16x8_bitmask, |x| Ok(simd::bitmask::<i16>(x)));
+simd_bin_op!(i16x8_narrow_i32x4_s, |x0, x1| simd::narrow(
+    x0,
+    x1,
+    <i16 as Narrow<i32>>::narrow
+));
+simd_bin_op!(i16x8_narrow_i32x4_u, |x0, x1| simd::narrow(
+    x0,
+    x1,
+    <u16 as Narrow<i32>>::narrow
+));
+simd_un_op!(i16x8_extend_low_i8x16_s, |x| simd::extend_low(
+    x,
+    <i16 as Extend<i8>>::extend
+));
+simd_un_op!(i16x8_extend_high_i8x16_s, |x| simd::extend_high(
+    x,
+    <i16 as Extend<i8>>::extend
+));
+simd_un_op!(i16x8_extend_low_i8x16_u, |x| simd::extend_low(
+    x,
+    <u16 as Extend<u8>>::extend
+));
+simd_un_op!(i16x8_extend_high_i8x16_u, |x| simd::extend_high(
+    x,
+    <u16 as Extend<u8>>::extend
+));
+simd_bin_op!(i16x8_shl, |x0, x1: u32| simd::map(x0, |a: i16| Ok(
+    a.wrapping_shl(x1)
+)));
+simd_bin_op!(i16x8_shr_s, |x0, x1: u32| simd::map(x0, |a: i16| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i16x8_shr_u, |x0, x1: u32| simd::map(x0, |a: u16| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i16x8_add, |x0, x1| simd::zip(x0, x1, <i16 as IntOps>::add));
+simd_bin_op!(i16x8_add_sat_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i16, b: i16| Ok(a.saturating_add(b))
+));
+simd_bin_op!(i16x8_add_sat_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u16, b: u16| Ok(a.saturating_add(b))
+));
+simd_bin_op!(i16x8_sub, |x0, x1| simd::zip(x0, x1, <i16 as IntOps>::sub));
+simd_bin_op!(i16x8_sub_sat_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i16, b: i16| Ok(a.saturating_sub(b))
+));
+simd_bin_op!(i16x8_sub_sat_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u16, b: u16| Ok(a.saturating_sub(b))
+));
+simd_un_op!(f64x2_nearest, |x| simd::map(x, <f64 as FloatOps>::nearest));
+simd_bin_op!(i16x8_mul, |x0, x1| simd::zip(x0, x1, <i16 as IntOps>::mul));
+simd_bin_op!(i16x8_min_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i16, b: i16| Ok(a.min(b))
+));
+simd_bin_op!(i16x8_min_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u16, b: u16| Ok(a.min(b))
+));
+simd_bin_op!(i16x8_max_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i16, b: i16| Ok(a.max(b))
+));
+simd_bin_op!(i16x8_max_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u16, b: u16| Ok(a.max(b))
+));
+simd_bin_op!(i16x8_avgr_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u16, b: u16| Ok((a as u32 + b as u32).div_ceil(2) as u16)
+));
+simd_bin_op!(i16x8_extmul_low_i8x16_s, |x0, x1| simd::extmul_low(
+    x0,
+    x1,
+    |a: i8, b: i8| Ok(a as i16 * b as i16)
+));
+simd_bin_op!(i16x8_extmul_high_i8x16_s, |x0, x1| simd::extmul_high(
+    x0,
+    x1,
+    |a: i8, b: i8| Ok(a as i16 * b as i16)
+));
+simd_bin_op!(i16x8_extmul_low_i8x16_u, |x0, x1| simd::extmul_low(
+    x0,
+    x1,
+    |a: u8, b: u8| Ok(a as u16 * b as u16)
+));
+simd_bin_op!(i16x8_extmul_high_i8x16_u, |x0, x1| simd::extmul_high(
+    x0,
+    x1,
+    |a: u8, b: u8| Ok(a as u16 * b as u16)
+));
+simd_un_op!(i32x4_abs, |x| simd::map(x, |a: i32| Ok(a.wrapping_abs())));
+simd_un_op!(i32x4_neg, |x| simd::map(x, |a: i32| Ok(a.wrapping_neg())));
+simd_un_op!(i32x4_all_true, |x| Ok(simd::all_true::<i32>(x)));
+simd_un_op!(i32x4_bitmask, |x| Ok(simd::bitmask::<i32>(x)));
+simd_un_op!(i32x4_extend_low_i16x8_s, |x| simd::extend_low(
+    x,
+    <i32 as Extend<i16>>::extend
+));
+simd_un_op!(i32x4_extend_high_i16x8_s, |x| simd::extend_high(
+    x,
+    <i32 as Extend<i16>>::extend
+));
+simd_un_op!(i32x4_extend_low_i16x8_u, |x| simd::extend_low(
+    x,
+    <u32 as Extend<u16>>::extend
+));
+simd_un_op!(i32x4_extend_high_i16x8_u, |x| simd::extend_high(
+    x,
+    <u32 as Extend<u16>>::extend
+));
+simd_bin_op!(i32x4_shl, |x0, x1: u32| simd::map(x0, |a: i32| Ok(
+    a.wrapping_shl(x1)
+)));
+simd_bin_op!(i32x4_shr_s, |x0, x1: u32| simd::map(x0, |a: i32| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i32x4_shr_u, |x0, x1: u32| simd::map(x0, |a: u32| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i32x4_add, |x0, x1| simd::zip(x0, x1, <i32 as IntOps>::add));
+simd_bin_op!(i32x4_sub, |x0, x1| simd::zip(x0, x1, <i32 as IntOps>::sub));
+simd_bin_op!(i32x4_mul, |x0, x1| simd::zip(x0, x1, <i32 as IntOps>::mul));
+simd_bin_op!(i32x4_min_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i32, b: i32| Ok(a.min(b))
+));
+simd_bin_op!(i32x4_min_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u32, b: u32| Ok(a.min(b))
+));
+simd_bin_op!(i32x4_max_s, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: i32, b: i32| Ok(a.max(b))
+));
+simd_bin_op!(i32x4_max_u, |x0, x1| simd::zip(
+    x0,
+    x1,
+    |a: u32, b: u32| Ok(a.max(b))
+));
+simd_bin_op!(i32x4_dot_i16x8_s, simd::dot);
+simd_bin_op!(i32x4_extmul_low_i16x8_s, |x0, x1| simd::extmul_low(
+    x0,
+    x1,
+    |a: i16, b: i16| Ok(a as i32 * b as i32)
+));
+simd_bin_op!(i32x4_extmul_high_i16x8_s, |x0, x1| simd::extmul_high(
+    x0,
+    x1,
+    |a: i16, b: i16| Ok(a as i32 * b as i32)
+));
+simd_bin_op!(i32x4_extmul_low_i16x8_u, |x0, x1| simd::extmul_low(
+    x0,
+    x1,
+    |a: u16, b: u16| Ok(a as u32 * b as u32)
+));
+simd_bin_op!(i32x4_extmul_high_i16x8_u, |x0, x1| simd::extmul_high(
+    x0,
+    x1,
+    |a: u16, b: u16| Ok(a as u32 * b as u32)
+));
+simd_un_op!(i64x2_abs, |x| simd::map(x, |a: i64| Ok(a.wrapping_abs())));
+simd_un_op!(i64x2_neg, |x| simd::map(x, |a: i64| Ok(a.wrapping_neg())));
+simd_un_op!(i64x2_all_true, |x| Ok(simd::all_true::<i64>(x)));
+simd_un_op!(i64x2_bitmask, |x| Ok(simd::bitmask::<i64>(x)));
+simd_un_op!(i64x2_extend_low_i32x4_s, |x| simd::extend_low(
+    x,
+    <i64 as Extend<i32>>::extend
+));
+simd_un_op!(i64x2_extend_high_i32x4_s, |x| simd::extend_high(
+    x,
+    <i64 as Extend<i32>>::extend
+));
+simd_un_op!(i64x2_extend_low_i32x4_u, |x| simd::extend_low(
+    x,
+    <u64 as Extend<u32>>::extend
+));
+simd_un_op!(i64x2_extend_high_i32x4_u, |x| simd::extend_high(
+    x,
+    <u64 as Extend<u32>>::extend
+));
+simd_bin_op!(i64x2_shl, |x0, x1: u32| simd::map(x0, |a: i64| Ok(
+    a.wrapping_shl(x1)
+)));
+simd_bin_op!(i64x2_shr_s, |x0, x1: u32| simd::map(x0, |a: i64| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i64x2_shr_u, |x0, x1: u32| simd::map(x0, |a: u64| Ok(
+    a.wrapping_shr(x1)
+)));
+simd_bin_op!(i64x2_add, |x0, x1| simd::zip(x0, x1, <i64 as IntOps>::add));
+simd_bin_op!(i64x2_sub, |x0, x1| simd::zip(x0, x1, <i64 as IntOps>::sub));
+simd_bin_op!(i64x2_mul, |x0, x1| simd::zip(x0, x1, <i64 as IntOps>::mul));
+simd_bin_op!(i64x2_eq, |x0, x1| simd::cmp(x0, x1, <i64 as RelOps>::eq));
+simd_bin_op!(i64x2_ne, |x0, x1| simd::cmp(x0, x1, <i64 as RelOps>::ne));
+simd_bin_op!(i64x2_lt_s, |x0, x1| simd::cmp(x0, x1, <i64 as RelOps>::lt));
+simd_bin_op!(i64x2_gt_s, |x0, x1| simd::cmp(x0, x1, <i64 as RelOps>::gt));
+simd_bin_op!(i64x2_le_s, |x0, x1| simd::cmp(x0, x1, <i64 as RelOps>::le));
+simd_bin_op!(i64x2_ge_s, |x0, x1| simd::cmp(x0, x1, <i64 as RelOps>::ge));
+simd_bin_op!(i64x2_extmul_low_i32x4_s, |x0, x1| simd::extmul_low(
+    x0,
+    x1,
+    |a: i32, b: i32| Ok(a as i64 * b as i64)
+));
+simd_bin_op!(i64x2_extmul_high_i32x4_s, |x0, x1| simd::extmul_high(
+    x0,
+    x1,
+    |a: i32, b: i32| Ok(a as i64 * b as i64)
+));
+simd_bin_op!(i64x2_extmul_low_i32x4_u, |x0, x1| simd::extmul_low(
+    x0,
+    x1,
+    |a: u32, b: u32| Ok(a as u64 * b as u64)
+));
+simd_bin_op!(i64x2_extmul_high_i32x4_u, |x0, x1| simd::extmul_high(
+    x0,
+    x1,
+    |a: u32, b: u32| Ok(a as u64 * b as u64)
+));
+simd_un_op!(f32x4_abs, |x| simd::map(x, <f32 as FloatOps>::abs));
+simd_un_op!(f32x4_neg, |x| simd::map(x, <f32 as FloatOps>::neg));
+simd_un_op!(f32x4_sqrt, |x| simd::map(x, <f32 as FloatOps>::sqrt));
+simd_bin_op!(f32x4_add, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f32 as FloatOps>::add
+));
+simd_bin_op!(f32x4_sub, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f32 as FloatOps>::sub
+));
+simd_bin_op!(f32x4_mul, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f32 as FloatOps>::mul
+));
+simd_bin_op!(f32x4_div, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f32 as FloatOps>::div
+));
+simd_bin_op!(f32x4_min, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f32 as FloatOps>::min
+));
+simd_bin_op!(f32x4_max, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f32 as FloatOps>::max
+));
+simd_bin_op!(f32x4_pmin, |x0, x1| simd::zip(x0, x1, |a: f32, b: f32| Ok(
+    if b < a { b } else { a }
+)));
+simd_bin_op!(f32x4_pmax, |x0, x1| simd::zip(x0, x1, |a: f32, b: f32| Ok(
+    if a < b { b } else { a }
+)));
+simd_un_op!(f64x2_abs, |x| simd::map(x, <f64 as FloatOps>::abs));
+simd_un_op!(f64x2_neg, |x| simd::map(x, <f64 as FloatOps>::neg));
+simd_un_op!(f64x2_sqrt, |x| simd::map(x, <f64 as FloatOps>::sqrt));
+simd_bin_op!(f64x2_add, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f64 as FloatOps>::add
+));
+simd_bin_op!(f64x2_sub, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f64 as FloatOps>::sub
+));
+simd_bin_op!(f64x2_mul, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f64 as FloatOps>::mul
+));
+simd_bin_op!(f64x2_div, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f64 as FloatOps>::div
+));
+simd_bin_op!(f64x2_min, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f64 as FloatOps>::min
+));
+simd_bin_op!(f64x2_max, |x0, x1| simd::zip(
+    x0,
+    x1,
+    <f64 as FloatOps>::max
+));
+simd_bin_op!(f64x2_pmin, |x0, x1| simd::zip(x0, x1, |a: f64, b: f64| Ok(
+    if b < a { b } else { a }
+)));
+simd_bin_op!(f64x2_pmax, |x0, x1| simd::zip(x0, x1, |a: f64, b: f64| Ok(
+    if a < b { b } else { a }
+)));
+simd_un_op!(i32x4_trunc_sat_f32x4_s, |x| simd::map(
+    x,
+    <i32 as Trunc<f32>>::trunc_sat
+));
+simd_un_op!(i32x4_trunc_sat_f32x4_u, |x| simd::map(
+    x,
+    <u32 as Trunc<f32>>::trunc_sat
+));
+simd_un_op!(f32x4_convert_i32x4_s, |x| simd::map(
+    x,
+    <f32 as Convert<i32>>::convert
+));
+simd_un_op!(f32x4_convert_i32x4_u, |x| simd::map(
+    x,
+    <f32 as Convert<u32>>::convert
+));
+simd_un_op!(i32x4_trunc_sat_f64x2_s_zero, |x| simd::narrow_zero(
+    x,
+    <i32 as Trunc<f64>>::trunc_sat
+));
+simd_un_op!(i32x4_trunc_sat_f64x2_u_zero, |x| simd::narrow_zero(
+    x,
+    <u32 as Trunc<f64>>::trunc_sat
+));
+simd_un_op!(f64x2_convert_low_i32x4_s, |x| simd::extend_low(
+    x,
+    <f64 as Convert<i32>>::convert
+));
+simd_un_op!(f64x2_convert_low_i32x4_u, |x| simd::extend_low(
+    x,
+    <f64 as Convert<u32>>::convert
+));
+
 // Miscellaneous instructions
 
 macro_rules! copy_imm_to_stack {
@@ -3850,6 +4947,26 @@ copy_imm_to_stack!(copy_imm_to_stack_f64, f64);
 copy_imm_to_stack!(copy_imm_to_stack_func_ref, UnguardedFuncRef);
 copy_imm_to_stack!(copy_imm_to_stack_extern_ref, UnguardedExternRef);
 
+threaded_instr!(copy_imm_to_stack_v128(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    // Read immediate value
+    let (x, ip) = read_imm_v128(ip);
+
+    // Write value to stack
+    let ip = write_stack(ip, sp, x);
+
+    // Execute next instruction
+    next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+});
+
 macro_rules! copy_stack {
     ($copy_stack_t:ident, $T:ty) => {
         threaded_instr!($copy_stack_t(
@@ -3878,6 +4995,7 @@ copy_stack!(copy_stack_i32, i32);
 copy_stack!(copy_stack_i64, i64);
 copy_stack!(copy_stack_f32, f32);
 copy_stack!(copy_stack_f64, f64);
+copy_stack!(copy_stack_v128, u128);
 copy_stack!(copy_stack_func_ref, UnguardedFuncRef);
 copy_stack!(copy_stack_extern_ref, UnguardedExternRef);
 
@@ -3992,9 +5110,39 @@ threaded_instr!(enter(
     next_instr(ip, sp, md, ms, ix, sx, dx, cx)
 });
 
+threaded_instr!(checkpoint(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    if let Err(trap) = (*(*cx).store).consume_fuel() {
+        // Save the registers, so execution can be resumed from this instruction.
+        (*cx).ip = ip.offset(-1);
+        (*cx).sp = sp;
+        (*cx).md = md;
+        (*cx).ms = ms;
+        (*cx).ix = ix;
+        (*cx).sx = sx;
+        (*cx).dx = dx;
+        return ControlFlow::Trap(trap).to_bits();
+    }
+
+    // Skip the function, which is only needed when resuming.
+    let (_, ip): (UnguardedFunc, _) = read_imm(ip);
+
+    // Execute next instruction.
+    next_instr(ip, sp, md, ms, ix, sx, dx, cx)
+});
+
 // Helper functions
 
 /// Executes the next instruction.
+#[cfg(not(any(feature = "trampoline", debug_assertions)))]
 pub(crate) unsafe fn next_instr(
     ip: Ip,
     sp: Sp,
@@ -4009,6 +5157,31 @@ pub(crate) unsafe fn next_instr(
     (instr)(ip, sp, md, ms, ix, sx, dx, cx)
 }
 
+/// Returns to the interpreter loop, which executes the next instruction.
+///
+/// This is used instead of calling the next instruction directly when sibling calls can't be
+/// relied on, so that the native stack doesn't grow with each instruction that is executed.
+#[cfg(any(feature = "trampoline", debug_assertions))]
+pub(crate) unsafe fn next_instr(
+    ip: Ip,
+    sp: Sp,
+    md: Md,
+    ms: Ms,
+    ix: Ix,
+    sx: Sx,
+    dx: Dx,
+    cx: Cx,
+) -> ControlFlowBits {
+    (*cx).ip = ip;
+    (*cx).sp = sp;
+    (*cx).md = md;
+    (*cx).ms = ms;
+    (*cx).ix = ix;
+    (*cx).sx = sx;
+    (*cx).dx = dx;
+    ControlFlow::Continue.to_bits()
+}
+
 /// Reads an immediate value.
 unsafe fn read_imm<T>(ip: Ip) -> (T, Ip)
 where
@@ -4019,6 +5192,15 @@ where
     (val, ip)
 }
 
+/// Reads an immediate `v128` value.
+///
+/// A `v128` is wider than an instruction slot, so it is split over multiple slots.
+unsafe fn read_imm_v128(ip: Ip) -> (u128, Ip) {
+    let val = ip.cast::<u128>().read_unaligned();
+    let ip = ip.add(mem::size_of::<u128>() / mem::size_of::<InstrSlot>());
+    (val, ip)
+}
+
 /// Reads a value from the stack.
 unsafe fn read_stack<T>(ip: Ip, sp: Sp) -> (T, Ip)
 where
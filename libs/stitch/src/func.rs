@@ -3,11 +3,12 @@ use {
         code::{Code, UncompiledCode},
         decode::{Decode, DecodeError, Decoder},
         error::Error,
-        exec,
+        exec::{self, Suspension},
         instance::Instance,
         into_host_func::IntoHostFunc,
         stack::StackGuard,
         store::{Handle, InternedFuncType, Store, StoreId, UnguardedHandle},
+        trap::Trap,
         val::{Val, ValType},
     },
     std::{error, fmt, mem, sync::Arc},
@@ -42,19 +43,48 @@ impl Func {
     /// - If the actual result count does not match the expected result count.
     /// - If the argument types do not match the expected parameter types.
     pub fn call(self, store: &mut Store, args: &[Val], results: &mut [Val]) -> Result<(), Error> {
+        self.check_call(store, args, results)?;
+        exec::exec(store, self, args, results)
+    }
+
+    /// Calls this [`Func`] with the given arguments, like [`Func::call`], but suspends the call
+    /// instead of trapping if it runs out of fuel or is interrupted.
+    ///
+    /// If the call finishes, the results are written to the `results` slice. Otherwise, the
+    /// returned [`SuspendedCall`] can be used to resume it.
+    ///
+    /// Only calls that are not made from a host function can be suspended.
+    ///
+    /// # Errors
+    ///
+    /// - If the argument count does not match the expected parameter count.
+    /// - If the actual result count does not match the expected result count.
+    /// - If the argument types do not match the expected parameter types.
+    pub fn call_resumable(
+        self,
+        store: &mut Store,
+        args: &[Val],
+        results: &mut [Val],
+    ) -> Result<ResumableCall, Error> {
+        self.check_call(store, args, results)?;
+        let suspension = exec::exec_resumable(store, self, args, results)?;
+        Ok(ResumableCall::new(self, suspension))
+    }
+
+    fn check_call(self, store: &Store, args: &[Val], results: &[Val]) -> Result<(), FuncError> {
         let type_ = self.type_(store);
         if args.len() != type_.params().len() {
-            return Err(FuncError::ParamCountMismatch)?;
+            return Err(FuncError::ParamCountMismatch);
         }
         if results.len() != type_.results().len() {
-            return Err(FuncError::ResultCountMismatch)?;
+            return Err(FuncError::ResultCountMismatch);
         }
         for (arg, param_type) in args.iter().zip(type_.params().iter().copied()) {
             if arg.type_() != param_type {
-                return Err(FuncError::ParamTypeMismatch)?;
+                return Err(FuncError::ParamTypeMismatch);
             }
         }
-        exec::exec(store, self, args, results)
+        Ok(())
     }
 
     /// Creates a new Wasm function from its raw parts.
@@ -106,6 +136,82 @@ impl Func {
     }
 }
 
+/// The outcome of [`Func::call_resumable`].
+#[derive(Debug)]
+pub enum ResumableCall {
+    /// The call finished, and its results were written.
+    Finished,
+    /// The call ran out of fuel or was interrupted.
+    Suspended(SuspendedCall),
+}
+
+impl ResumableCall {
+    fn new(func: Func, suspension: Option<Suspension>) -> Self {
+        match suspension {
+            Some(suspension) => Self::Suspended(SuspendedCall {
+                func,
+                suspension: Some(suspension),
+            }),
+            None => Self::Finished,
+        }
+    }
+}
+
+/// A call that was suspended because it ran out of fuel or was interrupted.
+///
+/// The call frames of a suspended call stay on the stack for the current thread until it is
+/// resumed or dropped, so suspended calls have to be resumed or dropped on the thread that made
+/// them, in the reverse order in which they were suspended.
+#[derive(Debug)]
+pub struct SuspendedCall {
+    func: Func,
+    suspension: Option<Suspension>,
+}
+
+impl SuspendedCall {
+    /// Returns the [`Func`] that was called.
+    pub fn func(&self) -> Func {
+        self.func
+    }
+
+    /// Returns the [`Trap`] that suspended this call: either [`Trap::OutOfFuel`] or
+    /// [`Trap::Interrupted`].
+    pub fn trap(&self) -> Trap {
+        self.suspension.as_ref().unwrap().trap
+    }
+
+    /// Resumes this call, after adding fuel or clearing the cause of the interruption.
+    ///
+    /// If the call finishes, the results are written to the `results` slice.
+    ///
+    /// # Errors
+    ///
+    /// If the actual result count does not match the expected result count.
+    ///
+    /// # Panics
+    ///
+    /// If another call that was suspended after this one is still suspended.
+    pub fn resume(
+        mut self,
+        store: &mut Store,
+        results: &mut [Val],
+    ) -> Result<ResumableCall, Error> {
+        if results.len() != self.func.type_(store).results().len() {
+            return Err(FuncError::ResultCountMismatch)?;
+        }
+        let suspension = exec::resume(store, self.suspension.take().unwrap(), results)?;
+        Ok(ResumableCall::new(self.func, suspension))
+    }
+}
+
+impl Drop for SuspendedCall {
+    fn drop(&mut self) {
+        if let Some(suspension) = self.suspension.take() {
+            exec::discard(&suspension);
+        }
+    }
+}
+
 /// An unguarded version of [`Func`].
 pub(crate) type UnguardedFunc = UnguardedHandle<FuncEntity>;
 
@@ -145,12 +251,13 @@ impl FuncType {
     /// [`FuncType`] [] -> [`ValType`?].
     pub(crate) fn from_val_type(type_: Option<ValType>) -> FuncType {
         thread_local! {
-            static TYPES: [FuncType; 7] = [
+            static TYPES: [FuncType; 8] = [
                 FuncType::new(vec![], vec![]),
                 FuncType::new(vec![], vec![ValType::I32]),
                 FuncType::new(vec![], vec![ValType::I64]),
                 FuncType::new(vec![], vec![ValType::F32]),
                 FuncType::new(vec![], vec![ValType::F64]),
+                FuncType::new(vec![], vec![ValType::V128]),
                 FuncType::new(vec![], vec![ValType::FuncRef]),
                 FuncType::new(vec![], vec![ValType::ExternRef]),
             ];
@@ -162,8 +269,9 @@ impl FuncType {
             Some(ValType::I64) => types[2].clone(),
             Some(ValType::F32) => types[3].clone(),
             Some(ValType::F64) => types[4].clone(),
-            Some(ValType::FuncRef) => types[5].clone(),
-            Some(ValType::ExternRef) => types[6].clone(),
+            Some(ValType::V128) => types[5].clone(),
+            Some(ValType::FuncRef) => types[6].clone(),
+            Some(ValType::ExternRef) => types[7].clone(),
         })
     }
 
@@ -1,20 +1,56 @@
 use {
-    makepad_stitch::{Engine, Linker, Module, Store, Val, ValType},
-    std::{env, fs},
+    makepad_stitch::{Engine, Linker, Module, Store, Val, ValType, Wasi, WasiConfig},
+    std::{env, fs, path::PathBuf, process},
 };
 
+// Usage: stitch [--dir <dir>[::<guest dir>]]... [--env <key>=<value>]... <wasm file> [args]...
+//
+// A module that exports `_start` is run as a WASI command, with the remaining arguments as its
+// command line. Otherwise, the first argument names the exported function to call, and the
+// rest are its arguments.
 fn main() {
     let mut args = env::args();
     args.next().unwrap();
-    let wasm_file = args.next().unwrap();
-    let func_name = args.next().unwrap();
-    let args: Vec<_> = args.collect();
+    let mut config = WasiConfig::default();
+    let wasm_file = loop {
+        let arg = args.next().unwrap();
+        match arg.as_str() {
+            "--dir" => {
+                let dir = args.next().unwrap();
+                let (host_dir, guest_dir) = dir.split_once("::").unwrap_or((&dir, &dir));
+                config
+                    .preopened_dirs
+                    .push((guest_dir.to_string(), PathBuf::from(host_dir)));
+            }
+            "--env" => {
+                let env = args.next().unwrap();
+                let (key, val) = env.split_once('=').unwrap();
+                config.envs.push((key.to_string(), val.to_string()));
+            }
+            _ => break arg,
+        }
+    };
     let engine = Engine::new();
     let mut store = Store::new(engine);
-    let bytes = fs::read(wasm_file).unwrap();
+    let bytes = fs::read(&wasm_file).unwrap();
     let module = Module::new(store.engine(), &bytes).unwrap();
-    let linker = Linker::new();
+    let is_command = module.export("_start").is_some();
+    if is_command {
+        config.args = Some(wasm_file).into_iter().chain(args.by_ref()).collect();
+    }
+    let wasi = Wasi::new(config);
+    let mut linker = Linker::new();
+    wasi.add_to_linker(&mut store, &mut linker);
     let instance = linker.instantiate(&mut store, &module).unwrap();
+    if is_command {
+        let code = wasi.run(&mut store, &instance).unwrap();
+        process::exit(code as i32);
+    }
+    if let Some(mem) = instance.exported_mem("memory") {
+        wasi.set_mem(mem);
+    }
+    let func_name = args.next().unwrap();
+    let args: Vec<_> = args.collect();
     let func = instance.exported_func(&func_name).unwrap();
     let args: Vec<_> = func
         .type_(&store)
@@ -41,6 +77,7 @@ fn parse_val(type_: ValType, string: &str) -> Val {
         ValType::I64 => string.parse::<i64>().unwrap().into(),
         ValType::F32 => string.parse::<f32>().unwrap().into(),
         ValType::F64 => string.parse::<f64>().unwrap().into(),
+        ValType::V128 => string.parse::<u128>().unwrap().into(),
         ValType::FuncRef => unimplemented!(),
         ValType::ExternRef => unimplemented!(),
     }
@@ -52,6 +89,7 @@ fn print_val(val: Val) {
         Val::I64(val) => println!("{}", val),
         Val::F32(val) => println!("{}", val),
         Val::F64(val) => println!("{}", val),
+        Val::V128(val) => println!("{}", val),
         Val::FuncRef(_) => unimplemented!(),
         Val::ExternRef(_) => unimplemented!(),
     }
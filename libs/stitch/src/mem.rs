@@ -207,12 +207,13 @@ impl MemEntity {
         self.bytes
             .resize((new_size as usize).checked_mul(PAGE_SIZE).unwrap(), 0);
         let new_data = self.bytes.as_mut_ptr();
+        let new_len = self.bytes.len() as u32;
         let mut ptr = stack.ptr();
         while ptr != stack.base_ptr() {
             ptr = *ptr.offset(-3).cast();
             if *ptr.offset(-2).cast::<*mut u8>() == old_data {
                 *ptr.offset(-2).cast() = new_data;
-                *ptr.offset(-1).cast() = new_size;
+                *ptr.offset(-1).cast() = new_len;
             }
         }
         Ok(old_size)
@@ -39,6 +39,10 @@ macro_rules! impl_rel_ops {
     };
 }
 
+impl_rel_ops!(i8);
+impl_rel_ops!(u8);
+impl_rel_ops!(i16);
+impl_rel_ops!(u16);
 impl_rel_ops!(i32);
 impl_rel_ops!(u32);
 impl_rel_ops!(i64);
@@ -144,6 +148,10 @@ macro_rules! impl_int_ops {
     };
 }
 
+impl_int_ops!(i8);
+impl_int_ops!(u8);
+impl_int_ops!(i16);
+impl_int_ops!(u16);
 impl_int_ops!(i32);
 impl_int_ops!(u32);
 impl_int_ops!(i64);
@@ -178,18 +186,32 @@ macro_rules! impl_float_ops {
             }
 
             fn ceil(self) -> Result<Self, Trap> {
+                // The rounding functions in the standard library may return a signalling NaN
+                // unchanged, but Wasm requires the result to be a quiet NaN.
+                if self.is_nan() {
+                    return Ok(self + self);
+                }
                 Ok(self.ceil())
             }
 
             fn floor(self) -> Result<Self, Trap> {
+                if self.is_nan() {
+                    return Ok(self + self);
+                }
                 Ok(self.floor())
             }
 
             fn trunc(self) -> Result<Self, Trap> {
+                if self.is_nan() {
+                    return Ok(self + self);
+                }
                 Ok(self.trunc())
             }
 
             fn nearest(self) -> Result<Self, Trap> {
+                if self.is_nan() {
+                    return Ok(self + self);
+                }
                 let round = self.round();
                 if self.fract().abs() != 0.5 {
                     Ok(round)
@@ -292,6 +314,10 @@ macro_rules! impl_extend {
     };
 }
 
+impl_extend!(i8, i16);
+impl_extend!(u8, u16);
+impl_extend!(i16, i32);
+impl_extend!(u16, u32);
 impl_extend!(i32, i64);
 impl_extend!(u32, u64);
 
@@ -311,6 +337,25 @@ macro_rules! impl_wrap {
 
 impl_wrap!(u64, u32);
 
+pub(crate) trait Narrow<T>: Sized {
+    fn narrow(val: T) -> Result<Self, Trap>;
+}
+
+macro_rules! impl_narrow {
+    ($T:ty, $U:ty) => {
+        impl Narrow<$T> for $U {
+            fn narrow(val: $T) -> Result<Self, Trap> {
+                Ok(val.clamp(Self::MIN as $T, Self::MAX as $T) as Self)
+            }
+        }
+    };
+}
+
+impl_narrow!(i16, i8);
+impl_narrow!(i16, u8);
+impl_narrow!(i32, i16);
+impl_narrow!(i32, u16);
+
 pub(crate) trait Trunc<T>: Sized {
     fn trunc(val: T) -> Result<Self, Trap>;
 
@@ -0,0 +1,289 @@
+//! Portable lane-wise operations on `v128` values.
+//!
+//! A `v128` is represented as a `u128`, and split into an array of lanes whenever an operation
+//! needs to look at the individual lanes. Lanes are always stored in little-endian order, so that
+//! lane 0 corresponds to the lowest bits of the `u128`, regardless of the byte order of the host.
+
+use {crate::trap::Trap, std::mem};
+
+/// A type that can be used as the lane of a `v128`.
+pub(crate) trait Lane: Copy + Default + PartialEq {
+    /// An array with one element for each lane of a `v128`.
+    type Lanes: Copy + Default + AsRef<[Self]> + AsMut<[Self]>;
+
+    /// Splits a `v128` into its lanes.
+    fn split(x: u128) -> Self::Lanes;
+
+    /// Joins the given lanes into a `v128`.
+    fn join(lanes: Self::Lanes) -> u128;
+}
+
+macro_rules! impl_lane {
+    ($T:ty, $N:literal) => {
+        impl Lane for $T {
+            type Lanes = [$T; $N];
+
+            fn split(x: u128) -> Self::Lanes {
+                let bytes = x.to_le_bytes();
+                let mut lanes = [<$T>::default(); $N];
+                for (lane, bytes) in lanes
+                    .iter_mut()
+                    .zip(bytes.chunks_exact(mem::size_of::<$T>()))
+                {
+                    *lane = <$T>::from_le_bytes(bytes.try_into().unwrap());
+                }
+                lanes
+            }
+
+            fn join(lanes: Self::Lanes) -> u128 {
+                let mut bytes = [0u8; 16];
+                for (bytes, lane) in bytes.chunks_exact_mut(mem::size_of::<$T>()).zip(lanes) {
+                    bytes.copy_from_slice(&lane.to_le_bytes());
+                }
+                u128::from_le_bytes(bytes)
+            }
+        }
+    };
+}
+
+impl_lane!(i8, 16);
+impl_lane!(u8, 16);
+impl_lane!(i16, 8);
+impl_lane!(u16, 8);
+impl_lane!(i32, 4);
+impl_lane!(u32, 4);
+impl_lane!(i64, 2);
+impl_lane!(u64, 2);
+impl_lane!(f32, 4);
+impl_lane!(f64, 2);
+
+/// Returns a `v128` with every lane set to the given value.
+pub(crate) fn splat<T: Lane>(x: T) -> u128 {
+    let mut lanes = T::Lanes::default();
+    lanes.as_mut().fill(x);
+    T::join(lanes)
+}
+
+/// Returns the lane with the given index.
+pub(crate) fn extract_lane<T: Lane>(x: u128, lane_idx: u8) -> T {
+    T::split(x).as_ref()[lane_idx as usize]
+}
+
+/// Returns a copy of `x` with the lane with the given index replaced by `y`.
+pub(crate) fn replace_lane<T: Lane>(x: u128, lane_idx: u8, y: T) -> u128 {
+    let mut lanes = T::split(x);
+    lanes.as_mut()[lane_idx as usize] = y;
+    T::join(lanes)
+}
+
+/// Applies `f` to each lane.
+pub(crate) fn map<T: Lane, U: Lane>(
+    x: u128,
+    f: impl Fn(T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    let mut z = U::Lanes::default();
+    for (z, x) in z.as_mut().iter_mut().zip(T::split(x).as_ref()) {
+        *z = f(*x)?;
+    }
+    Ok(U::join(z))
+}
+
+/// Applies `f` to each pair of corresponding lanes.
+pub(crate) fn zip<T: Lane, U: Lane>(
+    x: u128,
+    y: u128,
+    f: impl Fn(T, T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    let mut z = U::Lanes::default();
+    for ((z, x), y) in z
+        .as_mut()
+        .iter_mut()
+        .zip(T::split(x).as_ref())
+        .zip(T::split(y).as_ref())
+    {
+        *z = f(*x, *y)?;
+    }
+    Ok(U::join(z))
+}
+
+/// Compares each pair of corresponding lanes with `f`, and sets all bits of the corresponding
+/// lane of the result if `f` returns a non-zero value.
+pub(crate) fn cmp<T: Lane>(
+    x: u128,
+    y: u128,
+    f: impl Fn(T, T) -> Result<i32, Trap>,
+) -> Result<u128, Trap> {
+    let lane_bits = mem::size_of::<T>() * 8;
+    let lane_mask = u128::MAX >> (128 - lane_bits);
+    let mut z = 0;
+    for (lane_idx, (x, y)) in T::split(x)
+        .as_ref()
+        .iter()
+        .zip(T::split(y).as_ref())
+        .enumerate()
+    {
+        if f(*x, *y)? != 0 {
+            z |= lane_mask << (lane_idx * lane_bits);
+        }
+    }
+    Ok(z)
+}
+
+/// Applies `f` to each lane in the low half of `x`, producing lanes that are twice as wide.
+pub(crate) fn extend_low<T: Lane, U: Lane>(
+    x: u128,
+    f: impl Fn(T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    let mut z = U::Lanes::default();
+    for (z, x) in z.as_mut().iter_mut().zip(T::split(x).as_ref()) {
+        *z = f(*x)?;
+    }
+    Ok(U::join(z))
+}
+
+/// Applies `f` to each lane in the high half of `x`, producing lanes that are twice as wide.
+pub(crate) fn extend_high<T: Lane, U: Lane>(
+    x: u128,
+    f: impl Fn(T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    let x = T::split(x);
+    let x = x.as_ref();
+    let mut z = U::Lanes::default();
+    for (z, x) in z.as_mut().iter_mut().zip(&x[x.len() / 2..]) {
+        *z = f(*x)?;
+    }
+    Ok(U::join(z))
+}
+
+/// Applies `f` to each pair of corresponding lanes in the low halves of `x` and `y`, producing
+/// lanes that are twice as wide.
+pub(crate) fn extmul_low<T: Lane, U: Lane>(
+    x: u128,
+    y: u128,
+    f: impl Fn(T, T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    zip(x, y, f)
+}
+
+/// Applies `f` to each pair of corresponding lanes in the high halves of `x` and `y`, producing
+/// lanes that are twice as wide.
+pub(crate) fn extmul_high<T: Lane, U: Lane>(
+    x: u128,
+    y: u128,
+    f: impl Fn(T, T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    zip(x >> 64, y >> 64, f)
+}
+
+/// Applies `f` to each pair of adjacent lanes, producing lanes that are twice as wide.
+pub(crate) fn extadd_pairwise<T: Lane, U: Lane>(
+    x: u128,
+    f: impl Fn(T, T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    let x = T::split(x);
+    let mut z = U::Lanes::default();
+    for (z, x) in z.as_mut().iter_mut().zip(x.as_ref().chunks_exact(2)) {
+        *z = f(x[0], x[1])?;
+    }
+    Ok(U::join(z))
+}
+
+/// Applies `f` to each lane of `x` followed by each lane of `y`, producing lanes that are half as
+/// wide.
+pub(crate) fn narrow<T: Lane, U: Lane>(
+    x: u128,
+    y: u128,
+    f: impl Fn(T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    let mut z = U::Lanes::default();
+    for (z, x) in z
+        .as_mut()
+        .iter_mut()
+        .zip(T::split(x).as_ref().iter().chain(T::split(y).as_ref()))
+    {
+        *z = f(*x)?;
+    }
+    Ok(U::join(z))
+}
+
+/// Applies `f` to each lane of `x`, producing lanes that are half as wide. The high half of the
+/// result is set to zero.
+pub(crate) fn narrow_zero<T: Lane, U: Lane>(
+    x: u128,
+    f: impl Fn(T) -> Result<U, Trap>,
+) -> Result<u128, Trap> {
+    let mut z = U::Lanes::default();
+    for (z, x) in z.as_mut().iter_mut().zip(T::split(x).as_ref()) {
+        *z = f(*x)?;
+    }
+    Ok(U::join(z))
+}
+
+/// Returns `1` if every lane is non-zero, and `0` otherwise.
+pub(crate) fn all_true<T: Lane>(x: u128) -> i32 {
+    T::split(x)
+        .as_ref()
+        .iter()
+        .all(|x| *x != T::default())
+        .into()
+}
+
+/// Returns a mask with one bit for each lane, which is set if the most significant bit of the lane
+/// is set.
+pub(crate) fn bitmask<T: Lane>(x: u128) -> i32 {
+    let lane_bits = mem::size_of::<T>() * 8;
+    let mut mask = 0;
+    for lane_idx in 0..128 / lane_bits {
+        if x >> ((lane_idx + 1) * lane_bits - 1) & 1 != 0 {
+            mask |= 1 << lane_idx;
+        }
+    }
+    mask
+}
+
+/// Selects each bit from `x` if the corresponding bit in `c` is set, and from `y` otherwise.
+pub(crate) fn bitselect(x: u128, y: u128, c: u128) -> u128 {
+    x & c | y & !c
+}
+
+/// Selects each lane of the result from the concatenation of `x` and `y`, according to the given
+/// lane indices.
+pub(crate) fn shuffle(x: u128, y: u128, lane_idxs: [u8; 16]) -> u128 {
+    let x = u8::split(x);
+    let y = u8::split(y);
+    let mut z = [0u8; 16];
+    for (z, lane_idx) in z.iter_mut().zip(lane_idxs) {
+        let lane_idx = lane_idx as usize;
+        *z = if lane_idx < 16 {
+            x[lane_idx]
+        } else {
+            y[lane_idx - 16]
+        };
+    }
+    u8::join(z)
+}
+
+/// Selects each lane of the result from `x`, according to the lane indices in `y`. Lane indices
+/// that are out of range select zero.
+pub(crate) fn swizzle(x: u128, y: u128) -> u128 {
+    let x = u8::split(x);
+    let mut z = [0u8; 16];
+    for (z, lane_idx) in z.iter_mut().zip(u8::split(y)) {
+        *z = x.get(lane_idx as usize).copied().unwrap_or(0);
+    }
+    u8::join(z)
+}
+
+/// Multiplies each pair of corresponding signed 16-bit lanes, and adds adjacent pairs of the
+/// 32-bit products.
+pub(crate) fn dot(x: u128, y: u128) -> Result<u128, Trap> {
+    let x = i16::split(x);
+    let y = i16::split(y);
+    let mut z = [0i32; 4];
+    for (lane_idx, z) in z.iter_mut().enumerate() {
+        let lo = x[2 * lane_idx] as i32 * y[2 * lane_idx] as i32;
+        let hi = x[2 * lane_idx + 1] as i32 * y[2 * lane_idx + 1] as i32;
+        *z = lo.wrapping_add(hi);
+    }
+    Ok(i32::join(z))
+}
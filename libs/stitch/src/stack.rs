@@ -70,6 +70,11 @@ impl Drop for StackGuard {
     }
 }
 
+// Every value occupies a single stack slot, so with SIMD enabled a slot must be wide enough to
+// hold a `v128`.
+#[cfg(feature = "simd")]
+pub(crate) type StackSlot = u128;
+#[cfg(not(feature = "simd"))]
 pub(crate) type StackSlot = u64;
 
 thread_local! {
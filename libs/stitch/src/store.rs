@@ -9,12 +9,17 @@ use {
         global::GlobalEntity,
         mem::MemEntity,
         table::TableEntity,
+        trap::Trap,
     },
     std::{
         collections::HashMap,
         fmt,
         hash::{Hash, Hasher},
         ptr::NonNull,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
     },
 };
 
@@ -31,6 +36,8 @@ pub struct Store {
     elems: Vec<AliasableBox<ElemEntity>>,
     datas: Vec<AliasableBox<DataEntity>>,
     externs: Vec<AliasableBox<ExternEntity>>,
+    fuel: Option<u64>,
+    interrupted: Arc<AtomicBool>,
 }
 
 impl Store {
@@ -47,6 +54,8 @@ impl Store {
             elems: Vec::new(),
             datas: Vec::new(),
             externs: Vec::new(),
+            fuel: None,
+            interrupted: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -54,6 +63,42 @@ impl Store {
         &self.engine
     }
 
+    /// Returns the fuel left in this [`Store`], or `None` if fuel is unlimited.
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Sets the fuel left in this [`Store`], or makes it unlimited if `fuel` is `None`.
+    ///
+    /// If the [`Engine`] has metering enabled, each function entry and loop iteration consumes
+    /// one unit of fuel, and running out of fuel traps with [`Trap::OutOfFuel`].
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// Returns an [`InterruptHandle`] for interrupting code running in this [`Store`].
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            interrupted: self.interrupted.clone(),
+        }
+    }
+
+    /// Consumes a unit of fuel, or returns the trap to raise if execution has to stop.
+    pub(crate) fn consume_fuel(&mut self) -> Result<(), Trap> {
+        if self.interrupted.load(Ordering::Relaxed) {
+            self.interrupted.store(false, Ordering::Relaxed);
+            return Err(Trap::Interrupted);
+        }
+        match &mut self.fuel {
+            Some(0) => Err(Trap::OutOfFuel),
+            Some(fuel) => {
+                *fuel -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
     pub(crate) fn id(&self) -> StoreId {
         self.id
     }
@@ -138,6 +183,23 @@ impl Store {
     }
 }
 
+/// A handle for interrupting the code running in a [`Store`], from any thread.
+///
+/// The code traps with [`Trap::Interrupted`] at its next function entry or loop iteration, if
+/// its [`Engine`] has metering enabled. An interrupt that arrives while no code is running takes
+/// effect on the next call.
+#[derive(Clone, Debug)]
+pub struct InterruptHandle {
+    interrupted: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    /// Interrupts the code running in the [`Store`].
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+}
+
 /// A unique identifier for a [`Store`].
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct StoreId(usize);
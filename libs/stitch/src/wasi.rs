@@ -0,0 +1,1210 @@
+use {
+    crate::{error::Error, func::Func, instance::Instance, linker::Linker, mem::Mem, store::Store},
+    std::{
+        collections::hash_map::RandomState,
+        fmt, fs,
+        fs::{File, Metadata, OpenOptions},
+        hash::{BuildHasher, Hasher},
+        io,
+        io::{Read, Seek, SeekFrom, Write},
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The name of the module that WASI preview1 imports are defined in.
+const MODULE_NAME: &str = "wasi_snapshot_preview1";
+
+const CLOCKID_REALTIME: u32 = 0;
+const CLOCKID_MONOTONIC: u32 = 1;
+const CLOCKID_PROCESS_CPUTIME: u32 = 2;
+const CLOCKID_THREAD_CPUTIME: u32 = 3;
+
+const FILETYPE_UNKNOWN: u8 = 0;
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+const FILETYPE_SYMBOLIC_LINK: u8 = 7;
+
+const FDFLAGS_APPEND: u32 = 1 << 0;
+
+const OFLAGS_CREAT: u32 = 1 << 0;
+const OFLAGS_DIRECTORY: u32 = 1 << 1;
+const OFLAGS_EXCL: u32 = 1 << 2;
+const OFLAGS_TRUNC: u32 = 1 << 3;
+
+const LOOKUPFLAGS_SYMLINK_FOLLOW: u32 = 1 << 0;
+
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_FD_WRITE: u64 = 1 << 6;
+const RIGHTS_ALL: u64 = (1 << 30) - 1;
+
+const WHENCE_SET: u32 = 0;
+const WHENCE_CUR: u32 = 1;
+const WHENCE_END: u32 = 2;
+
+const EVENTTYPE_CLOCK: u8 = 0;
+const SUBCLOCKFLAGS_ABSTIME: u16 = 1 << 0;
+
+const PREOPENTYPE_DIR: u8 = 0;
+
+const IOVEC_SIZE: u32 = 8;
+const FDSTAT_SIZE: u32 = 24;
+const FILESTAT_SIZE: u32 = 64;
+const DIRENT_SIZE: u32 = 24;
+const SUBSCRIPTION_SIZE: u32 = 48;
+const EVENT_SIZE: u32 = 32;
+
+/// The configuration of a [`Wasi`] instance.
+pub struct WasiConfig {
+    /// The command line arguments, including the program name.
+    pub args: Vec<String>,
+    /// The environment variables.
+    pub envs: Vec<(String, String)>,
+    /// The directories the guest has access to, as pairs of the path the guest sees and the
+    /// path on the host.
+    pub preopened_dirs: Vec<(String, PathBuf)>,
+    pub stdin: Box<dyn Read + Send>,
+    pub stdout: Box<dyn Write + Send>,
+    pub stderr: Box<dyn Write + Send>,
+}
+
+impl Default for WasiConfig {
+    fn default() -> Self {
+        Self {
+            args: Vec::new(),
+            envs: Vec::new(),
+            preopened_dirs: Vec::new(),
+            stdin: Box::new(io::stdin()),
+            stdout: Box::new(io::stdout()),
+            stderr: Box::new(io::stderr()),
+        }
+    }
+}
+
+impl fmt::Debug for WasiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WasiConfig")
+            .field("args", &self.args)
+            .field("envs", &self.envs)
+            .field("preopened_dirs", &self.preopened_dirs)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A host implementation of WASI preview1.
+///
+/// Guests only get access to the host file system through the preopened directories, and paths
+/// that would lead outside of these are rejected.
+#[derive(Clone, Debug)]
+pub struct Wasi {
+    state: Arc<Mutex<WasiState>>,
+}
+
+impl Wasi {
+    /// Creates a new [`Wasi`] with the given [`WasiConfig`].
+    pub fn new(config: WasiConfig) -> Self {
+        let mut fds = vec![
+            Some(Descriptor::Stdin(config.stdin)),
+            Some(Descriptor::Stdout(config.stdout)),
+            Some(Descriptor::Stderr(config.stderr)),
+        ];
+        for (guest_path, host_path) in config.preopened_dirs {
+            fds.push(Some(Descriptor::Dir(Dir {
+                root: host_path.canonicalize().unwrap_or(host_path),
+                path: PathBuf::new(),
+                preopened_path: Some(guest_path),
+            })));
+        }
+        Self {
+            state: Arc::new(Mutex::new(WasiState {
+                args: config.args,
+                envs: config
+                    .envs
+                    .into_iter()
+                    .map(|(key, val)| format!("{}={}", key, val))
+                    .collect(),
+                fds,
+                mem: None,
+                start_time: Instant::now(),
+                random_state: RandomState::new(),
+                random_counter: 0,
+            })),
+        }
+    }
+
+    /// Defines the WASI preview1 functions on the given [`Linker`].
+    ///
+    /// Functions that are not supported are defined as well, and return `ENOSYS`, so that every
+    /// module built for WASI can be instantiated.
+    pub fn add_to_linker(&self, store: &mut Store, linker: &mut Linker) {
+        macro_rules! define_funcs {
+            ($($name:ident($($param:ident: $type:ty),*);)*) => {
+                $(
+                    let state = self.state.clone();
+                    linker.define(
+                        MODULE_NAME,
+                        stringify!($name),
+                        Func::wrap(store, move |store: &mut Store, $($param: $type),*| -> u32 {
+                            match state.lock().unwrap().$name(store, $($param),*) {
+                                Ok(()) => 0,
+                                Err(errno) => errno.0 as u32,
+                            }
+                        }),
+                    );
+                )*
+            };
+        }
+
+        macro_rules! define_unsupported_funcs {
+            ($($name:ident($($type:ty),*);)*) => {
+                $(
+                    linker.define(
+                        MODULE_NAME,
+                        stringify!($name),
+                        Func::wrap(store, |$(_: $type),*| -> u32 { Errno::NOSYS.0 as u32 }),
+                    );
+                )*
+            };
+        }
+
+        define_funcs! {
+            args_get(argv: u32, argv_buf: u32);
+            args_sizes_get(argc_ptr: u32, argv_buf_size_ptr: u32);
+            environ_get(environ: u32, environ_buf: u32);
+            environ_sizes_get(environ_count_ptr: u32, environ_buf_size_ptr: u32);
+            clock_res_get(id: u32, resolution_ptr: u32);
+            clock_time_get(id: u32, precision: u64, time_ptr: u32);
+            fd_advise(fd: u32, offset: u64, len: u64, advice: u32);
+            fd_close(fd: u32);
+            fd_datasync(fd: u32);
+            fd_fdstat_get(fd: u32, fdstat_ptr: u32);
+            fd_fdstat_set_flags(fd: u32, flags: u32);
+            fd_filestat_get(fd: u32, filestat_ptr: u32);
+            fd_filestat_set_size(fd: u32, size: u64);
+            fd_pread(fd: u32, iovs: u32, iovs_len: u32, offset: u64, nread_ptr: u32);
+            fd_prestat_get(fd: u32, prestat_ptr: u32);
+            fd_prestat_dir_name(fd: u32, path: u32, path_len: u32);
+            fd_pwrite(fd: u32, iovs: u32, iovs_len: u32, offset: u64, nwritten_ptr: u32);
+            fd_read(fd: u32, iovs: u32, iovs_len: u32, nread_ptr: u32);
+            fd_readdir(fd: u32, buf: u32, buf_len: u32, cookie: u64, bufused_ptr: u32);
+            fd_renumber(fd: u32, to: u32);
+            fd_seek(fd: u32, offset: u64, whence: u32, newoffset_ptr: u32);
+            fd_sync(fd: u32);
+            fd_tell(fd: u32, offset_ptr: u32);
+            fd_write(fd: u32, iovs: u32, iovs_len: u32, nwritten_ptr: u32);
+            path_create_directory(fd: u32, path: u32, path_len: u32);
+            path_filestat_get(fd: u32, flags: u32, path: u32, path_len: u32, filestat_ptr: u32);
+            path_open(
+                fd: u32,
+                dirflags: u32,
+                path: u32,
+                path_len: u32,
+                oflags: u32,
+                rights_base: u64,
+                rights_inheriting: u64,
+                fdflags: u32,
+                fd_ptr: u32
+            );
+            path_remove_directory(fd: u32, path: u32, path_len: u32);
+            path_rename(
+                fd: u32,
+                old_path: u32,
+                old_path_len: u32,
+                new_fd: u32,
+                new_path: u32,
+                new_path_len: u32
+            );
+            path_unlink_file(fd: u32, path: u32, path_len: u32);
+            poll_oneoff(subscriptions: u32, events: u32, subscription_count: u32, event_count_ptr: u32);
+            random_get(buf: u32, buf_len: u32);
+            sched_yield();
+        }
+
+        define_unsupported_funcs! {
+            fd_allocate(u32, u64, u64);
+            fd_fdstat_set_rights(u32, u64, u64);
+            fd_filestat_set_times(u32, u64, u64, u32);
+            path_filestat_set_times(u32, u32, u32, u32, u64, u64, u32);
+            path_link(u32, u32, u32, u32, u32, u32, u32);
+            path_readlink(u32, u32, u32, u32, u32, u32);
+            path_symlink(u32, u32, u32, u32, u32);
+            proc_raise(u32);
+            sock_accept(u32, u32, u32);
+            sock_recv(u32, u32, u32, u32, u32, u32);
+            sock_send(u32, u32, u32, u32, u32);
+            sock_shutdown(u32, u32);
+        }
+
+        linker.define(
+            MODULE_NAME,
+            "proc_exit",
+            Func::wrap(store, |code: u32| -> Result<(), Error> {
+                Err(Error::Exit(code))
+            }),
+        );
+    }
+
+    /// Sets the [`Mem`] that pointers passed to WASI functions point into.
+    ///
+    /// This is the memory exported by the module as `memory`.
+    pub fn set_mem(&self, mem: Mem) {
+        self.state.lock().unwrap().mem = Some(SendMem(mem));
+    }
+
+    /// Runs the `_start` function of the given WASI command instance, and returns its exit code.
+    ///
+    /// # Panics
+    ///
+    /// If the instance does not export a `memory` and a `_start` function.
+    pub fn run(&self, store: &mut Store, instance: &Instance) -> Result<u32, Error> {
+        self.set_mem(instance.exported_mem("memory").expect("no memory export"));
+        let start = instance.exported_func("_start").expect("no _start export");
+        let result = match start.call(store, &[], &mut []) {
+            Ok(()) => Ok(0),
+            Err(Error::Exit(code)) => Ok(code),
+            Err(error) => Err(error),
+        };
+        self.state.lock().unwrap().flush();
+        result
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Errno(u16);
+
+impl Errno {
+    const ACCES: Self = Self(2);
+    const BADF: Self = Self(8);
+    const EXIST: Self = Self(20);
+    const FAULT: Self = Self(21);
+    const INVAL: Self = Self(28);
+    const IO: Self = Self(29);
+    const ISDIR: Self = Self(31);
+    const NAMETOOLONG: Self = Self(37);
+    const NOENT: Self = Self(44);
+    const NOSYS: Self = Self(52);
+    const NOTDIR: Self = Self(54);
+    const NOTEMPTY: Self = Self(55);
+    const NOTSUP: Self = Self(58);
+    const PERM: Self = Self(63);
+    const SPIPE: Self = Self(70);
+    const NOTCAPABLE: Self = Self(76);
+}
+
+impl From<io::Error> for Errno {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NOENT,
+            io::ErrorKind::PermissionDenied => Self::ACCES,
+            io::ErrorKind::AlreadyExists => Self::EXIST,
+            io::ErrorKind::InvalidInput => Self::INVAL,
+            io::ErrorKind::NotADirectory => Self::NOTDIR,
+            io::ErrorKind::IsADirectory => Self::ISDIR,
+            io::ErrorKind::DirectoryNotEmpty => Self::NOTEMPTY,
+            io::ErrorKind::Unsupported => Self::NOTSUP,
+            _ => Self::IO,
+        }
+    }
+}
+
+/// A [`Mem`] that can be moved to the thread a host function runs on.
+#[derive(Debug)]
+struct SendMem(Mem);
+
+// SAFETY: a `Mem` is a handle, and the memory it refers to can only be reached through the
+// `Store` that owns it.
+unsafe impl Send for SendMem {}
+
+struct Dir {
+    /// The preopened directory this directory was opened from, which the guest cannot leave.
+    root: PathBuf,
+    /// The path of this directory, relative to `root`.
+    path: PathBuf,
+    preopened_path: Option<String>,
+}
+
+impl Dir {
+    fn host_path(&self) -> PathBuf {
+        self.root.join(&self.path)
+    }
+
+    /// Resolves a path the guest passed relative to this directory to a host path.
+    fn resolve(&self, path: &str) -> Result<(PathBuf, PathBuf), Errno> {
+        if path.contains('\0') || path.contains('\\') {
+            return Err(Errno::INVAL);
+        }
+        if path.starts_with('/') {
+            return Err(Errno::NOTCAPABLE);
+        }
+        let mut resolved = self.path.clone();
+        for component in path.split('/') {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    if !resolved.pop() {
+                        return Err(Errno::NOTCAPABLE);
+                    }
+                }
+                _ => resolved.push(component),
+            }
+        }
+        let host_path = self.root.join(&resolved);
+        // A symbolic link could still point outside of the root, so check where the
+        // nearest existing ancestor really is.
+        let mut ancestor = host_path.as_path();
+        loop {
+            if let Ok(real_path) = ancestor.canonicalize() {
+                if !real_path.starts_with(&self.root) {
+                    return Err(Errno::NOTCAPABLE);
+                }
+                break;
+            }
+            ancestor = match ancestor.parent() {
+                Some(parent) => parent,
+                None => break,
+            };
+        }
+        Ok((resolved, host_path))
+    }
+}
+
+enum Descriptor {
+    Stdin(Box<dyn Read + Send>),
+    Stdout(Box<dyn Write + Send>),
+    Stderr(Box<dyn Write + Send>),
+    Dir(Dir),
+    File { file: File, append: bool },
+}
+
+impl Descriptor {
+    fn filetype(&self) -> u8 {
+        match self {
+            Self::Stdin(_) | Self::Stdout(_) | Self::Stderr(_) => FILETYPE_CHARACTER_DEVICE,
+            Self::Dir(_) => FILETYPE_DIRECTORY,
+            Self::File { .. } => FILETYPE_REGULAR_FILE,
+        }
+    }
+
+    fn reader(&mut self) -> Result<&mut dyn Read, Errno> {
+        match self {
+            Self::Stdin(stdin) => Ok(stdin),
+            Self::File { file, .. } => Ok(file),
+            Self::Dir(_) => Err(Errno::ISDIR),
+            _ => Err(Errno::BADF),
+        }
+    }
+
+    fn writer(&mut self) -> Result<&mut dyn Write, Errno> {
+        match self {
+            Self::Stdout(stdout) => Ok(stdout),
+            Self::Stderr(stderr) => Ok(stderr),
+            Self::File { file, .. } => Ok(file),
+            Self::Dir(_) => Err(Errno::ISDIR),
+            _ => Err(Errno::BADF),
+        }
+    }
+
+    fn file(&mut self) -> Result<&mut File, Errno> {
+        match self {
+            Self::File { file, .. } => Ok(file),
+            Self::Dir(_) => Err(Errno::ISDIR),
+            _ => Err(Errno::SPIPE),
+        }
+    }
+
+    fn dir(&self) -> Result<&Dir, Errno> {
+        match self {
+            Self::Dir(dir) => Ok(dir),
+            _ => Err(Errno::NOTDIR),
+        }
+    }
+}
+
+/// A view of the guest memory, in which every access is bounds checked.
+struct MemView<'a>(&'a mut [u8]);
+
+impl MemView<'_> {
+    fn slice(&self, ptr: u32, len: u32) -> Result<&[u8], Errno> {
+        let start = ptr as usize;
+        let end = start.checked_add(len as usize).ok_or(Errno::FAULT)?;
+        self.0.get(start..end).ok_or(Errno::FAULT)
+    }
+
+    fn slice_mut(&mut self, ptr: u32, len: u32) -> Result<&mut [u8], Errno> {
+        let start = ptr as usize;
+        let end = start.checked_add(len as usize).ok_or(Errno::FAULT)?;
+        self.0.get_mut(start..end).ok_or(Errno::FAULT)
+    }
+
+    fn str(&self, ptr: u32, len: u32) -> Result<&str, Errno> {
+        std::str::from_utf8(self.slice(ptr, len)?).map_err(|_| Errno::INVAL)
+    }
+
+    fn read_u16(&self, ptr: u32) -> Result<u16, Errno> {
+        Ok(u16::from_le_bytes(self.slice(ptr, 2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&self, ptr: u32) -> Result<u32, Errno> {
+        Ok(u32::from_le_bytes(self.slice(ptr, 4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&self, ptr: u32) -> Result<u64, Errno> {
+        Ok(u64::from_le_bytes(self.slice(ptr, 8)?.try_into().unwrap()))
+    }
+
+    fn write_bytes(&mut self, ptr: u32, bytes: &[u8]) -> Result<(), Errno> {
+        self.slice_mut(ptr, bytes.len() as u32)?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn write_u8(&mut self, ptr: u32, val: u8) -> Result<(), Errno> {
+        self.write_bytes(ptr, &[val])
+    }
+
+    fn write_u16(&mut self, ptr: u32, val: u16) -> Result<(), Errno> {
+        self.write_bytes(ptr, &val.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, ptr: u32, val: u32) -> Result<(), Errno> {
+        self.write_bytes(ptr, &val.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, ptr: u32, val: u64) -> Result<(), Errno> {
+        self.write_bytes(ptr, &val.to_le_bytes())
+    }
+
+    /// Reads an array of `iovec`s, as pairs of a pointer and a length.
+    fn iovecs(&self, iovs: u32, iovs_len: u32) -> Result<Vec<(u32, u32)>, Errno> {
+        let size = iovs_len.checked_mul(IOVEC_SIZE).ok_or(Errno::FAULT)?;
+        Ok(self
+            .slice(iovs, size)?
+            .chunks_exact(IOVEC_SIZE as usize)
+            .map(|iov| {
+                (
+                    u32::from_le_bytes(iov[0..4].try_into().unwrap()),
+                    u32::from_le_bytes(iov[4..8].try_into().unwrap()),
+                )
+            })
+            .collect())
+    }
+
+    fn write_filestat(
+        &mut self,
+        ptr: u32,
+        filetype: u8,
+        metadata: Option<&Metadata>,
+    ) -> Result<(), Errno> {
+        fn nanos(time: io::Result<SystemTime>) -> u64 {
+            time.ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_nanos() as u64)
+        }
+
+        self.slice_mut(ptr, FILESTAT_SIZE)?.fill(0);
+        self.write_u8(ptr + 16, filetype)?;
+        self.write_u64(ptr + 24, 1)?;
+        if let Some(metadata) = metadata {
+            self.write_u64(ptr + 32, metadata.len())?;
+            self.write_u64(ptr + 40, nanos(metadata.accessed()))?;
+            self.write_u64(ptr + 48, nanos(metadata.modified()))?;
+            self.write_u64(ptr + 56, nanos(metadata.created()))?;
+        }
+        Ok(())
+    }
+}
+
+struct WasiState {
+    args: Vec<String>,
+    /// The environment variables, as `key=value` strings.
+    envs: Vec<String>,
+    fds: Vec<Option<Descriptor>>,
+    mem: Option<SendMem>,
+    start_time: Instant,
+    random_state: RandomState,
+    random_counter: u64,
+}
+
+impl fmt::Debug for WasiState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WasiState")
+            .field("args", &self.args)
+            .field("envs", &self.envs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasiState {
+    fn mem<'a>(&self, store: &'a mut Store) -> Result<MemView<'a>, Errno> {
+        let mem = self.mem.as_ref().ok_or(Errno::FAULT)?.0;
+        Ok(MemView(mem.bytes_mut(store)))
+    }
+
+    fn fd(&mut self, fd: u32) -> Result<&mut Descriptor, Errno> {
+        self.fds
+            .get_mut(fd as usize)
+            .and_then(|fd| fd.as_mut())
+            .ok_or(Errno::BADF)
+    }
+
+    fn insert_fd(&mut self, descriptor: Descriptor) -> u32 {
+        match self.fds.iter().position(|fd| fd.is_none()) {
+            Some(fd) => {
+                self.fds[fd] = Some(descriptor);
+                fd as u32
+            }
+            None => {
+                self.fds.push(Some(descriptor));
+                self.fds.len() as u32 - 1
+            }
+        }
+    }
+
+    fn resolve_path(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        path: u32,
+        path_len: u32,
+    ) -> Result<PathBuf, Errno> {
+        let path = self.mem(store)?.str(path, path_len)?.to_string();
+        Ok(self.fd(fd)?.dir()?.resolve(&path)?.1)
+    }
+
+    fn flush(&mut self) {
+        for fd in self.fds.iter_mut().flatten() {
+            if let Descriptor::Stdout(writer) | Descriptor::Stderr(writer) = fd {
+                let _ = writer.flush();
+            }
+        }
+    }
+
+    /// Writes a list of NUL terminated strings to `buf`, and pointers to them to `ptrs`.
+    fn write_strings(
+        store: &mut Store,
+        mem: &SendMem,
+        strings: &[String],
+        ptrs: u32,
+        buf: u32,
+    ) -> Result<(), Errno> {
+        let mut mem = MemView(mem.0.bytes_mut(store));
+        let mut ptr = buf;
+        for (index, string) in strings.iter().enumerate() {
+            mem.write_u32(ptrs + 4 * index as u32, ptr)?;
+            mem.write_bytes(ptr, string.as_bytes())?;
+            mem.write_u8(ptr + string.len() as u32, 0)?;
+            ptr += string.len() as u32 + 1;
+        }
+        Ok(())
+    }
+
+    fn write_string_sizes(
+        &self,
+        store: &mut Store,
+        strings: &[String],
+        count_ptr: u32,
+        buf_size_ptr: u32,
+    ) -> Result<(), Errno> {
+        let mut mem = self.mem(store)?;
+        mem.write_u32(count_ptr, strings.len() as u32)?;
+        mem.write_u32(
+            buf_size_ptr,
+            strings.iter().map(|string| string.len() as u32 + 1).sum(),
+        )
+    }
+
+    fn args_get(&mut self, store: &mut Store, argv: u32, argv_buf: u32) -> Result<(), Errno> {
+        let mem = self.mem.as_ref().ok_or(Errno::FAULT)?;
+        Self::write_strings(store, mem, &self.args, argv, argv_buf)
+    }
+
+    fn args_sizes_get(
+        &mut self,
+        store: &mut Store,
+        argc_ptr: u32,
+        argv_buf_size_ptr: u32,
+    ) -> Result<(), Errno> {
+        self.write_string_sizes(store, &self.args, argc_ptr, argv_buf_size_ptr)
+    }
+
+    fn environ_get(
+        &mut self,
+        store: &mut Store,
+        environ: u32,
+        environ_buf: u32,
+    ) -> Result<(), Errno> {
+        let mem = self.mem.as_ref().ok_or(Errno::FAULT)?;
+        Self::write_strings(store, mem, &self.envs, environ, environ_buf)
+    }
+
+    fn environ_sizes_get(
+        &mut self,
+        store: &mut Store,
+        environ_count_ptr: u32,
+        environ_buf_size_ptr: u32,
+    ) -> Result<(), Errno> {
+        self.write_string_sizes(store, &self.envs, environ_count_ptr, environ_buf_size_ptr)
+    }
+
+    fn now(&self, id: u32) -> Result<u64, Errno> {
+        match id {
+            CLOCKID_REALTIME => Ok(SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_nanos() as u64)),
+            // We don't track CPU time, so the CPU time clocks are approximated by the time
+            // since the instance was created.
+            CLOCKID_MONOTONIC | CLOCKID_PROCESS_CPUTIME | CLOCKID_THREAD_CPUTIME => {
+                Ok(self.start_time.elapsed().as_nanos() as u64)
+            }
+            _ => Err(Errno::INVAL),
+        }
+    }
+
+    fn clock_res_get(
+        &mut self,
+        store: &mut Store,
+        id: u32,
+        resolution_ptr: u32,
+    ) -> Result<(), Errno> {
+        self.now(id)?;
+        self.mem(store)?.write_u64(resolution_ptr, 1)
+    }
+
+    fn clock_time_get(
+        &mut self,
+        store: &mut Store,
+        id: u32,
+        _precision: u64,
+        time_ptr: u32,
+    ) -> Result<(), Errno> {
+        let time = self.now(id)?;
+        self.mem(store)?.write_u64(time_ptr, time)
+    }
+
+    fn fd_advise(
+        &mut self,
+        _store: &mut Store,
+        fd: u32,
+        _offset: u64,
+        _len: u64,
+        _advice: u32,
+    ) -> Result<(), Errno> {
+        // Advice may be ignored.
+        self.fd(fd)?.file()?;
+        Ok(())
+    }
+
+    fn fd_close(&mut self, _store: &mut Store, fd: u32) -> Result<(), Errno> {
+        self.fd(fd)?;
+        self.fds[fd as usize] = None;
+        Ok(())
+    }
+
+    fn fd_datasync(&mut self, _store: &mut Store, fd: u32) -> Result<(), Errno> {
+        Ok(self.fd(fd)?.file()?.sync_data()?)
+    }
+
+    fn fd_fdstat_get(&mut self, store: &mut Store, fd: u32, fdstat_ptr: u32) -> Result<(), Errno> {
+        let descriptor = self.fd(fd)?;
+        let filetype = descriptor.filetype();
+        let flags = match descriptor {
+            Descriptor::File { append: true, .. } => FDFLAGS_APPEND as u16,
+            _ => 0,
+        };
+        let mut mem = self.mem(store)?;
+        mem.slice_mut(fdstat_ptr, FDSTAT_SIZE)?.fill(0);
+        mem.write_u8(fdstat_ptr, filetype)?;
+        mem.write_u16(fdstat_ptr + 2, flags)?;
+        mem.write_u64(fdstat_ptr + 8, RIGHTS_ALL)?;
+        mem.write_u64(fdstat_ptr + 16, RIGHTS_ALL)
+    }
+
+    fn fd_fdstat_set_flags(
+        &mut self,
+        _store: &mut Store,
+        fd: u32,
+        flags: u32,
+    ) -> Result<(), Errno> {
+        // Flags can't be changed on an open file, so only setting the current flags succeeds.
+        let append = matches!(self.fd(fd)?, Descriptor::File { append: true, .. });
+        if flags == if append { FDFLAGS_APPEND } else { 0 } {
+            Ok(())
+        } else {
+            Err(Errno::NOTSUP)
+        }
+    }
+
+    fn fd_filestat_get(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        filestat_ptr: u32,
+    ) -> Result<(), Errno> {
+        let descriptor = self.fd(fd)?;
+        let filetype = descriptor.filetype();
+        let metadata = match descriptor {
+            Descriptor::File { file, .. } => Some(file.metadata()?),
+            Descriptor::Dir(dir) => Some(fs::metadata(dir.host_path())?),
+            _ => None,
+        };
+        self.mem(store)?
+            .write_filestat(filestat_ptr, filetype, metadata.as_ref())
+    }
+
+    fn fd_filestat_set_size(
+        &mut self,
+        _store: &mut Store,
+        fd: u32,
+        size: u64,
+    ) -> Result<(), Errno> {
+        Ok(self.fd(fd)?.file()?.set_len(size)?)
+    }
+
+    fn fd_pread(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        iovs: u32,
+        iovs_len: u32,
+        offset: u64,
+        nread_ptr: u32,
+    ) -> Result<(), Errno> {
+        let mut mem = self.mem(store)?;
+        let iovecs = mem.iovecs(iovs, iovs_len)?;
+        let file = self.fd(fd)?.file()?;
+        // Reading at an offset does not move the file position.
+        let position = file.stream_position()?;
+        file.seek(SeekFrom::Start(offset))?;
+        let result = read_iovecs(file, &mut mem, &iovecs);
+        file.seek(SeekFrom::Start(position))?;
+        mem.write_u32(nread_ptr, result?)
+    }
+
+    fn fd_prestat_get(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        prestat_ptr: u32,
+    ) -> Result<(), Errno> {
+        let Descriptor::Dir(Dir {
+            preopened_path: Some(path),
+            ..
+        }) = self.fd(fd)?
+        else {
+            return Err(Errno::BADF);
+        };
+        let path_len = path.len() as u32;
+        let mut mem = self.mem(store)?;
+        mem.write_u32(prestat_ptr, 0)?;
+        mem.write_u8(prestat_ptr, PREOPENTYPE_DIR)?;
+        mem.write_u32(prestat_ptr + 4, path_len)
+    }
+
+    fn fd_prestat_dir_name(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        path_ptr: u32,
+        path_len: u32,
+    ) -> Result<(), Errno> {
+        let Descriptor::Dir(Dir {
+            preopened_path: Some(path),
+            ..
+        }) = self.fd(fd)?
+        else {
+            return Err(Errno::BADF);
+        };
+        let path = path.clone();
+        if path.len() > path_len as usize {
+            return Err(Errno::NAMETOOLONG);
+        }
+        self.mem(store)?.write_bytes(path_ptr, path.as_bytes())
+    }
+
+    fn fd_pwrite(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        iovs: u32,
+        iovs_len: u32,
+        offset: u64,
+        nwritten_ptr: u32,
+    ) -> Result<(), Errno> {
+        let mut mem = self.mem(store)?;
+        let iovecs = mem.iovecs(iovs, iovs_len)?;
+        let file = self.fd(fd)?.file()?;
+        let position = file.stream_position()?;
+        file.seek(SeekFrom::Start(offset))?;
+        let result = write_iovecs(file, &mem, &iovecs);
+        file.seek(SeekFrom::Start(position))?;
+        mem.write_u32(nwritten_ptr, result?)
+    }
+
+    fn fd_read(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        iovs: u32,
+        iovs_len: u32,
+        nread_ptr: u32,
+    ) -> Result<(), Errno> {
+        let mut mem = self.mem(store)?;
+        let iovecs = mem.iovecs(iovs, iovs_len)?;
+        let nread = read_iovecs(self.fd(fd)?.reader()?, &mut mem, &iovecs)?;
+        mem.write_u32(nread_ptr, nread)
+    }
+
+    fn fd_readdir(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        buf: u32,
+        buf_len: u32,
+        cookie: u64,
+        bufused_ptr: u32,
+    ) -> Result<(), Errno> {
+        let host_path = self.fd(fd)?.dir()?.host_path();
+        let mut entries = vec![
+            (".".to_string(), FILETYPE_DIRECTORY),
+            ("..".to_string(), FILETYPE_DIRECTORY),
+        ];
+        let mut dir_entries = Vec::new();
+        for entry in fs::read_dir(host_path)? {
+            let entry = entry?;
+            let filetype = match entry.file_type() {
+                Ok(file_type) => filetype(file_type),
+                Err(_) => FILETYPE_UNKNOWN,
+            };
+            dir_entries.push((entry.file_name().to_string_lossy().into_owned(), filetype));
+        }
+        // The cookie is an index into the entries, so they need a stable order.
+        dir_entries.sort();
+        entries.extend(dir_entries);
+
+        let mut mem = self.mem(store)?;
+        let mut bytes = Vec::new();
+        for (index, (name, filetype)) in entries.iter().enumerate().skip(cookie as usize) {
+            let mut dirent = [0; DIRENT_SIZE as usize];
+            dirent[0..8].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+            dirent[16..20].copy_from_slice(&(name.len() as u32).to_le_bytes());
+            dirent[20] = *filetype;
+            bytes.extend_from_slice(&dirent);
+            bytes.extend_from_slice(name.as_bytes());
+            if bytes.len() >= buf_len as usize {
+                break;
+            }
+        }
+        // If the last entry doesn't fit, it is cut off, and the guest retries with a larger
+        // buffer.
+        bytes.truncate(buf_len as usize);
+        mem.write_bytes(buf, &bytes)?;
+        mem.write_u32(bufused_ptr, bytes.len() as u32)
+    }
+
+    fn fd_renumber(&mut self, _store: &mut Store, fd: u32, to: u32) -> Result<(), Errno> {
+        self.fd(fd)?;
+        self.fd(to)?;
+        self.fds[to as usize] = self.fds[fd as usize].take();
+        Ok(())
+    }
+
+    fn fd_seek(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        offset: u64,
+        whence: u32,
+        newoffset_ptr: u32,
+    ) -> Result<(), Errno> {
+        let offset = offset as i64;
+        let seek_from = match whence {
+            WHENCE_SET => SeekFrom::Start(u64::try_from(offset).map_err(|_| Errno::INVAL)?),
+            WHENCE_CUR => SeekFrom::Current(offset),
+            WHENCE_END => SeekFrom::End(offset),
+            _ => return Err(Errno::INVAL),
+        };
+        let new_offset = self.fd(fd)?.file()?.seek(seek_from)?;
+        self.mem(store)?.write_u64(newoffset_ptr, new_offset)
+    }
+
+    fn fd_sync(&mut self, _store: &mut Store, fd: u32) -> Result<(), Errno> {
+        Ok(self.fd(fd)?.file()?.sync_all()?)
+    }
+
+    fn fd_tell(&mut self, store: &mut Store, fd: u32, offset_ptr: u32) -> Result<(), Errno> {
+        let offset = self.fd(fd)?.file()?.stream_position()?;
+        self.mem(store)?.write_u64(offset_ptr, offset)
+    }
+
+    fn fd_write(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        iovs: u32,
+        iovs_len: u32,
+        nwritten_ptr: u32,
+    ) -> Result<(), Errno> {
+        let mut mem = self.mem(store)?;
+        let iovecs = mem.iovecs(iovs, iovs_len)?;
+        let nwritten = write_iovecs(self.fd(fd)?.writer()?, &mem, &iovecs)?;
+        mem.write_u32(nwritten_ptr, nwritten)
+    }
+
+    fn path_create_directory(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        path: u32,
+        path_len: u32,
+    ) -> Result<(), Errno> {
+        Ok(fs::create_dir(
+            self.resolve_path(store, fd, path, path_len)?,
+        )?)
+    }
+
+    fn path_filestat_get(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        flags: u32,
+        path: u32,
+        path_len: u32,
+        filestat_ptr: u32,
+    ) -> Result<(), Errno> {
+        let path = self.resolve_path(store, fd, path, path_len)?;
+        let metadata = if flags & LOOKUPFLAGS_SYMLINK_FOLLOW != 0 {
+            fs::metadata(path)?
+        } else {
+            fs::symlink_metadata(path)?
+        };
+        self.mem(store)?.write_filestat(
+            filestat_ptr,
+            filetype(metadata.file_type()),
+            Some(&metadata),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn path_open(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        _dirflags: u32,
+        path: u32,
+        path_len: u32,
+        oflags: u32,
+        rights_base: u64,
+        _rights_inheriting: u64,
+        fdflags: u32,
+        fd_ptr: u32,
+    ) -> Result<(), Errno> {
+        let guest_path = self.mem(store)?.str(path, path_len)?.to_string();
+        let dir = self.fd(fd)?.dir()?;
+        let root = dir.root.clone();
+        let (path, host_path) = dir.resolve(&guest_path)?;
+
+        let write =
+            rights_base & RIGHTS_FD_WRITE != 0 || oflags & (OFLAGS_CREAT | OFLAGS_TRUNC) != 0;
+        let is_dir = fs::metadata(&host_path).is_ok_and(|metadata| metadata.is_dir());
+        let descriptor = if oflags & OFLAGS_DIRECTORY != 0 || (is_dir && !write) {
+            if !is_dir {
+                return Err(if host_path.exists() {
+                    Errno::NOTDIR
+                } else {
+                    Errno::NOENT
+                });
+            }
+            Descriptor::Dir(Dir {
+                root,
+                path,
+                preopened_path: None,
+            })
+        } else {
+            if is_dir {
+                return Err(Errno::ISDIR);
+            }
+            let append = fdflags & FDFLAGS_APPEND != 0;
+            let file = OpenOptions::new()
+                .read(rights_base & RIGHTS_FD_READ != 0 || !write)
+                .write(write && !append)
+                .append(append)
+                .create(oflags & OFLAGS_CREAT != 0)
+                .create_new(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0)
+                .truncate(oflags & OFLAGS_TRUNC != 0)
+                .open(&host_path)?;
+            Descriptor::File { file, append }
+        };
+        let new_fd = self.insert_fd(descriptor);
+        if let Err(errno) = self.mem(store)?.write_u32(fd_ptr, new_fd) {
+            self.fds[new_fd as usize] = None;
+            return Err(errno);
+        }
+        Ok(())
+    }
+
+    fn path_remove_directory(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        path: u32,
+        path_len: u32,
+    ) -> Result<(), Errno> {
+        let guest_path = self.mem(store)?.str(path, path_len)?.to_string();
+        let (path, host_path) = self.fd(fd)?.dir()?.resolve(&guest_path)?;
+        // The preopened directory itself can't be removed.
+        if path.as_os_str().is_empty() {
+            return Err(Errno::PERM);
+        }
+        Ok(fs::remove_dir(host_path)?)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn path_rename(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        old_path: u32,
+        old_path_len: u32,
+        new_fd: u32,
+        new_path: u32,
+        new_path_len: u32,
+    ) -> Result<(), Errno> {
+        let old_path = self.resolve_path(store, fd, old_path, old_path_len)?;
+        let new_path = self.resolve_path(store, new_fd, new_path, new_path_len)?;
+        Ok(fs::rename(old_path, new_path)?)
+    }
+
+    fn path_unlink_file(
+        &mut self,
+        store: &mut Store,
+        fd: u32,
+        path: u32,
+        path_len: u32,
+    ) -> Result<(), Errno> {
+        let path = self.resolve_path(store, fd, path, path_len)?;
+        if fs::symlink_metadata(&path)?.is_dir() {
+            return Err(Errno::ISDIR);
+        }
+        Ok(fs::remove_file(path)?)
+    }
+
+    fn poll_oneoff(
+        &mut self,
+        store: &mut Store,
+        subscriptions: u32,
+        events: u32,
+        subscription_count: u32,
+        event_count_ptr: u32,
+    ) -> Result<(), Errno> {
+        if subscription_count == 0 {
+            return Err(Errno::INVAL);
+        }
+        let mut mem = self.mem(store)?;
+        // Pairs of the userdata and the tag of each subscription, and the timeout of the clock
+        // subscriptions.
+        let mut subs = Vec::new();
+        for index in 0..subscription_count {
+            let ptr = subscriptions + index * SUBSCRIPTION_SIZE;
+            let userdata = mem.read_u64(ptr)?;
+            let tag = *mem.slice(ptr + 8, 1)?.first().unwrap();
+            let timeout = if tag == EVENTTYPE_CLOCK {
+                let id = mem.read_u32(ptr + 16)?;
+                let timeout = mem.read_u64(ptr + 24)?;
+                if mem.read_u16(ptr + 40)? & SUBCLOCKFLAGS_ABSTIME != 0 {
+                    Some(timeout.saturating_sub(self.now(id)?))
+                } else {
+                    self.now(id)?;
+                    Some(timeout)
+                }
+            } else {
+                None
+            };
+            subs.push((userdata, tag, timeout));
+        }
+
+        // File descriptors are always ready, so we only wait if there are only clocks.
+        let has_fds = subs.iter().any(|(_, _, timeout)| timeout.is_none());
+        let min_timeout = subs.iter().filter_map(|(_, _, timeout)| *timeout).min();
+        if let (false, Some(min_timeout)) = (has_fds, min_timeout) {
+            thread::sleep(Duration::from_nanos(min_timeout));
+        }
+
+        let mut event_count = 0;
+        for (userdata, tag, timeout) in subs {
+            if has_fds && timeout.is_some() {
+                continue;
+            }
+            if timeout.is_some() && timeout != min_timeout {
+                continue;
+            }
+            let ptr = events + event_count * EVENT_SIZE;
+            mem.slice_mut(ptr, EVENT_SIZE)?.fill(0);
+            mem.write_u64(ptr, userdata)?;
+            mem.write_u8(ptr + 10, tag)?;
+            event_count += 1;
+        }
+        mem.write_u32(event_count_ptr, event_count)
+    }
+
+    fn random_get(&mut self, store: &mut Store, buf: u32, buf_len: u32) -> Result<(), Errno> {
+        // The keys of a `RandomState` are random, which makes its hashes of a counter good
+        // enough for seeding hash tables, but not for cryptography.
+        let mut mem = self.mem(store)?;
+        for chunk in mem.slice_mut(buf, buf_len)?.chunks_mut(8) {
+            let mut hasher = self.random_state.build_hasher();
+            hasher.write_u64(self.random_counter);
+            self.random_counter += 1;
+            chunk.copy_from_slice(&hasher.finish().to_le_bytes()[..chunk.len()]);
+        }
+        Ok(())
+    }
+
+    fn sched_yield(&mut self, _store: &mut Store) -> Result<(), Errno> {
+        thread::yield_now();
+        Ok(())
+    }
+}
+
+fn filetype(file_type: fs::FileType) -> u8 {
+    if file_type.is_dir() {
+        FILETYPE_DIRECTORY
+    } else if file_type.is_file() {
+        FILETYPE_REGULAR_FILE
+    } else if file_type.is_symlink() {
+        FILETYPE_SYMBOLIC_LINK
+    } else {
+        FILETYPE_UNKNOWN
+    }
+}
+
+fn read_iovecs(
+    reader: &mut dyn Read,
+    mem: &mut MemView,
+    iovecs: &[(u32, u32)],
+) -> Result<u32, Errno> {
+    let mut nread = 0;
+    for &(ptr, len) in iovecs {
+        let count = reader.read(mem.slice_mut(ptr, len)?)?;
+        nread += count as u32;
+        if count < len as usize {
+            break;
+        }
+    }
+    Ok(nread)
+}
+
+fn write_iovecs(
+    writer: &mut dyn Write,
+    mem: &MemView,
+    iovecs: &[(u32, u32)],
+) -> Result<u32, Errno> {
+    let mut nwritten = 0;
+    for &(ptr, len) in iovecs {
+        writer.write_all(mem.slice(ptr, len)?)?;
+        nwritten += len;
+    }
+    Ok(nwritten)
+}
@@ -0,0 +1,197 @@
+use {
+    makepad_stitch::{
+        Engine, EngineConfig, Error, Func, Linker, Mem, Module, ResumableCall, Store, Trap, Val,
+    },
+    std::{thread, time::Duration},
+    wast::{parser, parser::ParseBuffer, Wat},
+};
+
+fn module(store: &Store, wat: &str) -> Module {
+    let buf = ParseBuffer::new(wat).unwrap();
+    let mut wat = parser::parse::<Wat>(&buf).unwrap();
+    let bytes = wat.encode().unwrap();
+    Module::new(store.engine(), &bytes).unwrap()
+}
+
+fn metered_store() -> Store {
+    Store::new(Engine::with_config(EngineConfig { metering: true }))
+}
+
+/// Sums the numbers from 1 to the given number.
+const SUM: &str = r#"
+    (module
+        (func (export "sum") (param $n i32) (result i32)
+            (local $sum i32)
+            (block $done
+                (loop $loop
+                    (br_if $done (i32.eqz (local.get $n)))
+                    (local.set $sum (i32.add (local.get $sum) (local.get $n)))
+                    (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                    (br $loop)))
+            (local.get $sum))
+        (func (export "spin")
+            (loop $loop (br $loop))))
+"#;
+
+fn export(store: &mut Store, wat: &str, name: &str) -> Func {
+    let module = module(store, wat);
+    let instance = Linker::new().instantiate(store, &module).unwrap();
+    instance.exported_func(name).unwrap()
+}
+
+#[test]
+fn out_of_fuel() {
+    let mut store = metered_store();
+    let spin = export(&mut store, SUM, "spin");
+    store.set_fuel(Some(1000));
+    assert!(matches!(
+        spin.call(&mut store, &[], &mut []),
+        Err(Error::Trap(Trap::OutOfFuel))
+    ));
+    assert_eq!(store.fuel(), Some(0));
+
+    // The stack is reset after a trap, so the store can still be used.
+    let sum = export(&mut store, SUM, "sum");
+    store.set_fuel(None);
+    let mut results = [Val::I32(0)];
+    sum.call(&mut store, &[Val::I32(10)], &mut results).unwrap();
+    assert_eq!(results[0].to_i32(), Some(55));
+}
+
+#[test]
+fn enough_fuel() {
+    let mut store = metered_store();
+    let sum = export(&mut store, SUM, "sum");
+    store.set_fuel(Some(1000));
+    let mut results = [Val::I32(0)];
+    sum.call(&mut store, &[Val::I32(100)], &mut results)
+        .unwrap();
+    assert_eq!(results[0].to_i32(), Some(5050));
+    // One unit for entering the function, and one for each iteration of the loop.
+    assert_eq!(store.fuel(), Some(1000 - 1 - 101));
+}
+
+#[test]
+fn metering_disabled() {
+    let mut store = Store::new(Engine::new());
+    let sum = export(&mut store, SUM, "sum");
+    store.set_fuel(Some(0));
+    let mut results = [Val::I32(0)];
+    sum.call(&mut store, &[Val::I32(100)], &mut results)
+        .unwrap();
+    assert_eq!(results[0].to_i32(), Some(5050));
+    assert_eq!(store.fuel(), Some(0));
+}
+
+#[test]
+fn resume_after_refuel() {
+    let mut store = metered_store();
+    let sum = export(&mut store, SUM, "sum");
+    store.set_fuel(Some(10));
+    let mut results = [Val::I32(0)];
+    let mut call = sum
+        .call_resumable(&mut store, &[Val::I32(1000)], &mut results)
+        .unwrap();
+    let mut suspensions = 0;
+    while let ResumableCall::Suspended(suspended) = call {
+        assert!(matches!(suspended.trap(), Trap::OutOfFuel));
+        suspensions += 1;
+        store.set_fuel(Some(10));
+        call = suspended.resume(&mut store, &mut results).unwrap();
+    }
+    assert_eq!(results[0].to_i32(), Some(500500));
+    assert_eq!(suspensions, 100);
+}
+
+#[test]
+fn drop_suspended_call() {
+    let mut store = metered_store();
+    let spin = export(&mut store, SUM, "spin");
+    let sum = export(&mut store, SUM, "sum");
+    store.set_fuel(Some(10));
+    let call = spin.call_resumable(&mut store, &[], &mut []).unwrap();
+    assert!(matches!(call, ResumableCall::Suspended(_)));
+    drop(call);
+
+    store.set_fuel(None);
+    let mut results = [Val::I32(0)];
+    sum.call(&mut store, &[Val::I32(10)], &mut results).unwrap();
+    assert_eq!(results[0].to_i32(), Some(55));
+}
+
+#[test]
+fn interrupt() {
+    let mut store = metered_store();
+    let spin = export(&mut store, SUM, "spin");
+    let handle = store.interrupt_handle();
+    let thread = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+    assert!(matches!(
+        spin.call(&mut store, &[], &mut []),
+        Err(Error::Trap(Trap::Interrupted))
+    ));
+    thread.join().unwrap();
+}
+
+#[test]
+fn resume_after_interrupt() {
+    let mut store = metered_store();
+    let sum = export(&mut store, SUM, "sum");
+    store.interrupt_handle().interrupt();
+    let mut results = [Val::I32(0)];
+    let ResumableCall::Suspended(call) = sum
+        .call_resumable(&mut store, &[Val::I32(10)], &mut results)
+        .unwrap()
+    else {
+        panic!("call was not interrupted");
+    };
+    assert!(matches!(call.trap(), Trap::Interrupted));
+    assert!(matches!(
+        call.resume(&mut store, &mut results).unwrap(),
+        ResumableCall::Finished
+    ));
+    assert_eq!(results[0].to_i32(), Some(55));
+}
+
+#[test]
+fn grow_mem_while_suspended() {
+    let wat = r#"
+        (module
+            (memory (export "memory") 1)
+            ;; Writes a byte to every KiB of memory, up to the given address.
+            (func (export "fill") (param $n i32) (result i32)
+                (local $i i32)
+                (loop $loop
+                    (i32.store8 (local.get $i) (i32.const 1))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1024)))
+                    (br_if $loop (i32.lt_u (local.get $i) (local.get $n))))
+                (memory.size)))
+    "#;
+    let mut store = metered_store();
+    let module = module(&store, wat);
+    let instance = Linker::new().instantiate(&mut store, &module).unwrap();
+    let fill = instance.exported_func("fill").unwrap();
+    let mem: Mem = instance.exported_mem("memory").unwrap();
+    store.set_fuel(Some(10));
+    let mut results = [Val::I32(0)];
+    let ResumableCall::Suspended(call) = fill
+        .call_resumable(&mut store, &[Val::I32(2 * 65536)], &mut results)
+        .unwrap()
+    else {
+        panic!("call was not suspended");
+    };
+    mem.grow(&mut store, 1).unwrap();
+    store.set_fuel(None);
+    assert!(matches!(
+        call.resume(&mut store, &mut results).unwrap(),
+        ResumableCall::Finished
+    ));
+    assert_eq!(results[0].to_i32(), Some(2));
+    assert!(mem
+        .bytes(&store)
+        .iter()
+        .step_by(1024)
+        .all(|&byte| byte == 1));
+}
@@ -5,9 +5,10 @@ use {
     },
     std::{collections::HashMap, sync::Arc},
     wast::{
-        core::{HeapType, NanPattern, WastArgCore, WastRetCore},
+        core::{HeapType, NanPattern, V128Pattern, WastArgCore, WastRetCore},
         parser,
         parser::ParseBuffer,
+        token::{F32, F64},
         QuoteWat, Wast, WastArg, WastDirective, WastExecute, WastInvoke, WastRet, Wat,
     },
 };
@@ -253,6 +254,7 @@ impl WastRunner {
                     WastArgCore::I64(arg) => arg.into(),
                     WastArgCore::F32(arg) => f32::from_bits(arg.bits).into(),
                     WastArgCore::F64(arg) => f64::from_bits(arg.bits).into(),
+                    WastArgCore::V128(arg) => u128::from_le_bytes(arg.to_le_bytes()).into(),
                     WastArgCore::RefNull(HeapType::Func) => FuncRef::null().into(),
                     WastArgCore::RefNull(HeapType::Extern) => ExternRef::null().into(),
                     WastArgCore::RefExtern(val) => ExternRef::new(&mut self.store, val).into(),
@@ -295,42 +297,9 @@ fn assert_result(store: &Store, actual: Val, expected: WastRet<'_>) {
             WastRetCore::I64(expected) => {
                 assert_eq!(actual.to_i64().unwrap(), expected)
             }
-            WastRetCore::F32(expected) => match expected {
-                NanPattern::CanonicalNan => {
-                    assert!(
-                        actual.to_f32().unwrap().to_bits() & 0b0_11111111_11111111111111111111111
-                            == 0b0_11111111_10000000000000000000000
-                    );
-                }
-                NanPattern::ArithmeticNan => {
-                    assert!(
-                        actual.to_f32().unwrap().to_bits() & 0b0_11111111_11111111111111111111111
-                            >= 0b0_11111111_10000000000000000000000
-                    );
-                }
-                NanPattern::Value(expected_result) => {
-                    assert_eq!(actual.to_f32().unwrap().to_bits(), expected_result.bits)
-                }
-            },
-            WastRetCore::F64(expected) => match expected {
-                NanPattern::CanonicalNan => {
-                    assert!(
-                        actual.to_f64().unwrap().to_bits()
-                            & 0b0_11111111111_1111111111111111111111111111111111111111111111111111
-                            == 0b0_11111111111_1000000000000000000000000000000000000000000000000000
-                    );
-                }
-                NanPattern::ArithmeticNan => {
-                    assert!(
-                        actual.to_f64().unwrap().to_bits()
-                            & 0b0_11111111111_1111111111111111111111111111111111111111111111111111
-                            >= 0b0_11111111111_1000000000000000000000000000000000000000000000000000
-                    );
-                }
-                NanPattern::Value(expected_result) => {
-                    assert_eq!(actual.to_f64().unwrap().to_bits(), expected_result.bits)
-                }
-            },
+            WastRetCore::F32(expected) => assert_f32(actual.to_f32().unwrap(), expected),
+            WastRetCore::F64(expected) => assert_f64(actual.to_f64().unwrap(), expected),
+            WastRetCore::V128(expected) => assert_v128(actual.to_v128().unwrap(), expected),
             WastRetCore::RefNull(Some(HeapType::Func)) => {
                 assert_eq!(actual, Val::FuncRef(FuncRef::null()));
             }
@@ -353,9 +322,86 @@ fn assert_result(store: &Store, actual: Val, expected: WastRet<'_>) {
     }
 }
 
+fn assert_f32(actual: f32, expected: NanPattern<F32>) {
+    match expected {
+        NanPattern::CanonicalNan => {
+            assert!(
+                actual.to_bits() & 0b0_11111111_11111111111111111111111
+                    == 0b0_11111111_10000000000000000000000
+            );
+        }
+        NanPattern::ArithmeticNan => {
+            assert!(
+                actual.to_bits() & 0b0_11111111_11111111111111111111111
+                    >= 0b0_11111111_10000000000000000000000
+            );
+        }
+        NanPattern::Value(expected) => {
+            assert_eq!(actual.to_bits(), expected.bits)
+        }
+    }
+}
+
+fn assert_f64(actual: f64, expected: NanPattern<F64>) {
+    match expected {
+        NanPattern::CanonicalNan => {
+            assert!(
+                actual.to_bits()
+                    & 0b0_11111111111_1111111111111111111111111111111111111111111111111111
+                    == 0b0_11111111111_1000000000000000000000000000000000000000000000000000
+            );
+        }
+        NanPattern::ArithmeticNan => {
+            assert!(
+                actual.to_bits()
+                    & 0b0_11111111111_1111111111111111111111111111111111111111111111111111
+                    >= 0b0_11111111111_1000000000000000000000000000000000000000000000000000
+            );
+        }
+        NanPattern::Value(expected) => {
+            assert_eq!(actual.to_bits(), expected.bits)
+        }
+    }
+}
+
+fn assert_v128(actual: u128, expected: V128Pattern) {
+    let bytes = actual.to_le_bytes();
+    match expected {
+        V128Pattern::I8x16(expected) => {
+            assert_eq!(bytes.map(|byte| byte as i8), expected);
+        }
+        V128Pattern::I16x8(expected) => {
+            for (actual, expected) in bytes.chunks_exact(2).zip(expected) {
+                assert_eq!(i16::from_le_bytes(actual.try_into().unwrap()), expected);
+            }
+        }
+        V128Pattern::I32x4(expected) => {
+            for (actual, expected) in bytes.chunks_exact(4).zip(expected) {
+                assert_eq!(i32::from_le_bytes(actual.try_into().unwrap()), expected);
+            }
+        }
+        V128Pattern::I64x2(expected) => {
+            for (actual, expected) in bytes.chunks_exact(8).zip(expected) {
+                assert_eq!(i64::from_le_bytes(actual.try_into().unwrap()), expected);
+            }
+        }
+        V128Pattern::F32x4(expected) => {
+            for (actual, expected) in bytes.chunks_exact(4).zip(expected) {
+                assert_f32(f32::from_le_bytes(actual.try_into().unwrap()), expected);
+            }
+        }
+        V128Pattern::F64x2(expected) => {
+            for (actual, expected) in bytes.chunks_exact(8).zip(expected) {
+                assert_f64(f64::from_le_bytes(actual.try_into().unwrap()), expected);
+            }
+        }
+    }
+}
+
 macro_rules! testsuite {
-    ($($name:ident => $file_name:literal,)*) => {
+    ($($(#[$attr:meta])* $name:ident => $file_name:literal,)*) => {
         $(
+            $(#[$attr])*
             #[test]
             fn $name() {
                 use std::{fs, path::PathBuf};
@@ -437,65 +483,120 @@ testsuite! {
     ref_null => "ref_null.wast",
     r#return => "return.wast",
     select => "select.wast",
-    /*
-    simd_address => "simd/address.wast",
-    simd_align => "simd/align.wast",
-    simd_bit_shift => "simd/bit_shift.wast",
-    simd_bitwise => "simd/bitwise.wast",
-    simd_boolean => "simd/boolean.wast",
-    simd_const => "simd/const.wast",
-    simd_conversions => "simd/conversions.wast",
-    simd_f32x4 => "simd/f32x4.wast",
-    simd_f32x4_arith => "simd/f32x4-arith.wast",
-    simd_f32x4_cmp => "simd/f32x4-cmp.wast",
-    simd_f32x4_pmin_pmax => "simd/f32x4-pmin-pmax.wast",
-    simd_f32x4_rounding => "simd/f32x4-rounding.wast",
-    simd_f64x2 => "simd/f64x2.wast",
-    simd_f64x2_arith => "simd/f64x2-arith.wast",
-    simd_f64x2_cmp => "simd/f64x2-cmp.wast",
-    simd_f64x2_pmin_pmax => "simd/f64x2-pmin-pmax.wast",
-    simd_f64x2_rounding => "simd/f64x2-rounding.wast",
-    simd_i16x8_arith => "simd/i16x8-arith.wast",
-    simd_i16x8_arith2 => "simd/i16x8-arith2.wast",
-    simd_i16x8_cmp => "simd/i16x8-cmp.wast",
-    simd_i16x8_extadd_pairwise_i8x16 => "simd/i16x8-extadd-pairwise-i8x16.wast",
-    simd_i16x8_extmul_i8x16 => "simd/i16x8-extmul-i8x16.wast",
-    simd_i16x8_q15mulr_sat_s => "simd/i16x8-q15mulr-sat_s.wast",
-    simd_i16x8_sat_arith => "simd/i16x8-sat-arith.wast",
-    simd_i32x4_arith => "simd/i32x4-arith.wast",
-    simd_i32x4_arith2 => "simd/i32x4-arith2.wast",
-    simd_i32x4_cmp => "simd/i32x4-cmp.wast",
-    simd_i32x4_dot_i16x8 => "simd/i32x4-dot_i16x8.wast",
-    simd_i32x4_extadd_pairwise_i16x8 => "simd/i32x4-extadd-pairwise-i16x8.wast",
-    simd_i32x4_extmul_i16x8 => "simd/i32x4-extmul-i16x8.wast",
-    simd_i32x4_trunc_sat_f32x4 => "simd/i32x4-trunc-sat-f32x4.wast",
-    simd_i32x4_trunc_sat_f64x2 => "simd/i32x4-trunc-sat-f64x2.wast",
-    simd_i64x2_arith => "simd/i64x2-arith.wast",
-    simd_i64x2_arith2 => "simd/i64x2-arith2.wast",
-    simd_i64x2_cmp => "simd/i64x2-cmp.wast",
-    simd_i64x2_extmul_i32x4 => "simd/i64x2-extmul-i32x4.wast",
-    simd_i8x16_arith => "simd/i8x16-arith.wast",
-    simd_i8x16_arith2 => "simd/i8x16-arith2.wast",
-    simd_i8x16_cmp => "simd/i8x16-cmp.wast",
-    simd_i8x16_sat_arith => "simd/i8x16-sat-arith.wast",
-    simd_int_to_int_extend => "simd/int-to-int-extend.wast",
-    simd_lane => "simd/lane.wast",
-    simd_linking => "simd/linking.wast",
-    simd_load => "simd/load.wast",
-    simd_load16_lane => "simd/load16_lane.wast",
-    simd_load32_lane => "simd/load32_lane.wast",
-    simd_load64_lane => "simd/load64_lane.wast",
-    simd_load8_lane => "simd/load8_lane.wast",
-    simd_load_extend => "simd/load_extend.wast",
-    simd_load_splat => "simd/load_splat.wast",
-    simd_load_zero => "simd/load_zero.wast",
-    simd_splat => "simd/splat.wast",
-    simd_store => "simd/store.wast",
-    simd_store16_lane => "simd/store16_lane.wast",
-    simd_store32_lane => "simd/store32_lane.wast",
-    simd_store64_lane => "simd/store64_lane.wast",
-    simd_store8_lane => "simd/store8_lane.wast",
-    */
+    #[cfg(feature = "simd")]
+    simd_address => "simd_address.wast",
+    #[cfg(feature = "simd")]
+    simd_align => "simd_align.wast",
+    #[cfg(feature = "simd")]
+    simd_bit_shift => "simd_bit_shift.wast",
+    #[cfg(feature = "simd")]
+    simd_bitwise => "simd_bitwise.wast",
+    #[cfg(feature = "simd")]
+    simd_boolean => "simd_boolean.wast",
+    #[cfg(feature = "simd")]
+    simd_const => "simd_const.wast",
+    #[cfg(feature = "simd")]
+    simd_conversions => "simd_conversions.wast",
+    #[cfg(feature = "simd")]
+    simd_f32x4 => "simd_f32x4.wast",
+    #[cfg(feature = "simd")]
+    simd_f32x4_arith => "simd_f32x4_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_f32x4_cmp => "simd_f32x4_cmp.wast",
+    #[cfg(feature = "simd")]
+    simd_f32x4_pmin_pmax => "simd_f32x4_pmin_pmax.wast",
+    #[cfg(feature = "simd")]
+    simd_f32x4_rounding => "simd_f32x4_rounding.wast",
+    #[cfg(feature = "simd")]
+    simd_f64x2 => "simd_f64x2.wast",
+    #[cfg(feature = "simd")]
+    simd_f64x2_arith => "simd_f64x2_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_f64x2_cmp => "simd_f64x2_cmp.wast",
+    #[cfg(feature = "simd")]
+    simd_f64x2_pmin_pmax => "simd_f64x2_pmin_pmax.wast",
+    #[cfg(feature = "simd")]
+    simd_f64x2_rounding => "simd_f64x2_rounding.wast",
+    #[cfg(feature = "simd")]
+    simd_i16x8_arith => "simd_i16x8_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_i16x8_arith2 => "simd_i16x8_arith2.wast",
+    #[cfg(feature = "simd")]
+    simd_i16x8_cmp => "simd_i16x8_cmp.wast",
+    #[cfg(feature = "simd")]
+    simd_i16x8_extadd_pairwise_i8x16 => "simd_i16x8_extadd_pairwise_i8x16.wast",
+    #[cfg(feature = "simd")]
+    simd_i16x8_extmul_i8x16 => "simd_i16x8_extmul_i8x16.wast",
+    #[cfg(feature = "simd")]
+    simd_i16x8_q15mulr_sat_s => "simd_i16x8_q15mulr_sat_s.wast",
+    #[cfg(feature = "simd")]
+    simd_i16x8_sat_arith => "simd_i16x8_sat_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_arith => "simd_i32x4_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_arith2 => "simd_i32x4_arith2.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_cmp => "simd_i32x4_cmp.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_dot_i16x8 => "simd_i32x4_dot_i16x8.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_extadd_pairwise_i16x8 => "simd_i32x4_extadd_pairwise_i16x8.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_extmul_i16x8 => "simd_i32x4_extmul_i16x8.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_trunc_sat_f32x4 => "simd_i32x4_trunc_sat_f32x4.wast",
+    #[cfg(feature = "simd")]
+    simd_i32x4_trunc_sat_f64x2 => "simd_i32x4_trunc_sat_f64x2.wast",
+    #[cfg(feature = "simd")]
+    simd_i64x2_arith => "simd_i64x2_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_i64x2_arith2 => "simd_i64x2_arith2.wast",
+    #[cfg(feature = "simd")]
+    simd_i64x2_cmp => "simd_i64x2_cmp.wast",
+    #[cfg(feature = "simd")]
+    simd_i64x2_extmul_i32x4 => "simd_i64x2_extmul_i32x4.wast",
+    #[cfg(feature = "simd")]
+    simd_i8x16_arith => "simd_i8x16_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_i8x16_arith2 => "simd_i8x16_arith2.wast",
+    #[cfg(feature = "simd")]
+    simd_i8x16_cmp => "simd_i8x16_cmp.wast",
+    #[cfg(feature = "simd")]
+    simd_i8x16_sat_arith => "simd_i8x16_sat_arith.wast",
+    #[cfg(feature = "simd")]
+    simd_int_to_int_extend => "simd_int_to_int_extend.wast",
+    #[cfg(feature = "simd")]
+    simd_lane => "simd_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_linking => "simd_linking.wast",
+    #[cfg(feature = "simd")]
+    simd_load => "simd_load.wast",
+    #[cfg(feature = "simd")]
+    simd_load16_lane => "simd_load16_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_load32_lane => "simd_load32_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_load64_lane => "simd_load64_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_load8_lane => "simd_load8_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_load_extend => "simd_load_extend.wast",
+    #[cfg(feature = "simd")]
+    simd_load_splat => "simd_load_splat.wast",
+    #[cfg(feature = "simd")]
+    simd_load_zero => "simd_load_zero.wast",
+    #[cfg(feature = "simd")]
+    simd_splat => "simd_splat.wast",
+    #[cfg(feature = "simd")]
+    simd_store => "simd_store.wast",
+    #[cfg(feature = "simd")]
+    simd_store16_lane => "simd_store16_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_store32_lane => "simd_store32_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_store64_lane => "simd_store64_lane.wast",
+    #[cfg(feature = "simd")]
+    simd_store8_lane => "simd_store8_lane.wast",
     skip_stack_guard_page => "skip-stack-guard-page.wast",
     stack => "stack.wast",
     start => "start.wast",
@@ -0,0 +1,276 @@
+use {
+    makepad_stitch::{Engine, Error, Linker, Module, Store, Wasi, WasiConfig},
+    std::{
+        env, fs,
+        io::{self, Write},
+        path::PathBuf,
+        process,
+        sync::{Arc, Mutex},
+    },
+    wast::{parser, parser::ParseBuffer, Wat},
+};
+
+#[derive(Clone, Debug, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Output {
+    fn contents(&self) -> Vec<u8> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs a WASI command written in the text format, and returns its exit code.
+fn run(wat: &str, mut config: WasiConfig) -> Result<(u32, Vec<u8>), Error> {
+    let buf = ParseBuffer::new(wat).unwrap();
+    let mut wat = parser::parse::<Wat>(&buf).unwrap();
+    let bytes = wat.encode().unwrap();
+    let stdout = Output::default();
+    config.stdout = Box::new(stdout.clone());
+    let wasi = Wasi::new(config);
+    let mut store = Store::new(Engine::new());
+    let mut linker = Linker::new();
+    wasi.add_to_linker(&mut store, &mut linker);
+    let module = Module::new(store.engine(), &bytes).unwrap();
+    let instance = linker.instantiate(&mut store, &module)?;
+    let code = wasi.run(&mut store, &instance)?;
+    Ok((code, stdout.contents()))
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("stitch-wasi-{}-{}", process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const IMPORTS: &str = r#"
+    (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "clock_time_get" (func $clock_time_get (param i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_prestat_get" (func $fd_prestat_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_prestat_dir_name" (func $fd_prestat_dir_name (param i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+
+    ;; Exits with the given errno if it is not zero.
+    (func $check (param i32)
+        (if (local.get 0) (then (call $proc_exit (local.get 0)))))
+
+    ;; Writes the given range of memory to stdout.
+    (func $print (param $ptr i32) (param $len i32)
+        (i32.store (i32.const 0) (local.get $ptr))
+        (i32.store (i32.const 4) (local.get $len))
+        (call $check (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))))
+"#;
+
+fn command(body: &str) -> String {
+    format!("(module {} {})", IMPORTS, body)
+}
+
+#[test]
+fn hello() {
+    let wat = command(
+        r#"
+        (data (i32.const 64) "hello, world\n")
+        (func (export "_start")
+            (call $print (i32.const 64) (i32.const 13)))
+        "#,
+    );
+    let (code, stdout) = run(&wat, WasiConfig::default()).unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(stdout, b"hello, world\n");
+}
+
+#[test]
+fn args_and_env() {
+    let wat = command(
+        r#"
+        (func (export "_start")
+            (call $check (call $args_sizes_get (i32.const 16) (i32.const 20)))
+            (call $check (call $args_get (i32.const 64) (i32.const 128)))
+            (call $print (i32.load (i32.const 68)) (i32.sub (i32.load (i32.const 20)) (i32.const 5)))
+            (call $check (call $environ_sizes_get (i32.const 16) (i32.const 20)))
+            (call $check (call $environ_get (i32.const 64) (i32.const 128)))
+            (call $print (i32.const 128) (i32.load (i32.const 20))))
+        "#,
+    );
+    let config = WasiConfig {
+        args: vec!["prog".to_string(), "first".to_string()],
+        envs: vec![("KEY".to_string(), "value".to_string())],
+        ..WasiConfig::default()
+    };
+    let (code, stdout) = run(&wat, config).unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(stdout, b"first\0KEY=value\0");
+}
+
+#[test]
+fn proc_exit() {
+    let wat = command(
+        r#"
+        (func (export "_start")
+            (call $proc_exit (i32.const 42))
+            unreachable)
+        "#,
+    );
+    let (code, stdout) = run(&wat, WasiConfig::default()).unwrap();
+    assert_eq!(code, 42);
+    assert!(stdout.is_empty());
+}
+
+#[test]
+fn clock_and_random() {
+    let wat = command(
+        r#"
+        (func (export "_start")
+            (call $check (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 16)))
+            (if (i64.eqz (i64.load (i32.const 16))) (then unreachable))
+            (call $check (call $random_get (i32.const 64) (i32.const 32)))
+            (if (i64.eq (i64.load (i32.const 64)) (i64.load (i32.const 72))) (then unreachable))
+            ;; an unknown clock
+            (call $proc_exit (call $clock_time_get (i32.const 99) (i64.const 1) (i32.const 16))))
+        "#,
+    );
+    let (code, _) = run(&wat, WasiConfig::default()).unwrap();
+    assert_eq!(code, 28);
+}
+
+#[test]
+fn preopened_dir() {
+    let dir = temp_dir("preopened_dir");
+    let wat = command(
+        r#"
+        (func (export "_start")
+            (call $check (call $fd_prestat_get (i32.const 3) (i32.const 16)))
+            (call $check (call $fd_prestat_dir_name (i32.const 3) (i32.const 64) (i32.load (i32.const 20))))
+            (call $print (i32.const 64) (i32.load (i32.const 20)))
+            (call $proc_exit (call $fd_prestat_get (i32.const 4) (i32.const 16))))
+        "#,
+    );
+    let config = WasiConfig {
+        preopened_dirs: vec![("/sandbox".to_string(), dir.clone())],
+        ..WasiConfig::default()
+    };
+    let (code, stdout) = run(&wat, config).unwrap();
+    assert_eq!(stdout, b"/sandbox");
+    // there is no fd 4, which ends the search for preopened directories
+    assert_eq!(code, 8);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn read_and_write_files() {
+    let dir = temp_dir("read_and_write_files");
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/input.txt"), "from the host").unwrap();
+    let wat = command(
+        r#"
+        (data (i32.const 256) "sub/../sub/input.txt")
+        (data (i32.const 288) "output.txt")
+        (data (i32.const 304) "from the guest")
+        (func (export "_start")
+            ;; read the input file, with the FD_READ right
+            (call $check (call $path_open
+                (i32.const 3) (i32.const 0) (i32.const 256) (i32.const 20)
+                (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 16)))
+            (i32.store (i32.const 32) (i32.const 512))
+            (i32.store (i32.const 36) (i32.const 64))
+            (call $check (call $fd_read (i32.load (i32.const 16)) (i32.const 32) (i32.const 1) (i32.const 40)))
+            (call $print (i32.const 512) (i32.load (i32.const 40)))
+            (call $check (call $fd_close (i32.load (i32.const 16))))
+            ;; create the output file, with O_CREAT | O_TRUNC and the FD_WRITE right
+            (call $check (call $path_open
+                (i32.const 3) (i32.const 0) (i32.const 288) (i32.const 10)
+                (i32.const 9) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 16)))
+            (i32.store (i32.const 32) (i32.const 304))
+            (i32.store (i32.const 36) (i32.const 14))
+            (call $check (call $fd_write (i32.load (i32.const 16)) (i32.const 32) (i32.const 1) (i32.const 40)))
+            (call $check (call $fd_close (i32.load (i32.const 16)))))
+        "#,
+    );
+    let config = WasiConfig {
+        preopened_dirs: vec![(".".to_string(), dir.clone())],
+        ..WasiConfig::default()
+    };
+    let (code, stdout) = run(&wat, config).unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(stdout, b"from the host");
+    assert_eq!(
+        fs::read_to_string(dir.join("output.txt")).unwrap(),
+        "from the guest"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn paths_cannot_leave_preopened_dir() {
+    let dir = temp_dir("paths_cannot_leave_preopened_dir");
+    fs::create_dir(dir.join("sandbox")).unwrap();
+    fs::write(dir.join("secret.txt"), "secret").unwrap();
+    for path in ["../secret.txt", "/secret.txt", "a/../../secret.txt"] {
+        let wat = command(&format!(
+            r#"
+            (data (i32.const 256) "{}")
+            (func (export "_start")
+                (call $proc_exit (call $path_open
+                    (i32.const 3) (i32.const 0) (i32.const 256) (i32.const {})
+                    (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 16))))
+            "#,
+            path,
+            path.len()
+        ));
+        let config = WasiConfig {
+            preopened_dirs: vec![(".".to_string(), dir.join("sandbox"))],
+            ..WasiConfig::default()
+        };
+        let (code, _) = run(&wat, config).unwrap();
+        // ENOTCAPABLE
+        assert_eq!(code, 76, "{}", path);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unsupported_funcs_return_nosys() {
+    let wat = r#"
+        (module
+            (import "wasi_snapshot_preview1" "sock_accept" (func $sock_accept (param i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+            (memory (export "memory") 1)
+            (func (export "_start")
+                (call $proc_exit (call $sock_accept (i32.const 3) (i32.const 0) (i32.const 0)))))
+    "#;
+    let (code, _) = run(wat, WasiConfig::default()).unwrap();
+    assert_eq!(code, 52);
+}
+
+#[test]
+fn traps_are_errors() {
+    let wat = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "_start") unreachable))
+    "#;
+    assert!(matches!(
+        run(wat, WasiConfig::default()),
+        Err(Error::Trap(_))
+    ));
+}
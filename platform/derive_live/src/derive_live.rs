@@ -188,7 +188,7 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
         
         tb.add("impl").stream(generic.clone());
         tb.add("LiveApplyReset for").ident(&struct_name).stream(generic.clone()).stream(where_clause.clone()).add("{");
-        let walk_fields = ["abs_pos","margin","width","height"];
+        let walk_fields = ["abs_pos","margin","width","height","min_width","max_width","min_height","max_height","aspect_ratio","grid_row","grid_column"];
         let layout_fields = ["scroll","clip_x","clip_y","padding","align","flow","spacing"];
                 
         tb.add("    fn apply_reset(&mut self, cx: &mut Cx, apply:&mut Apply, start_index:usize, nodes:&[LiveNode]) {");
@@ -0,0 +1,1283 @@
+use{
+    std::{
+        cell::RefCell,
+        collections::HashMap,
+        rc::Rc,
+    },
+    crate::{
+        makepad_live_id::{LiveId, live_id},
+        makepad_math::{Vec2, Vec4},
+        shader_ast::*,
+        shader_registry::ShaderRegistry,
+        swizzle::Swizzle,
+    }
+};
+
+// A tree walking interpreter for draw shaders. It runs the analysed shader AST
+// directly so software renderers can execute vertex() and pixel() on the CPU.
+// Inputs are unpacked with the same slot layout the GLSL generator uses.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CpuNumKind {
+    Bool,
+    Int,
+    Float,
+    Mat
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CpuNum {
+    pub kind: CpuNumKind,
+    pub len: usize,
+    pub v: [f32; 16]
+}
+
+#[derive(Debug)]
+pub struct CpuClosure {
+    call_ptr: FnPtr,
+    closure_def_index: ClosureDefIndex,
+    captured: Vec<CpuLocal>,
+}
+
+#[derive(Clone, Debug)]
+pub enum CpuValue {
+    Void,
+    Num(CpuNum),
+    Struct(Vec<CpuValue>),
+    Array(Vec<CpuValue>),
+    Texture(usize),
+    Closure(Rc<CpuClosure>),
+    DrawShader,
+}
+
+pub trait CpuTextureSampler {
+    fn sample_2d(&self, slot: usize, pos: Vec2) -> Vec4;
+}
+
+pub struct CpuShaderEnv<'a> {
+    pub registry: &'a ShaderRegistry,
+    pub const_table: &'a DrawShaderConstTable,
+    pub pass_uniforms: &'a [f32],
+    pub view_uniforms: &'a [f32],
+    pub draw_uniforms: &'a [f32],
+    pub user_uniforms: &'a [f32],
+    pub live_uniforms: &'a [f32],
+    pub sampler: &'a dyn CpuTextureSampler,
+}
+
+/// The dFdx/dFdy arguments recorded by the neighbouring pixels of a 2x2 quad.
+/// Derivatives are the difference between the n-th recorded argument of each side.
+pub struct CpuDerivatives<'a> {
+    pub left: &'a [CpuNum],
+    pub right: &'a [CpuNum],
+    pub top: &'a [CpuNum],
+    pub bottom: &'a [CpuNum],
+}
+
+#[derive(Clone, Copy, Debug)]
+enum CpuFieldKind {
+    Geometry {offset: usize},
+    Instance {offset: usize},
+    Varying,
+    Uniform {block_ident: Ident, offset: usize},
+    Texture {slot: usize},
+}
+
+#[derive(Clone, Debug)]
+struct CpuField {
+    ty: Ty,
+    kind: CpuFieldKind,
+    varying_offset: Option<usize>,
+}
+
+pub struct CpuDrawShader {
+    fields: Vec<CpuField>,
+    field_map: HashMap<Ident, usize>,
+    live_map: HashMap<ValuePtr, (usize, Ty)>,
+    vertex_fn: FnPtr,
+    pixel_fn: FnPtr,
+    pub varying_slots: usize,
+    pub uses_derivatives: bool,
+    swizzles: RefCell<HashMap<Ident, Rc<Vec<usize >> >>,
+}
+
+impl CpuDrawShader {
+    pub fn new(draw_shader_def: &DrawShaderDef, shader_registry: &ShaderRegistry) -> Option<Self> {
+        let mut fields = Vec::new();
+        let mut field_map = HashMap::new();
+        let mut geometry_slots = 0;
+        let mut instance_slots = 0;
+        let mut texture_slots = 0;
+        let mut varying_slots = 0;
+        let mut block_slots: HashMap<Ident, usize> = HashMap::new();
+
+        for (index, field) in draw_shader_def.fields.iter().enumerate() {
+            let ty = field.ty_expr.ty.borrow().as_ref().unwrap().clone();
+            let (kind, in_pixel) = match &field.kind {
+                DrawShaderFieldKind::Geometry {is_used_in_pixel_shader, ..} => {
+                    let offset = geometry_slots;
+                    geometry_slots += ty.slots();
+                    (CpuFieldKind::Geometry {offset}, is_used_in_pixel_shader.get())
+                }
+                DrawShaderFieldKind::Instance {is_used_in_pixel_shader, ..} => {
+                    let offset = instance_slots;
+                    instance_slots += ty.slots();
+                    (CpuFieldKind::Instance {offset}, is_used_in_pixel_shader.get())
+                }
+                DrawShaderFieldKind::Varying {..} => (CpuFieldKind::Varying, true),
+                DrawShaderFieldKind::Uniform {block_ident, ..} => {
+                    let slots = block_slots.entry(*block_ident).or_insert(0);
+                    let offset = *slots;
+                    *slots += ty.slots();
+                    (CpuFieldKind::Uniform {block_ident: *block_ident, offset}, false)
+                }
+                DrawShaderFieldKind::Texture {..} => {
+                    let slot = texture_slots;
+                    texture_slots += 1;
+                    (CpuFieldKind::Texture {slot}, false)
+                }
+            };
+            let varying_offset = if in_pixel {
+                let offset = varying_slots;
+                varying_slots += ty.slots();
+                Some(offset)
+            }
+            else {
+                None
+            };
+            field_map.insert(field.ident, index);
+            fields.push(CpuField {ty, kind, varying_offset});
+        }
+
+        let mut live_map = HashMap::new();
+        let mut live_slots = 0;
+        for (value_ptr, ty) in draw_shader_def.all_live_refs.borrow().iter() {
+            live_map.insert(*value_ptr, (live_slots, ty.clone()));
+            live_slots += ty.slots();
+        }
+
+        let vertex_fn = shader_registry.draw_shader_method_decl_from_ident(draw_shader_def, Ident(live_id!(vertex)))?.fn_ptr;
+        let pixel_fn = shader_registry.draw_shader_method_decl_from_ident(draw_shader_def, Ident(live_id!(pixel)))?.fn_ptr;
+
+        let mut uses_derivatives = false;
+        for fn_ptr in draw_shader_def.pixel_fns.borrow().iter() {
+            let fn_def = shader_registry.all_fns.get(fn_ptr).unwrap();
+            if let Some(deps) = fn_def.builtin_deps.borrow().as_ref() {
+                if deps.contains(&Ident(live_id!(dFdx))) || deps.contains(&Ident(live_id!(dFdy))) {
+                    uses_derivatives = true;
+                }
+            }
+        }
+
+        Some(Self {
+            fields,
+            field_map,
+            live_map,
+            vertex_fn,
+            pixel_fn,
+            varying_slots,
+            uses_derivatives,
+            swizzles: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Runs vertex() for one vertex and returns the clip space position.
+    /// The varyings for the pixel stage are written packed into `varyings`.
+    pub fn vertex(&self, env: &CpuShaderEnv, geometry: &[f32], instance: &[f32], varyings: &mut Vec<f32>) -> Vec4 {
+        let mut trace = Vec::new();
+        let mut runner = CpuRunner::new(self, env, None, &mut trace);
+        for (index, field) in self.fields.iter().enumerate() {
+            match field.kind {
+                CpuFieldKind::Geometry {offset} => {
+                    runner.fields[index] = unpack_value(&field.ty, geometry.get(offset..).unwrap_or(&[]));
+                }
+                CpuFieldKind::Instance {offset} => {
+                    runner.fields[index] = unpack_value(&field.ty, instance.get(offset..).unwrap_or(&[]));
+                }
+                _ => ()
+            }
+        }
+        let fn_def = env.registry.all_fns.get(&self.vertex_fn).unwrap();
+        let (position, _) = runner.call_fn(fn_def, vec![CpuValue::DrawShader]);
+
+        varyings.clear();
+        for (index, field) in self.fields.iter().enumerate() {
+            if field.varying_offset.is_some() {
+                pack_value(&runner.fields[index], varyings);
+            }
+        }
+        position.to_vec4()
+    }
+
+    /// Runs pixel() with interpolated `varyings` and returns the output color.
+    /// Every dFdx/dFdy argument is appended to `trace`, derivatives come out
+    /// of `derivatives` when given and are zero otherwise.
+    pub fn pixel(&self, env: &CpuShaderEnv, varyings: &[f32], derivatives: Option<&CpuDerivatives>, trace: &mut Vec<CpuNum>) -> Vec4 {
+        trace.clear();
+        let mut runner = CpuRunner::new(self, env, derivatives, trace);
+        for (index, field) in self.fields.iter().enumerate() {
+            if let Some(offset) = field.varying_offset {
+                runner.fields[index] = unpack_value(&field.ty, varyings.get(offset..).unwrap_or(&[]));
+            }
+        }
+        let fn_def = env.registry.all_fns.get(&self.pixel_fn).unwrap();
+        let (color, _) = runner.call_fn(fn_def, vec![CpuValue::DrawShader]);
+        color.to_vec4()
+    }
+
+    fn swizzle(&self, ident: Ident) -> Rc<Vec<usize >> {
+        if let Some(swizzle) = self.swizzles.borrow().get(&ident) {
+            return swizzle.clone()
+        }
+        let swizzle = Rc::new(Swizzle::parse(ident).unwrap().iter().cloned().collect::<Vec<_ >> ());
+        self.swizzles.borrow_mut().insert(ident, swizzle.clone());
+        swizzle
+    }
+}
+
+impl CpuNum {
+    pub fn new(kind: CpuNumKind, len: usize) -> Self {
+        Self {kind, len, v: [0.0; 16]}
+    }
+
+    pub fn float(v: f32) -> Self {
+        let mut num = Self::new(CpuNumKind::Float, 1);
+        num.v[0] = v;
+        num
+    }
+
+    pub fn int(v: i32) -> Self {
+        let mut num = Self::new(CpuNumKind::Int, 1);
+        num.v[0] = v as f32;
+        num
+    }
+
+    pub fn bool(v: bool) -> Self {
+        let mut num = Self::new(CpuNumKind::Bool, 1);
+        num.v[0] = if v {1.0} else {0.0};
+        num
+    }
+
+    pub fn from_vec4(v: Vec4) -> Self {
+        let mut num = Self::new(CpuNumKind::Float, 4);
+        num.v[0..4].copy_from_slice(&[v.x, v.y, v.z, v.w]);
+        num
+    }
+
+    pub fn to_vec4(&self) -> Vec4 {
+        Vec4 {x: self.get(0), y: self.get(1), z: self.get(2), w: self.get(3)}
+    }
+
+    pub fn to_vec2(&self) -> Vec2 {
+        Vec2 {x: self.get(0), y: self.get(1)}
+    }
+
+    fn get(&self, index: usize) -> f32 {
+        if self.len == 1 {self.v[0]} else if index < self.len {self.v[index]} else {0.0}
+    }
+
+    fn is_true(&self) -> bool {
+        self.v[0] != 0.0
+    }
+
+    fn is_scalar(&self) -> bool {
+        self.len == 1
+    }
+
+    fn mat_dim(&self) -> usize {
+        match self.len {4 => 2, 9 => 3, _ => 4}
+    }
+
+    fn map(&self, f: impl Fn(f32) -> f32) -> Self {
+        let mut out = *self;
+        for i in 0..self.len {
+            out.v[i] = f(self.v[i]);
+        }
+        out
+    }
+
+    // componentwise with scalars broadcast, the result takes the shape of the widest operand
+    fn zip(&self, other: &CpuNum, f: impl Fn(f32, f32) -> f32) -> Self {
+        let shape = if self.len >= other.len {self} else {other};
+        let kind = if self.kind == CpuNumKind::Int && other.kind == CpuNumKind::Int {
+            CpuNumKind::Int
+        }
+        else if shape.kind == CpuNumKind::Mat {
+            CpuNumKind::Mat
+        }
+        else {
+            CpuNumKind::Float
+        };
+        let mut out = Self::new(kind, shape.len);
+        for i in 0..shape.len {
+            out.v[i] = f(self.get(i), other.get(i));
+        }
+        out
+    }
+
+    fn zip3(&self, b: &CpuNum, c: &CpuNum, f: impl Fn(f32, f32, f32) -> f32) -> Self {
+        let len = self.len.max(b.len).max(c.len);
+        let mut out = Self::new(CpuNumKind::Float, len);
+        for i in 0..len {
+            out.v[i] = f(self.get(i), b.get(i), c.get(i));
+        }
+        out
+    }
+
+    fn compare(&self, other: &CpuNum, f: impl Fn(f32, f32) -> bool) -> Self {
+        let len = self.len.max(other.len);
+        let mut out = Self::new(CpuNumKind::Bool, len);
+        for i in 0..len {
+            out.v[i] = if f(self.get(i), other.get(i)) {1.0} else {0.0};
+        }
+        out
+    }
+
+    fn dot(&self, other: &CpuNum) -> f32 {
+        let mut sum = 0.0;
+        for i in 0..self.len.max(other.len) {
+            sum += self.get(i) * other.get(i);
+        }
+        sum
+    }
+
+    fn convert(&self, kind: CpuNumKind) -> Self {
+        let mut out = *self;
+        out.kind = kind;
+        for i in 0..self.len {
+            out.v[i] = convert_component(self.v[i], kind);
+        }
+        out
+    }
+}
+
+fn convert_component(v: f32, kind: CpuNumKind) -> f32 {
+    match kind {
+        CpuNumKind::Bool => if v != 0.0 {1.0} else {0.0},
+        CpuNumKind::Int => v.trunc(),
+        _ => v
+    }
+}
+
+fn num_shape(ty: &Ty) -> Option<(CpuNumKind, usize)> {
+    Some(match ty {
+        Ty::Bool => (CpuNumKind::Bool, 1),
+        Ty::Int => (CpuNumKind::Int, 1),
+        Ty::Float | Ty::Enum(_) => (CpuNumKind::Float, 1),
+        Ty::Bvec2 => (CpuNumKind::Bool, 2),
+        Ty::Bvec3 => (CpuNumKind::Bool, 3),
+        Ty::Bvec4 => (CpuNumKind::Bool, 4),
+        Ty::Ivec2 => (CpuNumKind::Int, 2),
+        Ty::Ivec3 => (CpuNumKind::Int, 3),
+        Ty::Ivec4 => (CpuNumKind::Int, 4),
+        Ty::Vec2 => (CpuNumKind::Float, 2),
+        Ty::Vec3 => (CpuNumKind::Float, 3),
+        Ty::Vec4 => (CpuNumKind::Float, 4),
+        Ty::Mat2 => (CpuNumKind::Mat, 4),
+        Ty::Mat3 => (CpuNumKind::Mat, 9),
+        Ty::Mat4 => (CpuNumKind::Mat, 16),
+        _ => return None
+    })
+}
+
+fn ty_lit_shape(ty_lit: TyLit) -> Option<(CpuNumKind, usize)> {
+    Some(match ty_lit {
+        TyLit::Bool => (CpuNumKind::Bool, 1),
+        TyLit::Int => (CpuNumKind::Int, 1),
+        TyLit::Float => (CpuNumKind::Float, 1),
+        TyLit::Bvec2 => (CpuNumKind::Bool, 2),
+        TyLit::Bvec3 => (CpuNumKind::Bool, 3),
+        TyLit::Bvec4 => (CpuNumKind::Bool, 4),
+        TyLit::Ivec2 => (CpuNumKind::Int, 2),
+        TyLit::Ivec3 => (CpuNumKind::Int, 3),
+        TyLit::Ivec4 => (CpuNumKind::Int, 4),
+        TyLit::Vec2 => (CpuNumKind::Float, 2),
+        TyLit::Vec3 => (CpuNumKind::Float, 3),
+        TyLit::Vec4 => (CpuNumKind::Float, 4),
+        TyLit::Mat2 => (CpuNumKind::Mat, 4),
+        TyLit::Mat3 => (CpuNumKind::Mat, 9),
+        TyLit::Mat4 => (CpuNumKind::Mat, 16),
+        TyLit::Texture2D | TyLit::TextureOES => return None
+    })
+}
+
+fn unpack_value(ty: &Ty, slots: &[f32]) -> CpuValue {
+    if let Some((kind, len)) = num_shape(ty) {
+        let mut num = CpuNum::new(kind, len);
+        for i in 0..len.min(slots.len()) {
+            num.v[i] = convert_component(slots[i], kind);
+        }
+        return CpuValue::Num(num)
+    }
+    match ty {
+        Ty::Array {elem_ty, len} => {
+            let elem_slots = elem_ty.slots();
+            CpuValue::Array((0..*len).map( | i | {
+                unpack_value(elem_ty, slots.get(i * elem_slots..).unwrap_or(&[]))
+            }).collect())
+        }
+        _ => CpuValue::Void
+    }
+}
+
+fn pack_value(value: &CpuValue, out: &mut Vec<f32>) {
+    match value {
+        CpuValue::Num(num) => out.extend_from_slice(&num.v[0..num.len]),
+        CpuValue::Array(values) | CpuValue::Struct(values) => for value in values {
+            pack_value(value, out)
+        },
+        _ => ()
+    }
+}
+
+fn zero_value(shader_registry: &ShaderRegistry, ty: &Ty) -> CpuValue {
+    if let Some((kind, len)) = num_shape(ty) {
+        return CpuValue::Num(CpuNum::new(kind, len))
+    }
+    match ty {
+        Ty::Array {elem_ty, len} => CpuValue::Array((0..*len).map( | _ | zero_value(shader_registry, elem_ty)).collect()),
+        Ty::Struct(struct_ptr) => {
+            let struct_def = shader_registry.structs.get(struct_ptr).unwrap();
+            CpuValue::Struct(struct_def.fields.iter().map( | field | {
+                zero_value(shader_registry, field.ty_expr.ty.borrow().as_ref().unwrap())
+            }).collect())
+        }
+        Ty::DrawShader(_) => CpuValue::DrawShader,
+        _ => CpuValue::Void
+    }
+}
+
+fn val_to_value(val: &Val) -> CpuValue {
+    CpuValue::Num(match val {
+        Val::Bool(v) => CpuNum::bool(*v),
+        Val::Int(v) => CpuNum::int(*v),
+        Val::Float(v) => CpuNum::float(*v),
+        Val::Vec4(v) => CpuNum::from_vec4(*v),
+    })
+}
+
+impl CpuValue {
+    fn num(&self) -> CpuNum {
+        match self {
+            CpuValue::Num(num) => *num,
+            _ => CpuNum::float(0.0)
+        }
+    }
+
+    pub fn to_vec4(&self) -> Vec4 {
+        self.num().to_vec4()
+    }
+
+    fn equals(&self, other: &CpuValue) -> bool {
+        match (self, other) {
+            (CpuValue::Num(a), CpuValue::Num(b)) => a.len == b.len && (0..a.len).all( | i | a.v[i] == b.v[i]),
+            (CpuValue::Struct(a), CpuValue::Struct(b)) | (CpuValue::Array(a), CpuValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all( | (a, b) | a.equals(b))
+            }
+            _ => false
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CpuLocal {
+    ident: Ident,
+    shadow: ScopeSymShadow,
+    value: CpuValue
+}
+
+struct CpuFrame {
+    fn_ptr: FnPtr,
+    locals: Vec<CpuLocal>,
+    closures: Vec<(usize, Rc<CpuClosure >)>,
+    const_table_offset: Option<usize>,
+}
+
+impl CpuFrame {
+    fn get_local(&self, ident: Ident, shadow: ScopeSymShadow) -> Option<&CpuValue> {
+        self.locals.iter().rev().find( | local | local.ident == ident && local.shadow == shadow).map( | local | &local.value)
+    }
+
+    fn set_local(&mut self, ident: Ident, shadow: ScopeSymShadow, value: CpuValue) {
+        if let Some(local) = self.locals.iter_mut().rev().find( | local | local.ident == ident && local.shadow == shadow) {
+            local.value = value;
+        }
+        else {
+            self.locals.push(CpuLocal {ident, shadow, value});
+        }
+    }
+}
+
+enum CpuFlow {
+    Normal,
+    Break,
+    Continue,
+    Return(CpuValue)
+}
+
+enum CpuFieldAccess {
+    DrawShader,
+    Struct(usize),
+    Swizzle,
+}
+
+struct CpuRunner<'a, 'b> {
+    shader: &'a CpuDrawShader,
+    env: &'a CpuShaderEnv<'b>,
+    fields: Vec<CpuValue>,
+    derivatives: Option<&'a CpuDerivatives<'a >>,
+    trace: &'a mut Vec<CpuNum>,
+}
+
+impl<'a, 'b> CpuRunner<'a, 'b> {
+    fn new(shader: &'a CpuDrawShader, env: &'a CpuShaderEnv<'b>, derivatives: Option<&'a CpuDerivatives<'a >>, trace: &'a mut Vec<CpuNum>) -> Self {
+        let fields = shader.fields.iter().map( | field | zero_value(env.registry, &field.ty)).collect();
+        Self {
+            shader,
+            env,
+            fields,
+            derivatives,
+            trace
+        }
+    }
+
+    fn call_fn(&mut self, fn_def: &FnDef, args: Vec<CpuValue>) -> (CpuValue, CpuFrame) {
+        let mut frame = CpuFrame {
+            fn_ptr: fn_def.fn_ptr,
+            locals: Vec::new(),
+            closures: Vec::new(),
+            const_table_offset: self.env.const_table.offsets.get(&fn_def.fn_ptr).cloned()
+        };
+        for (index, (param, arg)) in fn_def.params.iter().zip(args).enumerate() {
+            if let CpuValue::Closure(closure) = arg {
+                frame.closures.push((index, closure));
+            }
+            else if let Some(shadow) = param.shadow.get() {
+                frame.locals.push(CpuLocal {ident: param.ident, shadow, value: arg});
+            }
+        }
+        let ret = match self.exec_block(&mut frame, &fn_def.block) {
+            CpuFlow::Return(value) => value,
+            _ => CpuValue::Void
+        };
+        (ret, frame)
+    }
+
+    fn call_closure(&mut self, closure: &CpuClosure, args: Vec<CpuValue>) -> CpuValue {
+        let call_def = self.env.registry.all_fns.get(&closure.call_ptr).unwrap();
+        let closure_def = &call_def.closure_defs[closure.closure_def_index.0];
+        let mut frame = CpuFrame {
+            fn_ptr: closure.call_ptr,
+            locals: closure.captured.clone(),
+            closures: Vec::new(),
+            const_table_offset: None,
+        };
+        for (param, arg) in closure_def.params.iter().zip(args) {
+            if let Some(shadow) = param.shadow.get() {
+                frame.set_local(param.ident, shadow, arg);
+            }
+        }
+        match &closure_def.kind {
+            ClosureDefKind::Expr(expr) => self.eval_expr(&mut frame, expr),
+            ClosureDefKind::Block(block) => match self.exec_block(&mut frame, block) {
+                CpuFlow::Return(value) => value,
+                _ => CpuValue::Void
+            }
+        }
+    }
+
+    fn exec_block(&mut self, frame: &mut CpuFrame, block: &Block) -> CpuFlow {
+        for stmt in &block.stmts {
+            match self.exec_stmt(frame, stmt) {
+                CpuFlow::Normal => (),
+                flow => return flow
+            }
+        }
+        CpuFlow::Normal
+    }
+
+    fn exec_stmt(&mut self, frame: &mut CpuFrame, stmt: &Stmt) -> CpuFlow {
+        match stmt {
+            Stmt::Break {..} => CpuFlow::Break,
+            Stmt::Continue {..} => CpuFlow::Continue,
+            Stmt::For {ident, from_expr, to_expr, step_expr, block, ..} => {
+                let const_int = | expr: &Expr | expr.const_val.borrow().as_ref().unwrap().as_ref().unwrap().to_int().unwrap();
+                let from = const_int(from_expr);
+                let to = const_int(to_expr);
+                let step = if let Some(step_expr) = step_expr {
+                    const_int(step_expr)
+                } else if from < to {1} else {-1};
+                if step == 0 {
+                    return CpuFlow::Normal
+                }
+                // same iteration scheme as the generated glsl
+                let mut i = if from <= to {from} else {from - 1};
+                while if from <= to {i < to} else {i >= to} {
+                    frame.set_local(*ident, ScopeSymShadow(0), CpuValue::Num(CpuNum::int(i)));
+                    match self.exec_block(frame, block) {
+                        CpuFlow::Break => break,
+                        CpuFlow::Return(value) => return CpuFlow::Return(value),
+                        _ => ()
+                    }
+                    if step > 0 {i += step.abs()} else {i -= step.abs()}
+                }
+                CpuFlow::Normal
+            }
+            Stmt::If {expr, block_if_true, block_if_false, ..} => {
+                if self.eval_expr(frame, expr).num().is_true() {
+                    self.exec_block(frame, block_if_true)
+                }
+                else if let Some(block_if_false) = block_if_false {
+                    self.exec_block(frame, block_if_false)
+                }
+                else {
+                    CpuFlow::Normal
+                }
+            }
+            Stmt::Match {expr, matches, ..} => {
+                let value = self.eval_expr(frame, expr).num().v[0];
+                for match_item in matches {
+                    if (value - match_item.enum_value.get().unwrap() as f32).abs() < 0.5 {
+                        return self.exec_block(frame, &match_item.block)
+                    }
+                }
+                CpuFlow::Normal
+            }
+            Stmt::Let {ty, shadow, ident, expr, ..} => {
+                let value = if let Some(expr) = expr {
+                    self.eval_expr(frame, expr)
+                }
+                else {
+                    zero_value(self.env.registry, ty.borrow().as_ref().unwrap())
+                };
+                frame.set_local(*ident, shadow.get().unwrap(), value);
+                CpuFlow::Normal
+            }
+            Stmt::Return {expr, ..} => {
+                let value = if let Some(expr) = expr {self.eval_expr(frame, expr)} else {CpuValue::Void};
+                CpuFlow::Return(value)
+            }
+            Stmt::Block {block, ..} => self.exec_block(frame, block),
+            Stmt::Expr {expr, ..} => {
+                self.eval_expr(frame, expr);
+                CpuFlow::Normal
+            }
+        }
+    }
+
+    fn eval_expr(&mut self, frame: &mut CpuFrame, expr: &Expr) -> CpuValue {
+        if let Some(Some(val)) = expr.const_val.borrow().as_ref() {
+            // the const table holds the live edited values, fall back to the folded value
+            if let (Some(index), Some(offset)) = (expr.const_index.get(), frame.const_table_offset) {
+                let table = &self.env.const_table.table;
+                match val {
+                    Val::Float(_) => if let Some(v) = table.get(offset + index) {
+                        return CpuValue::Num(CpuNum::float(*v))
+                    }
+                    Val::Vec4(_) => if let Some(v) = table.get(offset + index..offset + index + 4) {
+                        return CpuValue::Num(CpuNum::from_vec4(Vec4 {x: v[0], y: v[1], z: v[2], w: v[3]}))
+                    }
+                    _ => ()
+                }
+            }
+            return val_to_value(val)
+        }
+        match &expr.kind {
+            ExprKind::Cond {expr, expr_if_true, expr_if_false, ..} => {
+                if self.eval_expr(frame, expr).num().is_true() {
+                    self.eval_expr(frame, expr_if_true)
+                }
+                else {
+                    self.eval_expr(frame, expr_if_false)
+                }
+            }
+            ExprKind::Bin {op, left_expr, right_expr, ..} => self.eval_bin_expr(frame, *op, left_expr, right_expr),
+            ExprKind::Un {op, expr, ..} => {
+                let num = self.eval_expr(frame, expr).num();
+                CpuValue::Num(match op {
+                    UnOp::Not => num.map( | v | if v != 0.0 {0.0} else {1.0}),
+                    UnOp::Neg => num.map( | v | -v),
+                })
+            }
+            ExprKind::Field {expr: base_expr, field_ident, ..} => {
+                match self.field_access(base_expr, *field_ident) {
+                    CpuFieldAccess::DrawShader => self.read_draw_shader_field(*field_ident),
+                    CpuFieldAccess::Struct(index) => match self.eval_expr(frame, base_expr) {
+                        CpuValue::Struct(mut fields) if index < fields.len() => fields.swap_remove(index),
+                        _ => CpuValue::Void
+                    },
+                    CpuFieldAccess::Swizzle => {
+                        let base = self.eval_expr(frame, base_expr).num();
+                        let swizzle = self.shader.swizzle(*field_ident);
+                        let mut out = CpuNum::new(if base.kind == CpuNumKind::Mat {CpuNumKind::Float} else {base.kind}, swizzle.len());
+                        for (i, index) in swizzle.iter().enumerate() {
+                            out.v[i] = base.get(*index);
+                        }
+                        CpuValue::Num(out)
+                    }
+                }
+            }
+            ExprKind::Index {expr, index_expr, ..} => {
+                let base = self.eval_expr(frame, expr);
+                let index = self.eval_expr(frame, index_expr).num().v[0].max(0.0) as usize;
+                match base {
+                    CpuValue::Array(mut values) if index < values.len() => values.swap_remove(index),
+                    CpuValue::Num(num) if num.kind == CpuNumKind::Mat => {
+                        let dim = num.mat_dim();
+                        let mut out = CpuNum::new(CpuNumKind::Float, dim);
+                        for row in 0..dim {
+                            out.v[row] = num.get((index * dim + row).min(num.len - 1));
+                        }
+                        CpuValue::Num(out)
+                    }
+                    CpuValue::Num(num) => {
+                        let mut out = CpuNum::new(num.kind, 1);
+                        out.v[0] = num.get(index);
+                        CpuValue::Num(out)
+                    }
+                    _ => CpuValue::Void
+                }
+            }
+            ExprKind::MethodCall {ident, arg_exprs, ..} => {
+                let registry = self.env.registry;
+                let fn_def = match arg_exprs[0].ty.borrow().as_ref().unwrap() {
+                    Ty::Struct(struct_ptr) => registry.struct_method_decl_from_ident(
+                        registry.structs.get(struct_ptr).unwrap(),
+                        *ident
+                    ),
+                    Ty::DrawShader(shader_ptr) => registry.draw_shader_method_decl_from_ident(
+                        registry.draw_shader_defs.get(shader_ptr).unwrap(),
+                        *ident
+                    ),
+                    _ => None
+                };
+                if let Some(fn_def) = fn_def {
+                    self.eval_call(frame, fn_def, arg_exprs)
+                }
+                else {
+                    CpuValue::Void
+                }
+            }
+            ExprKind::PlainCall {fn_ptr, arg_exprs, param_index, ..} => {
+                if let Some(param_index) = param_index.get() {
+                    let closure = frame.closures.iter().find( | (index, _) | *index == param_index).map( | (_, closure) | closure.clone());
+                    if let Some(closure) = closure {
+                        let args = arg_exprs.iter().map( | arg_expr | self.eval_expr(frame, arg_expr)).collect();
+                        self.call_closure(&closure, args)
+                    }
+                    else {
+                        CpuValue::Void
+                    }
+                }
+                else {
+                    let fn_def = self.env.registry.all_fns.get(&fn_ptr.unwrap()).unwrap();
+                    self.eval_call(frame, fn_def, arg_exprs)
+                }
+            }
+            ExprKind::BuiltinCall {ident, arg_exprs, ..} => {
+                let args: Vec<CpuValue> = arg_exprs.iter().map( | arg_expr | self.eval_expr(frame, arg_expr)).collect();
+                self.eval_builtin(ident.0, &args)
+            }
+            ExprKind::ClosureDef(_) => CpuValue::Void,
+            ExprKind::ConsCall {ty_lit, arg_exprs, ..} => {
+                let args: Vec<CpuNum> = arg_exprs.iter().map( | arg_expr | self.eval_expr(frame, arg_expr).num()).collect();
+                construct(*ty_lit, &args)
+            }
+            ExprKind::StructCons {struct_ptr, args, ..} => {
+                let struct_def = self.env.registry.structs.get(struct_ptr).unwrap();
+                let mut fields = Vec::with_capacity(struct_def.fields.len());
+                for field in &struct_def.fields {
+                    if let Some((_, arg_expr)) = args.iter().find( | (ident, _) | *ident == field.ident) {
+                        fields.push(self.eval_expr(frame, arg_expr));
+                    }
+                    else {
+                        fields.push(zero_value(self.env.registry, field.ty_expr.ty.borrow().as_ref().unwrap()));
+                    }
+                }
+                CpuValue::Struct(fields)
+            }
+            ExprKind::Var {kind, ..} => {
+                if let Some(Ty::DrawShader(_)) = expr.ty.borrow().as_ref() {
+                    return CpuValue::DrawShader
+                }
+                match kind.get().unwrap() {
+                    VarKind::Local {ident, shadow} | VarKind::MutLocal {ident, shadow} => {
+                        if let Some(value) = frame.get_local(ident, shadow) {
+                            value.clone()
+                        }
+                        else {
+                            zero_value(self.env.registry, expr.ty.borrow().as_ref().unwrap())
+                        }
+                    }
+                    VarKind::LiveValue(value_ptr) => {
+                        if let Some((offset, ty)) = self.shader.live_map.get(&value_ptr) {
+                            unpack_value(ty, self.env.live_uniforms.get(*offset..).unwrap_or(&[]))
+                        }
+                        else {
+                            zero_value(self.env.registry, expr.ty.borrow().as_ref().unwrap())
+                        }
+                    }
+                }
+            }
+            ExprKind::Lit {lit, ..} => val_to_value(&lit.to_val()),
+        }
+    }
+
+    fn field_access(&self, base_expr: &Expr, field_ident: Ident) -> CpuFieldAccess {
+        match base_expr.ty.borrow().as_ref() {
+            Some(Ty::DrawShader(_)) => CpuFieldAccess::DrawShader,
+            Some(Ty::Struct(struct_ptr)) => {
+                let struct_def = self.env.registry.structs.get(struct_ptr).unwrap();
+                CpuFieldAccess::Struct(struct_def.fields.iter().position( | field | field.ident == field_ident).unwrap())
+            }
+            _ => CpuFieldAccess::Swizzle
+        }
+    }
+
+    fn read_draw_shader_field(&self, field_ident: Ident) -> CpuValue {
+        let index = if let Some(index) = self.shader.field_map.get(&field_ident) {*index} else {
+            return CpuValue::Void
+        };
+        let field = &self.shader.fields[index];
+        match field.kind {
+            CpuFieldKind::Uniform {block_ident, offset} => {
+                let block = match block_ident.0 {
+                    live_id!(pass) => self.env.pass_uniforms,
+                    live_id!(view) => self.env.view_uniforms,
+                    live_id!(draw) => self.env.draw_uniforms,
+                    live_id!(user) => self.env.user_uniforms,
+                    _ => &[]
+                };
+                unpack_value(&field.ty, block.get(offset..).unwrap_or(&[]))
+            }
+            CpuFieldKind::Texture {slot} => CpuValue::Texture(slot),
+            _ => self.fields[index].clone()
+        }
+    }
+
+    fn eval_call(&mut self, frame: &mut CpuFrame, fn_def: &FnDef, arg_exprs: &[Expr]) -> CpuValue {
+        let mut args = Vec::with_capacity(arg_exprs.len());
+        for arg_expr in arg_exprs {
+            if let ExprKind::ClosureDef(closure_def_index) = &arg_expr.kind {
+                args.push(self.make_closure(frame, *closure_def_index));
+            }
+            else {
+                args.push(self.eval_expr(frame, arg_expr));
+            }
+        }
+        let (ret, callee_frame) = self.call_fn(fn_def, args);
+        // write back the inout params
+        for (param, arg_expr) in fn_def.params.iter().zip(arg_exprs) {
+            if !param.is_inout {
+                continue;
+            }
+            if let Some(shadow) = param.shadow.get() {
+                if let Some(value) = callee_frame.get_local(param.ident, shadow) {
+                    self.assign(frame, arg_expr, value.clone());
+                }
+            }
+        }
+        ret
+    }
+
+    fn make_closure(&mut self, frame: &CpuFrame, closure_def_index: ClosureDefIndex) -> CpuValue {
+        let call_def = self.env.registry.all_fns.get(&frame.fn_ptr).unwrap();
+        let closure_def = &call_def.closure_defs[closure_def_index.0];
+        let mut captured = Vec::new();
+        if let Some(syms) = closure_def.closed_over_syms.borrow().as_ref() {
+            for sym in syms {
+                if let Some(value) = frame.get_local(sym.ident, sym.shadow) {
+                    captured.push(CpuLocal {ident: sym.ident, shadow: sym.shadow, value: value.clone()});
+                }
+            }
+        }
+        CpuValue::Closure(Rc::new(CpuClosure {
+            call_ptr: frame.fn_ptr,
+            closure_def_index,
+            captured
+        }))
+    }
+
+    fn assign(&mut self, frame: &mut CpuFrame, expr: &Expr, value: CpuValue) {
+        match &expr.kind {
+            ExprKind::Var {kind, ..} => match kind.get().unwrap() {
+                VarKind::Local {ident, shadow} | VarKind::MutLocal {ident, shadow} => {
+                    frame.set_local(ident, shadow, value);
+                }
+                VarKind::LiveValue(_) => ()
+            }
+            ExprKind::Field {expr: base_expr, field_ident, ..} => {
+                match self.field_access(base_expr, *field_ident) {
+                    CpuFieldAccess::DrawShader => {
+                        if let Some(index) = self.shader.field_map.get(field_ident) {
+                            self.fields[*index] = value;
+                        }
+                    }
+                    CpuFieldAccess::Struct(index) => {
+                        let mut base = self.eval_expr(frame, base_expr);
+                        if let CpuValue::Struct(fields) = &mut base {
+                            fields[index] = value;
+                        }
+                        self.assign(frame, base_expr, base);
+                    }
+                    CpuFieldAccess::Swizzle => {
+                        let mut base = self.eval_expr(frame, base_expr).num();
+                        let value = value.num();
+                        let swizzle = self.shader.swizzle(*field_ident);
+                        for (i, index) in swizzle.iter().enumerate() {
+                            base.v[*index] = convert_component(value.get(i), base.kind);
+                        }
+                        self.assign(frame, base_expr, CpuValue::Num(base));
+                    }
+                }
+            }
+            ExprKind::Index {expr: base_expr, index_expr, ..} => {
+                let index = self.eval_expr(frame, index_expr).num().v[0].max(0.0) as usize;
+                let mut base = self.eval_expr(frame, base_expr);
+                match &mut base {
+                    CpuValue::Array(values) if index < values.len() => values[index] = value,
+                    CpuValue::Num(num) if num.kind == CpuNumKind::Mat => {
+                        let dim = num.mat_dim();
+                        let value = value.num();
+                        for row in 0..dim.min(value.len) {
+                            if index * dim + row < num.len {
+                                num.v[index * dim + row] = value.v[row];
+                            }
+                        }
+                    }
+                    CpuValue::Num(num) if index < num.len => num.v[index] = convert_component(value.num().v[0], num.kind),
+                    _ => ()
+                }
+                self.assign(frame, base_expr, base);
+            }
+            _ => ()
+        }
+    }
+
+    fn eval_bin_expr(&mut self, frame: &mut CpuFrame, op: BinOp, left_expr: &Expr, right_expr: &Expr) -> CpuValue {
+        match op {
+            BinOp::Assign => {
+                let value = self.eval_expr(frame, right_expr);
+                self.assign(frame, left_expr, value.clone());
+                value
+            }
+            BinOp::AddAssign | BinOp::SubAssign | BinOp::MulAssign | BinOp::DivAssign => {
+                let left = self.eval_expr(frame, left_expr).num();
+                let right = self.eval_expr(frame, right_expr).num();
+                let op = match op {
+                    BinOp::AddAssign => BinOp::Add,
+                    BinOp::SubAssign => BinOp::Sub,
+                    BinOp::MulAssign => BinOp::Mul,
+                    _ => BinOp::Div
+                };
+                let value = CpuValue::Num(arith(op, &left, &right));
+                self.assign(frame, left_expr, value.clone());
+                value
+            }
+            BinOp::Or => {
+                let value = self.eval_expr(frame, left_expr).num().is_true() || self.eval_expr(frame, right_expr).num().is_true();
+                CpuValue::Num(CpuNum::bool(value))
+            }
+            BinOp::And => {
+                let value = self.eval_expr(frame, left_expr).num().is_true() && self.eval_expr(frame, right_expr).num().is_true();
+                CpuValue::Num(CpuNum::bool(value))
+            }
+            BinOp::Eq | BinOp::Ne => {
+                let left = self.eval_expr(frame, left_expr);
+                let right = self.eval_expr(frame, right_expr);
+                let equal = left.equals(&right);
+                CpuValue::Num(CpuNum::bool(if let BinOp::Eq = op {equal} else {!equal}))
+            }
+            BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                let left = self.eval_expr(frame, left_expr).num().v[0];
+                let right = self.eval_expr(frame, right_expr).num().v[0];
+                CpuValue::Num(CpuNum::bool(match op {
+                    BinOp::Lt => left < right,
+                    BinOp::Le => left <= right,
+                    BinOp::Gt => left > right,
+                    _ => left >= right,
+                }))
+            }
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
+                let left = self.eval_expr(frame, left_expr).num();
+                let right = self.eval_expr(frame, right_expr).num();
+                CpuValue::Num(arith(op, &left, &right))
+            }
+        }
+    }
+
+    fn eval_builtin(&mut self, id: LiveId, args: &[CpuValue]) -> CpuValue {
+        let arg = | index: usize | args.get(index).map( | arg | arg.num()).unwrap_or(CpuNum::float(0.0));
+        let num = match id {
+            live_id!(abs) => arg(0).map( | v | v.abs()),
+            live_id!(acos) => arg(0).map( | v | v.acos()),
+            live_id!(asin) => arg(0).map( | v | v.asin()),
+            live_id!(atan) => if args.len() == 2 {
+                arg(0).zip(&arg(1), | y, x | y.atan2(x))
+            }
+            else {
+                arg(0).map( | v | v.atan())
+            },
+            live_id!(ceil) => arg(0).map( | v | v.ceil()),
+            live_id!(clamp) => arg(0).zip3(&arg(1), &arg(2), | x, lo, hi | x.max(lo).min(hi)),
+            live_id!(cos) => arg(0).map( | v | v.cos()),
+            live_id!(cross) => {
+                let (a, b) = (arg(0), arg(1));
+                let mut out = CpuNum::new(CpuNumKind::Float, 3);
+                out.v[0] = a.v[1] * b.v[2] - a.v[2] * b.v[1];
+                out.v[1] = a.v[2] * b.v[0] - a.v[0] * b.v[2];
+                out.v[2] = a.v[0] * b.v[1] - a.v[1] * b.v[0];
+                out
+            }
+            live_id!(degrees) => arg(0).map( | v | v.to_degrees()),
+            live_id!(dFdx) | live_id!(dFdy) => {
+                let value = arg(0);
+                let index = self.trace.len();
+                self.trace.push(value);
+                let mut out = value.map( | _ | 0.0);
+                if let Some(derivatives) = self.derivatives {
+                    // window space y points up like it does on the gpu
+                    let (plus, minus) = if id == live_id!(dFdx) {
+                        (derivatives.right, derivatives.left)
+                    }
+                    else {
+                        (derivatives.top, derivatives.bottom)
+                    };
+                    if let (Some(plus), Some(minus)) = (plus.get(index), minus.get(index)) {
+                        if plus.len == minus.len {
+                            out = plus.zip(minus, | a, b | a - b);
+                        }
+                    }
+                }
+                out
+            }
+            live_id!(distance) => CpuNum::float(length(&arg(0).zip(&arg(1), | a, b | a - b))),
+            live_id!(dot) => CpuNum::float(arg(0).dot(&arg(1))),
+            live_id!(equal) => arg(0).compare(&arg(1), | a, b | a == b),
+            live_id!(exp) => arg(0).map( | v | v.exp()),
+            live_id!(exp2) => arg(0).map( | v | v.exp2()),
+            live_id!(faceforward) => {
+                let n = arg(0);
+                if arg(2).dot(&arg(1)) < 0.0 {n} else {n.map( | v | -v)}
+            }
+            live_id!(floor) => arg(0).map( | v | v.floor()),
+            live_id!(fract) => arg(0).map( | v | v - v.floor()),
+            live_id!(greaterThan) => arg(0).compare(&arg(1), | a, b | a > b),
+            live_id!(greaterThanEqual) => arg(0).compare(&arg(1), | a, b | a >= b),
+            live_id!(inversesqrt) => arg(0).map( | v | 1.0 / v.sqrt()),
+            live_id!(inverse) => mat_inverse(&arg(0)),
+            live_id!(length) => CpuNum::float(length(&arg(0))),
+            live_id!(lessThan) => arg(0).compare(&arg(1), | a, b | a < b),
+            live_id!(lessThanEqual) => arg(0).compare(&arg(1), | a, b | a <= b),
+            live_id!(log) => arg(0).map( | v | v.ln()),
+            live_id!(log2) => arg(0).map( | v | v.log2()),
+            live_id!(matrixCompMult) => arg(0).zip(&arg(1), | a, b | a * b),
+            live_id!(max) => arg(0).zip(&arg(1), | a, b | a.max(b)),
+            live_id!(min) => arg(0).zip(&arg(1), | a, b | a.min(b)),
+            live_id!(mix) => arg(0).zip3(&arg(1), &arg(2), | a, b, t | a + (b - a) * t),
+            live_id!(mod) => arg(0).zip(&arg(1), | x, y | x - y * (x / y).floor()),
+            live_id!(normalize) => {
+                let v = arg(0);
+                let len = length(&v);
+                v.map( | c | c / len)
+            }
+            live_id!(not) => arg(0).map( | v | if v != 0.0 {0.0} else {1.0}),
+            live_id!(notEqual) => arg(0).compare(&arg(1), | a, b | a != b),
+            live_id!(pow) => arg(0).zip(&arg(1), | a, b | a.powf(b)),
+            live_id!(radians) => arg(0).map( | v | v.to_radians()),
+            live_id!(reflect) => {
+                let (i, n) = (arg(0), arg(1));
+                let d = 2.0 * n.dot(&i);
+                i.zip(&n, | i, n | i - d * n)
+            }
+            live_id!(refract) => {
+                let (i, n, eta) = (arg(0), arg(1), arg(2).v[0]);
+                let d = n.dot(&i);
+                let k = 1.0 - eta * eta * (1.0 - d * d);
+                if k < 0.0 {
+                    i.map( | _ | 0.0)
+                }
+                else {
+                    i.zip(&n, | i, n | eta * i - (eta * d + k.sqrt()) * n)
+                }
+            }
+            live_id!(sample2d) | live_id!(sample2d_rt) | live_id!(sample2dOES) => {
+                if let Some(CpuValue::Texture(slot)) = args.first() {
+                    CpuNum::from_vec4(self.env.sampler.sample_2d(*slot, arg(1).to_vec2()))
+                }
+                else {
+                    CpuNum::new(CpuNumKind::Float, 4)
+                }
+            }
+            live_id!(sign) => arg(0).map( | v | if v > 0.0 {1.0} else if v < 0.0 {-1.0} else {0.0}),
+            live_id!(sin) => arg(0).map( | v | v.sin()),
+            live_id!(smoothstep) => arg(0).zip3(&arg(1), &arg(2), | e0, e1, x | {
+                let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }),
+            live_id!(sqrt) => arg(0).map( | v | v.sqrt()),
+            live_id!(step) => arg(0).zip(&arg(1), | edge, x | if x < edge {0.0} else {1.0}),
+            live_id!(tan) => arg(0).map( | v | v.tan()),
+            live_id!(transpose) => {
+                let m = arg(0);
+                let dim = m.mat_dim();
+                let mut out = m;
+                for col in 0..dim {
+                    for row in 0..dim {
+                        out.v[col * dim + row] = m.v[row * dim + col];
+                    }
+                }
+                out
+            }
+            live_id!(all) => {
+                let v = arg(0);
+                CpuNum::bool((0..v.len).all( | i | v.v[i] != 0.0))
+            }
+            live_id!(any) => {
+                let v = arg(0);
+                CpuNum::bool((0..v.len).any( | i | v.v[i] != 0.0))
+            }
+            _ => return CpuValue::Void
+        };
+        CpuValue::Num(num)
+    }
+}
+
+fn length(v: &CpuNum) -> f32 {
+    v.dot(v).sqrt()
+}
+
+fn arith(op: BinOp, left: &CpuNum, right: &CpuNum) -> CpuNum {
+    if let BinOp::Mul = op {
+        let left_mat = left.kind == CpuNumKind::Mat;
+        let right_mat = right.kind == CpuNumKind::Mat;
+        // matrices are stored column major like glsl
+        if left_mat && right_mat {
+            let dim = left.mat_dim();
+            let mut out = CpuNum::new(CpuNumKind::Mat, left.len);
+            for col in 0..dim {
+                for row in 0..dim {
+                    out.v[col * dim + row] = (0..dim).map( | k | left.v[k * dim + row] * right.v[col * dim + k]).sum();
+                }
+            }
+            return out
+        }
+        if left_mat && !right.is_scalar() {
+            let dim = left.mat_dim();
+            let mut out = CpuNum::new(CpuNumKind::Float, dim);
+            for row in 0..dim {
+                out.v[row] = (0..dim).map( | k | left.v[k * dim + row] * right.get(k)).sum();
+            }
+            return out
+        }
+        if right_mat && !left.is_scalar() {
+            let dim = right.mat_dim();
+            let mut out = CpuNum::new(CpuNumKind::Float, dim);
+            for col in 0..dim {
+                out.v[col] = (0..dim).map( | k | left.get(k) * right.v[col * dim + k]).sum();
+            }
+            return out
+        }
+    }
+    let is_int = left.kind == CpuNumKind::Int && right.kind == CpuNumKind::Int;
+    left.zip(right, | a, b | match op {
+        BinOp::Add => a + b,
+        BinOp::Sub => a - b,
+        BinOp::Mul => a * b,
+        _ => if is_int {
+            if b == 0.0 {0.0} else {(a / b).trunc()}
+        }
+        else {
+            a / b
+        }
+    })
+}
+
+fn construct(ty_lit: TyLit, args: &[CpuNum]) -> CpuValue {
+    let (kind, len) = if let Some(shape) = ty_lit_shape(ty_lit) {shape} else {
+        return CpuValue::Void
+    };
+    let mut out = CpuNum::new(kind, len);
+    if kind == CpuNumKind::Mat {
+        let dim = out.mat_dim();
+        if args.len() == 1 && args[0].kind == CpuNumKind::Mat {
+            let src = &args[0];
+            let src_dim = src.mat_dim();
+            for col in 0..dim {
+                for row in 0..dim {
+                    out.v[col * dim + row] = if col < src_dim && row < src_dim {
+                        src.v[col * src_dim + row]
+                    } else if col == row {1.0} else {0.0};
+                }
+            }
+            return CpuValue::Num(out)
+        }
+        if args.len() == 1 && args[0].is_scalar() {
+            for i in 0..dim {
+                out.v[i * dim + i] = args[0].v[0];
+            }
+            return CpuValue::Num(out)
+        }
+    }
+    else if args.len() == 1 && args[0].is_scalar() {
+        let v = convert_component(args[0].v[0], kind);
+        for i in 0..len {
+            out.v[i] = v;
+        }
+        return CpuValue::Num(out)
+    }
+    let mut index = 0;
+    'outer: for arg in args {
+        for i in 0..arg.len {
+            if index >= len {
+                break 'outer;
+            }
+            out.v[index] = convert_component(arg.v[i], kind);
+            index += 1;
+        }
+    }
+    CpuValue::Num(out)
+}
+
+fn mat_inverse(m: &CpuNum) -> CpuNum {
+    // gauss-jordan on a column major matrix
+    let dim = m.mat_dim();
+    let mut a = [[0.0f32; 8]; 4];
+    for row in 0..dim {
+        for col in 0..dim {
+            a[row][col] = m.v[col * dim + row];
+        }
+        a[row][dim + row] = 1.0;
+    }
+    for col in 0..dim {
+        let mut pivot = col;
+        for row in col + 1..dim {
+            if a[row][col].abs() > a[pivot][col].abs() {
+                pivot = row;
+            }
+        }
+        if a[pivot][col] == 0.0 {
+            return CpuNum::new(CpuNumKind::Mat, m.len)
+        }
+        a.swap(col, pivot);
+        let p = a[col][col];
+        for k in 0..dim * 2 {
+            a[col][k] /= p;
+        }
+        for row in 0..dim {
+            if row != col {
+                let f = a[row][col];
+                for k in 0..dim * 2 {
+                    a[row][k] -= f * a[col][k];
+                }
+            }
+        }
+    }
+    let mut out = CpuNum::new(CpuNumKind::Mat, m.len);
+    for row in 0..dim {
+        for col in 0..dim {
+            out.v[col * dim + row] = a[row][dim + col];
+        }
+    }
+    out
+}
@@ -0,0 +1,102 @@
+use makepad_shader_compiler::makepad_live_compiler::*;
+use makepad_shader_compiler::makepad_live_id::*;
+use makepad_shader_compiler::makepad_math::*;
+use makepad_shader_compiler::shader_registry::ShaderRegistry;
+use makepad_shader_compiler::shader_ast::DrawShaderPtr;
+use makepad_shader_compiler::cpu_interpreter::*;
+
+const SOURCE: &'static str = r#"
+    DrawTest = {{DrawTest}} {
+        instance tint: #ff8000
+        instance scale: 2.0
+        varying uv: vec2
+
+        fn fade(self, x: float) -> float {
+            if x > 0.5 {
+                return 1.0;
+            }
+            return x * 2.0;
+        }
+
+        fn vertex(self) -> vec4 {
+            self.uv = vec2(0.25, 0.75);
+            return vec4(self.uv * self.scale, 0.0, 1.0);
+        }
+
+        fn pixel(self) -> vec4 {
+            let c = mix(self.tint, vec4(0.0, 0.0, 1.0, 1.0), self.uv.x);
+            let sum = 0.0;
+            for i in 0..4 {
+                sum += 0.125;
+            }
+            return vec4(c.rgb * self.uv.y, self.fade(self.uv.x) * sum);
+        }
+    }
+"#;
+
+struct DrawTest();
+
+struct NoTextures();
+
+impl CpuTextureSampler for NoTextures {
+    fn sample_2d(&self, _slot: usize, _pos: Vec2) -> Vec4 {
+        Vec4::default()
+    }
+}
+
+fn assert_close(a: Vec4, b: Vec4) {
+    for (a, b) in [(a.x, b.x), (a.y, b.y), (a.z, b.z), (a.w, b.w)] {
+        assert!((a - b).abs() < 1e-5, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn vertex_and_pixel() {
+    let module_id = LiveModuleId::from_str("test").unwrap();
+    let mut live_registry = LiveRegistry::default();
+    let result = live_registry.register_live_file("test.rs", "", module_id, SOURCE.to_string(), vec![LiveTypeInfo {
+        live_type: LiveType::of::<DrawTest>(),
+        type_name: live_id!(DrawTest),
+        module_id,
+        live_ignore: false,
+        fields: Vec::new()
+    }], TextPos::default());
+    if let Err(err) = result {
+        panic!("{}", err);
+    }
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty());
+
+    let shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(DrawTest)).unwrap());
+    let mut shader_registry = ShaderRegistry::new(false);
+    if let Err(err) = shader_registry.analyse_draw_shader(&live_registry, shader_ptr, | _, _, _, _, _, _ | {}) {
+        panic!("{}", live_registry.live_error_to_live_file_error(err));
+    }
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&shader_ptr).unwrap();
+    let const_table = shader_registry.compute_const_table(shader_ptr);
+    let cpu_shader = CpuDrawShader::new(draw_shader_def, &shader_registry).unwrap();
+
+    let env = CpuShaderEnv {
+        registry: &shader_registry,
+        const_table: &const_table,
+        pass_uniforms: &[],
+        view_uniforms: &[],
+        draw_uniforms: &[],
+        user_uniforms: &[],
+        live_uniforms: &[],
+        sampler: &NoTextures(),
+    };
+
+    // instance fields are packed in declaration order, the same as on the gpu
+    let instance = [1.0, 0.5, 0.0, 1.0, 3.0];
+    let mut varyings = Vec::new();
+    let position = cpu_shader.vertex(&env, &[], &instance, &mut varyings);
+    assert_close(position, vec4(0.75, 2.25, 0.0, 1.0));
+    assert_eq!(varyings.len(), cpu_shader.varying_slots);
+
+    // mix(tint, blue, 0.25) * 0.75, with an alpha of fade(0.25) * 0.5
+    let mut trace = Vec::new();
+    let color = cpu_shader.pixel(&env, &varyings, None, &mut trace);
+    assert_close(color, vec4(0.5625, 0.28125, 0.1875, 0.25));
+}
@@ -0,0 +1,286 @@
+use {
+    std::collections::BTreeMap,
+    crate::{
+        makepad_math::Rect,
+        area::Area,
+        cx::Cx,
+        event::Event,
+        window::WindowId,
+    }
+};
+
+// The accessibility tree is rebuilt from what the widgets report while drawing.
+// Cx keeps the last tree around and diffs each new one against it, the os
+// backends turn the resulting updates into events for their screen reader api.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AccessibilityId(pub u64);
+
+impl AccessibilityId {
+    /// Windows don't have a widget uid, this keeps them out of the way of heap addresses.
+    pub fn from_window_id(window_id: WindowId) -> Self {
+        Self((1 << 63) | window_id.id() as u64)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AccessibilityRole {
+    Window,
+    #[default]
+    Group,
+    Button,
+    CheckBox,
+    Slider,
+    TextInput,
+    Label,
+    List,
+    ListItem,
+    TabList,
+    Tab,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessibilityState {
+    Focusable,
+    Focused,
+    Checked,
+    Selected,
+    Editable,
+    Disabled,
+}
+
+impl AccessibilityState {
+    pub const ALL: [AccessibilityState; 6] = [
+        Self::Focusable,
+        Self::Focused,
+        Self::Checked,
+        Self::Selected,
+        Self::Editable,
+        Self::Disabled,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AccessibilityStates(u32);
+
+impl AccessibilityStates {
+    pub fn with(self, state: AccessibilityState, on: bool) -> Self {
+        let bit = 1 << state as u32;
+        Self(if on {self.0 | bit} else {self.0 & !bit})
+    }
+
+    pub fn contains(&self, state: AccessibilityState) -> bool {
+        self.0 & (1 << state as u32) != 0
+    }
+}
+
+/// What a screen reader can ask a node to do. The advertised actions of a node
+/// never list `SetValue`, nodes with a `numeric_value` accept it implicitly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessibilityAction {
+    Click,
+    Focus,
+    Increment,
+    Decrement,
+    SetValue(f64),
+}
+
+impl AccessibilityAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Click => "click",
+            Self::Focus => "focus",
+            Self::Increment => "increment",
+            Self::Decrement => "decrement",
+            Self::SetValue(_) => "set-value",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AccessibilityNumericValue {
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+    pub current: f64,
+}
+
+/// What a widget reports about itself through `Widget::accessibility`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccessibilityNode {
+    pub role: AccessibilityRole,
+    pub name: String,
+    pub value: String,
+    pub numeric_value: Option<AccessibilityNumericValue>,
+    pub states: AccessibilityStates,
+    pub actions: Vec<AccessibilityAction>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessibilityTreeNode {
+    pub id: AccessibilityId,
+    pub parent: Option<AccessibilityId>,
+    pub children: Vec<AccessibilityId>,
+    pub node: AccessibilityNode,
+    pub window_id: WindowId,
+    pub area: Area,
+    /// In logical pixels relative to the window.
+    pub rect: Rect,
+    /// In physical pixels relative to the screen.
+    pub screen_rect: Rect,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccessibilityTree {
+    pub roots: Vec<AccessibilityId>,
+    pub nodes: BTreeMap<AccessibilityId, AccessibilityTreeNode>,
+    pub focus: Option<AccessibilityId>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccessibilityUpdate {
+    Added {id: AccessibilityId, parent: Option<AccessibilityId>, index: usize},
+    Removed {id: AccessibilityId, parent: Option<AccessibilityId>, index: usize},
+    NameChanged(AccessibilityId),
+    ValueChanged(AccessibilityId),
+    StateChanged {id: AccessibilityId, state: AccessibilityState, on: bool},
+}
+
+#[derive(Clone, Debug)]
+pub struct AccessibilityActionEvent {
+    pub id: AccessibilityId,
+    pub action: AccessibilityAction,
+}
+
+impl AccessibilityTree {
+    pub fn index_in_parent(&self, id: AccessibilityId) -> usize {
+        let siblings = match self.nodes.get(&id).and_then( | node | node.parent) {
+            Some(parent) => &self.nodes[&parent].children,
+            None => &self.roots
+        };
+        siblings.iter().position( | child | *child == id).unwrap_or(0)
+    }
+
+    pub fn node_at_area(&self, area: Area) -> Option<AccessibilityId> {
+        if area.is_empty() {
+            return None
+        }
+        self.nodes.values().find( | node | node.area == area).map( | node | node.id)
+    }
+
+    /// Appends the updates that turn `self` into `new`. Subtrees are reported
+    /// once at their top node.
+    pub fn diff(&self, new: &AccessibilityTree, updates: &mut Vec<AccessibilityUpdate>) {
+        for (id, old) in &self.nodes {
+            if new.nodes.contains_key(id) {
+                continue
+            }
+            if old.parent.is_none_or( | parent | new.nodes.contains_key(&parent)) {
+                updates.push(AccessibilityUpdate::Removed {id: *id, parent: old.parent, index: self.index_in_parent(*id)});
+            }
+        }
+        for (id, node) in &new.nodes {
+            let Some(old) = self.nodes.get(id) else {
+                if node.parent.is_none_or( | parent | self.nodes.contains_key(&parent)) {
+                    updates.push(AccessibilityUpdate::Added {id: *id, parent: node.parent, index: new.index_in_parent(*id)});
+                }
+                continue
+            };
+            if old.node.name != node.node.name {
+                updates.push(AccessibilityUpdate::NameChanged(*id));
+            }
+            if old.node.value != node.node.value || old.node.numeric_value != node.node.numeric_value {
+                updates.push(AccessibilityUpdate::ValueChanged(*id));
+            }
+            for state in AccessibilityState::ALL {
+                let on = node.node.states.contains(state);
+                if old.node.states.contains(state) != on {
+                    updates.push(AccessibilityUpdate::StateChanged {id: *id, state, on});
+                }
+            }
+        }
+    }
+
+    fn set_focus(&mut self, focus: Option<AccessibilityId>) {
+        if let Some(node) = self.focus.and_then( | id | self.nodes.get_mut(&id)) {
+            node.node.states = node.node.states.with(AccessibilityState::Focused, false);
+        }
+        if let Some(node) = focus.and_then( | id | self.nodes.get_mut(&id)) {
+            node.node.states = node.node.states.with(AccessibilityState::Focused, true);
+        }
+        self.focus = focus;
+    }
+}
+
+#[derive(Default)]
+pub struct CxAccessibility {
+    pub (crate) enabled: bool,
+    pub (crate) tree: AccessibilityTree,
+    pub (crate) updates: Vec<AccessibilityUpdate>,
+}
+
+impl Cx {
+    /// Only true while an assistive technology listens, drawing skips the
+    /// accessibility bookkeeping otherwise.
+    pub fn accessibility_enabled(&self) -> bool {
+        self.accessibility.enabled
+    }
+
+    pub fn set_accessibility_enabled(&mut self, enabled: bool) {
+        if self.accessibility.enabled != enabled {
+            self.accessibility.enabled = enabled;
+            self.accessibility.tree = Default::default();
+            self.accessibility.updates.clear();
+            // cached draw lists have no accessibility items yet
+            self.redraw_all();
+        }
+    }
+
+    pub fn accessibility_tree(&self) -> &AccessibilityTree {
+        &self.accessibility.tree
+    }
+
+    /// Replaces the retained tree with one built during drawing and queues the differences.
+    pub fn update_accessibility_tree(&mut self, mut tree: AccessibilityTree) {
+        for node in tree.nodes.values_mut() {
+            let geom = &self.windows[node.window_id].window_geom;
+            let dpi = geom.dpi_factor;
+            node.screen_rect = Rect {
+                pos: (geom.position + node.rect.pos) * dpi,
+                size: node.rect.size * dpi
+            };
+        }
+        let focus = tree.node_at_area(self.keyboard.key_focus);
+        tree.set_focus(focus);
+        self.accessibility.tree.diff(&tree, &mut self.accessibility.updates);
+        self.accessibility.tree = tree;
+    }
+
+    pub (crate) fn update_accessibility_focus(&mut self) {
+        if !self.accessibility.enabled {
+            return
+        }
+        let focus = self.accessibility.tree.node_at_area(self.keyboard.key_focus);
+        if focus == self.accessibility.tree.focus {
+            return
+        }
+        let mut tree = self.accessibility.tree.clone();
+        tree.set_focus(focus);
+        self.accessibility.tree.diff(&tree, &mut self.accessibility.updates);
+        self.accessibility.tree = tree;
+    }
+
+    pub fn take_accessibility_updates(&mut self) -> Vec<AccessibilityUpdate> {
+        std::mem::take(&mut self.accessibility.updates)
+    }
+}
+
+impl Event {
+    /// The action a screen reader requested from the node `id`, if this event is one.
+    pub fn accessibility_action(&self, id: AccessibilityId) -> Option<AccessibilityAction> {
+        match self {
+            Event::Accessibility(e) if e.id == id => Some(e.action),
+            _ => None
+        }
+    }
+}
@@ -22,6 +22,7 @@ use {
         os::{CxOs},
         debug::Debug,
         performance_stats::PerformanceStats,
+        accessibility::CxAccessibility,
         event::{
             DrawEvent,
             CxFingers,
@@ -120,6 +121,8 @@ pub struct Cx {
     pub(crate) studio_http: String,
     
     pub performance_stats: PerformanceStats,
+    
+    pub (crate) accessibility: CxAccessibility,
 }
 
 #[derive(Clone)]
@@ -173,6 +176,7 @@ pub enum OsType {
     OpenHarmony(OpenHarmonyParams),
     LinuxWindow (LinuxWindowParams),
     LinuxDirect,
+    LinuxHeadless,
     Web(WebParams)
 }
 
@@ -189,6 +193,7 @@ impl OsType {
             OsType::Ios=>true,
             OsType::Android(_) => true,
             OsType::LinuxDirect=> true,
+            OsType::LinuxHeadless=> true,
             _=> false
         }
     }
@@ -295,6 +300,7 @@ impl Cx {
 
             self_ref: None,
             performance_stats: Default::default(),
+            accessibility: Default::default(),
         }
     }
 }
\ No newline at end of file
@@ -24,6 +24,8 @@ use {
         midi::MidiPortsEvent,
         video::VideoInputsEvent,
         draw_list::DrawListId,
+        file_dialogs::FileDialogEvent,
+        accessibility::AccessibilityActionEvent,
     },
 };
 
@@ -180,6 +182,9 @@ pub enum Event {
     ToWasmMsg(ToWasmMsgEvent),
     
     DesignerPick(DesignerPickEvent),
+    FileDialog(FileDialogEvent),
+    /// A screen reader asks the node with this id to act.
+    Accessibility(AccessibilityActionEvent),
 }
 
 impl Event{
@@ -253,7 +258,9 @@ impl Event{
             #[cfg(target_arch = "wasm32")]
             51=>"ToWasmMsg",
             
-            52=>"DesignerPick",            
+            52=>"DesignerPick",
+            53=>"FileDialog",
+            54=>"Accessibility",
             _=>panic!()
         }
     }
@@ -325,6 +332,8 @@ impl Event{
             Self::ToWasmMsg(_)=>51,
             
             Self::DesignerPick(_) =>52,
+            Self::FileDialog(_) =>53,
+            Self::Accessibility(_) =>54,
         }
     }
 }
@@ -93,3 +93,25 @@ impl Default for FileDialog {
     }
 }
 
+/// Which of the `Cx::open_system_*_dialog` calls a `FileDialogEvent` answers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileDialogKind {
+    SaveFile,
+    SelectFile,
+    SaveFolder,
+    SelectFolder,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileDialogResponse {
+    Selected(Vec<PathBuf>),
+    Cancelled,
+    Error(String),
+}
+
+/// Sent when a system file dialog closes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileDialogEvent {
+    pub kind: FileDialogKind,
+    pub response: FileDialogResponse,
+}
@@ -51,6 +51,8 @@ pub mod audio_stream;
 
 pub mod file_dialogs;
 
+pub mod accessibility;
+
 mod media_api;
 
 #[macro_use]
@@ -77,7 +79,7 @@ pub use {
     makepad_shader_compiler::makepad_live_id,
     smallvec,
     smallvec::SmallVec,
-    //makepad_image_formats::image,
+    makepad_image_formats,
     makepad_derive_live::*,
     log::*,
     makepad_math::*,
@@ -151,7 +153,7 @@ pub use {
         audio::*,
         thread::*,
         video::*,
-        web_socket::{WebSocket,WebSocketMessage},
+        web_socket::{WebSocket,WebSocketMessage,WebSocketCloseReason},
         event::{
             VirtualKeyboardEvent,
             HttpRequest,
@@ -217,10 +219,29 @@ pub use {
             ActionTrait,
             ActionDefaultRef
         },
+        file_dialogs::{
+            FileDialog,
+            FileDialogKind,
+            FileDialogResponse,
+            FileDialogEvent,
+        },
+        accessibility::{
+            AccessibilityId,
+            AccessibilityRole,
+            AccessibilityState,
+            AccessibilityStates,
+            AccessibilityAction,
+            AccessibilityActionEvent,
+            AccessibilityNumericValue,
+            AccessibilityNode,
+            AccessibilityTreeNode,
+            AccessibilityTree,
+            AccessibilityUpdate,
+        },
         cursor::MouseCursor,
         macos_menu::MacosMenu,
         draw_matrix::DrawMatrix,
-        window::{WindowHandle,CxWindowPool},
+        window::{WindowHandle,WindowId,CxWindowPool},
         pass::{
             PassId,
             CxPassParent,
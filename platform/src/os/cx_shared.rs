@@ -139,6 +139,7 @@ impl Cx {
     
     fn inner_key_focus_change(&mut self) {
         if let Some((prev, focus)) = self.keyboard.cycle_key_focus_changed(){
+            self.update_accessibility_focus();
             self.inner_call_event_handler(&Event::KeyFocus(KeyFocusEvent {
                 prev,
                 focus
@@ -61,7 +61,7 @@ use {
         gpu_info::GpuPerformance,
         os::cx_native::EventFlow,
         pass::{PassClearColor, PassClearDepth, PassId},
-        web_socket::WebSocketMessage,
+        web_socket::{WebSocketMessage, WebSocketCloseReason},
     },
     makepad_http::websocket::ServerWebSocket as WebSocketImpl,
     makepad_http::websocket::ServerWebSocketMessage as WebSocketMessageImpl
@@ -336,7 +336,7 @@ impl Cx {
             }
             FromJavaMessage::WebSocketClosed {sender} => {
                 self.os.websocket_parsers.remove(&sender.0);
-                let message = WebSocketMessage::Closed;
+                let message = WebSocketMessage::Closed(WebSocketCloseReason::no_status());
                 sender.1.send(message).ok();
             }
             FromJavaMessage::WebSocketError {error, sender} => {
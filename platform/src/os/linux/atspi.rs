@@ -0,0 +1,623 @@
+use {
+    std::sync::{
+        Arc, Mutex,
+        mpsc::{channel, Sender, Receiver},
+    },
+    self::super::dbus::{DBusConnection, DBusMessage, DBusSender, DBusValue},
+    crate::{
+        makepad_math::{Rect, DVec2},
+        thread::SignalToUI,
+        accessibility::{
+            AccessibilityId,
+            AccessibilityRole,
+            AccessibilityState,
+            AccessibilityAction,
+            AccessibilityActionEvent,
+            AccessibilityTree,
+            AccessibilityTreeNode,
+            AccessibilityUpdate,
+        },
+    }
+};
+
+// Exports the accessibility tree over AT-SPI2, the D-Bus protocol Orca and the
+// other Linux assistive technologies use. A bridge thread owns the connection
+// to the accessibility bus and answers queries from a copy of the tree, the ui
+// thread only publishes new trees and emits the change signals.
+
+const STATUS_INTERFACE: &str = "org.a11y.Status";
+const ROOT_PATH: &str = "/org/a11y/atspi/accessible/root";
+const NODE_PATH_PREFIX: &str = "/org/a11y/atspi/accessible/";
+const NULL_PATH: &str = "/org/a11y/atspi/null";
+
+const ACCESSIBLE_INTERFACE: &str = "org.a11y.atspi.Accessible";
+const APPLICATION_INTERFACE: &str = "org.a11y.atspi.Application";
+const ACTION_INTERFACE: &str = "org.a11y.atspi.Action";
+const VALUE_INTERFACE: &str = "org.a11y.atspi.Value";
+const COMPONENT_INTERFACE: &str = "org.a11y.atspi.Component";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const OBJECT_EVENT_INTERFACE: &str = "org.a11y.atspi.Event.Object";
+
+// AtspiRole values
+const ROLE_CHECK_BOX: u32 = 7;
+const ROLE_FRAME: u32 = 23;
+const ROLE_LABEL: u32 = 29;
+const ROLE_LIST: u32 = 31;
+const ROLE_LIST_ITEM: u32 = 32;
+const ROLE_PAGE_TAB: u32 = 37;
+const ROLE_PAGE_TAB_LIST: u32 = 38;
+const ROLE_PANEL: u32 = 39;
+const ROLE_PUSH_BUTTON: u32 = 43;
+const ROLE_SLIDER: u32 = 51;
+const ROLE_TEXT: u32 = 61;
+const ROLE_APPLICATION: u32 = 75;
+
+// AtspiStateType bit indices
+const STATE_ACTIVE: u32 = 1;
+const STATE_CHECKED: u32 = 4;
+const STATE_EDITABLE: u32 = 7;
+const STATE_ENABLED: u32 = 8;
+const STATE_FOCUSABLE: u32 = 11;
+const STATE_FOCUSED: u32 = 12;
+const STATE_HORIZONTAL: u32 = 14;
+const STATE_SELECTABLE: u32 = 22;
+const STATE_SELECTED: u32 = 23;
+const STATE_SENSITIVE: u32 = 24;
+const STATE_SHOWING: u32 = 25;
+const STATE_SINGLE_LINE: u32 = 26;
+const STATE_VISIBLE: u32 = 30;
+const STATE_CHECKABLE: u32 = 41;
+const STATE_READ_ONLY: u32 = 43;
+
+// AtspiCoordType and AtspiComponentLayer
+const COORD_TYPE_WINDOW: u32 = 1;
+const COORD_TYPE_PARENT: u32 = 2;
+const LAYER_WIDGET: u32 = 3;
+const LAYER_WINDOW: u32 = 7;
+
+pub enum LinuxAccessibilityEvent {
+    /// An assistive technology is listening, the tree should be built from now on.
+    Enabled,
+    Action(AccessibilityActionEvent),
+}
+
+#[derive(Default)]
+struct AtspiState {
+    app_name: String,
+    unique_name: String,
+    desktop: Option<(String, String)>,
+    app_id: i32,
+    tree: AccessibilityTree,
+}
+
+pub struct LinuxAccessibility {
+    sender: Sender<LinuxAccessibilityEvent>,
+    receiver: Receiver<LinuxAccessibilityEvent>,
+    state: Arc<Mutex<AtspiState>>,
+    bus: Arc<Mutex<Option<DBusSender>>>,
+    started: bool,
+}
+
+impl Default for LinuxAccessibility {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender,
+            receiver,
+            state: Default::default(),
+            bus: Default::default(),
+            started: false,
+        }
+    }
+}
+
+impl LinuxAccessibility {
+    /// Starts the bridge thread. It waits until the desktop reports assistive
+    /// technologies as enabled, or starts right away with `MAKEPAD_ACCESSIBILITY=1`.
+    pub fn start(&mut self) {
+        if self.started {
+            return
+        }
+        self.started = true;
+        self.state.lock().unwrap().app_name = std::env::current_exe().ok()
+            .and_then( | exe | exe.file_stem().map( | name | name.to_string_lossy().to_string()))
+            .unwrap_or_default();
+        let state = self.state.clone();
+        let bus = self.bus.clone();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            // most desktops without an accessibility bus simply end up here
+            let _ = run_bridge(state, bus.clone(), sender);
+            *bus.lock().unwrap() = None;
+        });
+    }
+
+    pub fn try_recv(&self) -> Option<LinuxAccessibilityEvent> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Hands a new tree to the bridge thread and tells listeners what changed.
+    pub fn publish(&mut self, tree: &AccessibilityTree, updates: Vec<AccessibilityUpdate>) {
+        let Some(bus) = self.bus.lock().unwrap().clone() else {
+            return
+        };
+        let mut state = self.state.lock().unwrap();
+        state.tree = tree.clone();
+        for update in updates {
+            let (path, member, detail, detail1, data) = match update {
+                AccessibilityUpdate::Added {id, parent, index} => (
+                    node_path(parent),
+                    "ChildrenChanged",
+                    "add",
+                    index as i32,
+                    state.object_ref(Some(id)),
+                ),
+                AccessibilityUpdate::Removed {id, parent, index} => (
+                    node_path(parent),
+                    "ChildrenChanged",
+                    "remove",
+                    index as i32,
+                    state.object_ref(Some(id)),
+                ),
+                AccessibilityUpdate::NameChanged(id) => (
+                    node_path(Some(id)),
+                    "PropertyChange",
+                    "accessible-name",
+                    0,
+                    DBusValue::String(state.tree.nodes[&id].node.name.clone()),
+                ),
+                AccessibilityUpdate::ValueChanged(id) => {
+                    let node = &state.tree.nodes[&id].node;
+                    let value = match node.numeric_value {
+                        Some(numeric) => DBusValue::Double(numeric.current),
+                        None => DBusValue::String(node.value.clone())
+                    };
+                    (node_path(Some(id)), "PropertyChange", "accessible-value", 0, value)
+                }
+                AccessibilityUpdate::StateChanged {id, state: changed, on} => {
+                    let (detail, on) = match changed {
+                        AccessibilityState::Focusable => ("focusable", on),
+                        AccessibilityState::Focused => ("focused", on),
+                        AccessibilityState::Checked => ("checked", on),
+                        AccessibilityState::Selected => ("selected", on),
+                        AccessibilityState::Editable => ("editable", on),
+                        AccessibilityState::Disabled => ("enabled", !on),
+                    };
+                    (node_path(Some(id)), "StateChanged", detail, on as i32, DBusValue::Int32(0))
+                }
+            };
+            let _ = bus.send(DBusMessage::signal(&path, OBJECT_EVENT_INTERFACE, member, vec![
+                DBusValue::string(detail),
+                DBusValue::Int32(detail1),
+                DBusValue::Int32(0),
+                DBusValue::variant(data),
+                DBusValue::dict(vec![]),
+            ]));
+        }
+    }
+}
+
+fn run_bridge(state: Arc<Mutex<AtspiState>>, bus_sender: Arc<Mutex<Option<DBusSender>>>, sender: Sender<LinuxAccessibilityEvent>) -> Result<(), String> {
+    let mut session = DBusConnection::session()?;
+    let forced = std::env::var("MAKEPAD_ACCESSIBILITY").map_or(false, | v | v == "1");
+    if !forced {
+        session.add_match(&format!(
+            "type='signal',interface='{}',member='PropertiesChanged',path='/org/a11y/bus'",
+            PROPERTIES_INTERFACE
+        ))?;
+        let reply = session.call(DBusMessage::method_call(
+            "org.a11y.Bus",
+            "/org/a11y/bus",
+            PROPERTIES_INTERFACE,
+            "Get",
+            vec![DBusValue::string(STATUS_INTERFACE), DBusValue::string("IsEnabled")]
+        ))?;
+        let mut enabled = reply.body.first().and_then( | v | v.as_bool()).unwrap_or(false);
+        while !enabled {
+            let message = session.read_message()?;
+            if message.is_signal(PROPERTIES_INTERFACE, "PropertiesChanged") &&
+                message.body.first().and_then( | v | v.as_str()) == Some(STATUS_INTERFACE) {
+                enabled = message.body.get(1).and_then( | v | v.dict_get("IsEnabled")).and_then( | v | v.as_bool()).unwrap_or(false);
+            }
+        }
+    }
+
+    let reply = session.call(DBusMessage::method_call(
+        "org.a11y.Bus",
+        "/org/a11y/bus",
+        "org.a11y.Bus",
+        "GetAddress",
+        vec![]
+    ))?;
+    let address = reply.body.first().and_then( | v | v.as_str()).ok_or("No accessibility bus address")?.to_string();
+    let mut bus = DBusConnection::connect(&address)?;
+    state.lock().unwrap().unique_name = bus.unique_name().to_string();
+
+    let reply = bus.call(DBusMessage::method_call(
+        "org.a11y.atspi.Registry",
+        ROOT_PATH,
+        "org.a11y.atspi.Socket",
+        "Embed",
+        vec![DBusValue::Struct(vec![
+            DBusValue::string(bus.unique_name()),
+            DBusValue::ObjectPath(ROOT_PATH.to_string()),
+        ])]
+    ))?;
+    if let Some(DBusValue::Struct(desktop)) = reply.body.first() {
+        if let (Some(name), Some(path)) = (desktop.first().and_then( | v | v.as_str()), desktop.get(1).and_then( | v | v.as_str())) {
+            state.lock().unwrap().desktop = Some((name.to_string(), path.to_string()));
+        }
+    }
+
+    *bus_sender.lock().unwrap() = Some(bus.sender());
+    let _ = sender.send(LinuxAccessibilityEvent::Enabled);
+    SignalToUI::set_ui_signal();
+
+    loop {
+        let message = bus.read_message()?;
+        if message.message_type != super::dbus::DBusMessageType::MethodCall {
+            continue
+        }
+        let (reply, action) = state.lock().unwrap().handle_method_call(&message);
+        if let Some(action) = action {
+            let _ = sender.send(LinuxAccessibilityEvent::Action(action));
+            SignalToUI::set_ui_signal();
+        }
+        bus.send(reply)?;
+    }
+}
+
+fn node_path(id: Option<AccessibilityId>) -> String {
+    match id {
+        Some(id) => format!("{}{:x}", NODE_PATH_PREFIX, id.0),
+        None => ROOT_PATH.to_string()
+    }
+}
+
+fn path_to_id(path: &str) -> Option<AccessibilityId> {
+    let hex = path.strip_prefix(NODE_PATH_PREFIX)?;
+    u64::from_str_radix(hex, 16).ok().map(AccessibilityId)
+}
+
+fn extents(rect: Rect) -> DBusValue {
+    DBusValue::Struct(vec![
+        DBusValue::Int32(rect.pos.x as i32),
+        DBusValue::Int32(rect.pos.y as i32),
+        DBusValue::Int32(rect.size.x as i32),
+        DBusValue::Int32(rect.size.y as i32),
+    ])
+}
+
+fn unknown_method(call: &DBusMessage) -> DBusMessage {
+    DBusMessage::error(call, "org.freedesktop.DBus.Error.UnknownMethod", &format!(
+        "Unknown method {}.{}",
+        call.interface.as_deref().unwrap_or(""),
+        call.member.as_deref().unwrap_or("")
+    ))
+}
+
+/// Which object a call addresses, the application root or one of the nodes.
+#[derive(Clone, Copy)]
+enum AtspiObject<'a> {
+    Root,
+    Node(&'a AccessibilityTreeNode),
+}
+
+impl AtspiState {
+    fn object_ref(&self, id: Option<AccessibilityId>) -> DBusValue {
+        DBusValue::Struct(vec![
+            DBusValue::string(&self.unique_name),
+            DBusValue::ObjectPath(node_path(id)),
+        ])
+    }
+
+    fn null_ref(&self) -> DBusValue {
+        DBusValue::Struct(vec![
+            DBusValue::string(""),
+            DBusValue::ObjectPath(NULL_PATH.to_string()),
+        ])
+    }
+
+    fn children<'a>(&'a self, object: AtspiObject<'a>) -> &'a [AccessibilityId] {
+        match object {
+            AtspiObject::Root => &self.tree.roots,
+            AtspiObject::Node(node) => &node.children,
+        }
+    }
+
+    fn interfaces(&self, object: AtspiObject) -> Vec<&'static str> {
+        match object {
+            AtspiObject::Root => vec![ACCESSIBLE_INTERFACE, APPLICATION_INTERFACE],
+            AtspiObject::Node(node) => {
+                let mut interfaces = vec![ACCESSIBLE_INTERFACE, COMPONENT_INTERFACE];
+                if node.node.actions.len() > 0 {
+                    interfaces.push(ACTION_INTERFACE);
+                }
+                if node.node.numeric_value.is_some() {
+                    interfaces.push(VALUE_INTERFACE);
+                }
+                interfaces
+            }
+        }
+    }
+
+    fn role(&self, object: AtspiObject) -> (u32, &'static str) {
+        let AtspiObject::Node(node) = object else {
+            return (ROLE_APPLICATION, "application")
+        };
+        match node.node.role {
+            AccessibilityRole::Window => (ROLE_FRAME, "frame"),
+            AccessibilityRole::Group => (ROLE_PANEL, "panel"),
+            AccessibilityRole::Button => (ROLE_PUSH_BUTTON, "push button"),
+            AccessibilityRole::CheckBox => (ROLE_CHECK_BOX, "check box"),
+            AccessibilityRole::Slider => (ROLE_SLIDER, "slider"),
+            AccessibilityRole::TextInput => (ROLE_TEXT, "text"),
+            AccessibilityRole::Label => (ROLE_LABEL, "label"),
+            AccessibilityRole::List => (ROLE_LIST, "list"),
+            AccessibilityRole::ListItem => (ROLE_LIST_ITEM, "list item"),
+            AccessibilityRole::TabList => (ROLE_PAGE_TAB_LIST, "page tab list"),
+            AccessibilityRole::Tab => (ROLE_PAGE_TAB, "page tab"),
+        }
+    }
+
+    fn state_set(&self, object: AtspiObject) -> DBusValue {
+        let mut bits = 0u64;
+        if let AtspiObject::Node(node) = object {
+            let states = node.node.states;
+            let mut set = | bit: u32 | bits |= 1 << bit;
+            set(STATE_VISIBLE);
+            set(STATE_SHOWING);
+            if !states.contains(AccessibilityState::Disabled) {
+                set(STATE_ENABLED);
+                set(STATE_SENSITIVE);
+            }
+            match node.node.role {
+                AccessibilityRole::Window => set(STATE_ACTIVE),
+                AccessibilityRole::CheckBox => set(STATE_CHECKABLE),
+                AccessibilityRole::Tab | AccessibilityRole::ListItem => set(STATE_SELECTABLE),
+                AccessibilityRole::Slider => set(STATE_HORIZONTAL),
+                AccessibilityRole::TextInput => {
+                    set(STATE_SINGLE_LINE);
+                    if !states.contains(AccessibilityState::Editable) {
+                        set(STATE_READ_ONLY);
+                    }
+                }
+                _ => ()
+            }
+            for (state, bit) in [
+                (AccessibilityState::Focusable, STATE_FOCUSABLE),
+                (AccessibilityState::Focused, STATE_FOCUSED),
+                (AccessibilityState::Checked, STATE_CHECKED),
+                (AccessibilityState::Selected, STATE_SELECTED),
+                (AccessibilityState::Editable, STATE_EDITABLE),
+            ] {
+                if states.contains(state) {
+                    set(bit);
+                }
+            }
+        }
+        DBusValue::Array("u".to_string(), vec![
+            DBusValue::Uint32(bits as u32),
+            DBusValue::Uint32((bits >> 32) as u32),
+        ])
+    }
+
+    fn parent_ref(&self, object: AtspiObject) -> DBusValue {
+        match object {
+            AtspiObject::Root => match &self.desktop {
+                Some((name, path)) => DBusValue::Struct(vec![
+                    DBusValue::string(name),
+                    DBusValue::ObjectPath(path.clone()),
+                ]),
+                None => self.null_ref()
+            },
+            AtspiObject::Node(node) => self.object_ref(node.parent),
+        }
+    }
+
+    fn extents_in(&self, node: &AccessibilityTreeNode, coord_type: u32) -> Rect {
+        let origin = match coord_type {
+            COORD_TYPE_WINDOW => self.tree.roots.iter()
+                .map( | id | &self.tree.nodes[id])
+                .find( | window | window.window_id == node.window_id)
+                .map_or(DVec2::default(), | window | window.screen_rect.pos),
+            COORD_TYPE_PARENT => node.parent
+                .map_or(DVec2::default(), | parent | self.tree.nodes[&parent].screen_rect.pos),
+            _ => DVec2::default()
+        };
+        Rect {pos: node.screen_rect.pos - origin, size: node.screen_rect.size}
+    }
+
+    fn property(&self, object: AtspiObject, interface: &str, name: &str) -> Option<DBusValue> {
+        Some(match (interface, name, object) {
+            (ACCESSIBLE_INTERFACE, "Name", AtspiObject::Root) => DBusValue::string(&self.app_name),
+            (ACCESSIBLE_INTERFACE, "Name", AtspiObject::Node(node)) => DBusValue::string(&node.node.name),
+            (ACCESSIBLE_INTERFACE, "Description", _) => DBusValue::string(""),
+            (ACCESSIBLE_INTERFACE, "Locale", _) => DBusValue::string(""),
+            (ACCESSIBLE_INTERFACE, "Parent", _) => self.parent_ref(object),
+            (ACCESSIBLE_INTERFACE, "ChildCount", _) => DBusValue::Int32(self.children(object).len() as i32),
+            (ACCESSIBLE_INTERFACE, "AccessibleId", AtspiObject::Root) => DBusValue::string(""),
+            (ACCESSIBLE_INTERFACE, "AccessibleId", AtspiObject::Node(node)) => DBusValue::String(format!("{:x}", node.id.0)),
+            (APPLICATION_INTERFACE, "ToolkitName", AtspiObject::Root) => DBusValue::string("makepad"),
+            (APPLICATION_INTERFACE, "Version", AtspiObject::Root) => DBusValue::string(env!("CARGO_PKG_VERSION")),
+            (APPLICATION_INTERFACE, "AtspiVersion", AtspiObject::Root) => DBusValue::string("2.1"),
+            (APPLICATION_INTERFACE, "Id", AtspiObject::Root) => DBusValue::Int32(self.app_id),
+            (ACTION_INTERFACE, "NActions", AtspiObject::Node(node)) => DBusValue::Int32(node.node.actions.len() as i32),
+            (VALUE_INTERFACE, _, AtspiObject::Node(node)) => {
+                let value = node.node.numeric_value?;
+                match name {
+                    "MinimumValue" => DBusValue::Double(value.min),
+                    "MaximumValue" => DBusValue::Double(value.max),
+                    "MinimumIncrement" => DBusValue::Double(value.step),
+                    "CurrentValue" => DBusValue::Double(value.current),
+                    "Text" => DBusValue::string(&node.node.value),
+                    _ => return None
+                }
+            }
+            _ => return None
+        })
+    }
+
+    fn property_names(interface: &str) -> &'static [&'static str] {
+        match interface {
+            ACCESSIBLE_INTERFACE => &["Name", "Description", "Locale", "Parent", "ChildCount", "AccessibleId"],
+            APPLICATION_INTERFACE => &["ToolkitName", "Version", "AtspiVersion", "Id"],
+            ACTION_INTERFACE => &["NActions"],
+            VALUE_INTERFACE => &["MinimumValue", "MaximumValue", "MinimumIncrement", "CurrentValue", "Text"],
+            _ => &[]
+        }
+    }
+
+    /// Answers one call from the bus, a few of them also turn into an action for the ui thread.
+    fn handle_method_call(&mut self, call: &DBusMessage) -> (DBusMessage, Option<AccessibilityActionEvent>) {
+        let path = call.path.as_deref().unwrap_or("");
+        let object = if path == ROOT_PATH {
+            AtspiObject::Root
+        }
+        else if let Some(node) = path_to_id(path).and_then( | id | self.tree.nodes.get(&id)) {
+            AtspiObject::Node(node)
+        }
+        else {
+            return (DBusMessage::error(call, "org.freedesktop.DBus.Error.UnknownObject", &format!("Unknown object {}", path)), None)
+        };
+        let interface = call.interface.as_deref().unwrap_or("");
+        let member = call.member.as_deref().unwrap_or("");
+        let arg = | index: usize | call.body.get(index);
+        let action = | id: AccessibilityId, action: AccessibilityAction | Some(AccessibilityActionEvent {id, action});
+
+        let body = match (interface, member) {
+            (PROPERTIES_INTERFACE, "Get") => {
+                let (Some(interface), Some(name)) = (arg(0).and_then( | v | v.as_str()), arg(1).and_then( | v | v.as_str())) else {
+                    return (unknown_method(call), None)
+                };
+                match self.property(object, interface, name) {
+                    Some(value) => vec![DBusValue::variant(value)],
+                    None => return (DBusMessage::error(call, "org.freedesktop.DBus.Error.UnknownProperty", name), None)
+                }
+            }
+            (PROPERTIES_INTERFACE, "GetAll") => {
+                let interface = arg(0).and_then( | v | v.as_str()).unwrap_or("");
+                let entries = Self::property_names(interface).iter().filter_map( | name | {
+                    self.property(object, interface, name).map( | value | (*name, value))
+                }).collect();
+                vec![DBusValue::dict(entries)]
+            }
+            (PROPERTIES_INTERFACE, "Set") => {
+                let interface = arg(0).and_then( | v | v.as_str()).unwrap_or("");
+                let name = arg(1).and_then( | v | v.as_str()).unwrap_or("");
+                match (interface, name, object) {
+                    (APPLICATION_INTERFACE, "Id", AtspiObject::Root) => {
+                        self.app_id = arg(2).and_then( | v | v.as_i32()).unwrap_or(0);
+                        return (DBusMessage::method_return(call, vec![]), None)
+                    }
+                    (VALUE_INTERFACE, "CurrentValue", AtspiObject::Node(node)) => {
+                        let value = arg(2).and_then( | v | v.as_f64()).unwrap_or(0.0);
+                        return (DBusMessage::method_return(call, vec![]), action(node.id, AccessibilityAction::SetValue(value)))
+                    }
+                    _ => return (DBusMessage::error(call, "org.freedesktop.DBus.Error.PropertyReadOnly", name), None)
+                }
+            }
+            (ACCESSIBLE_INTERFACE, "GetChildAtIndex") => {
+                let index = arg(0).and_then( | v | v.as_i32()).unwrap_or(-1);
+                match self.children(object).get(index.max(0) as usize) {
+                    Some(child) if index >= 0 => vec![self.object_ref(Some(*child))],
+                    _ => vec![self.null_ref()]
+                }
+            }
+            (ACCESSIBLE_INTERFACE, "GetChildren") => {
+                vec![DBusValue::Array("(so)".to_string(), self.children(object).iter().map( | id | self.object_ref(Some(*id))).collect())]
+            }
+            (ACCESSIBLE_INTERFACE, "GetIndexInParent") => vec![DBusValue::Int32(match object {
+                AtspiObject::Root => -1,
+                AtspiObject::Node(node) => self.tree.index_in_parent(node.id) as i32
+            })],
+            (ACCESSIBLE_INTERFACE, "GetRelationSet") => vec![DBusValue::Array("(ua(so))".to_string(), vec![])],
+            (ACCESSIBLE_INTERFACE, "GetRole") => vec![DBusValue::Uint32(self.role(object).0)],
+            (ACCESSIBLE_INTERFACE, "GetRoleName") | (ACCESSIBLE_INTERFACE, "GetLocalizedRoleName") => {
+                vec![DBusValue::string(self.role(object).1)]
+            }
+            (ACCESSIBLE_INTERFACE, "GetState") => vec![self.state_set(object)],
+            (ACCESSIBLE_INTERFACE, "GetAttributes") => {
+                vec![DBusValue::Array("{ss}".to_string(), vec![DBusValue::DictEntry(
+                    Box::new(DBusValue::string("toolkit")),
+                    Box::new(DBusValue::string("makepad"))
+                )])]
+            }
+            (ACCESSIBLE_INTERFACE, "GetApplication") => vec![self.object_ref(None)],
+            (ACCESSIBLE_INTERFACE, "GetInterfaces") => {
+                vec![DBusValue::Array("s".to_string(), self.interfaces(object).into_iter().map(DBusValue::string).collect())]
+            }
+            (APPLICATION_INTERFACE, "GetLocale") => vec![DBusValue::string("")],
+            ("org.freedesktop.DBus.Peer", "Ping") => vec![],
+            (COMPONENT_INTERFACE, _) | (ACTION_INTERFACE, _) => {
+                let AtspiObject::Node(node) = object else {
+                    return (unknown_method(call), None)
+                };
+                return self.handle_node_call(call, node, member)
+            }
+            _ => return (unknown_method(call), None)
+        };
+        (DBusMessage::method_return(call, body), None)
+    }
+
+    fn handle_node_call(&self, call: &DBusMessage, node: &AccessibilityTreeNode, member: &str) -> (DBusMessage, Option<AccessibilityActionEvent>) {
+        let arg_i32 = | index: usize | call.body.get(index).and_then( | v | v.as_i32()).unwrap_or(0);
+        let arg_u32 = | index: usize | call.body.get(index).and_then( | v | v.as_u32()).unwrap_or(0);
+        let action_at = | index: i32 | node.node.actions.get(index.max(0) as usize).filter( | _ | index >= 0);
+        let mut action = None;
+        let body = match member {
+            "Contains" => {
+                let rect = self.extents_in(node, arg_u32(2));
+                vec![DBusValue::Bool(rect.contains(DVec2 {x: arg_i32(0) as f64, y: arg_i32(1) as f64}))]
+            }
+            "GetAccessibleAtPoint" => {
+                // the smallest node under the point is the most specific one
+                let origin = self.extents_in(node, arg_u32(2)).pos - node.screen_rect.pos;
+                let point = DVec2 {x: arg_i32(0) as f64, y: arg_i32(1) as f64} - origin;
+                let hit = self.tree.nodes.values()
+                    .filter( | other | other.window_id == node.window_id && other.screen_rect.contains(point))
+                    .min_by( | a, b | {
+                        let (a, b) = (a.screen_rect.size.x * a.screen_rect.size.y, b.screen_rect.size.x * b.screen_rect.size.y);
+                        a.total_cmp(&b)
+                    });
+                vec![hit.map_or(self.null_ref(), | hit | self.object_ref(Some(hit.id)))]
+            }
+            "GetExtents" => vec![extents(self.extents_in(node, arg_u32(0)))],
+            "GetPosition" => {
+                let rect = self.extents_in(node, arg_u32(0));
+                vec![DBusValue::Int32(rect.pos.x as i32), DBusValue::Int32(rect.pos.y as i32)]
+            }
+            "GetSize" => vec![DBusValue::Int32(node.screen_rect.size.x as i32), DBusValue::Int32(node.screen_rect.size.y as i32)],
+            "GetLayer" => vec![DBusValue::Uint32(if node.node.role == AccessibilityRole::Window {LAYER_WINDOW} else {LAYER_WIDGET})],
+            "GetMDIZOrder" => vec![DBusValue::Int16(0)],
+            "GetAlpha" => vec![DBusValue::Double(1.0)],
+            "GrabFocus" => {
+                let focusable = node.node.states.contains(AccessibilityState::Focusable);
+                if focusable {
+                    action = Some(AccessibilityAction::Focus);
+                }
+                vec![DBusValue::Bool(focusable)]
+            }
+            "GetName" | "GetLocalizedName" => {
+                vec![DBusValue::string(action_at(arg_i32(0)).map_or("", | action | action.name()))]
+            }
+            "GetDescription" | "GetKeyBinding" => vec![DBusValue::string("")],
+            "GetActions" => vec![DBusValue::Array("(sss)".to_string(), node.node.actions.iter().map( | action | {
+                DBusValue::Struct(vec![
+                    DBusValue::string(action.name()),
+                    DBusValue::string(action.name()),
+                    DBusValue::string(""),
+                ])
+            }).collect())],
+            "DoAction" => {
+                action = action_at(arg_i32(0)).copied();
+                vec![DBusValue::Bool(action.is_some())]
+            }
+            _ => return (unknown_method(call), None)
+        };
+        (
+            DBusMessage::method_return(call, body),
+            action.map( | action | AccessibilityActionEvent {id: node.id, action})
+        )
+    }
+}
@@ -0,0 +1,877 @@
+use {
+    std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+        collections::VecDeque,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicU32, Ordering},
+        },
+    },
+    self::super::libc_sys,
+};
+
+// A small blocking D-Bus client. It speaks the wire protocol directly over the
+// unix socket of the bus, which is enough for the desktop portals and
+// accessibility without linking libdbus.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DBusValue {
+    Byte(u8),
+    Bool(bool),
+    Int16(i16),
+    Uint16(u16),
+    Int32(i32),
+    Uint32(u32),
+    Int64(i64),
+    Uint64(u64),
+    Double(f64),
+    String(String),
+    ObjectPath(String),
+    Signature(String),
+    UnixFd(u32),
+    /// The element signature is kept so empty arrays can still be written.
+    Array(String, Vec<DBusValue>),
+    Struct(Vec<DBusValue>),
+    DictEntry(Box<DBusValue>, Box<DBusValue>),
+    Variant(Box<DBusValue>),
+}
+
+impl DBusValue {
+    pub fn string(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+
+    pub fn variant(value: DBusValue) -> Self {
+        Self::Variant(Box::new(value))
+    }
+
+    /// Builds an `a{sv}` dictionary, the usual shape of option arguments.
+    pub fn dict(entries: Vec<(&str, DBusValue)>) -> Self {
+        Self::Array("{sv}".to_string(), entries.into_iter().map( | (key, value) | {
+            Self::DictEntry(Box::new(Self::string(key)), Box::new(Self::variant(value)))
+        }).collect())
+    }
+
+    /// A nul terminated byte array, how portals pass file system paths.
+    pub fn byte_string(s: &str) -> Self {
+        let mut bytes: Vec<DBusValue> = s.bytes().map(Self::Byte).collect();
+        bytes.push(Self::Byte(0));
+        Self::Array("y".to_string(), bytes)
+    }
+
+    pub fn signature(&self) -> String {
+        match self {
+            Self::Byte(_) => "y".to_string(),
+            Self::Bool(_) => "b".to_string(),
+            Self::Int16(_) => "n".to_string(),
+            Self::Uint16(_) => "q".to_string(),
+            Self::Int32(_) => "i".to_string(),
+            Self::Uint32(_) => "u".to_string(),
+            Self::Int64(_) => "x".to_string(),
+            Self::Uint64(_) => "t".to_string(),
+            Self::Double(_) => "d".to_string(),
+            Self::String(_) => "s".to_string(),
+            Self::ObjectPath(_) => "o".to_string(),
+            Self::Signature(_) => "g".to_string(),
+            Self::UnixFd(_) => "h".to_string(),
+            Self::Array(element, _) => format!("a{}", element),
+            Self::Struct(fields) => format!("({})", fields.iter().map( | f | f.signature()).collect::<String>()),
+            Self::DictEntry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+            Self::Variant(_) => "v".to_string(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) | Self::ObjectPath(s) | Self::Signature(s) => Some(s),
+            Self::Variant(v) => v.as_str(),
+            _ => None
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Self::Uint32(v) => Some(*v),
+            Self::Variant(v) => v.as_u32(),
+            _ => None
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Self::Int32(v) => Some(*v),
+            Self::Variant(v) => v.as_i32(),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Double(v) => Some(*v),
+            Self::Variant(v) => v.as_f64(),
+            _ => None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(v) => Some(*v),
+            Self::Variant(v) => v.as_bool(),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[DBusValue]> {
+        match self {
+            Self::Array(_, items) => Some(items),
+            Self::Variant(v) => v.as_array(),
+            _ => None
+        }
+    }
+
+    /// Looks up `key` in a dictionary with string keys.
+    pub fn dict_get(&self, key: &str) -> Option<&DBusValue> {
+        self.as_array()?.iter().find_map( | entry | match entry {
+            Self::DictEntry(k, v) if k.as_str() == Some(key) => Some(match &**v {
+                Self::Variant(v) => &**v,
+                v => v
+            }),
+            _ => None
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DBusMessageType {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+pub const DBUS_FLAG_NO_REPLY_EXPECTED: u8 = 0x1;
+
+#[derive(Clone, Debug)]
+pub struct DBusMessage {
+    pub message_type: DBusMessageType,
+    pub flags: u8,
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<DBusValue>,
+}
+
+impl DBusMessage {
+    fn new(message_type: DBusMessageType) -> Self {
+        Self {
+            message_type,
+            flags: 0,
+            serial: 0,
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            body: Vec::new(),
+        }
+    }
+
+    pub fn method_call(destination: &str, path: &str, interface: &str, member: &str, body: Vec<DBusValue>) -> Self {
+        Self {
+            destination: Some(destination.to_string()),
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            body,
+            ..Self::new(DBusMessageType::MethodCall)
+        }
+    }
+
+    pub fn signal(path: &str, interface: &str, member: &str, body: Vec<DBusValue>) -> Self {
+        Self {
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            body,
+            ..Self::new(DBusMessageType::Signal)
+        }
+    }
+
+    pub fn method_return(call: &DBusMessage, body: Vec<DBusValue>) -> Self {
+        Self {
+            destination: call.sender.clone(),
+            reply_serial: Some(call.serial),
+            flags: DBUS_FLAG_NO_REPLY_EXPECTED,
+            body,
+            ..Self::new(DBusMessageType::MethodReturn)
+        }
+    }
+
+    pub fn error(call: &DBusMessage, error_name: &str, message: &str) -> Self {
+        Self {
+            destination: call.sender.clone(),
+            reply_serial: Some(call.serial),
+            error_name: Some(error_name.to_string()),
+            flags: DBUS_FLAG_NO_REPLY_EXPECTED,
+            body: vec![DBusValue::string(message)],
+            ..Self::new(DBusMessageType::Error)
+        }
+    }
+
+    pub fn is_signal(&self, interface: &str, member: &str) -> bool {
+        self.message_type == DBusMessageType::Signal &&
+            self.interface.as_deref() == Some(interface) &&
+            self.member.as_deref() == Some(member)
+    }
+
+    pub fn is_method_call(&self, interface: &str, member: &str) -> bool {
+        self.message_type == DBusMessageType::MethodCall &&
+            self.interface.as_deref() == Some(interface) &&
+            self.member.as_deref() == Some(member)
+    }
+
+    pub fn signature(&self) -> String {
+        self.body.iter().map( | v | v.signature()).collect()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut body = DBusWriter::default();
+        for value in &self.body {
+            body.write_value(value);
+        }
+
+        let mut fields = Vec::new();
+        let mut field = | code: u8, value: DBusValue | {
+            fields.push(DBusValue::Struct(vec![DBusValue::Byte(code), DBusValue::variant(value)]));
+        };
+        if let Some(path) = &self.path {field(1, DBusValue::ObjectPath(path.clone()))}
+        if let Some(interface) = &self.interface {field(2, DBusValue::String(interface.clone()))}
+        if let Some(member) = &self.member {field(3, DBusValue::String(member.clone()))}
+        if let Some(error_name) = &self.error_name {field(4, DBusValue::String(error_name.clone()))}
+        if let Some(reply_serial) = self.reply_serial {field(5, DBusValue::Uint32(reply_serial))}
+        if let Some(destination) = &self.destination {field(6, DBusValue::String(destination.clone()))}
+        if let Some(sender) = &self.sender {field(7, DBusValue::String(sender.clone()))}
+        if self.body.len() > 0 {field(8, DBusValue::Signature(self.signature()))}
+
+        let mut out = DBusWriter::default();
+        out.buf.extend_from_slice(&[b'l', self.message_type as u8, self.flags, 1]);
+        out.write_u32(body.buf.len() as u32);
+        out.write_u32(self.serial);
+        out.write_value(&DBusValue::Array("(yv)".to_string(), fields));
+        out.align(8);
+        out.buf.extend_from_slice(&body.buf);
+        out.buf
+    }
+
+    /// Returns the total length of the message starting with this 16 byte header.
+    fn length_from_header(header: &[u8]) -> Result<usize, String> {
+        let reader = DBusReader::new(header, header[0] == b'B')?;
+        let body_len = reader.u32_at(4) as usize;
+        let fields_len = reader.u32_at(12) as usize;
+        Ok(((16 + fields_len + 7) & !7) + body_len)
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 16 {
+            return Err("D-Bus message too short".to_string())
+        }
+        let mut reader = DBusReader::new(data, data[0] == b'B')?;
+        let message_type = match data[1] {
+            1 => DBusMessageType::MethodCall,
+            2 => DBusMessageType::MethodReturn,
+            3 => DBusMessageType::Error,
+            4 => DBusMessageType::Signal,
+            t => return Err(format!("Unknown D-Bus message type {}", t))
+        };
+        let mut message = Self::new(message_type);
+        message.flags = data[2];
+        message.serial = reader.u32_at(8);
+
+        reader.pos = 12;
+        let mut signature = String::new();
+        if let DBusValue::Array(_, fields) = reader.read_value(b"a(yv)")? {
+            for field in fields {
+                if let DBusValue::Struct(field) = field {
+                    let value = match &field[1] {
+                        DBusValue::Variant(value) => &**value,
+                        _ => continue
+                    };
+                    match field[0] {
+                        DBusValue::Byte(1) => message.path = value.as_str().map(String::from),
+                        DBusValue::Byte(2) => message.interface = value.as_str().map(String::from),
+                        DBusValue::Byte(3) => message.member = value.as_str().map(String::from),
+                        DBusValue::Byte(4) => message.error_name = value.as_str().map(String::from),
+                        DBusValue::Byte(5) => message.reply_serial = value.as_u32(),
+                        DBusValue::Byte(6) => message.destination = value.as_str().map(String::from),
+                        DBusValue::Byte(7) => message.sender = value.as_str().map(String::from),
+                        DBusValue::Byte(8) => signature = value.as_str().unwrap_or("").to_string(),
+                        _ => ()
+                    }
+                }
+            }
+        }
+        reader.align(8)?;
+        let mut sig = signature.as_bytes();
+        while sig.len() > 0 {
+            let len = single_type_len(sig)?;
+            message.body.push(reader.read_value(&sig[0..len])?);
+            sig = &sig[len..];
+        }
+        Ok(message)
+    }
+}
+
+fn alignment_of(code: u8) -> usize {
+    match code {
+        b'y' | b'g' | b'v' => 1,
+        b'n' | b'q' => 2,
+        b'b' | b'i' | b'u' | b's' | b'o' | b'a' | b'h' => 4,
+        _ => 8,
+    }
+}
+
+/// The length of the first complete type in a signature.
+fn single_type_len(sig: &[u8]) -> Result<usize, String> {
+    match sig.first() {
+        Some(b'a') => Ok(1 + single_type_len(&sig[1..])?),
+        Some(open @ (b'(' | b'{')) => {
+            let close = if *open == b'(' {b')'} else {b'}'};
+            let mut len = 1;
+            loop {
+                match sig.get(len) {
+                    Some(c) if *c == close => return Ok(len + 1),
+                    Some(_) => len += single_type_len(&sig[len..])?,
+                    None => return Err("Unterminated D-Bus signature".to_string())
+                }
+            }
+        }
+        Some(_) => Ok(1),
+        None => Err("Empty D-Bus signature".to_string())
+    }
+}
+
+#[derive(Default)]
+struct DBusWriter {
+    buf: Vec<u8>,
+}
+
+impl DBusWriter {
+    fn align(&mut self, alignment: usize) {
+        while self.buf.len() % alignment != 0 {
+            self.buf.push(0);
+        }
+    }
+
+    fn write_u32(&mut self, v: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_u32(s.len() as u32);
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn write_signature(&mut self, s: &str) {
+        self.buf.push(s.len() as u8);
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn write_value(&mut self, value: &DBusValue) {
+        match value {
+            DBusValue::Byte(v) => self.buf.push(*v),
+            DBusValue::Bool(v) => self.write_u32(*v as u32),
+            DBusValue::Int16(v) => {self.align(2); self.buf.extend_from_slice(&v.to_le_bytes())}
+            DBusValue::Uint16(v) => {self.align(2); self.buf.extend_from_slice(&v.to_le_bytes())}
+            DBusValue::Int32(v) => self.write_u32(*v as u32),
+            DBusValue::Uint32(v) | DBusValue::UnixFd(v) => self.write_u32(*v),
+            DBusValue::Int64(v) => {self.align(8); self.buf.extend_from_slice(&v.to_le_bytes())}
+            DBusValue::Uint64(v) => {self.align(8); self.buf.extend_from_slice(&v.to_le_bytes())}
+            DBusValue::Double(v) => {self.align(8); self.buf.extend_from_slice(&v.to_le_bytes())}
+            DBusValue::String(s) | DBusValue::ObjectPath(s) => self.write_str(s),
+            DBusValue::Signature(s) => self.write_signature(s),
+            DBusValue::Array(element, items) => {
+                self.write_u32(0);
+                let len_pos = self.buf.len() - 4;
+                // the padding up to the first element is not part of the length
+                self.align(alignment_of(element.as_bytes()[0]));
+                let start = self.buf.len();
+                for item in items {
+                    self.write_value(item);
+                }
+                let len = (self.buf.len() - start) as u32;
+                self.buf[len_pos..len_pos + 4].copy_from_slice(&len.to_le_bytes());
+            }
+            DBusValue::Struct(fields) => {
+                self.align(8);
+                for field in fields {
+                    self.write_value(field);
+                }
+            }
+            DBusValue::DictEntry(key, value) => {
+                self.align(8);
+                self.write_value(key);
+                self.write_value(value);
+            }
+            DBusValue::Variant(value) => {
+                self.write_signature(&value.signature());
+                self.write_value(value);
+            }
+        }
+    }
+}
+
+struct DBusReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> DBusReader<'a> {
+    fn new(data: &'a [u8], big_endian: bool) -> Result<Self, String> {
+        if data.len() < 16 || (data[0] != b'l' && data[0] != b'B') {
+            return Err("Invalid D-Bus message header".to_string())
+        }
+        Ok(Self {data, pos: 0, big_endian})
+    }
+
+    fn u32_at(&self, pos: usize) -> u32 {
+        let bytes = [self.data[pos], self.data[pos + 1], self.data[pos + 2], self.data[pos + 3]];
+        if self.big_endian {u32::from_be_bytes(bytes)} else {u32::from_le_bytes(bytes)}
+    }
+
+    fn align(&mut self, alignment: usize) -> Result<(), String> {
+        self.pos = (self.pos + alignment - 1) & !(alignment - 1);
+        if self.pos > self.data.len() {
+            return Err("D-Bus message truncated".to_string())
+        }
+        Ok(())
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        self.align(N)?;
+        if self.pos + N > self.data.len() {
+            return Err("D-Bus message truncated".to_string())
+        }
+        let mut out = [0u8; N];
+        out.copy_from_slice(&self.data[self.pos..self.pos + N]);
+        if self.big_endian {
+            out.reverse();
+        }
+        self.pos += N;
+        Ok(out)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn read_string_bytes(&mut self, len: usize) -> Result<String, String> {
+        if self.pos + len + 1 > self.data.len() {
+            return Err("D-Bus message truncated".to_string())
+        }
+        let s = String::from_utf8_lossy(&self.data[self.pos..self.pos + len]).to_string();
+        self.pos += len + 1;
+        Ok(s)
+    }
+
+    fn read_signature(&mut self) -> Result<String, String> {
+        let len = self.bytes::<1>()?[0] as usize;
+        self.read_string_bytes(len)
+    }
+
+    fn read_value(&mut self, sig: &[u8]) -> Result<DBusValue, String> {
+        Ok(match sig[0] {
+            b'y' => DBusValue::Byte(self.bytes::<1>()?[0]),
+            b'b' => DBusValue::Bool(self.read_u32()? != 0),
+            b'n' => DBusValue::Int16(i16::from_le_bytes(self.bytes()?)),
+            b'q' => DBusValue::Uint16(u16::from_le_bytes(self.bytes()?)),
+            b'i' => DBusValue::Int32(i32::from_le_bytes(self.bytes()?)),
+            b'u' => DBusValue::Uint32(self.read_u32()?),
+            b'h' => DBusValue::UnixFd(self.read_u32()?),
+            b'x' => DBusValue::Int64(i64::from_le_bytes(self.bytes()?)),
+            b't' => DBusValue::Uint64(u64::from_le_bytes(self.bytes()?)),
+            b'd' => DBusValue::Double(f64::from_le_bytes(self.bytes()?)),
+            b's' => {
+                let len = self.read_u32()? as usize;
+                DBusValue::String(self.read_string_bytes(len)?)
+            }
+            b'o' => {
+                let len = self.read_u32()? as usize;
+                DBusValue::ObjectPath(self.read_string_bytes(len)?)
+            }
+            b'g' => DBusValue::Signature(self.read_signature()?),
+            b'v' => {
+                let sig = self.read_signature()?;
+                if sig.len() == 0 || single_type_len(sig.as_bytes())? != sig.len() {
+                    return Err(format!("Invalid D-Bus variant signature {}", sig))
+                }
+                DBusValue::variant(self.read_value(sig.as_bytes())?)
+            }
+            b'a' => {
+                let len = self.read_u32()? as usize;
+                let element = &sig[1..1 + single_type_len(&sig[1..])?];
+                self.align(alignment_of(element[0]))?;
+                let end = self.pos + len;
+                if end > self.data.len() {
+                    return Err("D-Bus message truncated".to_string())
+                }
+                let mut items = Vec::new();
+                while self.pos < end {
+                    items.push(self.read_value(element)?);
+                }
+                DBusValue::Array(String::from_utf8_lossy(element).to_string(), items)
+            }
+            b'(' => {
+                self.align(8)?;
+                let mut fields = Vec::new();
+                let mut field_sig = &sig[1..sig.len() - 1];
+                while field_sig.len() > 0 {
+                    let len = single_type_len(field_sig)?;
+                    fields.push(self.read_value(&field_sig[0..len])?);
+                    field_sig = &field_sig[len..];
+                }
+                DBusValue::Struct(fields)
+            }
+            b'{' => {
+                self.align(8)?;
+                let key_len = single_type_len(&sig[1..])?;
+                let key = self.read_value(&sig[1..1 + key_len])?;
+                let value = self.read_value(&sig[1 + key_len..sig.len() - 1])?;
+                DBusValue::DictEntry(Box::new(key), Box::new(value))
+            }
+            c => return Err(format!("Unsupported D-Bus type {}", c as char))
+        })
+    }
+}
+
+pub struct DBusConnection {
+    stream: UnixStream,
+    sender: DBusSender,
+    unique_name: String,
+    // messages that arrived while we were waiting for a method reply
+    pending: VecDeque<DBusMessage>,
+}
+
+impl DBusConnection {
+    /// Connects to the session bus named by `DBUS_SESSION_BUS_ADDRESS`.
+    pub fn session() -> Result<Self, String> {
+        let address = std::env::var("DBUS_SESSION_BUS_ADDRESS")
+            .map_err( | _ | "DBUS_SESSION_BUS_ADDRESS is not set".to_string())?;
+        Self::connect(&address)
+    }
+
+    pub fn connect(address: &str) -> Result<Self, String> {
+        // an address can list several transports, use the first unix one we can reach
+        let mut last_err = format!("No usable D-Bus address in {}", address);
+        for transport in address.split(';') {
+            let Some(params) = transport.strip_prefix("unix:") else {continue};
+            let stream = params.split(',').find_map( | param | {
+                if let Some(path) = param.strip_prefix("path=") {
+                    Some(UnixStream::connect(unescape_address(path)))
+                }
+                else if let Some(name) = param.strip_prefix("abstract=") {
+                    use std::os::linux::net::SocketAddrExt;
+                    Some(std::os::unix::net::SocketAddr::from_abstract_name(unescape_address(name).as_bytes())
+                        .and_then( | addr | UnixStream::connect_addr(&addr)))
+                }
+                else {
+                    None
+                }
+            });
+            match stream {
+                Some(Ok(stream)) => return Self::from_stream(stream),
+                Some(Err(err)) => last_err = format!("Cannot connect to D-Bus {}: {}", transport, err),
+                None => ()
+            }
+        }
+        Err(last_err)
+    }
+
+    fn from_stream(mut stream: UnixStream) -> Result<Self, String> {
+        let uid = unsafe {libc_sys::getuid()};
+        let uid_hex: String = uid.to_string().bytes().map( | b | format!("{:02x}", b)).collect();
+        stream.write_all(format!("\0AUTH EXTERNAL {}\r\n", uid_hex).as_bytes()).map_err( | e | e.to_string())?;
+        let line = read_auth_line(&mut stream)?;
+        if !line.starts_with("OK ") {
+            return Err(format!("D-Bus authentication failed: {}", line))
+        }
+        stream.write_all(b"BEGIN\r\n").map_err( | e | e.to_string())?;
+
+        let sender = DBusSender {
+            stream: Arc::new(Mutex::new(stream.try_clone().map_err( | e | e.to_string())?)),
+            serial: Arc::new(AtomicU32::new(0)),
+        };
+        let mut connection = Self {
+            stream,
+            sender,
+            unique_name: String::new(),
+            pending: VecDeque::new(),
+        };
+        let reply = connection.call(DBusMessage::method_call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "Hello",
+            vec![]
+        ))?;
+        connection.unique_name = reply.body.first().and_then( | v | v.as_str()).unwrap_or("").to_string();
+        Ok(connection)
+    }
+
+    /// The name the bus assigned to this connection, like `:1.42`.
+    pub fn unique_name(&self) -> &str {
+        &self.unique_name
+    }
+
+    /// Sends a message and returns the serial it was sent with.
+    pub fn send(&mut self, message: DBusMessage) -> Result<u32, String> {
+        self.sender.send(message)
+    }
+
+    /// A handle that can send on this connection from other threads while
+    /// this one blocks in `read_message`.
+    pub fn sender(&self) -> DBusSender {
+        self.sender.clone()
+    }
+
+    /// Sends a method call and blocks until its reply arrives. Error replies are
+    /// returned as `Err` with the error name and message.
+    pub fn call(&mut self, message: DBusMessage) -> Result<DBusMessage, String> {
+        let serial = self.send(message)?;
+        loop {
+            let message = self.read_from_socket()?;
+            if message.reply_serial == Some(serial) {
+                if message.message_type == DBusMessageType::Error {
+                    return Err(format!(
+                        "{}: {}",
+                        message.error_name.as_deref().unwrap_or("D-Bus error"),
+                        message.body.first().and_then( | v | v.as_str()).unwrap_or("")
+                    ))
+                }
+                return Ok(message)
+            }
+            self.pending.push_back(message);
+        }
+    }
+
+    /// Blocks until the next incoming signal, method call or unclaimed reply.
+    pub fn read_message(&mut self) -> Result<DBusMessage, String> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(message)
+        }
+        self.read_from_socket()
+    }
+
+    pub fn add_match(&mut self, rule: &str) -> Result<(), String> {
+        self.call(DBusMessage::method_call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "AddMatch",
+            vec![DBusValue::string(rule)]
+        ))?;
+        Ok(())
+    }
+
+    /// Claims a well known name and returns whether this connection is now its primary owner.
+    pub fn request_name(&mut self, name: &str) -> Result<bool, String> {
+        let reply = self.call(DBusMessage::method_call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "RequestName",
+            vec![DBusValue::string(name), DBusValue::Uint32(4)]
+        ))?;
+        Ok(reply.body.first().and_then( | v | v.as_u32()) == Some(1))
+    }
+
+    fn read_from_socket(&mut self) -> Result<DBusMessage, String> {
+        let mut data = vec![0u8; 16];
+        self.stream.read_exact(&mut data).map_err( | e | e.to_string())?;
+        let len = DBusMessage::length_from_header(&data)?;
+        data.resize(len, 0);
+        self.stream.read_exact(&mut data[16..]).map_err( | e | e.to_string())?;
+        DBusMessage::deserialize(&data)
+    }
+}
+
+#[derive(Clone)]
+pub struct DBusSender {
+    stream: Arc<Mutex<UnixStream>>,
+    serial: Arc<AtomicU32>,
+}
+
+impl DBusSender {
+    pub fn send(&self, mut message: DBusMessage) -> Result<u32, String> {
+        // the lock keeps messages from several threads from interleaving on the socket
+        let mut stream = self.stream.lock().unwrap();
+        message.serial = self.serial.fetch_add(1, Ordering::SeqCst) + 1;
+        stream.write_all(&message.serialize()).map_err( | e | e.to_string())?;
+        Ok(message.serial)
+    }
+}
+
+fn read_auth_line(stream: &mut UnixStream) -> Result<String, String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8];
+    while !line.ends_with(b"\r\n") {
+        if stream.read(&mut byte).map_err( | e | e.to_string())? == 0 {
+            return Err("D-Bus connection closed during authentication".to_string())
+        }
+        line.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&line[0..line.len() - 2]).to_string())
+}
+
+fn unescape_address(value: &str) -> String {
+    let mut out = Vec::new();
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(b) = std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then( | hex | u8::from_str_radix(hex, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: &DBusMessage) -> DBusMessage {
+        let data = message.serialize();
+        assert_eq!(DBusMessage::length_from_header(&data[0..16]), Ok(data.len()));
+        DBusMessage::deserialize(&data).unwrap()
+    }
+
+    #[test]
+    fn marshal_struct_in_array() {
+        // (sa(us)): the array length doesn't include the padding before its first element
+        let mut writer = DBusWriter::default();
+        writer.write_value(&DBusValue::Struct(vec![
+            DBusValue::string("ab"),
+            DBusValue::Array("(us)".to_string(), vec![
+                DBusValue::Struct(vec![DBusValue::Uint32(1), DBusValue::string("c")])
+            ])
+        ]));
+        assert_eq!(writer.buf, [
+            2, 0, 0, 0, b'a', b'b', 0,
+            0,
+            10, 0, 0, 0,
+            0, 0, 0, 0,
+            1, 0, 0, 0,
+            1, 0, 0, 0, b'c', 0
+        ]);
+    }
+
+    #[test]
+    fn filters_round_trip() {
+        let filters = DBusValue::Array("(sa(us))".to_string(), vec![
+            DBusValue::Struct(vec![
+                DBusValue::string("Images"),
+                DBusValue::Array("(us)".to_string(), vec![
+                    DBusValue::Struct(vec![DBusValue::Uint32(0), DBusValue::string("*.png")]),
+                    DBusValue::Struct(vec![DBusValue::Uint32(1), DBusValue::string("image/jpeg")]),
+                ])
+            ]),
+            DBusValue::Struct(vec![
+                DBusValue::string("Nothing"),
+                DBusValue::Array("(us)".to_string(), vec![])
+            ]),
+        ]);
+        let message = DBusMessage::method_call(
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.FileChooser",
+            "OpenFile",
+            vec![
+                DBusValue::string(""),
+                DBusValue::string("Open File"),
+                DBusValue::dict(vec![
+                    ("modal", DBusValue::Bool(true)),
+                    ("filters", filters.clone()),
+                    ("current_folder", DBusValue::byte_string("/tmp")),
+                ]),
+            ]
+        );
+        let decoded = round_trip(&message);
+        assert_eq!(decoded.signature(), "ssa{sv}");
+        assert!(decoded.is_method_call("org.freedesktop.portal.FileChooser", "OpenFile"));
+        assert_eq!(decoded.destination.as_deref(), Some("org.freedesktop.portal.Desktop"));
+        assert_eq!(decoded.path.as_deref(), Some("/org/freedesktop/portal/desktop"));
+        assert_eq!(decoded.body, message.body);
+
+        let options = &decoded.body[2];
+        assert_eq!(options.dict_get("filters"), Some(&filters));
+        assert_eq!(options.dict_get("filters").unwrap().signature(), "a(sa(us))");
+        assert_eq!(options.dict_get("modal").and_then( | v | v.as_bool()), Some(true));
+        assert_eq!(options.dict_get("current_folder"), Some(&DBusValue::byte_string("/tmp")));
+        assert_eq!(options.dict_get("missing"), None);
+    }
+
+    #[test]
+    fn response_lookup() {
+        // the shape of a portal Response signal, (ua{sv}) with the uris as an array of strings
+        let message = DBusMessage::signal("/org/freedesktop/portal/desktop/request/1_2/makepad0", "org.freedesktop.portal.Request", "Response", vec![
+            DBusValue::Uint32(0),
+            DBusValue::dict(vec![
+                ("uris", DBusValue::Array("s".to_string(), vec![DBusValue::string("file:///tmp/a"), DBusValue::string("file:///tmp/b")])),
+                ("choices", DBusValue::Array("(ss)".to_string(), vec![])),
+            ]),
+        ]);
+        let decoded = round_trip(&message);
+        assert!(decoded.is_signal("org.freedesktop.portal.Request", "Response"));
+        assert_eq!(decoded.body[0].as_u32(), Some(0));
+        let uris: Vec<&str> = decoded.body[1].dict_get("uris").and_then( | v | v.as_array()).unwrap()
+            .iter().filter_map( | v | v.as_str()).collect();
+        assert_eq!(uris, ["file:///tmp/a", "file:///tmp/b"]);
+        assert_eq!(decoded.body[1].dict_get("choices").and_then( | v | v.as_array()), Some(&[][..]));
+    }
+
+    #[test]
+    fn unmarshal_big_endian() {
+        // a signal with only a signature header field and a u32 body
+        let data = [
+            b'B', 4, 0, 1, 0, 0, 0, 4, 0, 0, 0, 7, 0, 0, 0, 7,
+            8, 1, b'g', 0, 1, b'u', 0,
+            0,
+            1, 2, 3, 4,
+        ];
+        assert_eq!(DBusMessage::length_from_header(&data[0..16]), Ok(data.len()));
+        let message = DBusMessage::deserialize(&data).unwrap();
+        assert_eq!(message.message_type, DBusMessageType::Signal);
+        assert_eq!(message.serial, 7);
+        assert_eq!(message.body, [DBusValue::Uint32(0x01020304)]);
+    }
+
+    #[test]
+    fn unmarshal_errors() {
+        let message = DBusMessage::method_call("a.b", "/", "a.b", "C", vec![DBusValue::string("hello")]);
+        let data = message.serialize();
+        assert!(DBusMessage::deserialize(&data[0..data.len() - 3]).is_err());
+        assert!(DBusMessage::deserialize(&data[0..12]).is_err());
+        let mut data = data;
+        data[0] = b'x';
+        assert!(DBusMessage::deserialize(&data).is_err());
+        assert!(single_type_len(b"a(su").is_err());
+        assert_eq!(single_type_len(b"a{sv}s"), Ok(5));
+    }
+}
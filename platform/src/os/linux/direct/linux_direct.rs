@@ -10,7 +10,8 @@ use {
     self::super::super::{
         gl_sys,
         select_timer::SelectTimers,
-        linux_media::CxLinuxMedia
+        linux_media::CxLinuxMedia,
+        linux_http::LinuxHttpRequests,
     },
     crate::{
         cx_api::{CxOsOp, CxOsApi, OpenUrlInPlace},
@@ -21,6 +22,7 @@ use {
             TimerEvent,
             Event,
             WindowGeom,
+            NetworkResponseChannel,
         },
         window::CxWindowPool,
         pass::CxPassParent,
@@ -182,6 +184,7 @@ impl Cx {
                         self.call_event_handler(&Event::Signal);
                     }
                     self.handle_action_receiver();
+                    self.handle_networking_events();
                 }
                 else {
                     self.call_event_handler(&Event::Timer(e))
@@ -277,6 +280,17 @@ impl Cx {
         }
     }
     
+    pub(crate) fn handle_networking_events(&mut self) {
+        let mut out = Vec::new();
+        while let Ok(item) = self.os.network_response.receiver.try_recv() {
+            self.os.http_requests.handle_response_item(&item);
+            out.push(item);
+        }
+        if out.len()>0 {
+            self.call_event_handler(&Event::NetworkResponses(out))
+        }
+    }
+    
     fn handle_platform_ops(&mut self, direct_app: &mut DirectApp) -> EventFlow {
         while let Some(op) = self.platform_ops.pop() {
             match op {
@@ -304,6 +318,12 @@ impl Cx {
                 CxOsOp::StopTimer(timer_id) => {
                     direct_app.timers.stop_timer(timer_id);
                 },
+                CxOsOp::HttpRequest{request_id, request} => {
+                    self.os.http_requests.make_http_request(request_id, request, self.os.network_response.sender.clone());
+                },
+                CxOsOp::CancelHttpRequest {request_id} => {
+                    self.os.http_requests.cancel_http_request(request_id);
+                },
                 _ => ()
             }
         }
@@ -338,13 +358,17 @@ impl CxOsApi for Cx {
 pub struct CxOs {
     pub (crate) media: CxLinuxMedia,
     pub (crate) start_time: Instant,
+    pub (crate) network_response: NetworkResponseChannel,
+    pub (crate) http_requests: LinuxHttpRequests,
 }
 
 impl Default for CxOs {
     fn default() -> Self {
         Self {
             start_time: Instant::now(),
-            media: Default::default()
+            media: Default::default(),
+            network_response: Default::default(),
+            http_requests: Default::default(),
         }
     }
 }
\ No newline at end of file
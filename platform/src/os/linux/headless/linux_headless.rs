@@ -0,0 +1,479 @@
+use {
+    std::rc::Rc,
+    std::time::{Instant, Duration},
+    std::cell::RefCell,
+    std::path::PathBuf,
+    self::super::{
+        software_render::{SoftwareSurface, SoftwareTarget},
+    },
+    self::super::super::{
+        select_timer::SelectTimers,
+        linux_media::CxLinuxMedia,
+        linux_http::LinuxHttpRequests,
+    },
+    crate::{
+        makepad_live_id::*,
+        cx_api::{CxOsOp, CxOsApi, OpenUrlInPlace},
+        makepad_math::*,
+        makepad_image_formats::{ImageBuffer, png},
+        thread::SignalToUI,
+        event::{
+            MouseDownEvent,
+            MouseUpEvent,
+            MouseMoveEvent,
+            ScrollEvent,
+            KeyEvent,
+            TextInputEvent,
+            TimerEvent,
+            Event,
+            WindowGeom,
+            NetworkResponseChannel,
+        },
+        pass::CxPassParent,
+        window::CxWindowPool,
+        cx::{Cx, OsType,},
+        gpu_info::GpuPerformance,
+        os::cx_native::EventFlow,
+        pass::{PassClearColor, PassClearDepth, PassId},
+    }
+};
+
+// Runs the app without a gpu or display server. Frames are rendered on the CPU
+// into the framebuffer in CxOs and can be written out as PNG files. With -frames
+// the app exits after that many frames or when it goes idle:
+// MAKEPAD=linux_headless cargo run -- -mode=1280x720 -scale=1 -dump=frames -frames=10
+// Tests can also drive a Cx directly with a HeadlessApp, see widgets/src/snapshot.rs
+
+#[derive(Debug)]
+pub enum HeadlessEvent {
+    Paint,
+    MouseDown(MouseDownEvent),
+    MouseUp(MouseUpEvent),
+    MouseMove(MouseMoveEvent),
+    Scroll(ScrollEvent),
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    TextInput(TextInputEvent),
+    Timer(TimerEvent),
+}
+
+pub struct HeadlessApp {
+    timers: SelectTimers,
+    width: usize,
+    height: usize,
+    dpi_factor: f64,
+    dump_dir: Option<PathBuf>,
+    max_frames: Option<usize>,
+    frames: usize,
+    fixed_time: Option<f64>,
+}
+
+impl HeadlessApp {
+    /// Creates a headless app with a fixed window size in physical pixels. Its clock
+    /// starts at zero and only moves with `advance_time`, so frames are reproducible.
+    pub fn new(width: usize, height: usize, dpi_factor: f64) -> Self {
+        Self {
+            timers: SelectTimers::new(),
+            width,
+            height,
+            dpi_factor,
+            dump_dir: None,
+            max_frames: None,
+            frames: 0,
+            fixed_time: Some(0.0),
+        }
+    }
+    
+    fn from_args() -> Self {
+        let mut mode = "1280x720".to_string();
+        let mut dpi_factor = 1.0;
+        let mut dump_dir = None;
+        let mut max_frames = None;
+        for arg in std::env::args() {
+            if arg.starts_with("-mode=") {
+                mode = arg.trim_start_matches("-mode=").to_string();
+            }
+            if arg.starts_with("-scale=") {
+                dpi_factor = arg.trim_start_matches("-scale=").parse().unwrap();
+            }
+            if arg.starts_with("-dump=") {
+                dump_dir = Some(PathBuf::from(arg.trim_start_matches("-dump=")));
+            }
+            if arg.starts_with("-frames=") {
+                max_frames = Some(arg.trim_start_matches("-frames=").parse().unwrap());
+            }
+        }
+        // accepts the same WxH-refresh syntax as the direct backend
+        let mut parts = mode.split(|c| c == 'x' || c == '-');
+        let width = parts.next().and_then(|w| w.parse().ok()).unwrap_or(1280);
+        let height = parts.next().and_then(|h| h.parse().ok()).unwrap_or(720);
+        if let Some(dump_dir) = &dump_dir {
+            std::fs::create_dir_all(dump_dir).unwrap();
+        }
+        Self {
+            timers: SelectTimers::new(),
+            width,
+            height,
+            dpi_factor,
+            dump_dir,
+            max_frames,
+            frames: 0,
+            fixed_time: None,
+        }
+    }
+    
+    pub fn time_now(&self) -> f64 {
+        if let Some(time) = self.fixed_time {
+            return time
+        }
+        self.timers.time_now()
+    }
+    
+    pub fn advance_time(&mut self, seconds: f64) {
+        if let Some(time) = &mut self.fixed_time {
+            *time += seconds;
+        }
+    }
+    
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+impl Cx {
+    pub fn event_loop(cx: Rc<RefCell<Cx >>) {
+
+        let mut cx = cx.borrow_mut();
+
+        let mut headless_app = HeadlessApp::from_args();
+        cx.headless_startup();
+        headless_app.timers.start_timer(0, 0.008, true);
+
+        let mut event_flow = EventFlow::Poll;
+        let mut timer_ids = Vec::new();
+
+        while event_flow != EventFlow::Exit {
+            if event_flow == EventFlow::Wait {
+                // with a frame limit an idle app will not produce more frames
+                if headless_app.max_frames.is_some() && headless_app.frames > 0 {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(8));
+            }
+            headless_app.timers.update_timers(&mut timer_ids);
+            let time = headless_app.time_now();
+            for timer_id in &timer_ids {
+                cx.headless_event_callback(
+                    &mut headless_app,
+                    HeadlessEvent::Timer(TimerEvent {
+                        timer_id: *timer_id,
+                        time:Some(time)
+                    })
+                );
+            }
+            event_flow = cx.headless_event_callback(&mut headless_app, HeadlessEvent::Paint);
+        }
+        cx.call_event_handler(&Event::Shutdown);
+    }
+
+    pub fn headless_startup(&mut self) {
+        self.os_type = OsType::LinuxHeadless;
+        self.gpu_info.performance = GpuPerformance::Tier1;
+        self.call_event_handler(&Event::Startup);
+        self.redraw_all();
+    }
+    
+    /// Runs frames until the app goes idle or `max_frames` have been painted. Every
+    /// frame advances the clock of a fixed time app by 1/60th of a second.
+    pub fn headless_settle(&mut self, headless_app: &mut HeadlessApp, max_frames: usize) -> EventFlow {
+        for _ in 0..max_frames {
+            let time = headless_app.time_now();
+            self.headless_event_callback(headless_app, HeadlessEvent::Timer(TimerEvent {
+                timer_id: 0,
+                time: Some(time)
+            }));
+            let event_flow = self.headless_event_callback(headless_app, HeadlessEvent::Paint);
+            if event_flow != EventFlow::Poll {
+                return event_flow
+            }
+            headless_app.advance_time(1.0 / 60.0);
+        }
+        EventFlow::Poll
+    }
+    
+    pub fn headless_event_callback(
+        &mut self,
+        headless_app: &mut HeadlessApp,
+        event: HeadlessEvent,
+    ) -> EventFlow {
+        if let EventFlow::Exit = self.handle_platform_ops(headless_app) {
+            return EventFlow::Exit
+        }
+
+        match event {
+            HeadlessEvent::Paint => {
+                if self.new_next_frames.len() != 0 {
+                    self.call_next_frame_event(headless_app.time_now());
+                }
+                if self.need_redrawing() {
+                    self.call_draw_event();
+                    self.software_compile_shaders();
+                }
+                if self.handle_repaint(headless_app) {
+                    headless_app.frames += 1;
+                    if let Some(dump_dir) = &headless_app.dump_dir {
+                        let path = dump_dir.join(format!("frame_{:05}.png", headless_app.frames));
+                        if let Err(err) = self.write_headless_frame_png(&path) {
+                            crate::error!("Cannot write frame {}: {}", path.display(), err);
+                        }
+                    }
+                    if let Some(max_frames) = headless_app.max_frames {
+                        if headless_app.frames >= max_frames {
+                            return EventFlow::Exit
+                        }
+                    }
+                }
+            }
+            HeadlessEvent::MouseDown(e) => {
+                self.fingers.process_tap_count(
+                    e.abs,
+                    e.time
+                );
+                self.fingers.mouse_down(e.button, CxWindowPool::id_zero());
+                self.call_event_handler(&Event::MouseDown(e))
+            }
+            HeadlessEvent::MouseMove(e) => {
+                self.call_event_handler(&Event::MouseMove(e));
+                self.fingers.cycle_hover_area(live_id!(mouse).into());
+                self.fingers.switch_captures();
+            }
+            HeadlessEvent::MouseUp(e) => {
+                let button = e.button;
+                self.call_event_handler(&Event::MouseUp(e));
+                self.fingers.mouse_up(button);
+                self.fingers.cycle_hover_area(live_id!(mouse).into());
+            }
+            HeadlessEvent::Scroll(e) => {
+                self.call_event_handler(&Event::Scroll(e))
+            }
+            HeadlessEvent::KeyDown(e) => {
+                self.keyboard.process_key_down(e.clone());
+                self.call_event_handler(&Event::KeyDown(e))
+            }
+            HeadlessEvent::KeyUp(e) => {
+                self.keyboard.process_key_up(e.clone());
+                self.call_event_handler(&Event::KeyUp(e))
+            }
+            HeadlessEvent::TextInput(e) => {
+                self.call_event_handler(&Event::TextInput(e))
+            }
+            HeadlessEvent::Timer(e) => {
+                if e.timer_id == 0 {
+                    if SignalToUI::check_and_clear_ui_signal() {
+                        self.handle_media_signals();
+                        self.call_event_handler(&Event::Signal);
+                    }
+                    self.handle_action_receiver();
+                    self.handle_networking_events();
+                }
+                else {
+                    self.call_event_handler(&Event::Timer(e))
+                }
+
+                if self.handle_live_edit() {
+                    self.call_event_handler(&Event::LiveEdit);
+                    self.redraw_all();
+                }
+            }
+        }
+        if self.any_passes_dirty() || self.need_redrawing() || self.new_next_frames.len() != 0 {
+            EventFlow::Poll
+        } else {
+            EventFlow::Wait
+        }
+    }
+
+    /// The last frame rendered into the headless framebuffer.
+    pub fn headless_frame(&self) -> ImageBuffer {
+        let framebuffer = &self.os.framebuffer;
+        ImageBuffer {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            data: framebuffer.to_bgra_u32(),
+        }
+    }
+
+    pub fn write_headless_frame_png(&self, path: &std::path::Path) -> Result<(), String> {
+        let data = png::encode(&self.headless_frame())?;
+        std::fs::write(path, data).map_err(|err| err.to_string())
+    }
+
+    pub fn draw_pass_to_fullscreen(
+        &mut self,
+        pass_id: PassId,
+        width: usize,
+        height: usize,
+    ) {
+        let draw_list_id = self.passes[pass_id].main_draw_list_id.unwrap();
+
+        self.setup_render_pass(pass_id);
+
+        let clear_color = if self.passes[pass_id].color_textures.len() == 0 {
+            self.passes[pass_id].clear_color
+        }
+        else {
+            match self.passes[pass_id].color_textures[0].clear_color {
+                PassClearColor::InitWith(color) => color,
+                PassClearColor::ClearWith(color) => color
+            }
+        };
+        let clear_depth = match self.passes[pass_id].clear_depth {
+            PassClearDepth::InitWith(depth) => depth,
+            PassClearDepth::ClearWith(depth) => depth
+        };
+
+        let framebuffer = &mut self.os.framebuffer;
+        framebuffer.resize(width, height);
+        if !self.passes[pass_id].dont_clear {
+            framebuffer.clear_color(clear_color);
+            framebuffer.clear_depth(clear_depth as f32);
+        }
+        let mut target = SoftwareTarget::new(
+            width,
+            height,
+            std::mem::take(&mut framebuffer.color),
+            Some(std::mem::take(&mut framebuffer.depth)),
+            true
+        );
+
+        let mut zbias = 0.0;
+        let zbias_step = self.passes[pass_id].zbias_step;
+
+        self.render_view(
+            pass_id,
+            draw_list_id,
+            &mut zbias,
+            zbias_step,
+            &mut target,
+        );
+
+        self.os.framebuffer.color = target.color;
+        self.os.framebuffer.depth = target.depth.unwrap();
+    }
+
+    pub (crate) fn handle_repaint(&mut self, headless_app: &mut HeadlessApp) -> bool {
+        let mut passes_todo = Vec::new();
+        self.compute_pass_repaint_order(&mut passes_todo);
+        self.repaint_id += 1;
+        let mut painted_window = false;
+        for pass_id in &passes_todo {
+            self.passes[*pass_id].set_time(headless_app.time_now() as f32);
+            match self.passes[*pass_id].parent.clone() {
+                CxPassParent::Window(_window_id) => {
+                    self.draw_pass_to_fullscreen(*pass_id, headless_app.width, headless_app.height);
+                    painted_window = true;
+                }
+                CxPassParent::Pass(_) => {
+                    self.draw_pass_to_magic_texture(*pass_id);
+                },
+                CxPassParent::None => {
+                    self.draw_pass_to_magic_texture(*pass_id);
+                }
+            }
+        }
+        painted_window
+    }
+
+    pub(crate) fn handle_networking_events(&mut self) {
+        let mut out = Vec::new();
+        while let Ok(item) = self.os.network_response.receiver.try_recv() {
+            self.os.http_requests.handle_response_item(&item);
+            out.push(item);
+        }
+        if out.len()>0 {
+            self.call_event_handler(&Event::NetworkResponses(out))
+        }
+    }
+
+    fn handle_platform_ops(&mut self, headless_app: &mut HeadlessApp) -> EventFlow {
+        while let Some(op) = self.platform_ops.pop() {
+            match op {
+                CxOsOp::CreateWindow(window_id) => {
+                    let window = &mut self.windows[window_id];
+                    let size = dvec2(headless_app.width as f64 / headless_app.dpi_factor, headless_app.height as f64 / headless_app.dpi_factor);
+                    window.window_geom = WindowGeom {
+                        dpi_factor: headless_app.dpi_factor,
+                        can_fullscreen: false,
+                        xr_is_presenting: false,
+                        is_fullscreen: true,
+                        is_topmost: true,
+                        position: dvec2(0.0, 0.0),
+                        inner_size: size,
+                        outer_size: size
+                    };
+                    window.is_created = true;
+                },
+                CxOsOp::Quit => {
+                    return EventFlow::Exit
+                },
+                CxOsOp::StartTimer {timer_id, interval, repeats} => {
+                    headless_app.timers.start_timer(timer_id, interval, repeats);
+                },
+                CxOsOp::StopTimer(timer_id) => {
+                    headless_app.timers.stop_timer(timer_id);
+                },
+                CxOsOp::HttpRequest{request_id, request} => {
+                    self.os.http_requests.make_http_request(request_id, request, self.os.network_response.sender.clone());
+                },
+                CxOsOp::CancelHttpRequest {request_id} => {
+                    self.os.http_requests.cancel_http_request(request_id);
+                },
+                _ => ()
+            }
+        }
+        EventFlow::Poll
+    }
+}
+
+impl CxOsApi for Cx {
+    fn init_cx_os(&mut self) {
+        self.live_expand();
+        if !Self::has_studio_web_socket() {
+            self.start_disk_live_file_watcher(100);
+        }
+        self.live_scan_dependencies();
+        self.native_load_dependencies();
+    }
+
+    fn spawn_thread<F>(&mut self, f: F) where F: FnOnce() + Send + 'static {
+        std::thread::spawn(f);
+    }
+
+    fn open_url(&mut self, _url:&str, _in_place:OpenUrlInPlace){
+        crate::error!("open_url not implemented on this platform");
+    }
+
+    fn seconds_since_app_start(&self)->f64{
+        Instant::now().duration_since(self.os.start_time).as_secs_f64()
+    }
+}
+
+pub struct CxOs {
+    pub (crate) media: CxLinuxMedia,
+    pub (crate) start_time: Instant,
+    pub (crate) network_response: NetworkResponseChannel,
+    pub (crate) http_requests: LinuxHttpRequests,
+    pub (crate) framebuffer: SoftwareSurface,
+}
+
+impl Default for CxOs {
+    fn default() -> Self {
+        Self {
+            start_time: Instant::now(),
+            media: Default::default(),
+            network_response: Default::default(),
+            http_requests: Default::default(),
+            framebuffer: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,628 @@
+use {
+    std::mem,
+    crate::{
+        makepad_shader_compiler::{
+            DrawShaderPtr,
+            cpu_interpreter::{CpuDrawShader, CpuShaderEnv, CpuTextureSampler, CpuDerivatives, CpuNum},
+        },
+        cx::Cx,
+        texture::{Texture, TextureId, TextureFormat, CxTexture, CxTexturePool},
+        makepad_math::{Mat4, DVec2, Vec2, Vec4},
+        pass::{PassClearColor, PassClearDepth, PassId, CxPassColorTexture},
+        draw_list::DrawListId,
+    },
+};
+
+// Renders the draw lists on the CPU. Shaders are run by the interpreter in the
+// shader compiler, triangles are rasterized in 2x2 quads so dFdx/dFdy work.
+// The state mirrors the opengl backend: LEQUAL depth test and premultiplied
+// alpha blending, rows are stored top down.
+
+impl Cx {
+
+    pub (crate) fn render_view(
+        &mut self,
+        pass_id: PassId,
+        draw_list_id: DrawListId,
+        zbias: &mut f32,
+        zbias_step: f32,
+        target: &mut SoftwareTarget,
+    ) {
+        let draw_items_len = self.draw_lists[draw_list_id].draw_items.len();
+        self.draw_lists[draw_list_id].uniform_view_transform(&Mat4::identity());
+
+        for draw_item_id in 0..draw_items_len {
+            if let Some(sub_list_id) = self.draw_lists[draw_list_id].draw_items[draw_item_id].kind.sub_list() {
+                self.render_view(
+                    pass_id,
+                    sub_list_id,
+                    zbias,
+                    zbias_step,
+                    target,
+                );
+                continue;
+            }
+            let draw_list = &mut self.draw_lists[draw_list_id];
+            let draw_item = &mut draw_list.draw_items[draw_item_id];
+
+            let draw_call = if let Some(draw_call) = draw_item.kind.draw_call_mut() {
+                draw_call
+            }else {
+                continue;
+            };
+
+            let sh = &self.draw_shaders.shaders[draw_call.draw_shader.draw_shader_id];
+            let os_shader_id = if let Some(os_shader_id) = sh.os_shader_id {
+                os_shader_id
+            }
+            else { // shader didnt compile somehow
+                continue;
+            };
+
+            draw_call.instance_dirty = false;
+            draw_call.uniforms_dirty = false;
+            draw_call.draw_uniforms.set_zbias(*zbias);
+            *zbias += zbias_step;
+
+            let instances = draw_item.instances.as_ref().unwrap();
+            let instance_slots = sh.mapping.instances.total_slots;
+            if instance_slots == 0 || instances.len() < instance_slots {
+                continue;
+            }
+
+            let geometry_id = if let Some(geometry_id) = draw_call.geometry_id {geometry_id}
+            else {
+                continue;
+            };
+            self.geometries[geometry_id].dirty = false;
+            let geometry = &self.geometries[geometry_id];
+
+            let sampler = SoftwareSampler {
+                textures: &self.textures,
+                slots: (0..sh.mapping.textures.len()).map( | i | {
+                    draw_call.texture_slots[i].as_ref().map( | texture | texture.texture_id())
+                }).collect()
+            };
+            let env = CpuShaderEnv {
+                registry: &self.shader_registry,
+                const_table: &sh.mapping.const_table,
+                pass_uniforms: &self.passes[pass_id].pass_uniforms.as_slice()[..],
+                view_uniforms: &draw_list.draw_list_uniforms.as_slice()[..],
+                draw_uniforms: &draw_call.draw_uniforms.as_slice()[..],
+                user_uniforms: &draw_call.user_uniforms,
+                live_uniforms: &sh.mapping.live_uniforms_buf,
+                sampler: &sampler,
+            };
+            target.draw_instanced(
+                &self.draw_shaders.os_shaders[os_shader_id].cpu_shader,
+                &env,
+                &geometry.vertices,
+                sh.mapping.geometries.total_slots,
+                &geometry.indices,
+                instances,
+                instance_slots,
+            );
+        }
+    }
+
+    pub fn setup_render_pass(&mut self, pass_id: PassId,) -> Option<DVec2> {
+        let dpi_factor = self.passes[pass_id].dpi_factor.unwrap();
+        let pass_rect = self.get_pass_rect(pass_id, dpi_factor).unwrap();
+        self.passes[pass_id].paint_dirty = false;
+
+        if pass_rect.size.x <0.5 || pass_rect.size.y < 0.5 {
+            return None
+        }
+
+        self.passes[pass_id].set_matrix(pass_rect.pos, pass_rect.size);
+        self.passes[pass_id].set_dpi_factor(dpi_factor);
+        Some(pass_rect.size)
+    }
+
+    pub fn draw_pass_to_texture(&mut self, pass_id: PassId, texture: &Texture) {
+        self.draw_pass_to_texture_inner(pass_id, Some(texture))
+    }
+
+    pub fn draw_pass_to_magic_texture(&mut self, pass_id: PassId) {
+        self.draw_pass_to_texture_inner(pass_id, None)
+    }
+
+    fn draw_pass_to_texture_inner(
+        &mut self,
+        pass_id: PassId,
+        maybe_texture: Option<&Texture>,
+    ) {
+        let draw_list_id = self.passes[pass_id].main_draw_list_id.unwrap();
+
+        let pass_size = if let Some(pz) = self.setup_render_pass(pass_id) {
+            pz
+        }
+        else {
+            return
+        };
+
+        let dpi_factor = self.passes[pass_id].dpi_factor.unwrap();
+        let size = dpi_factor * pass_size;
+        let (width, height) = (size.x as usize, size.y as usize);
+
+        let color_textures = if let Some(texture) = maybe_texture {
+            vec![CxPassColorTexture {
+                clear_color: PassClearColor::ClearWith(self.passes[pass_id].clear_color),
+                texture: texture.clone(),
+            }]
+        }
+        else {
+            self.passes[pass_id].color_textures.clone()
+        };
+
+        // there is only one color output, additional attachments are just cleared
+        let mut target = SoftwareTarget::default();
+        for (index, color_texture) in color_textures.iter().enumerate() {
+            let cxtexture = &mut self.textures[color_texture.texture.texture_id()];
+            cxtexture.update_render_target(width, height);
+            match color_texture.clear_color {
+                PassClearColor::InitWith(clear_color) => {
+                    if cxtexture.take_initial() {
+                        cxtexture.os.surface.clear_color(clear_color);
+                    }
+                },
+                PassClearColor::ClearWith(clear_color) => {
+                    cxtexture.os.surface.clear_color(clear_color);
+                }
+            }
+            if index == 0 {
+                target.width = width;
+                target.height = height;
+                target.clamp = matches!(cxtexture.format, TextureFormat::RenderBGRAu8 {..});
+                target.color = mem::take(&mut cxtexture.os.surface.color);
+            }
+        }
+
+        if let Some(depth_texture) = &self.passes[pass_id].depth_texture {
+            let cxtexture = &mut self.textures[depth_texture.texture_id()];
+            cxtexture.update_depth_stencil(width, height);
+            match self.passes[pass_id].clear_depth {
+                PassClearDepth::InitWith(clear_depth) => {
+                    if cxtexture.take_initial() {
+                        cxtexture.os.surface.clear_depth(clear_depth as f32);
+                    }
+                },
+                PassClearDepth::ClearWith(clear_depth) => {
+                    cxtexture.os.surface.clear_depth(clear_depth as f32);
+                }
+            }
+            target.depth = Some(mem::take(&mut cxtexture.os.surface.depth));
+        }
+
+        let mut zbias = 0.0;
+        let zbias_step = self.passes[pass_id].zbias_step;
+
+        self.render_view(
+            pass_id,
+            draw_list_id,
+            &mut zbias,
+            zbias_step,
+            &mut target,
+        );
+
+        if let Some(color_texture) = color_textures.first() {
+            self.textures[color_texture.texture.texture_id()].os.surface.color = mem::take(&mut target.color);
+        }
+        if let Some(depth_texture) = &self.passes[pass_id].depth_texture {
+            self.textures[depth_texture.texture_id()].os.surface.depth = target.depth.take().unwrap();
+        }
+    }
+
+    pub fn software_compile_shaders(&mut self) {
+        for draw_shader_ptr in &self.draw_shaders.compile_set {
+            if let Some(item) = self.draw_shaders.ptr_to_item.get(&draw_shader_ptr) {
+                let cx_shader = &mut self.draw_shaders.shaders[item.draw_shader_id];
+                let draw_shader_def = self.shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+                let cpu_shader = if let Some(cpu_shader) = CpuDrawShader::new(draw_shader_def, &self.shader_registry) {
+                    cpu_shader
+                }
+                else {
+                    continue;
+                };
+                // recompiles of the same shader replace the previous one
+                let os_shaders = &mut self.draw_shaders.os_shaders;
+                if let Some(index) = os_shaders.iter().position( | ds | ds.draw_shader_ptr == *draw_shader_ptr) {
+                    os_shaders[index].cpu_shader = cpu_shader;
+                    cx_shader.os_shader_id = Some(index);
+                }
+                else {
+                    cx_shader.os_shader_id = Some(os_shaders.len());
+                    os_shaders.push(CxOsDrawShader {
+                        draw_shader_ptr: *draw_shader_ptr,
+                        cpu_shader
+                    });
+                }
+            }
+        }
+        self.draw_shaders.compile_set.clear();
+    }
+}
+
+impl CxTexture {
+    fn update_render_target(&mut self, width: usize, height: usize) {
+        if self.alloc_render(width, height) || self.os.surface.color.len() != width * height {
+            self.os.surface.width = width;
+            self.os.surface.height = height;
+            self.os.surface.color = vec![Vec4::default(); width * height];
+        }
+    }
+
+    fn update_depth_stencil(&mut self, width: usize, height: usize) {
+        if self.alloc_depth(width, height) || self.os.surface.depth.len() != width * height {
+            self.os.surface.width = width;
+            self.os.surface.height = height;
+            self.os.surface.depth = vec![1.0; width * height];
+        }
+    }
+}
+
+pub struct CxOsDrawShader {
+    pub draw_shader_ptr: DrawShaderPtr,
+    pub cpu_shader: CpuDrawShader,
+}
+
+#[derive(Default, Clone)]
+pub struct CxOsGeometry {
+}
+
+#[derive(Default, Clone)]
+pub struct CxOsView {
+}
+
+#[derive(Default, Clone)]
+pub struct CxOsDrawCall {
+}
+
+#[derive(Default, Clone)]
+pub struct CxOsPass {
+}
+
+#[derive(Default, Clone)]
+pub struct CxOsTexture {
+    pub surface: SoftwareSurface,
+}
+
+/// A CPU side render target, colors are premultiplied like on the gpu.
+#[derive(Default, Clone)]
+pub struct SoftwareSurface {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Vec4>,
+    pub depth: Vec<f32>,
+}
+
+impl SoftwareSurface {
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if self.width != width || self.height != height || self.color.len() != width * height {
+            self.width = width;
+            self.height = height;
+            self.color = vec![Vec4::default(); width * height];
+            self.depth = vec![1.0; width * height];
+        }
+    }
+
+    pub fn clear_color(&mut self, color: Vec4) {
+        self.color.iter_mut().for_each( | c | *c = color);
+    }
+
+    pub fn clear_depth(&mut self, depth: f32) {
+        self.depth.iter_mut().for_each( | d | *d = depth);
+    }
+
+    /// Returns the color buffer as BGRA u32 pixels (A<<24|R<<16|G<<8|B).
+    pub fn to_bgra_u32(&self) -> Vec<u32> {
+        let to_u8 = | v: f32 | (v.max(0.0).min(1.0) * 255.0 + 0.5) as u32;
+        self.color.iter().map( | c | {
+            (to_u8(c.w) << 24) | (to_u8(c.x) << 16) | (to_u8(c.y) << 8) | to_u8(c.z)
+        }).collect()
+    }
+}
+
+struct SoftwareSampler<'a> {
+    textures: &'a CxTexturePool,
+    slots: Vec<Option<TextureId >>,
+}
+
+impl<'a> CpuTextureSampler for SoftwareSampler<'a> {
+    fn sample_2d(&self, slot: usize, pos: Vec2) -> Vec4 {
+        if let Some(Some(texture_id)) = self.slots.get(slot) {
+            sample_texture(&self.textures[*texture_id], pos)
+        }
+        else {
+            Vec4::default()
+        }
+    }
+}
+
+fn bilinear(width: usize, height: usize, pos: Vec2, texel: impl Fn(usize, usize) -> Vec4) -> Vec4 {
+    if width == 0 || height == 0 || !pos.x.is_finite() || !pos.y.is_finite() {
+        return Vec4::default()
+    }
+    let u = pos.x * width as f32 - 0.5;
+    let v = pos.y * height as f32 - 0.5;
+    let (fx, fy) = (u - u.floor(), v - v.floor());
+    let clamp_x = | x: f32 | (x.max(0.0) as usize).min(width - 1);
+    let clamp_y = | y: f32 | (y.max(0.0) as usize).min(height - 1);
+    let (x0, x1) = (clamp_x(u.floor()), clamp_x(u.floor() + 1.0));
+    let (y0, y1) = (clamp_y(v.floor()), clamp_y(v.floor() + 1.0));
+    let top = mix(texel(x0, y0), texel(x1, y0), fx);
+    let bottom = mix(texel(x0, y1), texel(x1, y1), fx);
+    mix(top, bottom, fy)
+}
+
+fn mix(a: Vec4, b: Vec4, t: f32) -> Vec4 {
+    Vec4 {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        z: a.z + (b.z - a.z) * t,
+        w: a.w + (b.w - a.w) * t,
+    }
+}
+
+fn bgra_to_vec4(c: u32) -> Vec4 {
+    Vec4 {
+        x: ((c >> 16) & 0xff) as f32 / 255.0,
+        y: ((c >> 8) & 0xff) as f32 / 255.0,
+        z: (c & 0xff) as f32 / 255.0,
+        w: ((c >> 24) & 0xff) as f32 / 255.0,
+    }
+}
+
+fn sample_texture(texture: &CxTexture, pos: Vec2) -> Vec4 {
+    match &texture.format {
+        TextureFormat::VecBGRAu8_32 {width, height, data: Some(data), ..} |
+        TextureFormat::VecMipBGRAu8_32 {width, height, data: Some(data), ..} => {
+            bilinear(*width, *height, pos, | x, y | bgra_to_vec4(data[y * width + x]))
+        }
+        TextureFormat::VecRGBAf32 {width, height, data: Some(data), ..} => {
+            bilinear(*width, *height, pos, | x, y | {
+                let i = (y * width + x) * 4;
+                Vec4 {x: data[i], y: data[i + 1], z: data[i + 2], w: data[i + 3]}
+            })
+        }
+        TextureFormat::VecRu8 {width, height, data: Some(data), unpack_row_length, ..} => {
+            let stride = unpack_row_length.unwrap_or(*width);
+            bilinear(*width, *height, pos, | x, y | {
+                Vec4 {x: data[y * stride + x] as f32 / 255.0, y: 0.0, z: 0.0, w: 1.0}
+            })
+        }
+        TextureFormat::VecRGu8 {width, height, data: Some(data), unpack_row_length, ..} => {
+            let stride = unpack_row_length.unwrap_or(*width);
+            bilinear(*width, *height, pos, | x, y | {
+                let i = (y * stride + x) * 2;
+                Vec4 {x: data[i] as f32 / 255.0, y: data[i + 1] as f32 / 255.0, z: 0.0, w: 1.0}
+            })
+        }
+        TextureFormat::VecRf32 {width, height, data: Some(data), ..} => {
+            bilinear(*width, *height, pos, | x, y | Vec4 {x: data[y * width + x], y: 0.0, z: 0.0, w: 1.0})
+        }
+        TextureFormat::RenderBGRAu8 {..} | TextureFormat::RenderRGBAf16 {..} | TextureFormat::RenderRGBAf32 {..} => {
+            // render targets are sampled nearest like the opengl backend sets them up
+            let surface = &texture.os.surface;
+            if surface.color.len() != surface.width * surface.height || surface.color.is_empty() || !pos.x.is_finite() || !pos.y.is_finite() {
+                return Vec4::default()
+            }
+            let x = ((pos.x * surface.width as f32).max(0.0) as usize).min(surface.width - 1);
+            let y = ((pos.y * surface.height as f32).max(0.0) as usize).min(surface.height - 1);
+            surface.color[y * surface.width + x]
+        }
+        _ => Vec4::default()
+    }
+}
+
+#[derive(Clone, Default)]
+struct RasterVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+    // varyings divided by w for perspective correct interpolation
+    varyings: Vec<f32>,
+}
+
+#[derive(Default)]
+pub struct SoftwareTarget {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Vec4>,
+    pub depth: Option<Vec<f32 >>,
+    pub clamp: bool,
+    vertices: Vec<RasterVertex>,
+    varyings: Vec<f32>,
+    quad_varyings: [Vec<f32>; 4],
+    quad_traces: [Vec<CpuNum>; 4],
+    trace: Vec<CpuNum>,
+}
+
+// pixel offsets within a 2x2 quad, in the order left-top, right-top, left-bottom, right-bottom
+const QUAD: [(usize, usize); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+fn edge(ax: f32, ay: f32, bx: f32, by: f32, px: f32, py: f32) -> f32 {
+    (bx - ax) * (py - ay) - (by - ay) * (px - ax)
+}
+
+// top-left fill rule so pixels on edges shared by two triangles are drawn once
+fn is_top_left(ax: f32, ay: f32, bx: f32, by: f32) -> bool {
+    let (dx, dy) = (bx - ax, by - ay);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+impl SoftwareTarget {
+    pub fn new(width: usize, height: usize, color: Vec<Vec4>, depth: Option<Vec<f32 >>, clamp: bool) -> Self {
+        Self {
+            width,
+            height,
+            color,
+            depth,
+            clamp,
+            ..Default::default()
+        }
+    }
+
+    fn draw_instanced(
+        &mut self,
+        shader: &CpuDrawShader,
+        env: &CpuShaderEnv,
+        vertices: &[f32],
+        geometry_slots: usize,
+        indices: &[u32],
+        instances: &[f32],
+        instance_slots: usize,
+    ) {
+        if geometry_slots == 0 || self.width == 0 || self.height == 0 {
+            return
+        }
+        let vertex_count = vertices.len() / geometry_slots;
+        let mut raster_vertices = mem::take(&mut self.vertices);
+        raster_vertices.resize(vertex_count, RasterVertex::default());
+
+        for instance in instances.chunks_exact(instance_slots) {
+            for (index, raster_vertex) in raster_vertices.iter_mut().enumerate() {
+                let geometry = &vertices[index * geometry_slots..(index + 1) * geometry_slots];
+                let clip = shader.vertex(env, geometry, instance, &mut self.varyings);
+                let inv_w = if clip.w > 0.0 {1.0 / clip.w} else {0.0};
+                raster_vertex.x = (clip.x * inv_w * 0.5 + 0.5) * self.width as f32;
+                raster_vertex.y = (0.5 - clip.y * inv_w * 0.5) * self.height as f32;
+                raster_vertex.z = clip.z * inv_w;
+                raster_vertex.inv_w = inv_w;
+                raster_vertex.varyings.clear();
+                raster_vertex.varyings.extend(self.varyings.iter().map( | v | v * inv_w));
+            }
+            for triangle in indices.chunks_exact(3) {
+                let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+                if a < vertex_count && b < vertex_count && c < vertex_count {
+                    self.draw_triangle(shader, env, [&raster_vertices[a], &raster_vertices[b], &raster_vertices[c]]);
+                }
+            }
+        }
+        self.vertices = raster_vertices;
+    }
+
+    fn draw_triangle(&mut self, shader: &CpuDrawShader, env: &CpuShaderEnv, mut v: [&RasterVertex; 3]) {
+        // no clipping against the near plane, triangles behind the camera are dropped
+        if v.iter().any( | v | v.inv_w <= 0.0) {
+            return
+        }
+        let mut area = edge(v[0].x, v[0].y, v[1].x, v[1].y, v[2].x, v[2].y);
+        if area == 0.0 || !area.is_finite() {
+            return
+        }
+        if area < 0.0 {
+            v.swap(1, 2);
+            area = -area;
+        }
+        let min_x = v.iter().fold(f32::MAX, | m, v | m.min(v.x)).floor().max(0.0) as usize;
+        let min_y = v.iter().fold(f32::MAX, | m, v | m.min(v.y)).floor().max(0.0) as usize;
+        let max_x = (v.iter().fold(f32::MIN, | m, v | m.max(v.x)).ceil().max(0.0) as usize).min(self.width);
+        let max_y = (v.iter().fold(f32::MIN, | m, v | m.max(v.y)).ceil().max(0.0) as usize).min(self.height);
+        if min_x >= max_x || min_y >= max_y {
+            return
+        }
+
+        let edges = [(1, 2), (2, 0), (0, 1)];
+        let top_left = edges.map( | (a, b) | is_top_left(v[a].x, v[a].y, v[b].x, v[b].y));
+        let varying_slots = v[0].varyings.len();
+
+        let mut qy = min_y & !1;
+        while qy < max_y {
+            let mut qx = min_x & !1;
+            while qx < max_x {
+                let mut bary = [[0.0f32; 3]; 4];
+                let mut covered = [false; 4];
+                for (q, (dx, dy)) in QUAD.iter().enumerate() {
+                    let (x, y) = (qx + dx, qy + dy);
+                    let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                    let mut inside = x < self.width && y < self.height;
+                    for (i, (a, b)) in edges.iter().enumerate() {
+                        let e = edge(v[*a].x, v[*a].y, v[*b].x, v[*b].y, px, py);
+                        bary[q][i] = e / area;
+                        if e < 0.0 || (e == 0.0 && !top_left[i]) {
+                            inside = false;
+                        }
+                    }
+                    covered[q] = inside;
+                }
+                if covered.iter().any( | c | *c) {
+                    for q in 0..4 {
+                        if !covered[q] && !shader.uses_derivatives {
+                            continue;
+                        }
+                        let b = bary[q];
+                        let inv_w = b[0] * v[0].inv_w + b[1] * v[1].inv_w + b[2] * v[2].inv_w;
+                        let varyings = &mut self.quad_varyings[q];
+                        varyings.clear();
+                        for i in 0..varying_slots {
+                            let value = b[0] * v[0].varyings[i] + b[1] * v[1].varyings[i] + b[2] * v[2].varyings[i];
+                            varyings.push(if inv_w != 0.0 {value / inv_w} else {0.0});
+                        }
+                        if shader.uses_derivatives {
+                            // first pass records the dFdx/dFdy arguments of the whole quad
+                            shader.pixel(env, varyings, None, &mut self.quad_traces[q]);
+                        }
+                    }
+                    for q in 0..4 {
+                        if !covered[q] {
+                            continue;
+                        }
+                        let (dx, dy) = QUAD[q];
+                        let b = bary[q];
+                        let z = b[0] * v[0].z + b[1] * v[1].z + b[2] * v[2].z;
+                        let color = if shader.uses_derivatives {
+                            let derivatives = CpuDerivatives {
+                                left: &self.quad_traces[dy * 2],
+                                right: &self.quad_traces[dy * 2 + 1],
+                                top: &self.quad_traces[dx],
+                                bottom: &self.quad_traces[2 + dx],
+                            };
+                            shader.pixel(env, &self.quad_varyings[q], Some(&derivatives), &mut self.trace)
+                        }
+                        else {
+                            shader.pixel(env, &self.quad_varyings[q], None, &mut self.trace)
+                        };
+                        self.write_pixel(qx + dx, qy + dy, z * 0.5 + 0.5, color);
+                    }
+                }
+                qx += 2;
+            }
+            qy += 2;
+        }
+    }
+
+    fn write_pixel(&mut self, x: usize, y: usize, depth: f32, color: Vec4) {
+        if !(0.0..=1.0).contains(&depth) {
+            return
+        }
+        let index = y * self.width + x;
+        if let Some(depth_buffer) = &mut self.depth {
+            if depth > depth_buffer[index] {
+                return
+            }
+            depth_buffer[index] = depth;
+        }
+        let src = if self.clamp {
+            Vec4 {
+                x: color.x.max(0.0).min(1.0),
+                y: color.y.max(0.0).min(1.0),
+                z: color.z.max(0.0).min(1.0),
+                w: color.w.max(0.0).min(1.0),
+            }
+        }
+        else {
+            color
+        };
+        // premultiplied alpha: ONE, ONE_MINUS_SRC_ALPHA
+        let dst = &mut self.color[index];
+        let f = 1.0 - src.w;
+        *dst = Vec4 {
+            x: src.x + dst.x * f,
+            y: src.y + dst.y * f,
+            z: src.z + dst.z * f,
+            w: src.w + dst.w * f,
+        };
+    }
+}
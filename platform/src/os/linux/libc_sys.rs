@@ -21,7 +21,7 @@ pub type time_t = c_ulong;
 pub type suseconds_t = c_ulong;
 
 type c_int =  std::os::raw::c_int;
-//type c_uint =  std::os::raw::c_uint;
+type c_uint =  std::os::raw::c_uint;
 type c_ulong = std::os::raw::c_ulong;
 type c_void = std::os::raw::c_void;
 type c_char = std::os::raw::c_char;
@@ -30,12 +30,24 @@ type size_t = usize;
 pub const FD_SETSIZE: usize = 1024;
 pub const EPIPE: c_int = 32;
 pub const O_RDWR: c_int = 2;
+pub const LC_CTYPE: c_int = 0;
+pub const POLLIN: i16 = 1;
+pub const PROT_READ: c_int = 1;
+pub const MAP_PRIVATE: c_int = 2;
+pub const MAP_FAILED: *mut c_void = !0 as *mut c_void;
 
 #[repr(C)]
 pub struct fd_set {
     fds_bits: [c_ulong; FD_SETSIZE / ULONG_SIZE],
 }
 
+#[repr(C)]
+pub struct pollfd {
+    pub fd: c_int,
+    pub events: i16,
+    pub revents: i16,
+}
+
 pub const RTLD_LAZY: c_int = 1;
 pub const RTLD_LOCAL: c_int = 0;
     
@@ -55,6 +67,12 @@ extern "C"{
         timeout: *mut timeval,
     ) -> c_int;
     pub fn read(fd: c_int, buf: *mut c_void, count: size_t) -> c_int;
+    pub fn write(fd: c_int, buf: *const c_void, count: size_t) -> isize;
+    pub fn poll(fds: *mut pollfd, nfds: c_ulong, timeout: c_int) -> c_int;
+    pub fn mmap(addr: *mut c_void, len: size_t, prot: c_int, flags: c_int, fd: c_int, offset: i64) -> *mut c_void;
+    pub fn munmap(addr: *mut c_void, len: size_t) -> c_int;
+    pub fn getuid() -> c_uint;
+    pub fn setlocale(category: c_int, locale: *const c_char) -> *mut c_char;
 }
 
 pub unsafe fn FD_SET(fd: c_int, set: *mut fd_set) -> () {
@@ -0,0 +1,473 @@
+use {
+    std::{
+        io::{Read, Write},
+        net::{TcpStream, Shutdown},
+        sync::{
+            Arc,
+            Mutex,
+            atomic::{AtomicBool, Ordering},
+            mpsc::Sender,
+        },
+    },
+    crate::{
+        makepad_live_id::*,
+        event::{
+            NetworkResponseItem,
+            NetworkResponse,
+            HttpError,
+            HttpMethod,
+            HttpProgress,
+            HttpRequest,
+            HttpResponse
+        },
+    }
+};
+
+/// A bidirectional byte stream a request can be sent over, a plain tcp socket or a tls session
+pub trait HttpStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> HttpStream for T {}
+
+/// Wraps a connected tcp socket in a tls session for `https` urls.
+/// Linux has no system tls stack we can rely on, so applications that need https
+/// plug one in with `set_https_connector`
+pub type HttpsConnector = fn(host: &str, stream: TcpStream, ignore_ssl_cert: bool) -> Result<Box<dyn HttpStream>, String>;
+
+static HTTPS_CONNECTOR: Mutex<Option<HttpsConnector >> = Mutex::new(None);
+
+pub fn set_https_connector(connector: HttpsConnector) {
+    *HTTPS_CONNECTOR.lock().unwrap() = Some(connector);
+}
+
+struct HttpReq {
+    request_id: LiveId,
+    cancel: Arc<AtomicBool>,
+    socket: Arc<Mutex<Option<TcpStream >> >,
+}
+
+#[derive(Default)]
+pub struct LinuxHttpRequests {
+    requests: Vec<HttpReq>
+}
+
+impl LinuxHttpRequests {
+    pub fn cancel_http_request(&mut self, request_id: LiveId) {
+        self.requests.retain( | v | {
+            if v.request_id == request_id {
+                v.cancel.store(true, Ordering::SeqCst);
+                // unblock the request thread if its waiting on the socket
+                if let Some(socket) = v.socket.lock().unwrap().take() {
+                    let _ = socket.shutdown(Shutdown::Both);
+                }
+                false
+            }
+            else {
+                true
+            }
+        })
+    }
+
+    pub fn handle_response_item(&mut self, item: &NetworkResponseItem) {
+        match &item.response {
+            NetworkResponse::HttpRequestError(_) |
+            NetworkResponse::HttpResponse(_) |
+            NetworkResponse::HttpStreamComplete(_) => {
+                self.requests.retain( | v | v.request_id != item.request_id);
+            }
+            _ => {
+            }
+        }
+    }
+
+    pub fn make_http_request(&mut self, request_id: LiveId, request: HttpRequest, networking_sender: Sender<NetworkResponseItem>) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let socket = Arc::new(Mutex::new(None));
+        self.requests.push(HttpReq {
+            request_id,
+            cancel: cancel.clone(),
+            socket: socket.clone()
+        });
+        std::thread::spawn(move || {
+            let metadata_id = request.metadata_id;
+            let mut http = HttpConnection {
+                request_id,
+                metadata_id,
+                cancel,
+                sender: networking_sender.clone()
+            };
+            if let Err(message) = http.run(request, &socket) {
+                if !http.is_cancelled() {
+                    let _ = networking_sender.send(NetworkResponseItem {
+                        request_id,
+                        response: NetworkResponse::HttpRequestError(HttpError {
+                            metadata_id,
+                            message
+                        })
+                    });
+                }
+            }
+        });
+    }
+}
+
+struct HttpConnection {
+    request_id: LiveId,
+    metadata_id: LiveId,
+    cancel: Arc<AtomicBool>,
+    sender: Sender<NetworkResponseItem>,
+}
+
+enum BodyFraming {
+    None,
+    Length(u64),
+    Chunked(ChunkedDecoder),
+    UntilClose,
+}
+
+impl HttpConnection {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    fn send(&self, response: NetworkResponse) -> Result<(), String> {
+        self.sender.send(NetworkResponseItem {
+            request_id: self.request_id,
+            response
+        }).map_err( | _ | "Network response channel closed".to_string())
+    }
+
+    fn run(&mut self, request: HttpRequest, socket: &Mutex<Option<TcpStream >>) -> Result<(), String> {
+        let split = request.split_url();
+        let tcp_stream = TcpStream::connect(format!("{}:{}", split.host, split.port))
+            .map_err( | e | format!("Error connecting to {}:{} {}", split.host, split.port, e)) ?;
+        *socket.lock().unwrap() = tcp_stream.try_clone().ok();
+        if self.is_cancelled() {
+            return Ok(())
+        }
+
+        let mut stream: Box<dyn HttpStream> = match split.proto {
+            "https" => {
+                let connector = *HTTPS_CONNECTOR.lock().unwrap();
+                if let Some(connector) = connector {
+                    connector(split.host, tcp_stream, request.ignore_ssl_cert) ?
+                }
+                else {
+                    return Err(format!("No https connector registered for {}, see linux_http::set_https_connector", request.url))
+                }
+            }
+            _ => Box::new(tcp_stream)
+        };
+
+        let host = match split.port {
+            "80" | "443" => split.host.to_string(),
+            port => format!("{}:{}", split.host, port)
+        };
+        let mut http_request = format!("{} /{} HTTP/1.1\r\nHost: {}\r\n", request.method.to_string(), split.file, host);
+        http_request.push_str(&request.get_headers_string());
+        if !request.headers.keys().any( | k | k.eq_ignore_ascii_case("connection")) {
+            http_request.push_str("Connection: close\r\n");
+        }
+        if let Some(body) = &request.body {
+            if !request.headers.keys().any( | k | k.eq_ignore_ascii_case("content-length")) {
+                http_request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+            }
+        }
+        http_request.push_str("\r\n");
+
+        stream.write_all(http_request.as_bytes()).map_err( | e | format!("Error writing http request {}", e)) ?;
+        if let Some(body) = &request.body {
+            stream.write_all(body).map_err( | e | format!("Error writing http request body {}", e)) ?;
+        }
+        stream.flush().map_err( | e | format!("Error writing http request {}", e)) ?;
+
+        // read until we have the full response head
+        let mut buffer = [0u8; 65536];
+        let mut head = Vec::new();
+        let head_end = loop {
+            if let Some(pos) = head.windows(4).position( | w | w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let bytes_read = self.read(&mut stream, &mut buffer) ?;
+            if bytes_read == 0 {
+                if self.is_cancelled() {
+                    return Ok(())
+                }
+                return Err("Connection closed before http response headers".to_string())
+            }
+            head.extend_from_slice(&buffer[0..bytes_read]);
+        };
+        let rest = head.split_off(head_end);
+        let head = String::from_utf8_lossy(&head).to_string();
+        let mut response = parse_response_head(self.metadata_id, &head) ?;
+
+        let mut framing = if request.method == HttpMethod::HEAD
+            || response.status_code < 200
+            || response.status_code == 204
+            || response.status_code == 304 {
+            BodyFraming::None
+        }
+        else if header_value(&response, "transfer-encoding").is_some_and(| v | v.to_ascii_lowercase().contains("chunked")) {
+            BodyFraming::Chunked(ChunkedDecoder::default())
+        }
+        else if let Some(len) = header_value(&response, "content-length") {
+            BodyFraming::Length(len.trim().parse().map_err( | _ | format!("Invalid content-length {}", len)) ?)
+        }
+        else {
+            BodyFraming::UntilClose
+        };
+        let total = if let BodyFraming::Length(len) = framing {len} else {0};
+
+        let mut body = Vec::new();
+        let mut loaded = 0u64;
+        let mut data = rest;
+        loop {
+            let mut decoded = Vec::new();
+            let done = match &mut framing {
+                BodyFraming::None => true,
+                BodyFraming::Length(remaining) => {
+                    let take = (*remaining).min(data.len() as u64) as usize;
+                    decoded.extend_from_slice(&data[0..take]);
+                    *remaining -= take as u64;
+                    *remaining == 0
+                }
+                BodyFraming::Chunked(decoder) => decoder.decode(&data, &mut decoded) ?,
+                BodyFraming::UntilClose => {
+                    decoded.extend_from_slice(&data);
+                    false
+                }
+            };
+
+            if !decoded.is_empty() {
+                loaded += decoded.len() as u64;
+                if request.is_streaming {
+                    self.send(NetworkResponse::HttpStreamResponse(HttpResponse {
+                        metadata_id: self.metadata_id,
+                        status_code: response.status_code,
+                        headers: response.headers.clone(),
+                        body: Some(decoded)
+                    })) ?;
+                }
+                else {
+                    body.extend_from_slice(&decoded);
+                    self.send(NetworkResponse::HttpProgress(HttpProgress {loaded, total})) ?;
+                }
+            }
+            if done {
+                break;
+            }
+
+            let bytes_read = self.read(&mut stream, &mut buffer) ?;
+            if self.is_cancelled() {
+                return Ok(())
+            }
+            if bytes_read == 0 {
+                if let BodyFraming::UntilClose = framing {
+                    break;
+                }
+                return Err("Connection closed before the http response body was complete".to_string())
+            }
+            data = buffer[0..bytes_read].to_vec();
+        }
+
+        if request.is_streaming {
+            self.send(NetworkResponse::HttpStreamComplete(HttpResponse {
+                body: None,
+                ..response
+            })) ?;
+        }
+        else {
+            response.body = Some(body);
+            self.send(NetworkResponse::HttpResponse(response)) ?;
+        }
+        Ok(())
+    }
+
+    fn read(&self, stream: &mut Box<dyn HttpStream>, buffer: &mut [u8]) -> Result<usize, String> {
+        match stream.read(buffer) {
+            Ok(bytes_read) => Ok(bytes_read),
+            // a cancel shuts the socket down underneath us, thats not an error
+            Err(_) if self.is_cancelled() => Ok(0),
+            Err(e) => Err(format!("Error reading http response {}", e))
+        }
+    }
+}
+
+fn header_value<'a>(response: &'a HttpResponse, name: &str) -> Option<&'a String> {
+    response.headers.iter().find( | (k, _) | k.eq_ignore_ascii_case(name)).and_then( | (_, v) | v.last())
+}
+
+fn parse_response_head(metadata_id: LiveId, head: &str) -> Result<HttpResponse, String> {
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or("");
+    let mut parts = status_line.splitn(3, ' ');
+    if !parts.next().unwrap_or("").starts_with("HTTP/") {
+        return Err(format!("Invalid http status line {}", status_line))
+    }
+    let status_code = parts.next().and_then( | v | v.parse().ok())
+        .ok_or_else( || format!("Invalid http status line {}", status_line)) ?;
+    let mut response = HttpResponse {
+        metadata_id,
+        status_code,
+        headers: Default::default(),
+        body: None
+    };
+    for line in lines {
+        if let Some((key, value)) = line.split_once(':') {
+            response.set_header(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    Ok(response)
+}
+
+// a size line is a hex number with optional extensions, a longer one means a broken stream
+const MAX_CHUNK_SIZE_LINE: usize = 4096;
+
+enum ChunkedState {
+    Size(Vec<u8>),
+    Data(u64),
+    DataEnd,
+    Trailer(usize),
+    Done
+}
+
+/// Incremental decoder for `Transfer-Encoding: chunked` bodies
+struct ChunkedDecoder {
+    state: ChunkedState
+}
+
+impl Default for ChunkedDecoder {
+    fn default() -> Self {
+        Self {state: ChunkedState::Size(Vec::new())}
+    }
+}
+
+impl ChunkedDecoder {
+    /// Appends the payload found in `data` to `out`, returns true once the terminating chunk is read
+    fn decode(&mut self, mut data: &[u8], out: &mut Vec<u8>) -> Result<bool, String> {
+        while !data.is_empty() {
+            match &mut self.state {
+                ChunkedState::Size(line) => {
+                    let byte = data[0];
+                    data = &data[1..];
+                    if byte != b'\n' {
+                        if line.len() == MAX_CHUNK_SIZE_LINE {
+                            return Err("Chunk size line too long".to_string())
+                        }
+                        line.push(byte);
+                        continue;
+                    }
+                    let line = String::from_utf8_lossy(line).to_string();
+                    let size = line.split(';').next().unwrap_or("").trim();
+                    let size = u64::from_str_radix(size, 16).map_err( | _ | format!("Invalid chunk size {}", size)) ?;
+                    self.state = if size == 0 {ChunkedState::Trailer(0)} else {ChunkedState::Data(size)};
+                }
+                ChunkedState::Data(remaining) => {
+                    let take = (*remaining).min(data.len() as u64) as usize;
+                    out.extend_from_slice(&data[0..take]);
+                    data = &data[take..];
+                    *remaining -= take as u64;
+                    if *remaining == 0 {
+                        self.state = ChunkedState::DataEnd;
+                    }
+                }
+                ChunkedState::DataEnd => {
+                    let byte = data[0];
+                    data = &data[1..];
+                    if byte == b'\n' {
+                        self.state = ChunkedState::Size(Vec::new());
+                    }
+                }
+                ChunkedState::Trailer(line_len) => {
+                    let byte = data[0];
+                    data = &data[1..];
+                    if byte == b'\n' {
+                        if *line_len == 0 {
+                            self.state = ChunkedState::Done;
+                        }
+                        else {
+                            *line_len = 0;
+                        }
+                    }
+                    else if byte != b'\r' {
+                        *line_len += 1;
+                    }
+                }
+                ChunkedState::Done => break
+            }
+        }
+        Ok(matches!(self.state, ChunkedState::Done))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // feeds `parts` to one decoder, returns the body and whether the last chunk was read
+    fn decode(parts: &[&[u8]]) -> Result<(Vec<u8>, bool), String> {
+        let mut decoder = ChunkedDecoder::default();
+        let mut out = Vec::new();
+        let mut done = false;
+        for part in parts {
+            done = decoder.decode(part, &mut out)?;
+        }
+        Ok((out, done))
+    }
+
+    #[test]
+    fn chunks() {
+        let body = b"5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+        assert_eq!(decode(&[body]), Ok((b"hello world".to_vec(), true)));
+        // upper and lower case hex, and padding around the size
+        let body = b"A\r\n0123456789\r\n a \r\nabcdefghij\r\n0\r\n\r\n";
+        assert_eq!(decode(&[body]), Ok((b"0123456789abcdefghij".to_vec(), true)));
+        // not done until the empty line after the last chunk
+        assert_eq!(decode(&[b"5\r\nhello\r\n0\r\n"]), Ok((b"hello".to_vec(), false)));
+    }
+
+    #[test]
+    fn chunk_extensions() {
+        let body = b"5;name=value\r\nhello\r\n6 ; quoted=\"a;b\"\r\n world\r\n0;last\r\n\r\n";
+        assert_eq!(decode(&[body]), Ok((b"hello world".to_vec(), true)));
+    }
+
+    #[test]
+    fn trailers() {
+        let body = b"5\r\nhello\r\n0\r\nX-Checksum: 1234\r\nX-Other: 5\r\n\r\n";
+        assert_eq!(decode(&[body]), Ok((b"hello".to_vec(), true)));
+        // a trailer is not the end of the body
+        let body = b"5\r\nhello\r\n0\r\nX-Checksum: 1234\r\n";
+        assert_eq!(decode(&[body]), Ok((b"hello".to_vec(), false)));
+    }
+
+    #[test]
+    fn chunks_split_across_reads() {
+        let body: &[u8] = b"5;ext\r\nhello\r\n6\r\n world\r\n0\r\nX-Trailer: 1\r\n\r\n";
+        for split in 0..body.len() {
+            let (first, second) = body.split_at(split);
+            assert_eq!(decode(&[first, second]), Ok((b"hello world".to_vec(), true)), "split at {}", split);
+        }
+        let bytes: Vec<&[u8]> = body.chunks(1).collect();
+        assert_eq!(decode(&bytes), Ok((b"hello world".to_vec(), true)));
+    }
+
+    #[test]
+    fn malformed_size_lines() {
+        for body in [
+            &b"zz\r\nhello\r\n"[..],
+            b"\r\nhello\r\n",
+            b";ext\r\nhello\r\n",
+            b"-5\r\nhello\r\n",
+            b"0x5\r\nhello\r\n",
+            b"5 5\r\nhello\r\n",
+            b"10000000000000000\r\n",
+        ] {
+            assert!(decode(&[body]).is_err(), "{:?}", String::from_utf8_lossy(body));
+        }
+        // a size line that never ends
+        let line = vec![b'0'; MAX_CHUNK_SIZE_LINE + 1];
+        assert!(decode(&[&line]).is_err());
+        let line = vec![b'0'; MAX_CHUNK_SIZE_LINE];
+        assert!(decode(&[&line]).is_ok());
+    }
+}
@@ -55,6 +55,12 @@ impl SelectTimers {
         )};  
     }
     
+    /// The time in seconds until the first timer fires, for event loops that wait on
+    /// something other than `select`.
+    pub fn next_timeout(&self) -> Option<f64> {
+        self.timers.front().map( | timer | timer.delta_timeout)
+    }
+    
     pub fn time_now(&self) -> f64 {
         let time_now = Instant::now(); //unsafe {mach_absolute_time()};
         (time_now.duration_since(self.time_start)).as_secs_f64() 
@@ -0,0 +1,363 @@
+use {
+    std::cell::RefCell,
+    std::rc::Rc,
+    self::super::{
+        opengl_wayland::WaylandOpenglWindow,
+        wayland_app::*,
+        wayland_event::WaylandEvent,
+        super::egl_sys,
+        super::x11::opengl_x11::OpenglCx,
+    },
+    crate::{
+        cx_api::CxOsOp,
+        makepad_math::dvec2,
+        makepad_live_id::*,
+        thread::SignalToUI,
+        event::Event,
+        pass::CxPassParent,
+        cx::{Cx, OsType, LinuxWindowParams},
+        gpu_info::GpuPerformance,
+        os::cx_native::EventFlow,
+        file_dialogs::FileDialogKind,
+    }
+};
+
+impl Cx {
+    /// Runs the app on the Wayland compositor, returns false without touching the Cx
+    /// when there is no usable compositor so the caller can fall back to X11.
+    pub fn wayland_event_loop(cx: Rc<RefCell<Cx>>) -> bool {
+        let opengl_windows = Rc::new(RefCell::new(Vec::new()));
+        let wayland_app = WaylandApp::new(Box::new({
+            let cx = cx.clone();
+            move | wayland_app,
+            event | {
+                let mut cx = cx.borrow_mut();
+                let mut opengl_windows = opengl_windows.borrow_mut();
+                cx.wayland_event_callback(wayland_app, event, &mut *opengl_windows)
+            }
+        }));
+        let Some(wayland_app) = wayland_app else {
+            return false
+        };
+        init_wayland_app_global(wayland_app);
+        if !get_wayland_app_global().is_usable() {
+            get_wayland_app_global().terminate_event_loop();
+            return false
+        }
+
+        cx.borrow_mut().self_ref = Some(cx.clone());
+        cx.borrow_mut().os_type = OsType::LinuxWindow(LinuxWindowParams {
+            // without server side decorations the app draws its own caption bar
+            custom_window_chrome: !get_wayland_app_global().has_server_side_decorations()
+        });
+        cx.borrow_mut().gpu_info.performance = GpuPerformance::Tier1;
+
+        cx.borrow_mut().os.opengl_cx = Some(unsafe {
+            OpenglCx::from_egl_platform_display(
+                egl_sys::EGL_PLATFORM_WAYLAND_KHR,
+                get_wayland_app_global().display,
+            )
+        });
+
+        cx.borrow_mut().os.accessibility.start();
+        cx.borrow_mut().call_event_handler(&Event::Startup);
+        cx.borrow_mut().redraw_all();
+        get_wayland_app_global().start_timer(0, 0.008, true);
+        get_wayland_app_global().event_loop();
+        true
+    }
+
+    fn wayland_event_callback(
+        &mut self,
+        wayland_app: &mut WaylandApp,
+        event: WaylandEvent,
+        opengl_windows: &mut Vec<WaylandOpenglWindow>
+    ) -> EventFlow {
+        if let EventFlow::Exit = self.handle_wayland_platform_ops(opengl_windows, wayland_app) {
+            return EventFlow::Exit
+        }
+
+        let mut paint_dirty = false;
+
+        match event {
+            WaylandEvent::AppGotFocus => {
+                for window in opengl_windows.iter_mut() {
+                    if let Some(main_pass_id) = self.windows[window.window_id].main_pass_id {
+                        self.repaint_pass(main_pass_id);
+                    }
+                }
+                paint_dirty = true;
+                self.call_event_handler(&Event::AppGotFocus);
+            }
+            WaylandEvent::AppLostFocus => {
+                self.call_event_handler(&Event::AppLostFocus);
+            }
+            WaylandEvent::WindowGeomChange(mut re) => {
+                if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == re.window_id) {
+                    if let Some(dpi_override) = self.windows[re.window_id].dpi_override {
+                        re.new_geom.inner_size *= re.new_geom.dpi_factor / dpi_override;
+                        re.new_geom.dpi_factor = dpi_override;
+                    }
+
+                    window.window_geom = re.new_geom.clone();
+                    self.windows[re.window_id].window_geom = re.new_geom.clone();
+                    if re.old_geom.inner_size != re.new_geom.inner_size || re.old_geom.dpi_factor != re.new_geom.dpi_factor {
+                        if let Some(main_pass_id) = self.windows[re.window_id].main_pass_id {
+                            self.redraw_pass_and_child_passes(main_pass_id);
+                        }
+                    }
+                }
+                self.call_event_handler(&Event::WindowGeomChange(re));
+            }
+            WaylandEvent::WindowClosed(wc) => {
+                let window_id = wc.window_id;
+                self.call_event_handler(&Event::WindowClosed(wc));
+                self.windows[window_id].is_created = false;
+                if let Some(index) = opengl_windows.iter().position( | w | w.window_id == window_id) {
+                    let mut window = opengl_windows.remove(index);
+                    self.os.opengl_cx.as_ref().unwrap().destroy_window_surface(window.egl_surface);
+                    window.wayland_window.close_window();
+                    if opengl_windows.len() == 0 {
+                        wayland_app.terminate_event_loop();
+                        self.call_event_handler(&Event::Shutdown);
+                        return EventFlow::Exit
+                    }
+                }
+            }
+            WaylandEvent::Paint => {
+                if self.new_next_frames.len() != 0 {
+                    self.call_next_frame_event(wayland_app.time_now());
+                }
+                if self.need_redrawing() {
+                    self.call_draw_event();
+                    self.os.opengl_cx.as_ref().unwrap().make_current();
+                    self.opengl_compile_shaders();
+                }
+                self.handle_wayland_repaint(opengl_windows, wayland_app.time_now());
+            }
+            WaylandEvent::MouseDown(e) => {
+                self.fingers.process_tap_count(
+                    e.abs,
+                    e.time
+                );
+                self.fingers.mouse_down(e.button, e.window_id);
+                self.call_event_handler(&Event::MouseDown(e.into()))
+            }
+            WaylandEvent::MouseMove(e) => {
+                self.call_event_handler(&Event::MouseMove(e.into()));
+                self.fingers.cycle_hover_area(live_id!(mouse).into());
+                self.fingers.switch_captures();
+            }
+            WaylandEvent::MouseUp(e) => {
+                let button = e.button;
+                self.call_event_handler(&Event::MouseUp(e.into()));
+                self.fingers.mouse_up(button);
+                self.fingers.cycle_hover_area(live_id!(mouse).into());
+            }
+            WaylandEvent::Scroll(e) => {
+                self.call_event_handler(&Event::Scroll(e.into()))
+            }
+            WaylandEvent::TouchUpdate(e) => {
+                self.fingers.process_touch_update_start(e.time, &e.touches);
+                let e = Event::TouchUpdate(e);
+                self.call_event_handler(&e);
+                let e = if let Event::TouchUpdate(e) = e {e} else {panic!()};
+                self.fingers.process_touch_update_end(&e.touches);
+            }
+            WaylandEvent::WindowDragQuery(e) => {
+                self.call_event_handler(&Event::WindowDragQuery(e))
+            }
+            WaylandEvent::WindowCloseRequested(e) => {
+                self.call_event_handler(&Event::WindowCloseRequested(e))
+            }
+            WaylandEvent::TextInput(e) => {
+                self.call_event_handler(&Event::TextInput(e))
+            }
+            WaylandEvent::KeyDown(e) => {
+                self.keyboard.process_key_down(e.clone());
+                self.call_event_handler(&Event::KeyDown(e))
+            }
+            WaylandEvent::KeyUp(e) => {
+                self.keyboard.process_key_up(e.clone());
+                self.call_event_handler(&Event::KeyUp(e))
+            }
+            WaylandEvent::TextCopy(e) => {
+                self.call_event_handler(&Event::TextCopy(e))
+            }
+            WaylandEvent::TextCut(e) => {
+                self.call_event_handler(&Event::TextCut(e))
+            }
+            WaylandEvent::Timer(e) => {
+                if e.timer_id == 0 {
+                    if SignalToUI::check_and_clear_ui_signal() {
+                        self.handle_media_signals();
+                        self.call_event_handler(&Event::Signal);
+                    }
+                    self.handle_action_receiver();
+                    self.handle_networking_events();
+                    self.handle_file_dialog_events();
+                    self.handle_accessibility_events();
+                }
+                else {
+                    self.call_event_handler(&Event::Timer(e))
+                }
+
+                if self.handle_live_edit() {
+                    self.call_event_handler(&Event::LiveEdit);
+                    self.redraw_all();
+                }
+            }
+        }
+
+        if self.any_passes_dirty() || self.need_redrawing() || paint_dirty {
+            EventFlow::Poll
+        } else {
+            EventFlow::Wait
+        }
+    }
+
+    fn handle_wayland_repaint(&mut self, opengl_windows: &mut Vec<WaylandOpenglWindow>, time: f64) {
+        self.os.opengl_cx.as_ref().unwrap().make_current();
+        let mut passes_todo = Vec::new();
+        self.compute_pass_repaint_order(&mut passes_todo);
+        self.repaint_id += 1;
+        for pass_id in &passes_todo {
+            self.passes[*pass_id].set_time(time as f32);
+            match self.passes[*pass_id].parent.clone() {
+                CxPassParent::Window(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.resize_buffers();
+                        self.draw_pass_to_egl_surface(*pass_id, window.egl_surface, &window.window_geom);
+                    }
+                }
+                CxPassParent::Pass(_) => {
+                    self.draw_pass_to_magic_texture(*pass_id);
+                },
+                CxPassParent::None => {
+                    self.draw_pass_to_magic_texture(*pass_id);
+                }
+            }
+        }
+    }
+
+    fn handle_wayland_platform_ops(&mut self, opengl_windows: &mut Vec<WaylandOpenglWindow>, wayland_app: &mut WaylandApp) -> EventFlow {
+        let mut ret = EventFlow::Poll;
+        while let Some(op) = self.platform_ops.pop() {
+            match op {
+                CxOsOp::CreateWindow(window_id) => {
+                    let window = &mut self.windows[window_id];
+                    // wayland clients can't place their own windows, so create_position is ignored
+                    let opengl_window = WaylandOpenglWindow::new(
+                        window_id,
+                        self.os.opengl_cx.as_ref().unwrap(),
+                        window.create_inner_size.unwrap_or(dvec2(800., 600.)),
+                        &window.create_title,
+                    );
+                    window.window_geom = opengl_window.window_geom.clone();
+                    opengl_windows.push(opengl_window);
+                    window.is_created = true;
+                },
+                CxOsOp::CloseWindow(window_id) => {
+                    if let Some(index) = opengl_windows.iter().position( | w | w.window_id == window_id) {
+                        self.windows[window_id].is_created = false;
+                        let mut window = opengl_windows.remove(index);
+                        self.os.opengl_cx.as_ref().unwrap().destroy_window_surface(window.egl_surface);
+                        window.wayland_window.close_window();
+                        if opengl_windows.len() == 0 {
+                            ret = EventFlow::Exit
+                        }
+                    }
+                },
+                CxOsOp::Quit => {
+                    ret = EventFlow::Exit
+                }
+                CxOsOp::MinimizeWindow(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.wayland_window.minimize();
+                    }
+                },
+                CxOsOp::MaximizeWindow(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.wayland_window.maximize();
+                    }
+                },
+                CxOsOp::RestoreWindow(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.wayland_window.restore();
+                    }
+                },
+                CxOsOp::FullscreenWindow(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.wayland_window.fullscreen();
+                    }
+                },
+                CxOsOp::NormalizeWindow(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.wayland_window.normalize();
+                    }
+                }
+                CxOsOp::SetTopmost(_window_id, _is_topmost) => {
+                    // xdg-shell has no way to keep a window on top
+                }
+                CxOsOp::ShowClipboardActions(_) => {
+                },
+                CxOsOp::CopyToClipboard(content) => {
+                    wayland_app.copy_to_clipboard(&content);
+                }
+                CxOsOp::XrStartPresenting => {
+                },
+                CxOsOp::XrStopPresenting => {
+                },
+                CxOsOp::ShowTextIME(_area, _pos) => {
+                },
+                CxOsOp::HideTextIME => {
+                },
+                CxOsOp::SetCursor(cursor) => {
+                    wayland_app.set_mouse_cursor(cursor);
+                },
+                CxOsOp::StartTimer {timer_id, interval, repeats} => {
+                    wayland_app.start_timer(timer_id, interval, repeats);
+                },
+                CxOsOp::StopTimer(timer_id) => {
+                    wayland_app.stop_timer(timer_id);
+                },
+                CxOsOp::StartDragging(_items) => {
+                    crate::error!("dragging out of the window is not supported on Wayland yet");
+                },
+                CxOsOp::UpdateMacosMenu(_menu) => {
+                },
+                CxOsOp::HttpRequest {request_id, request} => {
+                    self.os.http_requests.make_http_request(request_id, request, self.os.network_response.sender.clone());
+                },
+                CxOsOp::CancelHttpRequest {request_id} => {
+                    self.os.http_requests.cancel_http_request(request_id);
+                }
+                CxOsOp::PrepareVideoPlayback(_, _, _, _, _) |
+                CxOsOp::BeginVideoPlayback(_) |
+                CxOsOp::PauseVideoPlayback(_) |
+                CxOsOp::ResumeVideoPlayback(_) |
+                CxOsOp::MuteVideoPlayback(_) |
+                CxOsOp::UnmuteVideoPlayback(_) |
+                CxOsOp::CleanupVideoPlaybackResources(_) |
+                CxOsOp::UpdateVideoSurfaceTexture(_) => {
+                    crate::error!("video playback is not supported on Wayland yet");
+                }
+
+                // exporting a surface handle needs xdg-foreign, without a parent the dialogs are just not modal
+                CxOsOp::SaveFileDialog(settings) => {
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SaveFile, settings, String::new());
+                }
+                CxOsOp::SelectFileDialog(settings) => {
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SelectFile, settings, String::new());
+                }
+                CxOsOp::SaveFolderDialog(settings) => {
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SaveFolder, settings, String::new());
+                }
+                CxOsOp::SelectFolderDialog(settings) => {
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SelectFolder, settings, String::new());
+                }
+            }
+        }
+        ret
+    }
+}
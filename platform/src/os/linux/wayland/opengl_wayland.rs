@@ -0,0 +1,61 @@
+use {
+    self::super::{
+        wayland_window::WaylandWindow,
+        super::egl_sys,
+        super::x11::opengl_x11::OpenglCx,
+    },
+    crate::{
+        window::WindowId,
+        makepad_math::DVec2,
+        event::*,
+    },
+};
+
+pub struct WaylandOpenglWindow {
+    pub first_draw: bool,
+    pub window_id: WindowId,
+    pub window_geom: WindowGeom,
+    pub opening_repaint_count: u32,
+    pub cal_size: DVec2,
+    pub wayland_window: Box<WaylandWindow>,
+    pub egl_surface: egl_sys::EGLSurface,
+}
+
+impl WaylandOpenglWindow {
+    pub fn new(
+        window_id: WindowId,
+        opengl_cx: &OpenglCx,
+        inner_size: DVec2,
+        title: &str
+    ) -> WaylandOpenglWindow {
+        let mut wayland_window = Box::new(WaylandWindow::new(window_id));
+        wayland_window.init(title, inner_size);
+
+        let egl_surface = opengl_cx.create_window_surface(wayland_window.egl_window as egl_sys::EGLNativeWindowType);
+        opengl_cx.set_swap_interval(egl_surface, 0);
+
+        WaylandOpenglWindow {
+            first_draw: true,
+            window_id,
+            opening_repaint_count: 0,
+            cal_size: DVec2::default(),
+            window_geom: wayland_window.get_window_geom(),
+            wayland_window,
+            egl_surface,
+        }
+    }
+
+    pub fn resize_buffers(&mut self) -> bool {
+        let cal_size = DVec2 {
+            x: self.window_geom.inner_size.x * self.window_geom.dpi_factor,
+            y: self.window_geom.inner_size.y * self.window_geom.dpi_factor
+        };
+        if self.cal_size != cal_size {
+            self.cal_size = cal_size;
+            true
+        }
+        else {
+            false
+        }
+    }
+}
@@ -0,0 +1,1263 @@
+use {
+    std::{
+        collections::HashMap,
+        cell::RefCell,
+        ffi::{CStr, CString},
+        os::raw::{c_char, c_int, c_void},
+        ptr,
+        rc::Rc,
+    },
+    self::super::{
+        wayland_sys::*,
+        xkb_sys::{self, LibXkb},
+        wayland_event::WaylandEvent,
+        wayland_window::*,
+        super::select_timer::SelectTimers,
+        super::libc_sys,
+        super::x11::xlib_app::keysym_to_keycode,
+    },
+    crate::{
+        makepad_math::DVec2,
+        event::*,
+        cursor::MouseCursor,
+        os::cx_native::EventFlow,
+        window::WindowId,
+    },
+};
+
+static mut WAYLAND_APP: *mut WaylandApp = 0 as *mut _;
+
+pub fn get_wayland_app_global() -> &'static mut WaylandApp {
+    unsafe {
+        &mut *(WAYLAND_APP)
+    }
+}
+
+pub fn init_wayland_app_global(wayland_app: WaylandApp) {
+    unsafe {
+        WAYLAND_APP = Box::into_raw(Box::new(wayland_app));
+        (*WAYLAND_APP).bind_globals();
+    }
+}
+
+// the key repeat runs on our own timer list, with an id the Cx never hands out
+const KEY_REPEAT_TIMER_ID: u64 = u64::MAX;
+
+const TEXT_MIME_TYPES: [&[u8]; 3] = [b"text/plain;charset=utf-8\0", b"UTF8_STRING\0", b"text/plain\0"];
+
+pub struct WaylandOutput {
+    pub name: u32,
+    pub output: *mut wl_output,
+    pub scale: i32,
+}
+
+struct WaylandTouch {
+    id: i32,
+    window_id: WindowId,
+    state: TouchState,
+    abs: DVec2,
+}
+
+pub struct WaylandApp {
+    pub lib: LibWayland,
+    pub xkb: LibXkb,
+    pub display: *mut wl_display,
+    pub display_fd: c_int,
+    pub registry: *mut wl_registry,
+    pub compositor: *mut wl_compositor,
+    pub shm: *mut wl_shm,
+    pub seat: *mut wl_seat,
+    pub xdg_wm_base: *mut xdg_wm_base,
+    pub fractional_scale_manager: *mut wp_fractional_scale_manager_v1,
+    pub viewporter: *mut wp_viewporter,
+    pub decoration_manager: *mut zxdg_decoration_manager_v1,
+    pub data_device_manager: *mut wl_data_device_manager,
+    pub data_device: *mut wl_data_device,
+    pub outputs: Vec<WaylandOutput>,
+    pub app_id: CString,
+    pub window_map: HashMap<usize, *mut WaylandWindow>,
+
+    pointer: *mut wl_pointer,
+    pointer_surface: usize,
+    pointer_enter_serial: u32,
+    pending_scroll: DVec2,
+    pending_scroll_is_wheel: bool,
+    keyboard: *mut wl_keyboard,
+    keyboard_surface: usize,
+    touch: *mut wl_touch,
+    touches: Vec<WaylandTouch>,
+    /// The serial of the last input event, needed for clipboard and window move requests
+    last_serial: u32,
+
+    xkb_context: *mut xkb_sys::xkb_context,
+    xkb_keymap: *mut xkb_sys::xkb_keymap,
+    xkb_state: *mut xkb_sys::xkb_state,
+    xkb_compose_state: *mut xkb_sys::xkb_compose_state,
+    modifiers: KeyModifiers,
+    repeat_rate: i32,
+    repeat_delay: i32,
+    repeat_key: Option<u32>,
+
+    pub clipboard: String,
+    data_source: *mut wl_data_source,
+    selection_offer: *mut wl_data_offer,
+    offer_mime_types: HashMap<usize, Vec<String>>,
+
+    cursor_theme: *mut wl_cursor_theme,
+    cursor_theme_scale: i32,
+    cursor_surface: *mut wl_surface,
+    pub current_cursor: MouseCursor,
+    pub internal_cursor: MouseCursor,
+
+    pub timers: SelectTimers,
+    pub last_click_time: f64,
+    pub last_click_pos: DVec2,
+    event_loop_running: bool,
+    pub event_callback: Option<Box<dyn FnMut(&mut WaylandApp, WaylandEvent) -> EventFlow >>,
+    pub event_flow: EventFlow,
+}
+
+impl WaylandApp {
+    /// Connects to the compositor named by `WAYLAND_DISPLAY`, `None` if the libraries
+    /// are missing or the compositor lacks xdg-shell.
+    pub fn new(event_callback: Box<dyn FnMut(&mut WaylandApp, WaylandEvent) -> EventFlow>) -> Option<WaylandApp> {
+        let lib = LibWayland::try_load()?;
+        let xkb = LibXkb::try_load()?;
+        unsafe {
+            let display = (lib.wl_display_connect)(ptr::null());
+            if display.is_null() {
+                return None
+            }
+            let registry = lib.get_registry(display);
+            let app_id = std::env::current_exe().ok()
+                .and_then( | path | path.file_stem().map( | stem | stem.to_string_lossy().into_owned()))
+                .unwrap_or("makepad".to_string());
+
+            let xkb_context = (xkb.xkb_context_new)(xkb_sys::XKB_CONTEXT_NO_FLAGS);
+            // compose sequences and dead keys come from the locale, like XCompose on X11
+            let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+                .filter_map( | var | std::env::var(var).ok())
+                .find( | value | !value.is_empty())
+                .unwrap_or("C".to_string());
+            let locale = CString::new(locale).unwrap_or_default();
+            let xkb_compose_table = (xkb.xkb_compose_table_new_from_locale)(xkb_context, locale.as_ptr(), xkb_sys::XKB_COMPOSE_COMPILE_NO_FLAGS);
+            let xkb_compose_state = if xkb_compose_table.is_null() {
+                ptr::null_mut()
+            } else {
+                // the state keeps its own reference to the table
+                let xkb_compose_state = (xkb.xkb_compose_state_new)(xkb_compose_table, xkb_sys::XKB_COMPOSE_STATE_NO_FLAGS);
+                (xkb.xkb_compose_table_unref)(xkb_compose_table);
+                xkb_compose_state
+            };
+
+            Some(WaylandApp {
+                display_fd: (lib.wl_display_get_fd)(display),
+                lib,
+                xkb,
+                display,
+                registry,
+                compositor: ptr::null_mut(),
+                shm: ptr::null_mut(),
+                seat: ptr::null_mut(),
+                xdg_wm_base: ptr::null_mut(),
+                fractional_scale_manager: ptr::null_mut(),
+                viewporter: ptr::null_mut(),
+                decoration_manager: ptr::null_mut(),
+                data_device_manager: ptr::null_mut(),
+                data_device: ptr::null_mut(),
+                outputs: Vec::new(),
+                app_id: CString::new(app_id).unwrap_or_default(),
+                window_map: HashMap::new(),
+                pointer: ptr::null_mut(),
+                pointer_surface: 0,
+                pointer_enter_serial: 0,
+                pending_scroll: DVec2::default(),
+                pending_scroll_is_wheel: false,
+                keyboard: ptr::null_mut(),
+                keyboard_surface: 0,
+                touch: ptr::null_mut(),
+                touches: Vec::new(),
+                last_serial: 0,
+                xkb_context,
+                xkb_keymap: ptr::null_mut(),
+                xkb_state: ptr::null_mut(),
+                xkb_compose_state,
+                modifiers: KeyModifiers::default(),
+                repeat_rate: 25,
+                repeat_delay: 600,
+                repeat_key: None,
+                clipboard: String::new(),
+                data_source: ptr::null_mut(),
+                selection_offer: ptr::null_mut(),
+                offer_mime_types: HashMap::new(),
+                cursor_theme: ptr::null_mut(),
+                cursor_theme_scale: 0,
+                cursor_surface: ptr::null_mut(),
+                current_cursor: MouseCursor::Default,
+                internal_cursor: MouseCursor::Default,
+                timers: SelectTimers::new(),
+                last_click_time: 0.0,
+                last_click_pos: DVec2::default(),
+                event_loop_running: true,
+                event_callback: Some(event_callback),
+                event_flow: EventFlow::Poll,
+            })
+        }
+    }
+
+    /// Binds the globals once the app has its final address, which the listeners get as user data.
+    unsafe fn bind_globals(&mut self) {
+        let data = self as *mut WaylandApp as *mut c_void;
+        self.lib.add_listener(self.registry, &REGISTRY_LISTENER, data);
+        // the first roundtrip announces the globals, the second delivers their initial events
+        (self.lib.wl_display_roundtrip)(self.display);
+        (self.lib.wl_display_roundtrip)(self.display);
+    }
+
+    pub fn is_usable(&self) -> bool {
+        !self.compositor.is_null() && !self.xdg_wm_base.is_null()
+    }
+
+    pub fn has_server_side_decorations(&self) -> bool {
+        !self.decoration_manager.is_null()
+    }
+
+    pub fn output_scale(&self, output: *mut wl_output) -> i32 {
+        self.outputs.iter().find( | o | o.output == output).map( | o | o.scale).unwrap_or(1)
+    }
+
+    unsafe fn window_for_surface(&self, surface: usize) -> Option<&'static mut WaylandWindow> {
+        self.window_map.get(&surface).map( | window | &mut **window)
+    }
+
+    pub fn event_loop(&mut self) {
+        unsafe {
+            self.do_callback(WaylandEvent::Paint);
+
+            let mut timer_ids = Vec::new();
+            while self.event_loop_running {
+                match self.event_flow {
+                    EventFlow::Exit => {
+                        break;
+                    }
+                    EventFlow::Wait => {
+                        self.fire_timers(&mut timer_ids);
+                        let timeout = self.timers.next_timeout()
+                            .map( | timeout | (timeout * 1000.0).ceil() as c_int)
+                            .unwrap_or(-1);
+                        self.dispatch_events(timeout);
+                        self.event_flow = EventFlow::Poll;
+                    }
+                    EventFlow::Poll => {
+                        self.fire_timers(&mut timer_ids);
+                        self.dispatch_events(0);
+                        self.do_callback(WaylandEvent::Paint);
+                    }
+                }
+            }
+        }
+    }
+
+    fn fire_timers(&mut self, timer_ids: &mut Vec<u64>) {
+        let time = self.time_now();
+        self.timers.update_timers(timer_ids);
+        for timer_id in timer_ids.iter() {
+            if *timer_id == KEY_REPEAT_TIMER_ID {
+                self.repeat_key_down();
+                self.restart_key_repeat();
+                continue
+            }
+            self.do_callback(
+                WaylandEvent::Timer(TimerEvent {
+                    timer_id: *timer_id,
+                    time: Some(time)
+                })
+            );
+        }
+    }
+
+    /// Reads and dispatches the events from the compositor, waiting at most `timeout` milliseconds for them.
+    unsafe fn dispatch_events(&mut self, timeout: c_int) {
+        if self.display.is_null() {
+            return
+        }
+        while (self.lib.wl_display_prepare_read)(self.display) != 0 {
+            (self.lib.wl_display_dispatch_pending)(self.display);
+        }
+        (self.lib.wl_display_flush)(self.display);
+        let mut fds = [libc_sys::pollfd {fd: self.display_fd, events: libc_sys::POLLIN, revents: 0}];
+        if libc_sys::poll(fds.as_mut_ptr(), 1, timeout) > 0 && fds[0].revents & libc_sys::POLLIN != 0 {
+            if (self.lib.wl_display_read_events)(self.display) < 0 {
+                crate::error!("Wayland connection lost");
+                self.terminate_event_loop();
+                return
+            }
+        }
+        else {
+            (self.lib.wl_display_cancel_read)(self.display);
+        }
+        (self.lib.wl_display_dispatch_pending)(self.display);
+    }
+
+    pub fn do_callback(&mut self, event: WaylandEvent) {
+        if let Some(mut callback) = self.event_callback.take() {
+            self.event_flow = callback(self, event);
+            if let EventFlow::Exit = self.event_flow {
+                self.terminate_event_loop();
+            }
+            self.event_callback = Some(callback);
+        }
+    }
+
+    pub fn terminate_event_loop(&mut self) {
+        self.event_loop_running = false;
+        if !self.display.is_null() {
+            unsafe {(self.lib.wl_display_disconnect)(self.display)};
+            self.display = ptr::null_mut();
+        }
+    }
+
+    pub fn start_timer(&mut self, id: u64, timeout: f64, repeats: bool) {
+        self.timers.start_timer(id, timeout, repeats);
+    }
+
+    pub fn stop_timer(&mut self, id: u64) {
+        self.timers.stop_timer(id);
+    }
+
+    pub fn time_now(&self) -> f64 {
+        self.timers.time_now()
+    }
+
+    // Cursors
+
+    pub fn set_internal_mouse_cursor(&mut self, cursor: MouseCursor) {
+        if self.internal_cursor != cursor {
+            self.internal_cursor = cursor.clone();
+            self.set_mouse_cursor_(cursor);
+        }
+    }
+
+    pub fn restore_mouse_cursor(&mut self, cursor: MouseCursor) {
+        if self.internal_cursor != cursor {
+            self.internal_cursor = cursor.clone();
+            self.set_mouse_cursor_(cursor);
+        }
+    }
+
+    pub fn set_mouse_cursor(&mut self, cursor: MouseCursor) {
+        if self.current_cursor != cursor {
+            self.current_cursor = cursor.clone();
+            self.internal_cursor = cursor.clone();
+            self.set_mouse_cursor_(cursor);
+        }
+    }
+
+    fn cursor_names(cursor: MouseCursor) -> &'static [&'static [u8]] {
+        match cursor {
+            MouseCursor::Hidden => &[],
+            MouseCursor::EResize => &[b"e-resize\0", b"right_side\0"],
+            MouseCursor::NResize => &[b"n-resize\0", b"top_side\0"],
+            MouseCursor::NeResize => &[b"ne-resize\0", b"top_right_corner\0"],
+            MouseCursor::NwResize => &[b"nw-resize\0", b"top_left_corner\0"],
+            MouseCursor::SResize => &[b"s-resize\0", b"bottom_side\0"],
+            MouseCursor::SeResize => &[b"se-resize\0", b"bottom_right_corner\0"],
+            MouseCursor::SwResize => &[b"sw-resize\0", b"bottom_left_corner\0"],
+            MouseCursor::WResize => &[b"w-resize\0", b"left_side\0"],
+
+            MouseCursor::Default => &[b"default\0", b"left_ptr\0"],
+            MouseCursor::Crosshair => &[b"crosshair\0"],
+            MouseCursor::Hand => &[b"pointer\0", b"hand2\0", b"hand1\0"],
+            MouseCursor::Arrow => &[b"default\0", b"left_ptr\0"],
+            MouseCursor::Move => &[b"move\0", b"fleur\0"],
+            MouseCursor::NotAllowed => &[b"not-allowed\0", b"crossed_circle\0"],
+            MouseCursor::Text => &[b"text\0", b"xterm\0"],
+            MouseCursor::Wait => &[b"wait\0", b"watch\0"],
+            MouseCursor::Help => &[b"help\0", b"question_arrow\0"],
+            MouseCursor::NsResize => &[b"ns-resize\0", b"v_double_arrow\0"],
+            MouseCursor::NeswResize => &[b"nesw-resize\0", b"fd_double_arrow\0", b"size_fdiag\0"],
+            MouseCursor::EwResize => &[b"ew-resize\0", b"h_double_arrow\0"],
+            MouseCursor::NwseResize => &[b"nwse-resize\0", b"bd_double_arrow\0", b"size_bdiag\0"],
+            MouseCursor::ColResize => &[b"col-resize\0", b"split_h\0", b"h_double_arrow\0"],
+            MouseCursor::RowResize => &[b"row-resize\0", b"split_v\0", b"v_double_arrow\0"],
+        }
+    }
+
+    fn set_mouse_cursor_(&mut self, cursor: MouseCursor) {
+        if self.pointer.is_null() || self.pointer_surface == 0 {
+            return
+        }
+        unsafe {
+            if let MouseCursor::Hidden = cursor {
+                self.lib.request(self.pointer, WL_POINTER_SET_CURSOR, &mut [arg_u(self.pointer_enter_serial), arg_null(), arg_i(0), arg_i(0)]);
+                return
+            }
+            let Some(lib_cursor) = &self.lib.cursor else {return};
+            if self.shm.is_null() {
+                return
+            }
+            // load the theme at the scale of the window under the pointer so the cursor stays sharp
+            let scale = self.window_for_surface(self.pointer_surface)
+                .map( | window | window.get_dpi_factor().ceil() as i32)
+                .unwrap_or(1)
+                .max(1);
+            if self.cursor_theme.is_null() || self.cursor_theme_scale != scale {
+                if !self.cursor_theme.is_null() {
+                    (lib_cursor.wl_cursor_theme_destroy)(self.cursor_theme);
+                }
+                let size = std::env::var("XCURSOR_SIZE").ok().and_then( | size | size.parse().ok()).unwrap_or(24);
+                let theme = std::env::var("XCURSOR_THEME").ok().and_then( | theme | CString::new(theme).ok());
+                self.cursor_theme = (lib_cursor.wl_cursor_theme_load)(
+                    theme.as_ref().map( | theme | theme.as_ptr()).unwrap_or(ptr::null()),
+                    size * scale,
+                    self.shm
+                );
+                self.cursor_theme_scale = scale;
+            }
+            if self.cursor_theme.is_null() {
+                return
+            }
+            let wl_cursor = Self::cursor_names(cursor).iter()
+                .map( | name | (lib_cursor.wl_cursor_theme_get_cursor)(self.cursor_theme, name.as_ptr() as *const c_char))
+                .find( | wl_cursor | !wl_cursor.is_null());
+            let Some(wl_cursor) = wl_cursor else {return};
+            if (*wl_cursor).image_count == 0 {
+                return
+            }
+            let image = *(*wl_cursor).images;
+            let buffer = (lib_cursor.wl_cursor_image_get_buffer)(image);
+            if buffer.is_null() {
+                return
+            }
+            if self.cursor_surface.is_null() {
+                self.cursor_surface = self.lib.request_new(self.compositor, WL_COMPOSITOR_CREATE_SURFACE, self.lib.wl_surface_interface, &mut [arg_n()]);
+            }
+            if (self.lib.wl_proxy_get_version)(self.cursor_surface) >= 3 {
+                self.lib.request(self.cursor_surface, WL_SURFACE_SET_BUFFER_SCALE, &mut [arg_i(scale)]);
+            }
+            self.lib.request(self.cursor_surface, WL_SURFACE_ATTACH, &mut [arg_o(buffer), arg_i(0), arg_i(0)]);
+            self.lib.request(self.cursor_surface, WL_SURFACE_DAMAGE, &mut [arg_i(0), arg_i(0), arg_i(i32::MAX), arg_i(i32::MAX)]);
+            self.lib.request(self.cursor_surface, WL_SURFACE_COMMIT, &mut []);
+            self.lib.request(self.pointer, WL_POINTER_SET_CURSOR, &mut [
+                arg_u(self.pointer_enter_serial),
+                arg_o(self.cursor_surface),
+                arg_i((*image).hotspot_x as i32 / scale),
+                arg_i((*image).hotspot_y as i32 / scale)
+            ]);
+        }
+    }
+
+    // Pointer
+
+    fn pointer_motion(&mut self, pos: DVec2) {
+        let Some(window) = (unsafe {self.window_for_surface(self.pointer_surface)}) else {return};
+        window.last_mouse_pos = pos;
+        let response = window.send_window_drag_query();
+        window.send_mouse_move(pos, self.modifiers);
+        // a window that draws its own decorations gets resized from its edges
+        let window_size = window.last_window_geom.inner_size;
+        let edge = if window.server_side_decorations || window.get_is_maximized() {
+            None
+        }
+        else if pos.x < 10.0 && pos.y < 10.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_TOP_LEFT, MouseCursor::NwResize))
+        }
+        else if pos.x < 10.0 && pos.y >= window_size.y - 10.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_LEFT, MouseCursor::SwResize))
+        }
+        else if pos.x < 5.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_LEFT, MouseCursor::WResize))
+        }
+        else if pos.x >= window_size.x - 10.0 && pos.y < 10.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_TOP_RIGHT, MouseCursor::NeResize))
+        }
+        else if pos.x >= window_size.x - 10.0 && pos.y >= window_size.y - 10.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_RIGHT, MouseCursor::SeResize))
+        }
+        else if pos.x >= window_size.x - 5.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_RIGHT, MouseCursor::EResize))
+        }
+        else if pos.y <= 5.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_TOP, MouseCursor::NResize))
+        }
+        else if pos.y > window_size.y - 5.0 {
+            Some((XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM, MouseCursor::SResize))
+        }
+        else {
+            None
+        };
+        if let Some((edge, cursor)) = edge {
+            window.last_nc_mode = Some(edge);
+            self.set_internal_mouse_cursor(cursor);
+        }
+        else {
+            window.last_nc_mode = match response {
+                WindowDragQueryResponse::Caption => Some(NC_MODE_MOVE),
+                _ => None
+            };
+            self.restore_mouse_cursor(self.current_cursor);
+        }
+    }
+
+    fn pointer_button(&mut self, serial: u32, button: u32, pressed: bool) {
+        self.last_serial = serial;
+        let Some(window) = (unsafe {self.window_for_surface(self.pointer_surface)}) else {return};
+        let button = match button {
+            BTN_LEFT => 0,
+            BTN_RIGHT => 1,
+            BTN_MIDDLE => 2,
+            button => button.saturating_sub(BTN_LEFT) as usize
+        };
+        if !pressed {
+            window.send_mouse_up(button, self.modifiers);
+            return
+        }
+        let time_now = self.time_now();
+        match window.last_nc_mode {
+            Some(nc_mode) if button == 0 => {
+                // double clicking the caption toggles maximized
+                if nc_mode == NC_MODE_MOVE
+                    && time_now - self.last_click_time < 0.35
+                    && (window.last_mouse_pos - self.last_click_pos).length() < 5.0 {
+                    if window.get_is_maximized() {
+                        window.restore();
+                    }
+                    else {
+                        window.maximize();
+                    }
+                }
+                else {
+                    window.start_move_or_resize(nc_mode, serial);
+                }
+            }
+            _ => window.send_mouse_down(button, self.modifiers)
+        }
+        self.last_click_time = time_now;
+        self.last_click_pos = window.last_mouse_pos;
+    }
+
+    fn pointer_frame(&mut self) {
+        if self.pending_scroll == DVec2::default() {
+            return
+        }
+        let scroll = std::mem::take(&mut self.pending_scroll);
+        let Some(window) = (unsafe {self.window_for_surface(self.pointer_surface)}) else {return};
+        self.do_callback(WaylandEvent::Scroll(ScrollEvent {
+            window_id: window.window_id,
+            scroll,
+            abs: window.last_mouse_pos,
+            modifiers: self.modifiers,
+            is_mouse: self.pending_scroll_is_wheel,
+            handled_x: Default::default(),
+            handled_y: Default::default(),
+            time: self.time_now()
+        }));
+    }
+
+    // Keyboard
+
+    unsafe fn update_keymap(&mut self, fd: i32, size: u32) {
+        let map = libc_sys::mmap(ptr::null_mut(), size as usize, libc_sys::PROT_READ, libc_sys::MAP_PRIVATE, fd, 0);
+        libc_sys::close(fd);
+        if map == libc_sys::MAP_FAILED {
+            return
+        }
+        let keymap = (self.xkb.xkb_keymap_new_from_string)(
+            self.xkb_context,
+            map as *const c_char,
+            xkb_sys::XKB_KEYMAP_FORMAT_TEXT_V1,
+            xkb_sys::XKB_KEYMAP_COMPILE_NO_FLAGS
+        );
+        libc_sys::munmap(map, size as usize);
+        if keymap.is_null() {
+            return
+        }
+        if !self.xkb_state.is_null() {
+            (self.xkb.xkb_state_unref)(self.xkb_state);
+        }
+        if !self.xkb_keymap.is_null() {
+            (self.xkb.xkb_keymap_unref)(self.xkb_keymap);
+        }
+        self.xkb_keymap = keymap;
+        self.xkb_state = (self.xkb.xkb_state_new)(keymap);
+    }
+
+    unsafe fn update_modifiers(&mut self, depressed: u32, latched: u32, locked: u32, group: u32) {
+        if self.xkb_state.is_null() {
+            return
+        }
+        (self.xkb.xkb_state_update_mask)(self.xkb_state, depressed, latched, locked, 0, 0, group);
+        let is_active = | name: &[u8] | {
+            (self.xkb.xkb_state_mod_name_is_active)(self.xkb_state, name.as_ptr() as *const c_char, xkb_sys::XKB_STATE_MODS_EFFECTIVE) > 0
+        };
+        self.modifiers = KeyModifiers {
+            shift: is_active(xkb_sys::XKB_MOD_NAME_SHIFT),
+            control: is_active(xkb_sys::XKB_MOD_NAME_CTRL),
+            alt: is_active(xkb_sys::XKB_MOD_NAME_ALT),
+            logo: is_active(xkb_sys::XKB_MOD_NAME_LOGO),
+        };
+    }
+
+    /// The text a key produces, running it through the compose state for dead keys and compose sequences.
+    unsafe fn key_text(&mut self, keycode: u32, keysym: u32) -> String {
+        let xkb = &self.xkb;
+        if !self.xkb_compose_state.is_null()
+            && (xkb.xkb_compose_state_feed)(self.xkb_compose_state, keysym) != xkb_sys::XKB_COMPOSE_FEED_IGNORED {
+            match (xkb.xkb_compose_state_get_status)(self.xkb_compose_state) {
+                xkb_sys::XKB_COMPOSE_COMPOSING => return String::new(),
+                xkb_sys::XKB_COMPOSE_COMPOSED => {
+                    let compose_state = self.xkb_compose_state;
+                    let text = xkb_sys::get_utf8( | buffer, size | (xkb.xkb_compose_state_get_utf8)(compose_state, buffer, size));
+                    (xkb.xkb_compose_state_reset)(self.xkb_compose_state);
+                    return text
+                }
+                xkb_sys::XKB_COMPOSE_CANCELLED => {
+                    (xkb.xkb_compose_state_reset)(self.xkb_compose_state);
+                    return String::new()
+                }
+                _ => ()
+            }
+        }
+        let state = self.xkb_state;
+        xkb_sys::get_utf8( | buffer, size | (xkb.xkb_state_key_get_utf8)(state, keycode, buffer, size))
+    }
+
+    unsafe fn key_down(&mut self, keycode: u32, is_repeat: bool) {
+        if self.xkb_state.is_null() {
+            return
+        }
+        if self.keyboard_surface == 0 {
+            return
+        }
+        let keysym = (self.xkb.xkb_state_key_get_one_sym)(self.xkb_state, keycode);
+        let key_code = keysym_to_keycode(keysym);
+        let modifiers = self.modifiers;
+
+        if modifiers.control || modifiers.logo {
+            match key_code {
+                KeyCode::KeyV => self.paste_from_clipboard(),
+                KeyCode::KeyC => {
+                    let response = Rc::new(RefCell::new(None));
+                    self.do_callback(WaylandEvent::TextCopy(TextClipboardEvent {
+                        response: response.clone()
+                    }));
+                    let response = response.borrow();
+                    if let Some(response) = response.as_ref() {
+                        self.copy_to_clipboard(response);
+                    }
+                }
+                KeyCode::KeyX => {
+                    let response = Rc::new(RefCell::new(None));
+                    self.do_callback(WaylandEvent::TextCut(TextClipboardEvent {
+                        response: response.clone()
+                    }));
+                    let response = response.borrow();
+                    if let Some(response) = response.as_ref() {
+                        self.copy_to_clipboard(response);
+                    }
+                }
+                _ => ()
+            }
+        }
+
+        self.do_callback(WaylandEvent::KeyDown(KeyEvent {
+            key_code,
+            is_repeat,
+            modifiers,
+            time: self.time_now()
+        }));
+
+        if !(modifiers.control || modifiers.logo || modifiers.alt) {
+            let text = self.key_text(keycode, keysym);
+            let char_code = text.chars().next().unwrap_or('\0');
+            // the key down handler may have closed the window
+            if char_code >= ' ' && char_code != 127 as char {
+                if let Some(window) = self.window_for_surface(self.keyboard_surface) {
+                    window.send_text_input(text, false);
+                }
+            }
+        }
+    }
+
+    unsafe fn key_up(&mut self, keycode: u32) {
+        if self.xkb_state.is_null() {
+            return
+        }
+        let keysym = (self.xkb.xkb_state_key_get_one_sym)(self.xkb_state, keycode);
+        self.do_callback(WaylandEvent::KeyUp(KeyEvent {
+            key_code: keysym_to_keycode(keysym),
+            is_repeat: false,
+            modifiers: self.modifiers,
+            time: self.time_now()
+        }));
+    }
+
+    fn repeat_key_down(&mut self) {
+        if let Some(keycode) = self.repeat_key {
+            unsafe {self.key_down(keycode, true)};
+        }
+    }
+
+    fn restart_key_repeat(&mut self) {
+        // after the initial delay the key keeps repeating at the repeat rate
+        if self.repeat_key.is_some() {
+            self.timers.stop_timer(KEY_REPEAT_TIMER_ID);
+            self.timers.start_timer(KEY_REPEAT_TIMER_ID, 1.0 / self.repeat_rate.max(1) as f64, true);
+        }
+    }
+
+    fn stop_key_repeat(&mut self) {
+        if self.repeat_key.take().is_some() {
+            self.timers.stop_timer(KEY_REPEAT_TIMER_ID);
+        }
+    }
+
+    // Touch
+
+    unsafe fn send_touches(&mut self) {
+        let Some(window_id) = self.touches.first().map( | touch | touch.window_id) else {return};
+        let time = self.time_now();
+        let touches = self.touches.iter().map( | touch | TouchPoint {
+            state: touch.state,
+            abs: touch.abs,
+            time,
+            uid: touch.id as u64,
+            rotation_angle: 0.0,
+            force: 0.0,
+            radius: DVec2::default(),
+            handled: Default::default(),
+            sweep_lock: Default::default(),
+        }).collect();
+        self.do_callback(WaylandEvent::TouchUpdate(TouchUpdateEvent {
+            time,
+            window_id,
+            modifiers: self.modifiers,
+            touches,
+        }));
+        self.touches.retain( | touch | !matches!(touch.state, TouchState::Stop));
+        for touch in &mut self.touches {
+            touch.state = TouchState::Stable;
+        }
+    }
+
+    // Clipboard
+
+    pub fn copy_to_clipboard(&mut self, text: &str) {
+        self.clipboard = text.to_string();
+        if self.data_device_manager.is_null() || self.data_device.is_null() {
+            return
+        }
+        unsafe {
+            if !self.data_source.is_null() {
+                self.lib.request_destroy(self.data_source, WL_DATA_SOURCE_DESTROY);
+            }
+            self.data_source = self.lib.request_new(
+                self.data_device_manager,
+                WL_DATA_DEVICE_MANAGER_CREATE_DATA_SOURCE,
+                self.lib.wl_data_source_interface,
+                &mut [arg_n()]
+            );
+            let data = self as *mut WaylandApp as *mut c_void;
+            self.lib.add_listener(self.data_source, &DATA_SOURCE_LISTENER, data);
+            for mime_type in TEXT_MIME_TYPES {
+                self.lib.request(self.data_source, WL_DATA_SOURCE_OFFER, &mut [arg_s(CStr::from_bytes_with_nul_unchecked(mime_type))]);
+            }
+            self.lib.request(self.data_device, WL_DATA_DEVICE_SET_SELECTION, &mut [arg_o(self.data_source), arg_u(self.last_serial)]);
+            (self.lib.wl_display_flush)(self.display);
+        }
+    }
+
+    fn paste_from_clipboard(&mut self) {
+        let text = if !self.data_source.is_null() {
+            // we own the selection, reading it through the compositor would deadlock
+            Some(self.clipboard.clone())
+        }
+        else {
+            unsafe {self.receive_selection()}
+        };
+        if let Some(text) = text {
+            self.do_callback(WaylandEvent::TextInput(TextInputEvent {
+                input: text,
+                was_paste: true,
+                replace_last: false
+            }));
+        }
+    }
+
+    unsafe fn receive_selection(&mut self) -> Option<String> {
+        if self.selection_offer.is_null() {
+            return None
+        }
+        let mime_types = self.offer_mime_types.get(&(self.selection_offer as usize))?;
+        let mime_type = TEXT_MIME_TYPES.iter()
+            .map( | mime_type | CStr::from_bytes_with_nul_unchecked(mime_type))
+            .find( | mime_type | mime_types.iter().any( | m | m.as_bytes() == mime_type.to_bytes()))?;
+        let mut fds = [0, 0];
+        if libc_sys::pipe(fds.as_mut_ptr()) != 0 {
+            return None
+        }
+        self.lib.request(self.selection_offer, WL_DATA_OFFER_RECEIVE, &mut [arg_s(mime_type), arg_h(fds[1])]);
+        libc_sys::close(fds[1]);
+        (self.lib.wl_display_flush)(self.display);
+        // the other client writes the data and closes its end
+        let mut data = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = libc_sys::read(fds[0], buffer.as_mut_ptr() as *mut c_void, buffer.len());
+            if read <= 0 {
+                break
+            }
+            data.extend_from_slice(&buffer[..read as usize]);
+        }
+        libc_sys::close(fds[0]);
+        String::from_utf8(data).ok()
+    }
+
+    unsafe fn destroy_offer(&mut self, offer: *mut wl_data_offer) {
+        if !offer.is_null() {
+            self.offer_mime_types.remove(&(offer as usize));
+            self.lib.request_destroy(offer, WL_DATA_OFFER_DESTROY);
+        }
+    }
+}
+
+static REGISTRY_LISTENER: wl_registry_listener = wl_registry_listener {
+    global: registry_global,
+    global_remove: registry_global_remove,
+};
+
+static OUTPUT_LISTENER: wl_output_listener = wl_output_listener {
+    geometry: output_geometry,
+    mode: output_mode,
+    done: output_done,
+    scale: output_scale,
+};
+
+static XDG_WM_BASE_LISTENER: xdg_wm_base_listener = xdg_wm_base_listener {
+    ping: xdg_wm_base_ping,
+};
+
+static SEAT_LISTENER: wl_seat_listener = wl_seat_listener {
+    capabilities: seat_capabilities,
+    name: seat_name,
+};
+
+static POINTER_LISTENER: wl_pointer_listener = wl_pointer_listener {
+    enter: pointer_enter,
+    leave: pointer_leave,
+    motion: pointer_motion,
+    button: pointer_button,
+    axis: pointer_axis,
+    frame: pointer_frame,
+    axis_source: pointer_axis_source,
+    axis_stop: pointer_axis_stop,
+    axis_discrete: pointer_axis_discrete,
+};
+
+static KEYBOARD_LISTENER: wl_keyboard_listener = wl_keyboard_listener {
+    keymap: keyboard_keymap,
+    enter: keyboard_enter,
+    leave: keyboard_leave,
+    key: keyboard_key,
+    modifiers: keyboard_modifiers,
+    repeat_info: keyboard_repeat_info,
+};
+
+static TOUCH_LISTENER: wl_touch_listener = wl_touch_listener {
+    down: touch_down,
+    up: touch_up,
+    motion: touch_motion,
+    frame: touch_frame,
+    cancel: touch_cancel,
+};
+
+static DATA_DEVICE_LISTENER: wl_data_device_listener = wl_data_device_listener {
+    data_offer: data_device_data_offer,
+    enter: data_device_enter,
+    leave: data_device_leave,
+    motion: data_device_motion,
+    drop: data_device_drop,
+    selection: data_device_selection,
+};
+
+static DATA_OFFER_LISTENER: wl_data_offer_listener = wl_data_offer_listener {
+    offer: data_offer_offer,
+    source_actions: data_offer_source_actions,
+    action: data_offer_action,
+};
+
+static DATA_SOURCE_LISTENER: wl_data_source_listener = wl_data_source_listener {
+    target: data_source_target,
+    send: data_source_send,
+    cancelled: data_source_cancelled,
+    dnd_drop_performed: data_source_dnd_drop_performed,
+    dnd_finished: data_source_dnd_finished,
+    action: data_source_action,
+};
+
+unsafe fn app_from(data: *mut c_void) -> &'static mut WaylandApp {
+    &mut *(data as *mut WaylandApp)
+}
+
+unsafe extern "C" fn registry_global(data: *mut c_void, registry: *mut wl_registry, name: u32, interface: *const c_char, version: u32) {
+    let app = app_from(data);
+    let lib = &app.lib;
+    match CStr::from_ptr(interface).to_bytes() {
+        b"wl_compositor" => {
+            app.compositor = lib.bind(registry, name, lib.wl_compositor_interface, version.min(4));
+        }
+        b"wl_shm" => {
+            app.shm = lib.bind(registry, name, lib.wl_shm_interface, 1);
+        }
+        b"xdg_wm_base" => {
+            app.xdg_wm_base = lib.bind(registry, name, &xdg_wm_base_interface, version.min(5));
+            lib.add_listener(app.xdg_wm_base, &XDG_WM_BASE_LISTENER, data);
+        }
+        b"wl_seat" if app.seat.is_null() => {
+            app.seat = lib.bind(registry, name, lib.wl_seat_interface, version.min(5));
+            lib.add_listener(app.seat, &SEAT_LISTENER, data);
+            if !app.data_device_manager.is_null() {
+                create_data_device(app);
+            }
+        }
+        b"wl_output" => {
+            let output = lib.bind(registry, name, lib.wl_output_interface, version.min(3));
+            lib.add_listener(output, &OUTPUT_LISTENER, data);
+            app.outputs.push(WaylandOutput {name, output, scale: 1});
+        }
+        b"wl_data_device_manager" => {
+            app.data_device_manager = lib.bind(registry, name, lib.wl_data_device_manager_interface, version.min(3));
+            if !app.seat.is_null() {
+                create_data_device(app);
+            }
+        }
+        b"wp_fractional_scale_manager_v1" => {
+            app.fractional_scale_manager = lib.bind(registry, name, &wp_fractional_scale_manager_v1_interface, 1);
+        }
+        b"wp_viewporter" => {
+            app.viewporter = lib.bind(registry, name, &wp_viewporter_interface, 1);
+        }
+        b"zxdg_decoration_manager_v1" => {
+            app.decoration_manager = lib.bind(registry, name, &zxdg_decoration_manager_v1_interface, 1);
+        }
+        _ => ()
+    }
+}
+
+unsafe fn create_data_device(app: &mut WaylandApp) {
+    app.data_device = app.lib.request_new(
+        app.data_device_manager,
+        WL_DATA_DEVICE_MANAGER_GET_DATA_DEVICE,
+        app.lib.wl_data_device_interface,
+        &mut [arg_n(), arg_o(app.seat)]
+    );
+    let data = app as *mut WaylandApp as *mut c_void;
+    app.lib.add_listener(app.data_device, &DATA_DEVICE_LISTENER, data);
+}
+
+unsafe extern "C" fn registry_global_remove(data: *mut c_void, _registry: *mut wl_registry, name: u32) {
+    let app = app_from(data);
+    if let Some(index) = app.outputs.iter().position( | o | o.name == name) {
+        let output = app.outputs.remove(index);
+        (app.lib.wl_proxy_destroy)(output.output);
+    }
+}
+
+unsafe extern "C" fn output_geometry(_data: *mut c_void, _output: *mut wl_output, _x: i32, _y: i32, _physical_width: i32, _physical_height: i32, _subpixel: i32, _make: *const c_char, _model: *const c_char, _transform: i32) {
+}
+
+unsafe extern "C" fn output_mode(_data: *mut c_void, _output: *mut wl_output, _flags: u32, _width: i32, _height: i32, _refresh: i32) {
+}
+
+unsafe extern "C" fn output_done(_data: *mut c_void, _output: *mut wl_output) {
+}
+
+unsafe extern "C" fn output_scale(data: *mut c_void, output: *mut wl_output, factor: i32) {
+    let app = app_from(data);
+    if let Some(o) = app.outputs.iter_mut().find( | o | o.output == output) {
+        o.scale = factor.max(1);
+    }
+}
+
+unsafe extern "C" fn xdg_wm_base_ping(data: *mut c_void, wm_base: *mut xdg_wm_base, serial: u32) {
+    app_from(data).lib.request(wm_base, XDG_WM_BASE_PONG, &mut [arg_u(serial)]);
+}
+
+unsafe extern "C" fn seat_capabilities(data: *mut c_void, seat: *mut wl_seat, capabilities: u32) {
+    let app = app_from(data);
+    let lib = &app.lib;
+    let has_pointer = capabilities & WL_SEAT_CAPABILITY_POINTER != 0;
+    if has_pointer && app.pointer.is_null() {
+        app.pointer = lib.request_new(seat, WL_SEAT_GET_POINTER, lib.wl_pointer_interface, &mut [arg_n()]);
+        lib.add_listener(app.pointer, &POINTER_LISTENER, data);
+    }
+    else if !has_pointer && !app.pointer.is_null() {
+        lib.request_destroy(app.pointer, WL_POINTER_RELEASE);
+        app.pointer = ptr::null_mut();
+        app.pointer_surface = 0;
+    }
+    let has_keyboard = capabilities & WL_SEAT_CAPABILITY_KEYBOARD != 0;
+    if has_keyboard && app.keyboard.is_null() {
+        app.keyboard = lib.request_new(seat, WL_SEAT_GET_KEYBOARD, lib.wl_keyboard_interface, &mut [arg_n()]);
+        lib.add_listener(app.keyboard, &KEYBOARD_LISTENER, data);
+    }
+    else if !has_keyboard && !app.keyboard.is_null() {
+        lib.request_destroy(app.keyboard, WL_KEYBOARD_RELEASE);
+        app.keyboard = ptr::null_mut();
+        app.keyboard_surface = 0;
+        app.repeat_key = None;
+        app.timers.stop_timer(KEY_REPEAT_TIMER_ID);
+    }
+    let has_touch = capabilities & WL_SEAT_CAPABILITY_TOUCH != 0;
+    if has_touch && app.touch.is_null() {
+        app.touch = lib.request_new(seat, WL_SEAT_GET_TOUCH, lib.wl_touch_interface, &mut [arg_n()]);
+        lib.add_listener(app.touch, &TOUCH_LISTENER, data);
+    }
+    else if !has_touch && !app.touch.is_null() {
+        lib.request_destroy(app.touch, WL_TOUCH_RELEASE);
+        app.touch = ptr::null_mut();
+        app.touches.clear();
+    }
+}
+
+unsafe extern "C" fn seat_name(_data: *mut c_void, _seat: *mut wl_seat, _name: *const c_char) {
+}
+
+unsafe extern "C" fn pointer_enter(data: *mut c_void, _pointer: *mut wl_pointer, serial: u32, surface: *mut wl_surface, x: wl_fixed_t, y: wl_fixed_t) {
+    let app = app_from(data);
+    app.pointer_surface = surface as usize;
+    app.pointer_enter_serial = serial;
+    // the compositor doesn't keep our cursor across surfaces
+    let cursor = app.internal_cursor;
+    app.set_mouse_cursor_(cursor);
+    app.pointer_motion(DVec2 {x: wl_fixed_to_f64(x), y: wl_fixed_to_f64(y)});
+}
+
+unsafe extern "C" fn pointer_leave(data: *mut c_void, _pointer: *mut wl_pointer, _serial: u32, _surface: *mut wl_surface) {
+    app_from(data).pointer_surface = 0;
+}
+
+unsafe extern "C" fn pointer_motion(data: *mut c_void, _pointer: *mut wl_pointer, _time: u32, x: wl_fixed_t, y: wl_fixed_t) {
+    app_from(data).pointer_motion(DVec2 {x: wl_fixed_to_f64(x), y: wl_fixed_to_f64(y)});
+}
+
+unsafe extern "C" fn pointer_button(data: *mut c_void, _pointer: *mut wl_pointer, serial: u32, _time: u32, button: u32, state: u32) {
+    app_from(data).pointer_button(serial, button, state == WL_POINTER_BUTTON_STATE_PRESSED);
+}
+
+unsafe extern "C" fn pointer_axis(data: *mut c_void, _pointer: *mut wl_pointer, _time: u32, axis: u32, value: wl_fixed_t) {
+    let app = app_from(data);
+    // wheels that send discrete steps get those instead, see pointer_axis_discrete
+    if app.pending_scroll_is_wheel {
+        return
+    }
+    match axis {
+        WL_POINTER_AXIS_VERTICAL_SCROLL => app.pending_scroll.y += wl_fixed_to_f64(value),
+        WL_POINTER_AXIS_HORIZONTAL_SCROLL => app.pending_scroll.x += wl_fixed_to_f64(value),
+        _ => ()
+    }
+}
+
+unsafe extern "C" fn pointer_frame(data: *mut c_void, _pointer: *mut wl_pointer) {
+    let app = app_from(data);
+    app.pointer_frame();
+    app.pending_scroll_is_wheel = false;
+}
+
+unsafe extern "C" fn pointer_axis_source(data: *mut c_void, _pointer: *mut wl_pointer, axis_source: u32) {
+    app_from(data).pending_scroll_is_wheel = axis_source == WL_POINTER_AXIS_SOURCE_WHEEL;
+}
+
+unsafe extern "C" fn pointer_axis_stop(_data: *mut c_void, _pointer: *mut wl_pointer, _time: u32, _axis: u32) {
+}
+
+unsafe extern "C" fn pointer_axis_discrete(data: *mut c_void, _pointer: *mut wl_pointer, axis: u32, discrete: i32) {
+    let app = app_from(data);
+    // one wheel step scrolls as much as a step on windows
+    app.pending_scroll_is_wheel = true;
+    match axis {
+        WL_POINTER_AXIS_VERTICAL_SCROLL => app.pending_scroll.y = discrete as f64 * 120.0,
+        WL_POINTER_AXIS_HORIZONTAL_SCROLL => app.pending_scroll.x = discrete as f64 * 120.0,
+        _ => ()
+    }
+}
+
+unsafe extern "C" fn keyboard_keymap(data: *mut c_void, _keyboard: *mut wl_keyboard, format: u32, fd: i32, size: u32) {
+    let app = app_from(data);
+    if format != WL_KEYBOARD_KEYMAP_FORMAT_XKB_V1 {
+        libc_sys::close(fd);
+        return
+    }
+    app.update_keymap(fd, size);
+}
+
+unsafe extern "C" fn keyboard_enter(data: *mut c_void, _keyboard: *mut wl_keyboard, serial: u32, surface: *mut wl_surface, _keys: *mut wl_array) {
+    let app = app_from(data);
+    app.last_serial = serial;
+    app.keyboard_surface = surface as usize;
+    if let Some(window) = app.window_for_surface(surface as usize) {
+        window.send_focus_event();
+    }
+}
+
+unsafe extern "C" fn keyboard_leave(data: *mut c_void, _keyboard: *mut wl_keyboard, _serial: u32, surface: *mut wl_surface) {
+    let app = app_from(data);
+    app.keyboard_surface = 0;
+    app.stop_key_repeat();
+    if !app.xkb_compose_state.is_null() {
+        (app.xkb.xkb_compose_state_reset)(app.xkb_compose_state);
+    }
+    if let Some(window) = app.window_for_surface(surface as usize) {
+        window.send_focus_lost_event();
+    }
+}
+
+unsafe extern "C" fn keyboard_key(data: *mut c_void, _keyboard: *mut wl_keyboard, serial: u32, _time: u32, key: u32, state: u32) {
+    let app = app_from(data);
+    app.last_serial = serial;
+    // evdev keycodes are offset by 8 from xkb ones
+    let keycode = key + 8;
+    if state == WL_KEYBOARD_KEY_STATE_PRESSED {
+        app.stop_key_repeat();
+        app.key_down(keycode, false);
+        if app.repeat_rate > 0 && !app.xkb_keymap.is_null() && (app.xkb.xkb_keymap_key_repeats)(app.xkb_keymap, keycode) != 0 {
+            app.repeat_key = Some(keycode);
+            app.timers.start_timer(KEY_REPEAT_TIMER_ID, app.repeat_delay as f64 / 1000.0, false);
+        }
+    }
+    else {
+        if app.repeat_key == Some(keycode) {
+            app.stop_key_repeat();
+        }
+        app.key_up(keycode);
+    }
+}
+
+unsafe extern "C" fn keyboard_modifiers(data: *mut c_void, _keyboard: *mut wl_keyboard, _serial: u32, mods_depressed: u32, mods_latched: u32, mods_locked: u32, group: u32) {
+    app_from(data).update_modifiers(mods_depressed, mods_latched, mods_locked, group);
+}
+
+unsafe extern "C" fn keyboard_repeat_info(data: *mut c_void, _keyboard: *mut wl_keyboard, rate: i32, delay: i32) {
+    let app = app_from(data);
+    app.repeat_rate = rate;
+    app.repeat_delay = delay;
+}
+
+unsafe extern "C" fn touch_down(data: *mut c_void, _touch: *mut wl_touch, serial: u32, _time: u32, surface: *mut wl_surface, id: i32, x: wl_fixed_t, y: wl_fixed_t) {
+    let app = app_from(data);
+    app.last_serial = serial;
+    let Some(window) = app.window_for_surface(surface as usize) else {return};
+    app.touches.retain( | touch | touch.id != id);
+    app.touches.push(WaylandTouch {
+        id,
+        window_id: window.window_id,
+        state: TouchState::Start,
+        abs: DVec2 {x: wl_fixed_to_f64(x), y: wl_fixed_to_f64(y)},
+    });
+}
+
+unsafe extern "C" fn touch_up(data: *mut c_void, _touch: *mut wl_touch, serial: u32, _time: u32, id: i32) {
+    let app = app_from(data);
+    app.last_serial = serial;
+    if let Some(touch) = app.touches.iter_mut().find( | touch | touch.id == id) {
+        touch.state = TouchState::Stop;
+    }
+}
+
+unsafe extern "C" fn touch_motion(data: *mut c_void, _touch: *mut wl_touch, _time: u32, id: i32, x: wl_fixed_t, y: wl_fixed_t) {
+    let app = app_from(data);
+    if let Some(touch) = app.touches.iter_mut().find( | touch | touch.id == id) {
+        touch.abs = DVec2 {x: wl_fixed_to_f64(x), y: wl_fixed_to_f64(y)};
+        if !matches!(touch.state, TouchState::Start) {
+            touch.state = TouchState::Move;
+        }
+    }
+}
+
+unsafe extern "C" fn touch_frame(data: *mut c_void, _touch: *mut wl_touch) {
+    app_from(data).send_touches();
+}
+
+unsafe extern "C" fn touch_cancel(data: *mut c_void, _touch: *mut wl_touch) {
+    let app = app_from(data);
+    for touch in &mut app.touches {
+        touch.state = TouchState::Stop;
+    }
+    app.send_touches();
+}
+
+unsafe extern "C" fn data_device_data_offer(data: *mut c_void, _data_device: *mut wl_data_device, offer: *mut wl_data_offer) {
+    let app = app_from(data);
+    app.offer_mime_types.insert(offer as usize, Vec::new());
+    app.lib.add_listener(offer, &DATA_OFFER_LISTENER, data);
+}
+
+unsafe extern "C" fn data_device_enter(data: *mut c_void, _data_device: *mut wl_data_device, _serial: u32, _surface: *mut wl_surface, _x: wl_fixed_t, _y: wl_fixed_t, offer: *mut wl_data_offer) {
+    // drag and drop isn't supported yet, an offer we don't accept refuses the drop
+    let app = app_from(data);
+    app.destroy_offer(offer);
+}
+
+unsafe extern "C" fn data_device_leave(_data: *mut c_void, _data_device: *mut wl_data_device) {
+}
+
+unsafe extern "C" fn data_device_motion(_data: *mut c_void, _data_device: *mut wl_data_device, _time: u32, _x: wl_fixed_t, _y: wl_fixed_t) {
+}
+
+unsafe extern "C" fn data_device_drop(_data: *mut c_void, _data_device: *mut wl_data_device) {
+}
+
+unsafe extern "C" fn data_device_selection(data: *mut c_void, _data_device: *mut wl_data_device, offer: *mut wl_data_offer) {
+    let app = app_from(data);
+    let old_offer = std::mem::replace(&mut app.selection_offer, offer);
+    if old_offer != offer {
+        app.destroy_offer(old_offer);
+    }
+}
+
+unsafe extern "C" fn data_offer_offer(data: *mut c_void, offer: *mut wl_data_offer, mime_type: *const c_char) {
+    let app = app_from(data);
+    if let Some(mime_types) = app.offer_mime_types.get_mut(&(offer as usize)) {
+        mime_types.push(CStr::from_ptr(mime_type).to_string_lossy().into_owned());
+    }
+}
+
+unsafe extern "C" fn data_offer_source_actions(_data: *mut c_void, _offer: *mut wl_data_offer, _source_actions: u32) {
+}
+
+unsafe extern "C" fn data_offer_action(_data: *mut c_void, _offer: *mut wl_data_offer, _dnd_action: u32) {
+}
+
+unsafe extern "C" fn data_source_target(_data: *mut c_void, _source: *mut wl_data_source, _mime_type: *const c_char) {
+}
+
+unsafe extern "C" fn data_source_send(data: *mut c_void, _source: *mut wl_data_source, _mime_type: *const c_char, fd: i32) {
+    let app = app_from(data);
+    let mut bytes = app.clipboard.as_bytes();
+    while !bytes.is_empty() {
+        let written = libc_sys::write(fd, bytes.as_ptr() as *const c_void, bytes.len());
+        if written <= 0 {
+            break
+        }
+        bytes = &bytes[written as usize..];
+    }
+    libc_sys::close(fd);
+}
+
+unsafe extern "C" fn data_source_cancelled(data: *mut c_void, source: *mut wl_data_source) {
+    // another client took over the clipboard
+    let app = app_from(data);
+    if app.data_source == source {
+        app.data_source = ptr::null_mut();
+    }
+    app.lib.request_destroy(source, WL_DATA_SOURCE_DESTROY);
+}
+
+unsafe extern "C" fn data_source_dnd_drop_performed(_data: *mut c_void, _source: *mut wl_data_source) {
+}
+
+unsafe extern "C" fn data_source_dnd_finished(_data: *mut c_void, _source: *mut wl_data_source) {
+}
+
+unsafe extern "C" fn data_source_action(_data: *mut c_void, _source: *mut wl_data_source, _dnd_action: u32) {
+}
@@ -0,0 +1,43 @@
+use {
+    crate::{
+        event::{
+            MouseDownEvent,
+            MouseUpEvent,
+            MouseMoveEvent,
+            ScrollEvent,
+            TouchUpdateEvent,
+            WindowGeomChangeEvent,
+            WindowDragQueryEvent,
+            WindowCloseRequestedEvent,
+            WindowClosedEvent,
+            TextInputEvent,
+            KeyEvent,
+            TextClipboardEvent,
+            TimerEvent,
+        },
+    }
+};
+
+#[derive(Debug)]
+pub enum WaylandEvent {
+    AppGotFocus,
+    AppLostFocus,
+    WindowGeomChange(WindowGeomChangeEvent),
+    WindowClosed(WindowClosedEvent),
+    Paint,
+    
+    MouseDown(MouseDownEvent),
+    MouseUp(MouseUpEvent),
+    MouseMove(MouseMoveEvent),
+    Scroll(ScrollEvent),
+    TouchUpdate(TouchUpdateEvent),
+    
+    WindowDragQuery(WindowDragQueryEvent),
+    WindowCloseRequested(WindowCloseRequestedEvent),
+    TextInput(TextInputEvent),
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    TextCopy(TextClipboardEvent),
+    TextCut(TextClipboardEvent),
+    Timer(TimerEvent),
+}
@@ -0,0 +1,630 @@
+#![allow(non_camel_case_types)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+
+// Client side libwayland bindings. The libraries are loaded at runtime so the same
+// binary keeps working on X11-only systems. Requests go through the untyped
+// wl_proxy_marshal_array_flags, the protocol extensions we need (xdg-shell,
+// fractional-scale, viewporter, xdg-decoration) are described by hand below.
+
+use {
+    std::{
+        ffi::CStr,
+        os::raw::{c_char, c_int, c_void},
+        ptr,
+    },
+    self::super::super::module_loader::ModuleLoader,
+};
+
+#[repr(C)]
+pub struct wl_display {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+pub struct wl_proxy {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+pub struct wl_egl_window {
+    _unused: [u8; 0],
+}
+
+pub type wl_registry = wl_proxy;
+pub type wl_callback = wl_proxy;
+pub type wl_compositor = wl_proxy;
+pub type wl_surface = wl_proxy;
+pub type wl_seat = wl_proxy;
+pub type wl_pointer = wl_proxy;
+pub type wl_keyboard = wl_proxy;
+pub type wl_touch = wl_proxy;
+pub type wl_output = wl_proxy;
+pub type wl_shm = wl_proxy;
+pub type wl_buffer = wl_proxy;
+pub type wl_data_device_manager = wl_proxy;
+pub type wl_data_device = wl_proxy;
+pub type wl_data_source = wl_proxy;
+pub type wl_data_offer = wl_proxy;
+pub type xdg_wm_base = wl_proxy;
+pub type xdg_surface = wl_proxy;
+pub type xdg_toplevel = wl_proxy;
+pub type wp_fractional_scale_manager_v1 = wl_proxy;
+pub type wp_fractional_scale_v1 = wl_proxy;
+pub type wp_viewporter = wl_proxy;
+pub type wp_viewport = wl_proxy;
+pub type zxdg_decoration_manager_v1 = wl_proxy;
+pub type zxdg_toplevel_decoration_v1 = wl_proxy;
+
+pub type wl_fixed_t = i32;
+
+pub fn wl_fixed_to_f64(f: wl_fixed_t) -> f64 {
+    f as f64 / 256.0
+}
+
+#[repr(C)]
+pub struct wl_message {
+    pub name: *const c_char,
+    pub signature: *const c_char,
+    pub types: *const *const wl_interface,
+}
+
+#[repr(C)]
+pub struct wl_interface {
+    pub name: *const c_char,
+    pub version: c_int,
+    pub method_count: c_int,
+    pub methods: *const wl_message,
+    pub event_count: c_int,
+    pub events: *const wl_message,
+}
+
+// the hand written interfaces below are immutable statics
+unsafe impl Sync for wl_message {}
+unsafe impl Sync for wl_interface {}
+
+#[repr(C)]
+pub struct wl_array {
+    pub size: usize,
+    pub alloc: usize,
+    pub data: *mut c_void,
+}
+
+impl wl_array {
+    pub unsafe fn as_u32_slice(&self) -> &[u32] {
+        if self.data.is_null() {
+            return &[]
+        }
+        std::slice::from_raw_parts(self.data as *const u32, self.size / 4)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union wl_argument {
+    pub i: i32,
+    pub u: u32,
+    pub f: wl_fixed_t,
+    pub s: *const c_char,
+    pub o: *mut wl_proxy,
+    pub n: u32,
+    pub a: *mut wl_array,
+    pub h: i32,
+}
+
+pub fn arg_i(i: i32) -> wl_argument {wl_argument {i}}
+pub fn arg_u(u: u32) -> wl_argument {wl_argument {u}}
+pub fn arg_s(s: &CStr) -> wl_argument {wl_argument {s: s.as_ptr()}}
+pub fn arg_o(o: *mut wl_proxy) -> wl_argument {wl_argument {o}}
+pub fn arg_h(h: i32) -> wl_argument {wl_argument {h}}
+pub fn arg_n() -> wl_argument {wl_argument {n: 0}}
+pub fn arg_null() -> wl_argument {wl_argument {o: ptr::null_mut()}}
+
+pub const WL_MARSHAL_FLAG_DESTROY: u32 = 1;
+
+// wl_display
+pub const WL_DISPLAY_GET_REGISTRY: u32 = 1;
+
+// wl_registry
+pub const WL_REGISTRY_BIND: u32 = 0;
+
+// wl_compositor
+pub const WL_COMPOSITOR_CREATE_SURFACE: u32 = 0;
+
+// wl_surface
+pub const WL_SURFACE_DESTROY: u32 = 0;
+pub const WL_SURFACE_ATTACH: u32 = 1;
+pub const WL_SURFACE_DAMAGE: u32 = 2;
+pub const WL_SURFACE_COMMIT: u32 = 6;
+pub const WL_SURFACE_SET_BUFFER_SCALE: u32 = 8;
+
+// wl_seat
+pub const WL_SEAT_GET_POINTER: u32 = 0;
+pub const WL_SEAT_GET_KEYBOARD: u32 = 1;
+pub const WL_SEAT_GET_TOUCH: u32 = 2;
+pub const WL_SEAT_CAPABILITY_POINTER: u32 = 1;
+pub const WL_SEAT_CAPABILITY_KEYBOARD: u32 = 2;
+pub const WL_SEAT_CAPABILITY_TOUCH: u32 = 4;
+
+// wl_pointer
+pub const WL_POINTER_SET_CURSOR: u32 = 0;
+pub const WL_POINTER_RELEASE: u32 = 1;
+pub const WL_POINTER_BUTTON_STATE_PRESSED: u32 = 1;
+pub const WL_POINTER_AXIS_VERTICAL_SCROLL: u32 = 0;
+pub const WL_POINTER_AXIS_HORIZONTAL_SCROLL: u32 = 1;
+pub const WL_POINTER_AXIS_SOURCE_WHEEL: u32 = 0;
+
+// linux/input-event-codes.h
+pub const BTN_LEFT: u32 = 0x110;
+pub const BTN_RIGHT: u32 = 0x111;
+pub const BTN_MIDDLE: u32 = 0x112;
+
+// wl_keyboard
+pub const WL_KEYBOARD_RELEASE: u32 = 0;
+pub const WL_KEYBOARD_KEYMAP_FORMAT_XKB_V1: u32 = 1;
+pub const WL_KEYBOARD_KEY_STATE_PRESSED: u32 = 1;
+
+// wl_touch
+pub const WL_TOUCH_RELEASE: u32 = 0;
+
+// wl_data_device_manager
+pub const WL_DATA_DEVICE_MANAGER_CREATE_DATA_SOURCE: u32 = 0;
+pub const WL_DATA_DEVICE_MANAGER_GET_DATA_DEVICE: u32 = 1;
+
+// wl_data_source
+pub const WL_DATA_SOURCE_OFFER: u32 = 0;
+pub const WL_DATA_SOURCE_DESTROY: u32 = 1;
+
+// wl_data_device
+pub const WL_DATA_DEVICE_SET_SELECTION: u32 = 1;
+
+// wl_data_offer
+pub const WL_DATA_OFFER_RECEIVE: u32 = 1;
+pub const WL_DATA_OFFER_DESTROY: u32 = 2;
+
+// xdg_wm_base
+pub const XDG_WM_BASE_GET_XDG_SURFACE: u32 = 2;
+pub const XDG_WM_BASE_PONG: u32 = 3;
+
+// xdg_surface
+pub const XDG_SURFACE_DESTROY: u32 = 0;
+pub const XDG_SURFACE_GET_TOPLEVEL: u32 = 1;
+pub const XDG_SURFACE_ACK_CONFIGURE: u32 = 4;
+
+// xdg_toplevel
+pub const XDG_TOPLEVEL_DESTROY: u32 = 0;
+pub const XDG_TOPLEVEL_SET_TITLE: u32 = 2;
+pub const XDG_TOPLEVEL_SET_APP_ID: u32 = 3;
+pub const XDG_TOPLEVEL_MOVE: u32 = 5;
+pub const XDG_TOPLEVEL_RESIZE: u32 = 6;
+pub const XDG_TOPLEVEL_SET_MAXIMIZED: u32 = 9;
+pub const XDG_TOPLEVEL_UNSET_MAXIMIZED: u32 = 10;
+pub const XDG_TOPLEVEL_SET_FULLSCREEN: u32 = 11;
+pub const XDG_TOPLEVEL_UNSET_FULLSCREEN: u32 = 12;
+pub const XDG_TOPLEVEL_SET_MINIMIZED: u32 = 13;
+
+pub const XDG_TOPLEVEL_STATE_MAXIMIZED: u32 = 1;
+pub const XDG_TOPLEVEL_STATE_FULLSCREEN: u32 = 2;
+pub const XDG_TOPLEVEL_STATE_ACTIVATED: u32 = 4;
+
+pub const XDG_TOPLEVEL_RESIZE_EDGE_TOP: u32 = 1;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM: u32 = 2;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_LEFT: u32 = 4;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_TOP_LEFT: u32 = 5;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_LEFT: u32 = 6;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_RIGHT: u32 = 8;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_TOP_RIGHT: u32 = 9;
+pub const XDG_TOPLEVEL_RESIZE_EDGE_BOTTOM_RIGHT: u32 = 10;
+
+// wp_fractional_scale_manager_v1
+pub const WP_FRACTIONAL_SCALE_MANAGER_V1_GET_FRACTIONAL_SCALE: u32 = 1;
+// wp_fractional_scale_v1
+pub const WP_FRACTIONAL_SCALE_V1_DESTROY: u32 = 0;
+
+// wp_viewporter
+pub const WP_VIEWPORTER_GET_VIEWPORT: u32 = 1;
+// wp_viewport
+pub const WP_VIEWPORT_DESTROY: u32 = 0;
+pub const WP_VIEWPORT_SET_DESTINATION: u32 = 2;
+
+// zxdg_decoration_manager_v1
+pub const ZXDG_DECORATION_MANAGER_V1_GET_TOPLEVEL_DECORATION: u32 = 1;
+// zxdg_toplevel_decoration_v1
+pub const ZXDG_TOPLEVEL_DECORATION_V1_DESTROY: u32 = 0;
+pub const ZXDG_TOPLEVEL_DECORATION_V1_SET_MODE: u32 = 1;
+pub const ZXDG_TOPLEVEL_DECORATION_V1_MODE_SERVER_SIDE: u32 = 2;
+
+// Protocol extensions, transcribed from the xml files in wayland-protocols.
+// Object and new_id arguments don't need types here because every request that
+// creates an object passes its interface explicitly, and no event creates one.
+
+static NULL_TYPES: [usize; 8] = [0; 8];
+
+macro_rules! wl_msg {
+    ($name:literal, $signature:literal) => {
+        wl_message {
+            name: concat!($name, "\0").as_ptr() as *const c_char,
+            signature: concat!($signature, "\0").as_ptr() as *const c_char,
+            types: &NULL_TYPES as *const _ as *const *const wl_interface,
+        }
+    }
+}
+
+macro_rules! wl_iface {
+    ($name:literal, $version:literal, $requests:ident, $events:ident) => {
+        wl_interface {
+            name: concat!($name, "\0").as_ptr() as *const c_char,
+            version: $version,
+            method_count: $requests.len() as c_int,
+            methods: $requests.as_ptr(),
+            event_count: $events.len() as c_int,
+            events: $events.as_ptr(),
+        }
+    }
+}
+
+static NO_MESSAGES: [wl_message; 0] = [];
+
+static XDG_WM_BASE_REQUESTS: [wl_message; 4] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("create_positioner", "n"),
+    wl_msg!("get_xdg_surface", "no"),
+    wl_msg!("pong", "u"),
+];
+static XDG_WM_BASE_EVENTS: [wl_message; 1] = [
+    wl_msg!("ping", "u"),
+];
+pub static xdg_wm_base_interface: wl_interface = wl_iface!("xdg_wm_base", 5, XDG_WM_BASE_REQUESTS, XDG_WM_BASE_EVENTS);
+
+static XDG_SURFACE_REQUESTS: [wl_message; 5] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("get_toplevel", "n"),
+    wl_msg!("get_popup", "n?oo"),
+    wl_msg!("set_window_geometry", "iiii"),
+    wl_msg!("ack_configure", "u"),
+];
+static XDG_SURFACE_EVENTS: [wl_message; 1] = [
+    wl_msg!("configure", "u"),
+];
+pub static xdg_surface_interface: wl_interface = wl_iface!("xdg_surface", 5, XDG_SURFACE_REQUESTS, XDG_SURFACE_EVENTS);
+
+static XDG_TOPLEVEL_REQUESTS: [wl_message; 14] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("set_parent", "?o"),
+    wl_msg!("set_title", "s"),
+    wl_msg!("set_app_id", "s"),
+    wl_msg!("show_window_menu", "ouii"),
+    wl_msg!("move", "ou"),
+    wl_msg!("resize", "ouu"),
+    wl_msg!("set_max_size", "ii"),
+    wl_msg!("set_min_size", "ii"),
+    wl_msg!("set_maximized", ""),
+    wl_msg!("unset_maximized", ""),
+    wl_msg!("set_fullscreen", "?o"),
+    wl_msg!("unset_fullscreen", ""),
+    wl_msg!("set_minimized", ""),
+];
+static XDG_TOPLEVEL_EVENTS: [wl_message; 4] = [
+    wl_msg!("configure", "iia"),
+    wl_msg!("close", ""),
+    wl_msg!("configure_bounds", "4ii"),
+    wl_msg!("wm_capabilities", "5a"),
+];
+pub static xdg_toplevel_interface: wl_interface = wl_iface!("xdg_toplevel", 5, XDG_TOPLEVEL_REQUESTS, XDG_TOPLEVEL_EVENTS);
+
+static WP_FRACTIONAL_SCALE_MANAGER_V1_REQUESTS: [wl_message; 2] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("get_fractional_scale", "no"),
+];
+pub static wp_fractional_scale_manager_v1_interface: wl_interface = wl_iface!("wp_fractional_scale_manager_v1", 1, WP_FRACTIONAL_SCALE_MANAGER_V1_REQUESTS, NO_MESSAGES);
+
+static WP_FRACTIONAL_SCALE_V1_REQUESTS: [wl_message; 1] = [
+    wl_msg!("destroy", ""),
+];
+static WP_FRACTIONAL_SCALE_V1_EVENTS: [wl_message; 1] = [
+    wl_msg!("preferred_scale", "u"),
+];
+pub static wp_fractional_scale_v1_interface: wl_interface = wl_iface!("wp_fractional_scale_v1", 1, WP_FRACTIONAL_SCALE_V1_REQUESTS, WP_FRACTIONAL_SCALE_V1_EVENTS);
+
+static WP_VIEWPORTER_REQUESTS: [wl_message; 2] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("get_viewport", "no"),
+];
+pub static wp_viewporter_interface: wl_interface = wl_iface!("wp_viewporter", 1, WP_VIEWPORTER_REQUESTS, NO_MESSAGES);
+
+static WP_VIEWPORT_REQUESTS: [wl_message; 3] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("set_source", "ffff"),
+    wl_msg!("set_destination", "ii"),
+];
+pub static wp_viewport_interface: wl_interface = wl_iface!("wp_viewport", 1, WP_VIEWPORT_REQUESTS, NO_MESSAGES);
+
+static ZXDG_DECORATION_MANAGER_V1_REQUESTS: [wl_message; 2] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("get_toplevel_decoration", "no"),
+];
+pub static zxdg_decoration_manager_v1_interface: wl_interface = wl_iface!("zxdg_decoration_manager_v1", 1, ZXDG_DECORATION_MANAGER_V1_REQUESTS, NO_MESSAGES);
+
+static ZXDG_TOPLEVEL_DECORATION_V1_REQUESTS: [wl_message; 3] = [
+    wl_msg!("destroy", ""),
+    wl_msg!("set_mode", "u"),
+    wl_msg!("unset_mode", ""),
+];
+static ZXDG_TOPLEVEL_DECORATION_V1_EVENTS: [wl_message; 1] = [
+    wl_msg!("configure", "u"),
+];
+pub static zxdg_toplevel_decoration_v1_interface: wl_interface = wl_iface!("zxdg_toplevel_decoration_v1", 1, ZXDG_TOPLEVEL_DECORATION_V1_REQUESTS, ZXDG_TOPLEVEL_DECORATION_V1_EVENTS);
+
+// Listeners, one function pointer per event in protocol order.
+
+#[repr(C)]
+pub struct wl_registry_listener {
+    pub global: unsafe extern "C" fn(data: *mut c_void, registry: *mut wl_registry, name: u32, interface: *const c_char, version: u32),
+    pub global_remove: unsafe extern "C" fn(data: *mut c_void, registry: *mut wl_registry, name: u32),
+}
+
+#[repr(C)]
+pub struct wl_surface_listener {
+    pub enter: unsafe extern "C" fn(data: *mut c_void, surface: *mut wl_surface, output: *mut wl_output),
+    pub leave: unsafe extern "C" fn(data: *mut c_void, surface: *mut wl_surface, output: *mut wl_output),
+}
+
+#[repr(C)]
+pub struct wl_output_listener {
+    pub geometry: unsafe extern "C" fn(data: *mut c_void, output: *mut wl_output, x: i32, y: i32, physical_width: i32, physical_height: i32, subpixel: i32, make: *const c_char, model: *const c_char, transform: i32),
+    pub mode: unsafe extern "C" fn(data: *mut c_void, output: *mut wl_output, flags: u32, width: i32, height: i32, refresh: i32),
+    pub done: unsafe extern "C" fn(data: *mut c_void, output: *mut wl_output),
+    pub scale: unsafe extern "C" fn(data: *mut c_void, output: *mut wl_output, factor: i32),
+}
+
+#[repr(C)]
+pub struct wl_seat_listener {
+    pub capabilities: unsafe extern "C" fn(data: *mut c_void, seat: *mut wl_seat, capabilities: u32),
+    pub name: unsafe extern "C" fn(data: *mut c_void, seat: *mut wl_seat, name: *const c_char),
+}
+
+#[repr(C)]
+pub struct wl_pointer_listener {
+    pub enter: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, serial: u32, surface: *mut wl_surface, x: wl_fixed_t, y: wl_fixed_t),
+    pub leave: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, serial: u32, surface: *mut wl_surface),
+    pub motion: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, time: u32, x: wl_fixed_t, y: wl_fixed_t),
+    pub button: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, serial: u32, time: u32, button: u32, state: u32),
+    pub axis: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, time: u32, axis: u32, value: wl_fixed_t),
+    pub frame: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer),
+    pub axis_source: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, axis_source: u32),
+    pub axis_stop: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, time: u32, axis: u32),
+    pub axis_discrete: unsafe extern "C" fn(data: *mut c_void, pointer: *mut wl_pointer, axis: u32, discrete: i32),
+}
+
+#[repr(C)]
+pub struct wl_keyboard_listener {
+    pub keymap: unsafe extern "C" fn(data: *mut c_void, keyboard: *mut wl_keyboard, format: u32, fd: i32, size: u32),
+    pub enter: unsafe extern "C" fn(data: *mut c_void, keyboard: *mut wl_keyboard, serial: u32, surface: *mut wl_surface, keys: *mut wl_array),
+    pub leave: unsafe extern "C" fn(data: *mut c_void, keyboard: *mut wl_keyboard, serial: u32, surface: *mut wl_surface),
+    pub key: unsafe extern "C" fn(data: *mut c_void, keyboard: *mut wl_keyboard, serial: u32, time: u32, key: u32, state: u32),
+    pub modifiers: unsafe extern "C" fn(data: *mut c_void, keyboard: *mut wl_keyboard, serial: u32, mods_depressed: u32, mods_latched: u32, mods_locked: u32, group: u32),
+    pub repeat_info: unsafe extern "C" fn(data: *mut c_void, keyboard: *mut wl_keyboard, rate: i32, delay: i32),
+}
+
+#[repr(C)]
+pub struct wl_touch_listener {
+    pub down: unsafe extern "C" fn(data: *mut c_void, touch: *mut wl_touch, serial: u32, time: u32, surface: *mut wl_surface, id: i32, x: wl_fixed_t, y: wl_fixed_t),
+    pub up: unsafe extern "C" fn(data: *mut c_void, touch: *mut wl_touch, serial: u32, time: u32, id: i32),
+    pub motion: unsafe extern "C" fn(data: *mut c_void, touch: *mut wl_touch, time: u32, id: i32, x: wl_fixed_t, y: wl_fixed_t),
+    pub frame: unsafe extern "C" fn(data: *mut c_void, touch: *mut wl_touch),
+    pub cancel: unsafe extern "C" fn(data: *mut c_void, touch: *mut wl_touch),
+}
+
+#[repr(C)]
+pub struct wl_data_device_listener {
+    pub data_offer: unsafe extern "C" fn(data: *mut c_void, data_device: *mut wl_data_device, offer: *mut wl_data_offer),
+    pub enter: unsafe extern "C" fn(data: *mut c_void, data_device: *mut wl_data_device, serial: u32, surface: *mut wl_surface, x: wl_fixed_t, y: wl_fixed_t, offer: *mut wl_data_offer),
+    pub leave: unsafe extern "C" fn(data: *mut c_void, data_device: *mut wl_data_device),
+    pub motion: unsafe extern "C" fn(data: *mut c_void, data_device: *mut wl_data_device, time: u32, x: wl_fixed_t, y: wl_fixed_t),
+    pub drop: unsafe extern "C" fn(data: *mut c_void, data_device: *mut wl_data_device),
+    pub selection: unsafe extern "C" fn(data: *mut c_void, data_device: *mut wl_data_device, offer: *mut wl_data_offer),
+}
+
+#[repr(C)]
+pub struct wl_data_offer_listener {
+    pub offer: unsafe extern "C" fn(data: *mut c_void, offer: *mut wl_data_offer, mime_type: *const c_char),
+    pub source_actions: unsafe extern "C" fn(data: *mut c_void, offer: *mut wl_data_offer, source_actions: u32),
+    pub action: unsafe extern "C" fn(data: *mut c_void, offer: *mut wl_data_offer, dnd_action: u32),
+}
+
+#[repr(C)]
+pub struct wl_data_source_listener {
+    pub target: unsafe extern "C" fn(data: *mut c_void, source: *mut wl_data_source, mime_type: *const c_char),
+    pub send: unsafe extern "C" fn(data: *mut c_void, source: *mut wl_data_source, mime_type: *const c_char, fd: i32),
+    pub cancelled: unsafe extern "C" fn(data: *mut c_void, source: *mut wl_data_source),
+    pub dnd_drop_performed: unsafe extern "C" fn(data: *mut c_void, source: *mut wl_data_source),
+    pub dnd_finished: unsafe extern "C" fn(data: *mut c_void, source: *mut wl_data_source),
+    pub action: unsafe extern "C" fn(data: *mut c_void, source: *mut wl_data_source, dnd_action: u32),
+}
+
+#[repr(C)]
+pub struct xdg_wm_base_listener {
+    pub ping: unsafe extern "C" fn(data: *mut c_void, wm_base: *mut xdg_wm_base, serial: u32),
+}
+
+#[repr(C)]
+pub struct xdg_surface_listener {
+    pub configure: unsafe extern "C" fn(data: *mut c_void, surface: *mut xdg_surface, serial: u32),
+}
+
+#[repr(C)]
+pub struct xdg_toplevel_listener {
+    pub configure: unsafe extern "C" fn(data: *mut c_void, toplevel: *mut xdg_toplevel, width: i32, height: i32, states: *mut wl_array),
+    pub close: unsafe extern "C" fn(data: *mut c_void, toplevel: *mut xdg_toplevel),
+    pub configure_bounds: unsafe extern "C" fn(data: *mut c_void, toplevel: *mut xdg_toplevel, width: i32, height: i32),
+    pub wm_capabilities: unsafe extern "C" fn(data: *mut c_void, toplevel: *mut xdg_toplevel, capabilities: *mut wl_array),
+}
+
+#[repr(C)]
+pub struct wp_fractional_scale_v1_listener {
+    pub preferred_scale: unsafe extern "C" fn(data: *mut c_void, fractional_scale: *mut wp_fractional_scale_v1, scale: u32),
+}
+
+#[repr(C)]
+pub struct zxdg_toplevel_decoration_v1_listener {
+    pub configure: unsafe extern "C" fn(data: *mut c_void, decoration: *mut zxdg_toplevel_decoration_v1, mode: u32),
+}
+
+// libwayland-cursor
+
+#[repr(C)]
+pub struct wl_cursor_theme {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+pub struct wl_cursor_image {
+    pub width: u32,
+    pub height: u32,
+    pub hotspot_x: u32,
+    pub hotspot_y: u32,
+    pub delay: u32,
+}
+
+#[repr(C)]
+pub struct wl_cursor {
+    pub image_count: u32,
+    pub images: *mut *mut wl_cursor_image,
+    pub name: *mut c_char,
+}
+
+pub struct LibWayland {
+    pub wl_display_connect: unsafe extern "C" fn(name: *const c_char) -> *mut wl_display,
+    pub wl_display_disconnect: unsafe extern "C" fn(display: *mut wl_display),
+    pub wl_display_get_fd: unsafe extern "C" fn(display: *mut wl_display) -> c_int,
+    pub wl_display_dispatch_pending: unsafe extern "C" fn(display: *mut wl_display) -> c_int,
+    pub wl_display_roundtrip: unsafe extern "C" fn(display: *mut wl_display) -> c_int,
+    pub wl_display_flush: unsafe extern "C" fn(display: *mut wl_display) -> c_int,
+    pub wl_display_prepare_read: unsafe extern "C" fn(display: *mut wl_display) -> c_int,
+    pub wl_display_read_events: unsafe extern "C" fn(display: *mut wl_display) -> c_int,
+    pub wl_display_cancel_read: unsafe extern "C" fn(display: *mut wl_display),
+    pub wl_proxy_marshal_array_flags: unsafe extern "C" fn(proxy: *mut wl_proxy, opcode: u32, interface: *const wl_interface, version: u32, flags: u32, args: *mut wl_argument) -> *mut wl_proxy,
+    pub wl_proxy_add_listener: unsafe extern "C" fn(proxy: *mut wl_proxy, implementation: *const c_void, data: *mut c_void) -> c_int,
+    pub wl_proxy_get_version: unsafe extern "C" fn(proxy: *mut wl_proxy) -> u32,
+    pub wl_proxy_destroy: unsafe extern "C" fn(proxy: *mut wl_proxy),
+
+    pub wl_registry_interface: *const wl_interface,
+    pub wl_compositor_interface: *const wl_interface,
+    pub wl_surface_interface: *const wl_interface,
+    pub wl_seat_interface: *const wl_interface,
+    pub wl_pointer_interface: *const wl_interface,
+    pub wl_keyboard_interface: *const wl_interface,
+    pub wl_touch_interface: *const wl_interface,
+    pub wl_output_interface: *const wl_interface,
+    pub wl_shm_interface: *const wl_interface,
+    pub wl_data_device_manager_interface: *const wl_interface,
+    pub wl_data_device_interface: *const wl_interface,
+    pub wl_data_source_interface: *const wl_interface,
+
+    pub wl_egl_window_create: unsafe extern "C" fn(surface: *mut wl_surface, width: c_int, height: c_int) -> *mut wl_egl_window,
+    pub wl_egl_window_destroy: unsafe extern "C" fn(egl_window: *mut wl_egl_window),
+    pub wl_egl_window_resize: unsafe extern "C" fn(egl_window: *mut wl_egl_window, width: c_int, height: c_int, dx: c_int, dy: c_int),
+
+    pub cursor: Option<LibWaylandCursor>,
+
+    _keep_client_alive: ModuleLoader,
+    _keep_egl_alive: ModuleLoader,
+}
+
+pub struct LibWaylandCursor {
+    pub wl_cursor_theme_load: unsafe extern "C" fn(name: *const c_char, size: c_int, shm: *mut wl_shm) -> *mut wl_cursor_theme,
+    pub wl_cursor_theme_destroy: unsafe extern "C" fn(theme: *mut wl_cursor_theme),
+    pub wl_cursor_theme_get_cursor: unsafe extern "C" fn(theme: *mut wl_cursor_theme, name: *const c_char) -> *mut wl_cursor,
+    pub wl_cursor_image_get_buffer: unsafe extern "C" fn(image: *mut wl_cursor_image) -> *mut wl_buffer,
+    _keep_module_alive: ModuleLoader,
+}
+
+impl LibWayland {
+    pub fn try_load() -> Option<LibWayland> {
+        let client = ModuleLoader::load("libwayland-client.so.0").or_else( | _ | ModuleLoader::load("libwayland-client.so")).ok()?;
+        let egl = ModuleLoader::load("libwayland-egl.so.1").or_else( | _ | ModuleLoader::load("libwayland-egl.so")).ok()?;
+        Some(LibWayland {
+            wl_display_connect: client.get_symbol("wl_display_connect").ok()?,
+            wl_display_disconnect: client.get_symbol("wl_display_disconnect").ok()?,
+            wl_display_get_fd: client.get_symbol("wl_display_get_fd").ok()?,
+            wl_display_dispatch_pending: client.get_symbol("wl_display_dispatch_pending").ok()?,
+            wl_display_roundtrip: client.get_symbol("wl_display_roundtrip").ok()?,
+            wl_display_flush: client.get_symbol("wl_display_flush").ok()?,
+            wl_display_prepare_read: client.get_symbol("wl_display_prepare_read").ok()?,
+            wl_display_read_events: client.get_symbol("wl_display_read_events").ok()?,
+            wl_display_cancel_read: client.get_symbol("wl_display_cancel_read").ok()?,
+            // needs libwayland 1.20 or newer
+            wl_proxy_marshal_array_flags: client.get_symbol("wl_proxy_marshal_array_flags").ok()?,
+            wl_proxy_add_listener: client.get_symbol("wl_proxy_add_listener").ok()?,
+            wl_proxy_get_version: client.get_symbol("wl_proxy_get_version").ok()?,
+            wl_proxy_destroy: client.get_symbol("wl_proxy_destroy").ok()?,
+
+            // the core interface descriptions are exported as data by libwayland-client
+            wl_registry_interface: client.get_symbol("wl_registry_interface").ok()?,
+            wl_compositor_interface: client.get_symbol("wl_compositor_interface").ok()?,
+            wl_surface_interface: client.get_symbol("wl_surface_interface").ok()?,
+            wl_seat_interface: client.get_symbol("wl_seat_interface").ok()?,
+            wl_pointer_interface: client.get_symbol("wl_pointer_interface").ok()?,
+            wl_keyboard_interface: client.get_symbol("wl_keyboard_interface").ok()?,
+            wl_touch_interface: client.get_symbol("wl_touch_interface").ok()?,
+            wl_output_interface: client.get_symbol("wl_output_interface").ok()?,
+            wl_shm_interface: client.get_symbol("wl_shm_interface").ok()?,
+            wl_data_device_manager_interface: client.get_symbol("wl_data_device_manager_interface").ok()?,
+            wl_data_device_interface: client.get_symbol("wl_data_device_interface").ok()?,
+            wl_data_source_interface: client.get_symbol("wl_data_source_interface").ok()?,
+
+            wl_egl_window_create: egl.get_symbol("wl_egl_window_create").ok()?,
+            wl_egl_window_destroy: egl.get_symbol("wl_egl_window_destroy").ok()?,
+            wl_egl_window_resize: egl.get_symbol("wl_egl_window_resize").ok()?,
+
+            cursor: LibWaylandCursor::try_load(),
+
+            _keep_client_alive: client,
+            _keep_egl_alive: egl,
+        })
+    }
+
+    pub unsafe fn add_listener<T>(&self, proxy: *mut wl_proxy, listener: &'static T, data: *mut c_void) {
+        (self.wl_proxy_add_listener)(proxy, listener as *const T as *const c_void, data);
+    }
+
+    /// Sends a request that doesn't create an object.
+    pub unsafe fn request(&self, proxy: *mut wl_proxy, opcode: u32, args: &mut [wl_argument]) {
+        let version = (self.wl_proxy_get_version)(proxy);
+        (self.wl_proxy_marshal_array_flags)(proxy, opcode, ptr::null(), version, 0, args.as_mut_ptr());
+    }
+
+    /// Sends a request with a `new_id` argument, the new object has the version of `proxy`.
+    pub unsafe fn request_new(&self, proxy: *mut wl_proxy, opcode: u32, interface: *const wl_interface, args: &mut [wl_argument]) -> *mut wl_proxy {
+        let version = (self.wl_proxy_get_version)(proxy);
+        (self.wl_proxy_marshal_array_flags)(proxy, opcode, interface, version, 0, args.as_mut_ptr())
+    }
+
+    /// Sends a destructor request and destroys the proxy.
+    pub unsafe fn request_destroy(&self, proxy: *mut wl_proxy, opcode: u32) {
+        let version = (self.wl_proxy_get_version)(proxy);
+        (self.wl_proxy_marshal_array_flags)(proxy, opcode, ptr::null(), version, WL_MARSHAL_FLAG_DESTROY, ptr::null_mut());
+    }
+
+    pub unsafe fn get_registry(&self, display: *mut wl_display) -> *mut wl_registry {
+        self.request_new(display as *mut wl_proxy, WL_DISPLAY_GET_REGISTRY, self.wl_registry_interface, &mut [arg_n()])
+    }
+
+    pub unsafe fn bind(&self, registry: *mut wl_registry, name: u32, interface: *const wl_interface, version: u32) -> *mut wl_proxy {
+        let mut args = [arg_u(name), wl_argument {s: (*interface).name}, arg_u(version), arg_n()];
+        (self.wl_proxy_marshal_array_flags)(registry, WL_REGISTRY_BIND, interface, version, 0, args.as_mut_ptr())
+    }
+}
+
+impl LibWaylandCursor {
+    fn try_load() -> Option<LibWaylandCursor> {
+        let module = ModuleLoader::load("libwayland-cursor.so.0").or_else( | _ | ModuleLoader::load("libwayland-cursor.so")).ok()?;
+        Some(LibWaylandCursor {
+            wl_cursor_theme_load: module.get_symbol("wl_cursor_theme_load").ok()?,
+            wl_cursor_theme_destroy: module.get_symbol("wl_cursor_theme_destroy").ok()?,
+            wl_cursor_theme_get_cursor: module.get_symbol("wl_cursor_theme_get_cursor").ok()?,
+            wl_cursor_image_get_buffer: module.get_symbol("wl_cursor_image_get_buffer").ok()?,
+            _keep_module_alive: module,
+        })
+    }
+}
@@ -0,0 +1,462 @@
+use {
+    std::{
+        cell::Cell,
+        ffi::CString,
+        os::raw::c_void,
+        ptr,
+        rc::Rc,
+    },
+    self::super::{
+        wayland_sys::*,
+        wayland_event::WaylandEvent,
+        wayland_app::get_wayland_app_global,
+    },
+    crate::{
+        area::Area,
+        event::*,
+        makepad_math::DVec2,
+        window::WindowId,
+    },
+};
+
+pub struct WaylandWindow {
+    pub window_id: WindowId,
+    pub surface: *mut wl_surface,
+    pub xdg_surface: *mut xdg_surface,
+    pub xdg_toplevel: *mut xdg_toplevel,
+    pub fractional_scale: *mut wp_fractional_scale_v1,
+    pub viewport: *mut wp_viewport,
+    pub decoration: *mut zxdg_toplevel_decoration_v1,
+    pub egl_window: *mut wl_egl_window,
+    pub last_window_geom: WindowGeom,
+    pub last_mouse_pos: DVec2,
+    /// The resize edge under the mouse, or `Some(0)` for the caption, when the window draws its own decorations
+    pub last_nc_mode: Option<u32>,
+    pub server_side_decorations: bool,
+    pub is_configured: bool,
+    inner_size: DVec2,
+    pending_size: Option<DVec2>,
+    pending_states: Vec<u32>,
+    is_maximized: bool,
+    is_fullscreen: bool,
+    /// The scale in 120ths from wp_fractional_scale_v1, 0 if the compositor doesn't send one
+    preferred_scale: u32,
+    outputs: Vec<*mut wl_output>,
+}
+
+pub const NC_MODE_MOVE: u32 = 0;
+
+impl WaylandWindow {
+    pub fn new(window_id: WindowId) -> WaylandWindow {
+        WaylandWindow {
+            window_id,
+            surface: ptr::null_mut(),
+            xdg_surface: ptr::null_mut(),
+            xdg_toplevel: ptr::null_mut(),
+            fractional_scale: ptr::null_mut(),
+            viewport: ptr::null_mut(),
+            decoration: ptr::null_mut(),
+            egl_window: ptr::null_mut(),
+            last_window_geom: WindowGeom::default(),
+            last_mouse_pos: DVec2::default(),
+            last_nc_mode: None,
+            server_side_decorations: false,
+            is_configured: false,
+            inner_size: DVec2::default(),
+            pending_size: None,
+            pending_states: Vec::new(),
+            is_maximized: false,
+            is_fullscreen: false,
+            preferred_scale: 0,
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn init(&mut self, title: &str, inner_size: DVec2) {
+        let app = get_wayland_app_global();
+        let lib = &app.lib;
+        let data = self as *mut WaylandWindow as *mut c_void;
+        unsafe {
+            self.surface = lib.request_new(app.compositor, WL_COMPOSITOR_CREATE_SURFACE, lib.wl_surface_interface, &mut [arg_n()]);
+            lib.add_listener(self.surface, &SURFACE_LISTENER, data);
+            app.window_map.insert(self.surface as usize, self);
+
+            if !app.fractional_scale_manager.is_null() && !app.viewporter.is_null() {
+                self.fractional_scale = lib.request_new(
+                    app.fractional_scale_manager,
+                    WP_FRACTIONAL_SCALE_MANAGER_V1_GET_FRACTIONAL_SCALE,
+                    &wp_fractional_scale_v1_interface,
+                    &mut [arg_n(), arg_o(self.surface)]
+                );
+                lib.add_listener(self.fractional_scale, &FRACTIONAL_SCALE_LISTENER, data);
+                self.viewport = lib.request_new(
+                    app.viewporter,
+                    WP_VIEWPORTER_GET_VIEWPORT,
+                    &wp_viewport_interface,
+                    &mut [arg_n(), arg_o(self.surface)]
+                );
+            }
+
+            self.xdg_surface = lib.request_new(app.xdg_wm_base, XDG_WM_BASE_GET_XDG_SURFACE, &xdg_surface_interface, &mut [arg_n(), arg_o(self.surface)]);
+            lib.add_listener(self.xdg_surface, &XDG_SURFACE_LISTENER, data);
+            self.xdg_toplevel = lib.request_new(self.xdg_surface, XDG_SURFACE_GET_TOPLEVEL, &xdg_toplevel_interface, &mut [arg_n()]);
+            lib.add_listener(self.xdg_toplevel, &XDG_TOPLEVEL_LISTENER, data);
+
+            let title = CString::new(title).unwrap_or_default();
+            lib.request(self.xdg_toplevel, XDG_TOPLEVEL_SET_TITLE, &mut [arg_s(&title)]);
+            lib.request(self.xdg_toplevel, XDG_TOPLEVEL_SET_APP_ID, &mut [arg_s(&app.app_id)]);
+
+            if !app.decoration_manager.is_null() {
+                self.decoration = lib.request_new(
+                    app.decoration_manager,
+                    ZXDG_DECORATION_MANAGER_V1_GET_TOPLEVEL_DECORATION,
+                    &zxdg_toplevel_decoration_v1_interface,
+                    &mut [arg_n(), arg_o(self.xdg_toplevel)]
+                );
+                lib.add_listener(self.decoration, &DECORATION_LISTENER, data);
+                lib.request(self.decoration, ZXDG_TOPLEVEL_DECORATION_V1_SET_MODE, &mut [arg_u(ZXDG_TOPLEVEL_DECORATION_V1_MODE_SERVER_SIDE)]);
+                self.server_side_decorations = true;
+            }
+
+            self.inner_size = inner_size;
+            let physical_size = self.physical_size();
+            self.egl_window = (lib.wl_egl_window_create)(self.surface, physical_size.0, physical_size.1);
+
+            // a surface can't have content before its first configure has been acknowledged
+            lib.request(self.surface, WL_SURFACE_COMMIT, &mut []);
+            while !self.is_configured {
+                if (lib.wl_display_roundtrip)(app.display) < 0 {
+                    panic!("Wayland connection lost while creating a window");
+                }
+            }
+        }
+        self.last_window_geom = self.get_window_geom();
+    }
+
+    pub fn close_window(&mut self) {
+        let app = get_wayland_app_global();
+        let lib = &app.lib;
+        app.window_map.remove(&(self.surface as usize));
+        unsafe {
+            if !self.egl_window.is_null() {
+                (lib.wl_egl_window_destroy)(self.egl_window);
+                self.egl_window = ptr::null_mut();
+            }
+            if !self.decoration.is_null() {
+                lib.request_destroy(self.decoration, ZXDG_TOPLEVEL_DECORATION_V1_DESTROY);
+                self.decoration = ptr::null_mut();
+            }
+            if !self.viewport.is_null() {
+                lib.request_destroy(self.viewport, WP_VIEWPORT_DESTROY);
+                self.viewport = ptr::null_mut();
+            }
+            if !self.fractional_scale.is_null() {
+                lib.request_destroy(self.fractional_scale, WP_FRACTIONAL_SCALE_V1_DESTROY);
+                self.fractional_scale = ptr::null_mut();
+            }
+            if !self.xdg_toplevel.is_null() {
+                lib.request_destroy(self.xdg_toplevel, XDG_TOPLEVEL_DESTROY);
+                self.xdg_toplevel = ptr::null_mut();
+            }
+            if !self.xdg_surface.is_null() {
+                lib.request_destroy(self.xdg_surface, XDG_SURFACE_DESTROY);
+                self.xdg_surface = ptr::null_mut();
+            }
+            if !self.surface.is_null() {
+                lib.request_destroy(self.surface, WL_SURFACE_DESTROY);
+                self.surface = ptr::null_mut();
+            }
+            (lib.wl_display_flush)(app.display);
+        }
+    }
+
+    pub fn minimize(&self) {
+        unsafe {get_wayland_app_global().lib.request(self.xdg_toplevel, XDG_TOPLEVEL_SET_MINIMIZED, &mut [])};
+    }
+
+    pub fn maximize(&self) {
+        unsafe {get_wayland_app_global().lib.request(self.xdg_toplevel, XDG_TOPLEVEL_SET_MAXIMIZED, &mut [])};
+    }
+
+    pub fn restore(&self) {
+        unsafe {get_wayland_app_global().lib.request(self.xdg_toplevel, XDG_TOPLEVEL_UNSET_MAXIMIZED, &mut [])};
+    }
+
+    pub fn fullscreen(&self) {
+        unsafe {get_wayland_app_global().lib.request(self.xdg_toplevel, XDG_TOPLEVEL_SET_FULLSCREEN, &mut [arg_null()])};
+    }
+
+    pub fn normalize(&self) {
+        unsafe {get_wayland_app_global().lib.request(self.xdg_toplevel, XDG_TOPLEVEL_UNSET_FULLSCREEN, &mut [])};
+    }
+
+    pub fn get_is_maximized(&self) -> bool {
+        self.is_maximized
+    }
+
+    /// Hands an interactive move (`NC_MODE_MOVE`) or resize of the window to the compositor.
+    pub fn start_move_or_resize(&self, nc_mode: u32, serial: u32) {
+        let app = get_wayland_app_global();
+        unsafe {
+            if nc_mode == NC_MODE_MOVE {
+                app.lib.request(self.xdg_toplevel, XDG_TOPLEVEL_MOVE, &mut [arg_o(app.seat), arg_u(serial)]);
+            }
+            else {
+                app.lib.request(self.xdg_toplevel, XDG_TOPLEVEL_RESIZE, &mut [arg_o(app.seat), arg_u(serial), arg_u(nc_mode)]);
+            }
+        }
+    }
+
+    pub fn get_dpi_factor(&self) -> f64 {
+        if self.preferred_scale != 0 {
+            return self.preferred_scale as f64 / 120.0
+        }
+        // without fractional scaling use the largest integer scale of the outputs we are on
+        let app = get_wayland_app_global();
+        self.outputs.iter().map( | output | app.output_scale(*output)).max().unwrap_or(1) as f64
+    }
+
+    fn physical_size(&self) -> (i32, i32) {
+        let dpi_factor = self.get_dpi_factor();
+        (
+            ((self.inner_size.x * dpi_factor).round() as i32).max(1),
+            ((self.inner_size.y * dpi_factor).round() as i32).max(1)
+        )
+    }
+
+    pub fn get_window_geom(&self) -> WindowGeom {
+        WindowGeom {
+            xr_is_presenting: false,
+            can_fullscreen: true,
+            is_topmost: false,
+            is_fullscreen: self.is_fullscreen,
+            inner_size: self.inner_size,
+            outer_size: self.inner_size,
+            dpi_factor: self.get_dpi_factor(),
+            // Wayland doesn't tell clients where their windows are
+            position: DVec2::default(),
+        }
+    }
+
+    /// Sizes the EGL buffer for the current size and scale. The new size and scale are
+    /// committed together with the next frame.
+    fn update_buffer(&mut self) {
+        let app = get_wayland_app_global();
+        let physical_size = self.physical_size();
+        unsafe {
+            (app.lib.wl_egl_window_resize)(self.egl_window, physical_size.0, physical_size.1, 0, 0);
+            if !self.viewport.is_null() && self.preferred_scale != 0 {
+                app.lib.request(self.viewport, WP_VIEWPORT_SET_DESTINATION, &mut [
+                    arg_i(self.inner_size.x as i32),
+                    arg_i(self.inner_size.y as i32)
+                ]);
+            }
+            else if (app.lib.wl_proxy_get_version)(self.surface) >= 3 {
+                app.lib.request(self.surface, WL_SURFACE_SET_BUFFER_SCALE, &mut [arg_i(self.get_dpi_factor() as i32)]);
+            }
+        }
+    }
+
+    fn scale_changed(&mut self) {
+        if !self.is_configured {
+            return
+        }
+        self.update_buffer();
+        self.send_change_event();
+    }
+
+    pub fn time_now(&self) -> f64 {
+        get_wayland_app_global().time_now()
+    }
+
+    pub fn do_callback(&mut self, event: WaylandEvent) {
+        get_wayland_app_global().do_callback(event);
+    }
+
+    pub fn send_change_event(&mut self) {
+        let new_geom = self.get_window_geom();
+        let old_geom = self.last_window_geom.clone();
+        self.last_window_geom = new_geom.clone();
+
+        self.do_callback(WaylandEvent::WindowGeomChange(WindowGeomChangeEvent {
+            window_id: self.window_id,
+            old_geom: old_geom,
+            new_geom: new_geom
+        }));
+        self.do_callback(WaylandEvent::Paint);
+    }
+
+    pub fn send_focus_event(&mut self) {
+        self.do_callback(WaylandEvent::AppGotFocus);
+    }
+
+    pub fn send_focus_lost_event(&mut self) {
+        self.do_callback(WaylandEvent::AppLostFocus);
+    }
+
+    pub fn send_mouse_down(&mut self, button: usize, modifiers: KeyModifiers) {
+        self.do_callback(WaylandEvent::MouseDown(MouseDownEvent {
+            button,
+            modifiers,
+            window_id: self.window_id,
+            abs: self.last_mouse_pos,
+            time: self.time_now(),
+            handled: Cell::new(Area::Empty),
+        }));
+    }
+
+    pub fn send_mouse_up(&mut self, button: usize, modifiers: KeyModifiers) {
+        self.do_callback(WaylandEvent::MouseUp(MouseUpEvent {
+            button,
+            modifiers,
+            window_id: self.window_id,
+            abs: self.last_mouse_pos,
+            time: self.time_now()
+        }));
+    }
+
+    pub fn send_mouse_move(&mut self, pos: DVec2, modifiers: KeyModifiers) {
+        self.last_mouse_pos = pos;
+        self.do_callback(WaylandEvent::MouseMove(MouseMoveEvent {
+            window_id: self.window_id,
+            abs: pos,
+            modifiers: modifiers,
+            time: self.time_now(),
+            handled: Cell::new(Area::Empty),
+        }));
+    }
+
+    pub fn send_window_drag_query(&mut self) -> WindowDragQueryResponse {
+        let response = Rc::new(Cell::new(WindowDragQueryResponse::NoAnswer));
+        self.do_callback(WaylandEvent::WindowDragQuery(WindowDragQueryEvent {
+            window_id: self.window_id,
+            abs: self.last_mouse_pos,
+            response: response.clone()
+        }));
+        response.get()
+    }
+
+    pub fn send_close_requested_event(&mut self) -> bool {
+        let accept_close = Rc::new(Cell::new(true));
+        self.do_callback(WaylandEvent::WindowCloseRequested(WindowCloseRequestedEvent {
+            window_id: self.window_id,
+            accept_close: accept_close.clone()
+        }));
+        if !accept_close.get() {
+            return false
+        }
+        true
+    }
+
+    pub fn send_text_input(&mut self, input: String, replace_last: bool) {
+        self.do_callback(WaylandEvent::TextInput(TextInputEvent {
+            input: input,
+            was_paste: false,
+            replace_last: replace_last
+        }))
+    }
+}
+
+static SURFACE_LISTENER: wl_surface_listener = wl_surface_listener {
+    enter: surface_enter,
+    leave: surface_leave,
+};
+
+static XDG_SURFACE_LISTENER: xdg_surface_listener = xdg_surface_listener {
+    configure: xdg_surface_configure,
+};
+
+static XDG_TOPLEVEL_LISTENER: xdg_toplevel_listener = xdg_toplevel_listener {
+    configure: xdg_toplevel_configure,
+    close: xdg_toplevel_close,
+    configure_bounds: xdg_toplevel_configure_bounds,
+    wm_capabilities: xdg_toplevel_wm_capabilities,
+};
+
+static FRACTIONAL_SCALE_LISTENER: wp_fractional_scale_v1_listener = wp_fractional_scale_v1_listener {
+    preferred_scale: fractional_scale_preferred_scale,
+};
+
+static DECORATION_LISTENER: zxdg_toplevel_decoration_v1_listener = zxdg_toplevel_decoration_v1_listener {
+    configure: decoration_configure,
+};
+
+unsafe extern "C" fn surface_enter(data: *mut c_void, _surface: *mut wl_surface, output: *mut wl_output) {
+    let window = &mut *(data as *mut WaylandWindow);
+    let old_dpi_factor = window.get_dpi_factor();
+    window.outputs.push(output);
+    if window.get_dpi_factor() != old_dpi_factor {
+        window.scale_changed();
+    }
+}
+
+unsafe extern "C" fn surface_leave(data: *mut c_void, _surface: *mut wl_surface, output: *mut wl_output) {
+    let window = &mut *(data as *mut WaylandWindow);
+    let old_dpi_factor = window.get_dpi_factor();
+    window.outputs.retain( | o | *o != output);
+    if window.get_dpi_factor() != old_dpi_factor {
+        window.scale_changed();
+    }
+}
+
+unsafe extern "C" fn xdg_surface_configure(data: *mut c_void, xdg_surface: *mut xdg_surface, serial: u32) {
+    let window = &mut *(data as *mut WaylandWindow);
+    get_wayland_app_global().lib.request(xdg_surface, XDG_SURFACE_ACK_CONFIGURE, &mut [arg_u(serial)]);
+
+    let old_geom = window.get_window_geom();
+    if let Some(size) = window.pending_size.take() {
+        window.inner_size = size;
+    }
+    window.is_maximized = window.pending_states.contains(&XDG_TOPLEVEL_STATE_MAXIMIZED);
+    window.is_fullscreen = window.pending_states.contains(&XDG_TOPLEVEL_STATE_FULLSCREEN);
+    let was_configured = window.is_configured;
+    window.is_configured = true;
+
+    let new_geom = window.get_window_geom();
+    if !was_configured || new_geom != old_geom {
+        window.update_buffer();
+    }
+    if was_configured && new_geom != old_geom {
+        window.send_change_event();
+    }
+}
+
+unsafe extern "C" fn xdg_toplevel_configure(data: *mut c_void, _toplevel: *mut xdg_toplevel, width: i32, height: i32, states: *mut wl_array) {
+    let window = &mut *(data as *mut WaylandWindow);
+    // a zero size leaves the size up to us
+    if width > 0 && height > 0 {
+        window.pending_size = Some(DVec2 {x: width as f64, y: height as f64});
+    }
+    window.pending_states = (*states).as_u32_slice().to_vec();
+    if window.pending_states.contains(&XDG_TOPLEVEL_STATE_ACTIVATED) {
+        window.send_focus_event();
+    }
+}
+
+unsafe extern "C" fn xdg_toplevel_close(data: *mut c_void, _toplevel: *mut xdg_toplevel) {
+    let window = &mut *(data as *mut WaylandWindow);
+    if window.send_close_requested_event() {
+        // the event handler removes the window, so this is the last use of it
+        let window_id = window.window_id;
+        window.do_callback(WaylandEvent::WindowClosed(WindowClosedEvent {window_id}));
+    }
+}
+
+unsafe extern "C" fn xdg_toplevel_configure_bounds(_data: *mut c_void, _toplevel: *mut xdg_toplevel, _width: i32, _height: i32) {
+}
+
+unsafe extern "C" fn xdg_toplevel_wm_capabilities(_data: *mut c_void, _toplevel: *mut xdg_toplevel, _capabilities: *mut wl_array) {
+}
+
+unsafe extern "C" fn fractional_scale_preferred_scale(data: *mut c_void, _fractional_scale: *mut wp_fractional_scale_v1, scale: u32) {
+    let window = &mut *(data as *mut WaylandWindow);
+    if window.preferred_scale != scale {
+        window.preferred_scale = scale;
+        window.scale_changed();
+    }
+}
+
+unsafe extern "C" fn decoration_configure(data: *mut c_void, _decoration: *mut zxdg_toplevel_decoration_v1, mode: u32) {
+    let window = &mut *(data as *mut WaylandWindow);
+    window.server_side_decorations = mode == ZXDG_TOPLEVEL_DECORATION_V1_MODE_SERVER_SIDE;
+}
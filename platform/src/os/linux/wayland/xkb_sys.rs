@@ -0,0 +1,128 @@
+#![allow(non_camel_case_types)]
+
+// libxkbcommon, loaded at runtime like libwayland. Keysyms are the X11 ones so
+// the keycode translation is shared with the X11 backend.
+
+use {
+    std::os::raw::{c_char, c_int},
+    self::super::super::module_loader::ModuleLoader,
+};
+
+#[repr(C)]
+pub struct xkb_context {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_keymap {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_state {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_compose_table {
+    _unused: [u8; 0],
+}
+
+#[repr(C)]
+pub struct xkb_compose_state {
+    _unused: [u8; 0],
+}
+
+pub type xkb_keycode_t = u32;
+pub type xkb_keysym_t = u32;
+
+pub const XKB_CONTEXT_NO_FLAGS: c_int = 0;
+pub const XKB_KEYMAP_FORMAT_TEXT_V1: c_int = 1;
+pub const XKB_KEYMAP_COMPILE_NO_FLAGS: c_int = 0;
+pub const XKB_STATE_MODS_EFFECTIVE: c_int = 8;
+pub const XKB_COMPOSE_COMPILE_NO_FLAGS: c_int = 0;
+pub const XKB_COMPOSE_STATE_NO_FLAGS: c_int = 0;
+
+pub const XKB_COMPOSE_FEED_IGNORED: c_int = 0;
+
+pub const XKB_COMPOSE_NOTHING: c_int = 0;
+pub const XKB_COMPOSE_COMPOSING: c_int = 1;
+pub const XKB_COMPOSE_COMPOSED: c_int = 2;
+pub const XKB_COMPOSE_CANCELLED: c_int = 3;
+
+pub const XKB_MOD_NAME_SHIFT: &[u8] = b"Shift\0";
+pub const XKB_MOD_NAME_CTRL: &[u8] = b"Control\0";
+pub const XKB_MOD_NAME_ALT: &[u8] = b"Mod1\0";
+pub const XKB_MOD_NAME_LOGO: &[u8] = b"Mod4\0";
+
+pub struct LibXkb {
+    pub xkb_context_new: unsafe extern "C" fn(flags: c_int) -> *mut xkb_context,
+    pub xkb_context_unref: unsafe extern "C" fn(context: *mut xkb_context),
+    pub xkb_keymap_new_from_string: unsafe extern "C" fn(context: *mut xkb_context, string: *const c_char, format: c_int, flags: c_int) -> *mut xkb_keymap,
+    pub xkb_keymap_unref: unsafe extern "C" fn(keymap: *mut xkb_keymap),
+    pub xkb_keymap_key_repeats: unsafe extern "C" fn(keymap: *mut xkb_keymap, key: xkb_keycode_t) -> c_int,
+    pub xkb_state_new: unsafe extern "C" fn(keymap: *mut xkb_keymap) -> *mut xkb_state,
+    pub xkb_state_unref: unsafe extern "C" fn(state: *mut xkb_state),
+    pub xkb_state_update_mask: unsafe extern "C" fn(state: *mut xkb_state, depressed_mods: u32, latched_mods: u32, locked_mods: u32, depressed_layout: u32, latched_layout: u32, locked_layout: u32) -> c_int,
+    pub xkb_state_key_get_one_sym: unsafe extern "C" fn(state: *mut xkb_state, key: xkb_keycode_t) -> xkb_keysym_t,
+    pub xkb_state_key_get_utf8: unsafe extern "C" fn(state: *mut xkb_state, key: xkb_keycode_t, buffer: *mut c_char, size: usize) -> c_int,
+    pub xkb_state_mod_name_is_active: unsafe extern "C" fn(state: *mut xkb_state, name: *const c_char, type_: c_int) -> c_int,
+
+    pub xkb_compose_table_new_from_locale: unsafe extern "C" fn(context: *mut xkb_context, locale: *const c_char, flags: c_int) -> *mut xkb_compose_table,
+    pub xkb_compose_table_unref: unsafe extern "C" fn(table: *mut xkb_compose_table),
+    pub xkb_compose_state_new: unsafe extern "C" fn(table: *mut xkb_compose_table, flags: c_int) -> *mut xkb_compose_state,
+    pub xkb_compose_state_unref: unsafe extern "C" fn(state: *mut xkb_compose_state),
+    pub xkb_compose_state_reset: unsafe extern "C" fn(state: *mut xkb_compose_state),
+    pub xkb_compose_state_feed: unsafe extern "C" fn(state: *mut xkb_compose_state, keysym: xkb_keysym_t) -> c_int,
+    pub xkb_compose_state_get_status: unsafe extern "C" fn(state: *mut xkb_compose_state) -> c_int,
+    pub xkb_compose_state_get_utf8: unsafe extern "C" fn(state: *mut xkb_compose_state, buffer: *mut c_char, size: usize) -> c_int,
+
+    _keep_module_alive: ModuleLoader,
+}
+
+impl LibXkb {
+    pub fn try_load() -> Option<LibXkb> {
+        let module = ModuleLoader::load("libxkbcommon.so.0").or_else( | _ | ModuleLoader::load("libxkbcommon.so")).ok()?;
+        Some(LibXkb {
+            xkb_context_new: module.get_symbol("xkb_context_new").ok()?,
+            xkb_context_unref: module.get_symbol("xkb_context_unref").ok()?,
+            xkb_keymap_new_from_string: module.get_symbol("xkb_keymap_new_from_string").ok()?,
+            xkb_keymap_unref: module.get_symbol("xkb_keymap_unref").ok()?,
+            xkb_keymap_key_repeats: module.get_symbol("xkb_keymap_key_repeats").ok()?,
+            xkb_state_new: module.get_symbol("xkb_state_new").ok()?,
+            xkb_state_unref: module.get_symbol("xkb_state_unref").ok()?,
+            xkb_state_update_mask: module.get_symbol("xkb_state_update_mask").ok()?,
+            xkb_state_key_get_one_sym: module.get_symbol("xkb_state_key_get_one_sym").ok()?,
+            xkb_state_key_get_utf8: module.get_symbol("xkb_state_key_get_utf8").ok()?,
+            xkb_state_mod_name_is_active: module.get_symbol("xkb_state_mod_name_is_active").ok()?,
+
+            xkb_compose_table_new_from_locale: module.get_symbol("xkb_compose_table_new_from_locale").ok()?,
+            xkb_compose_table_unref: module.get_symbol("xkb_compose_table_unref").ok()?,
+            xkb_compose_state_new: module.get_symbol("xkb_compose_state_new").ok()?,
+            xkb_compose_state_unref: module.get_symbol("xkb_compose_state_unref").ok()?,
+            xkb_compose_state_reset: module.get_symbol("xkb_compose_state_reset").ok()?,
+            xkb_compose_state_feed: module.get_symbol("xkb_compose_state_feed").ok()?,
+            xkb_compose_state_get_status: module.get_symbol("xkb_compose_state_get_status").ok()?,
+            xkb_compose_state_get_utf8: module.get_symbol("xkb_compose_state_get_utf8").ok()?,
+
+            _keep_module_alive: module,
+        })
+    }
+}
+
+/// Reads a utf8 string from one of the `(buffer, size)` getters, which return the
+/// length without the nul terminator.
+pub unsafe fn get_utf8(getter: impl Fn(*mut c_char, usize) -> c_int) -> String {
+    let mut buffer = [0u8; 64];
+    let len = getter(buffer.as_mut_ptr() as *mut c_char, buffer.len());
+    if len <= 0 {
+        return String::new()
+    }
+    if len as usize >= buffer.len() {
+        let mut buffer = vec![0u8; len as usize + 1];
+        getter(buffer.as_mut_ptr() as *mut c_char, buffer.len());
+        buffer.truncate(len as usize);
+        return String::from_utf8_lossy(&buffer).into_owned()
+    }
+    String::from_utf8_lossy(&buffer[..len as usize]).into_owned()
+}
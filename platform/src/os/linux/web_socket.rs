@@ -1,21 +1,36 @@
-
 use crate::event::HttpRequest;
-use crate::web_socket::{WebSocketMessage};
-use std::sync::mpsc::{channel, Sender};
-use std::net::TcpStream;
+use crate::web_socket::{WebSocketMessage, WebSocketCloseReason};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::net::{Shutdown, TcpStream};
 use std::io::{Read};
-use makepad_http::utils::write_bytes_to_tcp_stream_no_error;
-use makepad_http::websocket::{ServerWebSocket, ServerWebSocketMessageFormat, ServerWebSocketMessageHeader, ServerWebSocketMessage, SERVER_WEB_SOCKET_PONG_MESSAGE};
+use std::time::Duration;
+use makepad_http::utils::{read_head_from_tcp_stream, write_bytes_to_tcp_stream_no_error};
+use makepad_http::websocket::{ClientWebSocket, ClientWebSocketMessage, WEB_SOCKET_CLOSE_NORMAL, WEB_SOCKET_CLOSE_ABNORMAL};
+
+enum WriterMsg{
+    Message(WebSocketMessage),
+    Pong(Vec<u8>),
+    Close(u16, String)
+}
 
 pub struct OsWebSocket{
-    sender: Option<Sender<WebSocketMessage>>
+    sender: Option<Sender<WriterMsg>>
+}
+
+impl Drop for OsWebSocket{
+    fn drop(&mut self){
+        // start the close handshake, the reader thread ends when the server answers
+        if let Some(sender) = &self.sender{
+            let _ = sender.send(WriterMsg::Close(WEB_SOCKET_CLOSE_NORMAL, String::new()));
+        }
+    }
 }
 
 impl OsWebSocket{
     pub fn send_message(&mut self, message:WebSocketMessage)->Result<(),()>{
         // lets encode the message into a membuffer and send it to the write thread
         if let Some(sender) = &mut self.sender{
-            if sender.send(message).is_err(){
+            if sender.send(WriterMsg::Message(message)).is_err(){
                 return Err(());
             }
             return Ok(())
@@ -24,106 +39,150 @@ impl OsWebSocket{
     }
                     
     pub fn open(_socket_id:u64, request: HttpRequest, rx_sender:Sender<WebSocketMessage>)->OsWebSocket{
-        // parse the url
+        let (sender, receiver) = channel();
+        let writer_sender = sender.clone();
+        // connecting and the handshake block, so they happen on the reader thread.
+        // messages sent in the meantime wait in the channel for the writer
+        let _reader_thread = std::thread::spawn(move || {
+            let Some((stream, web_socket)) = Self::connect(&request, &rx_sender) else{
+                return
+            };
+            let _ = rx_sender.send(WebSocketMessage::Opened);
+            let output_stream = stream.try_clone().unwrap();
+            let writer_socket = web_socket.clone();
+            let writer_thread = std::thread::spawn(move || {
+                Self::run_writer(output_stream, writer_socket, receiver)
+            });
+            let mut stream = stream;
+            if Self::run_reader(&mut stream, web_socket, writer_sender, rx_sender){
+                // the writer ends after sending its close frame, only then can the connection go
+                let _ = writer_thread.join();
+            }
+            let _ = stream.shutdown(Shutdown::Both);
+        });
+        OsWebSocket{sender:Some(sender)}
+    }
+    
+    fn connect(request: &HttpRequest, rx_sender: &Sender<WebSocketMessage>)->Option<(TcpStream, ClientWebSocket)>{
         let split = request.split_url();
-        // strip off any hashes
-        // alright we have proto, host, port and hash now
-        // lets open a tcpstream
-        let stream = TcpStream::connect(format!("{}:{}", split.host, split.port));
-        // alright lets construct a http request
-        // lets join the headers
-        
-        let mut http_request = format!("GET /{} HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: SxJdXBRtW7Q4awLDhflO0Q==\r\n", split.file, split.host);
-        http_request.push_str(&request.get_headers_string());
-        http_request.push_str("\r\n"); 
-        
-        // lets write the http request
-        if stream.is_err(){
-            rx_sender.send(WebSocketMessage::Error("Error connecting websocket tcpstream".into())).unwrap();
-            return OsWebSocket{sender:None}
+        if split.proto == "wss" || split.proto == "https"{
+            let _ = rx_sender.send(WebSocketMessage::Error("Secure websockets are not supported on this platform".into()));
+            return None
         }
-        let mut stream = stream.unwrap();
+        let Ok(mut stream) = TcpStream::connect(format!("{}:{}", split.host, split.port)) else{
+            let _ = rx_sender.send(WebSocketMessage::Error("Error connecting websocket tcpstream".into()));
+            return None
+        };
+        // subprotocols and permessage-deflate are asked for with the usual headers,
+        // the handshake checks the server answer against them
+        let mut protocols = Vec::new();
+        let mut deflate = false;
+        let mut extra_headers = String::new();
+        for (name, values) in &request.headers{
+            if name.eq_ignore_ascii_case("sec-websocket-protocol"){
+                protocols.extend(values.iter().flat_map(|v| v.split(',')).map(|v| v.trim()).filter(|v| !v.is_empty()));
+            }
+            else if name.eq_ignore_ascii_case("sec-websocket-extensions"){
+                deflate = values.iter().any(|v| v.contains("permessage-deflate"));
+            }
+            else{
+                extra_headers.push_str(&format!("{}: {}\r\n", name, values.join(",")));
+            }
+        }
+        let key = ClientWebSocket::create_key();
+        let http_request = ClientWebSocket::create_upgrade_request(
+            split.host,
+            &format!("/{}", split.file),
+            &key,
+            &protocols,
+            deflate,
+            &extra_headers
+        );
         if write_bytes_to_tcp_stream_no_error(&mut stream, http_request.as_bytes()){
-            rx_sender.send(WebSocketMessage::Error("Error writing request to websocket".into())).unwrap();
-            return OsWebSocket{sender:None}
+            let _ = rx_sender.send(WebSocketMessage::Error("Error writing request to websocket".into()));
+            return None
         }
-        
-        // lets start the thread
-        let mut input_stream = stream.try_clone().unwrap();
-        let mut output_stream = stream.try_clone().unwrap();
-        let (sender, receiver) = channel();
-        
-        let _writer_thread = std::thread::spawn(move || {
-            while let Ok(msg) = receiver.recv(){
-                match msg{
-                    WebSocketMessage::Binary(data)=>{
-                        let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Binary, false);
-                        if write_bytes_to_tcp_stream_no_error(&mut output_stream, header.as_slice()) ||
-                        write_bytes_to_tcp_stream_no_error(&mut output_stream, &data){
-                            break;
-                        }
-                    }
-                    WebSocketMessage::String(data)=>{
-                        let header = ServerWebSocketMessageHeader::from_len(data.len(), ServerWebSocketMessageFormat::Binary, false);
-                        if write_bytes_to_tcp_stream_no_error(&mut output_stream, header.as_slice()) ||
-                        write_bytes_to_tcp_stream_no_error(&mut output_stream, &data.as_bytes()){
-                            break;
-                        }
-                    }
-                    _=>{
-                        crate::error!("WebSocketMessage of this type sending not implemented");
-                    }
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(30)));
+        let head = read_head_from_tcp_stream(&mut stream);
+        let _ = stream.set_read_timeout(None);
+        let Some(head) = head else{
+            let _ = rx_sender.send(WebSocketMessage::Error("Error reading websocket upgrade response".into()));
+            return None
+        };
+        match ClientWebSocket::from_upgrade_response(&String::from_utf8_lossy(&head), &key, &protocols, deflate){
+            Ok(web_socket)=>Some((stream, web_socket)),
+            Err(e)=>{
+                let _ = rx_sender.send(WebSocketMessage::Error(e));
+                None
+            }
+        }
+    }
+    
+    fn run_writer(mut output_stream: TcpStream, web_socket: ClientWebSocket, receiver: Receiver<WriterMsg>){
+        while let Ok(msg) = receiver.recv(){
+            let frame = match msg{
+                WriterMsg::Message(WebSocketMessage::Binary(data))=>web_socket.build_binary(&data),
+                WriterMsg::Message(WebSocketMessage::String(data))=>web_socket.build_text(&data),
+                WriterMsg::Message(_)=>{
+                    crate::error!("WebSocketMessage of this type sending not implemented");
+                    continue
                 }
+                WriterMsg::Pong(data)=>web_socket.build_pong(&data),
+                WriterMsg::Close(code, reason)=>{
+                    // nothing may be sent after the close frame
+                    let _ = write_bytes_to_tcp_stream_no_error(&mut output_stream, &web_socket.build_close(code, &reason));
+                    break
+                }
+            };
+            if write_bytes_to_tcp_stream_no_error(&mut output_stream, &frame){
+                break;
             }
-        });
-        
-        let _reader_thread = std::thread::spawn(move || {
-            let mut web_socket = ServerWebSocket::new();
-            let mut done = false;
-            while !done {
-                let mut buffer = [0u8; 65535];
-                match input_stream.read(&mut buffer) {
-                    Ok(bytes_read) => {
-                        web_socket.parse(&buffer[0..bytes_read], | result | {
-                            match result {
-                                Ok(ServerWebSocketMessage::Ping(_)) => {
-                                    println!("ping!");
-                                    if write_bytes_to_tcp_stream_no_error(&mut input_stream, &SERVER_WEB_SOCKET_PONG_MESSAGE){
-                                        done = true;
-                                        let _ = rx_sender.send(WebSocketMessage::Error("Pong message send failed".into()));
-                                    }
-                                },
-                                Ok(ServerWebSocketMessage::Pong(_)) => {
-                                },
-                                Ok(ServerWebSocketMessage::Text(text)) => {
-                                    if rx_sender.send(WebSocketMessage::String(text.into())).is_err(){
-                                        done = true;
-                                    };
-                                    println!("text => {}", text);
-                                },
-                                Ok(ServerWebSocketMessage::Binary(data)) => {
-                                    if rx_sender.send(WebSocketMessage::Binary(data.into())).is_err(){
-                                        done = true;
-                                    };
-                                    println!("binary!");
-                                },
-                                Ok(ServerWebSocketMessage::Close) => {
-                                    let _ = rx_sender.send(WebSocketMessage::Closed);
-                                    done = true;
-                                },
-                                Err(e) => {
-                                    eprintln!("Websocket error {:?}", e);
-                                }
-                            }
-                        });                            
-                    }
+        }
+    }
+    
+    /// Returns whether the connection ended with a close frame
+    fn run_reader(input_stream: &mut TcpStream, mut web_socket: ClientWebSocket, writer: Sender<WriterMsg>, rx_sender: Sender<WebSocketMessage>)->bool{
+        let mut buffer = vec![0u8; 65536];
+        let mut closed = false;
+        while !closed {
+            let bytes_read = match input_stream.read(&mut buffer){
+                Ok(0)=>{
+                    let _ = rx_sender.send(WebSocketMessage::Closed(WebSocketCloseReason::new(WEB_SOCKET_CLOSE_ABNORMAL, "Connection dropped")));
+                    break
+                }
+                Ok(bytes_read)=>bytes_read,
+                Err(e)=>{
+                    let _ = rx_sender.send(WebSocketMessage::Error(format!("Failed to receive data: {}", e)));
+                    break
+                }
+            };
+            web_socket.parse(&buffer[0..bytes_read], | result | {
+                match result {
+                    Ok(ClientWebSocketMessage::Ping(data)) => {
+                        let _ = writer.send(WriterMsg::Pong(data.to_vec()));
+                    },
+                    Ok(ClientWebSocketMessage::Pong(_)) => {
+                    },
+                    Ok(ClientWebSocketMessage::Text(text)) => {
+                        let _ = rx_sender.send(WebSocketMessage::String(text.into()));
+                    },
+                    Ok(ClientWebSocketMessage::Binary(data)) => {
+                        let _ = rx_sender.send(WebSocketMessage::Binary(data.into()));
+                    },
+                    Ok(ClientWebSocketMessage::Close{code, reason}) => {
+                        // echo the close, if we started the handshake the writer is already gone
+                        let _ = writer.send(WriterMsg::Close(code, reason.to_string()));
+                        let _ = rx_sender.send(WebSocketMessage::Closed(WebSocketCloseReason::new(code, reason)));
+                        closed = true;
+                    },
                     Err(e) => {
-                        eprintln!("Failed to receive data: {}", e);
+                        let _ = writer.send(WriterMsg::Close(e.close_code(), String::new()));
+                        let _ = rx_sender.send(WebSocketMessage::Error(format!("Websocket protocol error {:?}", e)));
+                        closed = true;
                     }
                 }
-            }
-        });
-                
-        OsWebSocket{sender:Some(sender)}
+            });
+        }
+        closed
     }
-}
\ No newline at end of file
+}
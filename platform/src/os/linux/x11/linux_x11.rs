@@ -11,19 +11,24 @@ use {
         x11::xlib_event::*,
         x11::xlib_app::*,
         x11::x11_sys,
-        linux_media::CxLinuxMedia
+        linux_media::CxLinuxMedia,
+        linux_http::LinuxHttpRequests,
+        xdg_portal::LinuxFileDialogs,
+        atspi::{LinuxAccessibility, LinuxAccessibilityEvent},
     },
     crate::{
         cx_api::{CxOsOp, CxOsApi, OpenUrlInPlace}, 
         makepad_math::dvec2,
         makepad_live_id::*,
         thread::SignalToUI,
-        event::Event,
+        event::{Event, MouseUpEvent, NetworkResponseChannel},
+        window::CxWindowPool,
         pass::CxPassParent,
         cx::{Cx, OsType,LinuxWindowParams}, 
         os::cx_stdin::{PollTimers},
         gpu_info::GpuPerformance,
         os::cx_native::EventFlow,
+        file_dialogs::FileDialogKind,
     }
 };
 
@@ -40,6 +45,12 @@ impl Cx {
         if is_stdin_loop {
             cx.borrow_mut().in_makepad_studio = true;
         }
+        // the Wayland backend has no IME, compose or drag and drop support yet, so it is opt in
+        else if std::env::var("MAKEPAD_WAYLAND").map_or(false, | v | v == "1")
+            && std::env::var_os("WAYLAND_DISPLAY").is_some()
+            && Cx::wayland_event_loop(cx.clone()) {
+            return
+        }
         init_xlib_app_global(Box::new({
             let cx = cx.clone();
             move | xlib_app,
@@ -65,6 +76,7 @@ impl Cx {
             return cx.borrow_mut().stdin_event_loop();
         }
         
+        cx.borrow_mut().os.accessibility.start();
         cx.borrow_mut().call_event_handler(&Event::Startup);
         cx.borrow_mut().redraw_all();
         get_xlib_app_global().start_timer(0,0.008,true);
@@ -175,13 +187,26 @@ impl Cx {
                 self.call_event_handler(&Event::TextInput(e))
             }
             XlibEvent::Drag(e) => {
-                self.call_event_handler(&Event::Drag(e))
+                self.call_event_handler(&Event::Drag(e));
+                self.drag_drop.cycle_drag();
             }
             XlibEvent::Drop(e) => {
-                self.call_event_handler(&Event::Drop(e))
+                self.call_event_handler(&Event::Drop(e));
+                self.drag_drop.cycle_drag();
             }
             XlibEvent::DragEnd => {
-                self.call_event_handler(&Event::DragEnd)
+                // the pointer grab of a drag swallows the mouse up
+                self.call_event_handler(&Event::MouseUp(MouseUpEvent {
+                    abs: dvec2(-100000.0, -100000.0),
+                    button: 0,
+                    window_id: CxWindowPool::id_zero(),
+                    modifiers: Default::default(),
+                    time: 0.0
+                }));
+                self.fingers.mouse_up(0);
+                self.fingers.cycle_hover_area(live_id!(mouse).into());
+                self.call_event_handler(&Event::DragEnd);
+                self.drag_drop.cycle_drag();
             }
             XlibEvent::KeyDown(e) => {
                 self.keyboard.process_key_down(e.clone());
@@ -205,6 +230,9 @@ impl Cx {
                         self.call_event_handler(&Event::Signal);
                     }
                     self.handle_action_receiver();
+                    self.handle_networking_events();
+                    self.handle_file_dialog_events();
+                    self.handle_accessibility_events();
                 }
                 else{
                     self.call_event_handler(&Event::Timer(e))
@@ -226,6 +254,35 @@ impl Cx {
     }
 
     pub(crate) fn handle_networking_events(&mut self) {
+        let mut out = Vec::new();
+        while let Ok(item) = self.os.network_response.receiver.try_recv() {
+            // remove the request object on error or end
+            self.os.http_requests.handle_response_item(&item);
+            out.push(item);
+        }
+        if out.len()>0 {
+            self.call_event_handler(&Event::NetworkResponses(out))
+        }
+    }
+    
+    pub(crate) fn handle_file_dialog_events(&mut self) {
+        while let Some(event) = self.os.file_dialogs.try_recv() {
+            self.call_event_handler(&Event::FileDialog(event))
+        }
+    }
+    
+    pub(crate) fn handle_accessibility_events(&mut self) {
+        while let Some(event) = self.os.accessibility.try_recv() {
+            match event {
+                LinuxAccessibilityEvent::Enabled => self.set_accessibility_enabled(true),
+                LinuxAccessibilityEvent::Action(e) => self.call_event_handler(&Event::Accessibility(e)),
+            }
+        }
+        // the tree changes while drawing, publish it once the frame is done
+        let updates = self.take_accessibility_updates();
+        if updates.len() > 0 {
+            self.os.accessibility.publish(&self.accessibility.tree, updates);
+        }
     }
     
     pub (crate) fn handle_repaint(&mut self, opengl_windows: &mut Vec<OpenglWindow>) {
@@ -254,6 +311,14 @@ impl Cx {
         }
     }
     
+    // the portal identifies the window a dialog belongs to as x11:<window id in hex>
+    fn portal_parent_window(opengl_windows: &[OpenglWindow]) -> String {
+        opengl_windows.first()
+            .and_then( | w | w.xlib_window.window)
+            .map( | window | format!("x11:{:x}", window))
+            .unwrap_or_default()
+    }
+    
     fn handle_platform_ops(&mut self, opengl_windows: &mut Vec<OpenglWindow>, xlib_app: &mut XlibApp) -> EventFlow {
         let mut ret = EventFlow::Poll;
         while let Some(op) = self.platform_ops.pop() {
@@ -323,11 +388,18 @@ impl Cx {
                 CxOsOp::XrStopPresenting => {
                     //todo!()
                 },
-                CxOsOp::ShowTextIME(_area, _pos) => {
-                    //todo!()
+                CxOsOp::ShowTextIME(_area, pos) => {
+                    // put the candidate window below the text input, at the cursor
+                    let rect = self.get_ime_area_rect();
+                    let spot = dvec2(rect.pos.x + pos.x, rect.pos.y + rect.size.y);
+                    for w in opengl_windows.iter_mut() {
+                        w.xlib_window.set_ime_spot(spot);
+                    }
                 }
                 CxOsOp::HideTextIME => {
-                    //todo!()
+                    for w in opengl_windows.iter_mut() {
+                        w.xlib_window.reset_ime();
+                    }
                 },
                 CxOsOp::SetCursor(cursor) => {
                     xlib_app.set_mouse_cursor(cursor);
@@ -338,15 +410,16 @@ impl Cx {
                 CxOsOp::StopTimer(timer_id) => {
                     xlib_app.stop_timer(timer_id);
                 },
-                CxOsOp::StartDragging(_dragged_item) => {
+                CxOsOp::StartDragging(items) => {
+                    xlib_app.start_dragging(items);
                 },
                 CxOsOp::UpdateMacosMenu(_menu) => {
                 },
-                CxOsOp::HttpRequest{request_id:_, request:_} => {
-                    todo!()
+                CxOsOp::HttpRequest{request_id, request} => {
+                    self.os.http_requests.make_http_request(request_id, request, self.os.network_response.sender.clone());
                 },
-                CxOsOp::CancelHttpRequest {request_id:_} => {
-                    todo!();
+                CxOsOp::CancelHttpRequest {request_id} => {
+                    self.os.http_requests.cancel_http_request(request_id);
                 }
                 CxOsOp::PrepareVideoPlayback(_, _, _, _, _) => todo!(),
                 CxOsOp::BeginVideoPlayback(_) => todo!(),
@@ -357,10 +430,22 @@ impl Cx {
                 CxOsOp::CleanupVideoPlaybackResources(_) => todo!(),
                 CxOsOp::UpdateVideoSurfaceTexture(_) => todo!(),
 
-                CxOsOp::SaveFileDialog(_) => todo!(),
-                CxOsOp::SelectFileDialog(_) => todo!(),
-                CxOsOp::SaveFolderDialog(_) => todo!(),
-                CxOsOp::SelectFolderDialog(_) => todo!(),
+                CxOsOp::SaveFileDialog(settings) => {
+                    let parent = Self::portal_parent_window(opengl_windows);
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SaveFile, settings, parent);
+                }
+                CxOsOp::SelectFileDialog(settings) => {
+                    let parent = Self::portal_parent_window(opengl_windows);
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SelectFile, settings, parent);
+                }
+                CxOsOp::SaveFolderDialog(settings) => {
+                    let parent = Self::portal_parent_window(opengl_windows);
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SaveFolder, settings, parent);
+                }
+                CxOsOp::SelectFolderDialog(settings) => {
+                    let parent = Self::portal_parent_window(opengl_windows);
+                    self.os.file_dialogs.open_file_dialog(FileDialogKind::SelectFolder, settings, parent);
+                }
             }
         }
         ret
@@ -399,7 +484,11 @@ pub struct CxOs {
     pub(crate) media: CxLinuxMedia,
     pub (crate) stdin_timers: PollTimers,
     pub (crate) start_time: Option<Instant>,
+    pub (crate) network_response: NetworkResponseChannel,
+    pub (crate) http_requests: LinuxHttpRequests,
+    pub (crate) file_dialogs: LinuxFileDialogs,
+    pub (crate) accessibility: LinuxAccessibility,
     // HACK(eddyb) generalize this to EGL, properly.
-    pub(super) opengl_cx: Option<OpenglCx>,
+    pub(crate) opengl_cx: Option<OpenglCx>,
 }
 
@@ -2,6 +2,7 @@ pub mod x11_sys;
 pub mod opengl_x11;
 pub mod xlib_app; 
 pub mod xlib_window;
+pub mod xlib_dnd;
 pub mod xlib_event;
 pub mod linux_x11; 
 pub mod linux_x11_stdin; 
@@ -30,17 +30,24 @@ impl Cx {
         &mut self,
         pass_id: PassId,
         opengl_window: &mut OpenglWindow,
+    ) {
+        self.draw_pass_to_egl_surface(pass_id, opengl_window.egl_surface, &opengl_window.window_geom);
+    }
+    
+    pub fn draw_pass_to_egl_surface(
+        &mut self,
+        pass_id: PassId,
+        egl_surface: egl_sys::EGLSurface,
+        window_geom: &WindowGeom,
     ) {
         let draw_list_id = self.passes[pass_id].main_draw_list_id.unwrap();
         
         self.setup_render_pass(pass_id);
         
-        let egl_surface = opengl_window.egl_surface;
-        
         self.passes[pass_id].paint_dirty = false;
 
-        let pix_width = opengl_window.window_geom.inner_size.x * opengl_window.window_geom.dpi_factor;
-        let pix_height = opengl_window.window_geom.inner_size.y * opengl_window.window_geom.dpi_factor;
+        let pix_width = window_geom.inner_size.x * window_geom.dpi_factor;
+        let pix_height = window_geom.inner_size.y * window_geom.dpi_factor;
         unsafe {
             let opengl_cx = self.os.opengl_cx.as_ref().unwrap();
             (opengl_cx.libegl.eglMakeCurrent.unwrap())(opengl_cx.egl_display, egl_surface, egl_surface, opengl_cx.egl_context);
@@ -381,6 +388,35 @@ impl OpenglCx {
         }
     }
 
+    pub fn create_window_surface(&self, native_window: egl_sys::EGLNativeWindowType) -> egl_sys::EGLSurface {
+        let egl_surface = unsafe {
+            (self.libegl.eglCreateWindowSurface.unwrap())(
+                self.egl_display,
+                self.egl_config,
+                native_window,
+                std::ptr::null(),
+            )
+        };
+        assert!(!egl_surface.is_null(), "eglCreateWindowSurface failed");
+        egl_surface
+    }
+    
+    pub fn destroy_window_surface(&self, egl_surface: egl_sys::EGLSurface) {
+        self.make_current();
+        unsafe {
+            (self.libegl.eglDestroySurface.unwrap())(self.egl_display, egl_surface);
+        }
+    }
+    
+    /// With a swap interval of 0 eglSwapBuffers doesn't wait for the compositor,
+    /// which on Wayland would block forever while a window is hidden.
+    pub fn set_swap_interval(&self, egl_surface: egl_sys::EGLSurface, interval: i32) {
+        unsafe {
+            (self.libegl.eglMakeCurrent.unwrap())(self.egl_display, egl_surface, egl_surface, self.egl_context);
+            (self.libegl.eglSwapInterval.unwrap())(self.egl_display, interval);
+        }
+    }
+
     pub fn make_current(&self) {
         unsafe {
             (self.libegl.eglMakeCurrent.unwrap())(
@@ -454,15 +490,7 @@ impl OpenglWindow {
         let custom_window_chrome = false;
         xlib_window.init(title, inner_size, position, visual_info, custom_window_chrome);
 
-        let egl_surface = unsafe {
-            (opengl_cx.libegl.eglCreateWindowSurface.unwrap())(
-                opengl_cx.egl_display,
-                opengl_cx.egl_config,
-                xlib_window.window.unwrap(),
-                std::ptr::null(),
-            )
-        };
-        assert!(!egl_surface.is_null(), "eglCreateWindowSurface failed");
+        let egl_surface = opengl_cx.create_window_surface(xlib_window.window.unwrap());
 
         OpenglWindow {
             first_draw: true,
@@ -11,7 +11,9 @@ use {
         x11_sys,
         xlib_event::XlibEvent,
         xlib_window::*,
+        xlib_dnd::Dnd,
         super::select_timer::SelectTimers,
+        super::libc_sys,
     },
     crate::{
         makepad_math::DVec2,
@@ -64,7 +66,19 @@ impl XlibApp {
         unsafe {
             let display = x11_sys::XOpenDisplay(ptr::null());
             let display_fd = x11_sys::XConnectionNumber(display);
-            let xim = x11_sys::XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            // input methods are picked from the locale and the XMODIFIERS environment variable,
+            // the builtin one that handles dead keys and compose needs at least the ctype locale
+            libc_sys::setlocale(libc_sys::LC_CTYPE, "\0".as_ptr() as *const _);
+            if x11_sys::XSupportsLocale() == 0 {
+                libc_sys::setlocale(libc_sys::LC_CTYPE, "C.UTF-8\0".as_ptr() as *const _);
+            }
+            x11_sys::XSetLocaleModifiers("\0".as_ptr() as *const _);
+            let mut xim = x11_sys::XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            if xim.is_null() {
+                // the configured input method server isn't running, fall back to the builtin one
+                x11_sys::XSetLocaleModifiers("@im=none\0".as_ptr() as *const _);
+                xim = x11_sys::XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            }
             //let mut signal_fds = [0, 0];
             //libc_sys::pipe(signal_fds.as_mut_ptr());
             x11_sys::XrmInitialize();
@@ -99,11 +113,15 @@ impl XlibApp {
             let mut event = mem::MaybeUninit::uninit();
             x11_sys::XNextEvent(self.display, event.as_mut_ptr());
             let mut event = event.assume_init();
+            // the input method consumes the keys of a composition or dead key sequence
+            if x11_sys::XFilterEvent(&mut event, 0) != 0 {
+                continue
+            }
             match event.type_ as u32 {
                 x11_sys::SelectionNotify => {
                     let selection = event.xselection;
-                    if selection.property == self.dnd.atoms.selection {
-                        self.dnd.handle_selection_event(&selection);
+                    if selection.selection == self.dnd.atoms.selection {
+                        self.dnd_handle_selection_notify(&selection);
                     } else {
                         // first get the size of the thing
                         let mut actual_type = mem::MaybeUninit::uninit();
@@ -162,6 +180,10 @@ impl XlibApp {
                 },
                 x11_sys::SelectionRequest => {
                     let request = event.xselectionrequest;
+                    if request.selection == self.dnd.atoms.selection {
+                        self.dnd_handle_selection_request(&request);
+                        continue
+                    }
                     let mut response = x11_sys::XSelectionEvent {
                         type_: x11_sys::SelectionNotify as i32,
                         serial: 0,
@@ -247,6 +269,10 @@ impl XlibApp {
                 },
                 x11_sys::MotionNotify => { // mousemove
                     let motion = event.xmotion;
+                    if self.dnd.is_dragging() {
+                        self.dnd_source_motion(motion.x_root, motion.y_root, motion.state, motion.time);
+                        continue
+                    }
                     if let Some(window_ptr) = self.window_map.get(&motion.window) {
                         let window = &mut (**window_ptr);
                         let x = motion.x;
@@ -403,7 +429,7 @@ impl XlibApp {
                                 }
                             }
                             else {
-                                window.send_mouse_down(button.button as usize, self.xkeystate_to_modifiers(button.state))
+                                window.send_mouse_down(Self::xbutton_to_mouse_button(button.button), self.xkeystate_to_modifiers(button.state))
                             }
                         }
                     }
@@ -412,12 +438,22 @@ impl XlibApp {
                 },
                 x11_sys::ButtonRelease => { // mouse up
                     let button = event.xbutton;
+                    if self.dnd.is_dragging() {
+                        self.dnd_source_release(button.time);
+                        continue
+                    }
                     if let Some(window_ptr) = self.window_map.get(&button.window) {
                         let window = &mut (**window_ptr);
-                        window.send_mouse_up(button.button as usize, self.xkeystate_to_modifiers(button.state))
+                        window.send_mouse_up(Self::xbutton_to_mouse_button(button.button), self.xkeystate_to_modifiers(button.state))
                     }
                 },
                 x11_sys::KeyPress => {
+                    if self.dnd.is_dragging() {
+                        if self.xkeyevent_to_keycode(&mut event.xkey) == KeyCode::Escape {
+                            self.dnd_source_cancel();
+                        }
+                        continue
+                    }
                     if let Some(window_ptr) = self.window_map.get(&event.xkey.window) {
                         let window = &mut (**window_ptr);
                         let block_text = if event.xkey.keycode != 0 {
@@ -482,30 +518,12 @@ impl XlibApp {
                         }else {false};
                         
                         if !block_text {
-                            // decode the character
-                            let mut buffer = [0u8; 32];
-                            let mut keysym = mem::MaybeUninit::uninit();
-                            let mut status = mem::MaybeUninit::uninit();
-                            let count = x11_sys::Xutf8LookupString(
-                                window.xic.unwrap(),
-                                &mut event.xkey,
-                                buffer.as_mut_ptr() as *mut c_char,
-                                buffer.len() as c_int,
-                                keysym.as_mut_ptr(),
-                                status.as_mut_ptr(),
-                            );
-                            //let keysym = keysym.assume_init();
-                            let status = status.assume_init();
-                            if status != x11_sys::XBufferOverflow {
-                                let utf8 = std::str::from_utf8(&buffer[..count as usize]).unwrap_or("").to_string();
-                                let char_code = utf8.chars().next().unwrap_or('\0');
-                                if char_code >= ' ' && char_code != 127 as char {
-                                    self.do_callback(XlibEvent::TextInput(TextInputEvent {
-                                        input: utf8,
-                                        was_paste: false,
-                                        replace_last: false
-                                    }));
-                                }
+                            // decode the character, or the text an input method commits
+                            let utf8 = self.lookup_key_text(window.xic, &mut event.xkey);
+                            let char_code = utf8.chars().next().unwrap_or('\0');
+                            if char_code >= ' ' && char_code != 127 as char {
+                                let replace_last = window.take_ime_preedit();
+                                window.send_text_input(utf8, replace_last);
                             }
                         }
                     }
@@ -540,14 +558,8 @@ impl XlibApp {
                             window.close_window();
                         }
                     }
-                    if event.message_type == self.dnd.atoms.enter {
-                        self.dnd.handle_enter_event(&event);
-                    } else if event.message_type == self.dnd.atoms.drop {
-                        self.dnd.handle_drop_event(&event);
-                    } else if event.message_type == self.dnd.atoms.leave {
-                        self.dnd.handle_leave_event(&event);
-                    } else if event.message_type == self.dnd.atoms.position {
-                        self.dnd.handle_position_event(&event);
+                    else {
+                        self.dnd_handle_client_message(&event);
                     }
                 },
                 x11_sys::Expose => {
@@ -558,6 +570,16 @@ impl XlibApp {
                     (glx.glXSwapBuffers)(display, window);
                     */
                 },
+                x11_sys::FocusIn => {
+                    if let Some(window_ptr) = self.window_map.get(&event.xfocus.window) {
+                        (**window_ptr).set_ime_focus(true);
+                    }
+                },
+                x11_sys::FocusOut => {
+                    if let Some(window_ptr) = self.window_map.get(&event.xfocus.window) {
+                        (**window_ptr).set_ime_focus(false);
+                    }
+                },
                 x11_sys::VisibilityNotify => {
                     let event = event.xvisibility;
                     if event.state != x11_sys::VisibilityFullyObscured {
@@ -726,7 +748,21 @@ impl XlibApp {
         }
     }
 
-    fn xkeystate_to_modifiers(&self, state: c_uint) -> KeyModifiers {
+    /// X11 numbers the left, middle and right buttons 1, 2 and 3, and the back and forward buttons
+    /// 8 and 9. The other backends use 0 for left, 1 for right, 2 for middle, 3 for back and 4 for
+    /// forward.
+    fn xbutton_to_mouse_button(button: c_uint) -> usize {
+        match button {
+            1 => 0,
+            2 => 2,
+            3 => 1,
+            8 => 3,
+            9 => 4,
+            button => button as usize
+        }
+    }
+    
+    pub fn xkeystate_to_modifiers(&self, state: c_uint) -> KeyModifiers {
         KeyModifiers {
             alt: state & x11_sys::Mod1Mask != 0,
             shift: state & x11_sys::ShiftMask != 0,
@@ -735,6 +771,26 @@ impl XlibApp {
         }
     }
     
+    unsafe fn lookup_key_text(&self, xic: Option<x11_sys::XIC>, key_event: &mut x11_sys::XKeyEvent) -> String {
+        let mut buffer = vec![0u8; 32];
+        let mut keysym = 0;
+        let Some(xic) = xic else {
+            let count = x11_sys::XLookupString(key_event, buffer.as_mut_ptr() as *mut c_char, buffer.len() as c_int, &mut keysym, ptr::null_mut());
+            return String::from_utf8_lossy(&buffer[..count.max(0) as usize]).into_owned()
+        };
+        let mut status = 0;
+        let mut count = x11_sys::Xutf8LookupString(xic, key_event, buffer.as_mut_ptr() as *mut c_char, buffer.len() as c_int, &mut keysym, &mut status);
+        if status == x11_sys::XBufferOverflow {
+            // committed text can be longer than a single key, ask again with the size we got
+            buffer.resize(count as usize, 0);
+            count = x11_sys::Xutf8LookupString(xic, key_event, buffer.as_mut_ptr() as *mut c_char, buffer.len() as c_int, &mut keysym, &mut status);
+        }
+        if status != x11_sys::XLookupChars && status != x11_sys::XLookupBoth {
+            return String::new()
+        }
+        std::str::from_utf8(&buffer[..count as usize]).unwrap_or("").to_string()
+    }
+    
     fn xkeyevent_to_keycode(&self, key_event: &mut x11_sys::XKeyEvent) -> KeyCode {
         let mut keysym = 0;
         unsafe {
@@ -746,141 +802,7 @@ impl XlibApp {
                 ptr::null_mut(),
             );
         }
-        match keysym as u32 {
-            x11_sys::XK_a => KeyCode::KeyA,
-            x11_sys::XK_A => KeyCode::KeyA,
-            x11_sys::XK_b => KeyCode::KeyB,
-            x11_sys::XK_B => KeyCode::KeyB,
-            x11_sys::XK_c => KeyCode::KeyC,
-            x11_sys::XK_C => KeyCode::KeyC,
-            x11_sys::XK_d => KeyCode::KeyD,
-            x11_sys::XK_D => KeyCode::KeyD,
-            x11_sys::XK_e => KeyCode::KeyE,
-            x11_sys::XK_E => KeyCode::KeyE,
-            x11_sys::XK_f => KeyCode::KeyF,
-            x11_sys::XK_F => KeyCode::KeyF,
-            x11_sys::XK_g => KeyCode::KeyG,
-            x11_sys::XK_G => KeyCode::KeyG,
-            x11_sys::XK_h => KeyCode::KeyH,
-            x11_sys::XK_H => KeyCode::KeyH,
-            x11_sys::XK_i => KeyCode::KeyI,
-            x11_sys::XK_I => KeyCode::KeyI,
-            x11_sys::XK_j => KeyCode::KeyJ,
-            x11_sys::XK_J => KeyCode::KeyJ,
-            x11_sys::XK_k => KeyCode::KeyK,
-            x11_sys::XK_K => KeyCode::KeyK,
-            x11_sys::XK_l => KeyCode::KeyL,
-            x11_sys::XK_L => KeyCode::KeyL,
-            x11_sys::XK_m => KeyCode::KeyM,
-            x11_sys::XK_M => KeyCode::KeyM,
-            x11_sys::XK_n => KeyCode::KeyN,
-            x11_sys::XK_N => KeyCode::KeyN,
-            x11_sys::XK_o => KeyCode::KeyO,
-            x11_sys::XK_O => KeyCode::KeyO,
-            x11_sys::XK_p => KeyCode::KeyP,
-            x11_sys::XK_P => KeyCode::KeyP,
-            x11_sys::XK_q => KeyCode::KeyQ,
-            x11_sys::XK_Q => KeyCode::KeyQ,
-            x11_sys::XK_r => KeyCode::KeyR,
-            x11_sys::XK_R => KeyCode::KeyR,
-            x11_sys::XK_s => KeyCode::KeyS,
-            x11_sys::XK_S => KeyCode::KeyS,
-            x11_sys::XK_t => KeyCode::KeyT,
-            x11_sys::XK_T => KeyCode::KeyT,
-            x11_sys::XK_u => KeyCode::KeyU,
-            x11_sys::XK_U => KeyCode::KeyU,
-            x11_sys::XK_v => KeyCode::KeyV,
-            x11_sys::XK_V => KeyCode::KeyV,
-            x11_sys::XK_w => KeyCode::KeyW,
-            x11_sys::XK_W => KeyCode::KeyW,
-            x11_sys::XK_x => KeyCode::KeyX,
-            x11_sys::XK_X => KeyCode::KeyX,
-            x11_sys::XK_y => KeyCode::KeyY,
-            x11_sys::XK_Y => KeyCode::KeyY,
-            x11_sys::XK_z => KeyCode::KeyZ,
-            x11_sys::XK_Z => KeyCode::KeyZ,
-            
-            x11_sys::XK_0 => KeyCode::Key0,
-            x11_sys::XK_1 => KeyCode::Key1,
-            x11_sys::XK_2 => KeyCode::Key2,
-            x11_sys::XK_3 => KeyCode::Key3,
-            x11_sys::XK_4 => KeyCode::Key4,
-            x11_sys::XK_5 => KeyCode::Key5,
-            x11_sys::XK_6 => KeyCode::Key6,
-            x11_sys::XK_7 => KeyCode::Key7,
-            x11_sys::XK_8 => KeyCode::Key8,
-            x11_sys::XK_9 => KeyCode::Key9,
-            
-            x11_sys::XK_Alt_L => KeyCode::Alt,
-            x11_sys::XK_Alt_R => KeyCode::Alt,
-            x11_sys::XK_Meta_L => KeyCode::Logo,
-            x11_sys::XK_Meta_R => KeyCode::Logo,
-            x11_sys::XK_Shift_L => KeyCode::Shift,
-            x11_sys::XK_Shift_R => KeyCode::Shift,
-            x11_sys::XK_Control_L => KeyCode::Control,
-            x11_sys::XK_Control_R => KeyCode::Control,
-            
-            x11_sys::XK_equal => KeyCode::Equals,
-            x11_sys::XK_minus => KeyCode::Minus,
-            x11_sys::XK_bracketright => KeyCode::RBracket,
-            x11_sys::XK_bracketleft => KeyCode::LBracket,
-            x11_sys::XK_Return => KeyCode::ReturnKey,
-            x11_sys::XK_grave => KeyCode::Backtick,
-            x11_sys::XK_semicolon => KeyCode::Semicolon,
-            x11_sys::XK_backslash => KeyCode::Backslash,
-            x11_sys::XK_comma => KeyCode::Comma,
-            x11_sys::XK_slash => KeyCode::Slash,
-            x11_sys::XK_period => KeyCode::Period,
-            x11_sys::XK_Tab => KeyCode::Tab,
-            x11_sys::XK_ISO_Left_Tab => KeyCode::Tab,
-            x11_sys::XK_space => KeyCode::Space,
-            x11_sys::XK_BackSpace => KeyCode::Backspace,
-            x11_sys::XK_Escape => KeyCode::Escape,
-            x11_sys::XK_Caps_Lock => KeyCode::Capslock,
-            x11_sys::XK_KP_Decimal => KeyCode::NumpadDecimal,
-            x11_sys::XK_KP_Multiply => KeyCode::NumpadMultiply,
-            x11_sys::XK_KP_Add => KeyCode::NumpadAdd,
-            x11_sys::XK_Num_Lock => KeyCode::Numlock,
-            x11_sys::XK_KP_Divide => KeyCode::NumpadDivide,
-            x11_sys::XK_KP_Enter => KeyCode::NumpadEnter,
-            x11_sys::XK_KP_Subtract => KeyCode::NumpadSubtract,
-            //keysim::XK_9 => KeyCode::NumpadEquals,
-            x11_sys::XK_KP_0 => KeyCode::Numpad0,
-            x11_sys::XK_KP_1 => KeyCode::Numpad1,
-            x11_sys::XK_KP_2 => KeyCode::Numpad2,
-            x11_sys::XK_KP_3 => KeyCode::Numpad3,
-            x11_sys::XK_KP_4 => KeyCode::Numpad4,
-            x11_sys::XK_KP_5 => KeyCode::Numpad5,
-            x11_sys::XK_KP_6 => KeyCode::Numpad6,
-            x11_sys::XK_KP_7 => KeyCode::Numpad7,
-            x11_sys::XK_KP_8 => KeyCode::Numpad8,
-            x11_sys::XK_KP_9 => KeyCode::Numpad9,
-            
-            x11_sys::XK_F1 => KeyCode::F1,
-            x11_sys::XK_F2 => KeyCode::F2,
-            x11_sys::XK_F3 => KeyCode::F3,
-            x11_sys::XK_F4 => KeyCode::F4,
-            x11_sys::XK_F5 => KeyCode::F5,
-            x11_sys::XK_F6 => KeyCode::F6,
-            x11_sys::XK_F7 => KeyCode::F7,
-            x11_sys::XK_F8 => KeyCode::F8,
-            x11_sys::XK_F9 => KeyCode::F9,
-            x11_sys::XK_F10 => KeyCode::F10,
-            x11_sys::XK_F11 => KeyCode::F11,
-            x11_sys::XK_F12 => KeyCode::F12,
-            
-            x11_sys::XK_Print => KeyCode::PrintScreen,
-            x11_sys::XK_Home => KeyCode::Home,
-            x11_sys::XK_Page_Up => KeyCode::PageUp,
-            x11_sys::XK_Delete => KeyCode::Delete,
-            x11_sys::XK_End => KeyCode::End,
-            x11_sys::XK_Page_Down => KeyCode::PageDown,
-            x11_sys::XK_Left => KeyCode::ArrowLeft,
-            x11_sys::XK_Right => KeyCode::ArrowRight,
-            x11_sys::XK_Down => KeyCode::ArrowDown,
-            x11_sys::XK_Up => KeyCode::ArrowUp,
-            _ => KeyCode::Unknown,
-        }
+        keysym_to_keycode(keysym as u32)
     }
 
     pub unsafe fn copy_to_clipboard(&mut self, text: &String, window_id: c_ulong, time: u64) {
@@ -897,6 +819,145 @@ impl XlibApp {
     }
 }
 
+/// Maps an X11 keysym, which xkbcommon uses as well, to a key code.
+pub fn keysym_to_keycode(keysym: u32) -> KeyCode {
+    match keysym {
+        x11_sys::XK_a => KeyCode::KeyA,
+        x11_sys::XK_A => KeyCode::KeyA,
+        x11_sys::XK_b => KeyCode::KeyB,
+        x11_sys::XK_B => KeyCode::KeyB,
+        x11_sys::XK_c => KeyCode::KeyC,
+        x11_sys::XK_C => KeyCode::KeyC,
+        x11_sys::XK_d => KeyCode::KeyD,
+        x11_sys::XK_D => KeyCode::KeyD,
+        x11_sys::XK_e => KeyCode::KeyE,
+        x11_sys::XK_E => KeyCode::KeyE,
+        x11_sys::XK_f => KeyCode::KeyF,
+        x11_sys::XK_F => KeyCode::KeyF,
+        x11_sys::XK_g => KeyCode::KeyG,
+        x11_sys::XK_G => KeyCode::KeyG,
+        x11_sys::XK_h => KeyCode::KeyH,
+        x11_sys::XK_H => KeyCode::KeyH,
+        x11_sys::XK_i => KeyCode::KeyI,
+        x11_sys::XK_I => KeyCode::KeyI,
+        x11_sys::XK_j => KeyCode::KeyJ,
+        x11_sys::XK_J => KeyCode::KeyJ,
+        x11_sys::XK_k => KeyCode::KeyK,
+        x11_sys::XK_K => KeyCode::KeyK,
+        x11_sys::XK_l => KeyCode::KeyL,
+        x11_sys::XK_L => KeyCode::KeyL,
+        x11_sys::XK_m => KeyCode::KeyM,
+        x11_sys::XK_M => KeyCode::KeyM,
+        x11_sys::XK_n => KeyCode::KeyN,
+        x11_sys::XK_N => KeyCode::KeyN,
+        x11_sys::XK_o => KeyCode::KeyO,
+        x11_sys::XK_O => KeyCode::KeyO,
+        x11_sys::XK_p => KeyCode::KeyP,
+        x11_sys::XK_P => KeyCode::KeyP,
+        x11_sys::XK_q => KeyCode::KeyQ,
+        x11_sys::XK_Q => KeyCode::KeyQ,
+        x11_sys::XK_r => KeyCode::KeyR,
+        x11_sys::XK_R => KeyCode::KeyR,
+        x11_sys::XK_s => KeyCode::KeyS,
+        x11_sys::XK_S => KeyCode::KeyS,
+        x11_sys::XK_t => KeyCode::KeyT,
+        x11_sys::XK_T => KeyCode::KeyT,
+        x11_sys::XK_u => KeyCode::KeyU,
+        x11_sys::XK_U => KeyCode::KeyU,
+        x11_sys::XK_v => KeyCode::KeyV,
+        x11_sys::XK_V => KeyCode::KeyV,
+        x11_sys::XK_w => KeyCode::KeyW,
+        x11_sys::XK_W => KeyCode::KeyW,
+        x11_sys::XK_x => KeyCode::KeyX,
+        x11_sys::XK_X => KeyCode::KeyX,
+        x11_sys::XK_y => KeyCode::KeyY,
+        x11_sys::XK_Y => KeyCode::KeyY,
+        x11_sys::XK_z => KeyCode::KeyZ,
+        x11_sys::XK_Z => KeyCode::KeyZ,
+        
+        x11_sys::XK_0 => KeyCode::Key0,
+        x11_sys::XK_1 => KeyCode::Key1,
+        x11_sys::XK_2 => KeyCode::Key2,
+        x11_sys::XK_3 => KeyCode::Key3,
+        x11_sys::XK_4 => KeyCode::Key4,
+        x11_sys::XK_5 => KeyCode::Key5,
+        x11_sys::XK_6 => KeyCode::Key6,
+        x11_sys::XK_7 => KeyCode::Key7,
+        x11_sys::XK_8 => KeyCode::Key8,
+        x11_sys::XK_9 => KeyCode::Key9,
+        
+        x11_sys::XK_Alt_L => KeyCode::Alt,
+        x11_sys::XK_Alt_R => KeyCode::Alt,
+        x11_sys::XK_Meta_L => KeyCode::Logo,
+        x11_sys::XK_Meta_R => KeyCode::Logo,
+        x11_sys::XK_Shift_L => KeyCode::Shift,
+        x11_sys::XK_Shift_R => KeyCode::Shift,
+        x11_sys::XK_Control_L => KeyCode::Control,
+        x11_sys::XK_Control_R => KeyCode::Control,
+        
+        x11_sys::XK_equal => KeyCode::Equals,
+        x11_sys::XK_minus => KeyCode::Minus,
+        x11_sys::XK_bracketright => KeyCode::RBracket,
+        x11_sys::XK_bracketleft => KeyCode::LBracket,
+        x11_sys::XK_Return => KeyCode::ReturnKey,
+        x11_sys::XK_grave => KeyCode::Backtick,
+        x11_sys::XK_semicolon => KeyCode::Semicolon,
+        x11_sys::XK_backslash => KeyCode::Backslash,
+        x11_sys::XK_comma => KeyCode::Comma,
+        x11_sys::XK_slash => KeyCode::Slash,
+        x11_sys::XK_period => KeyCode::Period,
+        x11_sys::XK_Tab => KeyCode::Tab,
+        x11_sys::XK_ISO_Left_Tab => KeyCode::Tab,
+        x11_sys::XK_space => KeyCode::Space,
+        x11_sys::XK_BackSpace => KeyCode::Backspace,
+        x11_sys::XK_Escape => KeyCode::Escape,
+        x11_sys::XK_Caps_Lock => KeyCode::Capslock,
+        x11_sys::XK_KP_Decimal => KeyCode::NumpadDecimal,
+        x11_sys::XK_KP_Multiply => KeyCode::NumpadMultiply,
+        x11_sys::XK_KP_Add => KeyCode::NumpadAdd,
+        x11_sys::XK_Num_Lock => KeyCode::Numlock,
+        x11_sys::XK_KP_Divide => KeyCode::NumpadDivide,
+        x11_sys::XK_KP_Enter => KeyCode::NumpadEnter,
+        x11_sys::XK_KP_Subtract => KeyCode::NumpadSubtract,
+        //keysim::XK_9 => KeyCode::NumpadEquals,
+        x11_sys::XK_KP_0 => KeyCode::Numpad0,
+        x11_sys::XK_KP_1 => KeyCode::Numpad1,
+        x11_sys::XK_KP_2 => KeyCode::Numpad2,
+        x11_sys::XK_KP_3 => KeyCode::Numpad3,
+        x11_sys::XK_KP_4 => KeyCode::Numpad4,
+        x11_sys::XK_KP_5 => KeyCode::Numpad5,
+        x11_sys::XK_KP_6 => KeyCode::Numpad6,
+        x11_sys::XK_KP_7 => KeyCode::Numpad7,
+        x11_sys::XK_KP_8 => KeyCode::Numpad8,
+        x11_sys::XK_KP_9 => KeyCode::Numpad9,
+        
+        x11_sys::XK_F1 => KeyCode::F1,
+        x11_sys::XK_F2 => KeyCode::F2,
+        x11_sys::XK_F3 => KeyCode::F3,
+        x11_sys::XK_F4 => KeyCode::F4,
+        x11_sys::XK_F5 => KeyCode::F5,
+        x11_sys::XK_F6 => KeyCode::F6,
+        x11_sys::XK_F7 => KeyCode::F7,
+        x11_sys::XK_F8 => KeyCode::F8,
+        x11_sys::XK_F9 => KeyCode::F9,
+        x11_sys::XK_F10 => KeyCode::F10,
+        x11_sys::XK_F11 => KeyCode::F11,
+        x11_sys::XK_F12 => KeyCode::F12,
+        
+        x11_sys::XK_Print => KeyCode::PrintScreen,
+        x11_sys::XK_Home => KeyCode::Home,
+        x11_sys::XK_Page_Up => KeyCode::PageUp,
+        x11_sys::XK_Delete => KeyCode::Delete,
+        x11_sys::XK_End => KeyCode::End,
+        x11_sys::XK_Page_Down => KeyCode::PageDown,
+        x11_sys::XK_Left => KeyCode::ArrowLeft,
+        x11_sys::XK_Right => KeyCode::ArrowRight,
+        x11_sys::XK_Down => KeyCode::ArrowDown,
+        x11_sys::XK_Up => KeyCode::ArrowUp,
+        _ => KeyCode::Unknown,
+    }
+}
+
 pub struct XlibAtoms {
     pub clipboard: x11_sys::Atom,
     pub net_wm_moveresize: x11_sys::Atom,
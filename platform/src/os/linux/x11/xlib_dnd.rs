@@ -0,0 +1,892 @@
+use {
+    std::{
+        mem,
+        ptr,
+        sync::{Arc, Mutex},
+        os::raw::{c_int, c_uint, c_long, c_ulong, c_uchar, c_void},
+    },
+    self::super::{
+        x11_sys,
+        xlib_app::XlibApp,
+        xlib_event::XlibEvent,
+    },
+    self::super::super::xdg_portal::percent_decode,
+    crate::{
+        makepad_live_id::LiveId,
+        makepad_math::DVec2,
+        event::{DragEvent, DropEvent, DragItem, DragResponse, KeyModifiers},
+    },
+};
+
+// XDND version 5, https://www.freedesktop.org/wiki/Specifications/XDND/
+// As a target we request the dragged data on the first XdndPosition instead of
+// on XdndDrop, since makepad wants the items with every Drag event. As a source
+// we hold a pointer grab and drive the protocol from our own motion events, drags
+// over our own windows are delivered directly without a roundtrip through the server.
+
+const XDND_VERSION: c_long = 5;
+
+pub struct Dnd {
+    pub atoms: DndAtoms,
+    pub display: *mut x11_sys::Display,
+    target: Option<DndTarget>,
+    source: Option<DndSource>,
+}
+
+/// An incoming drag from another client (or another makepad process).
+struct DndTarget {
+    source_window: x11_sys::Window,
+    target_window: x11_sys::Window,
+    version: c_long,
+    data_type: Option<x11_sys::Atom>,
+    items: Option<Arc<Vec<DragItem >>>,
+    requested: bool,
+    root_pos: (c_int, c_int),
+    position_pending: bool,
+    drop_pending: bool,
+    action: x11_sys::Atom,
+    response: DragResponse,
+}
+
+/// An outgoing drag started with `Cx::start_dragging`.
+struct DndSource {
+    window: x11_sys::Window,
+    items: Arc<Vec<DragItem >>,
+    types: Vec<x11_sys::Atom>,
+    target_window: x11_sys::Window,
+    target_version: c_long,
+    internal: bool,
+    status_pending: bool,
+    pending_position: Option<(c_int, c_int, x11_sys::Time)>,
+    accepted_action: Option<x11_sys::Atom>,
+    response: DragResponse,
+    last_pos: DVec2,
+    modifiers: KeyModifiers,
+    dropped: bool,
+}
+
+impl Dnd {
+    pub unsafe fn new(display: *mut x11_sys::Display) -> Dnd {
+        Dnd {
+            atoms: DndAtoms::new(display),
+            display,
+            target: None,
+            source: None,
+        }
+    }
+
+    /// Enables drag-and-drop for the given window.
+    pub unsafe fn enable_for_window(&mut self, window: x11_sys::Window) {
+        // To enable drag-and-drop for a window, we need to set the XdndAware property of the window
+        // to the version of XDND we support.
+        let version = XDND_VERSION as c_ulong;
+        x11_sys::XChangeProperty(
+            self.display,
+            window,
+            self.atoms.aware,
+            x11_sys::XA_ATOM as x11_sys::Atom,
+            32,
+            x11_sys::PropModeReplace as c_int,
+            &version as *const c_ulong as *const c_uchar,
+            1
+        );
+    }
+
+    /// True while an outgoing drag holds the pointer.
+    pub fn is_dragging(&self) -> bool {
+        self.source.as_ref().map_or(false, | source | !source.dropped)
+    }
+
+    fn action_for_response(&self, response: DragResponse) -> x11_sys::Atom {
+        match response {
+            DragResponse::None => x11_sys::None as x11_sys::Atom,
+            DragResponse::Copy => self.atoms.action_copy,
+            DragResponse::Link => self.atoms.action_link,
+            DragResponse::Move => self.atoms.action_move,
+        }
+    }
+
+    fn response_for_action(&self, action: x11_sys::Atom) -> DragResponse {
+        if action == self.atoms.action_move {
+            DragResponse::Move
+        }
+        else if action == self.atoms.action_link {
+            DragResponse::Link
+        }
+        else {
+            DragResponse::Copy
+        }
+    }
+
+    unsafe fn send_client_message(&self, window: x11_sys::Window, message_type: x11_sys::Atom, data: [c_long; 5]) {
+        let mut event = x11_sys::XClientMessageEvent {
+            type_: x11_sys::ClientMessage as c_int,
+            serial: 0,
+            send_event: 0,
+            display: self.display,
+            window,
+            message_type,
+            format: 32,
+            data: {
+                let mut msg = mem::zeroed::<x11_sys::XClientMessageEvent__bindgen_ty_1>();
+                msg.l = data;
+                msg
+            }
+        };
+        x11_sys::XSendEvent(
+            self.display,
+            window,
+            x11_sys::False as c_int,
+            x11_sys::NoEventMask as c_long,
+            &mut event as *mut _ as *mut x11_sys::XEvent
+        );
+        x11_sys::XFlush(self.display);
+    }
+
+    /// Reads a whole window property, returns its type and the raw items.
+    unsafe fn get_property(&self, window: x11_sys::Window, property: x11_sys::Atom, req_type: x11_sys::Atom) -> Option<(x11_sys::Atom, Vec<u8>)> {
+        let mut data = Vec::new();
+        let mut offset = 0;
+        let length = 1024;
+        let mut actual_type = 0;
+        loop {
+            let mut actual_format = 0;
+            let mut nitems = 0;
+            let mut bytes_after = 0;
+            let mut prop = ptr::null_mut();
+            let result = x11_sys::XGetWindowProperty(
+                self.display,
+                window,
+                property,
+                offset,
+                length,
+                x11_sys::False as c_int,
+                req_type,
+                &mut actual_type,
+                &mut actual_format,
+                &mut nitems,
+                &mut bytes_after,
+                &mut prop,
+            );
+            if result != 0 || prop.is_null() {
+                return None
+            }
+            // format 32 items are handed out as longs by Xlib
+            let item_size = match actual_format {
+                8 => 1,
+                16 => mem::size_of::<std::os::raw::c_short>(),
+                _ => mem::size_of::<c_long>()
+            };
+            data.extend_from_slice(std::slice::from_raw_parts(prop as *const u8, nitems as usize * item_size));
+            x11_sys::XFree(prop as *mut c_void);
+            if bytes_after == 0 {
+                break;
+            }
+            // offsets are in 32 bit units
+            offset += length;
+        }
+        if actual_type == 0 {
+            return None
+        }
+        Some((actual_type, data))
+    }
+
+    unsafe fn get_atom_list(&self, window: x11_sys::Window, property: x11_sys::Atom) -> Vec<x11_sys::Atom> {
+        if let Some((_, data)) = self.get_property(window, property, x11_sys::XA_ATOM as x11_sys::Atom) {
+            data.chunks_exact(mem::size_of::<c_ulong>()).map( | chunk | {
+                let mut bytes = [0u8; mem::size_of::<c_ulong>()];
+                bytes.copy_from_slice(chunk);
+                c_ulong::from_ne_bytes(bytes)
+            }).collect()
+        }
+        else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the XDND version a window speaks, or None if it isn't XdndAware.
+    unsafe fn get_aware_version(&self, window: x11_sys::Window) -> Option<c_long> {
+        self.get_atom_list(window, self.atoms.aware).first().map( | version | *version as c_long)
+    }
+
+    /// Picks the best data type we know how to read from the types a source offers.
+    fn choose_data_type(&self, types: &[x11_sys::Atom]) -> Option<x11_sys::Atom> {
+        [
+            self.atoms.uri_list,
+            self.atoms.utf8_string,
+            self.atoms.text_plain_utf8,
+            self.atoms.text_plain,
+        ].into_iter().find( | ty | *ty != 0 && types.contains(ty))
+    }
+
+    fn parse_drop_data(&self, data_type: x11_sys::Atom, data: &[u8]) -> Vec<DragItem> {
+        let text = String::from_utf8_lossy(data);
+        if data_type == self.atoms.uri_list {
+            parse_uri_list(&text)
+        }
+        else {
+            let value = text.trim_end_matches('\0').to_string();
+            if value.len() == 0 {
+                Vec::new()
+            }
+            else {
+                vec![DragItem::String {value, internal_id: None}]
+            }
+        }
+    }
+
+    /// Serializes our dragged items into the requested selection target.
+    fn export_drag_data(&self, items: &[DragItem], target: x11_sys::Atom) -> Option<Vec<u8>> {
+        if target == self.atoms.uri_list {
+            let mut out = String::new();
+            for item in items {
+                if let DragItem::FilePath {path, internal_id} = item {
+                    out.push_str(&path_to_uri(path, *internal_id));
+                    out.push_str("\r\n");
+                }
+            }
+            Some(out.into_bytes())
+        }
+        else if target == self.atoms.utf8_string || target == self.atoms.text_plain_utf8 || target == self.atoms.text_plain {
+            let strings: Vec<&str> = items.iter().filter_map( | item | match item {
+                DragItem::String {value, ..} => Some(value.as_str()),
+                _ => None
+            }).collect();
+            let text = if strings.len() > 0 {
+                strings.join("\n")
+            }
+            else {
+                items.iter().filter_map( | item | match item {
+                    DragItem::FilePath {path, ..} => Some(path.as_str()),
+                    _ => None
+                }).collect::<Vec<_ >>().join("\n")
+            };
+            Some(text.into_bytes())
+        }
+        else {
+            None
+        }
+    }
+}
+
+impl XlibApp {
+
+    /// Starts an outgoing drag, the actual Drag events follow with the next pointer motion.
+    pub fn start_dragging(&mut self, items: Vec<DragItem>) {
+        if items.len() == 0 {
+            return
+        }
+        unsafe {
+            let (x_root, y_root, mask) = self.query_pointer();
+            // the drag starts from whichever of our windows is under the pointer
+            let window = match self.find_dnd_window(x_root, y_root) {
+                Some((window, _)) if self.window_map.contains_key(&window) => window,
+                _ => match self.window_map.keys().next() {
+                    Some(window) => *window,
+                    None => return
+                }
+            };
+
+            let mut types = Vec::new();
+            if items.iter().any( | item | matches!(item, DragItem::FilePath {..})) {
+                types.push(self.dnd.atoms.uri_list);
+            }
+            types.push(self.dnd.atoms.utf8_string);
+            types.push(self.dnd.atoms.text_plain_utf8);
+            types.push(self.dnd.atoms.text_plain);
+
+            x11_sys::XChangeProperty(
+                self.display,
+                window,
+                self.dnd.atoms.type_list,
+                x11_sys::XA_ATOM as x11_sys::Atom,
+                32,
+                x11_sys::PropModeReplace as c_int,
+                types.as_ptr() as *const c_uchar,
+                types.len() as c_int
+            );
+            x11_sys::XSetSelectionOwner(self.display, self.dnd.atoms.selection, window, x11_sys::CurrentTime as x11_sys::Time);
+
+            let cursor = self.load_first_cursor(&[b"dnd-move\0", b"grabbing\0", b"fleur\0"]).unwrap_or(0);
+            x11_sys::XGrabPointer(
+                self.display,
+                window,
+                x11_sys::False as c_int,
+                (x11_sys::ButtonMotionMask | x11_sys::PointerMotionMask | x11_sys::ButtonReleaseMask) as c_uint,
+                x11_sys::GrabModeAsync as c_int,
+                x11_sys::GrabModeAsync as c_int,
+                x11_sys::None as x11_sys::Window,
+                cursor,
+                x11_sys::CurrentTime as x11_sys::Time
+            );
+            if cursor != 0 {
+                x11_sys::XFreeCursor(self.display, cursor);
+            }
+            x11_sys::XFlush(self.display);
+
+            self.dnd.source = Some(DndSource {
+                window,
+                items: Arc::new(items),
+                types,
+                target_window: 0,
+                target_version: 0,
+                internal: false,
+                status_pending: false,
+                pending_position: None,
+                accepted_action: None,
+                response: DragResponse::None,
+                last_pos: DVec2::default(),
+                modifiers: self.xkeystate_to_modifiers(mask),
+                dropped: false,
+            });
+        }
+    }
+
+    unsafe fn root_window(&self) -> x11_sys::Window {
+        x11_sys::XRootWindow(self.display, x11_sys::XDefaultScreen(self.display))
+    }
+
+    unsafe fn query_pointer(&self) -> (c_int, c_int, c_uint) {
+        let mut root = 0;
+        let mut child = 0;
+        let mut x_root = 0;
+        let mut y_root = 0;
+        let mut x = 0;
+        let mut y = 0;
+        let mut mask = 0;
+        x11_sys::XQueryPointer(self.display, self.root_window(), &mut root, &mut child, &mut x_root, &mut y_root, &mut x, &mut y, &mut mask);
+        (x_root, y_root, mask)
+    }
+
+    /// Walks down the window tree under a root position to the first XdndAware window.
+    unsafe fn find_dnd_window(&self, x_root: c_int, y_root: c_int) -> Option<(x11_sys::Window, c_long)> {
+        let root = self.root_window();
+        let mut window = root;
+        // toplevels are usually nested in a window manager frame, so a few levels is plenty
+        for _ in 0..8 {
+            let mut x = 0;
+            let mut y = 0;
+            let mut child = 0;
+            if x11_sys::XTranslateCoordinates(self.display, root, window, x_root, y_root, &mut x, &mut y, &mut child) == 0 || child == 0 {
+                return None
+            }
+            window = child;
+            if let Some(version) = self.dnd.get_aware_version(window) {
+                return Some((window, version))
+            }
+        }
+        None
+    }
+
+    /// Converts a root position to logical coordinates of one of our windows.
+    unsafe fn dnd_local_pos(&self, window: x11_sys::Window, x_root: c_int, y_root: c_int) -> Option<DVec2> {
+        let window_ptr = self.window_map.get(&window)?;
+        let dpi_factor = (**window_ptr).last_window_geom.dpi_factor;
+        let mut x = 0;
+        let mut y = 0;
+        let mut child = 0;
+        x11_sys::XTranslateCoordinates(self.display, self.root_window(), window, x_root, y_root, &mut x, &mut y, &mut child);
+        Some(DVec2 {x: x as f64 / dpi_factor, y: y as f64 / dpi_factor})
+    }
+
+    // Source side
+
+    pub unsafe fn dnd_source_motion(&mut self, x_root: c_int, y_root: c_int, state: c_uint, time: x11_sys::Time) {
+        let modifiers = self.xkeystate_to_modifiers(state);
+        let (target_window, target_version) = self.find_dnd_window(x_root, y_root).unwrap_or((0, 0));
+        let internal = self.window_map.contains_key(&target_window);
+        let Some(source) = self.dnd.source.as_mut() else {return};
+        source.modifiers = modifiers;
+
+        if target_window != source.target_window {
+            let (old_window, old_internal, source_window) = (source.target_window, source.internal, source.window);
+            source.target_window = target_window;
+            source.target_version = target_version.min(XDND_VERSION);
+            source.internal = internal;
+            source.status_pending = false;
+            source.pending_position = None;
+            source.accepted_action = None;
+            source.response = DragResponse::None;
+            let (types, version) = (source.types.clone(), source.target_version);
+
+            if old_internal {
+                self.do_callback(XlibEvent::DragEnd);
+            }
+            else if old_window != 0 {
+                self.dnd.send_client_message(old_window, self.dnd.atoms.leave, [source_window as c_long, 0, 0, 0, 0]);
+            }
+            if target_window != 0 && !internal {
+                let mut data = [source_window as c_long, (version << 24) | if types.len() > 3 {1} else {0}, 0, 0, 0];
+                for (i, ty) in types.iter().take(3).enumerate() {
+                    data[2 + i] = *ty as c_long;
+                }
+                self.dnd.send_client_message(target_window, self.dnd.atoms.enter, data);
+            }
+        }
+
+        if target_window == 0 {
+            return
+        }
+        if internal {
+            let Some(abs) = self.dnd_local_pos(target_window, x_root, y_root) else {return};
+            let source = self.dnd.source.as_mut().unwrap();
+            source.last_pos = abs;
+            let items = source.items.clone();
+            let response = Arc::new(Mutex::new(DragResponse::Move));
+            self.do_callback(XlibEvent::Drag(DragEvent {
+                modifiers,
+                handled: Arc::new(Mutex::new(false)),
+                abs,
+                items,
+                response: response.clone(),
+            }));
+            if let Some(source) = self.dnd.source.as_mut() {
+                source.response = *response.lock().unwrap();
+            }
+        }
+        else {
+            let source = self.dnd.source.as_mut().unwrap();
+            if source.status_pending {
+                // wait for the XdndStatus of the previous position first
+                source.pending_position = Some((x_root, y_root, time));
+            }
+            else {
+                source.status_pending = true;
+                let source_window = source.window;
+                self.dnd_send_position(source_window, target_window, x_root, y_root, time);
+            }
+        }
+    }
+
+    unsafe fn dnd_send_position(&self, source_window: x11_sys::Window, target_window: x11_sys::Window, x_root: c_int, y_root: c_int, time: x11_sys::Time) {
+        let action = if self.dnd.source.as_ref().map_or(false, | source | source.modifiers.control) {
+            self.dnd.atoms.action_copy
+        }
+        else {
+            self.dnd.atoms.action_move
+        };
+        self.dnd.send_client_message(target_window, self.dnd.atoms.position, [
+            source_window as c_long,
+            0,
+            ((x_root as c_long) << 16) | (y_root as c_long & 0xffff),
+            time as c_long,
+            action as c_long,
+        ]);
+    }
+
+    pub unsafe fn dnd_source_release(&mut self, time: x11_sys::Time) {
+        x11_sys::XUngrabPointer(self.display, time);
+        x11_sys::XFlush(self.display);
+        let Some(source) = self.dnd.source.take() else {return};
+        if source.internal {
+            if source.response != DragResponse::None {
+                self.do_callback(XlibEvent::Drop(DropEvent {
+                    modifiers: source.modifiers,
+                    handled: Arc::new(Mutex::new(false)),
+                    abs: source.last_pos,
+                    items: source.items.clone(),
+                }));
+            }
+        }
+        else if source.target_window != 0 {
+            if source.accepted_action.is_some() {
+                self.dnd.send_client_message(source.target_window, self.dnd.atoms.drop, [source.window as c_long, 0, time as c_long, 0, 0]);
+                // we keep owning the selection until the target sends XdndFinished
+                self.dnd.source = Some(DndSource {dropped: true, ..source});
+            }
+            else {
+                self.dnd.send_client_message(source.target_window, self.dnd.atoms.leave, [source.window as c_long, 0, 0, 0, 0]);
+            }
+        }
+        self.do_callback(XlibEvent::DragEnd);
+    }
+
+    pub unsafe fn dnd_source_cancel(&mut self) {
+        x11_sys::XUngrabPointer(self.display, x11_sys::CurrentTime as x11_sys::Time);
+        x11_sys::XFlush(self.display);
+        if let Some(source) = self.dnd.source.take() {
+            if source.target_window != 0 && !source.internal {
+                self.dnd.send_client_message(source.target_window, self.dnd.atoms.leave, [source.window as c_long, 0, 0, 0, 0]);
+            }
+            self.do_callback(XlibEvent::DragEnd);
+        }
+    }
+
+    unsafe fn dnd_handle_status(&mut self, event: &x11_sys::XClientMessageEvent) {
+        let Some(source) = self.dnd.source.as_mut() else {return};
+        if event.data.l[0] as x11_sys::Window != source.target_window || source.dropped {
+            return
+        }
+        source.status_pending = false;
+        source.accepted_action = if event.data.l[1] & 1 != 0 {
+            Some(event.data.l[4] as x11_sys::Atom)
+        }
+        else {
+            None
+        };
+        if let Some((x_root, y_root, time)) = source.pending_position.take() {
+            source.status_pending = true;
+            let (source_window, target_window) = (source.window, source.target_window);
+            self.dnd_send_position(source_window, target_window, x_root, y_root, time);
+        }
+    }
+
+    /// Answers a conversion request for XdndSelection while we are the drag source.
+    pub unsafe fn dnd_handle_selection_request(&mut self, request: &x11_sys::XSelectionRequestEvent) {
+        let mut response = x11_sys::XSelectionEvent {
+            type_: x11_sys::SelectionNotify as i32,
+            serial: 0,
+            send_event: 0,
+            display: self.display,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            time: request.time,
+            property: request.property,
+        };
+        // obsolete clients may pass None as the property
+        if response.property == 0 {
+            response.property = request.target;
+        }
+        match &self.dnd.source {
+            Some(source) if request.target == self.dnd.atoms.targets => {
+                let mut targets = source.types.clone();
+                targets.push(self.dnd.atoms.targets);
+                x11_sys::XChangeProperty(
+                    self.display,
+                    request.requestor,
+                    response.property,
+                    x11_sys::XA_ATOM as x11_sys::Atom,
+                    32,
+                    x11_sys::PropModeReplace as c_int,
+                    targets.as_ptr() as *const c_uchar,
+                    targets.len() as c_int
+                );
+            }
+            Some(source) if source.types.contains(&request.target) => {
+                let data = self.dnd.export_drag_data(&source.items, request.target).unwrap_or_default();
+                x11_sys::XChangeProperty(
+                    self.display,
+                    request.requestor,
+                    response.property,
+                    request.target,
+                    8,
+                    x11_sys::PropModeReplace as c_int,
+                    data.as_ptr(),
+                    data.len() as c_int
+                );
+            }
+            _ => {
+                response.property = 0;
+            }
+        }
+        x11_sys::XSendEvent(self.display, request.requestor, 1, 0, &mut response as *mut _ as *mut x11_sys::XEvent);
+        x11_sys::XFlush(self.display);
+    }
+
+    // Target side
+
+    pub unsafe fn dnd_handle_client_message(&mut self, event: &x11_sys::XClientMessageEvent) {
+        let atoms = &self.dnd.atoms;
+        if event.message_type == atoms.enter {
+            self.dnd_handle_enter(event);
+        }
+        else if event.message_type == atoms.position {
+            self.dnd_handle_position(event);
+        }
+        else if event.message_type == atoms.leave {
+            self.dnd_handle_leave(event);
+        }
+        else if event.message_type == atoms.drop {
+            self.dnd_handle_drop(event);
+        }
+        else if event.message_type == atoms.status {
+            self.dnd_handle_status(event);
+        }
+        else if event.message_type == atoms.finished {
+            if self.dnd.source.as_ref().map_or(false, | source | source.dropped) {
+                self.dnd.source = None;
+            }
+        }
+    }
+
+    unsafe fn dnd_handle_enter(&mut self, event: &x11_sys::XClientMessageEvent) {
+        let source_window = event.data.l[0] as x11_sys::Window;
+        let version = (event.data.l[1] >> 24) & 0xff;
+        if version > XDND_VERSION {
+            return
+        }
+        // with bit 0 set the source has more than 3 types and lists them in XdndTypeList
+        let types = if event.data.l[1] & 1 != 0 {
+            self.dnd.get_atom_list(source_window, self.dnd.atoms.type_list)
+        }
+        else {
+            event.data.l[2..5].iter().map( | l | *l as x11_sys::Atom).filter( | atom | *atom != 0).collect()
+        };
+        let data_type = self.dnd.choose_data_type(&types);
+        self.dnd.target = Some(DndTarget {
+            source_window,
+            target_window: event.window,
+            version,
+            data_type,
+            items: None,
+            requested: false,
+            root_pos: (0, 0),
+            position_pending: false,
+            drop_pending: false,
+            action: self.dnd.atoms.action_copy,
+            response: DragResponse::None,
+        });
+    }
+
+    unsafe fn dnd_handle_position(&mut self, event: &x11_sys::XClientMessageEvent) {
+        let Some(target) = self.dnd.target.as_mut() else {return};
+        if target.source_window != event.data.l[0] as x11_sys::Window {
+            return
+        }
+        target.target_window = event.window;
+        target.root_pos = (((event.data.l[2] >> 16) & 0xffff) as c_int, (event.data.l[2] & 0xffff) as c_int);
+        target.action = if target.version >= 2 {event.data.l[4] as x11_sys::Atom} else {self.dnd.atoms.action_copy};
+        target.position_pending = true;
+
+        if target.items.is_some() {
+            self.dnd_send_drag();
+        }
+        else if let Some(data_type) = target.data_type {
+            if !target.requested {
+                target.requested = true;
+                // the reply comes back as a SelectionNotify, the status is sent from there
+                let time = if target.version >= 1 {event.data.l[3] as x11_sys::Time} else {x11_sys::CurrentTime as x11_sys::Time};
+                x11_sys::XConvertSelection(
+                    self.display,
+                    self.dnd.atoms.selection,
+                    data_type,
+                    self.dnd.atoms.selection,
+                    event.window,
+                    time
+                );
+                x11_sys::XFlush(self.display);
+            }
+        }
+        else {
+            let (source_window, target_window) = (target.source_window, target.target_window);
+            target.position_pending = false;
+            self.dnd_send_status(source_window, target_window, DragResponse::None);
+        }
+    }
+
+    unsafe fn dnd_handle_leave(&mut self, event: &x11_sys::XClientMessageEvent) {
+        if let Some(target) = &self.dnd.target {
+            if target.source_window == event.data.l[0] as x11_sys::Window {
+                self.dnd.target = None;
+                self.do_callback(XlibEvent::DragEnd);
+            }
+        }
+    }
+
+    unsafe fn dnd_handle_drop(&mut self, event: &x11_sys::XClientMessageEvent) {
+        let Some(target) = self.dnd.target.as_mut() else {return};
+        if target.source_window != event.data.l[0] as x11_sys::Window {
+            return
+        }
+        if target.items.is_some() {
+            self.dnd_send_drop();
+        }
+        else if target.requested {
+            // the data is still underway, drop once it arrives
+            target.drop_pending = true;
+        }
+        else {
+            let (source_window, target_window) = (target.source_window, target.target_window);
+            self.dnd.target = None;
+            self.dnd_send_finished(source_window, target_window, DragResponse::None);
+            self.do_callback(XlibEvent::DragEnd);
+        }
+    }
+
+    /// Handles the SelectionNotify that answers our XdndSelection conversion request.
+    pub unsafe fn dnd_handle_selection_notify(&mut self, selection: &x11_sys::XSelectionEvent) {
+        let Some(target) = &self.dnd.target else {return};
+        let Some(data_type) = target.data_type else {return};
+        let items = if selection.property != 0 {
+            let data = self.dnd.get_property(selection.requestor, selection.property, x11_sys::AnyPropertyType as x11_sys::Atom);
+            x11_sys::XDeleteProperty(self.display, selection.requestor, selection.property);
+            data.map( | (_, data) | self.dnd.parse_drop_data(data_type, &data)).unwrap_or_default()
+        }
+        else {
+            Vec::new()
+        };
+        let target = self.dnd.target.as_mut().unwrap();
+        target.items = Some(Arc::new(items));
+        if target.drop_pending {
+            self.dnd_send_drop();
+        }
+        else if target.position_pending {
+            self.dnd_send_drag();
+        }
+    }
+
+    unsafe fn dnd_send_drag(&mut self) {
+        let Some(target) = self.dnd.target.as_mut() else {return};
+        target.position_pending = false;
+        let (source_window, target_window, (x_root, y_root), action) = (target.source_window, target.target_window, target.root_pos, target.action);
+        let items = target.items.clone().unwrap_or_default();
+        let initial = self.dnd.response_for_action(action);
+        let abs = self.dnd_local_pos(target_window, x_root, y_root);
+        let response = match abs {
+            Some(abs) if items.len() > 0 => {
+                let response = Arc::new(Mutex::new(initial));
+                let (_, _, mask) = self.query_pointer();
+                self.do_callback(XlibEvent::Drag(DragEvent {
+                    modifiers: self.xkeystate_to_modifiers(mask),
+                    handled: Arc::new(Mutex::new(false)),
+                    abs,
+                    items,
+                    response: response.clone(),
+                }));
+                let response = *response.lock().unwrap();
+                response
+            }
+            _ => DragResponse::None
+        };
+        if let Some(target) = self.dnd.target.as_mut() {
+            target.response = response;
+        }
+        self.dnd_send_status(source_window, target_window, response);
+    }
+
+    unsafe fn dnd_send_drop(&mut self) {
+        let Some(target) = self.dnd.target.take() else {return};
+        let items = target.items.clone().unwrap_or_default();
+        let abs = self.dnd_local_pos(target.target_window, target.root_pos.0, target.root_pos.1);
+        let response = match abs {
+            Some(abs) if items.len() > 0 && target.response != DragResponse::None => {
+                let (_, _, mask) = self.query_pointer();
+                self.do_callback(XlibEvent::Drop(DropEvent {
+                    modifiers: self.xkeystate_to_modifiers(mask),
+                    handled: Arc::new(Mutex::new(false)),
+                    abs,
+                    items,
+                }));
+                target.response
+            }
+            _ => DragResponse::None
+        };
+        self.dnd_send_finished(target.source_window, target.target_window, response);
+        self.do_callback(XlibEvent::DragEnd);
+    }
+
+    unsafe fn dnd_send_status(&self, source_window: x11_sys::Window, target_window: x11_sys::Window, response: DragResponse) {
+        let accepted = response != DragResponse::None;
+        // bit 1 asks for a position message on every move since we don't pass a rectangle
+        self.dnd.send_client_message(source_window, self.dnd.atoms.status, [
+            target_window as c_long,
+            if accepted {0b11} else {0b10},
+            0,
+            0,
+            self.dnd.action_for_response(response) as c_long,
+        ]);
+    }
+
+    unsafe fn dnd_send_finished(&self, source_window: x11_sys::Window, target_window: x11_sys::Window, response: DragResponse) {
+        let accepted = response != DragResponse::None;
+        self.dnd.send_client_message(source_window, self.dnd.atoms.finished, [
+            target_window as c_long,
+            if accepted {1} else {0},
+            self.dnd.action_for_response(response) as c_long,
+            0,
+            0,
+        ]);
+    }
+}
+
+/// Parses a text/uri-list (RFC 2483) into drag items, file uris become
+/// `DragItem::FilePath`, anything else is passed on as `DragItem::String`.
+fn parse_uri_list(text: &str) -> Vec<DragItem> {
+    let mut items = Vec::new();
+    for line in text.split(|c| c == '\n' || c == '\r') {
+        let line = line.trim_matches('\0').trim();
+        if line.len() == 0 || line.starts_with('#') {
+            continue
+        }
+        if let Some(rest) = line.strip_prefix("file://") {
+            // makepad sources tag internal items with a fragment, like on macOS
+            let mut bits = rest.splitn(2, "#makepad_internal_id=");
+            let location = bits.next().unwrap();
+            let internal_id = bits.next().and_then( | id | id.parse::<u64>().ok()).map(LiveId);
+            // skip an optional host part, file://localhost/path
+            let Some(slash) = location.find('/') else {continue};
+            let path = String::from_utf8_lossy(&percent_decode(&location[slash..])).into_owned();
+            items.push(DragItem::FilePath {
+                path: if path == "/makepad_internal_empty" {String::new()} else {path},
+                internal_id
+            });
+        }
+        else {
+            items.push(DragItem::String {value: line.to_string(), internal_id: None});
+        }
+    }
+    items
+}
+
+fn path_to_uri(path: &str, internal_id: Option<LiveId>) -> String {
+    let path = if path.len() == 0 {"/makepad_internal_empty"} else {path};
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for b in path.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(b as char),
+            _ => uri.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    if let Some(id) = internal_id {
+        uri.push_str(&format!("#makepad_internal_id={}", id.0));
+    }
+    uri
+}
+
+pub struct DndAtoms {
+    pub action_copy: x11_sys::Atom,
+    pub action_link: x11_sys::Atom,
+    pub action_move: x11_sys::Atom,
+    pub aware: x11_sys::Atom,
+    pub drop: x11_sys::Atom,
+    pub enter: x11_sys::Atom,
+    pub finished: x11_sys::Atom,
+    pub leave: x11_sys::Atom,
+    pub position: x11_sys::Atom,
+    pub selection: x11_sys::Atom,
+    pub status: x11_sys::Atom,
+    pub type_list: x11_sys::Atom,
+    pub targets: x11_sys::Atom,
+    pub uri_list: x11_sys::Atom,
+    pub utf8_string: x11_sys::Atom,
+    pub text_plain: x11_sys::Atom,
+    pub text_plain_utf8: x11_sys::Atom,
+}
+
+impl DndAtoms {
+    pub unsafe fn new(display: *mut x11_sys::Display) -> DndAtoms {
+        DndAtoms {
+            action_copy: x11_sys::XInternAtom(display, "XdndActionCopy\0".as_ptr() as *const _, 0),
+            action_link: x11_sys::XInternAtom(display, "XdndActionLink\0".as_ptr() as *const _, 0),
+            action_move: x11_sys::XInternAtom(display, "XdndActionMove\0".as_ptr() as *const _, 0),
+            aware: x11_sys::XInternAtom(display, "XdndAware\0".as_ptr() as *const _, 0),
+            drop: x11_sys::XInternAtom(display, "XdndDrop\0".as_ptr() as *const _, 0),
+            enter: x11_sys::XInternAtom(display, "XdndEnter\0".as_ptr() as *const _, 0),
+            finished: x11_sys::XInternAtom(display, "XdndFinished\0".as_ptr() as *const _, 0),
+            leave: x11_sys::XInternAtom(display, "XdndLeave\0".as_ptr() as *const _, 0),
+            position: x11_sys::XInternAtom(display, "XdndPosition\0".as_ptr() as *const _, 0),
+            selection: x11_sys::XInternAtom(display, "XdndSelection\0".as_ptr() as *const _, 0),
+            status: x11_sys::XInternAtom(display, "XdndStatus\0".as_ptr() as *const _, 0),
+            type_list: x11_sys::XInternAtom(display, "XdndTypeList\0".as_ptr() as *const _, 0),
+            targets: x11_sys::XInternAtom(display, "TARGETS\0".as_ptr() as *const _, 0),
+            uri_list: x11_sys::XInternAtom(display, "text/uri-list\0".as_ptr() as *const _, 0),
+            utf8_string: x11_sys::XInternAtom(display, "UTF8_STRING\0".as_ptr() as *const _, 0),
+            text_plain: x11_sys::XInternAtom(display, "text/plain\0".as_ptr() as *const _, 0),
+            text_plain_utf8: x11_sys::XInternAtom(display, "text/plain;charset=utf-8\0".as_ptr() as *const _, 0),
+        }
+    }
+}
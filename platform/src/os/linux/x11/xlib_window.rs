@@ -3,9 +3,9 @@ use {
         mem,
         cell::Cell,
         rc::Rc,
-        os::raw::{c_ulong, c_long, c_void, c_char},
+        os::raw::{c_int, c_ulong, c_long, c_void, c_char},
         ptr,
-        ffi::{CStr, CString, OsStr},
+        ffi::{CStr, OsStr},
     },
     self::super::{
         x11_sys,
@@ -25,6 +25,7 @@ use {
 pub struct XlibWindow {
     pub window: Option<c_ulong>,
     pub xic: Option<x11_sys::XIC>,
+    pub xic_style: x11_sys::XIMStyle,
     pub attributes: Option<x11_sys::XSetWindowAttributes>,
     pub visual_info: Option<x11_sys::XVisualInfo>,
     //pub child_windows: Vec<XlibChildWindow>,
@@ -34,6 +35,7 @@ pub struct XlibWindow {
     pub last_window_geom: WindowGeom,
     
     pub ime_spot: DVec2,
+    pub ime_preedit: String,
     pub current_cursor: MouseCursor,
     pub last_mouse_pos: DVec2,
 }
@@ -55,6 +57,7 @@ impl XlibWindow {
         XlibWindow {
             window: None,
             xic: None,
+            xic_style: 0,
             attributes: None,
             visual_info: None,
             //child_windows: Vec::new(),
@@ -62,6 +65,7 @@ impl XlibWindow {
             last_window_geom: WindowGeom::default(),
             last_nc_mode: None,
             ime_spot: DVec2::default(),
+            ime_preedit: String::new(),
             current_cursor: MouseCursor::Default,
             last_mouse_pos: DVec2::default(),
         }
@@ -186,24 +190,13 @@ impl XlibWindow {
                 x11_sys::XFlush(display);
             }
             
-            let xic = x11_sys::XCreateIC(
-                get_xlib_app_global().xim,
-                x11_sys::XNInputStyle.as_ptr(),
-                (x11_sys::XIMPreeditNothing | x11_sys::XIMStatusNothing) as i32,
-                x11_sys::XNClientWindow.as_ptr(),
-                window,
-                x11_sys::XNFocusWindow.as_ptr(),
-                window,
-                ptr::null_mut() as *mut c_void
-            );
-            
             // Create a window
             get_xlib_app_global().window_map.insert(window, self);
             
             self.attributes = Some(attributes);
             self.visual_info = Some(visual_info);
             self.window = Some(window);
-            self.xic = Some(xic);
+            self.xic = self.create_ic(window);
             self.last_window_geom = self.get_window_geom();
             
             let new_geom = self.get_window_geom();
@@ -256,6 +249,9 @@ impl XlibWindow {
     
     pub fn close_window(&mut self) {
         unsafe {
+            if let Some(xic) = self.xic.take() {
+                x11_sys::XDestroyIC(xic);
+            }
             x11_sys::XDestroyWindow(get_xlib_app_global().display, self.window.unwrap());
             self.window = None;
             // lets remove us from the mapping
@@ -333,8 +329,158 @@ impl XlibWindow {
         maximized
     }
     
+    /// Creates the input context, preferring on-the-spot preedit where we render the
+    /// composition ourselves, then over-the-spot where the input method draws it at the spot.
+    unsafe fn create_ic(&mut self, window: c_ulong) -> Option<x11_sys::XIC> {
+        let xim = get_xlib_app_global().xim;
+        if xim.is_null() {
+            return None
+        }
+        let mut styles: *mut x11_sys::XIMStyles = ptr::null_mut();
+        let mut supported = Vec::new();
+        if x11_sys::XGetIMValues(xim, x11_sys::XNQueryInputStyle.as_ptr(), &mut styles, ptr::null_mut::<c_void>()).is_null() && !styles.is_null() {
+            supported.extend_from_slice(std::slice::from_raw_parts((*styles).supported_styles, (*styles).count_styles as usize));
+            x11_sys::XFree(styles as *mut c_void);
+        }
+        let preferred = [
+            x11_sys::XIMPreeditCallbacks | x11_sys::XIMStatusNothing,
+            x11_sys::XIMPreeditCallbacks | x11_sys::XIMStatusNone,
+            x11_sys::XIMPreeditPosition | x11_sys::XIMStatusNothing,
+            x11_sys::XIMPreeditPosition | x11_sys::XIMStatusNone,
+            x11_sys::XIMPreeditNothing | x11_sys::XIMStatusNothing,
+            x11_sys::XIMPreeditNone | x11_sys::XIMStatusNone,
+        ];
+        
+        // Xlib copies the callback structs, so they only have to live for the XCreateIC call
+        let client_data = self as *mut XlibWindow as x11_sys::XPointer;
+        let start = x11_sys::XIMCallback {client_data, callback: xim_preedit_start as *mut c_void};
+        let done = x11_sys::XIMCallback {client_data, callback: xim_preedit_done as *mut c_void};
+        let draw = x11_sys::XIMCallback {client_data, callback: xim_preedit_draw as *mut c_void};
+        let caret = x11_sys::XIMCallback {client_data, callback: xim_preedit_caret as *mut c_void};
+        let spot = x11_sys::XPoint {x: 0, y: 0};
+        
+        for style in preferred.iter().map( | style | *style as x11_sys::XIMStyle) {
+            if supported.len() > 0 && !supported.contains(&style) {
+                continue
+            }
+            let preedit_attributes = if style & x11_sys::XIMPreeditCallbacks as x11_sys::XIMStyle != 0 {
+                x11_sys::XVaCreateNestedList(
+                    0,
+                    x11_sys::XNPreeditStartCallback.as_ptr(),
+                    &start,
+                    x11_sys::XNPreeditDoneCallback.as_ptr(),
+                    &done,
+                    x11_sys::XNPreeditDrawCallback.as_ptr(),
+                    &draw,
+                    x11_sys::XNPreeditCaretCallback.as_ptr(),
+                    &caret,
+                    ptr::null_mut::<c_void>()
+                )
+            }
+            else if style & x11_sys::XIMPreeditPosition as x11_sys::XIMStyle != 0 {
+                x11_sys::XVaCreateNestedList(0, x11_sys::XNSpotLocation.as_ptr(), &spot, ptr::null_mut::<c_void>())
+            }
+            else {
+                ptr::null_mut()
+            };
+            let xic = if preedit_attributes.is_null() {
+                x11_sys::XCreateIC(
+                    xim,
+                    x11_sys::XNInputStyle.as_ptr(),
+                    style,
+                    x11_sys::XNClientWindow.as_ptr(),
+                    window,
+                    x11_sys::XNFocusWindow.as_ptr(),
+                    window,
+                    ptr::null_mut::<c_void>()
+                )
+            }
+            else {
+                let xic = x11_sys::XCreateIC(
+                    xim,
+                    x11_sys::XNInputStyle.as_ptr(),
+                    style,
+                    x11_sys::XNClientWindow.as_ptr(),
+                    window,
+                    x11_sys::XNFocusWindow.as_ptr(),
+                    window,
+                    x11_sys::XNPreeditAttributes.as_ptr(),
+                    preedit_attributes,
+                    ptr::null_mut::<c_void>()
+                );
+                x11_sys::XFree(preedit_attributes);
+                xic
+            };
+            if !xic.is_null() {
+                self.xic_style = style;
+                return Some(xic)
+            }
+        }
+        None
+    }
+    
+    /// Moves the candidate window of the input method, `spot` is in logical window coordinates.
     pub fn set_ime_spot(&mut self, spot: DVec2) {
         self.ime_spot = spot;
+        let Some(xic) = self.xic else {return};
+        if self.xic_style & (x11_sys::XIMPreeditPosition | x11_sys::XIMPreeditCallbacks) as x11_sys::XIMStyle == 0 {
+            return
+        }
+        let dpi_factor = self.last_window_geom.dpi_factor;
+        let spot = x11_sys::XPoint {
+            x: (spot.x * dpi_factor) as i16,
+            y: (spot.y * dpi_factor) as i16
+        };
+        unsafe {
+            let preedit_attributes = x11_sys::XVaCreateNestedList(0, x11_sys::XNSpotLocation.as_ptr(), &spot, ptr::null_mut::<c_void>());
+            x11_sys::XSetICValues(xic, x11_sys::XNPreeditAttributes.as_ptr(), preedit_attributes, ptr::null_mut::<c_void>());
+            x11_sys::XFree(preedit_attributes);
+        }
+    }
+    
+    pub fn set_ime_focus(&mut self, focus: bool) {
+        if let Some(xic) = self.xic {
+            unsafe {
+                if focus {
+                    x11_sys::XSetICFocus(xic);
+                }
+                else {
+                    x11_sys::XUnsetICFocus(xic);
+                }
+            }
+        }
+    }
+    
+    /// Drops a pending composition, for instance when the text input loses focus.
+    pub fn reset_ime(&mut self) {
+        if let Some(xic) = self.xic {
+            unsafe {
+                let pending = x11_sys::Xutf8ResetIC(xic);
+                if !pending.is_null() {
+                    x11_sys::XFree(pending as *mut c_void);
+                }
+            }
+        }
+        self.ime_preedit.clear();
+    }
+    
+    /// Shows the current composition in the focused text input. Each update replaces the
+    /// previous one through `replace_last`, an empty string removes it again.
+    pub fn set_ime_preedit(&mut self, preedit: String) {
+        if preedit == self.ime_preedit {
+            return
+        }
+        let replace_last = !self.ime_preedit.is_empty();
+        self.ime_preedit = preedit.clone();
+        self.send_text_input(preedit, replace_last);
+    }
+    
+    /// Called when the input method commits text, returns true if the commit
+    /// has to replace a composition we are still showing.
+    pub fn take_ime_preedit(&mut self) -> bool {
+        let had_preedit = !self.ime_preedit.is_empty();
+        self.ime_preedit.clear();
+        had_preedit
     }
     
     pub fn get_position(&self) -> DVec2 {
@@ -508,6 +654,52 @@ impl XlibWindow {
     
 }
 
+unsafe extern "C" fn xim_preedit_start(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, _call_data: x11_sys::XPointer) -> c_int {
+    let window = &mut *(client_data as *mut XlibWindow);
+    window.ime_preedit.clear();
+    // no limit on the composition length
+    -1
+}
+
+unsafe extern "C" fn xim_preedit_done(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, _call_data: x11_sys::XPointer) {
+    let window = &mut *(client_data as *mut XlibWindow);
+    window.set_ime_preedit(String::new());
+}
+
+unsafe extern "C" fn xim_preedit_draw(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, call_data: x11_sys::XPointer) {
+    let window = &mut *(client_data as *mut XlibWindow);
+    let draw = &*(call_data as *mut x11_sys::XIMPreeditDrawCallbackStruct);
+    
+    // replace chg_length chars at chg_first with the new text
+    let inserted: Vec<char> = if draw.text.is_null() {
+        Vec::new()
+    }
+    else {
+        let text = &*draw.text;
+        if text.encoding_is_wchar != 0 {
+            if text.string.wide_char.is_null() {
+                return // only the feedback (highlighting) changed
+            }
+            std::slice::from_raw_parts(text.string.wide_char, text.length as usize).iter()
+                .filter_map( | c | char::from_u32(*c as u32)).collect()
+        }
+        else {
+            if text.string.multi_byte.is_null() {
+                return
+            }
+            CStr::from_ptr(text.string.multi_byte).to_string_lossy().chars().collect()
+        }
+    };
+    let mut chars: Vec<char> = window.ime_preedit.chars().collect();
+    let first = (draw.chg_first.max(0) as usize).min(chars.len());
+    let end = (first + draw.chg_length.max(0) as usize).min(chars.len());
+    chars.splice(first..end, inserted);
+    window.set_ime_preedit(chars.into_iter().collect());
+}
+
+unsafe extern "C" fn xim_preedit_caret(_xic: x11_sys::XIC, _client_data: x11_sys::XPointer, _call_data: x11_sys::XPointer) {
+    // the text input draws its own cursor at the end of the composition
+}
 
 #[derive(Clone, Copy, PartialEq)]
 #[repr(C)]
@@ -545,274 +737,3 @@ pub const _NET_WM_STATE_ADD: c_long = 1;/* add/set property */
 pub const _NET_WM_STATE_TOGGLE: c_long = 2;/* toggle property  */
 
 /* move via keyboard */
-
-pub struct Dnd {
-    pub atoms: DndAtoms,
-    pub display: *mut x11_sys::Display,
-    pub type_list: Option<Vec<x11_sys::Atom >>,
-    pub selection: Option<CString>,
-}
-
-impl Dnd {
-    pub unsafe fn new(display: *mut x11_sys::Display) -> Dnd {
-        Dnd {
-            atoms: DndAtoms::new(display),
-            display,
-            type_list: None,
-            selection: None,
-        }
-    }
-    
-    /// Enables drag-and-drop for the given window.
-    pub unsafe fn enable_for_window(&mut self, window: x11_sys::Window) {
-        // To enable drag-and-drop for a window, we need to set the XDndAware property of the window
-        // to the version of XDnd we support.
-        
-        // I took this value from the Winit source code. Apparently, this is the latest version, and
-        // hasn't changed since 2002.
-        let version = 5 as c_ulong;
-        
-        x11_sys::XChangeProperty(
-            self.display,
-            window,
-            self.atoms.aware,
-            4, // XA_ATOM
-            32,
-            x11_sys::PropModeReplace as std::os::raw::c_int,
-            &version as *const c_ulong as *const std::os::raw::c_uchar,
-            1
-        );
-    }
-    
-    /// Handles a XDndEnter event.
-    pub unsafe fn handle_enter_event(&mut self, event: &x11_sys::XClientMessageEvent) {
-        // The XDndEnter event is sent by the source window when a drag begins. That is, the mouse
-        // enters the client rectangle of the target window. The target window is supposed to
-        // respond to this by requesting the list of types supported by the source.
-        
-        let source_window = event.data.l[0] as x11_sys::Window;
-        let has_more_types = event.data.l[1] & (1 << 0) != 0;
-        
-        // If the has_more_types flags is set, we have to obtain the list of supported types from
-        // the XDndTypeList property. Otherwise, we can obtain the list of supported types from the
-        // event itself.
-        self.type_list = Some(if has_more_types {
-            self.get_type_list_property(source_window)
-        } else {
-            event.data.l[2..4]
-                .iter()
-                .map( | &l | l as x11_sys::Atom)
-                .filter( | &atom | atom != x11_sys::None as x11_sys::Atom)
-                .collect()
-        });
-    }
-    
-    /// Handles a XDndDrop event.
-    pub unsafe fn handle_drop_event(&mut self, event: &x11_sys::XClientMessageEvent) {
-        // The XDndLeave event is sent by the source window when a drag is confirmed. That is, the
-        // mouse button is released while the mouse is inside the client rectangle of the target
-        // window. The target window is supposed to respond to this by requesting that the selection
-        // representing the thing being dragged is converted to the appropriate data type (in our
-        // case, a URI list). The source window, in turn, is supposed to respond this by sending a
-        // selection event containing the data to the source window.
-        
-        let target_window = event.window as x11_sys::Window;
-        self.convert_selection(target_window);
-        self.type_list = None;
-    }
-    
-    /// Handles a XDndLeave event.
-    pub unsafe fn handle_leave_event(&mut self, _event: &x11_sys::XClientMessageEvent) {
-        // The XDndLeave event is sent by the source window when a drag is canceled. That is, the
-        // mouse leaves the client rectangle of the target window. The target window is supposed to
-        // repsond this this by pretending the drag never happened.
-        
-        self.type_list = None;
-    }
-    
-    /// Handles a XDndPosition event.
-    pub unsafe fn handle_position_event(&mut self, event: &x11_sys::XClientMessageEvent) {
-        // The XDndPosition event is sent by the source window after the XDndEnter event, every time
-        // the mouse is moved. The target window is supposed to respond to this by sending a status
-        // event to the source window notifying whether it can accept the drag at this position.
-        
-        let target_window = event.window as x11_sys::Window;
-        let source_window = event.data.l[0] as x11_sys::Window;
-        
-        // For now we accept te drag if and only if the list of types supported by the source
-        // includes a uri list.
-        //
-        // TODO: Extend this test by taking into account the position of the mouse as well.
-        let accepted = self.type_list.as_ref().map_or(false, | type_list | type_list.contains(&self.atoms.uri_list));
-        
-        // Notify the source window whether we can accept the drag at this position.
-        self.send_status_event(source_window, target_window, accepted);
-        
-        // If this is the first time we've accepted the drag, request that the drag-and-drop
-        // selection be converted to a URI list. The target window is supposed to respond to this by
-        // sending a XSelectionEvent containing the URI list.
-        
-        // Since this is an asynchronous operation, its possible for another XDndPosition event to
-        // come in before the response to the first conversion request has been received. In this
-        // case, a second conversion request will be sent, the response to which will be ignored.
-        if accepted && self.selection.is_none() {
-        }
-    }
-    
-    /// Handles a XSelectionEvent.
-    pub unsafe fn handle_selection_event(&mut self, _event: &x11_sys::XSelectionEvent) {
-        // The XSelectionEvent is sent by the source window in response to a request by the source
-        // window to convert the selection representing the thing being dragged to the appropriate
-        // data type. This request is always sent in response to a XDndDrop event, so this event
-        // should only be received after a drop operation has completed.
-        
-        //let source_window = event.requestor;
-        //let selection = CString::new(self.get_selection_property(source_window)).unwrap();
-        
-        // TODO: Actually use the selection
-    }
-    
-    /// Gets the XDndSelection property from the source window.
-    pub unsafe fn get_selection_property(&mut self, source_window: x11_sys::Window) -> Vec< std::os::raw::c_uchar> {
-        let mut selection = Vec::new();
-        let mut offset = 0;
-        let length = 1024;
-        let mut actual_type = 0;
-        let mut actual_format = 0;
-        let mut nitems = 0;
-        let mut bytes_after = 0;
-        let mut prop = ptr::null_mut();
-        loop {
-            x11_sys::XGetWindowProperty(
-                self.display,
-                source_window,
-                self.atoms.selection,
-                offset,
-                length,
-                x11_sys::False as std::os::raw::c_int,
-                self.atoms.uri_list,
-                &mut actual_type,
-                &mut actual_format,
-                &mut nitems,
-                &mut bytes_after,
-                &mut prop,
-            );
-            selection.extend_from_slice(std::slice::from_raw_parts(prop as *mut  std::os::raw::c_uchar, nitems as usize));
-            x11_sys::XFree(prop as *mut c_void);
-            if bytes_after == 0 {
-                break;
-            }
-            offset += length;
-        };
-        selection
-    }
-    
-    /// Gets the XDndTypeList property from the source window.
-    pub unsafe fn get_type_list_property(&mut self, source_window: x11_sys::Window) -> Vec<x11_sys::Atom> {
-        let mut type_list = Vec::new();
-        let mut offset = 0;
-        let length = 1024;
-        let mut actual_type = 0;
-        let mut actual_format = 0;
-        let mut nitems = 0;
-        let mut bytes_after = 0;
-        let mut prop = ptr::null_mut();
-        loop {
-            x11_sys::XGetWindowProperty(
-                self.display,
-                source_window,
-                self.atoms.type_list,
-                offset,
-                length,
-                x11_sys::False as  std::os::raw::c_int,
-                4, // XA_ATOM,
-                &mut actual_type,
-                &mut actual_format,
-                &mut nitems,
-                &mut bytes_after,
-                &mut prop,
-            );
-            type_list.extend_from_slice(std::slice::from_raw_parts(prop as *mut x11_sys::Atom, nitems as usize));
-            x11_sys::XFree(prop as *mut c_void);
-            if bytes_after == 0 {
-                break;
-            }
-            offset += length;
-        };
-        type_list
-    }
-    
-    /// Sends a XDndStatus event to the target window.
-    pub unsafe fn send_status_event(&mut self, source_window: x11_sys::Window, target_window: x11_sys::Window, accepted: bool) {
-        x11_sys::XSendEvent(
-            self.display,
-            source_window,
-            x11_sys::False as  std::os::raw::c_int,
-            x11_sys::NoEventMask as  std::os::raw::c_long,
-            &mut x11_sys::XClientMessageEvent {
-                type_: x11_sys::ClientMessage as  std::os::raw::c_int,
-                serial: 0,
-                send_event: 0,
-                display: self.display,
-                window: source_window,
-                message_type: self.atoms.status,
-                format: 32,
-                data: {
-                    let mut data = mem::zeroed::<x11_sys::XClientMessageEvent__bindgen_ty_1>();
-                    data.l[0] = target_window as c_long;
-                    data.l[1] = if accepted {1 << 0} else {0};
-                    data.l[2] = 0;
-                    data.l[3] = 0;
-                    data.l[4] = if accepted {self.atoms.action_private} else {self.atoms.none} as c_long;
-                    data
-                }
-            } as *mut x11_sys::XClientMessageEvent as *mut x11_sys::XEvent
-        );
-        x11_sys::XFlush(self.display);
-    }
-    
-    // Requests that the selection representing the thing being dragged is converted to the
-    // appropriate data type (in our case, a URI list).
-    pub unsafe fn convert_selection(&self, target_window: x11_sys::Window) {
-        x11_sys::XConvertSelection(
-            self.display,
-            self.atoms.selection,
-            self.atoms.uri_list,
-            self.atoms.selection,
-            target_window,
-            x11_sys::CurrentTime as x11_sys::Time,
-        );
-    }
-}
-
-pub struct DndAtoms {
-    pub action_private: x11_sys::Atom,
-    pub aware: x11_sys::Atom,
-    pub drop: x11_sys::Atom,
-    pub enter: x11_sys::Atom,
-    pub leave: x11_sys::Atom,
-    pub none: x11_sys::Atom,
-    pub position: x11_sys::Atom,
-    pub selection: x11_sys::Atom,
-    pub status: x11_sys::Atom,
-    pub type_list: x11_sys::Atom,
-    pub uri_list: x11_sys::Atom,
-}
-
-impl DndAtoms {
-    pub unsafe fn new(display: *mut x11_sys::Display) -> DndAtoms {
-        DndAtoms {
-            action_private: x11_sys::XInternAtom(display, "XdndActionPrivate\0".as_ptr() as *const _, 0),
-            aware: x11_sys::XInternAtom(display, "XdndAware\0".as_ptr() as *const _, 0),
-            drop: x11_sys::XInternAtom(display, "XdndDrop\0".as_ptr() as *const _, 0),
-            enter: x11_sys::XInternAtom(display, "XdndEnter\0".as_ptr() as *const _, 0),
-            leave: x11_sys::XInternAtom(display, "XdndLeave\0".as_ptr() as *const _, 0),
-            none: x11_sys::XInternAtom(display, "None\0".as_ptr() as *const _, 0),
-            position: x11_sys::XInternAtom(display, "XdndPosition\0".as_ptr() as *const _, 0),
-            selection: x11_sys::XInternAtom(display, "XdndSelection\0".as_ptr() as *const _, 0),
-            status: x11_sys::XInternAtom(display, "XdndStatus\0".as_ptr() as *const _, 0),
-            type_list: x11_sys::XInternAtom(display, "XdndTypeList\0".as_ptr() as *const _, 0),
-            uri_list: x11_sys::XInternAtom(display, "text/uri-list\0".as_ptr() as *const _, 0),
-        }
-    }
-}
@@ -0,0 +1,226 @@
+use {
+    std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc::{channel, Sender, Receiver},
+        },
+    },
+    self::super::dbus::{DBusConnection, DBusMessage, DBusValue},
+    crate::{
+        thread::SignalToUI,
+        file_dialogs::{FileDialog, Filter, FileDialogKind, FileDialogResponse, FileDialogEvent},
+    }
+};
+
+// File dialogs through org.freedesktop.portal.FileChooser. The portal works on
+// any desktop (and inside flatpak) and lets the desktop environment show its own
+// dialog. Every dialog runs on its own thread and bus connection since the portal
+// only answers once the user closes it.
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const FILE_CHOOSER_INTERFACE: &str = "org.freedesktop.portal.FileChooser";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+static HANDLE_TOKEN: AtomicUsize = AtomicUsize::new(0);
+
+pub struct LinuxFileDialogs {
+    sender: Sender<FileDialogEvent>,
+    receiver: Receiver<FileDialogEvent>,
+}
+
+impl Default for LinuxFileDialogs {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {sender, receiver}
+    }
+}
+
+impl LinuxFileDialogs {
+    /// Shows a dialog, `parent_window` is a portal window identifier like `x11:1a00003`
+    /// or empty. The result is picked up with `try_recv`.
+    pub fn open_file_dialog(&mut self, kind: FileDialogKind, settings: FileDialog, parent_window: String) {
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let response = match run_file_chooser(kind, &settings, &parent_window) {
+                Ok(response) => response,
+                Err(err) => FileDialogResponse::Error(err)
+            };
+            let _ = sender.send(FileDialogEvent {kind, response});
+            SignalToUI::set_ui_signal();
+        });
+    }
+
+    pub fn try_recv(&self) -> Option<FileDialogEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+fn run_file_chooser(kind: FileDialogKind, settings: &FileDialog, parent_window: &str) -> Result<FileDialogResponse, String> {
+    let mut connection = DBusConnection::session()?;
+    let handle_token = format!("makepad{}", HANDLE_TOKEN.fetch_add(1, Ordering::SeqCst));
+
+    // subscribe before calling so a fast response can't be missed
+    connection.add_match(&format!(
+        "type='signal',interface='{}',member='Response'",
+        REQUEST_INTERFACE
+    ))?;
+
+    let (method, default_title) = match kind {
+        FileDialogKind::SaveFile => ("SaveFile", "Save File"),
+        FileDialogKind::SelectFile => ("OpenFile", "Open File"),
+        FileDialogKind::SaveFolder => ("OpenFile", "Save to Folder"),
+        FileDialogKind::SelectFolder => ("OpenFile", "Select Folder"),
+    };
+
+    let mut options = vec![
+        ("handle_token", DBusValue::string(&handle_token)),
+        ("modal", DBusValue::Bool(true)),
+    ];
+    match kind {
+        FileDialogKind::SaveFile => {
+            if let Some(filename) = &settings.filename {
+                options.push(("current_name", DBusValue::string(filename)));
+            }
+        }
+        FileDialogKind::SelectFile => {
+            options.push(("multiple", DBusValue::Bool(false)));
+        }
+        FileDialogKind::SaveFolder => {
+            options.push(("directory", DBusValue::Bool(true)));
+            options.push(("accept_label", DBusValue::string("Save")));
+        }
+        FileDialogKind::SelectFolder => {
+            options.push(("directory", DBusValue::Bool(true)));
+        }
+    }
+    if let Some(location) = &settings.location {
+        options.push(("current_folder", DBusValue::byte_string(&location.to_string_lossy())));
+    }
+    if settings.filters.len() > 0 && matches!(kind, FileDialogKind::SaveFile | FileDialogKind::SelectFile) {
+        options.push(("filters", filters_value(&settings.filters)));
+    }
+
+    let title = settings.title.as_deref().unwrap_or(default_title);
+    let reply = connection.call(DBusMessage::method_call(
+        PORTAL_DESTINATION,
+        PORTAL_PATH,
+        FILE_CHOOSER_INTERFACE,
+        method,
+        vec![
+            DBusValue::string(parent_window),
+            DBusValue::string(title),
+            DBusValue::dict(options),
+        ]
+    ))?;
+    let handle = reply.body.first().and_then( | v | v.as_str()).unwrap_or("").to_string();
+
+    loop {
+        let message = connection.read_message()?;
+        if !message.is_signal(REQUEST_INTERFACE, "Response") || message.path.as_deref() != Some(&handle) {
+            continue
+        }
+        return match message.body.first().and_then( | v | v.as_u32()) {
+            Some(0) => {
+                let uris = message.body.get(1)
+                    .and_then( | results | results.dict_get("uris"))
+                    .and_then( | uris | uris.as_array())
+                    .unwrap_or(&[]);
+                Ok(FileDialogResponse::Selected(
+                    uris.iter().filter_map( | uri | uri.as_str()).filter_map(uri_to_path).collect()
+                ))
+            }
+            Some(1) => Ok(FileDialogResponse::Cancelled),
+            _ => Err("File chooser portal request failed".to_string())
+        }
+    }
+}
+
+// a(sa(us)), pattern type 0 is a glob
+fn filters_value(filters: &[Filter]) -> DBusValue {
+    DBusValue::Array("(sa(us))".to_string(), filters.iter().map( | filter | {
+        DBusValue::Struct(vec![
+            DBusValue::string(&filter.description),
+            DBusValue::Array("(us)".to_string(), filter.extensions.iter().map( | ext | {
+                DBusValue::Struct(vec![
+                    DBusValue::Uint32(0),
+                    DBusValue::String(format!("*.{}", ext.trim_start_matches('.')))
+                ])
+            }).collect())
+        ])
+    }).collect())
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // skip an optional host part, file://localhost/path
+    let path = &path[path.find('/')?..];
+    use std::os::unix::ffi::OsStringExt;
+    Some(PathBuf::from(std::ffi::OsString::from_vec(percent_decode(path))))
+}
+
+/// Decodes the %XX escapes of a uri path, malformed escapes are kept as is.
+pub(crate) fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(b) = std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then( | hex | u8::from_str_radix(hex, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_signature() {
+        let filters = filters_value(&[
+            Filter {description: "Images".to_string(), extensions: vec!["png".to_string(), ".jpg".to_string()]},
+            Filter {description: "Empty".to_string(), extensions: vec![]},
+        ]);
+        assert_eq!(filters.signature(), "a(sa(us))");
+        let filters = filters.as_array().unwrap();
+        assert_eq!(filters[0], DBusValue::Struct(vec![
+            DBusValue::string("Images"),
+            DBusValue::Array("(us)".to_string(), vec![
+                DBusValue::Struct(vec![DBusValue::Uint32(0), DBusValue::string("*.png")]),
+                DBusValue::Struct(vec![DBusValue::Uint32(0), DBusValue::string("*.jpg")]),
+            ])
+        ]));
+        assert_eq!(filters[1], DBusValue::Struct(vec![
+            DBusValue::string("Empty"),
+            DBusValue::Array("(us)".to_string(), vec![])
+        ]));
+    }
+
+    #[test]
+    fn uri_percent_decoding() {
+        assert_eq!(uri_to_path("file:///home/user/My%20File%2Etxt"), Some(PathBuf::from("/home/user/My File.txt")));
+        assert_eq!(uri_to_path("file:///tmp/100%25"), Some(PathBuf::from("/tmp/100%")));
+        // not an escape, kept as is
+        assert_eq!(uri_to_path("file:///tmp/a%zzb%4"), Some(PathBuf::from("/tmp/a%zzb%4")));
+        // paths are bytes, not necessarily utf8
+        use std::os::unix::ffi::OsStringExt;
+        assert_eq!(uri_to_path("file:///tmp/%FF"), Some(PathBuf::from(std::ffi::OsString::from_vec(b"/tmp/\xff".to_vec()))));
+    }
+
+    #[test]
+    fn uri_host() {
+        assert_eq!(uri_to_path("file://localhost/etc/hosts"), Some(PathBuf::from("/etc/hosts")));
+        assert_eq!(uri_to_path("file://localhost/a%20b"), Some(PathBuf::from("/a b")));
+        assert_eq!(uri_to_path("file:///"), Some(PathBuf::from("/")));
+        assert_eq!(uri_to_path("file://localhost"), None);
+        assert_eq!(uri_to_path("https://example.com/file"), None);
+    }
+}
@@ -1,6 +1,6 @@
 
 use crate::event::HttpRequest;
-use crate::web_socket::{WebSocketMessage};
+use crate::web_socket::{WebSocketMessage, WebSocketCloseReason};
 use std::sync::mpsc::{Sender};
 use std::sync::Mutex;
 use std::cell::RefCell;
@@ -27,7 +27,7 @@ pub unsafe extern "C" fn wasm_web_socket_closed(id: u32) {
         let mut list = list.borrow_mut();
         if let Some(index) = list.iter().position(|v| v.0 == id){
             let item = list.remove(index);
-            let _ = item.1.send(WebSocketMessage::Closed);
+            let _ = item.1.send(WebSocketMessage::Closed(WebSocketCloseReason::no_status()));
             SignalToUI::set_ui_signal();
         }
     }
@@ -1,6 +1,6 @@
 
 use crate::event::HttpRequest;
-use crate::web_socket::{WebSocketMessage};
+use crate::web_socket::{WebSocketMessage, WebSocketCloseReason};
 use crate::thread::SignalToUI;
 use std::sync::mpsc::{channel, Sender};
 use std::net::TcpStream;
@@ -116,7 +116,7 @@ impl OsWebSocket{
                                     //println!("binary!");
                                 },
                                 Ok(ServerWebSocketMessage::Close) => {
-                                    let _ = rx_sender.send(WebSocketMessage::Closed);
+                                    let _ = rx_sender.send(WebSocketMessage::Closed(WebSocketCloseReason::no_status()));
                                     SignalToUI::set_ui_signal();
                                     done = true;
                                 },
@@ -49,7 +49,29 @@ pub enum WebSocketMessage{
     Binary(Vec<u8>),
     String(String),
     Opened,
-    Closed
+    Closed(WebSocketCloseReason)
+}
+
+/// The close code and reason the server sent, see RFC 6455 section 7.4
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebSocketCloseReason{
+    pub code: u16,
+    pub reason: String,
+}
+
+impl WebSocketCloseReason{
+    pub fn new(code: u16, reason: &str)->Self{
+        Self{code, reason: reason.to_string()}
+    }
+    
+    /// For platforms that don't tell us why the socket closed
+    pub fn no_status()->Self{
+        Self{code: 1005, reason: String::new()}
+    }
+    
+    pub fn is_normal(&self)->bool{
+        self.code == 1000 || self.code == 1005
+    }
 }
 
 pub (crate) static WEB_SOCKET_THREAD_SENDER: Mutex<Option<Sender<WebSocketThreadMsg>>> = Mutex::new(None);
@@ -70,6 +70,13 @@ impl CxWindowPool {
         false
     }
     
+    pub fn created_window_ids(&self)->Vec<WindowId>{
+        self.0.pool.iter().enumerate()
+            .filter(|(_, item)| item.item.is_created)
+            .map(|(index, item)| WindowId(index, item.generation))
+            .collect()
+    }
+    
     pub fn id_zero()->WindowId{
         WindowId(0, 0)
     }
@@ -47,7 +47,56 @@ pub enum FileRequest {
         id: u64,
         patch: bool
     },
+    /// Requests the server to create an empty file. Fails if the path already exists.
+    CreateFile{
+        path: String
+    },
+    /// Requests the server to create a directory. Fails if the path already exists.
+    CreateDirectory{
+        path: String
+    },
+    /// Requests the server to rename or move a file or directory. Fails if the new path already
+    /// exists.
+    RenameFile{
+        old_path: String,
+        new_path: String
+    },
+    /// Requests the server to delete a file, or a directory with everything in it.
+    DeleteFile{
+        path: String
+    },
+    /// Requests the server to search the text of every file in the tree. Results are sent back
+    /// incrementally as `SearchResults` notifications, followed by `SearchFinished`. Starting a
+    /// search cancels the previous one.
+    Search(SearchRequest),
+}
+
+/// A project-wide text search.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct SearchRequest{
+    pub id: u64,
+    pub query: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Globs like `*.rs` or `src/**`. If not empty, only files that match one of them are searched.
+    /// Globs without a `/` match the file name, the others the path from the root.
+    pub include: Vec<String>,
+    /// Globs for files and directories that are skipped, in addition to the ones in `.gitignore`.
+    pub exclude: Vec<String>,
+}
 
+/// A line that contains a match of a search.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct SearchResult{
+    pub path: String,
+    pub line: u64,
+    /// The byte index of the match in the line.
+    pub column: u64,
+    /// The text of the line, which is cut short around the match for long lines.
+    pub line_text: String,
+    /// The byte range of the match within `line_text`.
+    pub start: u64,
+    pub end: u64,
 }
 
 /// A type for representing either a response or a notification from the collab server.
@@ -75,6 +124,12 @@ pub struct SaveFileResponse{
     pub id: u64, 
 }
 
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct RenameFileResponse{
+    pub old_path: String,
+    pub new_path: String,
+}
+
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub struct OpenFileResponse{
     pub path: String, 
@@ -94,8 +149,15 @@ pub enum FileResponse {
     /// The result of requesting the collab server to apply a delta to a revision of the file with
     /// the given id.
     SaveFile(Result<SaveFileResponse, FileError>),
-    
-    // Existing variants...
+    /// The path of the created file.
+    CreateFile(Result<String, FileError>),
+    /// The path of the created directory.
+    CreateDirectory(Result<String, FileError>),
+    RenameFile(Result<RenameFileResponse, FileError>),
+    /// The path of the deleted file or directory.
+    DeleteFile(Result<String, FileError>),
+    /// The id of the search that was started.
+    Search(Result<u64, FileError>),
 }
 
 /// A type for representing data about a file tree.
@@ -131,6 +193,10 @@ pub struct DirectoryEntry {
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileNotification {
     FileChangedOnDisk(SaveFileResponse),
+    /// The matches of a search in a single file.
+    SearchResults{id: u64, results: Vec<SearchResult>},
+    /// A search went through all files, or hit the maximum number of results.
+    SearchFinished{id: u64},
     // Notifies the client that another client applied the given delta to the file with the given
     // id. This is only sent for files for which the client is a participant.
    // DeltaWasApplied(TextFileId),
@@ -140,7 +206,10 @@ pub enum FileNotification {
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileError {
     Unknown(String),
-    CannotOpen(String)
+    CannotOpen(String),
+    AlreadyExists(String),
+    /// The path is absolute or goes outside of the root with `..`.
+    InvalidPath(String),
 }
 
 /// An identifier for files on the collab server.
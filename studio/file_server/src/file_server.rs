@@ -10,8 +10,11 @@ use {
             FileResponse,
             SaveKind,
             SaveFileResponse,
-            OpenFileResponse
+            OpenFileResponse,
+            RenameFileResponse,
+            SearchRequest,
         },
+        search,
     },
     std::{
         thread,
@@ -19,8 +22,8 @@ use {
         fmt,
         fs,
         time::Duration,
-        path::{Path, PathBuf},
-        sync::{Arc, RwLock, Mutex},
+        path::{Component, Path, PathBuf},
+        sync::{Arc, RwLock, Mutex, atomic::{AtomicU64, Ordering as AtomicOrdering}},
     },
 };
 
@@ -54,7 +57,8 @@ impl FileServer {
             shared: self.shared.clone(),
             _notification_sender: notification_sender,
             open_files: Default::default(),
-            stop_observation: Default::default()
+            stop_observation: Default::default(),
+            search_generation: Default::default(),
         }
     }
 }
@@ -69,6 +73,8 @@ pub struct FileServerConnection {
     _notification_sender: Box<dyn NotificationSender>,
     open_files: Arc<Mutex<Vec<(String, u64, Vec<u8>)>>>,
     stop_observation: Arc<Mutex<bool>>,
+    // The id of the running search. Searches stop as soon as this changes.
+    search_generation: Arc<AtomicU64>,
 }
 
 impl FileServerConnection {
@@ -83,6 +89,11 @@ impl FileServerConnection {
             FileRequest::LoadFileTree {with_data} => FileResponse::LoadFileTree(self.load_file_tree(with_data)),
             FileRequest::OpenFile{path,id} => FileResponse::OpenFile(self.open_file(path, id)),
             FileRequest::SaveFile{path, data, id, patch} => FileResponse::SaveFile(self.save_file(path, data, id, patch)),
+            FileRequest::CreateFile{path} => FileResponse::CreateFile(self.create_file(path)),
+            FileRequest::CreateDirectory{path} => FileResponse::CreateDirectory(self.create_directory(path)),
+            FileRequest::RenameFile{old_path, new_path} => FileResponse::RenameFile(self.rename_file(old_path, new_path)),
+            FileRequest::DeleteFile{path} => FileResponse::DeleteFile(self.delete_file(path)),
+            FileRequest::Search(request) => FileResponse::Search(self.start_search(request)),
         }
     }
     
@@ -173,6 +184,15 @@ impl FileServerConnection {
         path
     }
     
+    // Like `make_full_path`, but refuses paths that could point outside of the root.
+    fn make_checked_full_path(&self, child_path: &String) -> Result<PathBuf, FileError> {
+        let path = Path::new(child_path);
+        if child_path.is_empty() || !path.components().all(|component| matches!(component, Component::Normal(_))) {
+            return Err(FileError::InvalidPath(child_path.clone()))
+        }
+        Ok(self.make_full_path(child_path))
+    }
+    
     fn start_observation(&self) {
         let open_files = self.open_files.clone();
         let shared = self.shared.clone();
@@ -281,6 +301,96 @@ impl FileServerConnection {
             kind: if patch{SaveKind::Patch}else{SaveKind::Save}
         })
     }
+    
+    // Handles a `CreateFile` request.
+    fn create_file(&self, child_path: String) -> Result<String, FileError> {
+        let path = self.make_checked_full_path(&child_path) ?;
+        if path.exists() {
+            return Err(FileError::AlreadyExists(child_path))
+        }
+        fs::OpenOptions::new().write(true).create_new(true).open(&path).map_err(
+            | error | FileError::Unknown(error.to_string())
+        ) ?;
+        Ok(child_path)
+    }
+    
+    // Handles a `CreateDirectory` request.
+    fn create_directory(&self, child_path: String) -> Result<String, FileError> {
+        let path = self.make_checked_full_path(&child_path) ?;
+        if path.exists() {
+            return Err(FileError::AlreadyExists(child_path))
+        }
+        fs::create_dir(&path).map_err(
+            | error | FileError::Unknown(error.to_string())
+        ) ?;
+        Ok(child_path)
+    }
+    
+    // Handles a `RenameFile` request, which is also used to move files between directories.
+    fn rename_file(&self, old_child_path: String, new_child_path: String) -> Result<RenameFileResponse, FileError> {
+        let old_path = self.make_checked_full_path(&old_child_path) ?;
+        let new_path = self.make_checked_full_path(&new_child_path) ?;
+        if new_path.exists() {
+            return Err(FileError::AlreadyExists(new_child_path))
+        }
+        if new_path.starts_with(&old_path) {
+            return Err(FileError::InvalidPath(new_child_path))
+        }
+        // Hold the lock while renaming, so the observation thread doesn't see the file vanish.
+        let mut open_files = self.open_files.lock().unwrap();
+        fs::rename(&old_path, &new_path).map_err(
+            | error | FileError::Unknown(error.to_string())
+        ) ?;
+        for (path, _, _) in open_files.iter_mut() {
+            if let Some(rest) = child_path_rest(path, &old_child_path) {
+                *path = format!("{}{}", new_child_path, rest);
+            }
+        }
+        Ok(RenameFileResponse{
+            old_path: old_child_path,
+            new_path: new_child_path
+        })
+    }
+    
+    // Handles a `DeleteFile` request.
+    fn delete_file(&self, child_path: String) -> Result<String, FileError> {
+        let path = self.make_checked_full_path(&child_path) ?;
+        let mut open_files = self.open_files.lock().unwrap();
+        if path.is_dir() {
+            fs::remove_dir_all(&path)
+        }
+        else {
+            fs::remove_file(&path)
+        }.map_err(
+            | error | FileError::Unknown(error.to_string())
+        ) ?;
+        open_files.retain(|(path, _, _)| child_path_rest(path, &child_path).is_none());
+        Ok(child_path)
+    }
+    
+    // Handles a `Search` request. The results are sent as notifications from a separate thread.
+    fn start_search(&self, request: SearchRequest) -> Result<u64, FileError> {
+        let id = request.id;
+        self.search_generation.store(id, AtomicOrdering::Relaxed);
+        let root_path = self.shared.read().unwrap().root_path.clone();
+        let generation = self.search_generation.clone();
+        let notification_sender = self._notification_sender.clone();
+        thread::spawn(move || {
+            search::search(&root_path, request, generation, notification_sender);
+        });
+        Ok(id)
+    }
+}
+
+// If `path` is `parent_path` or lies inside of it, returns the part of `path` after it.
+fn child_path_rest<'a>(path: &'a str, parent_path: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(parent_path) ?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(rest)
+    }
+    else {
+        None
+    }
 }
 
 /// A trait for sending notifications over a connection.
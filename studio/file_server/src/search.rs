@@ -0,0 +1,576 @@
+use {
+    crate::{
+        makepad_file_protocol::{FileNotification, SearchRequest, SearchResult},
+        NotificationSender,
+    },
+    std::{
+        fs,
+        path::Path,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+    },
+};
+
+// A search stops once it found this many matches.
+const MAX_RESULTS: usize = 10_000;
+// Lines are cut off to about this many bytes around the match, so minified files don't flood the
+// results.
+const MAX_LINE_TEXT_LEN: usize = 256;
+const MAX_FILE_LEN: u64 = 16 * 1024 * 1024;
+
+/// Walks the directory tree below `root_path`, and sends the matches of `request` to
+/// `notification_sender`, one `SearchResults` notification per file. The search stops early as
+/// soon as `generation` no longer equals `request.id`, which is how a newer search cancels it.
+pub fn search(
+    root_path: &Path,
+    request: SearchRequest,
+    generation: Arc<AtomicU64>,
+    notification_sender: Box<dyn NotificationSender>,
+) {
+    let id = request.id;
+    let mut search = Search {
+        matcher: Matcher::new(&request.query, request.case_sensitive, request.whole_word),
+        include: request.include.iter().filter_map(|glob| Glob::new(glob)).collect(),
+        exclude: request.exclude.iter().filter_map(|glob| Glob::new(glob)).collect(),
+        id,
+        generation,
+        notification_sender,
+        result_count: 0,
+    };
+    if !request.query.is_empty() {
+        search.search_directory(root_path, "", &mut Vec::new());
+    }
+    if search.is_current() {
+        search
+            .notification_sender
+            .send_notification(FileNotification::SearchFinished { id });
+    }
+}
+
+struct Search {
+    matcher: Matcher,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    id: u64,
+    generation: Arc<AtomicU64>,
+    notification_sender: Box<dyn NotificationSender>,
+    result_count: usize,
+}
+
+impl Search {
+    fn is_current(&self) -> bool {
+        self.generation.load(Ordering::Relaxed) == self.id
+    }
+
+    // Returns false if the search should stop.
+    fn search_directory(&mut self, path: &Path, child_path: &str, ignores: &mut Vec<Gitignore>) -> bool {
+        let Ok(read_dir) = fs::read_dir(path) else {
+            return true;
+        };
+        let mut entries: Vec<_> = read_dir
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_type = entry.file_type().ok()?;
+                Some((entry.file_name().into_string().ok()?, entry.path(), file_type))
+            })
+            .collect();
+        entries.sort_by(|(name_a, ..), (name_b, ..)| name_a.cmp(name_b));
+
+        let pushed_gitignore = match fs::read_to_string(path.join(".gitignore")) {
+            Ok(text) => {
+                ignores.push(Gitignore::parse(child_path, &text));
+                true
+            }
+            Err(_) => false,
+        };
+
+        let mut keep_going = true;
+        for (name, entry_path, file_type) in entries {
+            if !self.is_current() || self.result_count >= MAX_RESULTS {
+                keep_going = false;
+                break;
+            }
+            // The file type doesn't follow symlinks, so symlinked directories are skipped rather
+            // than walked, as they can point back up the tree.
+            let is_dir = file_type.is_dir();
+            // Like the file tree, skip hidden files and the "target" directory.
+            if name.starts_with('.') || is_dir && name == "target" {
+                continue;
+            }
+            let entry_child_path = if child_path.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", child_path, name)
+            };
+            if is_ignored(ignores, &entry_child_path, is_dir)
+                || self.exclude.iter().any(|glob| glob.matches(&entry_child_path))
+            {
+                continue;
+            }
+            if is_dir {
+                if !self.search_directory(&entry_path, &entry_child_path, ignores) {
+                    keep_going = false;
+                    break;
+                }
+            } else if entry_path.is_file()
+                && (self.include.is_empty()
+                    || self.include.iter().any(|glob| glob.matches(&entry_child_path)))
+            {
+                self.search_file(&entry_path, entry_child_path);
+            }
+        }
+
+        if pushed_gitignore {
+            ignores.pop();
+        }
+        keep_going
+    }
+
+    fn search_file(&mut self, path: &Path, child_path: String) {
+        if fs::metadata(path).map_or(true, |metadata| metadata.len() > MAX_FILE_LEN) {
+            return;
+        }
+        let Ok(bytes) = fs::read(path) else {
+            return;
+        };
+        // Files with a nul byte near the start, or that aren't UTF-8, are taken to be binary.
+        if bytes[..bytes.len().min(8192)].contains(&0) {
+            return;
+        }
+        let Ok(text) = String::from_utf8(bytes) else {
+            return;
+        };
+        let mut results = Vec::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let mut start = 0;
+            while let Some((match_start, match_end)) = self.matcher.find_at(line, start) {
+                results.push(line_result(&child_path, line_index, line, match_start, match_end));
+                self.result_count += 1;
+                if self.result_count >= MAX_RESULTS {
+                    break;
+                }
+                start = match_end;
+            }
+            if self.result_count >= MAX_RESULTS {
+                break;
+            }
+        }
+        if !results.is_empty() && self.is_current() {
+            self.notification_sender.send_notification(FileNotification::SearchResults {
+                id: self.id,
+                results,
+            });
+        }
+    }
+}
+
+fn line_result(path: &str, line_index: usize, line: &str, start: usize, end: usize) -> SearchResult {
+    // Long lines are cut to a window around the match.
+    let (text_start, text_end) = if line.len() <= MAX_LINE_TEXT_LEN {
+        (0, line.len())
+    } else {
+        let mut text_start = start.saturating_sub(MAX_LINE_TEXT_LEN / 4);
+        while !line.is_char_boundary(text_start) {
+            text_start -= 1;
+        }
+        let mut text_end = (text_start + MAX_LINE_TEXT_LEN).max(end).min(line.len());
+        while !line.is_char_boundary(text_end) {
+            text_end += 1;
+        }
+        (text_start, text_end)
+    };
+    SearchResult {
+        path: path.to_string(),
+        line: line_index as u64,
+        column: start as u64,
+        line_text: line[text_start..text_end].to_string(),
+        start: (start - text_start) as u64,
+        end: (end - text_start) as u64,
+    }
+}
+
+// Finds a literal query in a line. Matching without case compares the lowercase forms of each
+// char, so match ranges always line up with the original text.
+struct Matcher {
+    query: Vec<char>,
+    case_sensitive: bool,
+    whole_word: bool,
+}
+
+impl Matcher {
+    fn new(query: &str, case_sensitive: bool, whole_word: bool) -> Self {
+        Self {
+            query: query.chars().collect(),
+            case_sensitive,
+            whole_word,
+        }
+    }
+
+    fn find_at(&self, line: &str, start: usize) -> Option<(usize, usize)> {
+        if self.query.is_empty() {
+            return None;
+        }
+        for (index, _) in line[start..].char_indices() {
+            let index = start + index;
+            if let Some(end) = self.match_len(&line[index..]).map(|len| index + len) {
+                if !self.whole_word || is_whole_word(line, index, end) {
+                    return Some((index, end));
+                }
+            }
+        }
+        None
+    }
+
+    fn match_len(&self, text: &str) -> Option<usize> {
+        let mut len = 0;
+        let mut chars = text.chars();
+        for &query_char in &self.query {
+            let char = chars.next()?;
+            let is_equal = if self.case_sensitive {
+                char == query_char
+            } else {
+                char.to_lowercase().eq(query_char.to_lowercase())
+            };
+            if !is_equal {
+                return None;
+            }
+            len += char.len_utf8();
+        }
+        Some(len)
+    }
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+fn is_whole_word(line: &str, start: usize, end: usize) -> bool {
+    let before = line[..start].chars().next_back();
+    let after = line[end..].chars().next();
+    let first = line[start..].chars().next();
+    let last = line[..end].chars().next_back();
+    let is_word = |char: Option<char>| char.is_some_and(is_word_char);
+    !(is_word(first) && is_word(before) || is_word(last) && is_word(after))
+}
+
+/// A glob pattern as used for the include and exclude filters of a search. `*` and `?` don't
+/// match `/`, `**` matches any number of directories, and `[a-z]` or `[!a-z]` match a class.
+struct Glob {
+    pattern: String,
+    // Patterns without a slash match the file name, the others the whole path.
+    match_name: bool,
+}
+
+impl Glob {
+    fn new(pattern: &str) -> Option<Self> {
+        let pattern = pattern.trim().trim_start_matches("./").trim_end_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Self {
+            match_name: !pattern.contains('/'),
+            pattern: pattern.trim_start_matches('/').to_string(),
+        })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        if self.match_name {
+            glob_match(self.pattern.as_bytes(), file_name(path).as_bytes())
+        } else {
+            glob_match(self.pattern.as_bytes(), path.as_bytes())
+        }
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) if rest.first() == Some(&b'*') => {
+            let rest = &rest[1..];
+            // `a/**/b` also matches `a/b`.
+            if let Some(after_slash) = rest.strip_prefix(b"/") {
+                if glob_match(after_slash, text) {
+                    return true;
+                }
+            }
+            (0..=text.len()).any(|index| glob_match(rest, &text[index..]))
+        }
+        Some((b'*', rest)) => {
+            for index in 0..=text.len() {
+                if glob_match(rest, &text[index..]) {
+                    return true;
+                }
+                if text.get(index) == Some(&b'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some((b'?', rest)) => match text.split_first() {
+            Some((&char, text)) => char != b'/' && glob_match(rest, text),
+            None => false,
+        },
+        Some((b'[', rest)) => {
+            // `]` right after the `[` or `[!` is part of the class.
+            let skip = if rest.first() == Some(&b'!') { 2 } else { 1 };
+            let Some(len) = rest.iter().skip(skip).position(|&char| char == b']') else {
+                return text.first() == Some(&b'[') && glob_match(rest, &text[1..]);
+            };
+            let class = &rest[..skip + len];
+            let rest = &rest[skip + len + 1..];
+            let Some((&char, text)) = text.split_first() else {
+                return false;
+            };
+            let (negate, class) = match class.strip_prefix(b"!") {
+                Some(class) => (true, class),
+                None => (false, class),
+            };
+            let mut is_in_class = false;
+            let mut index = 0;
+            while index < class.len() {
+                if index + 2 < class.len() && class[index + 1] == b'-' {
+                    is_in_class |= (class[index]..=class[index + 2]).contains(&char);
+                    index += 3;
+                } else {
+                    is_in_class |= class[index] == char;
+                    index += 1;
+                }
+            }
+            is_in_class != negate && char != b'/' && glob_match(rest, text)
+        }
+        Some((b'\\', rest)) if !rest.is_empty() => {
+            text.first() == Some(&rest[0]) && glob_match(&rest[1..], &text[1..])
+        }
+        Some((&char, rest)) => text.first() == Some(&char) && glob_match(rest, &text[1..]),
+    }
+}
+
+// The rules of a `.gitignore` file, in the directory at `base`.
+struct Gitignore {
+    base: String,
+    rules: Vec<GitignoreRule>,
+}
+
+struct GitignoreRule {
+    glob: Glob,
+    negate: bool,
+    dir_only: bool,
+}
+
+impl Gitignore {
+    fn parse(base: &str, text: &str) -> Self {
+        let rules = text
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_end();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let (negate, line) = match line.strip_prefix('!') {
+                    Some(line) => (true, line),
+                    None => (false, line.strip_prefix('\\').unwrap_or(line)),
+                };
+                let dir_only = line.ends_with('/');
+                let line = line.trim_end_matches('/');
+                // A slash anywhere but at the end anchors the pattern to the directory of the
+                // .gitignore file.
+                Some(GitignoreRule {
+                    glob: Glob {
+                        match_name: !line.contains('/'),
+                        pattern: line.trim_start_matches('/').to_string(),
+                    },
+                    negate,
+                    dir_only,
+                })
+            })
+            .collect();
+        Self {
+            base: base.to_string(),
+            rules,
+        }
+    }
+}
+
+// Rules from deeper .gitignore files, and later rules in the same file, take precedence.
+fn is_ignored(ignores: &[Gitignore], child_path: &str, is_dir: bool) -> bool {
+    let mut is_ignored = false;
+    for gitignore in ignores {
+        let path = if gitignore.base.is_empty() {
+            child_path
+        } else {
+            match child_path
+                .strip_prefix(gitignore.base.as_str())
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                Some(path) => path,
+                None => continue,
+            }
+        };
+        for rule in &gitignore.rules {
+            if (!rule.dir_only || is_dir) && rule.glob.matches(path) {
+                is_ignored = !rule.negate;
+            }
+        }
+    }
+    is_ignored
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{path::PathBuf, sync::mpsc},
+    };
+
+    #[test]
+    fn glob() {
+        let glob = Glob::new("*.rs").unwrap();
+        assert!(glob.matches("main.rs"));
+        assert!(glob.matches("src/deep/main.rs"));
+        assert!(!glob.matches("main.rs.bak"));
+
+        let glob = Glob::new("src/*.rs").unwrap();
+        assert!(glob.matches("src/main.rs"));
+        assert!(!glob.matches("src/deep/main.rs"));
+        assert!(!glob.matches("other/src/main.rs"));
+
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        assert!(glob.matches("src/main.rs"));
+        assert!(glob.matches("src/a/b/main.rs"));
+        assert!(!glob.matches("main.rs"));
+
+        let glob = Glob::new("**/test").unwrap();
+        assert!(glob.matches("test"));
+        assert!(glob.matches("a/b/test"));
+        assert!(!glob.matches("a/b/test2"));
+
+        let glob = Glob::new("file?.[a-c]").unwrap();
+        assert!(glob.matches("file1.b"));
+        assert!(!glob.matches("file1.d"));
+        assert!(!glob.matches("file12.b"));
+        assert!(Glob::new("[!a]").unwrap().matches("b"));
+        assert!(!Glob::new("[!a]").unwrap().matches("a"));
+
+        assert!(Glob::new("").is_none());
+        assert!(Glob::new("./").is_none());
+    }
+
+    #[test]
+    fn gitignore_negation() {
+        let ignores = [Gitignore::parse("", "*.log\n!keep.log\n")];
+        assert!(is_ignored(&ignores, "a.log", false));
+        assert!(is_ignored(&ignores, "dir/a.log", false));
+        assert!(!is_ignored(&ignores, "keep.log", false));
+        assert!(!is_ignored(&ignores, "dir/keep.log", false));
+        // a later rule wins over an earlier one
+        let ignores = [Gitignore::parse("", "!keep.log\n*.log\n")];
+        assert!(is_ignored(&ignores, "keep.log", false));
+        // a deeper .gitignore wins over the one above it
+        let ignores = [Gitignore::parse("", "*.log\n"), Gitignore::parse("sub", "!*.log\n")];
+        assert!(is_ignored(&ignores, "a.log", false));
+        assert!(!is_ignored(&ignores, "sub/a.log", false));
+        // comments, blank lines and an escaped `!` are not negations
+        let ignores = [Gitignore::parse("", "# comment\n\n\\!bang\n")];
+        assert!(is_ignored(&ignores, "!bang", false));
+        assert!(!is_ignored(&ignores, "# comment", false));
+    }
+
+    #[test]
+    fn gitignore_anchored() {
+        let ignores = [Gitignore::parse("", "/build\nsrc/gen\n")];
+        assert!(is_ignored(&ignores, "build", true));
+        assert!(!is_ignored(&ignores, "sub/build", true));
+        assert!(is_ignored(&ignores, "src/gen", true));
+        assert!(!is_ignored(&ignores, "other/src/gen", true));
+        // anchored to the directory of the .gitignore file, not the root
+        let ignores = [Gitignore::parse("sub", "/build\n")];
+        assert!(is_ignored(&ignores, "sub/build", true));
+        assert!(!is_ignored(&ignores, "build", true));
+        assert!(!is_ignored(&ignores, "sub/deeper/build", true));
+    }
+
+    #[test]
+    fn gitignore_double_star() {
+        let ignores = [Gitignore::parse("", "**/cache\nlogs/**/*.txt\n")];
+        assert!(is_ignored(&ignores, "cache", true));
+        assert!(is_ignored(&ignores, "a/b/cache", true));
+        assert!(is_ignored(&ignores, "logs/a.txt", false));
+        assert!(is_ignored(&ignores, "logs/a/b/a.txt", false));
+        assert!(!is_ignored(&ignores, "other/logs/a.txt", false));
+        assert!(!is_ignored(&ignores, "logs/a.rs", false));
+    }
+
+    #[test]
+    fn gitignore_dir_only() {
+        let ignores = [Gitignore::parse("", "out/\n")];
+        assert!(is_ignored(&ignores, "out", true));
+        assert!(is_ignored(&ignores, "sub/out", true));
+        assert!(!is_ignored(&ignores, "out", false));
+        assert!(!is_ignored(&ignores, "sub/out", false));
+    }
+
+    fn search_dir(root: &Path, query: &str) -> Vec<String> {
+        let (sender, receiver) = mpsc::channel();
+        let request = SearchRequest {
+            id: 1,
+            query: query.to_string(),
+            case_sensitive: true,
+            whole_word: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        };
+        search(
+            root,
+            request,
+            Arc::new(AtomicU64::new(1)),
+            Box::new(move |notification| sender.send(notification).unwrap()),
+        );
+        let mut paths = Vec::new();
+        for notification in receiver.try_iter() {
+            if let FileNotification::SearchResults { results, .. } = notification {
+                paths.extend(results.into_iter().map(|result| result.path));
+            }
+        }
+        paths
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("makepad_search_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn search_respects_gitignore() {
+        let root = temp_dir("gitignore");
+        fs::create_dir_all(root.join("out")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join(".gitignore"), "out/\n*.log\n").unwrap();
+        fs::write(root.join("src/main.rs"), "needle\n").unwrap();
+        fs::write(root.join("out/main.rs"), "needle\n").unwrap();
+        fs::write(root.join("a.log"), "needle\n").unwrap();
+        fs::write(root.join(".hidden"), "needle\n").unwrap();
+        assert_eq!(search_dir(&root, "needle"), ["src/main.rs"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn search_skips_symlinked_directories() {
+        let root = temp_dir("symlink");
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::write(root.join("dir/file.txt"), "needle\n").unwrap();
+        // a loop back up the tree, and a symlinked file which is still searched
+        std::os::unix::fs::symlink(&root, root.join("dir/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("dir/file.txt"), root.join("link.txt")).unwrap();
+        assert_eq!(search_dir(&root, "needle"), ["dir/file.txt", "link.txt"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -3,6 +3,7 @@ use crate::{
     makepad_code_editor::selection::Affinity,
     makepad_code_editor::session::SelectionMode,
     makepad_code_editor::history::NewGroup,
+    makepad_code_editor::widgets::*,
     makepad_widgets::*,
     makepad_micro_serde::*,
     makepad_widgets::file_tree::*,
@@ -12,8 +13,11 @@ use crate::{
     run_view::*,
     makepad_platform::studio::{JumpToFile,EditFile, PatchFile},
     log_list::*,
+    studio_file_tree::*,
+    search_results::*,
     makepad_code_editor::text::{Position},
     ai_chat::ai_chat_manager::AiChatManager,
+    lsp::lsp_manager::{LspManager, LspAction},
     build_manager::{
         build_protocol::BuildProcess,
         build_manager::{
@@ -37,6 +41,10 @@ live_design!{
 pub struct App {
     #[live] pub ui: WidgetRef,
     #[rust] pub data: AppData,
+    // The file operation the name bar above the file tree is open for
+    #[rust] file_operation: Option<StudioFileTreeAction>,
+    // A jump into a file that is still loading, done again once it is loaded
+    #[rust] pending_jump: Option<JumpToFile>,
 }
 
 impl LiveRegister for App{
@@ -133,6 +141,118 @@ impl App {
         let mut f = File::create(format!("makepad_state{}.ron", slot)).expect("Unable to create file");
         f.write_all(saved.as_bytes()).expect("Unable to write data");
     }
+    
+    fn begin_file_operation(&mut self, cx:&mut Cx, operation:StudioFileTreeAction){
+        let file_system = &self.data.file_system;
+        let display_path = |file_id| {
+            let path = file_system.file_node_path(file_id);
+            if path.is_empty() {"the root".to_string()} else {path}
+        };
+        let (label, name) = match &operation{
+            StudioFileTreeAction::NewFile(folder_id) => (format!("New file in {}", display_path(*folder_id)), Some(String::new())),
+            StudioFileTreeAction::NewFolder(folder_id) => (format!("New folder in {}", display_path(*folder_id)), Some(String::new())),
+            StudioFileTreeAction::Rename(file_id) => (format!("Rename {}", display_path(*file_id)), Some(file_system.file_node_name(*file_id))),
+            StudioFileTreeAction::Delete(file_id) => (format!("Delete {}?", display_path(*file_id)), None),
+            StudioFileTreeAction::None => return
+        };
+        self.ui.label(id!(file_op_label)).set_text(&label);
+        self.ui.view(id!(file_op_name)).set_visible(name.is_some());
+        let input = self.ui.text_input(id!(file_op_input));
+        if let Some(name) = name{
+            input.set_text(&name);
+            input.set_cursor(name.len(), 0);
+            input.set_key_focus(cx);
+        }
+        self.ui.view(id!(file_op_bar)).set_visible_and_redraw(cx, true);
+        self.file_operation = Some(operation);
+    }
+    
+    fn end_file_operation(&mut self, cx:&mut Cx, confirmed:bool){
+        let Some(operation) = self.file_operation.take() else{
+            return
+        };
+        self.ui.view(id!(file_op_bar)).set_visible_and_redraw(cx, false);
+        if !confirmed{
+            return
+        }
+        let name = self.ui.text_input(id!(file_op_input)).text();
+        let name = name.trim();
+        let file_system = &mut self.data.file_system;
+        match operation{
+            StudioFileTreeAction::Delete(file_id) => file_system.request_delete_file(file_id),
+            _ if name.is_empty() || name.contains('/') || name.contains('\\') => (),
+            StudioFileTreeAction::NewFile(folder_id) => file_system.request_create_file(folder_id, name),
+            StudioFileTreeAction::NewFolder(folder_id) => file_system.request_create_directory(folder_id, name),
+            StudioFileTreeAction::Rename(file_id) => file_system.request_rename_file(file_id, name),
+            StudioFileTreeAction::None => ()
+        }
+    }
+    
+    fn start_search(&mut self, cx:&mut Cx){
+        let query = self.ui.text_input(id!(search_input)).text();
+        let include = self.ui.text_input(id!(search_include)).text();
+        let exclude = self.ui.text_input(id!(search_exclude)).text();
+        self.data.file_system.request_search(
+            &query,
+            self.ui.check_box(id!(search_case_sensitive)).selected(cx),
+            self.ui.check_box(id!(search_whole_word)).selected(cx),
+            &include,
+            &exclude
+        );
+        let search_results = self.ui.search_results(id!(search_results));
+        search_results.reset_scroll(cx);
+        search_results.redraw(cx);
+    }
+    
+    fn handle_find_bar_action(&mut self, cx:&mut Cx, tab_id:LiveId, action:FindBarAction){
+        let dock = self.ui.dock(id!(dock));
+        let item = dock.item(tab_id);
+        let find_bar = item.find_bar(id!(find_bar));
+        let Some(EditSession::Code(session)) = self.data.file_system.get_session_mut(tab_id) else{
+            return
+        };
+        let mut text_did_change = false;
+        let match_count = match action{
+            FindBarAction::QueryChanged(query)=>{
+                session.set_search_query(Some(query))
+            }
+            FindBarAction::FindNext=>{
+                session.find_next();
+                Ok(session.search_matches().len())
+            }
+            FindBarAction::FindPrev=>{
+                session.find_prev();
+                Ok(session.search_matches().len())
+            }
+            FindBarAction::SelectAll=>{
+                session.select_all_search_matches();
+                Ok(session.search_matches().len())
+            }
+            FindBarAction::Replace(replacement)=>{
+                text_did_change = session.replace_next(&replacement);
+                Ok(session.search_matches().len())
+            }
+            FindBarAction::ReplaceAll(replacement)=>{
+                text_did_change = session.replace_all(&replacement) > 0;
+                Ok(session.search_matches().len())
+            }
+            FindBarAction::Close=>{
+                session.set_search_query(None).ok();
+                find_bar.set_visible_and_redraw(cx, false);
+                item.redraw(cx);
+                return
+            }
+            FindBarAction::None=>return
+        };
+        find_bar.set_match_count(cx, session.current_search_match_index(), match_count);
+        if let Some(mut editor) = item.studio_code_editor(id!(editor)).borrow_mut() {
+            editor.editor.jump_to_cursor(cx);
+        }
+        if text_did_change{
+            self.data.file_system.handle_sessions();
+            self.data.file_system.request_save_file_for_tab_id(tab_id, false)
+        }
+    }
 }
 
 #[derive(Default)]
@@ -140,6 +260,7 @@ pub struct AppData{
     pub build_manager: BuildManager,
     pub file_system: FileSystem,
     pub ai_chat_manager: AiChatManager,
+    pub lsp_manager: LspManager,
 }
 
 // all global app commands coming in from keybindings, and UI components
@@ -179,6 +300,7 @@ impl MatchEvent for App{
                 
         self.data.file_system.init(cx, &root_path);
         self.data.build_manager.init(cx, &root_path);
+        self.data.lsp_manager.init(&root_path);
         
                 
         //self.data.build_manager.discover_external_ip(cx);
@@ -205,9 +327,13 @@ impl MatchEvent for App{
                                 editor.editor.set_cursor_and_scroll(cx, pos, session);
                                 editor.editor.set_key_focus(cx);
                             }
+                            else{
+                                self.pending_jump = Some(jt);
+                            }
                         }
                     }
                     else{
+                        self.pending_jump = Some(jt.clone());
                         // lets open the editor
                         let tab_id = dock.unique_tab_id(file_id.0);
                         self.data.file_system.request_open_file(tab_id, file_id);
@@ -422,6 +548,22 @@ impl MatchEvent for App{
             BuildManagerAction::None=>()
         }
                 
+        match action.cast(){
+            LspAction::Completions{tab_id, position:_, items}=>{
+                if let Some(mut editor) = dock.item(tab_id).studio_code_editor(id!(editor)).borrow_mut() {
+                    if let Some(EditSession::Code(session)) = self.data.file_system.get_session_mut(tab_id) {
+                        editor.editor.show_completions(cx, session, items);
+                    }
+                }
+            }
+            LspAction::Hover{tab_id, position, text}=>{
+                if let Some(mut editor) = dock.item(tab_id).studio_code_editor(id!(editor)).borrow_mut() {
+                    editor.editor.show_hover(cx, position, &text);
+                }
+            }
+            LspAction::None=>()
+        }
+                
         match action.cast(){
             FileSystemAction::TreeLoaded => {
                 file_tree.redraw(cx);
@@ -429,6 +571,29 @@ impl MatchEvent for App{
                 self.data.ai_chat_manager.init(&mut self.data.file_system);
                 //self.open_code_file_by_path(cx, "examples/slides/src/app.rs");
             }
+            FileSystemAction::TreeReloaded => {
+                file_tree.redraw(cx);
+                self.data.file_system.ensure_unique_tab_names(cx, &dock);
+                dock.redraw(cx);
+            }
+            FileSystemAction::FilesDeleted(file_ids) => {
+                for file_id in file_ids {
+                    while let Some(tab_id) = self.data.file_system.file_node_id_to_tab_id(file_id) {
+                        dock.close_tab(cx, tab_id);
+                        self.data.file_system.remove_tab(tab_id);
+                    }
+                }
+            }
+            FileSystemAction::DocumentLoaded(file_id) => {
+                if let Some(jt) = self.pending_jump.take() {
+                    if self.data.file_system.path_to_file_node_id(&jt.file_name) == Some(file_id) {
+                        cx.action(AppAction::JumpTo(jt));
+                    }
+                }
+            }
+            FileSystemAction::SearchResultsChanged => {
+                self.ui.search_results(id!(search_results)).redraw(cx);
+            }
             FileSystemAction::RecompileNeeded => {
                 self.data.build_manager.start_recompile_timer(cx);
             }
@@ -443,15 +608,39 @@ impl MatchEvent for App{
             FileSystemAction::None=>()
         }
         
+        match action.cast(){
+            StudioFileTreeAction::None=>(),
+            operation=>self.begin_file_operation(cx, operation)
+        }
+        
         if let Some(action) = action.as_widget_action(){
             match action.cast(){
                 CodeEditorAction::TextDidChange => {
                     // lets write the file
-                    self.data.file_system.request_save_file_for_tab_id(action.path.from_end(1), false)
+                    self.data.file_system.request_save_file_for_tab_id(action.path.from_end(1), false);
+                    self.data.lsp_manager.handle_text_did_change(&mut self.data.file_system, action.path.from_end(1));
+                }
+                CodeEditorAction::RequestCompletion(position) => {
+                    self.data.lsp_manager.request_completion(&self.data.file_system, action.path.from_end(1), position);
+                }
+                CodeEditorAction::RequestHover(position) => {
+                    self.data.lsp_manager.request_hover(cx, &self.data.file_system, action.path.from_end(1), position);
+                }
+                CodeEditorAction::Find => {
+                    let item = dock.item(action.path.from_end(1));
+                    let find_bar = item.find_bar(id!(find_bar));
+                    find_bar.set_visible_and_redraw(cx, true);
+                    find_bar.set_key_focus(cx);
+                    item.redraw(cx);
                 }
                 CodeEditorAction::None=>{}
             }
             
+            let find_bar_action = action.cast::<FindBarAction>();
+            if !matches!(find_bar_action, FindBarAction::None){
+                self.handle_find_bar_action(cx, action.path.from_end(1), find_bar_action);
+            }
+            
             match action.cast(){
                 DockAction::TabCloseWasPressed(tab_id)=>{
                     dock.close_tab(cx, tab_id);
@@ -533,12 +722,33 @@ impl MatchEvent for App{
         for action in actions{
             self.handle_action(cx, action);
         }
-        if let Some(file_id) = file_tree.should_file_start_drag(&actions) {
-            let path = self.data.file_system.file_node_path(file_id);
-            file_tree.file_start_drag(cx, file_id, DragItem::FilePath {
-                path,
-                internal_id: None
-            }); 
+        
+        let selected_folder_id = |ui:&WidgetRef, file_system:&FileSystem| {
+            let selected = ui.studio_file_tree(id!(file_tree)).selected_node();
+            file_system.file_node_folder(selected.unwrap_or(live_id!(root)))
+        };
+        if self.ui.button(id!(new_file_button)).clicked(&actions) {
+            let folder_id = selected_folder_id(&self.ui, &self.data.file_system);
+            self.begin_file_operation(cx, StudioFileTreeAction::NewFile(folder_id));
+        }
+        if self.ui.button(id!(new_folder_button)).clicked(&actions) {
+            let folder_id = selected_folder_id(&self.ui, &self.data.file_system);
+            self.begin_file_operation(cx, StudioFileTreeAction::NewFolder(folder_id));
+        }
+        let file_op_input = self.ui.text_input(id!(file_op_input));
+        if self.ui.button(id!(file_op_ok)).clicked(&actions) || file_op_input.returned(&actions).is_some() {
+            self.end_file_operation(cx, true);
+        }
+        if self.ui.button(id!(file_op_cancel)).clicked(&actions) || file_op_input.escape(&actions) {
+            self.end_file_operation(cx, false);
+        }
+        
+        if self.ui.text_input(id!(search_input)).changed(&actions).is_some()
+            || self.ui.text_input(id!(search_include)).returned(&actions).is_some()
+            || self.ui.text_input(id!(search_exclude)).returned(&actions).is_some()
+            || self.ui.check_box(id!(search_case_sensitive)).changed(&actions).is_some()
+            || self.ui.check_box(id!(search_whole_word)).changed(&actions).is_some() {
+            self.start_search(cx);
         }
         
         for (i,id) in [*id!(preset_1),*id!(preset_2),*id!(preset_3),*id!(preset_4)].iter().enumerate(){
@@ -579,6 +789,7 @@ impl MatchEvent for App{
     
     fn handle_shutdown(&mut self, _cx:&mut Cx){
         self.data.build_manager.clear_active_builds();
+        self.data.lsp_manager.shutdown();
     }
 }
 
@@ -591,6 +802,7 @@ impl AppMain for App {
         self.data.file_system.handle_event(cx, event, &self.ui);
         self.data.build_manager.handle_event(cx, event, &mut self.data.file_system); 
         self.data.ai_chat_manager.handle_event(cx, event, &mut self.data.file_system);
+        self.data.lsp_manager.handle_event(cx, event, &mut self.data.file_system);
         if self.ui.dock(id!(dock)).check_and_clear_need_save(){
             self.save_state(0);
         }
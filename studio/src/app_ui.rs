@@ -5,8 +5,10 @@ live_design!{
     import makepad_widgets::base::*;
     import makepad_widgets::theme_desktop_dark::*;
     import makepad_studio::studio_editor::StudioCodeEditor;
+    import makepad_code_editor::widgets::FindBar;
     import makepad_studio::ai_chat::ai_chat_view::AiChatView;
     import makepad_studio::studio_file_tree::StudioFileTree;
+    import makepad_studio::search_results::SearchResults;
     import makepad_studio::run_view::RunView;
     import makepad_studio::log_list::LogList;
     import makepad_studio::run_list::RunList;
@@ -435,6 +437,7 @@ live_design!{
                         <LinkLabel> { width: Fit, text: "Docs", url: "https://publish.obsidian.md/makepad-docs"}
                     }
                 }
+                find_bar = <FindBar> { visible: false }
                 editor = <StudioCodeEditor> {} 
             }
             
@@ -534,12 +537,12 @@ live_design!{
                     content = {
                         spacing: (THEME_SPACE_2)
                         align: { y: 0.5 }
-                        <TextInput> {
+                        search_input = <TextInput> {
                             width: Fill,
                             empty_message: "Search",
                         }
 
-                        <CheckBoxCustom> {
+                        search_case_sensitive = <CheckBoxCustom> {
                             padding: 0.
                             text: ""
                             draw_check: { check_type: None }
@@ -550,7 +553,7 @@ live_design!{
                                 svg_file: dep("crate://self/resources/icons/icon_search_case_sensitive.svg"),
                             }
                         }
-                        <CheckBoxCustom> {
+                        search_whole_word = <CheckBoxCustom> {
                             padding: 0.
                             text:""
                             draw_check: { check_type: None }
@@ -575,10 +578,20 @@ live_design!{
                     }
                 }
                 <View> {
-                    flow: Down
-                    margin: <THEME_MSPACE_2> {}
-                    <P> { text: "this does not work yet." }
+                    width: Fill, height: Fit,
+                    flow: Right,
+                    spacing: (THEME_SPACE_2)
+                    padding: <THEME_MSPACE_2> {}
+                    search_include = <TextInput> {
+                        width: Fill,
+                        empty_message: "Include, like *.rs, src/**",
+                    }
+                    search_exclude = <TextInput> {
+                        width: Fill,
+                        empty_message: "Exclude",
+                    }
                 }
+                search_results = <SearchResults> {}
             }
             RunView = <RunView> {}
             StudioFileTree = <View> {
@@ -592,7 +605,7 @@ live_design!{
                             width: Fit, height: Fit,
                             flow: Right,
                             spacing: 0.,
-                            <ButtonFlat> {
+                            new_folder_button = <ButtonFlat> {
                                 width: 32.
                                 text: ""
                                 icon_walk: { width: 14. }
@@ -600,7 +613,7 @@ live_design!{
                                     svg_file: dep("crate://self/resources/icons/icon_filetree_folder_create.svg"),
                                 }
                             }
-                            <ButtonFlat> {
+                            new_file_button = <ButtonFlat> {
                                 width: 32.
                                 text: ""
                                 icon_walk: { width: 11. }
@@ -616,6 +629,29 @@ live_design!{
                         }
                     }
                 }
+                file_op_bar = <View> {
+                    visible: false,
+                    width: Fill, height: Fit,
+                    flow: Down,
+                    spacing: (THEME_SPACE_1)
+                    padding: <THEME_MSPACE_2> {}
+                    file_op_label = <Label> { text: "" }
+                    <View> {
+                        width: Fill, height: Fit,
+                        flow: Right,
+                        spacing: (THEME_SPACE_1)
+                        align: { y: 0.5 }
+                        file_op_name = <View> {
+                            width: Fill, height: Fit,
+                            file_op_input = <TextInput> {
+                                width: Fill,
+                                empty_message: "Name",
+                            }
+                        }
+                        file_op_ok = <ButtonFlat> { text: "OK" }
+                        file_op_cancel = <ButtonFlat> { text: "Cancel" }
+                    }
+                }
                 file_tree = <StudioFileTree> {}
             }
             LogList = <View> {
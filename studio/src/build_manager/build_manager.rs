@@ -22,6 +22,7 @@ use {
         text,
     },
     makepad_http::server::*,
+    makepad_http::router::*,
     std::{
         cell::RefCell,
         collections::{hash_map, HashMap},
@@ -684,9 +685,26 @@ impl BuildManager {
                 ),
                 ("/".to_string(), "".to_string()),
             ];
+            let mut router = HttpRouter::default();
+            router.get("/$watch", |_| {
+                HttpServerResponse::new(200, "Cache-Control: max-age:0\r\n", Vec::new())
+            });
+            router.get("/favicon.ico", |_| HttpServerResponse::new(200, "", Vec::new()));
+            for (prefix, dir) in remaps {
+                let mut static_dir = HttpStaticDir::new(dir);
+                static_dir.extra_headers = "Cross-Origin-Embedder-Policy: require-corp\r\n\
+                    Cross-Origin-Opener-Policy: same-origin\r\n"
+                    .to_string();
+                // the root is the working directory, so only hand out web assets
+                static_dir.extensions = Some(WEB_ASSET_EXTENSIONS);
+                router.static_dir(&prefix, static_dir);
+            }
             let mut socket_id_to_build_id = HashMap::new();
             while let Ok(message) = rx_request.recv() {
                 // only store last change, fix later
+                let Some(message) = router.handle(message) else {
+                    continue;
+                };
                 match message {
                     HttpServerRequest::ConnectWebSocket {
                         web_socket_id,
@@ -724,93 +742,7 @@ impl BuildManager {
                         }
                         // new incombing message from client
                     }
-                    HttpServerRequest::Get {
-                        headers,
-                        response_sender,
-                    } => {
-                        let path = &headers.path;
-                        // ok so this live connection.. where do we do it
-                        // i mean its just a network event msg. we can ignore that
-                        // we could just handle this in 'window'
-                        // or where shall we handle it
-                        // lets give live edit an api so you can codegen/live edit shaders?
-
-                        // alright wasm http server
-                        if path == "/$watch" {
-                            let header = "HTTP/1.1 200 OK\r\n\
-                                Cache-Control: max-age:0\r\n\
-                                Connection: close\r\n\r\n"
-                                .to_string();
-                            let _ = response_sender.send(HttpServerResponse {
-                                header,
-                                body: vec![],
-                            });
-                            continue;
-                        }
-                        if path == "/favicon.ico" {
-                            let header = "HTTP/1.1 200 OK\r\n\r\n".to_string();
-                            let _ = response_sender.send(HttpServerResponse {
-                                header,
-                                body: vec![],
-                            });
-                            continue;
-                        }
-
-                        let mime_type = if path.ends_with(".html") {
-                            "text/html"
-                        } else if path.ends_with(".wasm") {
-                            "application/wasm"
-                        } else if path.ends_with(".css") {
-                            "text/css"
-                        } else if path.ends_with(".js") {
-                            "text/javascript"
-                        } else if path.ends_with(".ttf") {
-                            "application/ttf"
-                        } else if path.ends_with(".png") {
-                            "image/png"
-                        } else if path.ends_with(".jpg") {
-                            "image/jpg"
-                        } else if path.ends_with(".svg") {
-                            "image/svg+xml"
-                        } else {
-                            continue;
-                        };
-
-                        if path.contains("..") || path.contains('\\') {
-                            continue;
-                        }
-
-                        let mut strip = None;
-                        for remap in &remaps {
-                            if let Some(s) = path.strip_prefix(&remap.0) {
-                                strip = Some(format!("{}{}", remap.1, s));
-                                break;
-                            }
-                        }
-                        if let Some(base) = strip {
-                            if let Ok(mut file_handle) = File::open(base) {
-                                let mut body = Vec::<u8>::new();
-                                if file_handle.read_to_end(&mut body).is_ok() {
-                                    let header = format!(
-                                        "HTTP/1.1 200 OK\r\n\
-                                            Content-Type: {}\r\n\
-                                            Cross-Origin-Embedder-Policy: require-corp\r\n\
-                                            Cross-Origin-Opener-Policy: same-origin\r\n\
-                                            Content-encoding: none\r\n\
-                                            Cache-Control: max-age:0\r\n\
-                                            Content-Length: {}\r\n\
-                                            Connection: close\r\n\r\n",
-                                        mime_type,
-                                        body.len()
-                                    );
-                                    let _ =
-                                        response_sender.send(HttpServerResponse { header, body });
-                                }
-                            }
-                        }
-                    }
-                    HttpServerRequest::Post { .. } => { //headers, body, response}=>{
-                    }
+                    HttpServerRequest::Get { .. } | HttpServerRequest::Post { .. } => {}
                 }
             }
         });
@@ -29,9 +29,22 @@ pub enum ChildStdIn {
     Term,
 }
 
+/// How the output of a child process on stdout is split into `ChildStdIO::StdOut` messages.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StdOutFraming {
+    /// Every line is a message.
+    Lines,
+    /// Every message is preceded by a `Content-Length` header, as in the language server protocol.
+    ContentLength,
+}
+
 impl ChildProcess {
     
     pub fn start(cmd: &str, args: &[String], current_dir: PathBuf, env: &[(&str, &str)], aux_chan:bool) -> Result<ChildProcess, std::io::Error> {
+        Self::start_with_framing(cmd, args, current_dir, env, aux_chan, StdOutFraming::Lines)
+    }
+    
+    pub fn start_with_framing(cmd: &str, args: &[String], current_dir: PathBuf, env: &[(&str, &str)], aux_chan:bool, framing: StdOutFraming) -> Result<ChildProcess, std::io::Error> {
         let (mut child, aux_chan_host_endpoint) = if aux_chan{
             let (aux_chan_host_endpoint, aux_chan_client_endpoint) =
                 aux_chan::make_host_and_client_endpoint_pair()?;
@@ -84,20 +97,23 @@ impl ChildProcess {
             thread::spawn(move || {
                 let mut reader = BufReader::new(stdout);
                 loop{
-                    let mut line = String::new();
-                    if let Ok(len) = reader.read_line(&mut line){
-                        if len == 0{
-                            break
+                    let message = match framing{
+                        StdOutFraming::Lines=>read_line_message(&mut reader),
+                        StdOutFraming::ContentLength=>read_content_length_message(&mut reader),
+                    };
+                    match message{
+                        Ok(Some(message))=>{
+                            if line_sender.send(ChildStdIO::StdOut(message)).is_err(){
+                                break;
+                            }
                         }
-                        if line_sender.send(ChildStdIO::StdOut(line)).is_err(){
+                        Ok(None)=>break,
+                        Err(_)=>{
+                            let _ = line_sender.send(ChildStdIO::Term);
+                            let _ = stdin_sender.send(ChildStdIn::Term);
                             break;
                         }
                     }
-                    else{
-                        let _ = line_sender.send(ChildStdIO::Term);
-                        let _ = stdin_sender.send(ChildStdIn::Term);
-                        break;
-                    }
                 }
             })
         };
@@ -160,3 +176,90 @@ impl ChildProcess {
         let _ = self.child.wait();
     }
 }
+
+fn read_line_message(reader: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0{
+        return Ok(None)
+    }
+    Ok(Some(line))
+}
+
+/// Larger messages are taken to be a broken server rather than allocated.
+const MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Reads a message of the form `Content-Length: 12\r\n\r\n{"id":1,...}`. Other headers are ignored.
+fn read_content_length_message(reader: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut content_length = None;
+    loop{
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0{
+            return Ok(None)
+        }
+        let line = line.trim();
+        if line.is_empty(){
+            if content_length.is_some(){
+                break
+            }
+            continue
+        }
+        if let Some((name, value)) = line.split_once(':'){
+            if name.trim().eq_ignore_ascii_case("content-length"){
+                content_length = Some(value.trim().parse::<usize>().map_err( | _ | {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid Content-Length {}", value.trim()))
+                })?);
+            }
+        }
+    }
+    let content_length = content_length.unwrap();
+    if content_length > MAX_CONTENT_LENGTH{
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Content-Length {} is too large", content_length)))
+    }
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+    Ok(Some(String::from_utf8_lossy(&content).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_length_framing() {
+        let input = "Content-Length: 7\r\n\r\n{\"a\":1}\
+            Content-Type: application/vscode-jsonrpc; charset=utf-8\r\ncontent-length:2\r\n\r\n[]\
+            \r\nContent-Length: 4\r\n\r\n\"é\"";
+        let mut reader = BufReader::new(input.as_bytes());
+        assert_eq!(read_content_length_message(&mut reader).unwrap().as_deref(), Some("{\"a\":1}"));
+        assert_eq!(read_content_length_message(&mut reader).unwrap().as_deref(), Some("[]"));
+        // stray blank lines between messages are skipped, the length counts bytes
+        assert_eq!(read_content_length_message(&mut reader).unwrap().as_deref(), Some("\"é\""));
+        assert_eq!(read_content_length_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn content_length_split_across_reads() {
+        // a reader that hands out a few bytes at a time, like a pipe
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = buf.len().min(self.0.len()).min(3);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+        let mut reader = BufReader::with_capacity(4, Trickle(b"Content-Length: 11\r\n\r\nhello world"));
+        assert_eq!(read_content_length_message(&mut reader).unwrap().as_deref(), Some("hello world"));
+    }
+
+    #[test]
+    fn content_length_errors() {
+        let read = | input: &str | read_content_length_message(&mut BufReader::new(input.as_bytes()));
+        assert!(read("Content-Length: 99999999999\r\n\r\n").is_err());
+        assert!(read("Content-Length: -1\r\n\r\n{}").is_err());
+        assert!(read("Content-Length: 10\r\n\r\n{}").is_err());
+        // headers without a length before the end of the stream
+        assert_eq!(read("Content-Type: json\r\n\r\n").unwrap(), None);
+    }
+}
@@ -2,7 +2,7 @@ use {
     std::collections::{HashMap, hash_map},
     std::path::Path,
     crate::{
-        makepad_code_editor::{CodeDocument, decoration::{Decoration, DecorationSet}, CodeSession},
+        makepad_code_editor::{CodeDocument, decoration::{Decoration, DecorationSet}, language::language_for_path, CodeSession},
         makepad_platform::makepad_live_compiler::LiveFileChange,
         makepad_widgets::*,
         makepad_widgets::file_tree::*,
@@ -17,7 +17,9 @@ use {
             FileNodeData,
             FileTreeData,
             SaveKind,
-            SaveFileResponse
+            SaveFileResponse,
+            SearchRequest,
+            SearchResult,
         },
     },
 };
@@ -30,7 +32,13 @@ pub struct FileSystem {
     pub path_to_file_node_id: HashMap<String, LiveId>,
     pub tab_id_to_file_node_id: HashMap<LiveId, LiveId>,
     pub tab_id_to_session: HashMap<LiveId, EditSession>,
-    pub open_documents: HashMap<LiveId, OpenDocument>
+    pub open_documents: HashMap<LiveId, OpenDocument>,
+    pub search_results: Vec<SearchResult>,
+    pub search_is_running: bool,
+    search_id: u64,
+    tree_loaded: bool,
+    // Renames the server has done, applied to the open files when the tree is reloaded
+    pending_renames: Vec<(String, String)>,
 }
 
 pub enum EditSession {
@@ -68,6 +76,13 @@ pub struct FileEdge {
 #[derive(DefaultNone, Debug, Clone)]
 pub enum FileSystemAction {
     TreeLoaded,
+    /// The tree was loaded again, after the first time.
+    TreeReloaded,
+    /// Files that may be open in tabs were deleted.
+    FilesDeleted(Vec<LiveId>),
+    /// The text of a file that was opened has arrived.
+    DocumentLoaded(LiveId),
+    SearchResultsChanged,
     RecompileNeeded,
     LiveReloadNeeded(LiveFileChange),
     FileChangedOnDisk(SaveFileResponse),
@@ -116,6 +131,58 @@ impl FileSystem {
         self.file_client.send_request(FileRequest::LoadFileTree {with_data: false});
     }
     
+    /// Creates an empty file called `name` in the folder `folder_id`.
+    pub fn request_create_file(&mut self, folder_id: LiveId, name: &str) {
+        let path = self.child_path(folder_id, name);
+        self.file_client.send_request(FileRequest::CreateFile{path});
+    }
+    
+    pub fn request_create_directory(&mut self, folder_id: LiveId, name: &str) {
+        let path = self.child_path(folder_id, name);
+        self.file_client.send_request(FileRequest::CreateDirectory{path});
+    }
+    
+    pub fn request_rename_file(&mut self, file_id: LiveId, new_name: &str) {
+        let old_path = self.file_node_path(file_id);
+        let new_path = self.child_path(self.file_node_folder_parent(file_id), new_name);
+        if old_path != new_path {
+            self.file_client.send_request(FileRequest::RenameFile{old_path, new_path});
+        }
+    }
+    
+    /// Moves a file or folder into the folder `folder_id`.
+    pub fn request_move_file(&mut self, file_id: LiveId, folder_id: LiveId) {
+        let old_path = self.file_node_path(file_id);
+        let new_path = self.child_path(folder_id, &self.file_node_name(file_id));
+        if old_path != new_path {
+            self.file_client.send_request(FileRequest::RenameFile{old_path, new_path});
+        }
+    }
+    
+    pub fn request_delete_file(&mut self, file_id: LiveId) {
+        let path = self.file_node_path(file_id);
+        self.file_client.send_request(FileRequest::DeleteFile{path});
+    }
+    
+    /// Starts a search in all files, replacing the results of the previous one. `include` and
+    /// `exclude` are comma separated globs.
+    pub fn request_search(&mut self, query: &str, case_sensitive: bool, whole_word: bool, include: &str, exclude: &str) {
+        fn split_globs(globs: &str) -> Vec<String> {
+            globs.split(',').map(|glob| glob.trim()).filter(|glob| !glob.is_empty()).map(|glob| glob.to_string()).collect()
+        }
+        self.search_id += 1;
+        self.search_results.clear();
+        self.search_is_running = !query.is_empty();
+        self.file_client.send_request(FileRequest::Search(SearchRequest{
+            id: self.search_id,
+            query: query.to_string(),
+            case_sensitive,
+            whole_word,
+            include: split_globs(include),
+            exclude: split_globs(exclude),
+        }));
+    }
+    
     pub fn remove_tab(&mut self, tab_id: LiveId) {
         self.tab_id_to_file_node_id.remove(&tab_id);
         self.tab_id_to_session.remove(&tab_id);
@@ -177,7 +244,13 @@ impl FileSystem {
                     FileClientMessage::Response(response) => match response {
                         FileResponse::LoadFileTree(response) => {
                             self.load_file_tree(response.unwrap());
-                            cx.action(FileSystemAction::TreeLoaded)
+                            if self.tree_loaded {
+                                cx.action(FileSystemAction::TreeReloaded)
+                            }
+                            else {
+                                self.tree_loaded = true;
+                                cx.action(FileSystemAction::TreeLoaded)
+                            }
                             // dock.select_tab(cx, dock, state, live_id!(file_tree).into(), live_id!(file_tree).into(), Animate::No);
                         }
                         FileResponse::OpenFile(result) => {
@@ -193,7 +266,11 @@ impl FileSystem {
                                     match self.open_documents.get(&file_id){
                                         Some(OpenDocument::CodeLoading(dec))=>{
                                             let dec = dec.clone();
-                                            self.open_documents.insert(file_id, OpenDocument::Code(CodeDocument::new(response.data.into(), dec)));
+                                            let doc = CodeDocument::new(response.data.into(), dec);
+                                            if let Some(language) = language_for_path(self.file_node_path(file_id)){
+                                                doc.set_language(language);
+                                            }
+                                            self.open_documents.insert(file_id, OpenDocument::Code(doc));
                                         }
                                         Some(OpenDocument::Code(_))=>{
                                         }
@@ -204,17 +281,45 @@ impl FileSystem {
                                         }
                                         _=>panic!()
                                     }
-                                    
+                                    cx.action(FileSystemAction::DocumentLoaded(file_id));
                                     dock.redraw(cx);
                                 }
                                 Err(FileError::CannotOpen(_unix_path)) => {
                                 }
-                                Err(FileError::Unknown(err)) => {
-                                    log!("File error unknown {}", err);
+                                Err(err) => {
+                                    log!("File error {:?}", err);
                                     // ignore
                                 }
                             }
                         }
+                        FileResponse::CreateFile(result) | FileResponse::CreateDirectory(result) => match result {
+                            Ok(_) => self.reload_file_tree(),
+                            Err(err) => log!("Cannot create file {:?}", err)
+                        }
+                        FileResponse::RenameFile(result) => match result {
+                            Ok(response) => {
+                                self.pending_renames.push((response.old_path, response.new_path));
+                                self.reload_file_tree();
+                            }
+                            Err(err) => log!("Cannot rename file {:?}", err)
+                        }
+                        FileResponse::DeleteFile(result) => match result {
+                            Ok(path) => {
+                                let file_ids: Vec<LiveId> = self.path_to_file_node_id.iter()
+                                    .filter(|(file_path, _)| child_path_rest(file_path, &path).is_some())
+                                    .map(|(_, file_id)| *file_id)
+                                    .collect();
+                                for file_id in &file_ids {
+                                    self.open_documents.remove(file_id);
+                                }
+                                cx.action(FileSystemAction::FilesDeleted(file_ids));
+                                self.reload_file_tree();
+                            }
+                            Err(err) => log!("Cannot delete file {:?}", err)
+                        }
+                        FileResponse::Search(result) => if let Err(err) = result {
+                            log!("Cannot search {:?}", err);
+                        }
                         FileResponse::SaveFile(result) => match result {
                             Ok(response) => {
                                 self.process_save_response(cx, response);
@@ -241,6 +346,14 @@ impl FileSystem {
                                 // we should chuck this into the load comparison
                                 cx.action( FileSystemAction::FileChangedOnDisk(response));
                             }
+                            FileNotification::SearchResults{id, results} => if id == self.search_id {
+                                self.search_results.extend(results);
+                                cx.action(FileSystemAction::SearchResultsChanged);
+                            }
+                            FileNotification::SearchFinished{id} => if id == self.search_id {
+                                self.search_is_running = false;
+                                cx.action(FileSystemAction::SearchResultsChanged);
+                            }
                         }
                         //self.editors.handle_collab_notification(cx, &mut state.editor_state, notification)
                     }
@@ -451,6 +564,14 @@ impl FileSystem {
         };
     }
     
+    pub fn remove_decorations_with_id(&mut self, file_node_id: &LiveId, id: usize) {
+        match self.open_documents.get_mut(file_node_id) {
+            Some(OpenDocument::CodeLoading(dec)) => dec.remove_decorations_with_id(id),
+            Some(OpenDocument::Code(doc)) => doc.remove_decorations_with_id(id),
+            Some(_) | None=>()
+        };
+    }
+    
     pub fn clear_all_decorations(&mut self) {
         // ok lets see if we have a document
         // ifnot, we create a new one
@@ -516,6 +637,51 @@ impl FileSystem {
         self.file_nodes.get(&file_node_id).unwrap().name.clone()
     }
     
+    /// Returns the node itself for folders, and the folder it is in for files.
+    pub fn file_node_folder(&self, file_node_id: LiveId) -> LiveId {
+        match self.file_nodes.get(&file_node_id) {
+            Some(file_node) if file_node.is_file() => self.file_node_folder_parent(file_node_id),
+            _ => file_node_id
+        }
+    }
+    
+    fn file_node_folder_parent(&self, file_node_id: LiveId) -> LiveId {
+        self.file_nodes.get(&file_node_id)
+            .and_then(|file_node| file_node.parent_edge.as_ref())
+            .map_or(live_id!(root), |edge| edge.file_node_id)
+    }
+    
+    fn child_path(&self, folder_id: LiveId, name: &str) -> String {
+        let folder_path = self.file_node_path(folder_id);
+        if folder_path.is_empty() {
+            name.to_string()
+        }
+        else {
+            format!("{}/{}", folder_path, name)
+        }
+    }
+    
+    // File node ids are derived from paths, so the tabs and documents of renamed files move to
+    // new ids.
+    fn apply_rename(&mut self, old_path: &str, new_path: &str) {
+        let renamed: Vec<(LiveId, LiveId)> = self.path_to_file_node_id.iter()
+            .filter_map(|(path, file_id)| {
+                let rest = child_path_rest(path, old_path)?;
+                Some((*file_id, LiveId::from_str(&format!("{}{}", new_path, rest))))
+            })
+            .collect();
+        for (old_id, new_id) in renamed {
+            for file_id in self.tab_id_to_file_node_id.values_mut() {
+                if *file_id == old_id {
+                    *file_id = new_id;
+                }
+            }
+            if let Some(document) = self.open_documents.remove(&old_id) {
+                self.open_documents.insert(new_id, document);
+            }
+        }
+    }
+    
     pub fn file_node_path(&self, file_node_id: LiveId) -> String {
         let mut path = self.root_path.clone();
         let mut file_node = &self.file_nodes[file_node_id];
@@ -659,10 +825,15 @@ impl FileSystem {
             file_node_id
         }
         
+        for (old_path, new_path) in std::mem::take(&mut self.pending_renames) {
+            self.apply_rename(&old_path, &new_path);
+        }
+        
         self.root_path = tree_data.root_path;
         
         
         self.file_nodes.clear();
+        self.path_to_file_node_id.clear();
         
         create_file_node(
             Some(live_id!(root).into()),
@@ -673,4 +844,15 @@ impl FileSystem {
             tree_data.root,
         );
     }
-}
\ No newline at end of file
+}
+
+// If `path` is `parent_path` or lies inside of it, returns the part of `path` after it.
+fn child_path_rest<'a>(path: &'a str, parent_path: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(parent_path)?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(rest)
+    }
+    else {
+        None
+    }
+}
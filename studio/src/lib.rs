@@ -6,12 +6,14 @@ pub mod build_manager;
 pub mod file_system;
 pub mod studio_editor;
 pub mod studio_file_tree;
+pub mod search_results;
 pub mod log_list;
 pub mod run_list;
 pub mod run_view;
 pub mod profiler;
 pub mod integration;
 pub mod ai_chat;
+pub mod lsp;
 
 //pub use makepad_code_editor;
 
@@ -0,0 +1,395 @@
+use {
+    crate::{
+        makepad_micro_serde::*,
+        makepad_platform::{error, thread::ToUIReceiver},
+        build_manager::child_process::{ChildProcess, ChildStdIO, ChildStdIn, StdOutFraming},
+        lsp::lsp_protocol::*,
+    },
+    std::{
+        path::Path,
+        process::Child,
+        sync::mpsc::Sender,
+        thread,
+    },
+};
+
+/// The command line used to start a language server.
+#[derive(Clone, Debug)]
+pub struct LspServerConfig {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl LspServerConfig {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Parses a command line like `pylsp -v`.
+    pub fn from_command_line(command_line: &str) -> Option<Self> {
+        let mut parts = command_line.split_whitespace().map(|part| part.to_string());
+        Some(Self {
+            command: parts.next()?,
+            args: parts.collect(),
+        })
+    }
+}
+
+/// The parts of the `initialize` result we act on.
+#[derive(Clone, Debug, Default)]
+pub struct LspCapabilities {
+    pub position_encoding: PositionEncoding,
+    pub incremental_sync: bool,
+    pub completion_trigger_characters: Vec<String>,
+    pub has_completion: bool,
+    pub has_hover: bool,
+}
+
+impl LspCapabilities {
+    fn from_json(capabilities: &JsonValue) -> Self {
+        let position_encoding = json_str(capabilities, "positionEncoding")
+            .and_then(PositionEncoding::from_name)
+            .unwrap_or_default();
+        // textDocumentSync is either a TextDocumentSyncKind or TextDocumentSyncOptions
+        let sync_kind = capabilities.key("textDocumentSync").and_then(|sync| match sync {
+            JsonValue::Object(_) => sync.key("change").and_then(json_usize),
+            _ => json_usize(sync),
+        });
+        let completion = capabilities.key("completionProvider");
+        Self {
+            position_encoding,
+            // byte based edits can only be sent as is to servers that count in bytes
+            incremental_sync: sync_kind == Some(2) && position_encoding == PositionEncoding::Utf8,
+            completion_trigger_characters: match completion.and_then(|completion| completion.key("triggerCharacters")) {
+                Some(JsonValue::Array(chars)) => chars.iter().filter_map(|char| char.string().cloned()).collect(),
+                _ => Vec::new()
+            },
+            has_completion: completion.is_some(),
+            has_hover: !matches!(capabilities.key("hoverProvider"), None | Some(JsonValue::Bool(false))),
+        }
+    }
+}
+
+pub enum LspEvent {
+    /// The server finished initializing, and its capabilities are known.
+    Initialized,
+    Notification {method: String, params: JsonValue},
+    Response {id: u64, result: Result<JsonValue, LspError>},
+    Exited,
+}
+
+enum LspClientState {
+    /// Messages are held back until the server has answered `initialize`.
+    Initializing {initialize_id: u64, queue: Vec<String>},
+    Running,
+    Exited,
+}
+
+/// A connection to a language server process, speaking JSON-RPC over its stdin and stdout.
+pub struct LspClient {
+    child: Child,
+    stdin_sender: Sender<ChildStdIn>,
+    message_receiver: ToUIReceiver<Result<LspMessage, ()>>,
+    next_request_id: u64,
+    state: LspClientState,
+    pub capabilities: LspCapabilities,
+}
+
+impl LspClient {
+    pub fn start(config: &LspServerConfig, root_path: &Path) -> Result<Self, std::io::Error> {
+        let ChildProcess {child, stdin_sender, line_receiver, ..} = ChildProcess::start_with_framing(
+            &config.command,
+            &config.args,
+            root_path.to_path_buf(),
+            &[],
+            false,
+            StdOutFraming::ContentLength
+        )?;
+
+        let message_receiver = ToUIReceiver::default();
+        let message_sender = message_receiver.sender();
+        thread::spawn(move || {
+            while let Ok(io) = line_receiver.recv() {
+                match io {
+                    ChildStdIO::StdOut(json) => match LspMessage::from_json(&json) {
+                        Ok(message) => {
+                            if message_sender.send(Ok(message)).is_err() {
+                                return
+                            }
+                        }
+                        Err(err) => {
+                            error!("Cannot parse language server message {}: {}", err, json);
+                        }
+                    }
+                    // servers log to stderr, which we don't show
+                    ChildStdIO::StdErr(_) => (),
+                    ChildStdIO::Term | ChildStdIO::Kill => break
+                }
+            }
+            let _ = message_sender.send(Err(()));
+        });
+
+        let mut client = Self {
+            child,
+            stdin_sender,
+            message_receiver,
+            next_request_id: 1,
+            state: LspClientState::Running,
+            capabilities: LspCapabilities::default(),
+        };
+
+        let root_uri = path_to_uri(root_path);
+        let initialize_id = client.send_request("initialize", json_object(vec![
+            ("processId", JsonValue::U64(std::process::id() as u64)),
+            ("clientInfo", json_object(vec![("name", json_string("makepad-studio"))])),
+            ("rootUri", json_string(&root_uri)),
+            ("workspaceFolders", JsonValue::Array(vec![json_object(vec![
+                ("uri", json_string(&root_uri)),
+                ("name", json_string(&root_path.file_name().map_or(String::new(), |name| name.to_string_lossy().to_string()))),
+            ])])),
+            ("capabilities", json_object(vec![
+                ("general", json_object(vec![
+                    ("positionEncodings", JsonValue::Array(vec![json_string("utf-8"), json_string("utf-16")])),
+                ])),
+                ("textDocument", json_object(vec![
+                    ("synchronization", json_object(vec![])),
+                    ("publishDiagnostics", json_object(vec![])),
+                    ("completion", json_object(vec![
+                        // the editor inserts plain text only
+                        ("completionItem", json_object(vec![("snippetSupport", JsonValue::Bool(false))])),
+                    ])),
+                    ("hover", json_object(vec![
+                        ("contentFormat", JsonValue::Array(vec![json_string("plaintext"), json_string("markdown")])),
+                    ])),
+                ])),
+            ])),
+        ]));
+        client.state = LspClientState::Initializing {initialize_id, queue: Vec::new()};
+        Ok(client)
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, LspClientState::Running)
+    }
+
+    /// Sends a request and returns its id, which the matching `LspEvent::Response` carries.
+    pub fn send_request(&mut self, method: &str, params: JsonValue) -> u64 {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+        self.send(request_json(id, method, params));
+        id
+    }
+
+    pub fn send_notification(&mut self, method: &str, params: JsonValue) {
+        self.send(notification_json(method, params));
+    }
+
+    fn send(&mut self, json: String) {
+        match &mut self.state {
+            LspClientState::Initializing {queue, ..} => queue.push(json),
+            LspClientState::Running => {
+                let _ = self.stdin_sender.send(ChildStdIn::Send(frame_message(&json)));
+            }
+            LspClientState::Exited => ()
+        }
+    }
+
+    fn send_now(&self, json: String) {
+        let _ = self.stdin_sender.send(ChildStdIn::Send(frame_message(&json)));
+    }
+
+    /// Returns the next message from the server that needs handling by the caller. Requests
+    /// from the server and the `initialize` handshake are handled here.
+    pub fn try_recv(&mut self) -> Option<LspEvent> {
+        loop {
+            let message = match self.message_receiver.try_recv() {
+                Ok(Ok(message)) => message,
+                Ok(Err(())) => {
+                    self.state = LspClientState::Exited;
+                    return Some(LspEvent::Exited)
+                }
+                Err(_) => return None
+            };
+            match message {
+                LspMessage::Response {id, result} => {
+                    if let LspClientState::Initializing {initialize_id, queue} = &mut self.state {
+                        if id == *initialize_id {
+                            let queue = std::mem::take(queue);
+                            let result = match result {
+                                Ok(result) => result,
+                                Err(err) => {
+                                    error!("Language server failed to initialize: {}", err.message);
+                                    self.shutdown();
+                                    return Some(LspEvent::Exited)
+                                }
+                            };
+                            if let Some(capabilities) = result.key("capabilities") {
+                                self.capabilities = LspCapabilities::from_json(capabilities);
+                            }
+                            self.state = LspClientState::Running;
+                            self.send_now(notification_json("initialized", json_object(vec![])));
+                            for json in queue {
+                                self.send_now(json);
+                            }
+                            return Some(LspEvent::Initialized)
+                        }
+                    }
+                    return Some(LspEvent::Response {id, result})
+                }
+                LspMessage::Notification {method, params} => {
+                    return Some(LspEvent::Notification {method, params})
+                }
+                LspMessage::Request {id, method, params} => {
+                    let result = match method.as_str() {
+                        // we have no settings, so every requested section is null
+                        "workspace/configuration" => Ok(JsonValue::Array(
+                            match params.key("items") {
+                                Some(JsonValue::Array(items)) => items.iter().map(|_| JsonValue::Null).collect(),
+                                _ => Vec::new()
+                            }
+                        )),
+                        "client/registerCapability" |
+                        "client/unregisterCapability" |
+                        "window/workDoneProgress/create" |
+                        "window/showMessageRequest" => Ok(JsonValue::Null),
+                        _ => Err(LspError {
+                            code: LSP_METHOD_NOT_FOUND,
+                            message: format!("Unsupported method {}", method),
+                        })
+                    };
+                    self.send_now(response_json(id, result));
+                }
+            }
+        }
+    }
+
+    /// Asks the server to exit, and closes its stdin once the messages have been written.
+    pub fn shutdown(&mut self) {
+        if let LspClientState::Exited = self.state {
+            return
+        }
+        if let LspClientState::Running = self.state {
+            self.send_request("shutdown", JsonValue::Null);
+            self.send_notification("exit", JsonValue::Null);
+        }
+        else {
+            let _ = self.child.kill();
+        }
+        let _ = self.stdin_sender.send(ChildStdIn::Term);
+        self.state = LspClientState::Exited;
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::time::{Duration, Instant},
+    };
+
+    /// A fake server that writes `output` up front and records everything it is sent in `log`.
+    fn scripted_server(output: &str, log: &Path) -> LspServerConfig {
+        LspServerConfig {
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                r#"printf '%s' "$1"; cat > "$2""#.to_string(),
+                "sh".to_string(),
+                output.to_string(),
+                log.to_string_lossy().to_string(),
+            ],
+        }
+    }
+
+    fn recv(client: &mut LspClient) -> LspEvent {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Some(event) = client.try_recv() {
+                return event
+            }
+            assert!(Instant::now() < deadline, "no message from the language server");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Splits the recorded input back into messages, as `method` or `response`.
+    fn sent_messages(log: &str) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut rest = log;
+        while let Some(header) = rest.strip_prefix("Content-Length: ") {
+            let (length, body) = header.split_once("\r\n\r\n").unwrap();
+            let (json, next) = body.split_at(length.parse().unwrap());
+            messages.push(match LspMessage::from_json(json).unwrap() {
+                LspMessage::Request {method, ..} | LspMessage::Notification {method, ..} => method,
+                LspMessage::Response {..} => "response".to_string(),
+            });
+            rest = next;
+        }
+        assert!(rest.is_empty(), "unframed input {:?}", rest);
+        messages
+    }
+
+    #[test]
+    fn initialize_handshake() {
+        let dir = std::env::temp_dir().join(format!("makepad_lsp_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("sent.txt");
+        // the server asks for configuration before it answers initialize
+        let output = [
+            request_json(7, "workspace/configuration", json_object(vec![
+                ("items", JsonValue::Array(vec![json_object(vec![])])),
+            ])),
+            response_json(JsonValue::U64(1), Ok(json_object(vec![
+                ("capabilities", json_object(vec![
+                    ("positionEncoding", json_string("utf-8")),
+                    ("textDocumentSync", JsonValue::U64(2)),
+                    ("completionProvider", json_object(vec![
+                        ("triggerCharacters", JsonValue::Array(vec![json_string(".")])),
+                    ])),
+                ])),
+            ]))),
+        ].iter().map(|json| frame_message(json)).collect::<String>();
+
+        let mut client = LspClient::start(&scripted_server(&output, &log), &dir).unwrap();
+        assert!(!client.is_running());
+        // held back until the server is initialized
+        client.send_notification("textDocument/didOpen", json_object(vec![]));
+        assert!(matches!(recv(&mut client), LspEvent::Initialized));
+        assert!(client.is_running());
+        assert_eq!(client.capabilities.position_encoding, PositionEncoding::Utf8);
+        assert!(client.capabilities.incremental_sync);
+        assert!(client.capabilities.has_completion);
+        assert!(!client.capabilities.has_hover);
+        assert_eq!(client.capabilities.completion_trigger_characters, ["."]);
+
+        client.shutdown();
+        let _ = client.child.wait();
+        assert_eq!(sent_messages(&std::fs::read_to_string(&log).unwrap()), [
+            "initialize",
+            "response",
+            "initialized",
+            "textDocument/didOpen",
+            "shutdown",
+            "exit",
+        ]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn server_exit() {
+        // a server that dies without answering
+        let config = LspServerConfig::from_command_line("sh -c true").unwrap();
+        let mut client = LspClient::start(&config, &std::env::temp_dir()).unwrap();
+        assert!(matches!(recv(&mut client), LspEvent::Exited));
+        assert!(!client.is_running());
+    }
+}
@@ -0,0 +1,392 @@
+use {
+    crate::{
+        app::AppAction,
+        file_system::file_system::{EditSession, FileSystem, OpenDocument},
+        lsp::{
+            lsp_client::{LspClient, LspEvent, LspServerConfig},
+            lsp_protocol::*,
+        },
+        makepad_code_editor::{
+            decoration::{Decoration, DecorationType},
+            language::Language,
+            text::{Edit, Position},
+            CodeDocument, CompletionItem,
+        },
+        makepad_micro_serde::*,
+        makepad_widgets::*,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        env,
+        path::{Path, PathBuf},
+        sync::mpsc::Receiver,
+    },
+};
+
+/// The decoration id of diagnostics that come from a language server, so they can be replaced
+/// without touching the diagnostics of the build.
+pub const LSP_DECORATION_ID: usize = 1;
+
+#[derive(DefaultNone, Debug, Clone)]
+pub enum LspAction {
+    Completions {tab_id: LiveId, position: Position, items: Vec<CompletionItem>},
+    Hover {tab_id: LiveId, position: Position, text: String},
+    None
+}
+
+enum LspRequest {
+    Completion {tab_id: LiveId, position: Position},
+    Hover {tab_id: LiveId, file_id: LiveId, position: Position},
+}
+
+struct LspServer {
+    client: LspClient,
+    pending_requests: HashMap<u64, LspRequest>,
+}
+
+struct LspDocument {
+    language_name: &'static str,
+    uri: String,
+    version: u64,
+    edit_receiver: Receiver<Vec<Edit>>,
+    diagnostics: Vec<LspDiagnostic>,
+}
+
+/// Runs a language server per language, keeps it in sync with the open code documents, and
+/// turns what it sends back into decorations, completions and hovers.
+#[derive(Default)]
+pub struct LspManager {
+    is_enabled: bool,
+    root_path: PathBuf,
+    /// Server command lines by lowercase language name.
+    configs: HashMap<String, LspServerConfig>,
+    servers: HashMap<&'static str, LspServer>,
+    /// Languages whose server could not be started or exited, which we don't retry.
+    failed_languages: HashSet<&'static str>,
+    documents: HashMap<LiveId, LspDocument>,
+}
+
+impl LspManager {
+    /// Servers are configured on the command line with `--lsp=<language>:<command line>`, like
+    /// `--lsp=python:pylsp`. An empty command line disables a language, `--no-lsp` disables all.
+    pub fn init(&mut self, root_path: &Path) {
+        self.is_enabled = true;
+        self.root_path = root_path.canonicalize().unwrap_or(root_path.to_path_buf());
+        self.set_server_config("rust", LspServerConfig::from_command_line("rust-analyzer"));
+        for arg in env::args() {
+            if arg == "--no-lsp" {
+                self.is_enabled = false;
+            }
+            else if let Some((language_name, command_line)) = arg.strip_prefix("--lsp=").and_then(|arg| arg.split_once(':')) {
+                self.set_server_config(language_name, LspServerConfig::from_command_line(command_line));
+            }
+        }
+    }
+
+    pub fn set_server_config(&mut self, language_name: &str, config: Option<LspServerConfig>) {
+        let language_name = language_name.to_lowercase();
+        match config {
+            Some(config) => self.configs.insert(language_name, config),
+            None => self.configs.remove(&language_name),
+        };
+    }
+
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &Event, file_system: &mut FileSystem) {
+        if !self.is_enabled {
+            return
+        }
+        if let Event::Signal = event {
+            self.close_stale_documents(file_system);
+            self.open_new_documents(file_system);
+            self.handle_server_events(cx, file_system);
+        }
+        self.send_document_changes(file_system);
+    }
+
+    /// Called when the text of the editor in `tab_id` changed, to request completions after
+    /// typing a trigger character like `.`.
+    pub fn handle_text_did_change(&mut self, file_system: &mut FileSystem, tab_id: LiveId) {
+        let Some(EditSession::Code(session)) = file_system.get_session_mut(tab_id) else {
+            return
+        };
+        let Some(selection_index) = session.last_added_selection_index() else {
+            return
+        };
+        let position = session.selections()[selection_index].cursor.position;
+        let prev_char = session.document().as_text().as_lines()[position.line_index][..position.byte_index]
+            .chars()
+            .next_back();
+        let Some(prev_char) = prev_char else {
+            return
+        };
+        let is_trigger = self.server_for_tab(file_system, tab_id).map_or(false, |(server, _)| {
+            server.client.capabilities.completion_trigger_characters.iter().any(|trigger| trigger.starts_with(prev_char))
+        });
+        if is_trigger {
+            self.request_completion(file_system, tab_id, position);
+        }
+    }
+
+    pub fn request_completion(&mut self, file_system: &FileSystem, tab_id: LiveId, position: Position) {
+        self.send_document_changes(file_system);
+        let Some((server, params)) = self.position_params(file_system, tab_id, position) else {
+            return
+        };
+        if !server.client.capabilities.has_completion {
+            return
+        }
+        let id = server.client.send_request("textDocument/completion", params);
+        server.pending_requests.insert(id, LspRequest::Completion {tab_id, position});
+    }
+
+    /// Requests hover information from the server. Diagnostics at the position are shown even
+    /// without a server that supports hovers.
+    pub fn request_hover(&mut self, cx: &mut Cx, file_system: &FileSystem, tab_id: LiveId, position: Position) {
+        self.send_document_changes(file_system);
+        let Some(&file_id) = file_system.tab_id_to_file_node_id.get(&tab_id) else {
+            return
+        };
+        if let Some((server, params)) = self.position_params(file_system, tab_id, position) {
+            if server.client.capabilities.has_hover {
+                let id = server.client.send_request("textDocument/hover", params);
+                server.pending_requests.insert(id, LspRequest::Hover {tab_id, file_id, position});
+                return
+            }
+        }
+        if let Some(text) = self.hover_text(file_id, position, None) {
+            cx.action(LspAction::Hover {tab_id, position, text});
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        for server in self.servers.values_mut() {
+            server.client.shutdown();
+        }
+        self.servers.clear();
+    }
+
+    fn server_for_tab(&mut self, file_system: &FileSystem, tab_id: LiveId) -> Option<(&mut LspServer, &LspDocument)> {
+        let file_id = file_system.tab_id_to_file_node_id.get(&tab_id)?;
+        let document = self.documents.get(file_id)?;
+        Some((self.servers.get_mut(document.language_name)?, document))
+    }
+
+    fn position_params(&mut self, file_system: &FileSystem, tab_id: LiveId, position: Position) -> Option<(&mut LspServer, JsonValue)> {
+        let file_id = *file_system.tab_id_to_file_node_id.get(&tab_id)?;
+        let Some(OpenDocument::Code(code_document)) = file_system.open_documents.get(&file_id) else {
+            return None
+        };
+        let (server, document) = self.server_for_tab(file_system, tab_id)?;
+        let encoding = server.client.capabilities.position_encoding;
+        let params = json_object(vec![
+            ("textDocument", json_object(vec![("uri", json_string(&document.uri))])),
+            ("position", encoding.position_to_json(code_document.as_text().as_lines(), position)),
+        ]);
+        Some((server, params))
+    }
+
+    fn open_new_documents(&mut self, file_system: &FileSystem) {
+        let mut new_documents = Vec::new();
+        for (file_id, document) in &file_system.open_documents {
+            if let OpenDocument::Code(document) = document {
+                if !self.documents.contains_key(file_id) {
+                    new_documents.push((*file_id, document.clone()));
+                }
+            }
+        }
+        for (file_id, document) in new_documents {
+            self.open_document(file_system, file_id, document);
+        }
+    }
+
+    // Documents of files that were renamed or deleted are closed. A renamed file is opened again
+    // under its new uri by `open_new_documents`.
+    fn close_stale_documents(&mut self, file_system: &FileSystem) {
+        let stale_ids: Vec<LiveId> = self.documents.keys()
+            .filter(|file_id| !file_system.open_documents.contains_key(file_id))
+            .cloned()
+            .collect();
+        for file_id in stale_ids {
+            let document = self.documents.remove(&file_id).unwrap();
+            if let Some(server) = self.servers.get_mut(document.language_name) {
+                server.client.send_notification("textDocument/didClose", json_object(vec![
+                    ("textDocument", json_object(vec![("uri", json_string(&document.uri))])),
+                ]));
+            }
+        }
+    }
+
+    fn open_document(&mut self, file_system: &FileSystem, file_id: LiveId, document: CodeDocument) {
+        let language = document.language();
+        let uri = path_to_uri(&self.root_path.join(file_system.file_node_path(file_id)));
+        let Some(server) = self.server_for_language(language) else {
+            return
+        };
+        server.client.send_notification("textDocument/didOpen", json_object(vec![
+            ("textDocument", json_object(vec![
+                ("uri", json_string(&uri)),
+                ("languageId", json_string(&language.name().to_lowercase())),
+                ("version", JsonValue::U64(0)),
+                ("text", JsonValue::String(document.as_text().to_string())),
+            ])),
+        ]));
+        self.documents.insert(file_id, LspDocument {
+            language_name: language.name(),
+            uri,
+            version: 0,
+            edit_receiver: document.subscribe_to_edits(),
+            diagnostics: Vec::new(),
+        });
+    }
+
+    fn server_for_language(&mut self, language: &'static dyn Language) -> Option<&mut LspServer> {
+        let name = language.name();
+        if !self.servers.contains_key(name) {
+            if self.failed_languages.contains(name) {
+                return None
+            }
+            let config = self.configs.get(&name.to_lowercase())?;
+            match LspClient::start(config, &self.root_path) {
+                Ok(client) => {
+                    self.servers.insert(name, LspServer {
+                        client,
+                        pending_requests: HashMap::new(),
+                    });
+                }
+                Err(err) => {
+                    log!("Cannot start language server {} for {}: {}", config.command, name, err);
+                    self.failed_languages.insert(name);
+                    return None
+                }
+            }
+        }
+        self.servers.get_mut(name)
+    }
+
+    fn send_document_changes(&mut self, file_system: &FileSystem) {
+        for (file_id, document) in &mut self.documents {
+            let mut edits = Vec::new();
+            while let Ok(new_edits) = document.edit_receiver.try_recv() {
+                edits.extend(new_edits);
+            }
+            if edits.is_empty() {
+                continue
+            }
+            let Some(server) = self.servers.get_mut(document.language_name) else {
+                continue
+            };
+            let content_changes = if server.client.capabilities.incremental_sync {
+                edits_to_content_changes(&edits)
+            }
+            else {
+                let Some(OpenDocument::Code(code_document)) = file_system.open_documents.get(file_id) else {
+                    continue
+                };
+                vec![json_object(vec![("text", JsonValue::String(code_document.as_text().to_string()))])]
+            };
+            document.version += 1;
+            server.client.send_notification("textDocument/didChange", json_object(vec![
+                ("textDocument", json_object(vec![
+                    ("uri", json_string(&document.uri)),
+                    ("version", JsonValue::U64(document.version)),
+                ])),
+                ("contentChanges", JsonValue::Array(content_changes)),
+            ]));
+        }
+    }
+
+    fn handle_server_events(&mut self, cx: &mut Cx, file_system: &mut FileSystem) {
+        let language_names: Vec<&'static str> = self.servers.keys().cloned().collect();
+        for language_name in language_names {
+            loop {
+                let Some(server) = self.servers.get_mut(language_name) else {
+                    break
+                };
+                let Some(event) = server.client.try_recv() else {
+                    break
+                };
+                match event {
+                    LspEvent::Initialized => (),
+                    LspEvent::Notification {method, params} => {
+                        if method == "textDocument/publishDiagnostics" {
+                            let encoding = server.client.capabilities.position_encoding;
+                            self.handle_diagnostics(cx, file_system, encoding, &params);
+                        }
+                    }
+                    LspEvent::Response {id, result} => {
+                        let Some(request) = server.pending_requests.remove(&id) else {
+                            continue
+                        };
+                        self.handle_response(cx, request, result);
+                    }
+                    LspEvent::Exited => {
+                        log!("Language server for {} exited", language_name);
+                        self.servers.remove(language_name);
+                        self.failed_languages.insert(language_name);
+                        self.documents.retain(|_, document| document.language_name != language_name);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_diagnostics(&mut self, cx: &mut Cx, file_system: &mut FileSystem, encoding: PositionEncoding, params: &JsonValue) {
+        let Some(uri) = json_str(params, "uri") else {
+            return
+        };
+        let Some((&file_id, document)) = self.documents.iter_mut().find(|(_, document)| document.uri == uri) else {
+            return
+        };
+        let Some(OpenDocument::Code(code_document)) = file_system.open_documents.get(&file_id) else {
+            return
+        };
+        document.diagnostics = parse_diagnostics(encoding, code_document.as_text().as_lines(), params);
+        file_system.remove_decorations_with_id(&file_id, LSP_DECORATION_ID);
+        for diagnostic in &document.diagnostics {
+            let ty = match diagnostic.severity {
+                LspSeverity::Error => DecorationType::Error,
+                LspSeverity::Warning => DecorationType::Warning,
+                LspSeverity::Information | LspSeverity::Hint => continue,
+            };
+            file_system.add_decoration(
+                file_id,
+                Decoration::new(LSP_DECORATION_ID, diagnostic.start, diagnostic.end, ty)
+            );
+        }
+        cx.action(AppAction::RedrawFile(file_id));
+    }
+
+    fn handle_response(&mut self, cx: &mut Cx, request: LspRequest, result: Result<JsonValue, LspError>) {
+        match request {
+            LspRequest::Completion {tab_id, position} => {
+                let items = result.map_or(Vec::new(), |result| parse_completion_items(&result));
+                if !items.is_empty() {
+                    cx.action(LspAction::Completions {tab_id, position, items});
+                }
+            }
+            LspRequest::Hover {tab_id, file_id, position} => {
+                let hover_text = result.ok().and_then(|result| parse_hover_text(&result));
+                if let Some(text) = self.hover_text(file_id, position, hover_text) {
+                    cx.action(LspAction::Hover {tab_id, position, text});
+                }
+            }
+        }
+    }
+
+    /// Puts the messages of the diagnostics at `position` in front of the hover text.
+    fn hover_text(&self, file_id: LiveId, position: Position, hover_text: Option<String>) -> Option<String> {
+        let mut lines: Vec<String> = self.documents.get(&file_id).map_or(Vec::new(), |document| {
+            document.diagnostics.iter()
+                .filter(|diagnostic| diagnostic.start <= position && position <= diagnostic.end)
+                .map(|diagnostic| diagnostic.message.clone())
+                .collect()
+        });
+        lines.extend(hover_text);
+        if lines.is_empty() {
+            None
+        }
+        else {
+            Some(lines.join("\n\n"))
+        }
+    }
+}
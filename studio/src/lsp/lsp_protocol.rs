@@ -0,0 +1,337 @@
+use {
+    crate::{
+        makepad_micro_serde::*,
+        makepad_code_editor::{
+            text::{Change, Edit, Position},
+            CompletionItem,
+        },
+    },
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+// JSON-RPC messages as used by the language server protocol. We work on `JsonValue` trees
+// rather than derived types, because servers send a lot of fields we don't know about.
+
+#[derive(Clone, Debug)]
+pub enum LspMessage {
+    Request {id: JsonValue, method: String, params: JsonValue},
+    Response {id: u64, result: Result<JsonValue, LspError>},
+    Notification {method: String, params: JsonValue},
+}
+
+#[derive(Clone, Debug)]
+pub struct LspError {
+    pub code: i64,
+    pub message: String,
+}
+
+pub const LSP_METHOD_NOT_FOUND: i64 = -32601;
+
+impl LspMessage {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value = JsonValue::deserialize_json(json).map_err(|err| format!("{:?}", err))?;
+        let method = value.key("method").and_then(|method| method.string()).cloned();
+        let params = value.key("params").cloned().unwrap_or(JsonValue::Null);
+        match (value.key("id"), method) {
+            (Some(id), Some(method)) => Ok(LspMessage::Request {id: id.clone(), method, params}),
+            (None, Some(method)) => Ok(LspMessage::Notification {method, params}),
+            (Some(id), None) => {
+                // we only ever send numeric ids
+                let id = json_usize(id).ok_or_else(|| format!("Unexpected response id {:?}", id))? as u64;
+                let result = match value.key("error") {
+                    Some(error) => Err(LspError {
+                        code: match error.key("code") {
+                            Some(JsonValue::I64(code)) => *code,
+                            Some(JsonValue::U64(code)) => *code as i64,
+                            _ => 0
+                        },
+                        message: json_str(error, "message").unwrap_or("").to_string(),
+                    }),
+                    None => Ok(value.key("result").cloned().unwrap_or(JsonValue::Null))
+                };
+                Ok(LspMessage::Response {id, result})
+            }
+            (None, None) => Err("Message has neither an id nor a method".to_string())
+        }
+    }
+}
+
+pub fn request_json(id: u64, method: &str, params: JsonValue) -> String {
+    json_object(vec![
+        ("jsonrpc", json_string("2.0")),
+        ("id", JsonValue::U64(id)),
+        ("method", json_string(method)),
+        ("params", params),
+    ]).serialize_json()
+}
+
+pub fn notification_json(method: &str, params: JsonValue) -> String {
+    json_object(vec![
+        ("jsonrpc", json_string("2.0")),
+        ("method", json_string(method)),
+        ("params", params),
+    ]).serialize_json()
+}
+
+pub fn response_json(id: JsonValue, result: Result<JsonValue, LspError>) -> String {
+    let mut fields = vec![
+        ("jsonrpc", json_string("2.0")),
+        ("id", id),
+    ];
+    match result {
+        Ok(result) => fields.push(("result", result)),
+        Err(error) => fields.push(("error", json_object(vec![
+            ("code", JsonValue::I64(error.code)),
+            ("message", json_string(&error.message)),
+        ]))),
+    }
+    json_object(fields).serialize_json()
+}
+
+/// Wraps a serialized message in the header the protocol puts in front of every message.
+pub fn frame_message(json: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", json.len(), json)
+}
+
+pub fn json_object(fields: Vec<(&str, JsonValue)>) -> JsonValue {
+    JsonValue::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect::<HashMap<_, _>>())
+}
+
+pub fn json_string(value: &str) -> JsonValue {
+    JsonValue::String(value.to_string())
+}
+
+pub fn json_str<'a>(value: &'a JsonValue, key: &str) -> Option<&'a str> {
+    value.key(key).and_then(|value| value.string()).map(|value| value.as_str())
+}
+
+pub fn json_usize(value: &JsonValue) -> Option<usize> {
+    match value {
+        JsonValue::U64(value) => Some(*value as usize),
+        JsonValue::I64(value) if *value >= 0 => Some(*value as usize),
+        JsonValue::F64(value) if *value >= 0.0 => Some(*value as usize),
+        _ => None
+    }
+}
+
+fn json_array(value: Option<&JsonValue>) -> &[JsonValue] {
+    match value {
+        Some(JsonValue::Array(values)) => values,
+        _ => &[]
+    }
+}
+
+/// The unit in which a server counts the `character` of a position. Servers that don't
+/// announce anything use UTF-16 code units.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+}
+
+impl PositionEncoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "utf-8" => Some(Self::Utf8),
+            "utf-16" => Some(Self::Utf16),
+            _ => None
+        }
+    }
+
+    pub fn position_to_json(self, lines: &[String], position: Position) -> JsonValue {
+        let character = match self {
+            Self::Utf8 => position.byte_index,
+            Self::Utf16 => lines.get(position.line_index).map_or(position.byte_index, |line| {
+                line[..position.byte_index.min(line.len())].encode_utf16().count()
+            })
+        };
+        json_object(vec![
+            ("line", JsonValue::U64(position.line_index as u64)),
+            ("character", JsonValue::U64(character as u64)),
+        ])
+    }
+
+    /// Lines we don't have the text of are assumed to be ASCII.
+    pub fn position_from_json(self, lines: &[String], value: &JsonValue) -> Option<Position> {
+        let line_index = json_usize(value.key("line")?)?;
+        let character = json_usize(value.key("character")?)?;
+        let byte_index = match (self, lines.get(line_index)) {
+            (Self::Utf8, Some(line)) => character.min(line.len()),
+            (Self::Utf16, Some(line)) => {
+                let mut count = 0;
+                line.char_indices()
+                    .find(|(_, char)| {
+                        count += char.len_utf16();
+                        count > character
+                    })
+                    .map_or(line.len(), |(index, _)| index)
+            }
+            (_, None) => character
+        };
+        Some(Position {line_index, byte_index})
+    }
+
+    pub fn range_to_json(self, lines: &[String], start: Position, end: Position) -> JsonValue {
+        json_object(vec![
+            ("start", self.position_to_json(lines, start)),
+            ("end", self.position_to_json(lines, end)),
+        ])
+    }
+
+    pub fn range_from_json(self, lines: &[String], value: &JsonValue) -> Option<(Position, Position)> {
+        Some((
+            self.position_from_json(lines, value.key("start")?)?,
+            self.position_from_json(lines, value.key("end")?)?,
+        ))
+    }
+}
+
+/// Converts the edits of a document into `contentChanges` for an incremental
+/// `textDocument/didChange`. Edit positions are byte offsets, so this is only valid for
+/// servers that use `PositionEncoding::Utf8`.
+pub fn edits_to_content_changes(edits: &[Edit]) -> Vec<JsonValue> {
+    edits.iter().map(|edit| {
+        let (start, end, text) = match &edit.change {
+            Change::Insert(position, text) => (*position, *position, text.to_string()),
+            Change::Delete(position, length) => (*position, *position + *length, String::new()),
+        };
+        json_object(vec![
+            ("range", PositionEncoding::Utf8.range_to_json(&[], start, end)),
+            ("text", JsonValue::String(text)),
+        ])
+    }).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LspSeverity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+#[derive(Clone, Debug)]
+pub struct LspDiagnostic {
+    pub start: Position,
+    pub end: Position,
+    pub severity: LspSeverity,
+    pub message: String,
+}
+
+pub fn parse_diagnostics(encoding: PositionEncoding, lines: &[String], params: &JsonValue) -> Vec<LspDiagnostic> {
+    json_array(params.key("diagnostics")).iter().filter_map(|diagnostic| {
+        let (start, end) = encoding.range_from_json(lines, diagnostic.key("range")?)?;
+        let severity = match diagnostic.key("severity").and_then(json_usize) {
+            Some(2) => LspSeverity::Warning,
+            Some(3) => LspSeverity::Information,
+            Some(4) => LspSeverity::Hint,
+            _ => LspSeverity::Error,
+        };
+        Some(LspDiagnostic {
+            start,
+            end,
+            severity,
+            message: json_str(diagnostic, "message").unwrap_or("").to_string(),
+        })
+    }).collect()
+}
+
+/// Parses the result of `textDocument/completion`, which is either a list of items or an
+/// object with an `items` field.
+pub fn parse_completion_items(result: &JsonValue) -> Vec<CompletionItem> {
+    let items = match result {
+        JsonValue::Array(items) => items.as_slice(),
+        _ => json_array(result.key("items"))
+    };
+    items.iter().filter_map(|item| {
+        let label = json_str(item, "label")?.to_string();
+        let insert_text = item.key("textEdit")
+            .and_then(|edit| json_str(edit, "newText"))
+            .or_else(|| json_str(item, "insertText"))
+            .unwrap_or(&label)
+            .to_string();
+        Some(CompletionItem {
+            detail: json_str(item, "detail").map(|detail| detail.to_string()),
+            label,
+            insert_text,
+        })
+    }).collect()
+}
+
+/// Parses the result of `textDocument/hover` into plain text. Markdown code fences are
+/// dropped, but their contents are kept.
+pub fn parse_hover_text(result: &JsonValue) -> Option<String> {
+    fn push_contents(contents: &JsonValue, text: &mut String) {
+        match contents {
+            JsonValue::String(value) => push_markdown(value, text),
+            JsonValue::Array(values) => for value in values {
+                push_contents(value, text);
+            }
+            _ => if let Some(value) = json_str(contents, "value") {
+                push_markdown(value, text)
+            }
+        }
+    }
+
+    fn push_markdown(markdown: &str, text: &mut String) {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        for line in markdown.lines() {
+            if !line.trim_start().starts_with("```") {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+
+    let mut text = String::new();
+    push_contents(result.key("contents")?, &mut text);
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    }
+    else {
+        Some(text.to_string())
+    }
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::new();
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        }
+        else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // file:///C:/dir on windows
+    if cfg!(windows) && path.as_bytes().get(2) == Some(&b':') {
+        return Some(PathBuf::from(&path[1..]))
+    }
+    Some(PathBuf::from(path))
+}
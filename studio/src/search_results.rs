@@ -0,0 +1,173 @@
+use {
+    crate::{
+        makepad_platform::studio::JumpToFile,
+        app::{AppAction, AppData},
+        file_system::file_system::FileSystem,
+        makepad_widgets::*,
+    },
+};
+
+live_design!{
+    import makepad_draw::shader::std::*;
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    SearchItem = <View> {
+        height: Fit, width: Fill
+        padding: <THEME_MSPACE_1> {}
+        show_bg: true,
+        draw_bg: {
+            instance is_even: 0.0
+            fn pixel(self) -> vec4 {
+                return mix(THEME_COLOR_BG_EVEN, THEME_COLOR_BG_ODD, self.is_even);
+            }
+        }
+        flow = <TextFlow>{
+            width: Fill,
+            height: Fit
+        }
+    }
+
+    SearchResults = {{SearchResults}}{
+        height: Fill, width: Fill,
+        list = <PortalList> {
+            grab_key_focus: false
+            drag_scrolling: false
+            height: Fill, width: Fill,
+            flow: Down
+            FileItem = <SearchItem> {
+                padding: {left: (THEME_SPACE_2), top: (THEME_SPACE_2), right: (THEME_SPACE_2), bottom: (THEME_SPACE_1)}
+            }
+            ResultItem = <SearchItem> {
+                padding: {left: (THEME_SPACE_3 * 2), top: (THEME_SPACE_1), right: (THEME_SPACE_2), bottom: (THEME_SPACE_1)}
+            }
+            Status = <SearchItem> {
+                padding: <THEME_MSPACE_2> {}
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResultLink{index: usize}
+
+// A row in the list is either the path above the matches in a file, or a match.
+enum SearchRow {
+    File(usize),
+    Result(usize),
+}
+
+#[derive(Live, LiveHook, Widget)]
+pub struct SearchResults{
+    #[deref] view: View
+}
+
+impl SearchResults{
+    fn draw_results(&mut self, cx: &mut Cx2d, list: &mut PortalList, file_system: &FileSystem){
+        let mut rows = Vec::new();
+        for (index, result) in file_system.search_results.iter().enumerate(){
+            if index == 0 || file_system.search_results[index - 1].path != result.path{
+                rows.push(SearchRow::File(index));
+            }
+            rows.push(SearchRow::Result(index));
+        }
+        // the last row says whether the search is still running
+        list.set_item_range(cx, 0, rows.len() + 1);
+        let mut label = String::new();
+        while let Some(item_id) = list.next_visible_item(cx){
+            let is_even = if item_id & 1 == 0 {1.0} else {0.0};
+            let Some(row) = rows.get(item_id) else{
+                if item_id > rows.len(){
+                    continue
+                }
+                let mut item = list.item(cx, item_id, live_id!(Status)).as_view();
+                item.apply_over(cx, live!{draw_bg: {is_even: (is_even)}});
+                while let Some(step) = item.draw(cx, &mut Scope::empty()).step(){
+                    if let Some(mut tf) = step.as_text_flow().borrow_mut(){
+                        let files = rows.len() - file_system.search_results.len();
+                        if file_system.search_is_running{
+                            fmt_over!(label, "Searching... {} results in {} files", file_system.search_results.len(), files);
+                        }
+                        else{
+                            fmt_over!(label, "{} results in {} files", file_system.search_results.len(), files);
+                        }
+                        tf.draw_text(cx, &label);
+                    }
+                }
+                continue
+            };
+            match *row{
+                SearchRow::File(index) => {
+                    let mut item = list.item(cx, item_id, live_id!(FileItem)).as_view();
+                    item.apply_over(cx, live!{draw_bg: {is_even: (is_even)}});
+                    while let Some(step) = item.draw(cx, &mut Scope::empty()).step(){
+                        if let Some(mut tf) = step.as_text_flow().borrow_mut(){
+                            tf.bold.push();
+                            tf.draw_text(cx, &file_system.search_results[index].path);
+                            tf.bold.pop();
+                        }
+                    }
+                }
+                SearchRow::Result(index) => {
+                    let result = &file_system.search_results[index];
+                    let mut item = list.item(cx, item_id, live_id!(ResultItem)).as_view();
+                    item.apply_over(cx, live!{draw_bg: {is_even: (is_even)}});
+                    while let Some(step) = item.draw(cx, &mut Scope::empty()).step(){
+                        if let Some(mut tf) = step.as_text_flow().borrow_mut(){
+                            fmt_over!(label, "{}:", result.line + 1);
+                            tf.draw_link(cx, live_id!(link), SearchResultLink{index}, &label);
+                            let text = &result.line_text;
+                            let start = (result.start as usize).min(text.len());
+                            let end = (result.end as usize).clamp(start, text.len());
+                            tf.draw_text(cx, text[..start].trim_start());
+                            tf.bold.push();
+                            tf.draw_text(cx, &text[start..end]);
+                            tf.bold.pop();
+                            tf.draw_text(cx, &text[end..]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Widget for SearchResults {
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope:&mut Scope, walk:Walk)->DrawStep{
+        while let Some(step) = self.view.draw_walk(cx, scope, walk).step(){
+            if let Some(mut list) = step.as_portal_list().borrow_mut(){
+                self.draw_results(cx, &mut *list, &scope.data.get::<AppData>().unwrap().file_system)
+            }
+        }
+        DrawStep::done()
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        let list = self.view.portal_list(id!(list));
+        self.view.handle_event(cx, event, scope);
+        let data = scope.data.get::<AppData>().unwrap();
+        if let Event::Actions(actions) = event{
+            if list.any_items_with_actions(&actions){
+                for link in actions.filter_actions_data::<SearchResultLink>(){
+                    if let Some(result) = data.file_system.search_results.get(link.index){
+                        cx.action(AppAction::JumpTo(JumpToFile{
+                            file_name: result.path.clone(),
+                            line: result.line as u32,
+                            column: result.column as u32
+                        }));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl SearchResultsRef{
+    pub fn reset_scroll(&self, cx:&mut Cx){
+        if let Some(inner) = self.borrow_mut() {
+            let list = inner.view.portal_list(id!(list));
+            list.set_first_id_and_scroll(0,0.0);
+            list.redraw(cx);
+        }
+    }
+}
@@ -1,23 +1,75 @@
-
 use {
     crate::{
         app::{AppData},
         makepad_widgets::*,
-        makepad_widgets::file_tree::FileTree,
+        makepad_widgets::file_tree::{FileTree, FileTreeAction},
+        makepad_widgets::popup_menu::{PopupMenu, PopupMenuAction},
     },
 };
 
 live_design!{
     import makepad_widgets::theme_desktop_dark::*;
-        
+
     StudioFileTree = {{StudioFileTree}}{
         file_tree: <FileTree>{}
+        context_menu: <PopupMenu>{}
     }
 }
- 
-#[derive(Live, LiveHook, Widget)] 
+
+#[derive(Live, LiveHook, Widget)]
 pub struct StudioFileTree{
-    #[wrap] #[live] pub file_tree: FileTree
+    #[wrap] #[live] pub file_tree: FileTree,
+    #[live] context_menu: PopupMenu,
+    // The node the context menu is open for, and where it was opened
+    #[rust] context_menu_node: Option<(LiveId, DVec2)>,
+}
+
+/// The file operations picked from the context menu. They carry the folder to create in, or
+/// the node to rename or delete, and are handled by the app, which asks for a name or a
+/// confirmation first.
+#[derive(Clone, Debug, DefaultNone)]
+pub enum StudioFileTreeAction{
+    NewFile(LiveId),
+    NewFolder(LiveId),
+    Rename(LiveId),
+    Delete(LiveId),
+    None
+}
+
+impl StudioFileTree{
+    fn close_context_menu(&mut self, cx: &mut Cx){
+        self.context_menu_node = None;
+        self.context_menu.redraw(cx);
+    }
+
+    fn handle_context_menu(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
+        let Some((node_id, _)) = self.context_menu_node else{
+            return
+        };
+        let mut selected = None;
+        self.context_menu.handle_event_with(cx, event, self.file_tree.area(), &mut |_, action|{
+            if let PopupMenuAction::WasSelected(item_id) = action{
+                selected = Some(item_id.0);
+            }
+        });
+        if let Some(item_id) = selected{
+            let file_system = &scope.data.get::<AppData>().unwrap().file_system;
+            let folder_id = file_system.file_node_folder(node_id);
+            cx.action(match item_id{
+                live_id!(new_file) => StudioFileTreeAction::NewFile(folder_id),
+                live_id!(new_folder) => StudioFileTreeAction::NewFolder(folder_id),
+                live_id!(rename) => StudioFileTreeAction::Rename(node_id),
+                live_id!(delete) => StudioFileTreeAction::Delete(node_id),
+                _ => StudioFileTreeAction::None
+            });
+            self.close_context_menu(cx);
+        }
+        else if let Event::MouseDown(e) = event{
+            if !self.context_menu.menu_contains_pos(cx, e.abs){
+                self.close_context_menu(cx);
+            }
+        }
+    }
 }
 
 impl Widget for StudioFileTree {
@@ -30,10 +82,61 @@ impl Widget for StudioFileTree {
                 &mut self.file_tree
             );
         }
+        if let Some((node_id, abs)) = self.context_menu_node{
+            self.context_menu.begin(cx);
+            self.context_menu.draw_item(cx, live_id!(new_file).into(), "New File");
+            self.context_menu.draw_item(cx, live_id!(new_folder).into(), "New Folder");
+            if node_id != live_id!(root){
+                self.context_menu.draw_item(cx, live_id!(rename).into(), "Rename");
+                self.context_menu.draw_item(cx, live_id!(delete).into(), "Delete");
+            }
+            let area = self.file_tree.area();
+            let shift = abs - area.rect(cx).pos;
+            self.context_menu.end(cx, area, shift);
+        }
         DrawStep::done()
     }
-    
+
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope){
-        self.file_tree.handle_event(cx, event, scope);
+        self.handle_context_menu(cx, event, scope);
+
+        // Actions of the inner tree are passed on as our own, so they can be found by our uid.
+        let uid = self.widget_uid();
+        let file_tree_uid = self.file_tree.widget_uid();
+        let mut actions = cx.capture_actions(|cx| self.file_tree.handle_event(cx, event, scope));
+        for action in &mut actions{
+            let Some(action) = action.downcast_mut::<WidgetAction>() else{
+                continue
+            };
+            if action.widget_uid != file_tree_uid{
+                continue
+            }
+            action.widget_uid = uid;
+            let file_system = &mut scope.data.get_mut::<AppData>().unwrap().file_system;
+            match action.cast(){
+                FileTreeAction::ContextMenu(node_id, abs) => {
+                    self.context_menu_node = Some((node_id, abs));
+                    self.context_menu.redraw(cx);
+                }
+                FileTreeAction::ShouldFileStartDrag(node_id) => {
+                    let path = file_system.file_node_path(node_id);
+                    self.file_tree.start_dragging_file_node(cx, node_id, vec![DragItem::FilePath{
+                        path,
+                        internal_id: None
+                    }]);
+                }
+                FileTreeAction::DroppedOnFolder{node_id, folder_id} => {
+                    file_system.request_move_file(node_id, folder_id);
+                }
+                _ => ()
+            }
+        }
+        cx.extend_actions(actions);
     }
-}
\ No newline at end of file
+}
+
+impl StudioFileTreeRef{
+    pub fn selected_node(&self) -> Option<LiveId> {
+        self.borrow().and_then(|inner| inner.file_tree.selected_node())
+    }
+}
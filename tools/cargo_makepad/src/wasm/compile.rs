@@ -1,6 +1,7 @@
 use crate::makepad_shell::*;
 use crate::utils::*;
 use crate::makepad_http::server::*;
+use crate::makepad_http::router::*;
 use crate::makepad_wasm_strip::*;
 use std::{
     io::prelude::*,
@@ -308,64 +309,16 @@ pub fn start_wasm_server(root:PathBuf, lan:bool, port: u16) {
     });
     
     std::thread::spawn(move || {
-
+        let mut router = HttpRouter::default();
+        router.get("/$watch", |_| HttpServerResponse::new(200, "Cache-Control: max-age:0\r\n", Vec::new()));
+        router.get("/favicon.ico", |_| HttpServerResponse::new(200, "Cache-Control: max-age:0\r\n", Vec::new()));
+        let mut static_dir = HttpStaticDir::new(root);
+        static_dir.extra_headers = "Cross-Origin-Embedder-Policy: require-corp\r\nCross-Origin-Opener-Policy: same-origin\r\n".to_string();
+        static_dir.extensions = Some(WEB_ASSET_EXTENSIONS);
+        router.static_dir("/", static_dir);
         while let Ok(message) = rx_request.recv() {
-            // only store last change, fix later
-            match message {
-                HttpServerRequest::ConnectWebSocket {..} => {},
-                HttpServerRequest::DisconnectWebSocket {..} => {},
-                HttpServerRequest::BinaryMessage {..} => {}
-                HttpServerRequest::Get {headers, response_sender} => {
-                    let path = &headers.path;
-                    
-                    // alright wasm http server
-                    if path == "/$watch" || path == "/favicon.ico" {
-                        let header = "HTTP/1.1 200 OK\r\n\
-                        Cache-Control: max-age:0\r\n\
-                        Connection: close\r\n\r\n".to_string();
-                        let _ = response_sender.send(HttpServerResponse {header, body: vec![]});
-                        continue
-                    }
-                                            
-                    let mime_type = if path.ends_with(".html") {"text/html"}
-                    else if path.ends_with(".wasm") {"application/wasm"}
-                    else if path.ends_with(".css") {"text/css"}
-                    else if path.ends_with(".js") {"text/javascript"}
-                    else if path.ends_with(".ttf") {"application/ttf"}
-                    else if path.ends_with(".png") {"image/png"}
-                    else if path.ends_with(".jpg") {"image/jpg"}
-                    else if path.ends_with(".svg") {"image/svg+xml"}
-                    else {continue};
-                                            
-                    if path.contains("..") || path.contains('\\') {
-                        continue
-                    }
-                    let path = path.strip_prefix("/").unwrap();
-                                         
-                    let path = root.join(&path);
-                    //println!("OPENING {:?}", path);
-                    if let Ok(mut file_handle) = File::open(path) {
-                        let mut body = Vec::<u8>::new();
-                        if file_handle.read_to_end(&mut body).is_ok() {
-                            let header = format!(
-                                "HTTP/1.1 200 OK\r\n\
-                                Content-Type: {}\r\n\
-                                Cross-Origin-Embedder-Policy: require-corp\r\n\
-                                Cross-Origin-Opener-Policy: same-origin\r\n\
-                                Content-encoding: none\r\n\
-                                Cache-Control: max-age:0\r\n\
-                                Content-Length: {}\r\n\
-                                Connection: close\r\n\r\n",
-                                mime_type,
-                                body.len()
-                            );
-                            let _ = response_sender.send(HttpServerResponse {header, body});
-                        }
-                    }
-                }
-                HttpServerRequest::Post {..} => { //headers, body, response}=>{
-                }
-            }
+            // websockets and posts aren't used by the wasm server
+            let _ = router.handle(message);
         }
     }).join().unwrap();
 }
@@ -1,10 +1,9 @@
 use makepad_http::server::*;
+use makepad_http::router::*;
 
 use std::{
     net::SocketAddr,
     sync::mpsc,
-    io::prelude::*,
-    fs::File,
 };
 
 fn main() {
@@ -42,101 +41,21 @@ fn main() {
         //("/makepad/".to_string(),makepad_path.clone()),
     //    ("/".to_string(),"".to_string())
     //];
+    let mut router = HttpRouter::default();
+    router.get("/$watch", |_| HttpServerResponse::new(200, "Cache-Control: max-age:0\r\n", Vec::new()));
+    router.get("/favicon.ico", |_| HttpServerResponse::new(200, "", Vec::new()));
+    // the index page goes out without the cross origin isolation headers
+    router.get("/index.html", {
+        let index = HttpStaticDir::new(root_path.clone());
+        move |req| index.respond(req.headers, "index.html")
+    });
+    let mut static_dir = HttpStaticDir::new(root_path);
+    static_dir.extra_headers = "Cross-Origin-Embedder-Policy: require-corp\r\nCross-Origin-Opener-Policy: same-origin\r\n".to_string();
+    static_dir.extensions = Some(WEB_ASSET_EXTENSIONS);
+    router.static_dir("/", static_dir);
+    
     while let Ok(message) = rx_request.recv() {
-        match message{
-            HttpServerRequest::ConnectWebSocket {web_socket_id:_, response_sender:_, headers:_}=>{
-                
-            },
-            HttpServerRequest::DisconnectWebSocket {web_socket_id:_}=>{
-                
-            },
-            HttpServerRequest::BinaryMessage {web_socket_id:_, response_sender:_, data:_}=>{
-                
-            }
-            HttpServerRequest::Get{headers, response_sender}=>{
-                let path = &headers.path;
-                
-                
-                if path == "/$watch"{
-                    let header = "HTTP/1.1 200 OK\r\n\
-                            Cache-Control: max-age:0\r\n\
-                            Connection: close\r\n\r\n".to_string();
-                    let _ = response_sender.send(HttpServerResponse{header, body:vec![]});
-                    continue
-                }
-                
-                if path == "/favicon.ico"{
-                    let header = "HTTP/1.1 200 OK\r\n\r\n".to_string();
-                    let _ = response_sender.send(HttpServerResponse{header, body:vec![]});
-                    continue
-                }
-                
-                let mime_type = if path.ends_with(".html") {"text/html"}
-                else if path.ends_with(".wasm") {"application/wasm"}
-                else if path.ends_with(".css") {"text/css"}
-                else if path.ends_with(".js") {"text/javascript"}
-                else if path.ends_with(".ttf") {"application/ttf"}
-                else if path.ends_with(".png") {"image/png"}
-                else if path.ends_with(".woff"){"font/woff"}
-		              else if path.ends_with(".woff2"){"font/woff2"}
-                else if path.ends_with(".jpg") {"image/jpg"}
-                else if path.ends_with(".svg") {"image/svg+xml"}
-                else {continue};
-
-                if path.contains("..") || path.contains('\\'){
-                    continue
-                }
-                let coep = if path == "/" || path == "/index.html"{
-                    ""
-                }
-                else{"Cross-Origin-Embedder-Policy: require-corp\r\nCross-Origin-Opener-Policy: same-origin\r\n"};
-                
-                //let mut strip = None;
-                //for remap in &remaps{
-                //    if let Some(s) = path.strip_prefix(&remap.0){
-                //        strip = Some(format!("{}{}",remap.1, s));
-                //        break;
-                //    }
-                // }
-                let base = format!("{}{}", root_path, path);
-                // check if we have a .br file
-                let base_br = format!("{}{}.br", root_path, path);
-                if let Ok(mut file_handle) = File::open(base_br) {
-                    let mut body = Vec::<u8>::new();
-                    if file_handle.read_to_end(&mut body).is_ok() {
-                        let header = format!(
-                            "HTTP/1.1 200 OK\r\n\
-                            Content-Type: {}\r\n\
-                            {coep}Content-encoding: br\r\n\
-                            Cache-Control: max-age:0\r\n\
-                            Content-Length: {}\r\n\
-                            Connection: close\r\n\r\n",
-                            
-                            mime_type,
-                            body.len()
-                        );
-                        let _ = response_sender.send(HttpServerResponse{header, body});
-                    }
-                }
-                else if let Ok(mut file_handle) = File::open(base) {
-                    let mut body = Vec::<u8>::new();
-                    if file_handle.read_to_end(&mut body).is_ok() {
-                        let header = format!(
-                            "HTTP/1.1 200 OK\r\n\
-                            Content-Type: {}\r\n\
-                            {coep}Content-encoding: none\r\n\
-                            Cache-Control: max-age:0\r\n\
-                            Content-Length: {}\r\n\
-                            Connection: close\r\n\r\n",
-                            mime_type,
-                            body.len()
-                        );
-                        let _ = response_sender.send(HttpServerResponse{header, body});
-                    }
-                }
-            }
-            HttpServerRequest::Post{..}=>{//headers, body, response}=>{
-            }
-        }
+        // websockets and posts aren't used by the site
+        let _ = router.handle(message);
     }
 }
@@ -18,6 +18,7 @@ live_design!{
     import crate::multi_image::MultiImageBase;
     import crate::image_blend::ImageBlendBase;
     import crate::icon::IconBase;
+    import crate::svg::SvgBase;
     import crate::rotated_image::RotatedImageBase;
     import crate::modal::ModalBase;
     import crate::tooltip::TooltipBase;
@@ -28,6 +29,7 @@ live_design!{
     import crate::link_label::LinkLabelBase;
     import crate::portal_list::PortalListBase;
     import crate::flat_list::FlatListBase;
+    import crate::data_grid::DataGridBase;
     import crate::scroll_bars::ScrollBarsBase;
     import crate::view::ViewBase;
     import crate::nav_control::NavControlBase;
@@ -53,6 +55,7 @@ live_design!{
     import crate::window_menu::WindowMenuBase;
     import crate::html::HtmlBase;
     import crate::html::HtmlLinkBase;
+    import crate::html::HtmlImageBase;
     import crate::markdown::MarkdownBase,
     import crate::markdown::MarkdownLinkBase;
     import crate::root::RootBase;
@@ -936,6 +939,7 @@ live_design!{
     RootBase = <RootBase>{}
     HtmlBase = <HtmlBase>{}
     HtmlLinkBase = <HtmlLinkBase>{}
+    HtmlImageBase = <HtmlImageBase>{}
     TextFlowBase = <TextFlowBase>{}
     TextFlowLinkBase = <TextFlowLinkBase>{}
     MarkdownBase = <MarkdownBase>{}
@@ -944,6 +948,11 @@ live_design!{
     PageFlipBase = <PageFlipBase>{}
     ViewBase = <ViewBase>{}
     ButtonBase = <ButtonBase>{}
+    Svg = <SvgBase> {
+        width: Fit,
+        height: Fit,
+    }
+
     CheckBoxBase = <CheckBoxBase>{}
     DockBase = <DockBase>{}
     MultiWindowBase = <MultiWindowBase>{}
@@ -956,6 +965,7 @@ live_design!{
     FoldHeaderBase = <FoldHeaderBase> {}
     ImageBase = <ImageBase> {}
     IconBase = <IconBase> {}
+    SvgBase = <SvgBase> {}
     RotatedImageBase = <RotatedImageBase> {}
     ModalBase = <ModalBase> {}
     TooltipBase = <TooltipBase> {}
@@ -965,6 +975,7 @@ live_design!{
     LinkLabelBase = <LinkLabelBase> {}
     PortalListBase = <PortalListBase> {}
     FlatListBase = <FlatListBase>{}
+    DataGridBase = <DataGridBase>{}
     NavControlBase = <NavControlBase> {}
     PopupMenuBase = <PopupMenuBase> {}
     PopupMenuItemBase = <PopupMenuItemBase> {}
@@ -81,6 +81,10 @@ impl Widget for Button {
             _=>()
         }
         
+        if self.visible && self.enabled && event.accessibility_action(uid.into()) == Some(AccessibilityAction::Click) {
+            cx.widget_action_with_data(&self.action_data, uid, &scope.path, ButtonAction::Clicked(Default::default()));
+        }
+
         if self.visible {
             // The button only handles hits when it's visible and enabled.
             // If it's not enabled, we still show the button, but we set
@@ -145,6 +149,21 @@ impl Widget for Button {
     fn set_text(&mut self, v: &str) {
         self.text.as_mut_empty().push_str(v);
     }
+
+    fn accessibility(&self, _cx: &Cx) -> Option<AccessibilityNode> {
+        if !self.visible {
+            return None
+        }
+        Some(AccessibilityNode {
+            role: AccessibilityRole::Button,
+            name: self.text.as_ref().to_string(),
+            states: AccessibilityStates::default()
+                .with(AccessibilityState::Focusable, self.grab_key_focus)
+                .with(AccessibilityState::Disabled, !self.enabled),
+            actions: vec![AccessibilityAction::Click],
+            ..Default::default()
+        })
+    }
 }
 
 impl Button {
@@ -98,6 +98,12 @@ impl Widget for CheckBox {
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
         let uid = self.widget_uid();
         self.animator_handle_event(cx, event);
+        
+        if event.accessibility_action(uid.into()) == Some(AccessibilityAction::Click) {
+            let on = !self.animator_in_state(cx, id!(selected.on));
+            self.animator_toggle(cx, on, Animate::Yes, id!(selected.on), id!(selected.off));
+            cx.widget_action_with_data(&self.action_data, uid, &scope.path, CheckBoxAction::Change(on));
+        }
                 
         match event.hits(cx, self.draw_check.area()) {
             Hit::FingerHoverIn(_) => {
@@ -139,6 +145,17 @@ impl Widget for CheckBox {
     fn set_text(&mut self, v: &str) {
         self.text.as_mut_empty().push_str(v);
     }
+    
+    fn accessibility(&self, cx: &Cx) -> Option<AccessibilityNode> {
+        Some(AccessibilityNode {
+            role: AccessibilityRole::CheckBox,
+            name: self.text.as_ref().to_string(),
+            states: AccessibilityStates::default()
+                .with(AccessibilityState::Checked, self.animator_in_state(cx, id!(selected.on))),
+            actions: vec![AccessibilityAction::Click],
+            ..Default::default()
+        })
+    }
 }
 
 impl CheckBoxRef {
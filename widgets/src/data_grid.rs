@@ -0,0 +1,700 @@
+use {
+    std::collections::BTreeSet,
+    crate::{
+        makepad_derive_widget::*,
+        makepad_draw::*,
+        widget::*,
+        portal_list::PortalList,
+        scroll_bar::{ScrollBar, ScrollAxis, ScrollBarAction},
+    }
+};
+
+live_design!{
+    DrawDataGridHeader = {{DrawDataGridHeader}} {}
+    DrawDataGridRow = {{DrawDataGridRow}} {}
+    DrawDataGridCell = {{DrawDataGridCell}} {}
+    DataGridBase = {{DataGrid}} {}
+}
+
+// The grid draws its cells itself instead of instancing a widget per cell, rows
+// are virtualized by an embedded PortalList and columns by skipping everything
+// outside the horizontal viewport. Frozen columns and header rows are drawn in
+// their own clipped regions so the scrolled cells slide underneath them.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataGridSortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone, Debug)]
+pub struct DataGridColumn {
+    pub id: LiveId,
+    pub title: String,
+    /// The initial width, the grid keeps its own once the user resizes the column.
+    pub width: f64,
+    pub sortable: bool,
+}
+
+impl DataGridColumn {
+    pub fn new(id: LiveId, title: &str, width: f64) -> Self {
+        Self {id, title: title.to_string(), width, sortable: true}
+    }
+
+    pub fn with_sortable(self, sortable: bool) -> Self {
+        Self {sortable, ..self}
+    }
+}
+
+/// Supplies the rows of a `DataGrid`. The grid looks it up in the scope data
+/// while drawing and handling events, either as a `Box<dyn DataGridSource>` or
+/// as the type registered with `DataGridRef::set_source`.
+pub trait DataGridSource {
+    fn columns(&self) -> Vec<DataGridColumn>;
+    fn row_count(&self) -> usize;
+    fn cell_text(&self, row: usize, column: LiveId) -> String;
+    /// Called when a sortable column header is clicked, row indices are expected
+    /// to follow the new order afterwards.
+    fn sort(&mut self, _column: LiveId, _order: DataGridSortOrder) {}
+}
+
+type SourceCast = for<'a, 'b> fn(&'a mut ScopeDataMut<'b>) -> Option<&'a mut dyn DataGridSource>;
+
+fn cast_boxed_source<'a>(data: &'a mut ScopeDataMut) -> Option<&'a mut dyn DataGridSource> {
+    data.get_mut::<Box<dyn DataGridSource>>().map( | source | source.as_mut() as &mut dyn DataGridSource)
+}
+
+fn cast_source<'a, T: DataGridSource + 'static>(data: &'a mut ScopeDataMut) -> Option<&'a mut dyn DataGridSource> {
+    data.get_mut::<T>().map( | source | source as &mut dyn DataGridSource)
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawDataGridHeader {
+    #[deref] draw_super: DrawQuad,
+    #[live] hover: f32,
+    #[live] dragging: f32,
+    /// 0 unsorted, 1 ascending, -1 descending
+    #[live] sort_order: f32,
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawDataGridRow {
+    #[deref] draw_super: DrawQuad,
+    #[live] is_even: f32,
+    #[live] selected: f32,
+    #[live] focussed: f32,
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawDataGridCell {
+    #[deref] draw_super: DrawQuad,
+    #[live] is_cursor: f32,
+    #[live] focussed: f32,
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum DataGridAction {
+    SelectionChanged,
+    Sorted {column: LiveId, order: DataGridSortOrder},
+    ColumnResized {column: LiveId, width: f64},
+    ColumnMoved {column: LiveId, index: usize},
+    /// A row was double clicked or return was pressed on it.
+    RowActivated(usize),
+    None
+}
+
+enum HeaderDrag {
+    None,
+    Press {index: usize},
+    Resize {index: usize, start_width: f64},
+    Move {index: usize, target: usize},
+}
+
+struct ColumnSpan {
+    index: usize,
+    x: f64,
+    width: f64,
+    frozen: bool,
+}
+
+#[derive(Live, LiveHook, Widget)]
+pub struct DataGrid {
+    #[redraw] #[rust] area: Area,
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+
+    #[live] list: PortalList,
+    #[live] scroll_bar: ScrollBar,
+
+    #[live] draw_header: DrawDataGridHeader,
+    #[live] draw_header_text: DrawText,
+    #[live] draw_row: DrawDataGridRow,
+    #[live] draw_cell: DrawDataGridCell,
+    #[live] draw_text: DrawText,
+    #[live] draw_marker: DrawColor,
+
+    #[live] header_layout: Layout,
+    #[live] cell_layout: Layout,
+    #[live(26.0)] header_height: f64,
+    #[live(22.0)] row_height: f64,
+    #[live(40.0)] min_column_width: f64,
+    #[live(4.0)] resize_margin: f64,
+    #[live(4.0)] min_drag_distance: f64,
+    #[live] frozen_rows: usize,
+    #[live] frozen_columns: usize,
+    #[live(true)] multi_select: bool,
+
+    #[rust] source_cast: Option<SourceCast>,
+    #[rust] columns: Vec<DataGridColumn>,
+    #[rust] row_count: usize,
+    #[rust] scroll_x: f64,
+    #[rust] view_width: f64,
+    #[rust] selected: BTreeSet<usize>,
+    #[rust] anchor: Option<usize>,
+    #[rust] cursor: Option<(usize, usize)>,
+    #[rust] sort: Option<(LiveId, DataGridSortOrder)>,
+    #[rust] hover_column: Option<usize>,
+    #[rust(HeaderDrag::None)] header_drag: HeaderDrag,
+    #[rust] header_area: Area,
+    #[rust] row_areas: Vec<(usize, Area)>,
+}
+
+impl DataGrid {
+    fn source<'a>(&self, data: &'a mut ScopeDataMut) -> Option<&'a mut dyn DataGridSource> {
+        self.source_cast.unwrap_or(cast_boxed_source)(data)
+    }
+
+    /// Keeps the order and widths the user picked, columns new to the source are
+    /// appended and the ones it dropped disappear.
+    fn sync_columns(&mut self, columns: Vec<DataGridColumn>) {
+        self.columns.retain( | old | columns.iter().any( | new | new.id == old.id));
+        for column in columns {
+            if let Some(old) = self.columns.iter_mut().find( | old | old.id == column.id) {
+                old.title = column.title;
+                old.sortable = column.sortable;
+            }
+            else {
+                self.columns.push(column);
+            }
+        }
+    }
+
+    fn frozen_width(&self) -> f64 {
+        self.columns.iter().take(self.frozen_columns).map( | column | column.width).sum()
+    }
+
+    fn total_width(&self) -> f64 {
+        self.columns.iter().map( | column | column.width).sum()
+    }
+
+    /// The screen positions of the columns that are at least partially visible.
+    fn column_spans(&self, left: f64, width: f64) -> Vec<ColumnSpan> {
+        let frozen_width = self.frozen_width();
+        let mut spans = Vec::new();
+        let mut x = left;
+        for (index, column) in self.columns.iter().enumerate() {
+            if index == self.frozen_columns.min(self.columns.len()) {
+                x = left + frozen_width - self.scroll_x;
+            }
+            let frozen = index < self.frozen_columns;
+            let visible = frozen || (x + column.width > left + frozen_width && x < left + width);
+            if visible {
+                spans.push(ColumnSpan {index, x, width: column.width, frozen});
+            }
+            x += column.width;
+        }
+        spans
+    }
+
+    fn begin_region(cx: &mut Cx2d, x: f64, y: f64, width: f64, height: f64) {
+        cx.begin_turtle(Walk::abs_rect(Rect {pos: dvec2(x, y), size: dvec2(width.max(0.0), height)}), Layout::default());
+    }
+
+    fn draw_cell_text(cx: &mut Cx2d, draw_text: &mut DrawText, layout: Layout, rect: Rect, text: &str) {
+        cx.begin_turtle(Walk::abs_rect(rect), layout);
+        draw_text.draw_walk(cx, Walk::fit(), Align::default(), text);
+        cx.end_turtle();
+    }
+
+    fn draw_header_row(&mut self, cx: &mut Cx2d) {
+        cx.begin_turtle(Walk::size(Size::Fill, Size::Fixed(self.header_height)), Layout::default());
+        let rect = cx.turtle().rect();
+        let spans = self.column_spans(rect.pos.x, rect.size.x);
+        let frozen_width = self.frozen_width().min(rect.size.x);
+
+        self.draw_header.hover = 0.0;
+        self.draw_header.dragging = 0.0;
+        self.draw_header.sort_order = 0.0;
+        self.draw_header.draw_abs(cx, rect);
+
+        for frozen in [true, false] {
+            if frozen {
+                Self::begin_region(cx, rect.pos.x, rect.pos.y, frozen_width, rect.size.y);
+            }
+            else {
+                Self::begin_region(cx, rect.pos.x + frozen_width, rect.pos.y, rect.size.x - frozen_width, rect.size.y);
+            }
+            for span in spans.iter().filter( | span | span.frozen == frozen) {
+                let column = &self.columns[span.index];
+                let dragged = match self.header_drag {
+                    HeaderDrag::Move {index, ..} => index == span.index,
+                    _ => false
+                };
+                self.draw_header.hover = if self.hover_column == Some(span.index) {1.0} else {0.0};
+                self.draw_header.dragging = if dragged {1.0} else {0.0};
+                self.draw_header.sort_order = match self.sort {
+                    Some((id, DataGridSortOrder::Ascending)) if id == column.id => 1.0,
+                    Some((id, DataGridSortOrder::Descending)) if id == column.id => -1.0,
+                    _ => 0.0
+                };
+                let cell = Rect {pos: dvec2(span.x, rect.pos.y), size: dvec2(span.width, rect.size.y)};
+                self.draw_header.draw_abs(cx, cell);
+                Self::draw_cell_text(cx, &mut self.draw_header_text, self.header_layout, cell, &column.title);
+            }
+            cx.end_turtle();
+        }
+
+        // where a dragged column would land
+        if let HeaderDrag::Move {target, ..} = self.header_drag {
+            let mut x = None;
+            for span in &spans {
+                if span.index == target {
+                    x = Some(span.x);
+                }
+                else if span.index + 1 == target {
+                    x = Some(span.x + span.width);
+                }
+            }
+            if let Some(x) = x {
+                self.draw_marker.draw_abs(cx, Rect {pos: dvec2(x - 1.0, rect.pos.y), size: dvec2(2.0, rect.size.y)});
+            }
+        }
+        cx.end_turtle_with_area(&mut self.header_area);
+    }
+
+    fn draw_data_row(&mut self, cx: &mut Cx2d, source: &dyn DataGridSource, row: usize) {
+        let focussed = if cx.has_key_focus(self.area) {1.0} else {0.0};
+        self.draw_row.is_even = if row & 1 == 0 {1.0} else {0.0};
+        self.draw_row.selected = if self.selected.contains(&row) {1.0} else {0.0};
+        self.draw_row.focussed = focussed;
+        let rect = self.draw_row.draw_walk(cx, Walk::size(Size::Fill, Size::Fixed(self.row_height)));
+        self.row_areas.push((row, self.draw_row.area()));
+
+        let spans = self.column_spans(rect.pos.x, rect.size.x);
+        let frozen_width = self.frozen_width().min(rect.size.x);
+        for frozen in [true, false] {
+            if frozen {
+                Self::begin_region(cx, rect.pos.x, rect.pos.y, frozen_width, rect.size.y);
+            }
+            else {
+                Self::begin_region(cx, rect.pos.x + frozen_width, rect.pos.y, rect.size.x - frozen_width, rect.size.y);
+            }
+            for span in spans.iter().filter( | span | span.frozen == frozen) {
+                let column = &self.columns[span.index];
+                let cell = Rect {pos: dvec2(span.x, rect.pos.y), size: dvec2(span.width, rect.size.y)};
+                self.draw_cell.is_cursor = if self.cursor == Some((row, span.index)) {1.0} else {0.0};
+                self.draw_cell.focussed = focussed;
+                self.draw_cell.draw_abs(cx, cell);
+                let text = source.cell_text(row, column.id);
+                Self::draw_cell_text(cx, &mut self.draw_text, self.cell_layout, cell, &text);
+            }
+            cx.end_turtle();
+        }
+    }
+
+    fn row_at(&self, cx: &Cx, abs: DVec2) -> Option<usize> {
+        self.row_areas.iter()
+            .find( | (_, area) | area.clipped_rect(cx).contains(abs))
+            .map( | (row, _) | *row)
+    }
+
+    fn column_at(&self, cx: &Cx, x: f64) -> Option<usize> {
+        let rect = self.header_area.rect(cx);
+        let frozen_width = self.frozen_width();
+        self.column_spans(rect.pos.x, rect.size.x).iter()
+            .filter( | span | span.frozen || x >= rect.pos.x + frozen_width)
+            .find( | span | x >= span.x && x < span.x + span.width)
+            .map( | span | span.index)
+    }
+
+    /// The column whose right edge is under `x`, those can be dragged to resize.
+    fn resize_edge_at(&self, cx: &Cx, x: f64) -> Option<usize> {
+        let rect = self.header_area.rect(cx);
+        self.column_spans(rect.pos.x, rect.size.x).iter()
+            .find( | span | (x - (span.x + span.width)).abs() <= self.resize_margin)
+            .map( | span | span.index)
+    }
+
+    /// The position a dragged column is inserted at, the number of columns left of `x`.
+    fn move_target_at(&self, cx: &Cx, x: f64) -> usize {
+        let rect = self.header_area.rect(cx);
+        let spans = self.column_spans(rect.pos.x, rect.size.x);
+        spans.iter()
+            .filter( | span | span.x + span.width * 0.5 < x)
+            .map( | span | span.index + 1)
+            .max()
+            .unwrap_or_else( || spans.first().map( | span | span.index).unwrap_or(0))
+    }
+
+    fn select_row(&mut self, cx: &mut Cx, row: usize, extend: bool, toggle: bool) {
+        let anchor = self.anchor.filter( | _ | extend && self.multi_select);
+        if let Some(anchor) = anchor {
+            if !toggle {
+                self.selected.clear();
+            }
+            self.selected.extend(anchor.min(row)..=anchor.max(row));
+        }
+        else if toggle && self.multi_select {
+            if !self.selected.remove(&row) {
+                self.selected.insert(row);
+            }
+            self.anchor = Some(row);
+        }
+        else {
+            self.selected.clear();
+            self.selected.insert(row);
+            self.anchor = Some(row);
+        }
+        let column = self.cursor.map( | (_, column) | column).unwrap_or(0);
+        self.cursor = Some((row, column));
+        self.area.redraw(cx);
+    }
+
+    fn move_cursor_to_row(&mut self, cx: &mut Cx, row: usize, extend: bool) {
+        if self.row_count == 0 {
+            return
+        }
+        let row = row.min(self.row_count - 1);
+        self.select_row(cx, row, extend, false);
+        if row >= self.frozen_rows {
+            self.list.scroll_to_visible(cx, row);
+        }
+    }
+
+    fn move_cursor_to_column(&mut self, cx: &mut Cx, column: usize) {
+        let Some((row, _)) = self.cursor else {return};
+        if self.columns.is_empty() {
+            return
+        }
+        let column = column.min(self.columns.len() - 1);
+        self.cursor = Some((row, column));
+        if column >= self.frozen_columns {
+            let frozen_width = self.frozen_width();
+            let start: f64 = self.columns[self.frozen_columns..column].iter().map( | column | column.width).sum();
+            let end = start + self.columns[column].width;
+            let view = (self.view_width - frozen_width).max(0.0);
+            let scroll_x = if start < self.scroll_x {start} else if end > self.scroll_x + view {end - view} else {self.scroll_x};
+            self.scroll_bar.set_scroll_pos_no_action(cx, scroll_x);
+            self.scroll_x = self.scroll_bar.get_scroll_pos();
+        }
+        self.area.redraw(cx);
+    }
+
+    fn selection_text(&self, source: &dyn DataGridSource) -> String {
+        let mut text = String::new();
+        for row in self.selected.iter().filter( | row | **row < self.row_count) {
+            let cells: Vec<String> = self.columns.iter().map( | column | source.cell_text(*row, column.id)).collect();
+            text.push_str(&cells.join("\t"));
+            text.push('\n');
+        }
+        text
+    }
+
+    fn handle_header_up(&mut self, cx: &mut Cx, scope: &mut Scope) {
+        let uid = self.widget_uid();
+        match std::mem::replace(&mut self.header_drag, HeaderDrag::None) {
+            HeaderDrag::Press {index} => {
+                let column = &self.columns[index];
+                if !column.sortable {
+                    return
+                }
+                let order = match self.sort {
+                    Some((id, DataGridSortOrder::Ascending)) if id == column.id => DataGridSortOrder::Descending,
+                    _ => DataGridSortOrder::Ascending
+                };
+                let column = column.id;
+                self.sort = Some((column, order));
+                if let Some(source) = self.source(&mut scope.data) {
+                    source.sort(column, order);
+                }
+                // row indices point at different rows now
+                self.anchor = None;
+                self.cursor = None;
+                if !self.selected.is_empty() {
+                    self.selected.clear();
+                    cx.widget_action(uid, &scope.path, DataGridAction::SelectionChanged);
+                }
+                cx.widget_action(uid, &scope.path, DataGridAction::Sorted {column, order});
+            }
+            HeaderDrag::Resize {index, ..} => {
+                let column = &self.columns[index];
+                cx.widget_action(uid, &scope.path, DataGridAction::ColumnResized {column: column.id, width: column.width});
+            }
+            HeaderDrag::Move {index, target} => {
+                let target = if target > index {target - 1} else {target};
+                if target != index {
+                    let column = self.columns.remove(index);
+                    let id = column.id;
+                    self.columns.insert(target, column);
+                    if let Some((row, cursor)) = self.cursor {
+                        if cursor == index {
+                            self.cursor = Some((row, target));
+                        }
+                    }
+                    cx.widget_action(uid, &scope.path, DataGridAction::ColumnMoved {column: id, index: target});
+                }
+            }
+            HeaderDrag::None => ()
+        }
+        self.area.redraw(cx);
+    }
+}
+
+impl Widget for DataGrid {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        let uid = self.widget_uid();
+
+        let mut scroll_to = None;
+        self.scroll_bar.handle_event_with(cx, event, &mut | _cx, action | {
+            if let ScrollBarAction::Scroll {scroll_pos, ..} = action {
+                scroll_to = Some(scroll_pos)
+            }
+        });
+        if let Some(scroll_to) = scroll_to {
+            self.scroll_x = scroll_to;
+            self.area.redraw(cx);
+        }
+
+        self.list.handle_event(cx, event, scope);
+        if self.scroll_bar.is_area_captured(cx) || self.list.is_scroll_bar_captured(cx) {
+            return
+        }
+
+        match event.hits_with_capture_overload(cx, self.area, true) {
+            Hit::KeyFocus(_) | Hit::KeyFocusLost(_) => {
+                self.area.redraw(cx);
+            }
+            Hit::FingerScroll(e) => {
+                if e.scroll.x != 0.0 && self.scroll_bar.set_scroll_pos_no_action(cx, self.scroll_x + e.scroll.x) {
+                    self.scroll_x = self.scroll_bar.get_scroll_pos();
+                    self.area.redraw(cx);
+                }
+            }
+            Hit::FingerHoverIn(e) | Hit::FingerHoverOver(e) => {
+                let in_header = self.header_area.clipped_rect(cx).contains(e.abs);
+                if in_header && self.resize_edge_at(cx, e.abs.x).is_some() {
+                    cx.set_cursor(MouseCursor::ColResize);
+                }
+                else {
+                    cx.set_cursor(MouseCursor::Default);
+                }
+                let hover_column = if in_header {self.column_at(cx, e.abs.x)} else {None};
+                if hover_column != self.hover_column {
+                    self.hover_column = hover_column;
+                    self.area.redraw(cx);
+                }
+            }
+            Hit::FingerHoverOut(_) => {
+                if self.hover_column.take().is_some() {
+                    self.area.redraw(cx);
+                }
+            }
+            Hit::FingerDown(e) => {
+                cx.set_key_focus(self.area);
+                if self.header_area.clipped_rect(cx).contains(e.abs) {
+                    if let Some(index) = self.resize_edge_at(cx, e.abs.x) {
+                        self.header_drag = HeaderDrag::Resize {index, start_width: self.columns[index].width};
+                    }
+                    else if let Some(index) = self.column_at(cx, e.abs.x) {
+                        self.header_drag = HeaderDrag::Press {index};
+                    }
+                }
+                else if let Some(row) = self.row_at(cx, e.abs) {
+                    let toggle = e.modifiers.control || e.modifiers.logo;
+                    self.select_row(cx, row, e.modifiers.shift, toggle);
+                    if let Some(column) = self.column_at(cx, e.abs.x) {
+                        self.cursor = Some((row, column));
+                    }
+                    cx.widget_action(uid, &scope.path, DataGridAction::SelectionChanged);
+                    if e.tap_count == 2 {
+                        cx.widget_action(uid, &scope.path, DataGridAction::RowActivated(row));
+                    }
+                }
+            }
+            Hit::FingerMove(e) => {
+                match self.header_drag {
+                    HeaderDrag::Resize {index, start_width} => {
+                        cx.set_cursor(MouseCursor::ColResize);
+                        self.columns[index].width = (start_width + e.abs.x - e.abs_start.x).max(self.min_column_width);
+                        self.area.redraw(cx);
+                    }
+                    HeaderDrag::Press {index} => if (e.abs.x - e.abs_start.x).abs() > self.min_drag_distance {
+                        let target = self.move_target_at(cx, e.abs.x);
+                        self.header_drag = HeaderDrag::Move {index, target};
+                        self.area.redraw(cx);
+                    }
+                    HeaderDrag::Move {index, target} => {
+                        let new_target = self.move_target_at(cx, e.abs.x);
+                        if new_target != target {
+                            self.header_drag = HeaderDrag::Move {index, target: new_target};
+                            self.area.redraw(cx);
+                        }
+                    }
+                    HeaderDrag::None => ()
+                }
+            }
+            Hit::FingerUp(_) => {
+                self.handle_header_up(cx, scope);
+            }
+            Hit::KeyDown(ke) => {
+                let extend = ke.modifiers.shift;
+                let row = self.cursor.map( | (row, _) | row);
+                let column = self.cursor.map( | (_, column) | column).unwrap_or(0);
+                let page = self.list.visible_items().max(2) - 1;
+                let target = match ke.key_code {
+                    KeyCode::ArrowUp => Some(row.map_or(0, | row | row.saturating_sub(1))),
+                    KeyCode::ArrowDown => Some(row.map_or(0, | row | row + 1)),
+                    KeyCode::PageUp => Some(row.map_or(0, | row | row.saturating_sub(page))),
+                    KeyCode::PageDown => Some(row.map_or(0, | row | row + page)),
+                    KeyCode::Home => Some(0),
+                    KeyCode::End => Some(usize::MAX),
+                    _ => None
+                };
+                if let Some(target) = target {
+                    self.move_cursor_to_row(cx, target, extend);
+                    cx.widget_action(uid, &scope.path, DataGridAction::SelectionChanged);
+                    return
+                }
+                match ke.key_code {
+                    KeyCode::ArrowLeft => {
+                        self.move_cursor_to_column(cx, column.saturating_sub(1));
+                    }
+                    KeyCode::ArrowRight => {
+                        self.move_cursor_to_column(cx, column + 1);
+                    }
+                    KeyCode::KeyA if self.multi_select && (ke.modifiers.control || ke.modifiers.logo) => {
+                        self.selected = (0..self.row_count).collect();
+                        self.area.redraw(cx);
+                        cx.widget_action(uid, &scope.path, DataGridAction::SelectionChanged);
+                    }
+                    KeyCode::ReturnKey => if let Some(row) = row {
+                        cx.widget_action(uid, &scope.path, DataGridAction::RowActivated(row));
+                    }
+                    _ => ()
+                }
+            }
+            Hit::TextCopy(ce) => {
+                if let Some(source) = self.source(&mut scope.data) {
+                    let text = self.selection_text(source);
+                    *ce.response.borrow_mut() = Some(text);
+                }
+            }
+            _ => ()
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
+        let source = self.source(&mut scope.data).map( | source | &*source);
+        match source {
+            Some(source) => {
+                self.sync_columns(source.columns());
+                self.row_count = source.row_count();
+            }
+            None => {
+                self.columns.clear();
+                self.row_count = 0;
+            }
+        }
+        self.selected.retain( | row | *row < self.row_count);
+        if self.cursor.is_some_and( | (row, column) | row >= self.row_count || column >= self.columns.len()) {
+            self.cursor = None;
+        }
+        self.row_areas.clear();
+
+        cx.begin_turtle(walk, self.layout);
+        let rect = cx.turtle().rect();
+        self.view_width = rect.size.x;
+        let total_width = self.total_width();
+        self.scroll_x = self.scroll_x.min(total_width - rect.size.x).max(0.0);
+
+        self.draw_header_row(cx);
+
+        if let Some(source) = source {
+            let frozen_rows = self.frozen_rows.min(self.row_count);
+            for row in 0..frozen_rows {
+                self.draw_data_row(cx, source, row);
+            }
+
+            let list_walk = self.list.walk(cx);
+            let mut list_scope = Scope::empty();
+            let _ = self.list.draw_walk(cx, &mut list_scope, list_walk);
+            self.list.set_item_range(cx, frozen_rows, self.row_count);
+            while let Some(row) = self.list.next_visible_item(cx) {
+                if row < self.row_count {
+                    self.draw_data_row(cx, source, row);
+                }
+            }
+            let _ = self.list.draw_walk(cx, &mut list_scope, list_walk);
+        }
+
+        self.scroll_x = self.scroll_bar.draw_scroll_bar(cx, ScrollAxis::Horizontal, rect, dvec2(total_width, rect.size.y));
+        cx.end_turtle_with_area(&mut self.area);
+        DrawStep::done()
+    }
+
+    fn accessibility(&self, _cx: &Cx) -> Option<AccessibilityNode> {
+        Some(AccessibilityNode {
+            role: AccessibilityRole::List,
+            states: AccessibilityStates::default().with(AccessibilityState::Focusable, true),
+            ..Default::default()
+        })
+    }
+}
+
+impl DataGridRef {
+    /// Makes the grid read its rows from scope data of type `T` instead of
+    /// expecting a `Box<dyn DataGridSource>`.
+    pub fn set_source<T: DataGridSource + 'static>(&self) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.source_cast = Some(cast_source::<T>);
+        }
+    }
+
+    pub fn selected_rows(&self) -> Vec<usize> {
+        if let Some(inner) = self.borrow() {
+            inner.selected.iter().copied().collect()
+        }
+        else {
+            Vec::new()
+        }
+    }
+
+    pub fn set_selected_rows(&self, cx: &mut Cx, rows: &[usize]) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.selected = rows.iter().copied().collect();
+            inner.anchor = rows.first().copied();
+            inner.area.redraw(cx);
+        }
+    }
+
+    pub fn selection_changed(&self, actions: &Actions) -> bool {
+        actions.filter_widget_actions(self.widget_uid()).any( | item | {
+            matches!(item.cast(), DataGridAction::SelectionChanged)
+        })
+    }
+
+    pub fn sorted(&self, actions: &Actions) -> Option<(LiveId, DataGridSortOrder)> {
+        actions.filter_widget_actions(self.widget_uid()).find_map( | item | {
+            if let DataGridAction::Sorted {column, order} = item.cast() {Some((column, order))} else {None}
+        })
+    }
+
+    pub fn row_activated(&self, actions: &Actions) -> Option<usize> {
+        actions.filter_widget_actions(self.widget_uid()).find_map( | item | {
+            if let DataGridAction::RowActivated(row) = item.cast() {Some(row)} else {None}
+        })
+    }
+}
@@ -123,10 +123,15 @@ pub enum FileTreeAction {
     FileClicked(LiveId),
     FolderClicked(LiveId),
     ShouldFileStartDrag(LiveId),
+    /// A node was right clicked at the given absolute position.
+    ContextMenu(LiveId, DVec2),
+    /// A node that was dragged out of this tree was dropped on a folder.
+    DroppedOnFolder{node_id: LiveId, folder_id: LiveId},
 }
 
 pub enum FileTreeNodeAction {
     WasClicked,
+    WasRightClicked(DVec2),
     Opening,
     Closing,
     ShouldStartDrag
@@ -178,6 +183,7 @@ impl FileTreeNode {
                 right: depth as f64 * 4.0,
                 bottom: 0.0,
             },
+            ..Default::default()
         }
     }
     
@@ -216,6 +222,10 @@ impl FileTreeNode {
                     actions.push((node_id, FileTreeNodeAction::ShouldStartDrag));
                 }
             }
+            Hit::FingerDown(fe) if fe.device.mouse_button() == Some(1) => {
+                self.animator_play(cx, id!(select.on));
+                actions.push((node_id, FileTreeNodeAction::WasRightClicked(fe.abs)));
+            }
             Hit::FingerDown(_) => {
                 self.animator_play(cx, id!(select.on));
                 if self.is_folder {
@@ -373,6 +383,21 @@ impl FileTree {
         }
     }
     
+    fn set_selected_node(&mut self, cx: &mut Cx, node_id: LiveId) {
+        if let Some(last_selected) = self.selected_node_id {
+            if last_selected != node_id {
+                if let Some((tree_node, _)) = self.tree_nodes.get_mut(&last_selected) {
+                    tree_node.set_is_selected(cx, false, Animate::Yes);
+                }
+            }
+        }
+        self.selected_node_id = Some(node_id);
+    }
+    
+    pub fn selected_node(&self) -> Option<LiveId> {
+        self.selected_node_id
+    }
+    
     pub fn start_dragging_file_node(
         &mut self,
         cx: &mut Cx,
@@ -381,8 +406,6 @@ impl FileTree {
     ) {
         self.dragging_node_id = Some(node_id);
 
-        log!("makepad: start_dragging_file_node");
-
         cx.start_dragging(items);
     }
 }
@@ -408,6 +431,34 @@ impl Widget for FileTree {
         for (node_id, (node, _)) in self.tree_nodes.iter_mut() {
             node.handle_event(cx, event, *node_id, scope, &mut node_actions);
         }
+        
+        // Folders accept the nodes that are dragged out of this tree.
+        if let Some(dragging_node_id) = self.dragging_node_id {
+            for (node_id, (node, _)) in self.tree_nodes.iter_mut() {
+                if !node.is_folder || *node_id == dragging_node_id {
+                    continue
+                }
+                match event.drag_hits(cx, node.draw_bg.area()) {
+                    DragHit::Drag(dh) => match dh.state {
+                        DragState::In | DragState::Over => {
+                            node.animator_play(cx, id!(hover.on));
+                            *dh.response.lock().unwrap() = DragResponse::Move;
+                        }
+                        DragState::Out => {
+                            node.animator_play(cx, id!(hover.off));
+                        }
+                    }
+                    DragHit::Drop(_) => {
+                        node.animator_play(cx, id!(hover.off));
+                        cx.widget_action(uid, &scope.path, FileTreeAction::DroppedOnFolder{
+                            node_id: dragging_node_id,
+                            folder_id: *node_id
+                        });
+                    }
+                    _ => ()
+                }
+            }
+        }
                 
         for (node_id, node_action) in node_actions {
             match node_action {
@@ -419,12 +470,7 @@ impl Widget for FileTree {
                 }
                 FileTreeNodeAction::WasClicked => {
                     cx.set_key_focus(self.scroll_bars.area());
-                    if let Some(last_selected) = self.selected_node_id {
-                        if last_selected != node_id {
-                            self.tree_nodes.get_mut(&last_selected).unwrap().0.set_is_selected(cx, false, Animate::Yes);
-                        }
-                    }
-                    self.selected_node_id = Some(node_id);
+                    self.set_selected_node(cx, node_id);
                     if self.is_folder(node_id){
                         cx.widget_action(uid, &scope.path, FileTreeAction::FolderClicked(node_id));
                     }
@@ -432,6 +478,11 @@ impl Widget for FileTree {
                         cx.widget_action(uid, &scope.path, FileTreeAction::FileClicked(node_id));
                     }
                 }
+                FileTreeNodeAction::WasRightClicked(abs) => {
+                    cx.set_key_focus(self.scroll_bars.area());
+                    self.set_selected_node(cx, node_id);
+                    cx.widget_action(uid, &scope.path, FileTreeAction::ContextMenu(node_id, abs));
+                }
                 FileTreeNodeAction::ShouldStartDrag => {
                     if self.dragging_node_id.is_none() {
                         cx.widget_action(uid, &scope.path, FileTreeAction::ShouldFileStartDrag(node_id));
@@ -497,7 +548,34 @@ impl FileTreeRef{
     }
     
     
-    pub fn file_start_drag(&self, cx: &mut Cx, _file_id: LiveId, item: DragItem) {
-        cx.start_dragging(vec![item]);
+    pub fn context_menu(&self, actions: &Actions) -> Option<(LiveId, DVec2)> {
+        if let Some(item) = actions.find_widget_action(self.widget_uid()) {
+            if let FileTreeAction::ContextMenu(node_id, abs) = item.cast() {
+                return Some((node_id, abs))
+            }
+        }
+        None
+    }
+    
+    pub fn dropped_on_folder(&self, actions: &Actions) -> Option<(LiveId, LiveId)> {
+        if let Some(item) = actions.find_widget_action(self.widget_uid()) {
+            if let FileTreeAction::DroppedOnFolder{node_id, folder_id} = item.cast() {
+                return Some((node_id, folder_id))
+            }
+        }
+        None
+    }
+    
+    pub fn selected_node(&self) -> Option<LiveId> {
+        self.borrow().and_then(|inner| inner.selected_node())
+    }
+    
+    pub fn file_start_drag(&self, cx: &mut Cx, file_id: LiveId, item: DragItem) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.start_dragging_file_node(cx, file_id, vec![item]);
+        }
+        else {
+            cx.start_dragging(vec![item]);
+        }
     }
 }
\ No newline at end of file
@@ -2,6 +2,8 @@ use crate::{
     makepad_derive_widget::*,
     makepad_draw::*,
     makepad_html::*,
+    image::Image,
+    image_cache::ImageCacheImpl,
     text_flow::TextFlow,
     widget::*,
 };
@@ -20,6 +22,8 @@ live_design!{
         }
     }
 
+    HtmlImageBase = {{HtmlImage}} {}
+
     HtmlBase = {{Html}} {
         // ok so we can use one drawtext
         // change to italic, change bold (SDF), strikethrough
@@ -59,6 +63,8 @@ pub struct Html {
 
     /// The stack of list levels encountered so far, used to track nested lists.
     #[rust] list_stack: Vec<ListLevel>,
+    /// The inline styles of the currently open tags, used to undo them at their close tags.
+    #[rust] style_stack: Vec<InlineStyle>,
 }
 
 // alright lets parse the HTML
@@ -134,6 +140,44 @@ impl Html {
             some_id!(br) => {
                 cx.turtle_new_line();
             }
+            some_id!(div) => {
+                cx.turtle_new_line();
+            }
+            some_id!(table) => {
+                trim_whitespace_in_text = TrimWhitespaceInText::Trim;
+                cx.turtle_new_line();
+                tf.begin_table(cx, table_columns(node));
+            }
+            some_id!(thead)
+            | some_id!(tbody)
+            | some_id!(tfoot) => {
+                trim_whitespace_in_text = TrimWhitespaceInText::Trim;
+            }
+            some_id!(tr) => {
+                trim_whitespace_in_text = TrimWhitespaceInText::Trim;
+                tf.begin_table_row(cx);
+            }
+            some_id!(th)
+            | some_id!(td) => {
+                trim_whitespace_in_text = TrimWhitespaceInText::Trim;
+                let header = node.open_tag_lc() == Some(live_id!(th));
+                // Handle the "align" attribute, otherwise the "text-align" inline style
+                let align = node.find_attr_lc(live_id!(align))
+                    .or_else(|| node.find_attr_lc(live_id!(style))
+                        .and_then(|style| css_declarations(style).find(|(property, _)| property == "text-align"))
+                        .map(|(_, value)| value)
+                    )
+                    .map(|align| match align.trim().to_ascii_lowercase().as_str() {
+                        "center" => 0.5,
+                        "right" | "end" => 1.0,
+                        _ => 0.0,
+                    })
+                    .unwrap_or(0.0);
+                let column_span = node.find_attr_lc(live_id!(colspan))
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(1);
+                tf.begin_table_cell(cx, header, align, column_span);
+            }
             some_id!(hr)
             | some_id!(sep) => {
                 cx.turtle_new_line();
@@ -268,6 +312,15 @@ impl Html {
                 cx.turtle_new_line();
                 cx.turtle_new_line();
             }
+            some_id!(div) => {
+                cx.turtle_new_line();
+            }
+            some_id!(table) => {
+                tf.end_table(cx);
+                cx.turtle_new_line();
+            }
+            some_id!(th)
+            | some_id!(td) => tf.end_table_cell(cx),
             some_id!(blockquote) => {
                 tf.ignore_newlines.pop();
                 tf.combine_spaces.pop();
@@ -341,14 +394,29 @@ impl Widget for Html {
         // alright lets iterate the html doc and draw it
         let mut node = self.doc.new_walker();
         let mut auto_id = 0;
+        self.style_stack.clear();
         while !node.done() {
             let mut trim = TrimWhitespaceInText::default();
             match Self::handle_open_tag(cx, tf, &mut node, &mut self.list_stack, &self.ul_markers, &self.ol_markers, &self.ol_separator) {
-                (Some(_), _tws) => {
-                    handle_custom_widget(cx, scope, tf, &self.doc, &mut node, &mut auto_id); 
+                (Some(tag), _tws) => {
+                    if !handle_custom_widget(cx, scope, tf, &self.doc, &mut node, &mut auto_id) && !is_void_element(tag) {
+                        // an unknown tag like `<span>` only contributes its inline style
+                        self.style_stack.push(InlineStyle::push(tf, tag, node.find_attr_lc(live_id!(style))));
+                    }
                 }
                 (None, tws) => {
                     trim = tws;
+                    if let Some(tag) = node.open_tag_lc().filter(|tag| !is_void_element(*tag)) {
+                        self.style_stack.push(InlineStyle::push(tf, tag, node.find_attr_lc(live_id!(style))));
+                    }
+                }
+            }
+            if let Some(tag) = node.close_tag_lc() {
+                // also undo the styles of tags that were left open inside this one
+                if let Some(index) = self.style_stack.iter().rposition(|style| style.tag == tag) {
+                    for style in self.style_stack.drain(index..).rev() {
+                        style.pop(tf);
+                    }
                 }
             }
             match Self::handle_close_tag(cx, tf, &mut node, &mut self.list_stack) {
@@ -368,7 +436,11 @@ impl Widget for Html {
     fn set_text(&mut self, v:&str){
         self.body.set(v);
         let mut errors = Some(Vec::new());
-        self.doc = parse_html(self.body.as_ref(), &mut errors, InternLiveId::No);
+        let new_doc = parse_html(self.body.as_ref(), &mut errors, InternLiveId::No);
+        if new_doc != self.doc{
+            self.doc = new_doc;
+            self.text_flow.clear_items();
+        }
         if errors.as_ref().unwrap().len()>0{
             log!("HTML parser returned errors {:?}", errors)
         }
@@ -376,6 +448,8 @@ impl Widget for Html {
 } 
 
 
+/// Draws the tag at `node` with the text flow's template of the same name,
+/// returning false if there is no such template.
 fn handle_custom_widget(
     cx: &mut Cx2d,
     _scope: &mut Scope,
@@ -383,7 +457,7 @@ fn handle_custom_widget(
     doc: &HtmlDoc,
     node: &mut HtmlWalker,
     auto_id: &mut u64,
-) {
+) -> bool {
     let id = if let Some(id) = node.find_attr_lc(live_id!(id)) {
         LiveId::from_str(id)
     } else {
@@ -396,13 +470,63 @@ fn handle_custom_widget(
     let mut scope_with_attrs = Scope::with_props_index(doc, node.index);
     // log!("FOUND CUSTOM WIDGET! template: {template:?}, id: {id:?}, attrs: {attrs:?}");
 
-    if let Some(item) = tf.item_with_scope(cx, &mut scope_with_attrs, id, template) {
+    let Some(item) = tf.item_with_scope(cx, &mut scope_with_attrs, id, template) else {
+        return false
+    };
+    // a void element like `<img>` has neither content nor a close tag to jump to
+    let is_void = is_void_element(node.open_tag_lc().unwrap());
+    if !is_void {
         item.set_text(node.find_text().unwrap_or(""));
-        let mut draw_scope = Scope::with_data(tf);
-        item.draw_all(cx, &mut draw_scope);
     }
+    let mut draw_scope = Scope::with_data(tf);
+    item.draw_all(cx, &mut draw_scope);
 
-    node.jump_to_close();
+    if !is_void {
+        node.jump_to_close();
+    }
+    true
+}
+
+/// Returns whether `tag` is an element that never has content or a close tag, like `<br>`.
+fn is_void_element(tag: LiveId) -> bool {
+    matches!(Some(tag),
+        some_id!(area) | some_id!(base) | some_id!(br) | some_id!(col) | some_id!(embed)
+        | some_id!(hr) | some_id!(img) | some_id!(input) | some_id!(link) | some_id!(meta)
+        | some_id!(source) | some_id!(track) | some_id!(wbr)
+    )
+}
+
+/// Counts the columns of the `<table>` opened at `node`: the widest row, including `colspan`s.
+/// Rows of tables nested in its cells are not counted.
+fn table_columns(node: &HtmlWalker) -> usize {
+    let mut walker = node.clone();
+    let mut depth = 0;
+    let mut columns = 0;
+    let mut row = 0;
+    walker.walk();
+    while !walker.done() {
+        match (walker.open_tag_lc(), walker.close_tag_lc()) {
+            (some_id!(table), _) => depth += 1,
+            (_, some_id!(table)) => {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            (some_id!(tr), _) if depth == 0 => row = 0,
+            (some_id!(th), _)
+            | (some_id!(td), _) if depth == 0 => {
+                row += walker.find_attr_lc(live_id!(colspan))
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(1usize)
+                    .max(1);
+                columns = columns.max(row);
+            }
+            _ => ()
+        }
+        walker.walk();
+    }
+    columns
 }
 
 
@@ -455,7 +579,7 @@ impl LiveHook for HtmlLink {
                 let doc = scope.props.get::<HtmlDoc>().unwrap();
                 let mut walker = doc.new_walker_with_index(scope.index + 1);
                 
-                if let Some((lc, attr)) = walker.while_attr_lc() {
+                while let Some((lc, attr)) = walker.while_attr_lc() {
                     match lc {
                         live_id!(href)=> {
                             self.url = attr.into()
@@ -575,6 +699,123 @@ impl Widget for HtmlLink {
 }
 
 
+/// An image drawn inline in an `Html` text flow from an `<img>` tag.
+///
+/// An `http://` or `https://` `src` is fetched with an HTTP request, a `crate://` one is
+/// loaded as a dependency resource. Files on disk are only loaded when `allow_local_files`
+/// is set, since the html may come from an untrusted source.
+/// Until the image is loaded, or if it can't be, the `alt` text is drawn instead.
+#[derive(Live, Widget)]
+pub struct HtmlImage {
+    #[deref] image: Image,
+    /// Whether a plain path or `file://` `src` is loaded from disk.
+    #[live(false)] allow_local_files: bool,
+    /// The size given by the `width` and `height` attributes, in pixels.
+    /// If only one is given, the other one follows the aspect ratio of the image.
+    #[rust] width: Option<f64>,
+    #[rust] height: Option<f64>,
+    #[rust] alt: String,
+    /// The id of the request fetching a remote `src`, while it is in flight.
+    #[rust] request_id: Option<LiveId>,
+}
+
+impl LiveHook for HtmlImage {
+    // After an HtmlImage instance has been instantiated ("applied"),
+    // load the image and its size from the `<img>` tag's attributes.
+    fn after_apply(&mut self, cx: &mut Cx, apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        match apply.from {
+            ApplyFrom::NewFromDoc {..} => {
+                let Some(doc) = apply.scope.as_ref().and_then(|scope| scope.props.get::<HtmlDoc>()) else {
+                    return
+                };
+                let mut walker = doc.new_walker_with_index(apply.scope.as_ref().unwrap().index + 1);
+                let mut src = "";
+                while let Some((lc, attr)) = walker.while_attr_lc() {
+                    match lc {
+                        live_id!(src) => src = attr,
+                        live_id!(alt) => self.alt = attr.into(),
+                        live_id!(width) => self.width = parse_pixels(attr),
+                        live_id!(height) => self.height = parse_pixels(attr),
+                        _ => ()
+                    }
+                }
+                if src.starts_with("http://") || src.starts_with("https://") {
+                    let request_id = LiveId::unique();
+                    cx.http_request(request_id, HttpRequest::new(src.to_string(), HttpMethod::GET));
+                    self.request_id = Some(request_id);
+                }
+                else if src.starts_with("crate://") {
+                    let _ = self.image.load_image_dep_by_path(cx, src, 0);
+                }
+                else if let Some(path) = Some(src.strip_prefix("file://").unwrap_or(src)).filter(|path| !path.is_empty() && !path.contains("://")) {
+                    if self.allow_local_files {
+                        let _ = self.image.load_image_file_by_path(cx, path, 0);
+                    }
+                }
+            }
+            _ => ()
+        }
+    }
+}
+
+impl Widget for HtmlImage {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, _scope: &mut Scope) {
+        let (Some(request_id), Event::NetworkResponses(responses)) = (self.request_id, event) else {
+            return
+        };
+        for response in responses.iter().filter(|response| response.request_id == request_id) {
+            match &response.response {
+                NetworkResponse::HttpResponse(res) => {
+                    self.request_id = None;
+                    let Some(body) = res.body.as_ref().filter(|_| res.status_code == 200) else {
+                        continue
+                    };
+                    let loaded = if body.starts_with(b"\x89PNG") {
+                        self.image.load_png_from_data(cx, body, 0)
+                    }
+                    else {
+                        self.image.load_jpg_from_data(cx, body, 0)
+                    };
+                    if loaded.is_ok() {
+                        // the text flow has no single area to redraw, and the image changes its layout
+                        cx.redraw_all();
+                    }
+                }
+                NetworkResponse::HttpRequestError(_) => self.request_id = None,
+                _ => ()
+            }
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, _walk: Walk) -> DrawStep {
+        let Some((width, height)) = self.image.size_in_pixels(cx) else {
+            if let Some(tf) = scope.data.get_mut::<TextFlow>() {
+                tf.draw_text(cx, &self.alt);
+            }
+            return DrawStep::done();
+        };
+        let (width, height) = (width as f64, height as f64);
+        let mut size = match (self.width, self.height) {
+            (Some(w), Some(h)) => dvec2(w, h),
+            (Some(w), None) => dvec2(w, w * height / width),
+            (None, Some(h)) => dvec2(h * width / height, h),
+            (None, None) => dvec2(width, height),
+        };
+        // scale images wider than the text flow down to fit it
+        let max_width = cx.turtle().padded_rect().size.x;
+        if max_width.is_finite() && max_width > 0.0 && size.x > max_width {
+            size *= max_width / size.x;
+        }
+        self.image.draw_walk(cx, Walk::fixed(size.x, size.y))
+    }
+}
+
+/// Parses an HTML length attribute like `120` or `120px`, percentages aren't supported.
+fn parse_pixels(value: &str) -> Option<f64> {
+    let value = value.trim();
+    value.strip_suffix("px").unwrap_or(value).trim().parse().ok()
+}
+
 /// The format and metadata of a list at a given nesting level.
 #[derive(Debug)]
 struct ListLevel {
@@ -693,3 +934,194 @@ pub fn to_roman_numeral(mut count: i32) -> Option<String> {
         None
     }
 }
+
+
+/// The text styles pushed onto a `TextFlow` for an open tag from its `style` attribute,
+/// so they can be popped again at the tag's close tag.
+///
+/// Supported are `color`, `font-size`, `font-weight`, `font-style` and `text-decoration`.
+#[derive(Default)]
+struct InlineStyle {
+    tag: LiveId,
+    font_color: bool,
+    font_size: bool,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+}
+
+impl InlineStyle {
+    fn push(tf: &mut TextFlow, tag: LiveId, style: Option<&str>) -> Self {
+        let mut inline = InlineStyle { tag, ..Default::default() };
+        for (property, value) in css_declarations(style.unwrap_or("")) {
+            let value = value.to_ascii_lowercase();
+            match property.as_str() {
+                "color" if !inline.font_color => {
+                    if let Some(color) = parse_css_color(&value) {
+                        tf.font_colors.push(color);
+                        inline.font_color = true;
+                    }
+                }
+                "font-size" if !inline.font_size => {
+                    inline.font_size = push_css_font_size(tf, &value);
+                }
+                "font-weight" if !inline.bold => {
+                    if value == "bold" || value == "bolder" || value.parse::<u32>().is_ok_and(|weight| weight >= 600) {
+                        tf.bold.push();
+                        inline.bold = true;
+                    }
+                }
+                "font-style" if !inline.italic => {
+                    if value == "italic" || value.starts_with("oblique") {
+                        tf.italic.push();
+                        inline.italic = true;
+                    }
+                }
+                "text-decoration"
+                | "text-decoration-line" => {
+                    for line in value.split_whitespace() {
+                        match line {
+                            "underline" if !inline.underline => {
+                                tf.underline.push();
+                                inline.underline = true;
+                            }
+                            "line-through" if !inline.strikethrough => {
+                                tf.strikethrough.push();
+                                inline.strikethrough = true;
+                            }
+                            _ => ()
+                        }
+                    }
+                }
+                _ => ()
+            }
+        }
+        inline
+    }
+
+    fn pop(&self, tf: &mut TextFlow) {
+        if self.font_color {
+            tf.font_colors.pop();
+        }
+        if self.font_size {
+            tf.font_sizes.pop();
+        }
+        if self.bold {
+            tf.bold.pop();
+        }
+        if self.italic {
+            tf.italic.pop();
+        }
+        if self.underline {
+            tf.underline.pop();
+        }
+        if self.strikethrough {
+            tf.strikethrough.pop();
+        }
+    }
+}
+
+/// Splits a `style` attribute into its lowercase property names and trimmed values.
+fn css_declarations(style: &str) -> impl Iterator<Item = (String, &str)> {
+    style.split(';').filter_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        let value = value.trim();
+        let value = value.strip_suffix("!important").unwrap_or(value).trim_end();
+        Some((property.trim().to_ascii_lowercase(), value))
+    })
+}
+
+/// Parses a CSS color: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`
+/// or one of the basic named colors.
+fn parse_css_color(value: &str) -> Option<Vec4> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None
+        }
+        let hex: String = match hex.len() {
+            3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => hex.into(),
+            _ => return None
+        };
+        let rgba = u32::from_str_radix(&hex, 16).ok()?;
+        return Some(Vec4::from_u32(if hex.len() == 6 { rgba << 8 | 0xff } else { rgba }))
+    }
+    if let Some(args) = value.strip_prefix("rgba(").or_else(|| value.strip_prefix("rgb(")) {
+        // color channels go up to 255 and alpha up to 1, unless given as a percentage
+        let channels = args.strip_suffix(')')?
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|channel| !channel.is_empty())
+            .enumerate()
+            .map(|(i, channel)| match channel.strip_suffix('%') {
+                Some(percent) => percent.parse::<f32>().ok().map(|p| p / 100.0),
+                None if i < 3 => channel.parse::<f32>().ok().map(|c| c / 255.0),
+                None => channel.parse::<f32>().ok(),
+            })
+            .collect::<Option<Vec<f32>>>()?;
+        return match channels[..] {
+            [r, g, b] => Some(vec4(r, g, b, 1.0)),
+            [r, g, b, a] => Some(vec4(r, g, b, a)),
+            _ => None
+        }
+    }
+    let rgb = match value {
+        "black" => 0x000000,
+        "silver" => 0xc0c0c0,
+        "gray" | "grey" => 0x808080,
+        "white" => 0xffffff,
+        "maroon" => 0x800000,
+        "red" => 0xff0000,
+        "purple" => 0x800080,
+        "fuchsia" | "magenta" => 0xff00ff,
+        "green" => 0x008000,
+        "lime" => 0x00ff00,
+        "olive" => 0x808000,
+        "yellow" => 0xffff00,
+        "navy" => 0x000080,
+        "blue" => 0x0000ff,
+        "teal" => 0x008080,
+        "aqua" | "cyan" => 0x00ffff,
+        "orange" => 0xffa500,
+        _ => return None
+    };
+    Some(Vec4::from_u32(rgb << 8 | 0xff))
+}
+
+/// Pushes a CSS `font-size` onto the text flow, returning whether it was a supported size.
+///
+/// Absolute sizes are relative to the browser default of 16px, which the text flow's
+/// `font_size` stands in for. Relative sizes scale the current font size.
+fn push_css_font_size(tf: &mut TextFlow, value: &str) -> bool {
+    let number = |unit: &str| value.strip_suffix(unit).and_then(|n| n.trim().parse::<f64>().ok());
+    if let Some(px) = number("px") {
+        tf.push_size_abs_scale(px / 16.0);
+    }
+    else if let Some(pt) = number("pt") {
+        tf.push_size_abs_scale(pt / 12.0);
+    }
+    else if let Some(rem) = number("rem") {
+        tf.push_size_abs_scale(rem);
+    }
+    else if let Some(em) = number("em") {
+        tf.push_size_rel_scale(em);
+    }
+    else if let Some(percent) = number("%") {
+        tf.push_size_rel_scale(percent / 100.0);
+    }
+    else {
+        match value {
+            "xx-small" => tf.push_size_abs_scale(0.6),
+            "x-small" => tf.push_size_abs_scale(0.75),
+            "small" => tf.push_size_abs_scale(0.89),
+            "medium" => tf.push_size_abs_scale(1.0),
+            "large" => tf.push_size_abs_scale(1.2),
+            "x-large" => tf.push_size_abs_scale(1.5),
+            "xx-large" => tf.push_size_abs_scale(2.0),
+            "smaller" => tf.push_size_rel_scale(1.0 / 1.2),
+            "larger" => tf.push_size_rel_scale(1.2),
+            _ => return false
+        }
+    }
+    true
+}
@@ -13,8 +13,8 @@ live_design!{
 pub struct Image {
     #[walk] walk: Walk,
     #[redraw] #[live] draw_bg: DrawQuad,
-    #[live] min_width: i64,
-    #[live] min_height: i64,
+    #[live] placeholder_width: i64,
+    #[live] placeholder_height: i64,
     #[live(1.0)] width_scale: f64,
     #[live] fit: ImageFit,
     #[live] source: LiveDependency,
@@ -33,6 +33,7 @@ impl ImageCacheImpl for Image {
 
 impl LiveHook for Image{
     fn after_apply(&mut self, cx: &mut Cx, _applyl: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        take_deprecated_placeholder_size(&mut self.walk, &mut self.placeholder_width, &mut self.placeholder_height);
         self.lazy_create_image_cache(cx);
         let source = self.source.clone();
         if source.as_str().len()>0 {
@@ -68,12 +69,12 @@ impl Image {
         let dpi = cx.current_dpi_factor();
         let (width, height) = if let Some(image_texture) = &self.texture {
             self.draw_bg.draw_vars.set_texture(0, image_texture);
-            let (width,height) = image_texture.get_format(cx).vec_width_height().unwrap_or((self.min_width as usize, self.min_height as usize));
+            let (width,height) = image_texture.get_format(cx).vec_width_height().unwrap_or((self.placeholder_width as usize, self.placeholder_height as usize));
             (width as f64 * self.width_scale, height as f64)
         }
         else {
             self.draw_bg.draw_vars.empty_texture(0);
-            (self.min_width as f64 / dpi, self.min_height as f64 / dpi)
+            (self.placeholder_width as f64 / dpi, self.placeholder_height as f64 / dpi)
         };
         
         let aspect = width / height;
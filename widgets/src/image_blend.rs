@@ -14,8 +14,8 @@ pub struct ImageBlend {
     #[walk] walk: Walk,
     #[animator] animator:Animator,
     #[redraw] #[live] draw_bg: DrawQuad,
-    #[live] min_width: i64,
-    #[live] min_height: i64,
+    #[live] placeholder_width: i64,
+    #[live] placeholder_height: i64,
     #[live(1.0)] width_scale: f64,
     #[live] fit: ImageFit,
     #[live] breathe: bool,
@@ -41,6 +41,7 @@ impl ImageCacheImpl for ImageBlend {
 
 impl LiveHook for ImageBlend{
     fn after_apply(&mut self, cx: &mut Cx, _applyl: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        take_deprecated_placeholder_size(&mut self.walk, &mut self.placeholder_width, &mut self.placeholder_height);
         self.lazy_create_image_cache(cx);
         let source = self.source.clone();
         if source.as_str().len()>0 {
@@ -71,12 +72,12 @@ impl ImageBlend {
         let rect = cx.peek_walk_turtle(walk);
         let dpi = cx.current_dpi_factor();
         let (width, height) = if let Some(image_texture) = &self.texture[0] {
-            let (width,height) = image_texture.get_format(cx).vec_width_height().unwrap_or((self.min_width as usize, self.min_height as usize));
+            let (width,height) = image_texture.get_format(cx).vec_width_height().unwrap_or((self.placeholder_width as usize, self.placeholder_height as usize));
             (width as f64 * self.width_scale, height as f64)
         }
         else {
             self.draw_bg.draw_vars.empty_texture(0);
-            (self.min_width as f64 / dpi, self.min_height as f64 / dpi)
+            (self.placeholder_width as f64 / dpi, self.placeholder_height as f64 / dpi)
         };
                 
         let aspect = width / height;
@@ -20,6 +20,19 @@ pub enum ImageFit{
     Size
 }
 
+/// `min_width` and `min_height` were the placeholder size of the image widgets before `Walk`
+/// took these names for its size constraints. As the walk splat now picks them up, they are
+/// moved back out of the walk here, which keeps them working as deprecated aliases of
+/// `placeholder_width` and `placeholder_height`.
+pub(crate) fn take_deprecated_placeholder_size(walk: &mut Walk, width: &mut i64, height: &mut i64) {
+    if let Some(min_width) = walk.min_width.take() {
+        *width = min_width as i64;
+    }
+    if let Some(min_height) = walk.min_height.take() {
+        *height = min_height as i64;
+    }
+}
+
 
 #[derive(Default, Clone)] 
 pub struct ImageBuffer {
@@ -58,6 +58,14 @@ impl Widget for Label {
         self.text.as_mut_empty().push_str(v);
     }
 
+    fn accessibility(&self, _cx: &Cx) -> Option<AccessibilityNode> {
+        Some(AccessibilityNode {
+            role: AccessibilityRole::Label,
+            name: self.text.as_ref().to_string(),
+            ..Default::default()
+        })
+    }
+
     fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
         let uid = self.widget_uid();
                 
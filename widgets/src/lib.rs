@@ -12,6 +12,7 @@ pub mod label;
 pub mod image;
 pub mod image_blend;
 pub mod icon;
+pub mod svg;
 pub mod link_label;
 pub mod drop_down;
 pub mod popup_menu;
@@ -52,6 +53,7 @@ pub mod slide_panel;
 pub mod page_flip;
 pub mod keyboard_view;
 pub mod flat_list;
+pub mod data_grid;
 pub mod file_tree;
 pub mod slides_view;
 pub mod color_picker;
@@ -84,6 +86,9 @@ pub mod designer_outline;
 pub mod designer_data;
 pub mod designer_toolbox;
 
+#[cfg(linux_headless)]
+pub mod snapshot;
+
 pub use crate::{
     data_binding::{DataBindingStore, DataBindingMap},
     button::*,
@@ -92,6 +97,7 @@ pub use crate::{
     image::*,
     image_blend::*,
     icon::*,
+    svg::*,
     label::*,
     slider::*,
     root::*,
@@ -109,6 +115,7 @@ pub use crate::{
     link_label::*,
     portal_list::*,
     flat_list::*,
+    data_grid::*,
     page_flip::*,
     slide_panel::*,
     fold_button::*,
@@ -164,6 +171,7 @@ pub fn live_design(cx: &mut Cx) {
     crate::multi_image::live_design(cx);
     crate::image_blend::live_design(cx);
     crate::icon::live_design(cx);
+    crate::svg::live_design(cx);
     crate::rotated_image::live_design(cx);
     crate::modal::live_design(cx);
     crate::tooltip::live_design(cx);
@@ -187,6 +195,7 @@ pub fn live_design(cx: &mut Cx) {
     crate::multi_window::live_design(cx);
     crate::portal_list::live_design(cx);
     crate::flat_list::live_design(cx);
+    crate::data_grid::live_design(cx);
     crate::slide_panel::live_design(cx);
     crate::tab::live_design(cx);
     crate::tab_bar::live_design(cx);
@@ -34,6 +34,9 @@ pub struct Markdown{
     #[rust] in_code_block: bool,
     #[rust] code_block_string: String,
     #[rust] doc: MarkdownDoc,
+    #[rust] table_columns: Vec<MarkdownTableAlign>,
+    #[rust] table_column: usize,
+    #[rust] table_header: bool,
     #[rust] auto_id: u64
 }
 
@@ -84,6 +87,7 @@ impl Markdown {
     
     fn process_markdown_doc(&mut self, doc:&MarkdownDoc, cx: &mut Cx2d){
         let tf = &mut self.text_flow;
+        let mut first_footnote = true;
         for node in &doc.nodes{
             match node{
                 MarkdownNode::BeginHead{level}=>{
@@ -116,6 +120,10 @@ impl Markdown {
                 },
                 MarkdownNode::EndNormal=>{
                                         
+                },
+                MarkdownNode::BeginListItem{label: MarkdownListLabel::Task{checked}}=>{
+                    cx.turtle_new_line();
+                    tf.begin_task_list_item(cx, *checked, 1.5);
                 },
                 MarkdownNode::BeginListItem{label}=>{
                     cx.turtle_new_line();
@@ -126,7 +134,7 @@ impl Markdown {
                         MarkdownListLabel::Number{start,end,..}=>{
                             &doc.decoded[*start..*end]
                         }
-                                                                        
+                        MarkdownListLabel::Task{..}=>unreachable!()
                     };
                     tf.begin_list_item(cx, str, 1.5);
                 },
@@ -161,6 +169,61 @@ impl Markdown {
                 MarkdownNode::EndUnderline=>{
                     tf.underline.pop();
                 },
+                MarkdownNode::BeginStrikethrough=>{
+                    tf.strikethrough.push();
+                },
+                MarkdownNode::EndStrikethrough=>{
+                    tf.strikethrough.pop();
+                },
+                MarkdownNode::BeginTable{columns}=>{
+                    cx.turtle_new_line_with_spacing(self.paragraph_spacing);
+                    self.table_columns.clone_from(columns);
+                    tf.begin_table(cx, columns.len());
+                },
+                MarkdownNode::EndTable=>{
+                    tf.end_table(cx);
+                    cx.turtle_new_line();
+                },
+                MarkdownNode::BeginTableRow{header}=>{
+                    self.table_column = 0;
+                    self.table_header = *header;
+                    tf.begin_table_row(cx);
+                },
+                MarkdownNode::EndTableRow=>(),
+                MarkdownNode::BeginTableCell=>{
+                    let align = match self.table_columns.get(self.table_column){
+                        Some(MarkdownTableAlign::Center)=>0.5,
+                        Some(MarkdownTableAlign::Right)=>1.0,
+                        _=>0.0
+                    };
+                    tf.begin_table_cell(cx, self.table_header, align, 1);
+                },
+                MarkdownNode::EndTableCell=>{
+                    tf.end_table_cell(cx);
+                    self.table_column += 1;
+                },
+                MarkdownNode::FootnoteRef{number}=>{
+                    const FOOTNOTE_FONT_SIZE_SCALE: f64 = 0.7;
+                    tf.push_size_rel_scale(FOOTNOTE_FONT_SIZE_SCALE);
+                    tf.draw_text(cx, &format!("[{}]", number));
+                    tf.font_sizes.pop();
+                },
+                MarkdownNode::BeginFootnote{number}=>{
+                    // the footnotes are at the end of the doc, split off from the text above
+                    if first_footnote{
+                        first_footnote = false;
+                        cx.turtle_new_line_with_spacing(self.paragraph_spacing);
+                        tf.sep(cx);
+                    }
+                    cx.turtle_new_line();
+                    const FOOTNOTE_FONT_SIZE_SCALE: f64 = 0.85;
+                    tf.push_size_rel_scale(FOOTNOTE_FONT_SIZE_SCALE);
+                    tf.begin_list_item(cx, &format!("{}.", number), 1.5);
+                },
+                MarkdownNode::EndFootnote=>{
+                    tf.end_list_item(cx);
+                    tf.font_sizes.pop();
+                },
                 MarkdownNode::BeginInlineCode=>{
                     const FIXED_FONT_SIZE_SCALE: f64 = 0.85;
                     tf.push_size_rel_scale(FIXED_FONT_SIZE_SCALE);
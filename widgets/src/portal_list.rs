@@ -80,6 +80,7 @@ pub struct PortalList {
     
     #[rust] templates: ComponentMap<LiveId, LivePtr>,
     #[rust] items: ComponentMap<usize, (LiveId, WidgetRef)>,
+    #[rust] accessibility_row: Option<usize>,
     //#[rust(DragState::None)] drag_state: DragState,
     #[rust(ScrollState::Stopped)] scroll_state: ScrollState
 }
@@ -306,6 +307,29 @@ impl PortalList {
 
     /// Returns the index of the next visible item that will be drawn by this PortalList.
     pub fn next_visible_item(&mut self, cx: &mut Cx2d) -> Option<usize> {
+        // every row becomes a list item node around whatever the caller draws into it
+        if let Some(index) = self.accessibility_row.take() {
+            let area = self.items.get(&index).map( | (_, item) | item.area()).unwrap_or(Area::Empty);
+            cx.end_accessibility_node(self.row_accessibility_id(index), area);
+        }
+        let next = self.next_visible_item_inner(cx);
+        if let Some(index) = next {
+            let node = AccessibilityNode {
+                role: AccessibilityRole::ListItem,
+                ..Default::default()
+            };
+            if cx.begin_accessibility_node(self.row_accessibility_id(index), node) {
+                self.accessibility_row = Some(index);
+            }
+        }
+        next
+    }
+
+    fn row_accessibility_id(&self, index: usize) -> AccessibilityId {
+        AccessibilityId(self.widget_uid().0 ^ (index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    fn next_visible_item_inner(&mut self, cx: &mut Cx2d) -> Option<usize> {
         let vi = self.vec_index;
         let layout = if vi == Vec2Index::Y { Layout::flow_down() } else { Layout::flow_right() };
         if let Some(draw_state) = self.draw_state.get() {
@@ -334,7 +358,8 @@ impl PortalList {
                                 abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y + self.first_scroll)),
                                 margin: Default::default(),
                                 width: Size::Fill,
-                                height: Size::Fit
+                                height: Size::Fit,
+                                ..Default::default()
                             }, layout);
                         }
                         Vec2Index::X => {
@@ -342,7 +367,8 @@ impl PortalList {
                                 abs_pos: Some(dvec2(viewport.pos.x + self.first_scroll, viewport.pos.y)),
                                 margin: Default::default(),
                                 width: Size::Fit,
-                                height: Size::Fill
+                                height: Size::Fill,
+                                ..Default::default()
                             }, layout);
                         }
                     }
@@ -375,7 +401,8 @@ impl PortalList {
                                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                                         margin: Default::default(),
                                         width: Size::Fill,
-                                        height: Size::Fit
+                                        height: Size::Fit,
+                                        ..Default::default()
                                     }, layout);
                                 }
                                 Vec2Index::X => {
@@ -383,7 +410,8 @@ impl PortalList {
                                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                                         margin: Default::default(),
                                         width: Size::Fit,
-                                        height: Size::Fill
+                                        height: Size::Fill,
+                                        ..Default::default()
                                     }, layout);
                                 }
                             }
@@ -414,7 +442,8 @@ impl PortalList {
                                 abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y + pos + rect.size.index(vi))),
                                 margin: Default::default(),
                                 width: Size::Fill,
-                                height: Size::Fit
+                                height: Size::Fit,
+                                ..Default::default()
                             }, layout);
                         }
                         Vec2Index::X => {
@@ -422,7 +451,8 @@ impl PortalList {
                                 abs_pos: Some(dvec2(viewport.pos.x + pos + rect.size.index(vi), viewport.pos.y)),
                                 margin: Default::default(),
                                 width: Size::Fit,
-                                height: Size::Fill
+                                height: Size::Fill,
+                                ..Default::default()
                             }, layout);
                         }
                     }
@@ -456,7 +486,8 @@ impl PortalList {
                                     abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y + total_height)),
                                     margin: Default::default(),
                                     width: Size::Fill,
-                                    height: Size::Fit
+                                    height: Size::Fit,
+                                    ..Default::default()
                                 }, Layout::flow_down());
                                 return Some(last_index + 1);
                             }
@@ -481,7 +512,8 @@ impl PortalList {
                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                         margin: Default::default(),
                         width: Size::Fill,
-                        height: Size::Fit
+                        height: Size::Fit,
+                        ..Default::default()
                     }, Layout::flow_down());
                     
                     return Some(index - 1);
@@ -628,6 +660,31 @@ impl PortalList {
         self.visible_items
     }
 
+    /// Scrolls just far enough to bring the item with the given `entry_id` into view,
+    /// the last visible item is assumed to be partially cut off.
+    pub fn scroll_to_visible(&mut self, cx: &mut Cx, entry_id: usize) {
+        let entry_id = entry_id.max(self.range_start);
+        if entry_id < self.first_id || (entry_id == self.first_id && self.first_scroll < 0.0) {
+            self.first_id = entry_id;
+        }
+        else if self.visible_items > 1 && entry_id + 2 > self.first_id + self.visible_items {
+            self.first_id = entry_id + 2 - self.visible_items;
+        }
+        else {
+            return
+        }
+        self.first_scroll = 0.0;
+        self.tail_range = false;
+        self.update_scroll_bar(cx);
+        self.area.redraw(cx);
+    }
+
+    /// Returns `true` while the scroll bar is being dragged, widgets wrapping the
+    /// list use this to leave the finger alone.
+    pub fn is_scroll_bar_captured(&self, cx: &Cx) -> bool {
+        self.scroll_bar.is_area_captured(cx)
+    }
+
     /// Returns `true` if this sanity check fails: the first item ID is within the item range.
     ///
     /// Returns `false` if the sanity check passes as expected.
@@ -910,6 +967,13 @@ impl Widget for PortalList {
         }
         DrawStep::done()
     }
+
+    fn accessibility(&self, _cx: &Cx) -> Option<AccessibilityNode> {
+        Some(AccessibilityNode {
+            role: AccessibilityRole::List,
+            ..Default::default()
+        })
+    }
 }
 
 impl PortalListRef {
@@ -195,6 +195,26 @@ impl Widget for Slider {
             }
         };
 
+        if let Some(action) = event.accessibility_action(uid.into()) {
+            let step = if self.step != 0.0 {self.step} else {(self.max - self.min) / 100.0};
+            let value = match action {
+                AccessibilityAction::Increment => Some(self.to_external() + step),
+                AccessibilityAction::Decrement => Some(self.to_external() - step),
+                AccessibilityAction::SetValue(v) => Some(v),
+                AccessibilityAction::Focus => {
+                    self.text_input.set_key_focus(cx);
+                    None
+                }
+                AccessibilityAction::Click => None
+            };
+            if let Some(value) = value {
+                self.set_internal(value.max(self.min).min(self.max));
+                self.draw_slider.redraw(cx);
+                self.update_text_input_and_redraw(cx);
+                cx.widget_action(uid, &scope.path, SliderAction::Slide(self.to_external()));
+            }
+        }
+
         if self.hover_actions_enabled {
             match event.hits_with_capture_overload(cx, self.label_area, true) {
                 Hit::FingerHoverIn(fh) => {
@@ -290,6 +310,23 @@ impl Widget for Slider {
             self.update_text_input()
         }
     }
+    
+    fn accessibility(&self, _cx: &Cx) -> Option<AccessibilityNode> {
+        let current = self.to_external();
+        Some(AccessibilityNode {
+            role: AccessibilityRole::Slider,
+            name: self.text.clone(),
+            value: self.text_input.text.clone(),
+            numeric_value: Some(AccessibilityNumericValue {
+                min: self.min,
+                max: self.max,
+                step: self.step,
+                current
+            }),
+            states: AccessibilityStates::default().with(AccessibilityState::Focusable, true),
+            actions: vec![AccessibilityAction::Increment, AccessibilityAction::Decrement, AccessibilityAction::Focus],
+        })
+    }
         
 }
 
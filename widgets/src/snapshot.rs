@@ -0,0 +1,358 @@
+use {
+    std::{
+        rc::Rc,
+        cell::{Cell, RefCell},
+        path::{Path, PathBuf},
+    },
+    crate::{
+        makepad_draw::*,
+        makepad_platform::{
+            makepad_image_formats::{ImageBuffer, png},
+            os::cx_native::EventFlow,
+        },
+        widget::*,
+    }
+};
+
+// Golden image tests for widgets. A widget from a live_design! snippet is drawn into a
+// fixed size headless window, driven with scripted input and compared to a stored PNG.
+// Only available with MAKEPAD=linux_headless, as pixels come from the software renderer.
+//
+// live_design!{
+//     import makepad_widgets::base::*;
+//     import makepad_widgets::theme_desktop_dark::*;
+//     MyButton = <Button> {text: "Hello"}
+// }
+//
+// let mut snapshot = WidgetSnapshot::new(live_design, module_path!(), live_id!(MyButton), dvec2(200.0, 60.0), 1.0);
+// snapshot.click(dvec2(20.0, 20.0));
+// snapshot.assert_snapshot("tests/snapshots/my_button.png", SnapshotTolerance::default());
+//
+// Run with MAKEPAD_UPDATE_SNAPSHOTS=1 to (re)write the stored images.
+
+// an animation that never finishes still produces a frame after this many steps
+const MAX_SETTLE_FRAMES: usize = 600;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotTolerance {
+    /// Largest per channel difference (0-255) for a pixel to still count as equal.
+    pub channel: u8,
+    /// Number of pixels that may differ beyond `channel` before the comparison fails.
+    pub pixels: usize,
+}
+
+impl Default for SnapshotTolerance {
+    fn default() -> Self {
+        Self {channel: 2, pixels: 0}
+    }
+}
+
+struct SnapshotRoot {
+    window: WindowHandle,
+    pass: Pass,
+    _depth_texture: Texture,
+    main_draw_list: DrawList2d,
+    overlay: Overlay,
+    widget: WidgetRef,
+}
+
+impl SnapshotRoot {
+    fn new(cx: &mut Cx, widget_ptr: LivePtr) -> Self {
+        let window = WindowHandle::new(cx);
+        let pass = Pass::new(cx);
+        window.set_pass(cx, &pass);
+        let depth_texture = Texture::new_with_format(cx, TextureFormat::DepthD32 {
+            size: TextureSize::Auto,
+            initial: true,
+        });
+        pass.set_depth_texture(cx, &depth_texture, PassClearDepth::ClearWith(1.0));
+        Self {
+            window,
+            pass,
+            _depth_texture: depth_texture,
+            main_draw_list: DrawList2d::new(cx),
+            overlay: Overlay::new(cx),
+            widget: WidgetRef::new_from_ptr(cx, Some(widget_ptr)),
+        }
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event) {
+        if let Event::Draw(event) = event {
+            let cx = &mut Cx2d::new(cx, event);
+            self.draw(cx);
+            return
+        }
+        self.overlay.handle_event(cx, event);
+        self.widget.handle_event(cx, event, &mut Scope::empty());
+    }
+
+    fn draw(&mut self, cx: &mut Cx2d) {
+        if !cx.will_redraw(&mut self.main_draw_list, Walk::default()) {
+            return
+        }
+        cx.begin_pass(&self.pass, None);
+        self.main_draw_list.begin_always(cx);
+        cx.begin_pass_sized_turtle(Layout::flow_down());
+        self.overlay.begin(cx);
+
+        self.widget.draw_all(cx, &mut Scope::empty());
+
+        self.overlay.end(cx);
+        cx.end_pass_sized_turtle();
+        self.main_draw_list.end(cx);
+        cx.end_pass(&self.pass);
+    }
+}
+
+pub struct WidgetSnapshot {
+    cx: Cx,
+    headless_app: HeadlessApp,
+    root: Rc<RefCell<Option<SnapshotRoot >>>,
+}
+
+impl WidgetSnapshot {
+    /// Instantiates component `name` from the `live_design!` in `module_path` in a window
+    /// of `size` logical pixels. The makepad_widgets designs are registered before
+    /// `live_design` runs, so the snippet only needs to import them.
+    pub fn new(live_design: fn(&mut Cx), module_path: &str, name: LiveId, size: DVec2, dpi_factor: f64) -> Self {
+        let module_id = LiveModuleId::from_str(module_path).unwrap();
+        let root = Rc::new(RefCell::new(None));
+        let mut cx = Cx::new(Box::new({
+            let root = root.clone();
+            move | cx, event | {
+                if let Event::Startup = event {
+                    let widget_ptr = cx.live_registry.borrow().module_id_and_name_to_ptr(module_id, name);
+                    match widget_ptr {
+                        Some(widget_ptr) => *root.borrow_mut() = Some(SnapshotRoot::new(cx, widget_ptr)),
+                        None => error!("Snapshot component {} not found in {:?}", name, module_id)
+                    }
+                }
+                if let Some(root) = root.borrow_mut().as_mut() {
+                    root.handle_event(cx, event);
+                }
+            }
+        }));
+
+        // the main module decides which dependencies (fonts, images) get loaded
+        cx.live_registry.borrow_mut().main_module = Some(LiveTypeInfo {
+            module_id,
+            live_type: LiveType::of::<WidgetSnapshot>(),
+            fields: Vec::new(),
+            live_ignore: true,
+            type_name: name
+        });
+        crate::live_design(&mut cx);
+        live_design(&mut cx);
+        cx.live_expand();
+        cx.live_scan_dependencies();
+        cx.native_load_dependencies();
+
+        let headless_app = HeadlessApp::new(
+            (size.x * dpi_factor) as usize,
+            (size.y * dpi_factor) as usize,
+            dpi_factor
+        );
+        cx.headless_startup();
+
+        let mut snapshot = Self {
+            cx,
+            headless_app,
+            root,
+        };
+        snapshot.settle();
+        snapshot
+    }
+
+    pub fn cx(&mut self) -> &mut Cx {
+        &mut self.cx
+    }
+
+    /// The widget under test, for instance to change its state before a snapshot.
+    pub fn widget(&self) -> WidgetRef {
+        self.root.borrow().as_ref().map(|root| root.widget.clone()).unwrap_or(WidgetRef::empty())
+    }
+
+    /// Runs frames until nothing is animating or redrawing anymore.
+    pub fn settle(&mut self) {
+        if self.cx.headless_settle(&mut self.headless_app, MAX_SETTLE_FRAMES) == EventFlow::Exit {
+            error!("Snapshot app requested to quit");
+        }
+    }
+
+    pub fn send_event(&mut self, event: HeadlessEvent) {
+        self.cx.headless_event_callback(&mut self.headless_app, event);
+        self.settle();
+    }
+
+    fn window_id(&self) -> WindowId {
+        self.root.borrow().as_ref().map(|root| root.window.window_id()).unwrap_or(CxWindowPool::id_zero())
+    }
+
+    pub fn mouse_move(&mut self, abs: DVec2) {
+        let event = HeadlessEvent::MouseMove(MouseMoveEvent {
+            abs,
+            window_id: self.window_id(),
+            modifiers: Default::default(),
+            time: self.headless_app.time_now(),
+            handled: Cell::new(Area::Empty),
+        });
+        self.send_event(event);
+    }
+
+    pub fn mouse_down(&mut self, abs: DVec2) {
+        let event = HeadlessEvent::MouseDown(MouseDownEvent {
+            abs,
+            button: 0,
+            window_id: self.window_id(),
+            modifiers: Default::default(),
+            time: self.headless_app.time_now(),
+            handled: Cell::new(Area::Empty),
+        });
+        self.send_event(event);
+    }
+
+    pub fn mouse_up(&mut self, abs: DVec2) {
+        let event = HeadlessEvent::MouseUp(MouseUpEvent {
+            abs,
+            button: 0,
+            window_id: self.window_id(),
+            modifiers: Default::default(),
+            time: self.headless_app.time_now(),
+        });
+        self.send_event(event);
+    }
+
+    /// Moves the mouse to `abs` and presses and releases the left button there.
+    pub fn click(&mut self, abs: DVec2) {
+        self.mouse_move(abs);
+        self.mouse_down(abs);
+        self.mouse_up(abs);
+    }
+
+    pub fn key_down(&mut self, key_code: KeyCode, modifiers: KeyModifiers) {
+        let event = HeadlessEvent::KeyDown(KeyEvent {
+            key_code,
+            is_repeat: false,
+            modifiers,
+            time: self.headless_app.time_now(),
+        });
+        self.send_event(event);
+    }
+
+    pub fn key_up(&mut self, key_code: KeyCode, modifiers: KeyModifiers) {
+        let event = HeadlessEvent::KeyUp(KeyEvent {
+            key_code,
+            is_repeat: false,
+            modifiers,
+            time: self.headless_app.time_now(),
+        });
+        self.send_event(event);
+    }
+
+    /// Presses and releases a key without modifiers.
+    pub fn key_press(&mut self, key_code: KeyCode) {
+        self.key_down(key_code, Default::default());
+        self.key_up(key_code, Default::default());
+    }
+
+    pub fn text_input(&mut self, input: &str) {
+        self.send_event(HeadlessEvent::TextInput(TextInputEvent {
+            input: input.to_string(),
+            replace_last: false,
+            was_paste: false,
+        }));
+    }
+
+    /// The current contents of the window.
+    pub fn frame(&mut self) -> ImageBuffer {
+        self.settle();
+        self.cx.headless_frame()
+    }
+
+    /// Compares the current frame against the PNG at `path`. On a mismatch the frame
+    /// and a diff image are written next to it as `<name>.actual.png` and `<name>.diff.png`.
+    /// With MAKEPAD_UPDATE_SNAPSHOTS set the frame is stored at `path` instead.
+    pub fn compare(&mut self, path: impl AsRef<Path>, tolerance: SnapshotTolerance) -> Result<(), String> {
+        let path = path.as_ref();
+        let actual = self.frame();
+
+        if std::env::var_os("MAKEPAD_UPDATE_SNAPSHOTS").is_some() {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err( | err | err.to_string())?;
+            }
+            return write_png(path, &actual)
+        }
+
+        let expected = match std::fs::read(path) {
+            Ok(data) => png::decode(&data)?,
+            Err(err) => {
+                write_png(&sibling_path(path, "actual"), &actual)?;
+                return Err(format!("Cannot read snapshot {}: {}, run with MAKEPAD_UPDATE_SNAPSHOTS=1 to create it", path.display(), err))
+            }
+        };
+
+        let (diff, differing) = diff_images(&expected, &actual, tolerance.channel);
+        if expected.width != actual.width || expected.height != actual.height || differing > tolerance.pixels {
+            write_png(&sibling_path(path, "actual"), &actual)?;
+            write_png(&sibling_path(path, "diff"), &diff)?;
+            return Err(format!(
+                "Snapshot {} differs: expected {}x{} got {}x{}, {} pixels differ (tolerance {})",
+                path.display(),
+                expected.width,
+                expected.height,
+                actual.width,
+                actual.height,
+                differing,
+                tolerance.pixels
+            ))
+        }
+        Ok(())
+    }
+
+    pub fn assert_snapshot(&mut self, path: impl AsRef<Path>, tolerance: SnapshotTolerance) {
+        if let Err(err) = self.compare(path, tolerance) {
+            panic!("{}", err);
+        }
+    }
+}
+
+fn sibling_path(path: &Path, kind: &str) -> PathBuf {
+    let stem = path.file_stem().map( | s | s.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!("{}.{}.png", stem, kind))
+}
+
+fn write_png(path: &Path, image: &ImageBuffer) -> Result<(), String> {
+    let data = png::encode(image)?;
+    std::fs::write(path, data).map_err( | err | format!("Cannot write {}: {}", path.display(), err))
+}
+
+// returns an image with differing pixels in red over a dimmed copy of the actual frame
+fn diff_images(expected: &ImageBuffer, actual: &ImageBuffer, channel: u8) -> (ImageBuffer, usize) {
+    let width = expected.width.max(actual.width);
+    let height = expected.height.max(actual.height);
+    let mut diff = ImageBuffer::new(width, height);
+    let mut differing = 0;
+    let pixel = | image: &ImageBuffer, x: usize, y: usize | {
+        if x < image.width && y < image.height {Some(image.data[y * image.width + x])} else {None}
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let out = match (pixel(expected, x, y), pixel(actual, x, y)) {
+                (Some(e), Some(a)) if (0..4).all( | i | {
+                    let shift = i * 8;
+                    (((e >> shift) & 0xff) as i32 - ((a >> shift) & 0xff) as i32).unsigned_abs() <= channel as u32
+                }) => {
+                    let luma = (((a >> 16) & 0xff) * 77 + ((a >> 8) & 0xff) * 150 + (a & 0xff) * 29) >> 8;
+                    let dim = luma / 3;
+                    0xff000000 | (dim << 16) | (dim << 8) | dim
+                }
+                _ => {
+                    differing += 1;
+                    0xffff0000
+                }
+            };
+            diff.data[y * width + x] = out;
+        }
+    }
+    (diff, differing)
+}
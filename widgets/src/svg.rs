@@ -0,0 +1,28 @@
+use crate::{
+    makepad_derive_widget::*,
+    makepad_draw::*,
+    widget::*
+};
+
+live_design! {
+    SvgBase = {{Svg}} {}
+}
+
+#[derive(Live, LiveHook, Widget)]
+pub struct Svg {
+    #[redraw]
+    #[live]
+    draw_svg: DrawSvg,
+    #[walk]
+    walk: Walk,
+}
+
+impl Widget for Svg {
+    fn handle_event(&mut self, _cx: &mut Cx, _event: &Event, _scope: &mut Scope) {
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, _scope: &mut Scope, walk: Walk) -> DrawStep {
+        self.draw_svg.draw_walk(cx, walk);
+        DrawStep::done()
+    }
+}
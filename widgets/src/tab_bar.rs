@@ -84,6 +84,11 @@ impl Widget for TabBar{
         if let Some(tab_id) = self.next_selected_tab_id.take() {
             cx.widget_action(uid, &scope.path, TabBarAction::TabWasPressed(tab_id));
         }
+        for tab_id in &self.tab_order {
+            if event.accessibility_action(self.tab_accessibility_id(*tab_id)) == Some(AccessibilityAction::Click) {
+                cx.widget_action(uid, &scope.path, TabBarAction::TabWasPressed(*tab_id));
+            }
+        }
         for (tab_id, (tab,_)) in self.tabs.iter_mut() {
             tab.handle_event_with(cx, event, &mut | cx, action | match action {
                 TabAction::WasPressed => {
@@ -153,6 +158,10 @@ impl TabBar {
         //if selected_tab.is_some(){
         //    self.selected_tab_id = None
         // }
+        cx.begin_accessibility_node(self.widget_uid().into(), AccessibilityNode {
+            role: AccessibilityRole::TabList,
+            ..Default::default()
+        });
         self.scroll_bars.begin(cx, walk, Layout::flow_right());
         self.tab_order.clear();
     }
@@ -171,6 +180,7 @@ impl TabBar {
         self.tabs.retain_visible();
         self.draw_fill.draw_walk(cx, Walk::size(Size::Fill, Size::Fill));
         self.scroll_bars.end(cx);
+        cx.end_accessibility_node(self.widget_uid().into(), self.scroll_bars.area());
     }
     
     pub fn draw_tab(&mut self, cx: &mut Cx2d, tab_id: LiveId, name: &str, template:LiveId) {
@@ -194,6 +204,22 @@ impl TabBar {
             let tab = self.get_or_create_tab(cx, tab_id, template);
             tab.draw(cx, name);
         }
+        if cx.accessibility_enabled() {
+            let (tab, _) = &self.tabs[tab_id];
+            let node = AccessibilityNode {
+                role: AccessibilityRole::Tab,
+                name: name.to_string(),
+                states: AccessibilityStates::default().with(AccessibilityState::Selected, tab.is_selected()),
+                actions: vec![AccessibilityAction::Click],
+                ..Default::default()
+            };
+            let area = tab.area();
+            cx.add_accessibility_node(self.tab_accessibility_id(tab_id), node, area);
+        }
+    }
+    
+    fn tab_accessibility_id(&self, tab_id: LiveId) -> AccessibilityId {
+        AccessibilityId(self.widget_uid().0 ^ tab_id.0)
     }
     
     fn get_or_create_tab(&mut self, cx: &mut Cx, tab_id: LiveId, template:LiveId) -> &mut Tab {
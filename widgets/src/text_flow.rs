@@ -33,7 +33,11 @@ pub enum FlowBlockType {
     Code = shader_enum(3),
     InlineCode = shader_enum(4),
     Underline = shader_enum(5),
-    Strikethrough = shader_enum(6)
+    Strikethrough = shader_enum(6),
+    TableHeader = shader_enum(7),
+    TableCell = shader_enum(8),
+    TaskUnchecked = shader_enum(9),
+    TaskChecked = shader_enum(10)
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
@@ -48,6 +52,16 @@ pub struct DrawFlowBlock {
     #[live] block_type: FlowBlockType
 }
 
+/// A table being drawn, its cells go in a grid turtle with a row per `begin_table_row`.
+struct FlowTable {
+    columns: usize,
+    rows: usize,
+    column: usize,
+    cell_open: bool,
+    /// The drawn cells as (row, column, column span, header), 0-based.
+    cells: SmallVec<[(usize, usize, usize, bool);32]>,
+}
+
 #[derive(Default)]
 pub struct StackCounter(usize);
 impl StackCounter{
@@ -85,6 +99,7 @@ pub struct TextFlow {
     #[walk] walk: Walk,
     
     #[rust] area_stack: SmallVec<[Area;4]>,
+    #[rust] table_stack: SmallVec<[FlowTable;2]>,
     #[rust] pub font_sizes: SmallVec<[f64;8]>,
     #[rust] pub font_colors: SmallVec<[Vec4;8]>,
    // #[rust] pub font: SmallVec<[Font;2]>,
@@ -113,6 +128,9 @@ pub struct TextFlow {
     #[live] list_item_walk: Walk,
     #[live] inline_code_padding: Padding,
     #[live] inline_code_margin: Margin,
+    #[live] table_walk: Walk,
+    #[live] table_cell_layout: Layout,
+    #[live] task_size: f64,
         
     #[redraw] #[rust] area:Area,
     #[rust] draw_state: DrawStateWrap<DrawState>,
@@ -242,6 +260,7 @@ impl TextFlow{
         self.font_sizes.clear();
         self.font_colors.clear();
         self.area_stack.clear();
+        self.table_stack.clear();
         self.top_drop.clear();
         self.combine_spaces.clear();
         self.ignore_newlines.clear();
@@ -280,8 +299,7 @@ impl TextFlow{
         self.draw_block.end(cx);
     }
     
-    pub fn begin_list_item(&mut self, cx:&mut Cx2d, dot:&str, pad:f64){
-        // alright we are going to push a block with a layout and a walk
+    fn begin_list_item_turtle(&mut self, cx:&mut Cx2d, pad:f64)->f64{
         let fs = self.font_sizes.last().unwrap_or(&self.font_size);
         self.draw_normal.text_style.font_size = *fs;
         let fc = self.font_colors.last().unwrap_or(&self.font_color);
@@ -294,6 +312,12 @@ impl TextFlow{
             },
             ..self.list_item_layout
         });
+        pad
+    }
+    
+    pub fn begin_list_item(&mut self, cx:&mut Cx2d, dot:&str, pad:f64){
+        // alright we are going to push a block with a layout and a walk
+        let pad = self.begin_list_item_turtle(cx, pad);
         // lets draw the 'marker' at -x 
         // lets get the turtle position and abs draw 
         
@@ -314,10 +338,118 @@ impl TextFlow{
         self.area_stack.push(self.draw_block.draw_vars.area);
     }
     
+    /// A list item with a checkbox in front of it instead of a bullet.
+    pub fn begin_task_list_item(&mut self, cx:&mut Cx2d, checked:bool, pad:f64){
+        let pad = self.begin_list_item_turtle(cx, pad);
+        let size = self.draw_normal.get_font_size() * self.task_size;
+        let line_height = self.draw_normal.line_height(cx);
+        let pos = cx.turtle().pos() - dvec2(pad, 0.0);
+        self.draw_block.line_color = self.draw_normal.color;
+        self.draw_block.block_type = if checked{
+            FlowBlockType::TaskChecked
+        }
+        else{
+            FlowBlockType::TaskUnchecked
+        };
+        self.draw_block.draw_abs(cx, Rect{
+            pos: pos + dvec2(0.0, ((line_height - size) * 0.5).max(0.0)),
+            size: dvec2(size, size)
+        });
+    }
+    
     pub fn end_list_item(&mut self, cx:&mut Cx2d){
         cx.end_turtle();
     }
     
+    /// Begins a table with `columns` columns that fit their widest cell. Cells are
+    /// drawn between `begin_table_cell` and `end_table_cell` inside a row.
+    pub fn begin_table(&mut self, cx:&mut Cx2d, columns:usize){
+        let columns = columns.clamp(1, GRID_MAX_TRACKS);
+        cx.begin_turtle(self.table_walk, Layout{
+            flow: Flow::Grid{
+                columns: GridTracks::new(&[GridTrack::Fit; GRID_MAX_TRACKS][..columns]),
+                rows: GridTracks::default(),
+                column_gap: 0.0,
+                row_gap: 0.0
+            },
+            ..Layout::default()
+        });
+        self.table_stack.push(FlowTable{
+            columns,
+            rows: 0,
+            column: 0,
+            cell_open: false,
+            cells: SmallVec::new(),
+        });
+    }
+    
+    pub fn begin_table_row(&mut self, cx:&mut Cx2d){
+        self.end_table_cell(cx);
+        if let Some(table) = self.table_stack.last_mut(){
+            table.rows += 1;
+            table.column = 0;
+        }
+    }
+    
+    /// Begins the next cell of the row spanning `column_span` columns. Header cells
+    /// are bold, `align` places the content horizontally where 0.0 is left and 1.0 is right.
+    /// A cell that is still open is ended first.
+    pub fn begin_table_cell(&mut self, cx:&mut Cx2d, header:bool, align:f64, column_span:usize){
+        self.end_table_cell(cx);
+        let Some(table) = self.table_stack.last_mut() else {return};
+        let row = table.rows.max(1) - 1;
+        let column = table.column.min(table.columns - 1);
+        let column_span = column_span.clamp(1, table.columns - column);
+        table.column += column_span;
+        table.cell_open = true;
+        table.cells.push((row, column, column_span, header));
+        if header{
+            self.bold.push();
+        }
+        cx.begin_turtle(
+            Walk::fit()
+                .with_grid_row(row + 1, 1)
+                .with_grid_column(column + 1, column_span)
+                .with_grid_align(Some(align), None),
+            self.table_cell_layout
+        );
+    }
+    
+    pub fn end_table_cell(&mut self, cx:&mut Cx2d){
+        let Some(table) = self.table_stack.last_mut() else {return};
+        if !table.cell_open{
+            return
+        }
+        table.cell_open = false;
+        if let Some(&(_, _, _, true)) = table.cells.last(){
+            self.bold.pop();
+        }
+        cx.end_turtle();
+    }
+    
+    pub fn end_table(&mut self, cx:&mut Cx2d){
+        self.end_table_cell(cx);
+        let Some(table) = self.table_stack.pop() else {return};
+        // the cell backgrounds are drawn once the grid is placed, the tracks are final
+        // before it ends and the table only moves as a whole after that
+        let turtle = cx.turtle();
+        let origin = turtle.rect().pos;
+        let cells:SmallVec<[(Rect, bool);32]> = table.cells.iter().map(|&(row, column, column_span, header)|{
+            (turtle.grid_area_rect(row, column, 1, column_span), header)
+        }).collect();
+        let rect = cx.end_turtle();
+        let shift = rect.pos - origin;
+        for (cell, header) in cells{
+            self.draw_block.block_type = if header{
+                FlowBlockType::TableHeader
+            }
+            else{
+                FlowBlockType::TableCell
+            };
+            self.draw_block.draw_abs(cx, Rect{pos: cell.pos + shift, size: cell.size});
+        }
+    }
+    
     pub fn sep(&mut self, cx:&mut Cx2d){
         self.draw_block.block_type = FlowBlockType::Sep;
         self.draw_block.draw_walk(cx, self.sep_walk);
@@ -443,6 +575,10 @@ impl TextFlow{
     }
      
     pub fn draw_text(&mut self, cx:&mut Cx2d, text:&str){
+        // text between the cells of a table has no place in its grid
+        if self.table_stack.last().is_some_and(|table| !table.cell_open){
+            return
+        }
         if let Some(DrawState::Drawing) = self.draw_state.get(){
             
             let dt = if self.fixed.value() > 0{
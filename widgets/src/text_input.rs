@@ -246,6 +246,10 @@ impl Widget for TextInput {
         if self.animator_handle_event(cx, event).must_redraw() {
             self.draw_bg.redraw(cx);
         }
+
+        if let Some(AccessibilityAction::Focus | AccessibilityAction::Click) = event.accessibility_action(uid.into()) {
+            self.set_key_focus(cx);
+        }
         
         match event.hit_designer(cx, self.draw_bg.area()){
             HitDesigner::DesignerPick(_e)=>{
@@ -482,7 +486,8 @@ impl Widget for TextInput {
                 ..
             }) if !self.is_read_only => {
                 let input = self.filter_input(input);
-                if !input.is_empty() {
+                // an empty replace_last removes a cancelled IME composition
+                if !input.is_empty() || replace_last {
                     let mut start = self.cursor.start().index;
                     let end = self.cursor.end().index;
                     if replace_last {
@@ -656,6 +661,19 @@ impl Widget for TextInput {
         self.cursor.tail.index = self.cursor.tail.index.min(text.len());
         self.history.clear();
     }
+
+    fn accessibility(&self, _cx: &Cx) -> Option<AccessibilityNode> {
+        Some(AccessibilityNode {
+            role: AccessibilityRole::TextInput,
+            name: self.empty_message.clone(),
+            value: self.text.clone(),
+            states: AccessibilityStates::default()
+                .with(AccessibilityState::Focusable, true)
+                .with(AccessibilityState::Editable, !self.is_read_only),
+            actions: vec![AccessibilityAction::Focus],
+            ..Default::default()
+        })
+    }
 }
 
 /// The saved (checkpointed) state of a text input widget.
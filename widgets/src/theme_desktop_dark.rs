@@ -570,6 +570,15 @@ live_design! {
         }
     }
 
+    HtmlImage = <HtmlImageBase> {
+        draw_bg: {
+            texture image: texture2d
+            fn pixel(self) -> vec4 {
+                return Pal::premul(sample2d(self.image, self.pos).xyzw);
+            }
+        }
+    }
+
     Html = <HtmlBase> {
         width: Fill, height: Fit,
         flow: RightWrap,
@@ -643,7 +652,18 @@ live_design! {
             margin: <THEME_MSPACE_V_1> {}
         }
 
+        table_walk: {
+            width: Fit, height: Fit,
+            margin: <THEME_MSPACE_V_1> {}
+        }
+        table_cell_layout: {
+            flow: Right,
+            padding: <THEME_MSPACE_1> { left: (THEME_SPACE_2), right: (THEME_SPACE_2) }
+        }
+        task_size: 0.9,
+
         a = <HtmlLink> {}
+        img = <HtmlImage> {}
 
         draw_block:{
             line_color: (THEME_COLOR_TEXT_DEFAULT)
@@ -728,6 +748,54 @@ live_design! {
                         sdf.fill(self.line_color);
                         return sdf.result;
                     }
+                    FlowBlockType::TableHeader => {
+                        sdf.rect(
+                            0.5,
+                            0.5,
+                            self.rect_size.x - 1.,
+                            self.rect_size.y - 1.
+                        );
+                        sdf.fill_keep(self.code_color);
+                        sdf.stroke(self.sep_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TableCell => {
+                        sdf.rect(
+                            0.5,
+                            0.5,
+                            self.rect_size.x - 1.,
+                            self.rect_size.y - 1.
+                        );
+                        sdf.stroke(self.sep_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TaskUnchecked => {
+                        sdf.box(
+                            1.,
+                            1.,
+                            self.rect_size.x - 2.,
+                            self.rect_size.y - 2.,
+                            2.
+                        );
+                        sdf.stroke(self.line_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TaskChecked => {
+                        sdf.box(
+                            1.,
+                            1.,
+                            self.rect_size.x - 2.,
+                            self.rect_size.y - 2.,
+                            2.
+                        );
+                        sdf.fill(self.line_color);
+                        let sz = self.rect_size.x;
+                        sdf.move_to(sz * 0.25, sz * 0.5);
+                        sdf.line_to(sz * 0.45, sz * 0.7);
+                        sdf.line_to(sz * 0.75, sz * 0.3);
+                        sdf.stroke(self.code_color, 1.5);
+                        return sdf.result;
+                    }
                 }
                 return #f00
             }
@@ -849,6 +917,16 @@ live_design! {
             width: Fill, height: 4.
             margin: <THEME_MSPACE_V_1> {}
         }
+
+        table_walk: {
+            width: Fit, height: Fit,
+            margin: <THEME_MSPACE_V_1> {}
+        }
+        table_cell_layout: {
+            flow: Right,
+            padding: <THEME_MSPACE_1> { left: (THEME_SPACE_2), right: (THEME_SPACE_2) }
+        }
+        task_size: 0.9,
         
         link = <TextFlowLink> {}
         
@@ -935,6 +1013,54 @@ live_design! {
                         sdf.fill(self.line_color);
                         return sdf.result;
                     }
+                    FlowBlockType::TableHeader => {
+                        sdf.rect(
+                            0.5,
+                            0.5,
+                            self.rect_size.x - 1.,
+                            self.rect_size.y - 1.
+                        );
+                        sdf.fill_keep(self.code_color);
+                        sdf.stroke(self.sep_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TableCell => {
+                        sdf.rect(
+                            0.5,
+                            0.5,
+                            self.rect_size.x - 1.,
+                            self.rect_size.y - 1.
+                        );
+                        sdf.stroke(self.sep_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TaskUnchecked => {
+                        sdf.box(
+                            1.,
+                            1.,
+                            self.rect_size.x - 2.,
+                            self.rect_size.y - 2.,
+                            2.
+                        );
+                        sdf.stroke(self.line_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TaskChecked => {
+                        sdf.box(
+                            1.,
+                            1.,
+                            self.rect_size.x - 2.,
+                            self.rect_size.y - 2.,
+                            2.
+                        );
+                        sdf.fill(self.line_color);
+                        let sz = self.rect_size.x;
+                        sdf.move_to(sz * 0.25, sz * 0.5);
+                        sdf.line_to(sz * 0.45, sz * 0.7);
+                        sdf.line_to(sz * 0.75, sz * 0.3);
+                        sdf.stroke(self.code_color, 1.5);
+                        return sdf.result;
+                    }
                 }
                 return #f00
             }
@@ -1111,6 +1237,16 @@ live_design! {
             margin: <THEME_MSPACE_V_1> {}
         }
 
+        table_walk: {
+            width: Fit, height: Fit,
+            margin: <THEME_MSPACE_V_1> {}
+        }
+        table_cell_layout: {
+            flow: Right,
+            padding: <THEME_MSPACE_1> { left: (THEME_SPACE_2), right: (THEME_SPACE_2) }
+        }
+        task_size: 0.9,
+
         draw_block: {
             line_color: (THEME_COLOR_TEXT_DEFAULT)
             sep_color: (THEME_COLOR_DIVIDER)
@@ -1195,6 +1331,54 @@ live_design! {
                         sdf.fill(self.line_color);
                         return sdf.result;
                     }
+                    FlowBlockType::TableHeader => {
+                        sdf.rect(
+                            0.5,
+                            0.5,
+                            self.rect_size.x - 1.,
+                            self.rect_size.y - 1.
+                        );
+                        sdf.fill_keep(self.code_color);
+                        sdf.stroke(self.sep_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TableCell => {
+                        sdf.rect(
+                            0.5,
+                            0.5,
+                            self.rect_size.x - 1.,
+                            self.rect_size.y - 1.
+                        );
+                        sdf.stroke(self.sep_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TaskUnchecked => {
+                        sdf.box(
+                            1.,
+                            1.,
+                            self.rect_size.x - 2.,
+                            self.rect_size.y - 2.,
+                            2.
+                        );
+                        sdf.stroke(self.line_color, 1.0);
+                        return sdf.result;
+                    }
+                    FlowBlockType::TaskChecked => {
+                        sdf.box(
+                            1.,
+                            1.,
+                            self.rect_size.x - 2.,
+                            self.rect_size.y - 2.,
+                            2.
+                        );
+                        sdf.fill(self.line_color);
+                        let sz = self.rect_size.x;
+                        sdf.move_to(sz * 0.25, sz * 0.5);
+                        sdf.line_to(sz * 0.45, sz * 0.7);
+                        sdf.line_to(sz * 0.75, sz * 0.3);
+                        sdf.stroke(self.code_color, 1.5);
+                        return sdf.result;
+                    }
                 }
                 return #f00
             }
@@ -3538,6 +3722,96 @@ live_design! {
         flow: Down
     }
 
+    DataGrid = <DataGridBase> {
+        width: Fill, height: Fill,
+        flow: Down
+
+        header_height: (THEME_DATA_ITEM_HEIGHT + 3.0)
+        row_height: (THEME_DATA_ITEM_HEIGHT)
+        min_column_width: 30.0
+        header_layout: {
+            align: { y: 0.5 }
+            padding: { left: (THEME_SPACE_2), right: 16.0 }
+        }
+        cell_layout: {
+            align: { y: 0.5 }
+            padding: { left: (THEME_SPACE_2), right: (THEME_SPACE_2) }
+        }
+
+        list: <PortalList> {
+            drag_scrolling: false
+        }
+        scroll_bar: <ScrollBar> {}
+
+        draw_header: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.rect(0., 0., self.rect_size.x, self.rect_size.y);
+                sdf.fill(mix(
+                    mix(THEME_COLOR_BG_CONTAINER, THEME_COLOR_CTRL_HOVER, self.hover),
+                    THEME_COLOR_CTRL_PRESSED,
+                    self.dragging
+                ));
+                sdf.rect(self.rect_size.x - 1.0, 3.0, 1.0, self.rect_size.y - 6.0);
+                sdf.fill(THEME_COLOR_DIVIDER);
+                sdf.rect(0., self.rect_size.y - 1.0, self.rect_size.x, 1.0);
+                sdf.fill(THEME_COLOR_DIVIDER);
+
+                if abs(self.sort_order) > 0.5 {
+                    let c = vec2(self.rect_size.x - 9.0, self.rect_size.y * 0.5);
+                    let sz = 3.0 * self.sort_order;
+                    sdf.move_to(c.x - abs(sz), c.y + sz * 0.5);
+                    sdf.line_to(c.x + abs(sz), c.y + sz * 0.5);
+                    sdf.line_to(c.x, c.y - sz * 0.5);
+                    sdf.close_path();
+                    sdf.fill(THEME_COLOR_TEXT_DEFAULT);
+                }
+                return sdf.result
+            }
+        }
+
+        draw_header_text: {
+            color: (THEME_COLOR_TEXT_DEFAULT)
+            text_style: <THEME_FONT_BOLD> {
+                font_size: (THEME_FONT_SIZE_P)
+            }
+        }
+
+        draw_row: {
+            fn pixel(self) -> vec4 {
+                return mix(
+                    mix(THEME_COLOR_BG_EVEN, THEME_COLOR_BG_ODD, self.is_even),
+                    mix(THEME_COLOR_CTRL_INACTIVE, THEME_COLOR_CTRL_SELECTED, self.focussed),
+                    self.selected
+                );
+            }
+        }
+
+        draw_cell: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.rect(self.rect_size.x - 1.0, 0., 1.0, self.rect_size.y);
+                sdf.fill(THEME_COLOR_DIVIDER);
+                if self.is_cursor * self.focussed > 0.5 {
+                    sdf.rect(1., 1., self.rect_size.x - 2.0, self.rect_size.y - 2.0);
+                    sdf.stroke(THEME_COLOR_TEXT_CURSOR, 1.0);
+                }
+                return sdf.result
+            }
+        }
+
+        draw_text: {
+            color: (THEME_COLOR_TEXT_DEFAULT)
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_P)
+            }
+        }
+
+        draw_marker: {
+            color: (THEME_COLOR_TEXT_CURSOR)
+        }
+    }
+
     CachedScrollXY = <CachedView> {
         scroll_bars: <ScrollBars> {show_scroll_x: true, show_scroll_y: true}
     }
@@ -1003,7 +1003,6 @@ impl View {
     pub fn walk_from_previous_size(&self, walk: Walk) -> Walk {
         let view_size = self.view_size.unwrap_or(DVec2::default());
         Walk {
-            abs_pos: walk.abs_pos,
             width: if walk.width.is_fill() {
                 walk.width
             } else {
@@ -1014,7 +1013,7 @@ impl View {
             } else {
                 Size::Fixed(view_size.y)
             },
-            margin: walk.margin,
+            ..walk
         }
     }
 
@@ -20,6 +20,12 @@ pub enum WidgetCache {
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub struct WidgetUid(pub u64);
 
+impl From<WidgetUid> for AccessibilityId {
+    fn from(uid: WidgetUid) -> Self {
+        AccessibilityId(uid.0)
+    }
+}
+
 pub trait WidgetDesign: WidgetNode {}
 
 #[derive(Clone, Debug, DefaultNone)]
@@ -112,6 +118,12 @@ pub trait Widget: WidgetNode {
 
     fn set_text(&mut self, _v: &str) {}
 
+    /// Describes the widget to screen readers. Nodes reported by widgets drawn
+    /// inside this one become its children, `None` leaves the widget out of the tree.
+    fn accessibility(&self, _cx: &Cx) -> Option<AccessibilityNode> {
+        None
+    }
+
     fn set_text_and_redraw(&mut self, cx: &mut Cx, v: &str) {
         self.set_text(v);
         self.redraw(cx);
@@ -205,6 +217,24 @@ pub struct WidgetRefInner {
 #[derive(Clone, Default)]
 pub struct WidgetRef(Rc<RefCell<Option<WidgetRefInner>>>);
 
+// a widget that draws in steps is entered again for every step, its node stays
+// open until the last one and the begin on re-entry is a no-op
+fn begin_widget_accessibility(cx: &mut Cx2d, widget: &dyn Widget) -> Option<AccessibilityId> {
+    if !cx.accessibility_enabled() {
+        return None
+    }
+    let node = widget.accessibility(cx)?;
+    let id = widget.widget_uid().into();
+    cx.begin_accessibility_node(id, node);
+    Some(id)
+}
+
+fn end_widget_accessibility(cx: &mut Cx2d, id: Option<AccessibilityId>, widget: &dyn Widget) {
+    if let Some(id) = id {
+        cx.end_accessibility_node(id, widget.area());
+    }
+}
+
 impl Debug for WidgetRef {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         write!(f, "WidgetRef {}", self.widget_uid().0)
@@ -407,7 +437,9 @@ impl WidgetRef {
             // if we're in a draw event, do taht here
             if let Event::Draw(e) = event {
                 let cx = &mut Cx2d::new(cx, e);
-                return inner.widget.draw_all(cx, scope);
+                let accessibility_id = begin_widget_accessibility(cx, &*inner.widget);
+                inner.widget.draw_all(cx, scope);
+                return end_widget_accessibility(cx, accessibility_id, &*inner.widget);
             }
             inner.widget.handle_event(cx, event, scope); 
         }
@@ -514,30 +546,36 @@ impl WidgetRef {
 
     pub fn draw_walk(&self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
         if let Some(inner) = self.0.borrow_mut().as_mut() {
+            let accessibility_id = begin_widget_accessibility(cx, &*inner.widget);
             if let Some(nd) = inner.widget.draw_walk(cx, scope, walk).step() {
                 if nd.is_empty() {
                     return DrawStep::make_step_here(self.clone());
                 }
                 return DrawStep::make_step_here(nd);
             }
+            end_widget_accessibility(cx, accessibility_id, &*inner.widget);
         }
         DrawStep::done()
     }
 
     pub fn draw_walk_all(&self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) {
         if let Some(inner) = self.0.borrow_mut().as_mut() {
-            inner.widget.draw_walk_all(cx, scope, walk)
+            let accessibility_id = begin_widget_accessibility(cx, &*inner.widget);
+            inner.widget.draw_walk_all(cx, scope, walk);
+            end_widget_accessibility(cx, accessibility_id, &*inner.widget);
         }
     }
 
     pub fn draw(&mut self, cx: &mut Cx2d, scope: &mut Scope) -> DrawStep {
         if let Some(inner) = self.0.borrow_mut().as_mut() {
+            let accessibility_id = begin_widget_accessibility(cx, &*inner.widget);
             if let Some(nd) = inner.widget.draw(cx, scope).step() {
                 if nd.is_empty() {
                     return DrawStep::make_step_here(self.clone());
                 }
                 return DrawStep::make_step_here(nd);
             }
+            end_widget_accessibility(cx, accessibility_id, &*inner.widget);
         }
         DrawStep::done()
     }
@@ -565,7 +603,9 @@ impl WidgetRef {
 
     pub fn draw_all(&self, cx: &mut Cx2d, scope: &mut Scope) {
         if let Some(inner) = self.0.borrow_mut().as_mut() {
-            return inner.widget.draw_all(cx, scope);
+            let accessibility_id = begin_widget_accessibility(cx, &*inner.widget);
+            inner.widget.draw_all(cx, scope);
+            end_widget_accessibility(cx, accessibility_id, &*inner.widget);
         }
     }
     
@@ -578,7 +618,9 @@ impl WidgetRef {
     
     pub fn draw_all_unscoped(&self, cx: &mut Cx2d) {
         if let Some(inner) = self.0.borrow_mut().as_mut() {
-            return inner.widget.draw_all_unscoped(cx);
+            let accessibility_id = begin_widget_accessibility(cx, &*inner.widget);
+            inner.widget.draw_all_unscoped(cx);
+            end_widget_accessibility(cx, accessibility_id, &*inner.widget);
         }
     }
 
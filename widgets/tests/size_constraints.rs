@@ -0,0 +1,48 @@
+#![cfg(linux_headless)]
+// Run with MAKEPAD=linux_headless.
+
+use makepad_widgets::{*, snapshot::*};
+
+live_design!{
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+    PlaceholderImage = <Image> {fit: Horizontal, width: 100, placeholder_width: 40, placeholder_height: 20}
+    // the placeholder aspect ratio decides the height, a min size constraint would not
+    DeprecatedMinImage = <Image> {fit: Horizontal, width: 100, min_width: 40, min_height: 20}
+    DeprecatedMinImageBlend = <ImageBlend> {fit: Horizontal, width: 100, min_width: 40, min_height: 20}
+    MinSizeView = <View> {
+        width: Fit, height: Fit, min_width: 50, min_height: 30,
+        <View> {width: 10, height: 10}
+    }
+    MaxSizeView = <View> {
+        width: Fit, height: Fit, max_width: 50,
+        <View> {width: 100, height: 10}
+    }
+    AspectView = <View> {
+        width: 80, height: Fit, aspect_ratio: 2.0,
+    }
+}
+
+fn widget_size(name: LiveId) -> DVec2 {
+    let mut snapshot = WidgetSnapshot::new(live_design, module_path!(), name, dvec2(120.0, 80.0), 1.0);
+    let widget = snapshot.widget();
+    widget.area().rect(snapshot.cx()).size
+}
+
+#[test]
+fn image_placeholder_size() {
+    assert_eq!(widget_size(live_id!(PlaceholderImage)), dvec2(100.0, 50.0));
+}
+
+#[test]
+fn image_min_size_is_placeholder_size() {
+    assert_eq!(widget_size(live_id!(DeprecatedMinImage)), dvec2(100.0, 50.0));
+    assert_eq!(widget_size(live_id!(DeprecatedMinImageBlend)), dvec2(100.0, 50.0));
+}
+
+#[test]
+fn fit_view_size_constraints() {
+    assert_eq!(widget_size(live_id!(MinSizeView)), dvec2(50.0, 30.0));
+    assert_eq!(widget_size(live_id!(MaxSizeView)), dvec2(50.0, 10.0));
+    assert_eq!(widget_size(live_id!(AspectView)), dvec2(80.0, 40.0));
+}
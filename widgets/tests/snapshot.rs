@@ -0,0 +1,57 @@
+#![cfg(linux_headless)]
+// Run with MAKEPAD=linux_headless, the stored images come from the software renderer.
+
+use makepad_widgets::{*, snapshot::*, makepad_platform::makepad_image_formats::png};
+
+live_design!{
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+    SnapshotButton = <Button> {text: "Press me"}
+}
+
+const SIZE: DVec2 = DVec2 {x: 120.0, y: 40.0};
+const BUTTON_POS: DVec2 = DVec2 {x: 20.0, y: 15.0};
+
+fn button_snapshot() -> WidgetSnapshot {
+    WidgetSnapshot::new(live_design, module_path!(), live_id!(SnapshotButton), SIZE, 1.0)
+}
+
+#[test]
+fn button_press() {
+    let mut snapshot = button_snapshot();
+    snapshot.assert_snapshot("tests/snapshots/button.png", SnapshotTolerance::default());
+    snapshot.mouse_move(BUTTON_POS);
+    snapshot.mouse_down(BUTTON_POS);
+    snapshot.assert_snapshot("tests/snapshots/button_pressed.png", SnapshotTolerance::default());
+}
+
+#[test]
+fn mismatch_writes_diff() {
+    if std::env::var_os("MAKEPAD_UPDATE_SNAPSHOTS").is_some() {
+        return
+    }
+    // compare the pressed button against the stored image of the idle one
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("snapshot_mismatch");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("button.png");
+    let actual_path = dir.join("button.actual.png");
+    let diff_path = dir.join("button.diff.png");
+    std::fs::copy("tests/snapshots/button.png", &path).unwrap();
+    let _ = std::fs::remove_file(&actual_path);
+    let _ = std::fs::remove_file(&diff_path);
+
+    let mut snapshot = button_snapshot();
+    snapshot.mouse_move(BUTTON_POS);
+    snapshot.mouse_down(BUTTON_POS);
+    let err = snapshot.compare(&path, SnapshotTolerance::default()).unwrap_err();
+    assert!(err.contains("pixels differ"), "{}", err);
+
+    let actual = png::decode(&std::fs::read(&actual_path).unwrap()).unwrap();
+    assert_eq!((actual.width, actual.height), (120, 40));
+    assert_eq!(actual.data, snapshot.frame().data);
+
+    // differing pixels are marked in red
+    let diff = png::decode(&std::fs::read(&diff_path).unwrap()).unwrap();
+    assert_eq!((diff.width, diff.height), (120, 40));
+    assert!(diff.data.iter().any( | pixel | *pixel == 0xffff0000));
+}